@@ -71,6 +71,14 @@ class MonitoringConfig:
         self.monitoring_interval_seconds = 30
         self.alert_cooldown_minutes = 15  # Prevent alert spam
         self.health_check_endpoints = self._get_health_check_endpoints()
+        # Payment events (PaymentRequested, PaymentApproved, PaymentProcessing,
+        # PaymentCompleted, PaymentFailed, PaymentRetryScheduled,
+        # PaymentDeadLettered, PaymentExpired, PaymentDestinationReplaced,
+        # TravelRuleDataAttached) are emitted on-chain only; the payment
+        # processor reads them from program logs and forwards each one here. The program never stores this URL.
+        # Events carry a gapless `sequence`, so a gap means the processor must
+        # re-read the PaymentSystem account before forwarding more.
+        self.payment_event_webhook = os.getenv('PAYMENT_EVENT_WEBHOOK_URL')
         
     def _load_alert_channels(self) -> List[AlertChannel]:
         """Load alert channels from environment variables"""
@@ -1,4 +1,4 @@
-import { Connection, PublicKey, Transaction, SystemProgram, LAMPORTS_PER_SOL } from '@solana/web3.js';
+import { Connection, PublicKey, Transaction, SystemProgram, LAMPORTS_PER_SOL, SYSVAR_SLOT_HASHES_PUBKEY } from '@solana/web3.js';
 import { Program, AnchorProvider, Wallet, BN } from '@project-serum/anchor';
 import { 
   VaultAccount,
@@ -509,7 +509,7 @@ export class VaultClient {
     }
   }
 
-  async addAuthFactor(method: 'totp' | 'webauthn' | 'sms' | 'email' | 'passkey', identifier: string, secretHash: Uint8Array, backupCodes: string[]): Promise<string> {
+  async addAuthFactor(method: 'totp' | 'webauthn' | 'sms' | 'email' | 'passkey', identifier: string, secretHash: Uint8Array): Promise<string> {
     if (!this.wallet || !this.program) {
       throw new Error('Wallet not connected');
     }
@@ -617,12 +617,34 @@ export class VaultClient {
     }
 
     try {
-      // Generate 10 backup codes
+      // Generate 10 backup codes locally; only their SHA-256 digests go on-chain
       const codes = [];
       for (let i = 0; i < 10; i++) {
-        const code = Math.random().toString(36).substring(2, 10).toUpperCase();
-        codes.push(code);
+        const bytes = new Uint8Array(8);
+        crypto.getRandomValues(bytes);
+        codes.push(Array.from(bytes, b => b.toString(16).padStart(2, '0')).join('').toUpperCase());
       }
+      const codeDigests = await Promise.all(codes.map(async code => {
+        const digest = await crypto.subtle.digest('SHA-256', new TextEncoder().encode(code));
+        return Array.from(new Uint8Array(digest));
+      }));
+      const clientEntropy = new Uint8Array(32);
+      crypto.getRandomValues(clientEntropy);
+
+      const [userAuthPDA] = PublicKey.findProgramAddressSync(
+        [Buffer.from('user_auth'), this.wallet.publicKey.toBuffer()],
+        this.programId
+      );
+
+      await this.program.methods
+        .generateBackupCodes(codeDigests, Array.from(clientEntropy))
+        .accounts({
+          userAuth: userAuthPDA,
+          user: this.wallet.publicKey,
+          slotHashes: SYSVAR_SLOT_HASHES_PUBKEY,
+        })
+        .rpc();
+
       return codes;
     } catch (error) {
       console.error('Error generating backup codes:', error);
@@ -657,9 +679,7 @@ export class VaultClient {
       const secretHash = new Uint8Array(32);
       crypto.getRandomValues(secretHash);
       
-      const backupCodes = await this.generateBackupCodes();
-      
-      return await this.addAuthFactor(method, identifier, secretHash, backupCodes);
+      return await this.addAuthFactor(method, identifier, secretHash);
     } catch (error) {
       console.error('Error setting up 2FA:', error);
       throw error;
@@ -0,0 +1,100 @@
+use anchor_lang::prelude::*;
+use sha2::{Digest, Sha256};
+use crate::errors::VaultError;
+
+const SALT_DOMAIN: &[u8] = b"vault-backup-code-salt";
+
+/// Entry count and first slot of the SlotHashes sysvar, ahead of the most
+/// recent hash
+const SLOT_HASHES_LATEST_OFFSET: usize = 8 + 8;
+
+/// Salted hashing for 2FA backup codes.
+///
+/// Anything computed on-chain is public, so codes are generated by the
+/// client and only their SHA-256 digests are submitted. The program mixes
+/// client entropy with the latest slot hash into a per-user salt and keeps
+/// SHA-256(salt || digest) for each code, never the code itself.
+pub struct BackupCodeHasher;
+
+impl BackupCodeHasher {
+    /// SHA-256 of a backup code, as the client submits it at generation
+    pub fn code_digest(code: &str) -> [u8; 32] {
+        Sha256::digest(code.as_bytes()).into()
+    }
+
+    /// Salt for a new set of codes
+    pub fn derive_salt(client_entropy: &[u8; 32], slot_hash: &[u8; 32], user: &Pubkey, now: i64) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(SALT_DOMAIN);
+        hasher.update(client_entropy);
+        hasher.update(slot_hash);
+        hasher.update(user.as_ref());
+        hasher.update(now.to_le_bytes());
+        hasher.finalize().into()
+    }
+
+    /// The value stored for a code with digest `code_digest`
+    pub fn salted_hash(salt: &[u8; 32], code_digest: &[u8; 32]) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(salt);
+        hasher.update(code_digest);
+        hasher.finalize().into()
+    }
+
+    /// The most recent hash in raw SlotHashes sysvar data
+    pub fn latest_slot_hash(slot_hashes: &[u8]) -> Result<[u8; 32]> {
+        let latest = slot_hashes
+            .get(SLOT_HASHES_LATEST_OFFSET..SLOT_HASHES_LATEST_OFFSET + 32)
+            .ok_or(VaultError::InvalidSlotHashes)?;
+        if slot_hashes[..8] == [0; 8] {
+            return Err(VaultError::InvalidSlotHashes.into());
+        }
+        let mut hash = [0u8; 32];
+        hash.copy_from_slice(latest);
+        Ok(hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::TotpVerifier;
+
+    #[test]
+    fn test_salted_hashes_depend_on_code_and_salt() {
+        let user = Pubkey::new_unique();
+        let salt = BackupCodeHasher::derive_salt(&[1; 32], &[2; 32], &user, 100);
+        assert_ne!(salt, BackupCodeHasher::derive_salt(&[1; 32], &[3; 32], &user, 100));
+        assert_ne!(salt, BackupCodeHasher::derive_salt(&[4; 32], &[2; 32], &user, 100));
+        assert_ne!(salt, BackupCodeHasher::derive_salt(&[1; 32], &[2; 32], &Pubkey::new_unique(), 100));
+
+        let digest = BackupCodeHasher::code_digest("a1b2-c3d4-e5f6");
+        // FIPS 180-2 SHA-256 test vector for "abc"
+        assert_eq!(BackupCodeHasher::code_digest("abc"), [
+            0xba, 0x78, 0x16, 0xbf, 0x8f, 0x01, 0xcf, 0xea,
+            0x41, 0x41, 0x40, 0xde, 0x5d, 0xae, 0x22, 0x23,
+            0xb0, 0x03, 0x61, 0xa3, 0x96, 0x17, 0x7a, 0x9c,
+            0xb4, 0x10, 0xff, 0x61, 0xf2, 0x00, 0x15, 0xad,
+        ]);
+        let stored = BackupCodeHasher::salted_hash(&salt, &digest);
+        assert!(TotpVerifier::constant_time_eq(&stored, &BackupCodeHasher::salted_hash(&salt, &digest)));
+        assert!(!TotpVerifier::constant_time_eq(&stored, &BackupCodeHasher::salted_hash(&[0; 32], &digest)));
+        assert!(!TotpVerifier::constant_time_eq(
+            &stored,
+            &BackupCodeHasher::salted_hash(&salt, &BackupCodeHasher::code_digest("a1b2-c3d4-e5f7")),
+        ));
+    }
+
+    #[test]
+    fn test_latest_slot_hash_reads_the_first_entry() {
+        let mut data = 2u64.to_le_bytes().to_vec();
+        data.extend_from_slice(&500u64.to_le_bytes());
+        data.extend_from_slice(&[9; 32]);
+        data.extend_from_slice(&499u64.to_le_bytes());
+        data.extend_from_slice(&[8; 32]);
+        assert_eq!(BackupCodeHasher::latest_slot_hash(&data).unwrap(), [9; 32]);
+
+        assert!(BackupCodeHasher::latest_slot_hash(&data[..40]).is_err());
+        assert!(BackupCodeHasher::latest_slot_hash(&0u64.to_le_bytes()).is_err());
+    }
+}
@@ -0,0 +1,219 @@
+use anchor_lang::prelude::*;
+use crate::errors::VaultError;
+
+/// Bech32 data alphabet; a character's index is its 5-bit value
+const BECH32_CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+/// Bech32 checksum length in 5-bit words
+const CHECKSUM_WORDS: usize = 6;
+
+/// Invoice timestamp length in 5-bit words (35 bits)
+const TIMESTAMP_WORDS: usize = 7;
+
+/// Recoverable signature length in 5-bit words (65 bytes)
+const SIGNATURE_WORDS: usize = 104;
+
+/// Tagged field types read from the invoice
+const TAG_PAYMENT_HASH: u8 = 1; // 'p'
+const TAG_EXPIRY: u8 = 6; // 'x'
+
+/// Payment hash length in 5-bit words (256 bits, padded to 260)
+const PAYMENT_HASH_WORDS: usize = 52;
+
+/// Millisatoshis per bitcoin, the unit of an invoice amount with no multiplier
+const MSAT_PER_BTC: u64 = 100_000_000_000;
+
+/// Fields of a BOLT11 Lightning invoice the payment system relies on.
+///
+/// Decoding verifies the bech32 checksum and the human-readable prefix but
+/// not the node signature; the node paying the invoice checks that, and a
+/// forged invoice can only name a payment hash nobody can settle.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bolt11Invoice {
+    pub amount_msat: Option<u64>, // None for invoices that leave the amount to the payer
+    pub timestamp: i64,           // Creation time, seconds since the epoch
+    pub expiry: i64,              // Seconds after `timestamp` the invoice stays payable
+    pub payment_hash: [u8; 32],   // SHA-256 of the preimage released on payment
+}
+
+impl Bolt11Invoice {
+    /// Expiry when the invoice does not set one
+    pub const DEFAULT_EXPIRY: i64 = 3600;
+
+    /// Decode a mainnet, testnet, signet or regtest invoice
+    pub fn decode(invoice: &str) -> Result<Self> {
+        let invoice = if invoice.bytes().all(|b| !b.is_ascii_uppercase()) {
+            invoice.to_string()
+        } else if invoice.bytes().all(|b| !b.is_ascii_lowercase()) {
+            invoice.to_ascii_lowercase()
+        } else {
+            return Err(VaultError::InvalidLightningInvoice.into());
+        };
+
+        let separator = invoice.rfind('1').ok_or(VaultError::InvalidLightningInvoice)?;
+        let (hrp, data) = (&invoice[..separator], &invoice[separator + 1..]);
+        let words = data.bytes()
+            .map(|c| BECH32_CHARSET.iter().position(|&x| x == c).map(|v| v as u8))
+            .collect::<Option<Vec<u8>>>()
+            .ok_or(VaultError::InvalidLightningInvoice)?;
+
+        if words.len() < TIMESTAMP_WORDS + SIGNATURE_WORDS + CHECKSUM_WORDS || !verify_checksum(hrp, &words) {
+            return Err(VaultError::InvalidLightningInvoice.into());
+        }
+
+        let amount_msat = parse_hrp_amount(hrp)?;
+        let payload = &words[..words.len() - CHECKSUM_WORDS - SIGNATURE_WORDS];
+        let timestamp = words_to_u64(&payload[..TIMESTAMP_WORDS]) as i64;
+
+        let mut payment_hash = None;
+        let mut expiry = Self::DEFAULT_EXPIRY;
+        let mut fields = &payload[TIMESTAMP_WORDS..];
+        while !fields.is_empty() {
+            if fields.len() < 3 {
+                return Err(VaultError::InvalidLightningInvoice.into());
+            }
+            let tag = fields[0];
+            let len = fields[1] as usize * 32 + fields[2] as usize;
+            let value = fields.get(3..3 + len).ok_or(VaultError::InvalidLightningInvoice)?;
+
+            // Readers skip fields with an unexpected length, per BOLT11
+            match tag {
+                TAG_PAYMENT_HASH if len == PAYMENT_HASH_WORDS && payment_hash.is_none() => {
+                    let bytes = words_to_bytes(value);
+                    payment_hash = Some(<[u8; 32]>::try_from(&bytes[..32]).unwrap());
+                },
+                TAG_EXPIRY if (1..=12).contains(&len) => {
+                    expiry = words_to_u64(value).min(i64::MAX as u64) as i64;
+                },
+                _ => {},
+            }
+            fields = &fields[3 + len..];
+        }
+
+        Ok(Self {
+            amount_msat,
+            timestamp,
+            expiry,
+            payment_hash: payment_hash.ok_or(VaultError::InvalidLightningInvoice)?,
+        })
+    }
+
+    /// First second at which the invoice can no longer be paid
+    pub fn expires_at(&self) -> i64 {
+        self.timestamp.saturating_add(self.expiry)
+    }
+}
+
+/// Amount encoded in the human-readable part, e.g. `lnbc2500u`
+fn parse_hrp_amount(hrp: &str) -> Result<Option<u64>> {
+    let rest = hrp.strip_prefix("ln").ok_or(VaultError::InvalidLightningInvoice)?;
+    let amount = ["bcrt", "bc", "tbs", "tb"].iter()
+        .find_map(|currency| rest.strip_prefix(currency))
+        .ok_or(VaultError::InvalidLightningInvoice)?;
+    if amount.is_empty() {
+        return Ok(None);
+    }
+
+    let (digits, multiplier) = match amount.as_bytes()[amount.len() - 1] {
+        b'0'..=b'9' => (amount, None),
+        m => (&amount[..amount.len() - 1], Some(m)),
+    };
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) || digits.starts_with('0') {
+        return Err(VaultError::InvalidLightningInvoice.into());
+    }
+    let value: u64 = digits.parse().map_err(|_| VaultError::InvalidLightningInvoice)?;
+
+    let msat = match multiplier {
+        None => value.checked_mul(MSAT_PER_BTC),
+        Some(b'm') => value.checked_mul(MSAT_PER_BTC / 1_000),
+        Some(b'u') => value.checked_mul(MSAT_PER_BTC / 1_000_000),
+        Some(b'n') => value.checked_mul(MSAT_PER_BTC / 1_000_000_000),
+        // A pico-bitcoin is a tenth of a millisatoshi
+        Some(b'p') if value % 10 == 0 => Some(value / 10),
+        _ => None,
+    };
+    msat.map(Some).ok_or(VaultError::InvalidLightningInvoice.into())
+}
+
+fn verify_checksum(hrp: &str, words: &[u8]) -> bool {
+    let expanded = hrp.bytes().map(|b| b >> 5)
+        .chain(std::iter::once(0))
+        .chain(hrp.bytes().map(|b| b & 31))
+        .chain(words.iter().copied());
+    polymod(expanded) == 1
+}
+
+fn polymod(values: impl Iterator<Item = u8>) -> u32 {
+    const GENERATOR: [u32; 5] = [0x3b6a57b2, 0x26508e6d, 0x1ea119fa, 0x3d4233dd, 0x2a1462b3];
+    values.fold(1u32, |chk, value| {
+        let top = chk >> 25;
+        let chk = ((chk & 0x1ffffff) << 5) ^ value as u32;
+        GENERATOR.iter().enumerate()
+            .filter(|(i, _)| (top >> i) & 1 == 1)
+            .fold(chk, |chk, (_, g)| chk ^ g)
+    })
+}
+
+/// Big-endian integer from 5-bit words; callers keep it within 64 bits
+fn words_to_u64(words: &[u8]) -> u64 {
+    words.iter().fold(0u64, |acc, &w| (acc << 5) | w as u64)
+}
+
+/// Regroup 5-bit words into bytes, dropping trailing padding bits
+fn words_to_bytes(words: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(words.len() * 5 / 8);
+    let (mut acc, mut bits) = (0u32, 0u32);
+    for &word in words {
+        acc = (acc << 5) | word as u32;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((acc >> bits) as u8);
+            acc &= (1 << bits) - 1;
+        }
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// "Please send $3 for a cup of coffee to the same peer, within one
+    /// minute" from the BOLT11 test vectors
+    const COFFEE_INVOICE: &str = "lnbc2500u1pvjluezpp5qqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqypqdq5xysxxatsyp3k7enxv4jsxqzpuaztrnwngzn3kdzw5hydlzf03qdgm2hdq27cqv3agm2awhz5se903vruatfhq77w3ls4evs3ch9zw97j25emudupq63nyw24cg27h2rspfj9srp";
+
+    #[test]
+    fn test_decodes_bolt11_test_vector() {
+        let invoice = Bolt11Invoice::decode(COFFEE_INVOICE).unwrap();
+        assert_eq!(invoice.amount_msat, Some(250_000_000));
+        assert_eq!(invoice.timestamp, 1496314658);
+        assert_eq!(invoice.expiry, 60);
+        assert_eq!(invoice.expires_at(), 1496314718);
+        let mut payment_hash = [0u8; 32];
+        for (i, byte) in payment_hash.iter_mut().enumerate() {
+            *byte = if i == 31 { 2 } else if i >= 30 { 1 } else { (i % 10) as u8 };
+        }
+        assert_eq!(invoice.payment_hash, payment_hash);
+
+        // Case is all-or-nothing, and any altered character breaks the checksum
+        assert_eq!(Bolt11Invoice::decode(&COFFEE_INVOICE.to_ascii_uppercase()).unwrap(), invoice);
+        assert!(Bolt11Invoice::decode(&COFFEE_INVOICE.replacen("lnbc", "LNbc", 1)).is_err());
+        assert!(Bolt11Invoice::decode(&COFFEE_INVOICE.replacen("2500u", "2501u", 1)).is_err());
+        assert!(Bolt11Invoice::decode(&COFFEE_INVOICE.replacen("qzpu", "qzpa", 1)).is_err());
+    }
+
+    #[test]
+    fn test_hrp_amount_multipliers() {
+        assert_eq!(parse_hrp_amount("lnbc").unwrap(), None);
+        assert_eq!(parse_hrp_amount("lnbc1").unwrap(), Some(100_000_000_000));
+        assert_eq!(parse_hrp_amount("lntb20m").unwrap(), Some(2_000_000_000));
+        assert_eq!(parse_hrp_amount("lnbcrt2500u").unwrap(), Some(250_000_000));
+        assert_eq!(parse_hrp_amount("lnbc10n").unwrap(), Some(1_000));
+        assert_eq!(parse_hrp_amount("lntbs10p").unwrap(), Some(1));
+        assert!(parse_hrp_amount("lnbc15p").is_err());
+        assert!(parse_hrp_amount("lnbc025u").is_err());
+        assert!(parse_hrp_amount("lnbc25x").is_err());
+        assert!(parse_hrp_amount("lnxy25u").is_err());
+    }
+}
@@ -0,0 +1,526 @@
+use anchor_lang::prelude::*;
+use solana_program::ed25519_program;
+use solana_program::sysvar::instructions::{load_current_index_checked, load_instruction_at_checked};
+use crate::errors::VaultError;
+use crate::state::authentication::StepUpOperation;
+
+/// Domain separator for state channel update signatures
+pub const STATE_CHANNEL_DOMAIN: &[u8] = b"VAULT_STATE_CHANNEL_UPDATE_V1";
+
+/// Domain separator for mutually signed state channel checkpoints
+pub const CHECKPOINT_DOMAIN: &[u8] = b"VAULT_STATE_CHANNEL_CHECKPOINT_V1";
+
+/// Domain separator for TOTP verifier attestations
+pub const TOTP_ATTESTATION_DOMAIN: &[u8] = b"VAULT_TOTP_ATTESTATION_V1";
+
+/// Domain separator for collateral withdrawal approvals
+pub const COLLATERAL_WITHDRAWAL_DOMAIN: &[u8] = b"VAULT_CHANNEL_WITHDRAWAL_V1";
+
+/// Domain separator for enhanced channel exposure limit increases
+pub const EXPOSURE_LIMIT_DOMAIN: &[u8] = b"VAULT_EXPOSURE_LIMIT_V1";
+
+/// Domain separator for participant-signed HFT operations
+pub const HFT_OPERATION_DOMAIN: &[u8] = b"VAULT_HFT_OPERATION_V1";
+
+/// Domain separator for enhanced channel layout migrations
+pub const CHANNEL_MIGRATION_DOMAIN: &[u8] = b"VAULT_CHANNEL_MIGRATION_V1";
+
+/// Domain separator for partial withdrawals from active enhanced channels
+pub const ENHANCED_WITHDRAWAL_DOMAIN: &[u8] = b"VAULT_ENHANCED_WITHDRAWAL_V1";
+
+/// Domain separator for multisig proposal approvals
+pub const MULTISIG_PROPOSAL_DOMAIN: &[u8] = b"VAULT_MULTISIG_PROPOSAL_V1";
+
+/// Domain separator for multisig approvals signed offline and submitted in a bundle
+pub const MULTISIG_OFFLINE_APPROVAL_DOMAIN: &[u8] = b"VAULT_MULTISIG_OFFLINE_APPROVAL_V1";
+
+/// Ed25519 group order L, little-endian. Canonical signatures have S < L.
+const ED25519_ORDER: [u8; 32] = [
+    0xed, 0xd3, 0xf5, 0x5c, 0x1a, 0x63, 0x12, 0x58,
+    0xd6, 0x9c, 0xf7, 0xa2, 0xde, 0xf9, 0xde, 0x14,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10,
+];
+
+const SIGNATURE_LEN: usize = 64;
+const PUBKEY_LEN: usize = 32;
+const OFFSETS_START: usize = 2;
+const OFFSETS_LEN: usize = 14;
+
+/// Ed25519 signature verifier for participant-signed messages.
+///
+/// Signatures are checked by the native Ed25519 program, which must run as
+/// the instruction immediately before ours. We then introspect that
+/// instruction to make sure it verified exactly the signatures, signers and
+/// message we expect.
+pub struct Ed25519Verifier;
+
+impl Ed25519Verifier {
+    /// Build the signed message for a state channel update
+    pub fn state_update_message(channel_id: &[u8; 32], version: u64, state_hash: &[u8; 32]) -> Vec<u8> {
+        let mut message = Vec::with_capacity(STATE_CHANNEL_DOMAIN.len() + 32 + 8 + 32);
+        message.extend_from_slice(STATE_CHANNEL_DOMAIN);
+        message.extend_from_slice(channel_id);
+        message.extend_from_slice(&version.to_le_bytes());
+        message.extend_from_slice(state_hash);
+        message
+    }
+
+    /// Build the signed message for a state channel checkpoint
+    pub fn checkpoint_message(channel_id: &[u8; 32], version: u64, state_hash: &[u8; 32]) -> Vec<u8> {
+        let mut message = Vec::with_capacity(CHECKPOINT_DOMAIN.len() + 32 + 8 + 32);
+        message.extend_from_slice(CHECKPOINT_DOMAIN);
+        message.extend_from_slice(channel_id);
+        message.extend_from_slice(&version.to_le_bytes());
+        message.extend_from_slice(state_hash);
+        message
+    }
+
+    /// Build the message the TOTP verifier signs after accepting a code for
+    /// the factor committed to by `key_commitment` at time step `counter`.
+    /// Any step-up proof the code is spent on is signed too, so a submitter
+    /// can't swap the operation or raise its cap.
+    pub fn totp_attestation_message(
+        user: &Pubkey,
+        key_commitment: &[u8; 32],
+        counter: u64,
+        step_up_operation: Option<StepUpOperation>,
+        amount_cap: u64,
+    ) -> Vec<u8> {
+        let mut message = Vec::with_capacity(TOTP_ATTESTATION_DOMAIN.len() + 32 + 32 + 8 + 2 + 8);
+        message.extend_from_slice(TOTP_ATTESTATION_DOMAIN);
+        message.extend_from_slice(user.as_ref());
+        message.extend_from_slice(key_commitment);
+        message.extend_from_slice(&counter.to_le_bytes());
+        match step_up_operation {
+            Some(operation) => message.extend_from_slice(&[1, operation as u8]),
+            None => message.push(0),
+        }
+        message.extend_from_slice(&amount_cap.to_le_bytes());
+        message
+    }
+
+    /// Build the message counterparties sign to approve a collateral withdrawal
+    pub fn collateral_withdrawal_message(
+        channel_id: &[u8; 32],
+        participant: &Pubkey,
+        amount: u64,
+        withdrawal_nonce: u64,
+    ) -> Vec<u8> {
+        let mut message = Vec::with_capacity(COLLATERAL_WITHDRAWAL_DOMAIN.len() + 32 + 32 + 8 + 8);
+        message.extend_from_slice(COLLATERAL_WITHDRAWAL_DOMAIN);
+        message.extend_from_slice(channel_id);
+        message.extend_from_slice(participant.as_ref());
+        message.extend_from_slice(&amount.to_le_bytes());
+        message.extend_from_slice(&withdrawal_nonce.to_le_bytes());
+        message
+    }
+
+    /// Build the message all participants sign to raise one participant's
+    /// exposure limit in one asset
+    pub fn exposure_limit_message(
+        channel_id: &[u8; 32],
+        participant: &Pubkey,
+        asset: u8,
+        new_limit: u64,
+        agreement_nonce: u64,
+    ) -> Vec<u8> {
+        let mut message = Vec::with_capacity(EXPOSURE_LIMIT_DOMAIN.len() + 32 + 32 + 1 + 8 + 8);
+        message.extend_from_slice(EXPOSURE_LIMIT_DOMAIN);
+        message.extend_from_slice(channel_id);
+        message.extend_from_slice(participant.as_ref());
+        message.push(asset);
+        message.extend_from_slice(&new_limit.to_le_bytes());
+        message.extend_from_slice(&agreement_nonce.to_le_bytes());
+        message
+    }
+
+    /// Build the message a participant signs for an enhanced channel HFT
+    /// operation, given its Borsh encoding
+    pub fn hft_operation_message(channel_id: &[u8; 32], operation: &[u8]) -> Vec<u8> {
+        let mut message = Vec::with_capacity(HFT_OPERATION_DOMAIN.len() + 32 + operation.len());
+        message.extend_from_slice(HFT_OPERATION_DOMAIN);
+        message.extend_from_slice(channel_id);
+        message.extend_from_slice(operation);
+        message
+    }
+
+    /// Build the message counterparties sign to let a participant withdraw
+    /// one asset from an active enhanced channel without waiting
+    pub fn enhanced_withdrawal_message(
+        channel_id: &[u8; 32],
+        participant: &Pubkey,
+        asset: u8,
+        amount: u64,
+        withdrawal_nonce: u64,
+    ) -> Vec<u8> {
+        let mut message = Vec::with_capacity(ENHANCED_WITHDRAWAL_DOMAIN.len() + 32 + 32 + 1 + 8 + 8);
+        message.extend_from_slice(ENHANCED_WITHDRAWAL_DOMAIN);
+        message.extend_from_slice(channel_id);
+        message.extend_from_slice(participant.as_ref());
+        message.push(asset);
+        message.extend_from_slice(&amount.to_le_bytes());
+        message.extend_from_slice(&withdrawal_nonce.to_le_bytes());
+        message
+    }
+
+    /// Build the message all participants sign to migrate a channel to a
+    /// new account layout version
+    pub fn channel_migration_message(channel_id: &[u8; 32], version: u8) -> Vec<u8> {
+        let mut message = Vec::with_capacity(CHANNEL_MIGRATION_DOMAIN.len() + 32 + 1);
+        message.extend_from_slice(CHANNEL_MIGRATION_DOMAIN);
+        message.extend_from_slice(channel_id);
+        message.push(version);
+        message
+    }
+
+    /// Build the message a multisig signer signs to approve a proposal,
+    /// given the proposal's digest
+    pub fn multisig_proposal_message(proposal_digest: &[u8; 32]) -> Vec<u8> {
+        let mut message = Vec::with_capacity(MULTISIG_PROPOSAL_DOMAIN.len() + 32);
+        message.extend_from_slice(MULTISIG_PROPOSAL_DOMAIN);
+        message.extend_from_slice(proposal_digest);
+        message
+    }
+
+    /// Build the message a signer signs offline to approve a proposal. The
+    /// wallet's approval nonce keeps a bundle from being replayed once any
+    /// bundle for the wallet has been applied.
+    pub fn multisig_offline_approval_message(proposal_digest: &[u8; 32], nonce: u64) -> Vec<u8> {
+        let mut message = Vec::with_capacity(MULTISIG_OFFLINE_APPROVAL_DOMAIN.len() + 32 + 8);
+        message.extend_from_slice(MULTISIG_OFFLINE_APPROVAL_DOMAIN);
+        message.extend_from_slice(proposal_digest);
+        message.extend_from_slice(&nonce.to_le_bytes());
+        message
+    }
+
+    /// Verify one signature per participant, in participant order, against
+    /// the Ed25519 program instruction preceding the current one
+    pub fn verify_participant_signatures(
+        instructions_sysvar: &AccountInfo,
+        participants: &[Pubkey],
+        signatures: &[Vec<u8>],
+        message: &[u8],
+    ) -> Result<()> {
+        let ed25519_data = Self::load_ed25519_data(instructions_sysvar)?;
+        Self::check_signature_set(&ed25519_data, participants, signatures, message)
+    }
+
+    /// Verify signatures over per-signer messages, in the given order,
+    /// against the Ed25519 program instruction preceding the current one
+    pub fn verify_signed_messages(
+        instructions_sysvar: &AccountInfo,
+        signers: &[Pubkey],
+        signatures: &[Vec<u8>],
+        messages: &[Vec<u8>],
+    ) -> Result<()> {
+        let ed25519_data = Self::load_ed25519_data(instructions_sysvar)?;
+        Self::check_signed_messages(&ed25519_data, signers, signatures, messages)
+    }
+
+    fn load_ed25519_data(instructions_sysvar: &AccountInfo) -> Result<Vec<u8>> {
+        let current_index = load_current_index_checked(instructions_sysvar)
+            .map_err(|_| VaultError::InvalidChannelSignature)?;
+        if current_index == 0 {
+            return Err(VaultError::InvalidChannelSignature.into());
+        }
+
+        let ed25519_ix = load_instruction_at_checked(current_index as usize - 1, instructions_sysvar)
+            .map_err(|_| VaultError::InvalidChannelSignature)?;
+        if ed25519_ix.program_id != ed25519_program::ID {
+            return Err(VaultError::InvalidChannelSignature.into());
+        }
+        Ok(ed25519_ix.data)
+    }
+
+    /// Check that Ed25519 program instruction data covers exactly the given
+    /// participants and signatures over `message`
+    pub fn check_signature_set(
+        ed25519_data: &[u8],
+        participants: &[Pubkey],
+        signatures: &[Vec<u8>],
+        message: &[u8],
+    ) -> Result<()> {
+        let messages = vec![message.to_vec(); participants.len()];
+        Self::check_signed_messages(ed25519_data, participants, signatures, &messages)
+    }
+
+    /// Check that Ed25519 program instruction data covers exactly the given
+    /// signers, each with its own signature and message
+    pub fn check_signed_messages(
+        ed25519_data: &[u8],
+        participants: &[Pubkey],
+        signatures: &[Vec<u8>],
+        messages: &[Vec<u8>],
+    ) -> Result<()> {
+        if participants.is_empty() || signatures.len() != participants.len() || messages.len() != participants.len() {
+            return Err(VaultError::InvalidChannelSignature.into());
+        }
+
+        for (i, signature) in signatures.iter().enumerate() {
+            if signature.len() != SIGNATURE_LEN || !Self::is_canonical(signature) {
+                return Err(VaultError::InvalidChannelSignature.into());
+            }
+            if signatures[..i].contains(signature) {
+                return Err(VaultError::InvalidChannelSignature.into());
+            }
+        }
+
+        let count = *ed25519_data.first().ok_or(VaultError::InvalidChannelSignature)? as usize;
+        if count != participants.len() {
+            return Err(VaultError::InvalidChannelSignature.into());
+        }
+
+        for (i, ((participant, signature), message)) in participants.iter().zip(signatures).zip(messages).enumerate() {
+            let offsets = OFFSETS_START + i * OFFSETS_LEN;
+            let read_u16 = |at: usize| -> Result<usize> {
+                ed25519_data
+                    .get(offsets + at..offsets + at + 2)
+                    .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]) as usize)
+                    .ok_or_else(|| VaultError::InvalidChannelSignature.into())
+            };
+
+            // All data must live in the Ed25519 instruction itself
+            for index_at in [2, 6, 12] {
+                if read_u16(index_at)? != u16::MAX as usize {
+                    return Err(VaultError::InvalidChannelSignature.into());
+                }
+            }
+
+            let signature_offset = read_u16(0)?;
+            let pubkey_offset = read_u16(4)?;
+            let message_offset = read_u16(8)?;
+            let message_len = read_u16(10)?;
+
+            let slice = |start: usize, len: usize| {
+                ed25519_data.get(start..start + len).ok_or(VaultError::InvalidChannelSignature)
+            };
+
+            if slice(pubkey_offset, PUBKEY_LEN)? != participant.as_ref()
+                || slice(signature_offset, SIGNATURE_LEN)? != signature.as_slice()
+                || slice(message_offset, message_len)? != message.as_slice()
+            {
+                return Err(VaultError::InvalidChannelSignature.into());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reject malleable signatures whose S component is not reduced mod L
+    fn is_canonical(signature: &[u8]) -> bool {
+        let s = &signature[32..SIGNATURE_LEN];
+        for i in (0..32).rev() {
+            if s[i] != ED25519_ORDER[i] {
+                return s[i] < ED25519_ORDER[i];
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Lay out Ed25519 program data the way the native program expects
+    fn ed25519_data(entries: &[(Pubkey, Vec<u8>)], message: &[u8]) -> Vec<u8> {
+        let entries: Vec<(Pubkey, Vec<u8>, Vec<u8>)> = entries
+            .iter()
+            .map(|(pubkey, signature)| (*pubkey, signature.clone(), message.to_vec()))
+            .collect();
+        ed25519_data_per_message(&entries)
+    }
+
+    fn ed25519_data_per_message(entries: &[(Pubkey, Vec<u8>, Vec<u8>)]) -> Vec<u8> {
+        let mut data = vec![entries.len() as u8, 0];
+        let header_len = OFFSETS_START + entries.len() * OFFSETS_LEN;
+        let mut payload = Vec::new();
+
+        for (pubkey, signature, message) in entries {
+            let pubkey_offset = header_len + payload.len();
+            payload.extend_from_slice(pubkey.as_ref());
+            let signature_offset = header_len + payload.len();
+            payload.extend_from_slice(signature);
+            let message_offset = header_len + payload.len();
+            payload.extend_from_slice(message);
+
+            for value in [
+                signature_offset, u16::MAX as usize,
+                pubkey_offset, u16::MAX as usize,
+                message_offset, message.len(), u16::MAX as usize,
+            ] {
+                data.extend_from_slice(&(value as u16).to_le_bytes());
+            }
+        }
+
+        data.extend_from_slice(&payload);
+        data
+    }
+
+    fn signature(seed: u8) -> Vec<u8> {
+        let mut signature = vec![seed; 64];
+        signature[63] = 0x01; // keep S well below L
+        signature
+    }
+
+    fn fixture() -> (Vec<Pubkey>, Vec<Vec<u8>>, Vec<u8>) {
+        let participants = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+        let signatures = vec![signature(1), signature(2)];
+        let message = Ed25519Verifier::state_update_message(&[7; 32], 3, &[9; 32]);
+        (participants, signatures, message)
+    }
+
+    fn entries(participants: &[Pubkey], signatures: &[Vec<u8>]) -> Vec<(Pubkey, Vec<u8>)> {
+        participants.iter().cloned().zip(signatures.iter().cloned()).collect()
+    }
+
+    #[test]
+    fn test_valid_signature_set() {
+        let (participants, signatures, message) = fixture();
+        let data = ed25519_data(&entries(&participants, &signatures), &message);
+        assert!(Ed25519Verifier::check_signature_set(&data, &participants, &signatures, &message).is_ok());
+    }
+
+    #[test]
+    fn test_rejects_missing_signature() {
+        let (participants, signatures, message) = fixture();
+        let data = ed25519_data(&entries(&participants[..1], &signatures[..1]), &message);
+        assert!(Ed25519Verifier::check_signature_set(&data, &participants, &signatures[..1], &message).is_err());
+        assert!(Ed25519Verifier::check_signature_set(&data, &participants[..1], &signatures, &message).is_err());
+    }
+
+    #[test]
+    fn test_rejects_out_of_order_signatures() {
+        let (participants, signatures, message) = fixture();
+        let swapped = vec![signatures[1].clone(), signatures[0].clone()];
+        let data = ed25519_data(&entries(&participants, &signatures), &message);
+        assert!(Ed25519Verifier::check_signature_set(&data, &participants, &swapped, &message).is_err());
+
+        let reversed: Vec<Pubkey> = participants.iter().rev().cloned().collect();
+        assert!(Ed25519Verifier::check_signature_set(&data, &reversed, &signatures, &message).is_err());
+    }
+
+    #[test]
+    fn test_rejects_duplicate_signatures() {
+        let (participants, signatures, message) = fixture();
+        let duplicated = vec![signatures[0].clone(), signatures[0].clone()];
+        let data = ed25519_data(&entries(&participants, &duplicated), &message);
+        assert!(Ed25519Verifier::check_signature_set(&data, &participants, &duplicated, &message).is_err());
+    }
+
+    #[test]
+    fn test_rejects_malleable_signature() {
+        let (participants, mut signatures, message) = fixture();
+        signatures[1][32..].copy_from_slice(&ED25519_ORDER);
+        let data = ed25519_data(&entries(&participants, &signatures), &message);
+        assert!(Ed25519Verifier::check_signature_set(&data, &participants, &signatures, &message).is_err());
+    }
+
+    #[test]
+    fn test_rejects_wrong_message_binding() {
+        let (participants, signatures, message) = fixture();
+        let data = ed25519_data(&entries(&participants, &signatures), &message);
+
+        let other_version = Ed25519Verifier::state_update_message(&[7; 32], 4, &[9; 32]);
+        let other_channel = Ed25519Verifier::state_update_message(&[8; 32], 3, &[9; 32]);
+        let undomained = message[STATE_CHANNEL_DOMAIN.len()..].to_vec();
+
+        for expected in [other_version, other_channel, undomained] {
+            assert!(Ed25519Verifier::check_signature_set(&data, &participants, &signatures, &expected).is_err());
+        }
+    }
+
+    #[test]
+    fn test_update_signatures_cannot_be_replayed_as_checkpoint() {
+        let (participants, signatures, message) = fixture();
+        let data = ed25519_data(&entries(&participants, &signatures), &message);
+
+        let checkpoint = Ed25519Verifier::checkpoint_message(&[7; 32], 3, &[9; 32]);
+        assert_ne!(checkpoint, message);
+        assert!(Ed25519Verifier::check_signature_set(&data, &participants, &signatures, &checkpoint).is_err());
+
+        let data = ed25519_data(&entries(&participants, &signatures), &checkpoint);
+        assert!(Ed25519Verifier::check_signature_set(&data, &participants, &signatures, &checkpoint).is_ok());
+    }
+
+    #[test]
+    fn test_totp_attestation_binds_step_up() {
+        let user = Pubkey::new_unique();
+        let message = |operation, cap| Ed25519Verifier::totp_attestation_message(&user, &[5; 32], 9, operation, cap);
+        let signed = message(Some(StepUpOperation::ClaimRewards), 1_000);
+
+        assert_ne!(signed, message(Some(StepUpOperation::DestinationChange), 1_000));
+        assert_ne!(signed, message(Some(StepUpOperation::ClaimRewards), 1_001));
+        assert_ne!(signed, message(None, 1_000));
+    }
+
+    #[test]
+    fn test_rejects_cross_instruction_offsets() {
+        let (participants, signatures, message) = fixture();
+        let mut data = ed25519_data(&entries(&participants, &signatures), &message);
+        // Point the first signature at another instruction
+        data[OFFSETS_START + 2..OFFSETS_START + 4].copy_from_slice(&0u16.to_le_bytes());
+        assert!(Ed25519Verifier::check_signature_set(&data, &participants, &signatures, &message).is_err());
+    }
+
+    #[test]
+    fn test_rejects_wrong_signature_length() {
+        let (participants, mut signatures, message) = fixture();
+        signatures[0].truncate(63);
+        let data = ed25519_data(&entries(&participants, &signatures), &message);
+        assert!(Ed25519Verifier::check_signature_set(&data, &participants, &signatures, &message).is_err());
+    }
+
+    #[test]
+    fn test_signed_messages_bind_each_signer_to_its_message() {
+        let (participants, signatures, _) = fixture();
+        let messages = vec![
+            Ed25519Verifier::hft_operation_message(&[7; 32], &[1, 2, 3]),
+            Ed25519Verifier::state_update_message(&[7; 32], 3, &[9; 32]),
+        ];
+        let entries: Vec<(Pubkey, Vec<u8>, Vec<u8>)> = participants
+            .iter()
+            .cloned()
+            .zip(signatures.iter().cloned())
+            .zip(messages.iter().cloned())
+            .map(|((pubkey, signature), message)| (pubkey, signature, message))
+            .collect();
+        let data = ed25519_data_per_message(&entries);
+        assert!(Ed25519Verifier::check_signed_messages(&data, &participants, &signatures, &messages).is_ok());
+
+        let swapped = vec![messages[1].clone(), messages[0].clone()];
+        assert!(Ed25519Verifier::check_signed_messages(&data, &participants, &signatures, &swapped).is_err());
+        assert!(Ed25519Verifier::check_signed_messages(&data, &participants, &signatures, &messages[..1]).is_err());
+    }
+
+    #[test]
+    fn test_rejects_multisig_signature_over_wrong_digest() {
+        let signer = vec![Pubkey::new_unique()];
+        let signature = vec![signature(1)];
+        let signed = Ed25519Verifier::multisig_proposal_message(&[1; 32]);
+        let data = ed25519_data(&entries(&signer, &signature), &signed);
+        assert!(Ed25519Verifier::check_signature_set(&data, &signer, &signature, &signed).is_ok());
+
+        let other_digest = Ed25519Verifier::multisig_proposal_message(&[2; 32]);
+        let bare_digest = vec![1; 32];
+        for expected in [other_digest, bare_digest] {
+            assert!(Ed25519Verifier::check_signature_set(&data, &signer, &signature, &expected).is_err());
+        }
+    }
+
+    #[test]
+    fn test_offline_approval_binds_the_nonce() {
+        let signer = vec![Pubkey::new_unique()];
+        let signature = vec![signature(1)];
+        let signed = Ed25519Verifier::multisig_offline_approval_message(&[1; 32], 4);
+        let data = ed25519_data(&entries(&signer, &signature), &signed);
+        assert!(Ed25519Verifier::check_signature_set(&data, &signer, &signature, &signed).is_ok());
+
+        let replayed = Ed25519Verifier::multisig_offline_approval_message(&[1; 32], 5);
+        let online = Ed25519Verifier::multisig_proposal_message(&[1; 32]);
+        for expected in [replayed, online] {
+            assert!(Ed25519Verifier::check_signature_set(&data, &signer, &signature, &expected).is_err());
+        }
+    }
+}
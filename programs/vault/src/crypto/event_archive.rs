@@ -0,0 +1,129 @@
+use anchor_lang::prelude::*;
+use sha2::{Digest, Sha256};
+
+/// A batch of events pruned from a log in one go
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ArchivedBatch {
+    pub count: u64,
+    pub batch_hash: [u8; 32],
+}
+
+/// Running SHA-256 chain over security events pruned from a user's log.
+///
+/// Each pruned event is Borsh-serialized and hashed into a leaf, and the
+/// root becomes SHA-256(root || leaf), starting from all zeroes. Anyone
+/// holding the pruned events in order can replay the fold and compare the
+/// result with the root stored on-chain.
+pub struct EventArchive;
+
+impl EventArchive {
+    /// Oldest events pruned at once when a log is full
+    pub const BATCH_SIZE: usize = 10;
+
+    pub fn leaf<T: AnchorSerialize>(event: &T) -> Result<[u8; 32]> {
+        Ok(Sha256::digest(event.try_to_vec()?).into())
+    }
+
+    pub fn fold(root: &[u8; 32], leaf: &[u8; 32]) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(root);
+        hasher.update(leaf);
+        hasher.finalize().into()
+    }
+
+    /// Make room for one more event under `max` by pruning the oldest
+    /// events, at least a batch at a time, and folding them into `root` in
+    /// order. The batch hash is SHA-256 over the pruned leaves.
+    pub fn prune<T: AnchorSerialize>(
+        events: &mut Vec<T>,
+        max: usize,
+        root: &mut [u8; 32],
+        archived_count: &mut u64,
+    ) -> Result<Option<ArchivedBatch>> {
+        if events.len() < max {
+            return Ok(None);
+        }
+        let pruned = (events.len() + 1 - max).max(Self::BATCH_SIZE).min(events.len());
+        if pruned == 0 {
+            return Ok(None);
+        }
+
+        let leaves = events[..pruned].iter().map(Self::leaf).collect::<Result<Vec<_>>>()?;
+        let mut batch = Sha256::new();
+        for leaf in &leaves {
+            batch.update(leaf);
+            *root = Self::fold(root, leaf);
+        }
+        events.drain(..pruned);
+        *archived_count += pruned as u64;
+
+        Ok(Some(ArchivedBatch {
+            count: pruned as u64,
+            batch_hash: batch.finalize().into(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Log = Vec<(u64, String)>;
+
+    fn event(n: u64) -> (u64, String) {
+        (n, format!("event {}", n))
+    }
+
+    /// Push `total` events into a log capped at `max`, returning the
+    /// remaining log, the root and count, and everything pruned in order
+    fn run(total: u64, max: usize) -> (Log, [u8; 32], u64, Log) {
+        let (mut log, mut root, mut count, mut pruned): (Log, _, _, Log) = (Vec::new(), [0u8; 32], 0u64, Vec::new());
+        for n in 0..total {
+            let before = log.clone();
+            if let Some(batch) = EventArchive::prune(&mut log, max, &mut root, &mut count).unwrap() {
+                let removed = &before[..batch.count as usize];
+                let mut hasher = Sha256::new();
+                for e in removed {
+                    hasher.update(EventArchive::leaf(e).unwrap());
+                }
+                assert_eq!(batch.batch_hash, <[u8; 32]>::from(hasher.finalize()));
+                pruned.extend_from_slice(removed);
+            }
+            log.push(event(n));
+            assert!(log.len() <= max);
+        }
+        (log, root, count, pruned)
+    }
+
+    #[test]
+    fn test_replaying_pruned_events_reproduces_the_root() {
+        let (log, root, count, pruned) = run(250, 100);
+        assert_eq!(count, pruned.len() as u64);
+        assert_eq!(count, 150);
+        assert_eq!(pruned, (0..150).map(event).collect::<Vec<_>>());
+        assert_eq!(log, (150..250).map(event).collect::<Vec<_>>());
+
+        let replayed = pruned.iter().fold([0u8; 32], |root, e| EventArchive::fold(&root, &EventArchive::leaf(e).unwrap()));
+        assert_eq!(replayed, root);
+
+        // Pruning is deterministic, and the chain commits to order
+        assert_eq!(run(250, 100).1, root);
+        let mut swapped = pruned.clone();
+        swapped.swap(0, 1);
+        let reordered = swapped.iter().fold([0u8; 32], |root, e| EventArchive::fold(&root, &EventArchive::leaf(e).unwrap()));
+        assert_ne!(reordered, root);
+    }
+
+    #[test]
+    fn test_nothing_is_archived_below_the_cap() {
+        let (log, root, count, pruned) = run(100, 100);
+        assert_eq!(log.len(), 100);
+        assert_eq!((root, count), ([0; 32], 0));
+        assert!(pruned.is_empty());
+
+        // Caps smaller than a batch empty the log
+        let (log, _, count, _) = run(6, 5);
+        assert_eq!(count, 5);
+        assert_eq!(log, vec![event(5)]);
+    }
+}
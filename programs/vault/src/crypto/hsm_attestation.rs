@@ -0,0 +1,207 @@
+use anchor_lang::prelude::*;
+use crate::crypto::Ed25519Verifier;
+use crate::errors::VaultError;
+use crate::state::multisig_wallet::{HSMKeyInfo, HsmAttestation, HsmVendor};
+
+/// Domain separator for HSM attestations over multisig proposals
+pub const HSM_ATTESTATION_DOMAIN: &[u8] = b"VAULT_HSM_ATTESTATION_V1";
+
+/// Signers, signatures and messages one Ed25519 instruction must cover
+type SignedEntries = (Vec<Pubkey>, Vec<Vec<u8>>, Vec<Vec<u8>>);
+
+/// Verifier for HSM attestations accompanying multisig signatures.
+///
+/// An attestation is the signer's HSM key signing the proposal digest along
+/// with its vendor and attestation certificate chain hash. Both must match
+/// what the wallet has configured for that signer, and the HSM signature is
+/// checked by the same Ed25519 program instruction as the signer's own.
+pub struct HsmAttestationVerifier;
+
+impl HsmAttestationVerifier {
+    /// Build the message an HSM key signs to attest a proposal digest
+    pub fn attestation_message(
+        vendor: &HsmVendor,
+        cert_chain_hash: &[u8; 32],
+        proposal_digest: &[u8; 32],
+    ) -> Vec<u8> {
+        let mut message = Vec::with_capacity(HSM_ATTESTATION_DOMAIN.len() + 1 + 32 + 32);
+        message.extend_from_slice(HSM_ATTESTATION_DOMAIN);
+        message.push(vendor.clone() as u8);
+        message.extend_from_slice(cert_chain_hash);
+        message.extend_from_slice(proposal_digest);
+        message
+    }
+
+    /// Check an attestation claims the HSM configured for its signer
+    pub fn check_key_binding(hsm_key: Option<&HSMKeyInfo>, attestation: &HsmAttestation) -> Result<()> {
+        let hsm_key = hsm_key.ok_or(VaultError::InvalidHsmAttestation)?;
+        if !hsm_key.is_active
+            || hsm_key.vendor != attestation.vendor
+            || hsm_key.attestation_chain_hash != attestation.cert_chain_hash
+        {
+            return Err(VaultError::InvalidHsmAttestation.into());
+        }
+        Ok(())
+    }
+
+    /// Verify a signer's proposal signature and its HSM attestation against
+    /// the Ed25519 program instruction preceding the current one, which
+    /// must cover the signer first and the HSM key second
+    pub fn verify_with_signer(
+        instructions_sysvar: &AccountInfo,
+        signer: &Pubkey,
+        signature: &[u8; 64],
+        hsm_key: Option<&HSMKeyInfo>,
+        attestation: &HsmAttestation,
+        proposal_digest: &[u8; 32],
+    ) -> Result<()> {
+        Self::check_key_binding(hsm_key, attestation)?;
+        let (signers, signatures, messages) =
+            Self::signed_entries(signer, signature, hsm_key, attestation, proposal_digest)?;
+        Ed25519Verifier::verify_signed_messages(instructions_sysvar, &signers, &signatures, &messages)
+            .map_err(|_| VaultError::InvalidHsmAttestation.into())
+    }
+
+    /// Check Ed25519 program instruction data against a signer's proposal
+    /// signature and its HSM attestation
+    pub fn check_with_signer(
+        ed25519_data: &[u8],
+        signer: &Pubkey,
+        signature: &[u8; 64],
+        hsm_key: Option<&HSMKeyInfo>,
+        attestation: &HsmAttestation,
+        proposal_digest: &[u8; 32],
+    ) -> Result<()> {
+        Self::check_key_binding(hsm_key, attestation)?;
+        let (signers, signatures, messages) =
+            Self::signed_entries(signer, signature, hsm_key, attestation, proposal_digest)?;
+        Ed25519Verifier::check_signed_messages(ed25519_data, &signers, &signatures, &messages)
+            .map_err(|_| VaultError::InvalidHsmAttestation.into())
+    }
+
+    fn signed_entries(
+        signer: &Pubkey,
+        signature: &[u8; 64],
+        hsm_key: Option<&HSMKeyInfo>,
+        attestation: &HsmAttestation,
+        proposal_digest: &[u8; 32],
+    ) -> Result<SignedEntries> {
+        let hsm_key = hsm_key.ok_or(VaultError::InvalidHsmAttestation)?;
+        Ok((
+            vec![*signer, Pubkey::new_from_array(hsm_key.public_key)],
+            vec![signature.to_vec(), attestation.signature.to_vec()],
+            vec![
+                Ed25519Verifier::multisig_proposal_message(proposal_digest),
+                Self::attestation_message(&attestation.vendor, &attestation.cert_chain_hash, proposal_digest),
+            ],
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Lay out Ed25519 program data the way the native program expects
+    fn ed25519_data(entries: &[(Pubkey, Vec<u8>, Vec<u8>)]) -> Vec<u8> {
+        let mut data = vec![entries.len() as u8, 0];
+        let header_len = 2 + entries.len() * 14;
+        let mut payload = Vec::new();
+
+        for (pubkey, signature, message) in entries {
+            let pubkey_offset = header_len + payload.len();
+            payload.extend_from_slice(pubkey.as_ref());
+            let signature_offset = header_len + payload.len();
+            payload.extend_from_slice(signature);
+            let message_offset = header_len + payload.len();
+            payload.extend_from_slice(message);
+
+            for value in [
+                signature_offset, u16::MAX as usize,
+                pubkey_offset, u16::MAX as usize,
+                message_offset, message.len(), u16::MAX as usize,
+            ] {
+                data.extend_from_slice(&(value as u16).to_le_bytes());
+            }
+        }
+
+        data.extend_from_slice(&payload);
+        data
+    }
+
+    fn hsm_key() -> HSMKeyInfo {
+        HSMKeyInfo {
+            key_id: 1,
+            public_key: Pubkey::new_unique().to_bytes(),
+            key_label: "treasury-1".to_string(),
+            vendor: HsmVendor::Yubico,
+            attestation_chain_hash: [3; 32],
+            created_at: 0,
+            last_used: 0,
+            is_active: true,
+            usage_count: 0,
+        }
+    }
+
+    fn attestation() -> HsmAttestation {
+        let mut signature = [5; 64];
+        signature[63] = 0x01; // keep S well below L
+        HsmAttestation {
+            vendor: HsmVendor::Yubico,
+            cert_chain_hash: [3; 32],
+            signature,
+        }
+    }
+
+    fn signer_signature() -> [u8; 64] {
+        let mut signature = [4; 64];
+        signature[63] = 0x01;
+        signature
+    }
+
+    #[test]
+    fn test_key_binding() {
+        let key = hsm_key();
+        HsmAttestationVerifier::check_key_binding(Some(&key), &attestation()).unwrap();
+        assert!(HsmAttestationVerifier::check_key_binding(None, &attestation()).is_err());
+
+        let mut other_vendor = attestation();
+        other_vendor.vendor = HsmVendor::Thales;
+        let mut other_chain = attestation();
+        other_chain.cert_chain_hash = [4; 32];
+        for attestation in [other_vendor, other_chain] {
+            assert!(HsmAttestationVerifier::check_key_binding(Some(&key), &attestation).is_err());
+        }
+
+        let mut retired = hsm_key();
+        retired.is_active = false;
+        assert!(HsmAttestationVerifier::check_key_binding(Some(&retired), &attestation()).is_err());
+    }
+
+    #[test]
+    fn test_attestation_binds_the_proposal_digest() {
+        let key = hsm_key();
+        let signer = Pubkey::new_unique();
+        let digest = [9; 32];
+        let attestation = attestation();
+        let data = ed25519_data(&[
+            (signer, signer_signature().to_vec(), Ed25519Verifier::multisig_proposal_message(&digest)),
+            (
+                Pubkey::new_from_array(key.public_key),
+                attestation.signature.to_vec(),
+                HsmAttestationVerifier::attestation_message(&attestation.vendor, &attestation.cert_chain_hash, &digest),
+            ),
+        ]);
+        HsmAttestationVerifier::check_with_signer(&data, &signer, &signer_signature(), Some(&key), &attestation, &digest)
+            .unwrap();
+
+        // Same bytes checked against another proposal or another HSM key
+        assert!(HsmAttestationVerifier::check_with_signer(
+            &data, &signer, &signer_signature(), Some(&key), &attestation, &[8; 32],
+        ).is_err());
+        let other_key = hsm_key();
+        assert!(HsmAttestationVerifier::check_with_signer(
+            &data, &signer, &signer_signature(), Some(&other_key), &attestation, &digest,
+        ).is_err());
+    }
+}
@@ -1,3 +1,17 @@
+pub mod backup_codes;
+pub mod bolt11;
 pub mod ecdsa_validator;
+pub mod ed25519_verifier;
+pub mod event_archive;
+pub mod hsm_attestation;
+pub mod totp;
+pub mod webauthn;
 
+pub use backup_codes::BackupCodeHasher;
+pub use bolt11::Bolt11Invoice;
 pub use ecdsa_validator::ECDSAValidator;
+pub use ed25519_verifier::Ed25519Verifier;
+pub use event_archive::{ArchivedBatch, EventArchive};
+pub use hsm_attestation::HsmAttestationVerifier;
+pub use totp::TotpVerifier;
+pub use webauthn::{AttestedCredential, AuthenticatorData, WebAuthnVerifier};
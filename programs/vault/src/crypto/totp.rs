@@ -0,0 +1,176 @@
+use sha2::{Digest, Sha256};
+
+/// RFC 6238 time-based one-time passwords over HMAC-SHA256, as used by the
+/// TOTP auth factor.
+///
+/// Codes are six digits for 30-second steps, and one step of clock drift
+/// either way is allowed. A code is only accepted for a step later than
+/// the last one accepted, so it can't be replayed.
+///
+/// The shared key never goes on-chain. The off-chain TOTP verifier holds it
+/// and checks codes with `verify`, then signs an attestation for the step
+/// it matched. The program only stores `commitment(key)` and checks the
+/// attested step with `accepts_step`.
+pub struct TotpVerifier;
+
+impl TotpVerifier {
+    pub const STEP_SECONDS: i64 = 30;
+    pub const DIGITS: u32 = 6;
+    pub const SKEW_STEPS: u64 = 1;
+
+    const BLOCK_SIZE: usize = 64;
+
+    /// HMAC-SHA256 (RFC 2104) of `message` under `key`
+    pub fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+        let mut block = [0u8; Self::BLOCK_SIZE];
+        if key.len() > Self::BLOCK_SIZE {
+            block[..32].copy_from_slice(&Sha256::digest(key));
+        } else {
+            block[..key.len()].copy_from_slice(key);
+        }
+
+        let mut inner = Sha256::new();
+        inner.update(block.map(|b| b ^ 0x36));
+        inner.update(message);
+        let mut outer = Sha256::new();
+        outer.update(block.map(|b| b ^ 0x5c));
+        outer.update(inner.finalize());
+        outer.finalize().into()
+    }
+
+    /// The `digits`-digit HOTP code (RFC 4226) for `counter`
+    pub fn code_at(key: &[u8], counter: u64, digits: u32) -> u32 {
+        let mac = Self::hmac_sha256(key, &counter.to_be_bytes());
+        let offset = (mac[mac.len() - 1] & 0x0f) as usize;
+        let binary = u32::from_be_bytes([mac[offset] & 0x7f, mac[offset + 1], mac[offset + 2], mac[offset + 3]]);
+        binary % 10u32.pow(digits)
+    }
+
+    /// SHA-256 commitment to a TOTP key, as stored on the factor
+    pub fn commitment(key: &[u8]) -> [u8; 32] {
+        Sha256::digest(key).into()
+    }
+
+    /// Whether an attested step is within the skew window around `now` and
+    /// later than `last_counter`
+    pub fn accepts_step(counter: u64, now: i64, last_counter: u64) -> bool {
+        if now < 0 || counter <= last_counter {
+            return false;
+        }
+        let current = (now / Self::STEP_SECONDS) as u64;
+        counter >= current.saturating_sub(Self::SKEW_STEPS) && counter <= current.saturating_add(Self::SKEW_STEPS)
+    }
+
+    /// Check `provided_code` against the steps around `now`, skipping any
+    /// at or before `last_counter`. Returns the step it matched.
+    pub fn verify(key: &[u8], provided_code: &str, now: i64, last_counter: u64) -> Option<u64> {
+        if provided_code.len() != Self::DIGITS as usize
+            || !provided_code.bytes().all(|b| b.is_ascii_digit())
+            || now < 0
+        {
+            return None;
+        }
+        let current = (now / Self::STEP_SECONDS) as u64;
+
+        // Every candidate step is compared so timing doesn't reveal which matched
+        let mut matched = None;
+        for counter in current.saturating_sub(Self::SKEW_STEPS)..=current.saturating_add(Self::SKEW_STEPS) {
+            let expected = format!("{:0width$}", Self::code_at(key, counter, Self::DIGITS), width = Self::DIGITS as usize);
+            if Self::constant_time_eq(expected.as_bytes(), provided_code.as_bytes()) && counter > last_counter {
+                matched = Some(counter);
+            }
+        }
+        matched
+    }
+
+    /// Byte comparison whose timing doesn't depend on where inputs differ
+    pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+        a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RFC 6238 appendix B seed for HMAC-SHA256: the ASCII digits repeated to 32 bytes
+    fn rfc_key() -> Vec<u8> {
+        b"1234567890".iter().cycle().take(32).copied().collect()
+    }
+
+    #[test]
+    fn test_hmac_matches_rfc_4231() {
+        let mac = TotpVerifier::hmac_sha256(b"Jefe", b"what do ya want for nothing?");
+        assert_eq!(mac, [
+            0x5b, 0xdc, 0xc1, 0x46, 0xbf, 0x60, 0x75, 0x4e,
+            0x6a, 0x04, 0x24, 0x26, 0x08, 0x95, 0x75, 0xc7,
+            0x5a, 0x00, 0x3f, 0x08, 0x9d, 0x27, 0x39, 0x83,
+            0x9d, 0xec, 0x58, 0xb9, 0x64, 0xec, 0x38, 0x43,
+        ]);
+
+        // Keys longer than a block are hashed first
+        let mac = TotpVerifier::hmac_sha256(&[0xaa; 131], b"Test Using Larger Than Block-Size Key - Hash Key First");
+        assert_eq!(mac, [
+            0x60, 0xe4, 0x31, 0x59, 0x1e, 0xe0, 0xb6, 0x7f,
+            0x0d, 0x8a, 0x26, 0xaa, 0xcb, 0xf5, 0xb7, 0x7f,
+            0x8e, 0x0b, 0xc6, 0x21, 0x37, 0x28, 0xc5, 0x14,
+            0x05, 0x46, 0x04, 0x0f, 0x0e, 0xe3, 0x7f, 0x54,
+        ]);
+    }
+
+    #[test]
+    fn test_codes_match_rfc_6238_vectors() {
+        let vectors = [
+            (59, 46119246),
+            (1111111109, 68084774),
+            (1111111111, 67062674),
+            (1234567890, 91819424),
+            (2000000000, 90698825),
+            (20000000000, 77737706),
+        ];
+        for (time, code) in vectors {
+            let counter = (time / TotpVerifier::STEP_SECONDS) as u64;
+            assert_eq!(TotpVerifier::code_at(&rfc_key(), counter, 8), code);
+        }
+    }
+
+    #[test]
+    fn test_codes_verify_once_within_the_skew_window() {
+        let rfc_key = rfc_key();
+        // Six-digit code for T = 59, step 1
+        assert_eq!(TotpVerifier::verify(&rfc_key, "119246", 59, 0), Some(1));
+        assert_eq!(TotpVerifier::verify(&rfc_key, "119246", 89, 0), Some(1));
+        assert_eq!(TotpVerifier::verify(&rfc_key, "119246", 30, 0), Some(1));
+        assert_eq!(TotpVerifier::verify(&rfc_key, "119246", 90, 0), None);
+
+        // Replays of the accepted step are refused
+        assert_eq!(TotpVerifier::verify(&rfc_key, "119246", 59, 1), None);
+
+        assert_eq!(TotpVerifier::verify(&rfc_key, "119247", 59, 0), None);
+        assert_eq!(TotpVerifier::verify(&rfc_key, "46119246", 59, 0), None);
+        assert_eq!(TotpVerifier::verify(&rfc_key, "11924a", 59, 0), None);
+        assert_eq!(TotpVerifier::verify(&[0; 32], "119246", 59, 0), None);
+    }
+
+    #[test]
+    fn test_attested_steps_accepted_once_within_the_skew_window() {
+        assert!(TotpVerifier::accepts_step(1, 59, 0));
+        assert!(TotpVerifier::accepts_step(2, 59, 0));
+        assert!(TotpVerifier::accepts_step(1, 89, 0));
+        assert!(!TotpVerifier::accepts_step(1, 90, 0));
+        assert!(!TotpVerifier::accepts_step(3, 59, 0));
+
+        // Replays and earlier steps are refused
+        assert!(!TotpVerifier::accepts_step(1, 59, 1));
+        assert!(!TotpVerifier::accepts_step(1, 59, 2));
+        assert!(!TotpVerifier::accepts_step(1, -1, 0));
+    }
+
+    #[test]
+    fn test_commitment_hides_the_key() {
+        let rfc_key = rfc_key();
+        let commitment = TotpVerifier::commitment(&rfc_key);
+        assert_ne!(commitment.to_vec(), rfc_key);
+        assert_ne!(commitment, TotpVerifier::commitment(&[0; 32]));
+    }
+}
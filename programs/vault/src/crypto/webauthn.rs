@@ -0,0 +1,341 @@
+use anchor_lang::prelude::*;
+use sha2::{Digest, Sha256};
+use crate::crypto::Ed25519Verifier;
+use crate::errors::VaultError;
+
+// Authenticator data flags
+const FLAG_USER_PRESENT: u8 = 0x01;
+const FLAG_USER_VERIFIED: u8 = 0x04;
+const FLAG_BACKUP_ELIGIBLE: u8 = 0x08;
+const FLAG_BACKED_UP: u8 = 0x10;
+const FLAG_ATTESTED_CREDENTIAL: u8 = 0x40;
+const FLAG_EXTENSIONS: u8 = 0x80;
+
+/// RP ID hash, flags and sign count
+const AUTHENTICATOR_DATA_MIN_LEN: usize = 37;
+
+/// AAGUID and credential ID length, ahead of the attested credential ID
+const ATTESTED_CREDENTIAL_HEADER_LEN: usize = 16 + 2;
+
+// COSE key labels and the values an Ed25519 key carries
+const COSE_KTY: i64 = 1;
+const COSE_ALG: i64 = 3;
+const COSE_OKP_CRV: i64 = -1;
+const COSE_OKP_X: i64 = -2;
+const COSE_KTY_OKP: i64 = 1;
+const COSE_ALG_EDDSA: i64 = -8;
+const COSE_CRV_ED25519: i64 = 6;
+
+/// The fixed-layout prefix of WebAuthn authenticator data
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuthenticatorData {
+    pub rp_id_hash: [u8; 32],
+    pub flags: u8,
+    pub sign_count: u32,
+}
+
+impl AuthenticatorData {
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        if data.len() < AUTHENTICATOR_DATA_MIN_LEN {
+            return Err(VaultError::InvalidWebAuthnAssertion.into());
+        }
+        let mut rp_id_hash = [0u8; 32];
+        rp_id_hash.copy_from_slice(&data[..32]);
+        Ok(Self {
+            rp_id_hash,
+            flags: data[32],
+            sign_count: u32::from_be_bytes([data[33], data[34], data[35], data[36]]),
+        })
+    }
+
+    pub fn user_present(&self) -> bool {
+        self.flags & FLAG_USER_PRESENT != 0
+    }
+
+    pub fn user_verified(&self) -> bool {
+        self.flags & FLAG_USER_VERIFIED != 0
+    }
+
+    /// Whether the credential may be synced to other devices
+    pub fn backup_eligible(&self) -> bool {
+        self.flags & FLAG_BACKUP_ELIGIBLE != 0
+    }
+
+    /// Whether the credential is currently synced
+    pub fn backed_up(&self) -> bool {
+        self.flags & FLAG_BACKED_UP != 0
+    }
+}
+
+/// Authenticator data from a registration, carrying the new credential
+#[derive(Clone, Debug, PartialEq)]
+pub struct AttestedCredential {
+    pub authenticator_data: AuthenticatorData,
+    pub aaguid: [u8; 16],
+    pub credential_id: Vec<u8>,
+    pub public_key: [u8; 32],
+}
+
+impl AttestedCredential {
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        let authenticator_data = AuthenticatorData::parse(data)?;
+        if authenticator_data.flags & FLAG_ATTESTED_CREDENTIAL == 0 {
+            return Err(VaultError::InvalidWebAuthnCredential.into());
+        }
+        let attested = &data[AUTHENTICATOR_DATA_MIN_LEN..];
+        let header = attested
+            .get(..ATTESTED_CREDENTIAL_HEADER_LEN)
+            .ok_or(VaultError::InvalidWebAuthnCredential)?;
+        let mut aaguid = [0u8; 16];
+        aaguid.copy_from_slice(&header[..16]);
+        let id_end = ATTESTED_CREDENTIAL_HEADER_LEN + u16::from_be_bytes([header[16], header[17]]) as usize;
+        let credential_id = attested
+            .get(ATTESTED_CREDENTIAL_HEADER_LEN..id_end)
+            .ok_or(VaultError::InvalidWebAuthnCredential)?
+            .to_vec();
+
+        // Only extension outputs may follow the credential key
+        let (public_key, key_len) = WebAuthnVerifier::read_ed25519_key(&attested[id_end..])?;
+        if id_end + key_len != attested.len() && authenticator_data.flags & FLAG_EXTENSIONS == 0 {
+            return Err(VaultError::InvalidWebAuthnCredential.into());
+        }
+
+        Ok(Self {
+            authenticator_data,
+            aaguid,
+            credential_id,
+            public_key,
+        })
+    }
+}
+
+/// Verifier for WebAuthn assertions from Ed25519 (COSE alg -8) credentials.
+///
+/// The authenticator signs its authenticator data followed by the SHA-256
+/// of the client data JSON. The signature is checked by the native Ed25519
+/// program, like other signatures in the vault. ES256 credentials can't be
+/// registered: the runtime has no P-256 precompile to check them with.
+pub struct WebAuthnVerifier;
+
+impl WebAuthnVerifier {
+    /// SHA-256 of a relying party ID, as it appears in authenticator data
+    pub fn rp_id_hash(rp_id: &str) -> [u8; 32] {
+        Sha256::digest(rp_id.as_bytes()).into()
+    }
+
+    /// Extract the public key from a COSE-encoded Ed25519 credential key
+    pub fn ed25519_public_key(cose_key: &[u8]) -> Result<[u8; 32]> {
+        match Self::read_ed25519_key(cose_key)? {
+            (public_key, len) if len == cose_key.len() => Ok(public_key),
+            _ => Err(VaultError::UnsupportedWebAuthnKey.into()),
+        }
+    }
+
+    /// Read a COSE-encoded Ed25519 key from the start of `data`, returning
+    /// the public key and the length of the encoding
+    fn read_ed25519_key(data: &[u8]) -> Result<([u8; 32], usize)> {
+        let mut reader = CborReader { data, at: 0 };
+        let entries = reader.map_len()?;
+        let (mut kty, mut alg, mut crv, mut x) = (None, None, None, None);
+        for _ in 0..entries {
+            match reader.int()? {
+                COSE_KTY => kty = Some(reader.int()?),
+                COSE_ALG => alg = Some(reader.int()?),
+                COSE_OKP_CRV => crv = Some(reader.int()?),
+                COSE_OKP_X => x = Some(reader.bytes()?),
+                _ => return Err(VaultError::UnsupportedWebAuthnKey.into()),
+            }
+        }
+        if kty != Some(COSE_KTY_OKP) || alg != Some(COSE_ALG_EDDSA) || crv != Some(COSE_CRV_ED25519) {
+            return Err(VaultError::UnsupportedWebAuthnKey.into());
+        }
+        let public_key = x.and_then(|x| x.try_into().ok()).ok_or(VaultError::UnsupportedWebAuthnKey)?;
+        Ok((public_key, reader.at))
+    }
+
+    /// Check authenticator data was produced for `expected_rp_id_hash` with
+    /// the user present
+    pub fn check_authenticator_data(data: &AuthenticatorData, expected_rp_id_hash: &[u8; 32]) -> Result<()> {
+        if *expected_rp_id_hash == [0; 32] || data.rp_id_hash != *expected_rp_id_hash || !data.user_present() {
+            return Err(VaultError::InvalidWebAuthnAssertion.into());
+        }
+        Ok(())
+    }
+
+    /// Whether a sign count can't follow the stored one. Authenticators
+    /// that don't keep a counter always report zero.
+    pub fn sign_count_regressed(stored: u32, reported: u32) -> bool {
+        (stored != 0 || reported != 0) && reported <= stored
+    }
+
+    /// The message an authenticator signs for an assertion
+    pub fn signed_message(authenticator_data: &[u8], client_data_hash: &[u8; 32]) -> Vec<u8> {
+        let mut message = Vec::with_capacity(authenticator_data.len() + 32);
+        message.extend_from_slice(authenticator_data);
+        message.extend_from_slice(client_data_hash);
+        message
+    }
+
+    /// Verify an assertion signature by `public_key` against the Ed25519
+    /// program instruction preceding the current one
+    pub fn verify_signature(
+        instructions_sysvar: &AccountInfo,
+        public_key: &[u8; 32],
+        signature: &[u8; 64],
+        authenticator_data: &[u8],
+        client_data_hash: &[u8; 32],
+    ) -> Result<()> {
+        Ed25519Verifier::verify_signed_messages(
+            instructions_sysvar,
+            &[Pubkey::new_from_array(*public_key)],
+            &[signature.to_vec()],
+            &[Self::signed_message(authenticator_data, client_data_hash)],
+        )
+        .map_err(|_| VaultError::InvalidWebAuthnAssertion.into())
+    }
+}
+
+/// Just enough CBOR to read a COSE key: a map of small integers and byte
+/// strings
+struct CborReader<'a> {
+    data: &'a [u8],
+    at: usize,
+}
+
+impl<'a> CborReader<'a> {
+    fn next(&mut self) -> Result<u8> {
+        let byte = *self.data.get(self.at).ok_or(VaultError::UnsupportedWebAuthnKey)?;
+        self.at += 1;
+        Ok(byte)
+    }
+
+    /// Major type and argument of the next item, for arguments up to a byte
+    fn header(&mut self) -> Result<(u8, u64)> {
+        let initial = self.next()?;
+        let argument = match initial & 0x1f {
+            n @ 0..=23 => n as u64,
+            24 => self.next()? as u64,
+            _ => return Err(VaultError::UnsupportedWebAuthnKey.into()),
+        };
+        Ok((initial >> 5, argument))
+    }
+
+    fn map_len(&mut self) -> Result<u64> {
+        match self.header()? {
+            (5, entries) => Ok(entries),
+            _ => Err(VaultError::UnsupportedWebAuthnKey.into()),
+        }
+    }
+
+    fn int(&mut self) -> Result<i64> {
+        match self.header()? {
+            (0, value) => Ok(value as i64),
+            (1, value) => Ok(-1 - value as i64),
+            _ => Err(VaultError::UnsupportedWebAuthnKey.into()),
+        }
+    }
+
+    fn bytes(&mut self) -> Result<&'a [u8]> {
+        let (2, len) = self.header()? else {
+            return Err(VaultError::UnsupportedWebAuthnKey.into());
+        };
+        let end = self.at + len as usize;
+        let data = self.data;
+        let bytes = data.get(self.at..end).ok_or(VaultError::UnsupportedWebAuthnKey)?;
+        self.at = end;
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cose_ed25519(x: &[u8; 32]) -> Vec<u8> {
+        let mut key = vec![0xa4, 0x01, 0x01, 0x03, 0x27, 0x20, 0x06, 0x21, 0x58, 0x20];
+        key.extend_from_slice(x);
+        key
+    }
+
+    fn authenticator_data(rp_id: &str, flags: u8, sign_count: u32) -> Vec<u8> {
+        let mut data = WebAuthnVerifier::rp_id_hash(rp_id).to_vec();
+        data.push(flags);
+        data.extend_from_slice(&sign_count.to_be_bytes());
+        data
+    }
+
+    #[test]
+    fn test_only_ed25519_cose_keys_are_accepted() {
+        assert_eq!(WebAuthnVerifier::ed25519_public_key(&cose_ed25519(&[7; 32])).unwrap(), [7; 32]);
+
+        // ES256: kty EC2, alg -7, crv P-256, x and y
+        let mut es256 = vec![0xa5, 0x01, 0x02, 0x03, 0x26, 0x20, 0x01, 0x21, 0x58, 0x20];
+        es256.extend_from_slice(&[1; 32]);
+        es256.extend_from_slice(&[0x22, 0x58, 0x20]);
+        es256.extend_from_slice(&[2; 32]);
+        assert!(WebAuthnVerifier::ed25519_public_key(&es256).is_err());
+
+        let key = cose_ed25519(&[7; 32]);
+        assert!(WebAuthnVerifier::ed25519_public_key(&key[..key.len() - 1]).is_err());
+        let mut trailing = key.clone();
+        trailing.push(0);
+        assert!(WebAuthnVerifier::ed25519_public_key(&trailing).is_err());
+    }
+
+    #[test]
+    fn test_authenticator_data_is_bound_to_the_rp_and_user_presence() {
+        let expected = WebAuthnVerifier::rp_id_hash("vault.example");
+        let data = AuthenticatorData::parse(&authenticator_data("vault.example", 0x05, 9)).unwrap();
+        assert_eq!(data.sign_count, 9);
+        WebAuthnVerifier::check_authenticator_data(&data, &expected).unwrap();
+        assert!(WebAuthnVerifier::check_authenticator_data(&data, &[0; 32]).is_err());
+
+        let other_rp = AuthenticatorData::parse(&authenticator_data("evil.example", 0x01, 9)).unwrap();
+        assert!(WebAuthnVerifier::check_authenticator_data(&other_rp, &expected).is_err());
+        let absent = AuthenticatorData::parse(&authenticator_data("vault.example", 0x04, 9)).unwrap();
+        assert!(WebAuthnVerifier::check_authenticator_data(&absent, &expected).is_err());
+        assert!(AuthenticatorData::parse(&[0; 36]).is_err());
+    }
+
+    fn registration(flags: u8, credential_id: &[u8], trailing: &[u8]) -> Vec<u8> {
+        let mut data = authenticator_data("vault.example", flags, 0);
+        data.extend_from_slice(&[3; 16]);
+        data.extend_from_slice(&(credential_id.len() as u16).to_be_bytes());
+        data.extend_from_slice(credential_id);
+        data.extend_from_slice(&cose_ed25519(&[7; 32]));
+        data.extend_from_slice(trailing);
+        data
+    }
+
+    #[test]
+    fn test_attested_credentials_carry_the_passkey_metadata() {
+        let credential = AttestedCredential::parse(&registration(0x5d, &[1, 2, 3], &[])).unwrap();
+        assert_eq!(credential.aaguid, [3; 16]);
+        assert_eq!(credential.credential_id, vec![1, 2, 3]);
+        assert_eq!(credential.public_key, [7; 32]);
+        let flags = &credential.authenticator_data;
+        assert!(flags.user_present() && flags.user_verified() && flags.backup_eligible() && flags.backed_up());
+
+        let device_bound = AttestedCredential::parse(&registration(0x45, &[1], &[])).unwrap();
+        assert!(!device_bound.authenticator_data.backup_eligible());
+        assert!(!device_bound.authenticator_data.backed_up());
+
+        // No attested credential, or bytes after the key without extensions
+        assert!(AttestedCredential::parse(&registration(0x05, &[1], &[])).is_err());
+        assert!(AttestedCredential::parse(&registration(0x45, &[1], &[0xa0])).is_err());
+        assert!(AttestedCredential::parse(&registration(0xc5, &[1], &[0xa0])).is_ok());
+
+        let data = registration(0x45, &[1, 2, 3], &[]);
+        assert!(AttestedCredential::parse(&data[..data.len() - 1]).is_err());
+        assert!(AttestedCredential::parse(&data[..AUTHENTICATOR_DATA_MIN_LEN + 17]).is_err());
+    }
+
+    #[test]
+    fn test_sign_counts_must_increase_unless_unsupported() {
+        assert!(!WebAuthnVerifier::sign_count_regressed(0, 0));
+        assert!(!WebAuthnVerifier::sign_count_regressed(0, 1));
+        assert!(!WebAuthnVerifier::sign_count_regressed(5, 6));
+        assert!(WebAuthnVerifier::sign_count_regressed(5, 5));
+        assert!(WebAuthnVerifier::sign_count_regressed(5, 0));
+    }
+}
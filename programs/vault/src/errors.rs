@@ -350,4 +350,391 @@ pub enum VaultError {
     
     #[msg("Clock unavailable")]
     ClockUnavailable,
+    
+    // Governance errors
+    #[msg("Timelock has not elapsed")]
+    TimelockNotElapsed,
+    
+    #[msg("Reward rate changes must go through the multisig")]
+    MultisigRateControlRequired,
+    
+    #[msg("Required account not provided")]
+    MissingRequiredAccount,
+    
+    #[msg("Claim id does not match payment")]
+    ClaimIdMismatch,
+    
+    // State channel errors
+    #[msg("Referenced state predates the latest checkpoint")]
+    StateBeforeCheckpoint,
+    
+    #[msg("State channel signatures are missing, out of order or invalid")]
+    InvalidChannelSignature,
+    
+    #[msg("Channel purpose is not permitted in this compliance region")]
+    ChannelPurposeRestricted,
+    
+    #[msg("Settlement leaf does not match the committed root")]
+    InvalidSettlementProof,
+    
+    #[msg("Settlement leaf has already been applied")]
+    SettlementLeafAlreadyApplied,
+    
+    // Enhanced state channel errors
+    #[msg("Order book side is full")]
+    OrderBookFull,
+    
+    #[msg("Order not found")]
+    OrderNotFound,
+    
+    #[msg("Trigger order limit price conflicts with its trigger price")]
+    TriggerLimitConflict,
+    
+    #[msg("Operation nonce already used")]
+    OperationReplayed,
+    
+    #[msg("Operation nonce out of sequence")]
+    InvalidOperationNonce,
+    
+    #[msg("Dispute evidence does not reference a sequenced operation")]
+    InvalidDisputeEvidence,
+    
+    #[msg("Operation would exceed the participant's exposure limit")]
+    ExposureLimitExceeded,
+    
+    #[msg("Pending operation has expired")]
+    OperationExpired,
+    
+    #[msg("Participant exceeded the channel's operation rate")]
+    RateLimitExceeded,
+    
+    #[msg("Asset is not valid for this channel operation")]
+    AssetMismatch,
+    
+    #[msg("Operation exceeds the session key's value cap")]
+    SessionKeyLimitExceeded,
+    
+    #[msg("Channel account version is not supported by this instruction")]
+    UnsupportedChannelVersion,
+    
+    // Multisig errors
+    #[msg("Multisig signature does not verify over the proposal digest")]
+    InvalidProposalSignature,
+    
+    #[msg("Proposal no longer matches the digest its signers approved")]
+    ProposalDigestMismatch,
+    
+    #[msg("Not enough approving signatures carry an HSM attestation")]
+    MissingHsmAttestation,
+    
+    #[msg("HSM attestation does not match the signer's HSM or fails verification")]
+    InvalidHsmAttestation,
+    
+    #[msg("Multisig payload is not a valid action for its transaction type")]
+    InvalidMultisigAction,
+    
+    #[msg("Account does not match the multisig action's target")]
+    ActionAccountMismatch,
+    
+    // Emergency errors
+    #[msg("Staking is paused by emergency mode")]
+    StakingPaused,
+    
+    #[msg("State channels are paused by emergency mode")]
+    ChannelsPaused,
+    
+    #[msg("Oracle writes are paused by emergency mode")]
+    OracleWritesPaused,
+    
+    #[msg("No emergency scope selected")]
+    EmptyEmergencyScope,
+    
+    // Multisig fast path errors
+    #[msg("Transaction does not qualify for the multisig fast path")]
+    FastPathUnavailable,
+    
+    #[msg("Fast path transfer exceeds a signer's daily limit")]
+    FastPathLimitExceeded,
+    
+    // Multisig veto errors
+    #[msg("Transaction is no longer within its veto window")]
+    VetoWindowClosed,
+    
+    // Multisig offline approval errors
+    #[msg("Offline approval was signed for a different approval nonce")]
+    StaleApprovalNonce,
+    
+    // Multisig hierarchy errors
+    #[msg("Parent wallet is invalid or would form a cycle")]
+    InvalidParentWallet,
+    
+    // SPL token payment errors
+    #[msg("Payment mint is not configured")]
+    UnsupportedPaymentMint,
+    
+    #[msg("Payment mint limits or fee are invalid")]
+    InvalidPaymentMintConfig,
+    
+    #[msg("Too many payment mints configured")]
+    TooManyPaymentMints,
+    
+    #[msg("Token account does not match the payment mint or recipient")]
+    InvalidPaymentTokenAccount,
+    
+    // Lightning invoice errors
+    #[msg("Lightning invoice has expired")]
+    InvoiceExpired,
+    
+    #[msg("Lightning invoice amount does not match the payment amount")]
+    InvoiceAmountMismatch,
+    
+    // Payment schedule errors
+    #[msg("Payment schedule frequency, amount or start time is invalid")]
+    InvalidPaymentSchedule,
+    
+    #[msg("Payment method cannot be scheduled")]
+    UnsupportedScheduleMethod,
+    
+    // Payment quote errors
+    #[msg("Payment quote does not match the request")]
+    InvalidPaymentQuote,
+    
+    // Payout velocity errors
+    #[msg("Enhanced KYC payout limit cannot be tighter than the standard limit")]
+    InvalidVelocityLimits,
+    
+    #[msg("Daily payout limit reached; retry once the window resets")]
+    VelocityLimitExceeded,
+    
+    // Payment approval errors
+    #[msg("Payment awaits multisig approval")]
+    PaymentApprovalRequired,
+    
+    // Address book errors
+    #[msg("Invalid or duplicate saved destination")]
+    InvalidSavedDestination,
+    #[msg("Address book is full for this payment method")]
+    SavedDestinationLimitReached,
+    #[msg("Saved destination not found")]
+    SavedDestinationNotFound,
+    #[msg("Saved destination is not active yet")]
+    SavedDestinationNotActive,
+    #[msg("Payouts are locked to saved destinations")]
+    SavedDestinationRequired,
+    
+    // Payment stream errors
+    #[msg("Invalid payment stream terms")]
+    InvalidPaymentStream,
+    #[msg("Withdrawal exceeds the stream's unlocked amount")]
+    StreamWithdrawalExceedsUnlocked,
+    #[msg("Payment stream already cancelled")]
+    PaymentStreamCancelled,
+    
+    // Payment retry errors
+    #[msg("Payment retry is still backing off")]
+    PaymentRetryBackoff,
+    #[msg("Dead-letter queue is full")]
+    DeadLetterQueueFull,
+    
+    // Split payment errors
+    #[msg("Split parts must number 2 to 3 and add up to 10000 basis points")]
+    InvalidSplitPayment,
+    
+    // Payment float errors
+    #[msg("Payment float can't cover this request")]
+    InsufficientPaymentFloat,
+    
+    // Destination screening errors
+    #[msg("Invalid destination screening policy")]
+    InvalidScreeningPolicy,
+    #[msg("Payout destination has not been screened")]
+    DestinationNotScreened,
+    #[msg("Payout destination screening has expired")]
+    DestinationScreeningExpired,
+    #[msg("Payout destination matches a sanctions list")]
+    DestinationSanctioned,
+    
+    // Idempotency errors
+    #[msg("Idempotency key already used with different parameters")]
+    IdempotencyKeyReused,
+    
+    // Destination replacement errors
+    #[msg("Payment destination can't be replaced again")]
+    DestinationReplacementLimitReached,
+    #[msg("Payment approval is for an earlier approval round")]
+    PaymentApprovalStale,
+    
+    // KYC tier errors
+    #[msg("A higher KYC tier cannot have tighter limits than the tier below")]
+    InvalidTierLimits,
+    #[msg("Transaction exceeds the KYC tier's per-transaction limit")]
+    KycTransactionLimitExceeded,
+    #[msg("Transaction exceeds the KYC tier's monthly limit")]
+    KycMonthlyLimitExceeded,
+    #[msg("Transaction exceeds the KYC tier's lifetime limit")]
+    KycLifetimeLimitExceeded,
+    
+    // Sanctions list errors
+    #[msg("Sanctions root must be set and stay current for a positive period")]
+    InvalidSanctionsRoot,
+    #[msg("Sanctions list proof does not verify against the current root")]
+    InvalidSanctionsProof,
+    
+    // Travel rule errors
+    #[msg("Travel rule data must be attached to this payment")]
+    TravelRuleDataRequired,
+    
+    // Region policy errors
+    #[msg("Feature is not available in the user's region")]
+    RegionFeatureRestricted,
+    #[msg("Commitment exceeds the user's region cap")]
+    RegionCommitmentCapExceeded,
+    
+    // Alert case errors
+    #[msg("Alert cannot move to that state from its current one")]
+    InvalidAlertTransition,
+    
+    // Freeze scope errors
+    #[msg("Freeze scope must block at least one action")]
+    InvalidFreezeScope,
+    
+    // Screening history errors
+    #[msg("Screening record has an invalid provider, risk score or expiry")]
+    InvalidScreeningRecord,
+    #[msg("Screening history hash chain does not verify")]
+    ScreeningHistoryTampered,
+    #[msg("High-value transaction needs a more recent AML screening")]
+    RecentScreeningRequired,
+    #[msg("Screening max age must be positive")]
+    InvalidScreeningMaxAge,
+    
+    // Compliance snapshot errors
+    #[msg("Snapshot profiles must be non-empty and in strictly ascending user order")]
+    InvalidSnapshotProfiles,
+    
+    // KYC trigger errors
+    #[msg("KYC trigger grace periods can't be negative")]
+    InvalidKycTrigger,
+    
+    // Screening provider errors
+    #[msg("Screening provider key hash must be set and differ from the current one")]
+    InvalidScreeningProvider,
+    #[msg("Screening provider is not registered")]
+    UnknownScreeningProvider,
+    #[msg("Screening provider registry is full")]
+    TooManyScreeningProviders,
+    #[msg("Screening was submitted under an inactive provider or rotated key")]
+    StaleScreeningKey,
+    
+    // Compliance flag errors
+    #[msg("Compliance flag is not set")]
+    ComplianceFlagNotSet,
+    #[msg("Clearing a compliance flag needs a justification")]
+    JustificationRequired,
+    
+    // Batch operation errors
+    #[msg("Batch is empty or larger than allowed")]
+    InvalidBatchSize,
+    
+    // WebAuthn errors
+    #[msg("Only Ed25519 WebAuthn credential keys are supported")]
+    UnsupportedWebAuthnKey,
+    #[msg("Invalid WebAuthn credential")]
+    InvalidWebAuthnCredential,
+    #[msg("Invalid WebAuthn assertion")]
+    InvalidWebAuthnAssertion,
+    #[msg("Authentication factor is disabled")]
+    AuthFactorDisabled,
+    #[msg("Invalid relying party ID")]
+    InvalidRpId,
+    
+    // Backup code errors
+    #[msg("Backup codes must be 1 to 10 distinct code digests")]
+    InvalidBackupCodeSet,
+    #[msg("A primary factor must have been verified in the last five minutes")]
+    RecentPrimaryVerificationRequired,
+    #[msg("SlotHashes sysvar data is malformed")]
+    InvalidSlotHashes,
+    
+    // Device management errors
+    #[msg("Device not found")]
+    DeviceNotFound,
+    #[msg("Device is already registered")]
+    DeviceAlreadyRegistered,
+    #[msg("Device registry is full of trusted devices")]
+    TooManyDevices,
+    #[msg("Device ID or name is empty or too long")]
+    InvalidDeviceLabel,
+    
+    // Session permission errors
+    #[msg("Session does not hold the permission this operation needs")]
+    MissingSessionPermission,
+    #[msg("Recently verified factors can't grant the requested permissions")]
+    PermissionUpgradeNotAllowed,
+    
+    // IP whitelist errors
+    #[msg("IP whitelist is full")]
+    IpWhitelistFull,
+    
+    // Account recovery errors
+    #[msg("Guardians must be at most 3 distinct keys other than the user")]
+    InvalidGuardians,
+    #[msg("Signer is not a recovery guardian")]
+    NotAGuardian,
+    #[msg("An account recovery is already pending")]
+    RecoveryAlreadyPending,
+    #[msg("No account recovery is pending")]
+    NoRecoveryPending,
+    #[msg("Recovery factor key is empty")]
+    InvalidRecoveryFactor,
+    
+    // Verification throttling errors
+    #[msg("Too many failed verifications; try again after the cool-down")]
+    VerificationThrottled,
+    
+    // Session renewal errors
+    #[msg("Session can only be renewed in the hour before its absolute expiry")]
+    SessionRenewalNotDue,
+    
+    // Passkey errors
+    #[msg("Credential ID is already registered for this user")]
+    DuplicateCredentialId,
+    #[msg("Passkeys must be used with user verification")]
+    UserVerificationRequired,
+    #[msg("The last strong factor can't be removed while 2FA is required")]
+    LastStrongFactor,
+    
+    // Operation risk errors
+    #[msg("Risk thresholds must be between 0 and 100")]
+    InvalidRiskThreshold,
+    #[msg("Operation risk is above the allowed threshold")]
+    OperationRiskTooHigh,
+    
+    // Re-verification campaign errors
+    #[msg("Re-verification cut-off must be in the past and the campaign must not have expired")]
+    InvalidReverificationCampaign,
+    
+    // Self-service unlock errors
+    #[msg("Account is not locked")]
+    AccountNotLocked,
+    #[msg("This account must be unlocked by the authority")]
+    AdminUnlockRequired,
+    #[msg("Self-unlock limit reached for the last 30 days; contact the authority")]
+    SelfUnlockLimitReached,
+    
+    // Session eviction errors
+    #[msg("Concurrent session limit reached; end a session first")]
+    SessionLimitReached,
+    
+    // Hardware wallet co-signer errors
+    #[msg("Co-signer must be a separate wallet")]
+    InvalidCosigner,
+    #[msg("No co-signer change is pending")]
+    NoCosignerChangePending,
+    #[msg("Operation value is above the co-sign threshold; the co-signer must sign")]
+    CosignerSignatureRequired,
+    
+    // TOTP attestation errors
+    #[msg("No TOTP verifier is configured")]
+    TotpVerifierNotConfigured,
 }
@@ -1,6 +1,8 @@
 use anchor_lang::prelude::*;
+use solana_program::sysvar;
 use crate::state::*;
 use crate::errors::VaultError;
+use crate::crypto::{AuthenticatorData, BackupCodeHasher, Ed25519Verifier, WebAuthnVerifier};
 
 #[derive(Accounts)]
 pub struct InitializeAuth<'info> {
@@ -74,8 +76,68 @@ pub struct VerifyAuthFactor<'info> {
     )]
     pub auth_config: Account<'info, AuthConfig>,
     
-    /// CHECK: User account for verification
-    pub user: AccountInfo<'info>,
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct VerifyWebAuthnAssertion<'info> {
+    #[account(
+        mut,
+        seeds = [b"user_auth", user_auth.user.as_ref()],
+        bump = user_auth.bump
+    )]
+    pub user_auth: Account<'info, UserAuth>,
+    
+    #[account(
+        seeds = [b"auth_config"],
+        bump = auth_config.bump
+    )]
+    pub auth_config: Account<'info, AuthConfig>,
+    
+    pub user: Signer<'info>,
+    
+    /// CHECK: Address is constrained to the instructions sysvar
+    #[account(address = sysvar::instructions::ID)]
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct VerifyTotpAttestation<'info> {
+    #[account(
+        mut,
+        seeds = [b"user_auth", user_auth.user.as_ref()],
+        bump = user_auth.bump
+    )]
+    pub user_auth: Account<'info, UserAuth>,
+    
+    #[account(
+        seeds = [b"auth_config"],
+        bump = auth_config.bump
+    )]
+    pub auth_config: Account<'info, AuthConfig>,
+    
+    pub user: Signer<'info>,
+    
+    /// CHECK: Address is constrained to the instructions sysvar
+    #[account(address = sysvar::instructions::ID)]
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct GenerateBackupCodes<'info> {
+    #[account(
+        mut,
+        seeds = [b"user_auth", user_auth.user.as_ref()],
+        bump = user_auth.bump
+    )]
+    pub user_auth: Account<'info, UserAuth>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    /// CHECK: Address is constrained to the slot hashes sysvar
+    #[account(address = sysvar::slot_hashes::ID)]
+    pub slot_hashes: AccountInfo<'info>,
 }
 
 #[derive(Accounts)]
@@ -129,6 +191,101 @@ pub struct RevokeSession<'info> {
     pub user: Signer<'info>,
 }
 
+#[derive(Accounts)]
+pub struct GetEventArchive<'info> {
+    #[account(
+        seeds = [b"user_auth", user_auth.user.as_ref()],
+        bump = user_auth.bump
+    )]
+    pub user_auth: Account<'info, UserAuth>,
+}
+
+#[derive(Accounts)]
+pub struct RenewSession<'info> {
+    #[account(
+        mut,
+        seeds = [b"user_auth", user_auth.user.as_ref()],
+        bump = user_auth.bump
+    )]
+    pub user_auth: Account<'info, UserAuth>,
+    
+    pub user: Signer<'info>,
+}
+
+/// Self-service unlock of the signer's own account
+#[derive(Accounts)]
+pub struct SelfUnlock<'info> {
+    #[account(
+        mut,
+        seeds = [b"user_auth", user_auth.user.as_ref()],
+        bump = user_auth.bump
+    )]
+    pub user_auth: Account<'info, UserAuth>,
+    
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ManageCosigner<'info> {
+    #[account(
+        mut,
+        seeds = [b"user_auth", user_auth.user.as_ref()],
+        bump = user_auth.bump
+    )]
+    pub user_auth: Account<'info, UserAuth>,
+    
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ManageDevice<'info> {
+    #[account(
+        mut,
+        seeds = [b"user_auth", user_auth.user.as_ref()],
+        bump = user_auth.bump
+    )]
+    pub user_auth: Account<'info, UserAuth>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpgradeSessionPermissions<'info> {
+    #[account(
+        mut,
+        seeds = [b"user_auth", user_auth.user.as_ref()],
+        bump = user_auth.bump
+    )]
+    pub user_auth: Account<'info, UserAuth>,
+    
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ManageRecovery<'info> {
+    #[account(
+        mut,
+        seeds = [b"user_auth", user_auth.user.as_ref()],
+        bump = user_auth.bump
+    )]
+    pub user_auth: Account<'info, UserAuth>,
+    
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct GuardianRecovery<'info> {
+    #[account(
+        mut,
+        seeds = [b"user_auth", user_auth.user.as_ref()],
+        bump = user_auth.bump
+    )]
+    pub user_auth: Account<'info, UserAuth>,
+    
+    pub guardian: Signer<'info>,
+}
+
 #[derive(Accounts)]
 pub struct LockAccount<'info> {
     #[account(
@@ -195,7 +352,6 @@ pub fn add_auth_factor(
     method: AuthMethod,
     identifier: String,
     secret_hash: [u8; 32],
-    backup_codes: Vec<String>,
 ) -> Result<()> {
     let user_auth = &mut ctx.accounts.user_auth;
     let auth_config = &ctx.accounts.auth_config;
@@ -211,19 +367,75 @@ pub fn add_auth_factor(
         return Err(VaultError::AuthMethodNotAllowed.into());
     }
     
-    user_auth.add_auth_factor(method, identifier, secret_hash, backup_codes)?;
+    user_auth.add_auth_factor(method, identifier, secret_hash)?;
     
     msg!("Authentication factor added for user: {}", user);
     
     Ok(())
 }
 
-/// Verify an authentication factor
-pub fn verify_auth_factor(
-    ctx: Context<VerifyAuthFactor>,
+/// Add a WebAuthn factor for a registered credential. Only Ed25519
+/// credential keys are supported.
+pub fn add_webauthn_factor(
+    ctx: Context<AddAuthFactor>,
+    method: AuthMethod,
+    identifier: String,
+    credential_id: Vec<u8>,
+    cose_public_key: Vec<u8>,
+) -> Result<()> {
+    let user_auth = &mut ctx.accounts.user_auth;
+    let auth_config = &ctx.accounts.auth_config;
+    let user = ctx.accounts.user.key();
+    
+    if user != user_auth.user {
+        return Err(VaultError::UnauthorizedAccess.into());
+    }
+    
+    if !auth_config.allowed_auth_methods.contains(&method) {
+        return Err(VaultError::AuthMethodNotAllowed.into());
+    }
+    
+    user_auth.add_webauthn_factor(method, identifier, credential_id, &cose_public_key)?;
+    
+    msg!("WebAuthn factor added for user: {}", user);
+    
+    Ok(())
+}
+
+/// Add a passkey from its registration's authenticator data and the
+/// SHA-256 of the attestation object. Only Ed25519 credential keys are
+/// supported.
+pub fn add_passkey_factor(
+    ctx: Context<AddAuthFactor>,
+    identifier: String,
+    authenticator_data: Vec<u8>,
+    attestation_object_hash: [u8; 32],
+) -> Result<()> {
+    let user_auth = &mut ctx.accounts.user_auth;
+    let auth_config = &ctx.accounts.auth_config;
+    let user = ctx.accounts.user.key();
+    
+    if user != user_auth.user {
+        return Err(VaultError::UnauthorizedAccess.into());
+    }
+    
+    if !auth_config.allowed_auth_methods.contains(&AuthMethod::Passkey) {
+        return Err(VaultError::AuthMethodNotAllowed.into());
+    }
+    
+    user_auth.add_passkey_factor(identifier, &authenticator_data, attestation_object_hash, &auth_config.webauthn_rp_id_hash)?;
+    
+    msg!("Passkey factor added for user: {}", user);
+    
+    Ok(())
+}
+
+/// Remove an authentication factor. Needs a factor management step-up
+/// verified with a different factor.
+pub fn remove_auth_factor(
+    ctx: Context<AddAuthFactor>,
     method: AuthMethod,
     identifier: String,
-    provided_code: String,
 ) -> Result<()> {
     let user_auth = &mut ctx.accounts.user_auth;
     let user = ctx.accounts.user.key();
@@ -233,17 +445,219 @@ pub fn verify_auth_factor(
         return Err(VaultError::UnauthorizedAccess.into());
     }
     
-    let is_valid = user_auth.verify_auth_factor(method, identifier, provided_code)?;
+    let downgraded = user_auth.remove_auth_factor(
+        method.clone(),
+        &identifier,
+        ctx.accounts.auth_config.require_2fa_globally,
+        Clock::get()?.unix_timestamp,
+    )?;
+    user_auth.add_security_event(
+        SecurityEventType::TwoFactorDisabled,
+        None,
+        None,
+        format!("Authentication factor removed: {:?}, sessions downgraded: {}", method, downgraded),
+        40, // Medium risk
+    )?;
     
-    if !is_valid {
-        return Err(VaultError::InvalidAuthCode.into());
+    msg!("Authentication factor removed for user {}: {:?}", user, method);
+    
+    Ok(())
+}
+
+/// Enable or disable an authentication factor. Needs a factor management
+/// step-up verified with a different factor.
+pub fn set_auth_factor_enabled(
+    ctx: Context<AddAuthFactor>,
+    method: AuthMethod,
+    identifier: String,
+    enabled: bool,
+) -> Result<()> {
+    let user_auth = &mut ctx.accounts.user_auth;
+    let user = ctx.accounts.user.key();
+    
+    // Verify user owns the account
+    if user != user_auth.user {
+        return Err(VaultError::UnauthorizedAccess.into());
     }
     
-    msg!("Authentication factor verified for user: {}", user);
+    let downgraded = user_auth.set_auth_factor_enabled(
+        method.clone(),
+        &identifier,
+        enabled,
+        ctx.accounts.auth_config.require_2fa_globally,
+        Clock::get()?.unix_timestamp,
+    )?;
+    if enabled {
+        user_auth.add_security_event(
+            SecurityEventType::TwoFactorEnabled,
+            None,
+            None,
+            format!("Authentication factor re-enabled: {:?}", method),
+            20, // Medium risk
+        )?;
+    } else {
+        user_auth.add_security_event(
+            SecurityEventType::TwoFactorDisabled,
+            None,
+            None,
+            format!("Authentication factor disabled: {:?}, sessions downgraded: {}", method, downgraded),
+            40, // Medium risk
+        )?;
+    }
+    
+    msg!("Authentication factor {} for user {}: {:?}", if enabled { "enabled" } else { "disabled" }, user, method);
+    
+    Ok(())
+}
+
+/// Verify a WebAuthn assertion for a WebAuthn or Passkey factor. The
+/// Ed25519 program instruction before this one must verify the assertion
+/// signature. An assertion whose sign count didn't move forward disables
+/// the factor as cloned; that is recorded rather than failed, so the mark
+/// sticks.
+pub fn verify_webauthn_assertion(
+    ctx: Context<VerifyWebAuthnAssertion>,
+    method: AuthMethod,
+    identifier: String,
+    assertion: WebAuthnAssertion,
+    step_up_operation: Option<StepUpOperation>,
+    amount_cap: u64,
+) -> Result<()> {
+    verify_assertion(ctx, method, identifier, assertion, step_up_operation, amount_cap)
+}
+
+/// Verify a passkey assertion. Passkeys must assert with user verification;
+/// otherwise this is `verify_webauthn_assertion`, and it also records
+/// whether the passkey is currently backed up.
+pub fn verify_passkey_assertion(
+    ctx: Context<VerifyWebAuthnAssertion>,
+    identifier: String,
+    assertion: WebAuthnAssertion,
+    step_up_operation: Option<StepUpOperation>,
+    amount_cap: u64,
+) -> Result<()> {
+    verify_assertion(ctx, AuthMethod::Passkey, identifier, assertion, step_up_operation, amount_cap)
+}
+
+fn verify_assertion(
+    ctx: Context<VerifyWebAuthnAssertion>,
+    method: AuthMethod,
+    identifier: String,
+    assertion: WebAuthnAssertion,
+    step_up_operation: Option<StepUpOperation>,
+    amount_cap: u64,
+) -> Result<()> {
+    let user_auth = &mut ctx.accounts.user_auth;
+    let user = ctx.accounts.user.key();
+    
+    if user != user_auth.user {
+        return Err(VaultError::UnauthorizedAccess.into());
+    }
+    
+    sync_reverification(user_auth, &ctx.accounts.auth_config, Clock::get()?.unix_timestamp);
+    let public_key = user_auth.webauthn_public_key(&method, &identifier, &assertion.credential_id)?;
+    WebAuthnVerifier::verify_signature(
+        &ctx.accounts.instructions_sysvar,
+        &public_key,
+        &assertion.signature,
+        &assertion.authenticator_data,
+        &assertion.client_data_hash,
+    )?;
+    let authenticator_data = AuthenticatorData::parse(&assertion.authenticator_data)?;
+    
+    if user_auth.record_webauthn_assertion(method.clone(), identifier.clone(), &authenticator_data, &ctx.accounts.auth_config.webauthn_rp_id_hash)? {
+        if let Some(operation) = step_up_operation {
+            user_auth.record_step_up(operation, amount_cap, method, identifier, Clock::get()?.unix_timestamp);
+        }
+        msg!("WebAuthn assertion verified for user: {}", user);
+    }
+    
+    Ok(())
+}
+
+/// Verify a TOTP factor from the off-chain verifier's signed attestation
+/// that it accepted a code for time step `counter`. Naming a
+/// `step_up_operation` records a single-use proof as for other factors; the
+/// verifier's attestation must cover the operation and `amount_cap`.
+pub fn verify_totp_attestation(
+    ctx: Context<VerifyTotpAttestation>,
+    identifier: String,
+    counter: u64,
+    signature: Vec<u8>,
+    step_up_operation: Option<StepUpOperation>,
+    amount_cap: u64,
+) -> Result<()> {
+    let user_auth = &mut ctx.accounts.user_auth;
+    let user = ctx.accounts.user.key();
+    
+    if user != user_auth.user {
+        return Err(VaultError::UnauthorizedAccess.into());
+    }
+    
+    sync_reverification(user_auth, &ctx.accounts.auth_config, Clock::get()?.unix_timestamp);
+    let verifier = ctx.accounts.auth_config.require_totp_verifier()?;
+    let message = Ed25519Verifier::totp_attestation_message(
+        &user,
+        &user_auth.totp_commitment(&identifier)?,
+        counter,
+        step_up_operation,
+        amount_cap,
+    );
+    Ed25519Verifier::verify_participant_signatures(
+        &ctx.accounts.instructions_sysvar,
+        &[verifier],
+        &[signature],
+        &message,
+    )?;
+    
+    user_auth.record_totp_attestation(identifier.clone(), counter)?;
+    if let Some(operation) = step_up_operation {
+        user_auth.record_step_up(operation, amount_cap, AuthMethod::TOTP, identifier, Clock::get()?.unix_timestamp);
+    }
+    
+    msg!("TOTP attestation verified for user: {}", user);
     
     Ok(())
 }
 
+/// Verify an authentication factor, returning whether the code was accepted.
+/// Naming a `step_up_operation` records a single-use proof that operation
+/// can consume, for amounts up to `amount_cap`. The user signs, so only they
+/// choose what the proof covers.
+pub fn verify_auth_factor(
+    ctx: Context<VerifyAuthFactor>,
+    method: AuthMethod,
+    identifier: String,
+    provided_code: String,
+    step_up_operation: Option<StepUpOperation>,
+    amount_cap: u64,
+) -> Result<bool> {
+    let user_auth = &mut ctx.accounts.user_auth;
+    let user = ctx.accounts.user.key();
+    
+    // Verify user owns the account
+    if user != user_auth.user {
+        return Err(VaultError::UnauthorizedAccess.into());
+    }
+    
+    sync_reverification(user_auth, &ctx.accounts.auth_config, Clock::get()?.unix_timestamp);
+    
+    // A failed code returns false rather than an error so the failure
+    // counts toward the factor lock and the account throttle
+    let is_valid = user_auth.verify_auth_factor(method.clone(), identifier.clone(), provided_code)?;
+    
+    if is_valid {
+        if let Some(operation) = step_up_operation {
+            user_auth.record_step_up(operation, amount_cap, method, identifier, Clock::get()?.unix_timestamp);
+        }
+        msg!("Authentication factor verified for user: {}", user);
+    } else {
+        msg!("Authentication factor rejected for user: {}", user);
+    }
+    
+    Ok(is_valid)
+}
+
 /// Create a new user session
 pub fn create_session(
     ctx: Context<CreateSession>,
@@ -266,6 +680,8 @@ pub fn create_session(
         return Err(VaultError::AccountLocked.into());
     }
     
+    sync_reverification(user_auth, auth_config, Clock::get()?.unix_timestamp);
+    
     // Verify 2FA if required
     if auth_config.require_2fa_globally {
         let has_2fa = auth_methods.iter().any(|method| {
@@ -289,6 +705,12 @@ pub fn create_session(
     
     let session_id = user_auth.create_session(device_id, ip_address, user_agent, auth_methods)?;
     
+    match user_auth.session_created_event(&session_id, auth_config.suspicious_session_risk_threshold, Clock::get()?.unix_timestamp) {
+        Some(SessionNotification::Created(event)) => emit!(event),
+        Some(SessionNotification::Suspicious(event)) => emit!(event),
+        None => {},
+    }
+    
     msg!("Session created for user {}: {}", user, session_id);
     
     Ok(())
@@ -307,6 +729,7 @@ pub fn validate_session(
         return Err(VaultError::UnauthorizedAccess.into());
     }
     
+    sync_reverification(user_auth, &ctx.accounts.auth_config, Clock::get()?.unix_timestamp);
     let is_valid = user_auth.validate_session(&session_id)?;
     
     if !is_valid {
@@ -338,6 +761,90 @@ pub fn revoke_session(
     Ok(())
 }
 
+/// Add permissions to a session after re-verifying a strong factor
+pub fn upgrade_session_permissions(
+    ctx: Context<UpgradeSessionPermissions>,
+    session_id: String,
+    permissions: Vec<SessionPermission>,
+) -> Result<()> {
+    let user_auth = &mut ctx.accounts.user_auth;
+    let user = ctx.accounts.user.key();
+    
+    // Verify user owns the account
+    if user != user_auth.user {
+        return Err(VaultError::UnauthorizedAccess.into());
+    }
+    
+    user_auth.upgrade_session_permissions(&session_id, &permissions, Clock::get()?.unix_timestamp)?;
+    user_auth.add_security_event(
+        SecurityEventType::SessionCreated,
+        Some(session_id.clone()),
+        None,
+        format!("Session permissions upgraded: {:?}", permissions),
+        30, // Medium risk
+    )?;
+    
+    msg!("Session {} permissions upgraded for user {}", session_id, user);
+    
+    Ok(())
+}
+
+/// Renew a session nearing its absolute expiry. Consumes a session renewal
+/// step-up proof.
+pub fn renew_session(
+    ctx: Context<RenewSession>,
+    session_id: String,
+) -> Result<()> {
+    let user_auth = &mut ctx.accounts.user_auth;
+    let user = ctx.accounts.user.key();
+    
+    // Verify user owns the account
+    if user != user_auth.user {
+        return Err(VaultError::UnauthorizedAccess.into());
+    }
+    
+    let absolute_expiry = user_auth.renew_session(&session_id, Clock::get()?.unix_timestamp)?;
+    user_auth.add_security_event(
+        SecurityEventType::SessionCreated,
+        Some(session_id.clone()),
+        None,
+        format!("Session renewed until {}", absolute_expiry),
+        20, // Medium risk
+    )?;
+    
+    msg!("Session renewed for user {}: {}", user, session_id);
+    
+    Ok(())
+}
+
+/// Log out everywhere: revoke every session, optionally keeping the caller's
+/// own, and move the session epoch on
+pub fn revoke_all_sessions(
+    ctx: Context<RevokeSession>,
+    except_session_id: Option<String>,
+) -> Result<()> {
+    let user_auth = &mut ctx.accounts.user_auth;
+    let user = ctx.accounts.user.key();
+    
+    // Verify user owns the account
+    if user != user_auth.user {
+        return Err(VaultError::UnauthorizedAccess.into());
+    }
+    
+    let revoked = user_auth.revoke_all_sessions(except_session_id.as_deref(), Clock::get()?.unix_timestamp);
+    user_auth.add_security_event(
+        SecurityEventType::SessionRevoked,
+        except_session_id,
+        None,
+        format!("All sessions revoked: {}", revoked),
+        30, // Medium risk
+    )?;
+    
+    msg!("All sessions revoked for user {}: {}", user, revoked);
+    
+    Ok(())
+}
+
 /// Lock a user account (admin only)
 pub fn lock_account(
     ctx: Context<LockAccount>,
@@ -347,34 +854,145 @@ pub fn lock_account(
     let auth_config = &ctx.accounts.auth_config;
     let authority = ctx.accounts.authority.key();
     
-    // Verify authority
-    if authority != auth_config.authority {
+    // Verify authority
+    if authority != auth_config.authority {
+        return Err(VaultError::UnauthorizedAccess.into());
+    }
+    
+    user_auth.lock_account(reason)?;
+    
+    msg!("Account locked for user {} by authority {}", user_auth.user, authority);
+    
+    Ok(())
+}
+
+/// Unlock a user account (admin only)
+pub fn unlock_account(
+    ctx: Context<LockAccount>,
+) -> Result<()> {
+    let user_auth = &mut ctx.accounts.user_auth;
+    let auth_config = &ctx.accounts.auth_config;
+    let authority = ctx.accounts.authority.key();
+    
+    // Verify authority
+    if authority != auth_config.authority {
+        return Err(VaultError::UnauthorizedAccess.into());
+    }
+    
+    user_auth.unlock_account(authority)?;
+    
+    msg!("Account unlocked for user {} by authority {}", user_auth.user, authority);
+    
+    Ok(())
+}
+
+/// Unlock the caller's own account once its lockout has passed, using
+/// `AccountUnlock` step-up proofs from two distinct factors or one proof and
+/// a backup code. Returns false if the backup code was wrong.
+pub fn self_unlock(
+    ctx: Context<SelfUnlock>,
+    backup_code: Option<String>,
+) -> Result<bool> {
+    let user_auth = &mut ctx.accounts.user_auth;
+    let user = ctx.accounts.user.key();
+    
+    // Verify user owns the account
+    if user != user_auth.user {
+        return Err(VaultError::UnauthorizedAccess.into());
+    }
+    
+    let unlocked = user_auth.self_unlock(backup_code, Clock::get()?.unix_timestamp)?;
+    
+    if unlocked {
+        msg!("Account self-unlocked for user {} ({} self-unlocks in window)", user, user_auth.self_unlocks.len());
+    } else {
+        msg!("Self-unlock rejected for user {}: invalid backup code", user);
+    }
+    
+    Ok(unlocked)
+}
+
+/// Queue setting, replacing or removing (`None`) the hardware wallet that
+/// must co-sign operations worth more than `threshold_usd` (USD, 6
+/// decimals). Consumes a co-signer step-up proof; the change applies after
+/// 24 hours.
+pub fn request_cosigner_change(
+    ctx: Context<ManageCosigner>,
+    cosigner: Option<Pubkey>,
+    threshold_usd: u64,
+) -> Result<()> {
+    let user_auth = &mut ctx.accounts.user_auth;
+    let user = ctx.accounts.user.key();
+    
+    // Verify user owns the account
+    if user != user_auth.user {
+        return Err(VaultError::UnauthorizedAccess.into());
+    }
+    
+    let effective_at = user_auth.request_cosigner_change(cosigner, threshold_usd, Clock::get()?.unix_timestamp)?;
+    user_auth.add_security_event(
+        SecurityEventType::CosignerChanged,
+        None,
+        None,
+        format!("Co-signer change to {:?} above {} requested, effective at {}", cosigner, threshold_usd, effective_at),
+        50, // Medium-high risk
+    )?;
+    
+    msg!("Co-signer change requested for user {}, effective at {}", user, effective_at);
+    
+    Ok(())
+}
+
+/// Cancel a queued co-signer change
+pub fn cancel_cosigner_change(
+    ctx: Context<ManageCosigner>,
+) -> Result<()> {
+    let user_auth = &mut ctx.accounts.user_auth;
+    let user = ctx.accounts.user.key();
+    
+    // Verify user owns the account
+    if user != user_auth.user {
         return Err(VaultError::UnauthorizedAccess.into());
     }
     
-    user_auth.lock_account(reason)?;
+    user_auth.cancel_cosigner_change(Clock::get()?.unix_timestamp)?;
+    user_auth.add_security_event(
+        SecurityEventType::CosignerChanged,
+        None,
+        None,
+        "Co-signer change cancelled".to_string(),
+        20, // Medium risk
+    )?;
     
-    msg!("Account locked for user {} by authority {}", user_auth.user, authority);
+    msg!("Co-signer change cancelled for user {}", user);
     
     Ok(())
 }
 
-/// Unlock a user account (admin only)
-pub fn unlock_account(
-    ctx: Context<LockAccount>,
+/// Apply a queued co-signer change once its delay has passed
+pub fn apply_cosigner_change(
+    ctx: Context<ManageCosigner>,
 ) -> Result<()> {
     let user_auth = &mut ctx.accounts.user_auth;
-    let auth_config = &ctx.accounts.auth_config;
-    let authority = ctx.accounts.authority.key();
+    let user = ctx.accounts.user.key();
     
-    // Verify authority
-    if authority != auth_config.authority {
+    // Verify user owns the account
+    if user != user_auth.user {
         return Err(VaultError::UnauthorizedAccess.into());
     }
     
-    user_auth.unlock_account(authority)?;
+    user_auth.apply_cosigner_change(Clock::get()?.unix_timestamp)?;
+    let settings = &user_auth.security_settings;
+    let details = format!("Co-signer set to {:?} above {}", settings.cosigner, settings.cosign_threshold_usd);
+    user_auth.add_security_event(
+        SecurityEventType::CosignerChanged,
+        None,
+        None,
+        details,
+        30, // Medium risk
+    )?;
     
-    msg!("Account unlocked for user {} by authority {}", user_auth.user, authority);
+    msg!("Co-signer change applied for user {}", user);
     
     Ok(())
 }
@@ -405,6 +1023,36 @@ pub fn update_auth_config(
     Ok(())
 }
 
+/// Set the relying party ID WebAuthn assertions must be made for
+/// (authority only)
+pub fn set_webauthn_rp_id(
+    ctx: Context<UpdateAuthConfig>,
+    rp_id: String,
+) -> Result<()> {
+    let authority = ctx.accounts.authority.key();
+    
+    ctx.accounts.auth_config.set_webauthn_rp_id(authority, &rp_id)?;
+    
+    msg!("WebAuthn relying party set to {} by authority: {}", rp_id, authority);
+    
+    Ok(())
+}
+
+/// Set the off-chain TOTP verifier whose attestations are accepted
+/// (authority only)
+pub fn set_totp_verifier(
+    ctx: Context<UpdateAuthConfig>,
+    verifier: Pubkey,
+) -> Result<()> {
+    let authority = ctx.accounts.authority.key();
+    
+    ctx.accounts.auth_config.set_totp_verifier(authority, verifier)?;
+    
+    msg!("TOTP verifier set to {} by authority: {}", verifier, authority);
+    
+    Ok(())
+}
+
 /// Check if user has required 2FA for operation
 pub fn check_2fa_requirement(
     ctx: Context<ValidateSession>,
@@ -422,7 +1070,7 @@ pub fn check_2fa_requirement(
     let requires_2fa = user_auth.requires_2fa_for_operation(&operation_type, amount);
     
     if requires_2fa {
-        let active_methods = user_auth.get_active_2fa_methods();
+        let active_methods = user_auth.get_active_2fa_methods(Clock::get()?.unix_timestamp);
         if active_methods.is_empty() {
             return Err(VaultError::TwoFactorRequired.into());
         }
@@ -445,7 +1093,7 @@ pub fn get_security_status(
         return Err(VaultError::UnauthorizedAccess.into());
     }
     
-    let active_2fa_methods = user_auth.get_active_2fa_methods();
+    let active_2fa_methods = user_auth.get_active_2fa_methods(Clock::get()?.unix_timestamp);
     let active_sessions = user_auth.active_sessions.len();
     let recent_events = user_auth.security_events.iter()
         .filter(|e| e.timestamp > Clock::get().unwrap().unix_timestamp - 86400)
@@ -460,6 +1108,18 @@ pub fn get_security_status(
     Ok(())
 }
 
+/// Root and length of the chain pruned security events were folded into,
+/// for checking off-chain archives
+pub fn get_event_archive(
+    ctx: Context<GetEventArchive>,
+) -> Result<EventArchiveRoot> {
+    let user_auth = &ctx.accounts.user_auth;
+    Ok(EventArchiveRoot {
+        archived_events_root: user_auth.archived_events_root,
+        archived_count: user_auth.archived_count,
+    })
+}
+
 /// Middleware function to validate authentication for protected operations
 pub fn validate_authenticated_operation(
     user_auth: &mut UserAuth,
@@ -480,7 +1140,7 @@ pub fn validate_authenticated_operation(
     
     // Check 2FA requirement
     if user_auth.requires_2fa_for_operation(operation_type, amount) {
-        let active_methods = user_auth.get_active_2fa_methods();
+        let active_methods = user_auth.get_active_2fa_methods(Clock::get()?.unix_timestamp);
         if active_methods.is_empty() {
             return Err(VaultError::TwoFactorRequired.into());
         }
@@ -501,10 +1161,130 @@ pub fn validate_authenticated_operation(
     Ok(())
 }
 
-/// Generate backup codes for 2FA recovery
+/// Guard for protected instructions: `session_id` must be an active session
+/// on an unlocked account that holds `permission`
+pub fn require_session_permission(
+    user_auth: &UserAuth,
+    session_id: &str,
+    permission: SessionPermission,
+) -> Result<()> {
+    if user_auth.is_locked() {
+        return Err(VaultError::AccountLocked.into());
+    }
+    if !user_auth.session_has_permission(session_id, permission, Clock::get()?.unix_timestamp)? {
+        return Err(VaultError::MissingSessionPermission.into());
+    }
+    Ok(())
+}
+
+/// Apply the program's re-verification campaign, if any, to `user_auth`
+pub fn sync_reverification(user_auth: &mut UserAuth, auth_config: &AuthConfig, now: i64) {
+    let downgraded = user_auth.sync_reverification(auth_config.reverification.as_ref(), now);
+    if downgraded > 0 {
+        msg!("Re-verification required for user {}: {} sessions now read-only", user_auth.user, downgraded);
+    }
+}
+
+/// Guard for high-value operations: when the user has a co-signer and
+/// `usd_value` (USD, 6 decimals; None if it couldn't be priced) is above
+/// their threshold, the co-signer must have signed the transaction
+pub fn require_cosigner(user_auth: &UserAuth, cosigner: Option<&Signer>, usd_value: Option<u64>) -> Result<()> {
+    if let Some(required) = user_auth.required_cosigner(usd_value, Clock::get()?.unix_timestamp) {
+        if cosigner.map(|signer| signer.key()) != Some(required) {
+            return Err(VaultError::CosignerSignatureRequired.into());
+        }
+    }
+    Ok(())
+}
+
+/// Amounts above this (1 BTC) add to an operation's risk
+const HIGH_VALUE_RISK_AMOUNT: u64 = 100_000_000;
+
+/// 0-100 risk score for an operation of `operation_type` in `session_id`.
+/// It combines the session's own risk, unresolved compromise indicators
+/// from the last day, how many operations of the same type were scored in
+/// the last hour, an unfamiliar destination, and high-value amounts.
+pub fn compute_operation_risk(
+    user_auth: &UserAuth,
+    session_id: &str,
+    operation_type: &str,
+    amount: u64,
+    destination_known: bool,
+    now: i64,
+) -> Result<u8> {
+    let session = user_auth.active_sessions.iter()
+        .find(|s| s.session_id == session_id)
+        .ok_or(VaultError::SessionNotFound)?;
+    let mut risk = session.risk_score as u32 * 2 / 5;
+    
+    let recent_indicators = user_auth.compromise_indicators.iter()
+        .filter(|i| !i.resolved && i.detected_at > now - 86400)
+        .count() as u32;
+    risk += (recent_indicators * 15).min(30);
+    
+    // Velocity: a few operations an hour are normal
+    let recent_operations = user_auth.security_events.iter()
+        .filter(|e| {
+            e.event_type == SecurityEventType::OperationRiskScored
+                && e.timestamp > now - 3600
+                && e.details.starts_with(&format!("{}:", operation_type))
+        })
+        .count() as u32;
+    risk += (recent_operations.saturating_sub(3) * 5).min(15);
+    
+    if !destination_known {
+        risk += 15;
+    }
+    if amount > HIGH_VALUE_RISK_AMOUNT {
+        risk += 10;
+    }
+    
+    Ok(risk.min(100) as u8)
+}
+
+/// Log an operation's risk score; `compute_operation_risk` counts these
+/// for velocity
+pub fn record_operation_risk(
+    user_auth: &mut UserAuth,
+    session_id: &str,
+    operation_type: &str,
+    risk: u8,
+) -> Result<()> {
+    user_auth.add_security_event(
+        SecurityEventType::OperationRiskScored,
+        Some(session_id.to_string()),
+        None,
+        format!("{}: risk {}", operation_type, risk),
+        risk,
+    )
+}
+
+/// Set the operation risk thresholds (authority only)
+pub fn set_risk_thresholds(
+    ctx: Context<UpdateAuthConfig>,
+    payment_approval: u8,
+    session_key: u8,
+    suspicious_session: u8,
+) -> Result<()> {
+    let authority = ctx.accounts.authority.key();
+    
+    ctx.accounts.auth_config.set_risk_thresholds(authority, payment_approval, session_key, suspicious_session)?;
+    
+    msg!("Risk thresholds set to {} (payment approval), {} (session keys) and {} (suspicious sessions) by authority: {}",
+         payment_approval, session_key, suspicious_session, authority);
+    
+    Ok(())
+}
+
+/// Replace the user's backup codes. Codes are generated client-side and
+/// submitted as SHA-256 digests; they are stored salted with `client_entropy`
+/// and the latest slot hash. Every earlier code is invalidated, and a primary
+/// factor must have been verified in the last five minutes.
 pub fn generate_backup_codes(
-    ctx: Context<AddAuthFactor>,
-) -> Result<Vec<String>> {
+    ctx: Context<GenerateBackupCodes>,
+    code_digests: Vec<[u8; 32]>,
+    client_entropy: [u8; 32],
+) -> Result<()> {
     let user_auth = &mut ctx.accounts.user_auth;
     let user = ctx.accounts.user.key();
     
@@ -513,35 +1293,27 @@ pub fn generate_backup_codes(
         return Err(VaultError::UnauthorizedAccess.into());
     }
     
-    // Generate 10 backup codes (simplified - would use cryptographically secure random)
-    let mut backup_codes = Vec::new();
     let clock = Clock::get()?;
-    let base = clock.unix_timestamp as u64;
-    
-    for i in 0..10 {
-        let code = format!("{:08x}", base.wrapping_add(i * 12345));
-        backup_codes.push(code);
-    }
-    
-    // Update security settings
-    user_auth.security_settings.backup_codes_generated = true;
-    user_auth.updated_at = clock.unix_timestamp;
+    let slot_hash = BackupCodeHasher::latest_slot_hash(&ctx.accounts.slot_hashes.try_borrow_data()?)?;
+    let salt = BackupCodeHasher::derive_salt(&client_entropy, &slot_hash, &user, clock.unix_timestamp);
+    user_auth.replace_backup_codes(&code_digests, salt, clock.unix_timestamp)?;
     
     // Log backup code generation
     user_auth.add_security_event(
         SecurityEventType::TwoFactorEnabled,
         None,
         None,
-        "Backup codes generated".to_string(),
+        format!("{} backup codes generated; previous codes invalidated", code_digests.len()),
         30, // Medium risk
     )?;
     
     msg!("Backup codes generated for user: {}", user);
     
-    Ok(backup_codes)
+    Ok(())
 }
 
-/// Verify backup code for account recovery
+/// Verify a backup code. Each code works once. A locked account is not
+/// unlocked here; that goes through `self_unlock` and its limits.
 pub fn verify_backup_code(
     ctx: Context<VerifyAuthFactor>,
     backup_code: String,
@@ -554,18 +1326,7 @@ pub fn verify_backup_code(
         return Err(VaultError::UnauthorizedAccess.into());
     }
     
-    // Find backup code in any auth factor
-    let mut code_found = false;
-    for factor in &mut user_auth.auth_factors {
-        if let Some(pos) = factor.backup_codes.iter().position(|c| c == &backup_code) {
-            // Remove used backup code
-            factor.backup_codes.remove(pos);
-            code_found = true;
-            break;
-        }
-    }
-    
-    if !code_found {
+    if !user_auth.consume_backup_code(&backup_code) {
         user_auth.add_security_event(
             SecurityEventType::LoginFailure,
             None,
@@ -577,23 +1338,261 @@ pub fn verify_backup_code(
         return Err(VaultError::InvalidBackupCode.into());
     }
     
-    // Unlock account if it was locked
-    if user_auth.account_status == AccountStatus::Locked {
-        user_auth.account_status = AccountStatus::Active;
-        user_auth.locked_until = None;
-    }
-    
     user_auth.add_security_event(
         SecurityEventType::RecoveryInitiated,
         None,
         None,
-        "Account recovered using backup code".to_string(),
+        "Backup code used".to_string(),
         40, // Medium-high risk
     )?;
     
     user_auth.updated_at = Clock::get()?.unix_timestamp;
     
-    msg!("Account recovered using backup code for user: {}", user);
+    msg!("Backup code verified for user: {}", user);
+    
+    Ok(())
+}
+
+/// Register a device the user signs in from. Untrusted devices are evicted
+/// least recently seen first once 20 are registered.
+pub fn register_device(
+    ctx: Context<ManageDevice>,
+    device_id: String,
+    name: String,
+    attestation_hash: [u8; 32],
+) -> Result<()> {
+    let user_auth = &mut ctx.accounts.user_auth;
+    let user = ctx.accounts.user.key();
+    
+    // Verify user owns the account
+    if user != user_auth.user {
+        return Err(VaultError::UnauthorizedAccess.into());
+    }
+    
+    let now = Clock::get()?.unix_timestamp;
+    if let Some(evicted) = user_auth.register_device(device_id.clone(), name, attestation_hash, now)? {
+        emit_device_change(user, evicted, DeviceChange::Evicted, 0, now);
+    }
+    emit_device_change(user, device_id, DeviceChange::Registered, 0, now);
+    
+    Ok(())
+}
+
+/// Trust a registered device. Consumes a device trust step-up proof from a
+/// TOTP, WebAuthn or Passkey factor.
+pub fn trust_device(
+    ctx: Context<ManageDevice>,
+    device_id: String,
+) -> Result<()> {
+    let user_auth = &mut ctx.accounts.user_auth;
+    let user = ctx.accounts.user.key();
+    
+    // Verify user owns the account
+    if user != user_auth.user {
+        return Err(VaultError::UnauthorizedAccess.into());
+    }
+    
+    let now = Clock::get()?.unix_timestamp;
+    user_auth.trust_device(&device_id, now)?;
+    user_auth.add_security_event(
+        SecurityEventType::DeviceRegistered,
+        None,
+        Some(device_id.clone()),
+        "Device trusted".to_string(),
+        30, // Medium risk
+    )?;
+    emit_device_change(user, device_id, DeviceChange::Trusted, 0, now);
+    
+    Ok(())
+}
+
+pub fn rename_device(
+    ctx: Context<ManageDevice>,
+    device_id: String,
+    name: String,
+) -> Result<()> {
+    let user_auth = &mut ctx.accounts.user_auth;
+    let user = ctx.accounts.user.key();
+    
+    // Verify user owns the account
+    if user != user_auth.user {
+        return Err(VaultError::UnauthorizedAccess.into());
+    }
+    
+    let now = Clock::get()?.unix_timestamp;
+    user_auth.rename_device(&device_id, name, now)?;
+    emit_device_change(user, device_id, DeviceChange::Renamed, 0, now);
+    
+    Ok(())
+}
+
+/// Remove a device and revoke every session started from it
+pub fn revoke_device(
+    ctx: Context<ManageDevice>,
+    device_id: String,
+) -> Result<()> {
+    let user_auth = &mut ctx.accounts.user_auth;
+    let user = ctx.accounts.user.key();
+    
+    // Verify user owns the account
+    if user != user_auth.user {
+        return Err(VaultError::UnauthorizedAccess.into());
+    }
+    
+    let now = Clock::get()?.unix_timestamp;
+    let revoked_sessions = user_auth.revoke_device(&device_id, now)?;
+    user_auth.add_security_event(
+        SecurityEventType::DeviceRevoked,
+        None,
+        Some(device_id.clone()),
+        format!("Device revoked with {} active sessions", revoked_sessions),
+        20, // Medium risk
+    )?;
+    emit_device_change(user, device_id.clone(), DeviceChange::Revoked, revoked_sessions, now);
+    
+    msg!("Device {} revoked for user {}", device_id, user);
+    
+    Ok(())
+}
+
+fn emit_device_change(user: Pubkey, device_id: String, change: DeviceChange, revoked_sessions: u32, timestamp: i64) {
+    emit!(DeviceLifecycleEvent {
+        user,
+        device_id,
+        change,
+        revoked_sessions,
+        timestamp,
+    });
+}
+
+/// Whitelist an IP address. `ip_hash` is SHA-256 of the user's privacy
+/// salt followed by the address, computed client-side. Requires a primary
+/// factor verified in the last five minutes.
+pub fn add_ip_to_whitelist(
+    ctx: Context<AddAuthFactor>,
+    ip_hash: [u8; 32],
+) -> Result<()> {
+    let user_auth = &mut ctx.accounts.user_auth;
+    let user = ctx.accounts.user.key();
+    
+    // Verify user owns the account
+    if user != user_auth.user {
+        return Err(VaultError::UnauthorizedAccess.into());
+    }
+    
+    if user_auth.add_ip_to_whitelist(ip_hash, Clock::get()?.unix_timestamp)? {
+        user_auth.add_security_event(
+            SecurityEventType::LoginSuccess,
+            None,
+            None,
+            "IP address whitelisted".to_string(),
+            30, // Medium risk
+        )?;
+        msg!("IP address whitelisted for user: {}", user);
+    }
+    
+    Ok(())
+}
+
+/// Replace the user's recovery guardians (up to 3). Requires a primary
+/// factor verified in the last five minutes.
+pub fn set_recovery_guardians(
+    ctx: Context<ManageRecovery>,
+    guardians: Vec<Pubkey>,
+) -> Result<()> {
+    let user_auth = &mut ctx.accounts.user_auth;
+    let user = ctx.accounts.user.key();
+    
+    // Verify user owns the account
+    if user != user_auth.user {
+        return Err(VaultError::UnauthorizedAccess.into());
+    }
+    
+    let count = guardians.len();
+    user_auth.set_guardians(guardians, Clock::get()?.unix_timestamp)?;
+    user_auth.add_security_event(
+        SecurityEventType::GuardiansUpdated,
+        None,
+        None,
+        format!("Recovery guardians set: {}", count),
+        40, // Medium-high risk
+    )?;
+    
+    msg!("Recovery guardians updated for user: {}", user);
+    
+    Ok(())
+}
+
+/// Start account recovery as one of the user's guardians. The recovery
+/// can be completed after 72 hours unless the user cancels it.
+/// `new_primary_factor_hash` is the `TotpVerifier::commitment` of a key
+/// enrolled with the off-chain verifier, never the key itself.
+pub fn initiate_recovery(
+    ctx: Context<GuardianRecovery>,
+    new_primary_factor_hash: [u8; 32],
+) -> Result<()> {
+    let user_auth = &mut ctx.accounts.user_auth;
+    let guardian = ctx.accounts.guardian.key();
+    
+    let executable_at = user_auth.initiate_recovery(guardian, new_primary_factor_hash, Clock::get()?.unix_timestamp)?;
+    user_auth.add_security_event(
+        SecurityEventType::RecoveryInitiated,
+        None,
+        None,
+        format!("Recovery initiated by guardian {}; completes after {}", guardian, executable_at),
+        90, // Critical risk
+    )?;
+    
+    msg!("Recovery initiated for user {} by guardian {}", user_auth.user, guardian);
+    
+    Ok(())
+}
+
+/// Cancel a pending recovery. Requires any factor verified in the last
+/// five minutes.
+pub fn cancel_recovery(
+    ctx: Context<ManageRecovery>,
+) -> Result<()> {
+    let user_auth = &mut ctx.accounts.user_auth;
+    let user = ctx.accounts.user.key();
+    
+    // Verify user owns the account
+    if user != user_auth.user {
+        return Err(VaultError::UnauthorizedAccess.into());
+    }
+    
+    user_auth.cancel_recovery(Clock::get()?.unix_timestamp)?;
+    user_auth.add_security_event(
+        SecurityEventType::RecoveryCancelled,
+        None,
+        None,
+        "Recovery cancelled by user".to_string(),
+        60, // High risk
+    )?;
+    
+    msg!("Recovery cancelled for user: {}", user);
+    
+    Ok(())
+}
+
+/// Complete a recovery once its delay has passed, replacing every auth
+/// factor and session with the recovery TOTP factor
+pub fn complete_recovery(
+    ctx: Context<GuardianRecovery>,
+) -> Result<()> {
+    let user_auth = &mut ctx.accounts.user_auth;
+    let guardian = ctx.accounts.guardian.key();
+    
+    user_auth.complete_recovery(&guardian, Clock::get()?.unix_timestamp)?;
+    user_auth.add_security_event(
+        SecurityEventType::RecoveryCompleted,
+        None,
+        None,
+        format!("Recovery completed by guardian {}; auth factors and sessions replaced", guardian),
+        90, // Critical risk
+    )?;
+    
+    msg!("Recovery completed for user {} by guardian {}", user_auth.user, guardian);
     
     Ok(())
 }
@@ -607,6 +1606,8 @@ pub fn update_security_settings(
     session_timeout: Option<u32>,
     max_concurrent_sessions: Option<u8>,
     auto_lock_on_suspicious: Option<bool>,
+    session_eviction: Option<SessionEvictionPolicy>,
+    session_id: String,
 ) -> Result<()> {
     let user_auth = &mut ctx.accounts.user_auth;
     let user = ctx.accounts.user.key();
@@ -615,6 +1616,7 @@ pub fn update_security_settings(
     if user != user_auth.user {
         return Err(VaultError::UnauthorizedAccess.into());
     }
+    require_session_permission(user_auth, &session_id, SessionPermission::Write)?;
     
     let settings = &mut user_auth.security_settings;
     
@@ -652,6 +1654,10 @@ pub fn update_security_settings(
         settings.auto_lock_on_suspicious = auto_lock;
     }
     
+    if let Some(policy) = session_eviction {
+        settings.session_eviction = policy;
+    }
+    
     user_auth.updated_at = Clock::get()?.unix_timestamp;
     
     user_auth.add_security_event(
@@ -1,6 +1,8 @@
 use anchor_lang::prelude::*;
 use crate::state::*;
 use crate::errors::VaultError;
+use crate::instructions::authentication::require_cosigner;
+use crate::instructions::kyc::check_region_policy;
 use rand::RngCore;
 
 #[derive(Accounts)]
@@ -23,6 +25,25 @@ pub struct CommitBTC<'info> {
     )]
     pub user_account: Account<'info, UserAccount>,
     
+    #[account(
+        seeds = [b"oracle"],
+        bump
+    )]
+    pub oracle_data: Account<'info, OracleData>,
+    
+    #[account(
+        seeds = [b"compliance_config"],
+        bump = compliance_config.bump
+    )]
+    pub compliance_config: Account<'info, ComplianceConfig>,
+    
+    /// Its region caps the USD value a new commitment may lock
+    #[account(
+        seeds = [b"kyc_profile", user.key().as_ref()],
+        bump = kyc_profile.bump
+    )]
+    pub kyc_profile: Account<'info, KYCProfile>,
+    
     #[account(mut)]
     pub user: Signer<'info>,
     pub system_program: Program<'info, System>,
@@ -74,6 +95,44 @@ pub struct UpdateCommitment<'info> {
     )]
     pub user_account: Account<'info, UserAccount>,
     
+    #[account(
+        seeds = [b"oracle"],
+        bump
+    )]
+    pub oracle_data: Account<'info, OracleData>,
+    
+    #[account(
+        seeds = [b"compliance_config"],
+        bump = compliance_config.bump
+    )]
+    pub compliance_config: Account<'info, ComplianceConfig>,
+    
+    /// The resized commitment must still fit its region's cap
+    #[account(
+        seeds = [b"kyc_profile", user.key().as_ref()],
+        bump = kyc_profile.bump
+    )]
+    pub kyc_profile: Account<'info, KYCProfile>,
+    
+    /// Holds the co-signer decommits above the user's threshold need
+    #[account(
+        seeds = [b"user_auth", user.key().as_ref()],
+        bump = user_auth.bump
+    )]
+    pub user_auth: Account<'info, UserAuth>,
+    
+    /// The user's hardware wallet, for decommits above their co-sign threshold
+    pub cosigner: Option<Signer<'info>>,
+    
+    /// Credited with reinvested sats over the new amount's cap; required
+    /// only when a decommit cuts into them
+    #[account(
+        mut,
+        seeds = [b"rewards", user.key().as_ref()],
+        bump = user_rewards.bump
+    )]
+    pub user_rewards: Option<Account<'info, UserRewards>>,
+    
     pub user: Signer<'info>,
 }
 
@@ -110,6 +169,8 @@ pub fn commit_btc(
             return Err(VaultError::KYCRequired.into());
         }
     }
+    ctx.accounts.kyc_profile.require_not_frozen(FrozenAction::Commit)?;
+    check_region_commitment_cap(&ctx.accounts.compliance_config, Some(&ctx.accounts.kyc_profile), &ctx.accounts.oracle_data, amount)?;
 
     // Validate ECDSA proof length
     if ecdsa_proof.is_empty() || ecdsa_proof.len() > 256 {
@@ -379,6 +440,19 @@ pub fn update_commitment(
             return Err(VaultError::KYCRequired.into());
         }
     }
+    let action = if new_amount < btc_commitment.amount { FrozenAction::Decommit } else { FrozenAction::Commit };
+    ctx.accounts.kyc_profile.require_not_frozen(action)?;
+    if new_amount < btc_commitment.amount {
+        // Valued at the oracle price; a stale price always needs the co-signer
+        let oracle_data = &ctx.accounts.oracle_data;
+        let decommit_usd = if oracle_data.is_stale()? {
+            None
+        } else {
+            Some(BTCCommitment::sats_to_rewards(btc_commitment.amount - new_amount, oracle_data.btc_price_usd)?)
+        };
+        require_cosigner(&ctx.accounts.user_auth, ctx.accounts.cosigner.as_ref(), decommit_usd)?;
+    }
+    check_region_commitment_cap(&ctx.accounts.compliance_config, Some(&ctx.accounts.kyc_profile), &ctx.accounts.oracle_data, new_amount)?;
 
     // Validate new ECDSA proof
     if new_ecdsa_proof.is_empty() || new_ecdsa_proof.len() > 256 {
@@ -431,6 +505,19 @@ pub fn update_commitment(
     btc_commitment.verified = false; // Needs re-verification
     btc_commitment.last_verification = 0;
 
+    // Reinvested exposure over the new amount's cap goes back as rewards
+    let released_sats = btc_commitment.clamp_reinvested_sats();
+    if released_sats > 0 {
+        if ctx.accounts.oracle_data.is_stale()? {
+            return Err(VaultError::OraclePriceUnavailable.into());
+        }
+        let released = BTCCommitment::sats_to_rewards(released_sats, ctx.accounts.oracle_data.btc_price_usd)?;
+        ctx.accounts.user_rewards.as_mut()
+            .ok_or(VaultError::MissingRequiredAccount)?
+            .credit(released)?;
+        msg!("Released {} reinvested sats as {} rewards", released_sats, released);
+    }
+
     // Update user account
     user_account.btc_commitment_amount = new_amount;
     user_account.last_activity = clock.unix_timestamp;
@@ -440,3 +527,15 @@ pub fn update_commitment(
 
     Ok(())
 }
+
+/// Hold a commitment of `sats` to the cap for the user's region, valued at
+/// the oracle price. A stale price only fails where a cap applies.
+fn check_region_commitment_cap(
+    compliance_config: &ComplianceConfig,
+    kyc_profile: Option<&KYCProfile>,
+    oracle_data: &OracleData,
+    sats: u64,
+) -> Result<()> {
+    let btc_price_usd = if oracle_data.is_stale()? { None } else { Some(oracle_data.btc_price_usd) };
+    check_region_policy(compliance_config, kyc_profile, RegionFeature::Commitment { sats, btc_price_usd })
+}
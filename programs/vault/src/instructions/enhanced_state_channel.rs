@@ -4,13 +4,25 @@
 //! supporting high-frequency trading, micro-transactions, and advanced dispute resolution.
 
 use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use crate::state::authentication::{AuthConfig, AuthMethod, UserAuth};
+use crate::state::emergency_state::{EmergencyScope, EmergencyState};
 use crate::state::enhanced_state_channel::*;
+use crate::state::kyc_compliance::{ComplianceConfig, FrozenAction, KYCProfile, RegionFeature};
 use crate::state::multisig_wallet::MultisigWallet;
+use crate::state::oracle::OracleData;
+use crate::state::security_monitoring::SecurityLevel;
+use crate::state::state_channel::{ChannelCollateral, ChannelUndercollateralized, StateChannel};
+use crate::state::treasury::Treasury;
 use crate::errors::VaultError;
+use crate::crypto::Ed25519Verifier;
+use crate::instructions::authentication::{compute_operation_risk, record_operation_risk};
+use crate::instructions::kyc::{check_region_policy, load_participant_kyc_profiles};
+use solana_program::sysvar;
 
 /// Initialize enhanced state channel
 #[derive(Accounts)]
-#[instruction(channel_id: [u8; 32], bump: u8)]
+#[instruction(channel_id: [u8; 32])]
 pub struct InitializeEnhancedStateChannel<'info> {
     #[account(
         init,
@@ -21,6 +33,18 @@ pub struct InitializeEnhancedStateChannel<'info> {
     )]
     pub enhanced_channel: Account<'info, EnhancedStateChannel>,
     
+    #[account(
+        seeds = [b"emergency_state"],
+        bump = emergency_state.bump
+    )]
+    pub emergency_state: Account<'info, EmergencyState>,
+    
+    #[account(
+        seeds = [b"compliance_config"],
+        bump = compliance_config.bump
+    )]
+    pub compliance_config: Account<'info, ComplianceConfig>,
+    
     #[account(mut)]
     pub authority: Signer<'info>,
     
@@ -28,6 +52,7 @@ pub struct InitializeEnhancedStateChannel<'info> {
     pub multisig_wallet: Account<'info, MultisigWallet>,
     
     pub system_program: Program<'info, System>,
+    // Remaining accounts: every participant's KYC profile, in participant order
 }
 
 /// Activate enhanced state channel
@@ -36,10 +61,17 @@ pub struct ActivateEnhancedChannel<'info> {
     #[account(
         mut,
         seeds = [b"enhanced_channel", enhanced_channel.channel_id.as_ref()],
-        bump = enhanced_channel.bump
+        bump = enhanced_channel.bump,
+        constraint = enhanced_channel.version == EnhancedStateChannel::VERSION @ VaultError::UnsupportedChannelVersion
     )]
     pub enhanced_channel: Account<'info, EnhancedStateChannel>,
     
+    #[account(
+        seeds = [b"emergency_state"],
+        bump = emergency_state.bump
+    )]
+    pub emergency_state: Account<'info, EmergencyState>,
+    
     #[account(mut)]
     pub authority: Signer<'info>,
 }
@@ -50,10 +82,18 @@ pub struct ProcessHFTOperation<'info> {
     #[account(
         mut,
         seeds = [b"enhanced_channel", enhanced_channel.channel_id.as_ref()],
-        bump = enhanced_channel.bump
+        bump = enhanced_channel.bump,
+        constraint = enhanced_channel.version == EnhancedStateChannel::VERSION @ VaultError::UnsupportedChannelVersion
     )]
     pub enhanced_channel: Account<'info, EnhancedStateChannel>,
     
+    #[account(
+        seeds = [b"emergency_state"],
+        bump = emergency_state.bump
+    )]
+    pub emergency_state: Account<'info, EmergencyState>,
+    
+    /// The operation's participant or their session key
     #[account(mut)]
     pub participant: Signer<'info>,
 }
@@ -64,38 +104,162 @@ pub struct ProcessMicroTransaction<'info> {
     #[account(
         mut,
         seeds = [b"enhanced_channel", enhanced_channel.channel_id.as_ref()],
-        bump = enhanced_channel.bump
+        bump = enhanced_channel.bump,
+        constraint = enhanced_channel.version == EnhancedStateChannel::VERSION @ VaultError::UnsupportedChannelVersion
     )]
     pub enhanced_channel: Account<'info, EnhancedStateChannel>,
     
+    #[account(
+        seeds = [b"emergency_state"],
+        bump = emergency_state.bump
+    )]
+    pub emergency_state: Account<'info, EmergencyState>,
+    
+    /// The sender or their session key
     #[account(mut)]
     pub from_participant: Signer<'info>,
 }
 
+/// Delegate a participant's channel signing to a session key, gated on
+/// their second authentication factor
+#[derive(Accounts)]
+pub struct RegisterSessionKey<'info> {
+    #[account(
+        mut,
+        seeds = [b"enhanced_channel", enhanced_channel.channel_id.as_ref()],
+        bump = enhanced_channel.bump,
+        constraint = enhanced_channel.version == EnhancedStateChannel::VERSION @ VaultError::UnsupportedChannelVersion
+    )]
+    pub enhanced_channel: Account<'info, EnhancedStateChannel>,
+    
+    #[account(
+        seeds = [b"emergency_state"],
+        bump = emergency_state.bump
+    )]
+    pub emergency_state: Account<'info, EmergencyState>,
+    
+    #[account(
+        mut,
+        seeds = [b"user_auth", participant.key().as_ref()],
+        bump = user_auth.bump
+    )]
+    pub user_auth: Account<'info, UserAuth>,
+    
+    /// Holds the risk score above which session keys are refused
+    #[account(
+        seeds = [b"auth_config"],
+        bump = auth_config.bump
+    )]
+    pub auth_config: Account<'info, AuthConfig>,
+    
+    pub participant: Signer<'info>,
+}
+
+/// Revoke a participant's session key, signed by either key
+#[derive(Accounts)]
+pub struct RevokeSessionKey<'info> {
+    #[account(
+        mut,
+        seeds = [b"enhanced_channel", enhanced_channel.channel_id.as_ref()],
+        bump = enhanced_channel.bump,
+        constraint = enhanced_channel.version == EnhancedStateChannel::VERSION @ VaultError::UnsupportedChannelVersion
+    )]
+    pub enhanced_channel: Account<'info, EnhancedStateChannel>,
+    
+    pub authority: Signer<'info>,
+}
+
+/// Realize PnL at the mark price and fold netted transfers into
+/// participant balances
+#[derive(Accounts)]
+pub struct CheckpointMicroTransactions<'info> {
+    #[account(
+        mut,
+        seeds = [b"enhanced_channel", enhanced_channel.channel_id.as_ref()],
+        bump = enhanced_channel.bump,
+        constraint = enhanced_channel.version == EnhancedStateChannel::VERSION @ VaultError::UnsupportedChannelVersion
+    )]
+    pub enhanced_channel: Account<'info, EnhancedStateChannel>,
+    
+    pub participant: Signer<'info>,
+}
+
+/// Raise a participant's exposure limit with every participant's signature
+#[derive(Accounts)]
+pub struct RaiseExposureLimit<'info> {
+    #[account(
+        mut,
+        seeds = [b"enhanced_channel", enhanced_channel.channel_id.as_ref()],
+        bump = enhanced_channel.bump,
+        constraint = enhanced_channel.version == EnhancedStateChannel::VERSION @ VaultError::UnsupportedChannelVersion
+    )]
+    pub enhanced_channel: Account<'info, EnhancedStateChannel>,
+    
+    #[account(
+        seeds = [b"emergency_state"],
+        bump = emergency_state.bump
+    )]
+    pub emergency_state: Account<'info, EmergencyState>,
+    
+    pub authority: Signer<'info>,
+    
+    /// CHECK: Address is constrained to the instructions sysvar
+    #[account(address = sysvar::instructions::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
+}
+
 /// Add pending operation
 #[derive(Accounts)]
 pub struct AddPendingOperation<'info> {
     #[account(
         mut,
         seeds = [b"enhanced_channel", enhanced_channel.channel_id.as_ref()],
-        bump = enhanced_channel.bump
+        bump = enhanced_channel.bump,
+        constraint = enhanced_channel.version == EnhancedStateChannel::VERSION @ VaultError::UnsupportedChannelVersion
     )]
     pub enhanced_channel: Account<'info, EnhancedStateChannel>,
     
+    #[account(
+        seeds = [b"emergency_state"],
+        bump = emergency_state.bump
+    )]
+    pub emergency_state: Account<'info, EmergencyState>,
+    
     #[account(mut)]
     pub participant: Signer<'info>,
 }
 
+/// Permissionless sweep of expired pending operations
+#[derive(Accounts)]
+pub struct EvictExpiredOperations<'info> {
+    #[account(
+        mut,
+        seeds = [b"enhanced_channel", enhanced_channel.channel_id.as_ref()],
+        bump = enhanced_channel.bump,
+        constraint = enhanced_channel.version == EnhancedStateChannel::VERSION @ VaultError::UnsupportedChannelVersion
+    )]
+    pub enhanced_channel: Account<'info, EnhancedStateChannel>,
+    
+    pub cranker: Signer<'info>,
+}
+
 /// Confirm pending operation
 #[derive(Accounts)]
 pub struct ConfirmOperation<'info> {
     #[account(
         mut,
         seeds = [b"enhanced_channel", enhanced_channel.channel_id.as_ref()],
-        bump = enhanced_channel.bump
+        bump = enhanced_channel.bump,
+        constraint = enhanced_channel.version == EnhancedStateChannel::VERSION @ VaultError::UnsupportedChannelVersion
     )]
     pub enhanced_channel: Account<'info, EnhancedStateChannel>,
     
+    #[account(
+        seeds = [b"emergency_state"],
+        bump = emergency_state.bump
+    )]
+    pub emergency_state: Account<'info, EmergencyState>,
+    
     #[account(mut)]
     pub participant: Signer<'info>,
 }
@@ -106,7 +270,8 @@ pub struct InitiateDispute<'info> {
     #[account(
         mut,
         seeds = [b"enhanced_channel", enhanced_channel.channel_id.as_ref()],
-        bump = enhanced_channel.bump
+        bump = enhanced_channel.bump,
+        constraint = enhanced_channel.version == EnhancedStateChannel::VERSION @ VaultError::UnsupportedChannelVersion
     )]
     pub enhanced_channel: Account<'info, EnhancedStateChannel>,
     
@@ -120,7 +285,8 @@ pub struct ResolveDispute<'info> {
     #[account(
         mut,
         seeds = [b"enhanced_channel", enhanced_channel.channel_id.as_ref()],
-        bump = enhanced_channel.bump
+        bump = enhanced_channel.bump,
+        constraint = enhanced_channel.version == EnhancedStateChannel::VERSION @ VaultError::UnsupportedChannelVersion
     )]
     pub enhanced_channel: Account<'info, EnhancedStateChannel>,
     
@@ -129,6 +295,330 @@ pub struct ResolveDispute<'info> {
     
     /// Multi-signature wallet for authorization
     pub multisig_wallet: Account<'info, MultisigWallet>,
+    
+    #[account(
+        mut,
+        seeds = [b"treasury"],
+        bump = treasury.bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+    
+    /// CHECK: Loser's collateral PDA, checked against the resolution in the handler
+    #[account(mut)]
+    pub loser_collateral: UncheckedAccount<'info>,
+    
+    /// CHECK: Winner's collateral PDA, or the winner's wallet if they never
+    /// deposited; checked against the resolution in the handler
+    #[account(mut)]
+    pub winner_collateral: UncheckedAccount<'info>,
+    
+    /// CHECK: Address is constrained to the instructions sysvar
+    #[account(address = sysvar::instructions::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
+}
+
+/// Lock collateral into an enhanced state channel
+#[derive(Accounts)]
+pub struct DepositEnhancedCollateral<'info> {
+    #[account(
+        seeds = [b"enhanced_channel", enhanced_channel.channel_id.as_ref()],
+        bump = enhanced_channel.bump,
+        constraint = enhanced_channel.version == EnhancedStateChannel::VERSION @ VaultError::UnsupportedChannelVersion
+    )]
+    pub enhanced_channel: Account<'info, EnhancedStateChannel>,
+    
+    #[account(
+        seeds = [b"emergency_state"],
+        bump = emergency_state.bump
+    )]
+    pub emergency_state: Account<'info, EmergencyState>,
+    
+    #[account(
+        seeds = [b"kyc_profile", participant.key().as_ref()],
+        bump = kyc_profile.bump
+    )]
+    pub kyc_profile: Account<'info, KYCProfile>,
+    
+    #[account(
+        init_if_needed,
+        payer = participant,
+        space = ChannelCollateral::LEN,
+        seeds = [b"enhanced_collateral", enhanced_channel.channel_id.as_ref(), participant.key().as_ref()],
+        bump
+    )]
+    pub collateral: Account<'info, ChannelCollateral>,
+    
+    #[account(mut)]
+    pub participant: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Lock USDC collateral into an enhanced state channel. The vault is a
+/// token account owned by the channel PDA.
+#[derive(Accounts)]
+pub struct DepositEnhancedTokenCollateral<'info> {
+    #[account(
+        seeds = [b"enhanced_channel", enhanced_channel.channel_id.as_ref()],
+        bump = enhanced_channel.bump,
+        constraint = enhanced_channel.version == EnhancedStateChannel::VERSION @ VaultError::UnsupportedChannelVersion
+    )]
+    pub enhanced_channel: Account<'info, EnhancedStateChannel>,
+    
+    #[account(
+        seeds = [b"emergency_state"],
+        bump = emergency_state.bump
+    )]
+    pub emergency_state: Account<'info, EmergencyState>,
+    
+    #[account(
+        seeds = [b"kyc_profile", participant.key().as_ref()],
+        bump = kyc_profile.bump
+    )]
+    pub kyc_profile: Account<'info, KYCProfile>,
+    
+    #[account(address = enhanced_channel.config.usdc_mint)]
+    pub usdc_mint: Account<'info, Mint>,
+    
+    #[account(
+        init_if_needed,
+        payer = participant,
+        seeds = [b"enhanced_usdc_vault", enhanced_channel.channel_id.as_ref(), participant.key().as_ref()],
+        bump,
+        token::mint = usdc_mint,
+        token::authority = enhanced_channel
+    )]
+    pub usdc_vault: Account<'info, TokenAccount>,
+    
+    #[account(mut, token::mint = usdc_mint, token::authority = participant)]
+    pub participant_usdc: Account<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub participant: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Reallocate a closed channel's SOL and USDC collateral to final balances.
+/// Remaining accounts are every participant's SOL collateral vault followed
+/// by every participant's USDC vault, in participant order.
+#[derive(Accounts)]
+pub struct SettleEnhancedChannel<'info> {
+    #[account(
+        mut,
+        seeds = [b"enhanced_channel", enhanced_channel.channel_id.as_ref()],
+        bump = enhanced_channel.bump,
+        constraint = enhanced_channel.version == EnhancedStateChannel::VERSION @ VaultError::UnsupportedChannelVersion
+    )]
+    pub enhanced_channel: Account<'info, EnhancedStateChannel>,
+    
+    #[account(
+        seeds = [b"emergency_state"],
+        bump = emergency_state.bump
+    )]
+    pub emergency_state: Account<'info, EmergencyState>,
+    
+    /// Receives the channel's collected fees
+    #[account(mut, seeds = [b"treasury"], bump)]
+    pub treasury: Account<'info, Treasury>,
+    
+    #[account(
+        mut,
+        token::mint = enhanced_channel.config.usdc_mint,
+        token::authority = treasury
+    )]
+    pub treasury_usdc: Account<'info, TokenAccount>,
+    
+    pub settler: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+/// Withdraw a participant's settled collateral once the channel is closed
+#[derive(Accounts)]
+pub struct ClaimEnhancedSettlement<'info> {
+    #[account(
+        seeds = [b"enhanced_channel", enhanced_channel.channel_id.as_ref()],
+        bump = enhanced_channel.bump,
+        constraint = enhanced_channel.version == EnhancedStateChannel::VERSION @ VaultError::UnsupportedChannelVersion,
+        constraint = enhanced_channel.status == EnhancedChannelStatus::Closed @ VaultError::SecurityViolation
+    )]
+    pub enhanced_channel: Account<'info, EnhancedStateChannel>,
+    
+    #[account(
+        seeds = [b"emergency_state"],
+        bump = emergency_state.bump
+    )]
+    pub emergency_state: Account<'info, EmergencyState>,
+    
+    #[account(
+        seeds = [b"kyc_profile", participant.key().as_ref()],
+        bump = kyc_profile.bump
+    )]
+    pub kyc_profile: Account<'info, KYCProfile>,
+    
+    #[account(
+        mut,
+        seeds = [b"enhanced_collateral", enhanced_channel.channel_id.as_ref(), participant.key().as_ref()],
+        bump = collateral.bump
+    )]
+    pub collateral: Account<'info, ChannelCollateral>,
+    
+    #[account(
+        mut,
+        seeds = [b"enhanced_usdc_vault", enhanced_channel.channel_id.as_ref(), participant.key().as_ref()],
+        bump
+    )]
+    pub usdc_vault: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        token::mint = enhanced_channel.config.usdc_mint,
+        token::authority = participant
+    )]
+    pub participant_usdc: Option<Account<'info, TokenAccount>>,
+    
+    #[account(mut)]
+    pub participant: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+/// Withdraw excess collateral from an active channel, immediately with
+/// every counterparty's co-signature or after the objection window
+#[derive(Accounts)]
+pub struct RequestChannelWithdrawal<'info> {
+    #[account(
+        seeds = [b"enhanced_channel", enhanced_channel.channel_id.as_ref()],
+        bump = enhanced_channel.bump,
+        constraint = enhanced_channel.version == EnhancedStateChannel::VERSION @ VaultError::UnsupportedChannelVersion
+    )]
+    pub enhanced_channel: Account<'info, EnhancedStateChannel>,
+    
+    #[account(
+        seeds = [b"emergency_state"],
+        bump = emergency_state.bump
+    )]
+    pub emergency_state: Account<'info, EmergencyState>,
+    
+    #[account(
+        seeds = [b"kyc_profile", participant.key().as_ref()],
+        bump = kyc_profile.bump
+    )]
+    pub kyc_profile: Account<'info, KYCProfile>,
+    
+    #[account(
+        init_if_needed,
+        payer = participant,
+        space = ChannelWithdrawal::LEN,
+        seeds = [b"enhanced_withdrawal", enhanced_channel.channel_id.as_ref(), participant.key().as_ref()],
+        bump
+    )]
+    pub withdrawal: Account<'info, ChannelWithdrawal>,
+    
+    #[account(
+        mut,
+        seeds = [b"enhanced_collateral", enhanced_channel.channel_id.as_ref(), participant.key().as_ref()],
+        bump = collateral.bump
+    )]
+    pub collateral: Option<Account<'info, ChannelCollateral>>,
+    
+    #[account(
+        mut,
+        seeds = [b"enhanced_usdc_vault", enhanced_channel.channel_id.as_ref(), participant.key().as_ref()],
+        bump
+    )]
+    pub usdc_vault: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        token::mint = enhanced_channel.config.usdc_mint,
+        token::authority = participant
+    )]
+    pub participant_usdc: Option<Account<'info, TokenAccount>>,
+    
+    #[account(mut)]
+    pub participant: Signer<'info>,
+    
+    /// CHECK: Address is constrained to the instructions sysvar
+    #[account(address = sysvar::instructions::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Pay out a withdrawal whose objection window has passed
+#[derive(Accounts)]
+pub struct ExecuteChannelWithdrawal<'info> {
+    #[account(
+        seeds = [b"enhanced_channel", enhanced_channel.channel_id.as_ref()],
+        bump = enhanced_channel.bump,
+        constraint = enhanced_channel.version == EnhancedStateChannel::VERSION @ VaultError::UnsupportedChannelVersion
+    )]
+    pub enhanced_channel: Account<'info, EnhancedStateChannel>,
+    
+    #[account(
+        seeds = [b"emergency_state"],
+        bump = emergency_state.bump
+    )]
+    pub emergency_state: Account<'info, EmergencyState>,
+    
+    #[account(
+        seeds = [b"kyc_profile", participant.key().as_ref()],
+        bump = kyc_profile.bump
+    )]
+    pub kyc_profile: Account<'info, KYCProfile>,
+    
+    #[account(
+        mut,
+        seeds = [b"enhanced_withdrawal", enhanced_channel.channel_id.as_ref(), participant.key().as_ref()],
+        bump = withdrawal.bump
+    )]
+    pub withdrawal: Account<'info, ChannelWithdrawal>,
+    
+    #[account(
+        mut,
+        seeds = [b"enhanced_collateral", enhanced_channel.channel_id.as_ref(), participant.key().as_ref()],
+        bump = collateral.bump
+    )]
+    pub collateral: Option<Account<'info, ChannelCollateral>>,
+    
+    #[account(
+        mut,
+        seeds = [b"enhanced_usdc_vault", enhanced_channel.channel_id.as_ref(), participant.key().as_ref()],
+        bump
+    )]
+    pub usdc_vault: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        token::mint = enhanced_channel.config.usdc_mint,
+        token::authority = participant
+    )]
+    pub participant_usdc: Option<Account<'info, TokenAccount>>,
+    
+    #[account(mut)]
+    pub participant: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+/// Counterparty objection cancelling a pending withdrawal
+#[derive(Accounts)]
+#[instruction(participant: Pubkey)]
+pub struct ObjectChannelWithdrawal<'info> {
+    #[account(
+        seeds = [b"enhanced_channel", enhanced_channel.channel_id.as_ref()],
+        bump = enhanced_channel.bump,
+        constraint = enhanced_channel.version == EnhancedStateChannel::VERSION @ VaultError::UnsupportedChannelVersion
+    )]
+    pub enhanced_channel: Account<'info, EnhancedStateChannel>,
+    
+    #[account(
+        mut,
+        seeds = [b"enhanced_withdrawal", enhanced_channel.channel_id.as_ref(), participant.as_ref()],
+        bump = withdrawal.bump
+    )]
+    pub withdrawal: Account<'info, ChannelWithdrawal>,
+    
+    pub objector: Signer<'info>,
 }
 
 /// Close enhanced state channel
@@ -137,7 +627,8 @@ pub struct CloseEnhancedChannel<'info> {
     #[account(
         mut,
         seeds = [b"enhanced_channel", enhanced_channel.channel_id.as_ref()],
-        bump = enhanced_channel.bump
+        bump = enhanced_channel.bump,
+        constraint = enhanced_channel.version == EnhancedStateChannel::VERSION @ VaultError::UnsupportedChannelVersion
     )]
     pub enhanced_channel: Account<'info, EnhancedStateChannel>,
     
@@ -154,23 +645,92 @@ pub struct BatchProcessOperations<'info> {
     #[account(
         mut,
         seeds = [b"enhanced_channel", enhanced_channel.channel_id.as_ref()],
-        bump = enhanced_channel.bump
+        bump = enhanced_channel.bump,
+        constraint = enhanced_channel.version == EnhancedStateChannel::VERSION @ VaultError::UnsupportedChannelVersion
+    )]
+    pub enhanced_channel: Account<'info, EnhancedStateChannel>,
+    
+    #[account(
+        seeds = [b"emergency_state"],
+        bump = emergency_state.bump
+    )]
+    pub emergency_state: Account<'info, EmergencyState>,
+    
+    /// The operations' participant or their session key
+    #[account(mut)]
+    pub participant: Signer<'info>,
+}
+
+/// Read-only view of a channel's counters
+#[derive(Accounts)]
+pub struct GetChannelStats<'info> {
+    #[account(
+        seeds = [b"enhanced_channel", enhanced_channel.channel_id.as_ref()],
+        bump = enhanced_channel.bump,
+        constraint = enhanced_channel.version == EnhancedStateChannel::VERSION @ VaultError::UnsupportedChannelVersion
+    )]
+    pub enhanced_channel: Account<'info, EnhancedStateChannel>,
+}
+
+/// Keeper crank firing stop-loss and take-profit orders
+#[derive(Accounts)]
+pub struct CrankChannelTriggers<'info> {
+    #[account(
+        mut,
+        seeds = [b"enhanced_channel", enhanced_channel.channel_id.as_ref()],
+        bump = enhanced_channel.bump,
+        constraint = enhanced_channel.version == EnhancedStateChannel::VERSION @ VaultError::UnsupportedChannelVersion
+    )]
+    pub enhanced_channel: Account<'info, EnhancedStateChannel>,
+    
+    #[account(
+        seeds = [b"emergency_state"],
+        bump = emergency_state.bump
+    )]
+    pub emergency_state: Account<'info, EmergencyState>,
+    
+    /// Fallback reference price when the channel hasn't traded yet
+    #[account(seeds = [b"oracle"], bump)]
+    pub oracle_data: Account<'info, OracleData>,
+    
+    pub keeper: Signer<'info>,
+}
+
+/// Rewrite a pre-versioning channel account in the current layout
+#[derive(Accounts)]
+#[instruction(channel_id: [u8; 32])]
+pub struct MigrateEnhancedChannel<'info> {
+    /// CHECK: Legacy layout can't be decoded as `EnhancedStateChannel`; the
+    /// handler checks its size and discriminator before reading it
+    #[account(
+        mut,
+        seeds = [b"enhanced_channel", channel_id.as_ref()],
+        bump,
+        owner = crate::ID
     )]
-    pub enhanced_channel: Account<'info, EnhancedStateChannel>,
+    pub enhanced_channel: UncheckedAccount<'info>,
     
+    /// Funds the rent for the larger account
     #[account(mut)]
-    pub participant: Signer<'info>,
+    pub payer: Signer<'info>,
+    
+    /// CHECK: Address is constrained to the instructions sysvar
+    #[account(address = sysvar::instructions::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
+    
+    pub system_program: Program<'info, System>,
 }
 
 /// Enhanced state channel instruction implementations
 impl<'info> InitializeEnhancedStateChannel<'info> {
     pub fn process(
-        ctx: Context<InitializeEnhancedStateChannel>,
+        ctx: Context<'_, '_, 'info, 'info, InitializeEnhancedStateChannel<'info>>,
         channel_id: [u8; 32],
         participants: Vec<ChannelParticipant>,
         config: ChannelConfig,
-        bump: u8,
     ) -> Result<()> {
+        ctx.accounts.emergency_state.require_not_paused(EmergencyScope::Channels)?;
+
         let enhanced_channel = &mut ctx.accounts.enhanced_channel;
         
         // Verify authority is a multisig signer
@@ -191,7 +751,22 @@ impl<'info> InitializeEnhancedStateChannel<'info> {
             VaultError::UnauthorizedAccess
         );
         
-        enhanced_channel.initialize(channel_id, participants, config, bump)?;
+        // Every participant's region must allow high-frequency trading channels,
+        // and none may have channels frozen
+        let participant_keys: Vec<Pubkey> = participants.iter().map(|p| p.pubkey).collect();
+        let kyc_profiles = load_participant_kyc_profiles(ctx.remaining_accounts, &participant_keys, ctx.program_id)?;
+        for kyc_profile in &kyc_profiles {
+            kyc_profile.require_not_frozen(FrozenAction::Channels)?;
+            check_region_policy(&ctx.accounts.compliance_config, Some(kyc_profile), RegionFeature::HftChannel)?;
+        }
+        
+        enhanced_channel.initialize(
+            channel_id,
+            participants,
+            config,
+            ctx.bumps.enhanced_channel,
+            Clock::get()?.unix_timestamp,
+        )?;
         
         msg!(
             "Enhanced state channel {} initialized with {} participants",
@@ -205,6 +780,8 @@ impl<'info> InitializeEnhancedStateChannel<'info> {
 
 impl<'info> ActivateEnhancedChannel<'info> {
     pub fn process(ctx: Context<ActivateEnhancedChannel>) -> Result<()> {
+        ctx.accounts.emergency_state.require_not_paused(EmergencyScope::Channels)?;
+
         let enhanced_channel = &mut ctx.accounts.enhanced_channel;
         
         // Verify authority is a participant
@@ -213,7 +790,7 @@ impl<'info> ActivateEnhancedChannel<'info> {
             VaultError::UnauthorizedAccess
         );
         
-        enhanced_channel.activate()?;
+        enhanced_channel.activate(Clock::get()?.unix_timestamp)?;
         
         msg!(
             "Enhanced state channel {} activated",
@@ -229,8 +806,11 @@ impl<'info> ProcessHFTOperation<'info> {
         ctx: Context<ProcessHFTOperation>,
         operation: HFTOperation,
     ) -> Result<()> {
+        ctx.accounts.emergency_state.require_not_paused(EmergencyScope::Channels)?;
+
         let enhanced_channel = &mut ctx.accounts.enhanced_channel;
-        let participant = ctx.accounts.participant.key();
+        let participant = operation.participant;
+        let now = Clock::get()?.unix_timestamp;
         
         // Verify participant authorization
         require!(
@@ -238,18 +818,100 @@ impl<'info> ProcessHFTOperation<'info> {
             VaultError::UnauthorizedAccess
         );
         
-        // Validate operation
+        // Signed by the participant or their session key
+        enhanced_channel.authorize_operation(&operation, &ctx.accounts.participant.key(), now)?;
+        
+        let fills = enhanced_channel.process_hft_operation(operation.clone(), participant, now)?;
+        emit_fills(enhanced_channel.channel_id, &fills);
+        
+        msg!(
+            "HFT operation {} processed for participant {} in channel {}",
+            operation.id,
+            participant,
+            bs58::encode(enhanced_channel.channel_id).into_string()
+        );
+        
+        Ok(())
+    }
+}
+
+impl<'info> CheckpointMicroTransactions<'info> {
+    pub fn process(ctx: Context<CheckpointMicroTransactions>) -> Result<()> {
+        let enhanced_channel = &mut ctx.accounts.enhanced_channel;
+        let participant = ctx.accounts.participant.key();
+        
+        require!(
+            enhanced_channel.is_participant(&participant),
+            VaultError::UnauthorizedAccess
+        );
+        require!(
+            enhanced_channel.status == EnhancedChannelStatus::Active,
+            VaultError::SecurityViolation
+        );
+        
+        let now = Clock::get()?.unix_timestamp;
+        let realized = enhanced_channel.realize_pnl(now)?;
+        for pnl in realized.iter() {
+            emit!(PnlRealized {
+                channel_id: enhanced_channel.channel_id,
+                participant: pnl.participant,
+                amount: pnl.amount,
+                mark_price: enhanced_channel.mark_price,
+                timestamp: now,
+            });
+        }
+        enhanced_channel.checkpoint_net_transfers(now)?;
+        
+        msg!(
+            "Checkpoint realized PnL for {} participants in channel {}",
+            realized.len(),
+            bs58::encode(enhanced_channel.channel_id).into_string()
+        );
+        
+        Ok(())
+    }
+}
+
+impl<'info> RaiseExposureLimit<'info> {
+    pub fn process(
+        ctx: Context<RaiseExposureLimit>,
+        participant: Pubkey,
+        asset: ChannelAsset,
+        new_limit: u64,
+        signatures: Vec<Vec<u8>>,
+    ) -> Result<()> {
+        ctx.accounts.emergency_state.require_not_paused(EmergencyScope::Channels)?;
+
+        let enhanced_channel = &mut ctx.accounts.enhanced_channel;
+        
         require!(
-            operation.participant == participant,
+            enhanced_channel.is_participant(&ctx.accounts.authority.key()),
             VaultError::UnauthorizedAccess
         );
         
-        enhanced_channel.process_hft_operation(operation.clone(), participant)?;
+        // Every participant, active or not, signs in participant order
+        let signers: Vec<Pubkey> = enhanced_channel.participants.iter().map(|p| p.pubkey).collect();
+        let message = Ed25519Verifier::exposure_limit_message(
+            &enhanced_channel.channel_id,
+            &participant,
+            asset as u8,
+            new_limit,
+            enhanced_channel.nonce,
+        );
+        Ed25519Verifier::verify_participant_signatures(
+            &ctx.accounts.instructions_sysvar.to_account_info(),
+            &signers,
+            &signatures,
+            &message,
+        )?;
+        
+        enhanced_channel.raise_exposure_limit(&participant, asset, new_limit, Clock::get()?.unix_timestamp)?;
         
         msg!(
-            "HFT operation {} processed for participant {} in channel {}",
-            operation.id,
+            "{:?} exposure limit for {} raised to {} in channel {}",
+            asset,
             participant,
+            new_limit,
             bs58::encode(enhanced_channel.channel_id).into_string()
         );
         
@@ -262,8 +924,11 @@ impl<'info> ProcessMicroTransaction<'info> {
         ctx: Context<ProcessMicroTransaction>,
         transaction: MicroTransaction,
     ) -> Result<()> {
+        ctx.accounts.emergency_state.require_not_paused(EmergencyScope::Channels)?;
+
         let enhanced_channel = &mut ctx.accounts.enhanced_channel;
-        let participant = ctx.accounts.from_participant.key();
+        let participant = transaction.from;
+        let now = Clock::get()?.unix_timestamp;
         
         // Verify participant authorization
         require!(
@@ -271,18 +936,15 @@ impl<'info> ProcessMicroTransaction<'info> {
             VaultError::UnauthorizedAccess
         );
         
-        // Validate transaction
-        require!(
-            transaction.from == participant,
-            VaultError::UnauthorizedAccess
-        );
+        // Signed by the sender or their session key
+        enhanced_channel.authorize_transfer(&transaction, &ctx.accounts.from_participant.key(), now)?;
         
         require!(
             transaction.amount > 0,
             VaultError::InvalidAllocation
         );
         
-        enhanced_channel.process_micro_transaction(transaction.clone(), participant)?;
+        enhanced_channel.process_micro_transaction(transaction.clone(), participant, now)?;
         
         msg!(
             "Micro-transaction {} processed: {} -> {} amount {}",
@@ -296,59 +958,409 @@ impl<'info> ProcessMicroTransaction<'info> {
     }
 }
 
+impl<'info> RegisterSessionKey<'info> {
+    pub fn process(
+        ctx: Context<RegisterSessionKey>,
+        delegate: Pubkey,
+        expires_at: i64,
+        max_operation_value: u64,
+        method: AuthMethod,
+        identifier: String,
+        code: String,
+        session_id: String,
+    ) -> Result<()> {
+        ctx.accounts.emergency_state.require_not_paused(EmergencyScope::Channels)?;
+
+        let enhanced_channel = &mut ctx.accounts.enhanced_channel;
+        let user_auth = &mut ctx.accounts.user_auth;
+        let participant = ctx.accounts.participant.key();
+        let now = Clock::get()?.unix_timestamp;
+        
+        require!(!user_auth.is_locked(), VaultError::AccountLocked);
+        require!(
+            !user_auth.get_active_2fa_methods(now).is_empty(),
+            VaultError::TwoFactorRequired
+        );
+        if !user_auth.verify_auth_factor(method, identifier, code)? {
+            return Err(VaultError::InvalidAuthCode.into());
+        }
+        
+        // A delegate has no destination; its risk is the session's and its value cap
+        let risk = compute_operation_risk(user_auth, &session_id, "session_key", max_operation_value, true, now)?;
+        if risk >= ctx.accounts.auth_config.session_key_risk_threshold {
+            return Err(VaultError::OperationRiskTooHigh.into());
+        }
+        record_operation_risk(user_auth, &session_id, "session_key", risk)?;
+        
+        enhanced_channel.register_session_key(
+            &participant,
+            delegate,
+            expires_at,
+            max_operation_value,
+            now,
+        )?;
+        
+        msg!(
+            "Session key {} registered for {} in channel {} until {}",
+            delegate,
+            participant,
+            bs58::encode(enhanced_channel.channel_id).into_string(),
+            expires_at
+        );
+        
+        Ok(())
+    }
+}
+
+impl<'info> RevokeSessionKey<'info> {
+    pub fn process(ctx: Context<RevokeSessionKey>, participant: Pubkey) -> Result<()> {
+        let enhanced_channel = &mut ctx.accounts.enhanced_channel;
+        let authority = ctx.accounts.authority.key();
+        
+        // The participant or the session key itself may revoke
+        let index = enhanced_channel.participants
+            .iter()
+            .position(|p| p.pubkey == participant)
+            .ok_or(VaultError::UnauthorizedAccess)?;
+        let delegate = enhanced_channel.session_keys[index].as_ref().map(|key| key.delegate);
+        require!(
+            authority == participant || delegate == Some(authority),
+            VaultError::UnauthorizedAccess
+        );
+        
+        enhanced_channel.revoke_session_key(&participant, Clock::get()?.unix_timestamp)?;
+        
+        msg!(
+            "Session key for {} revoked in channel {}",
+            participant,
+            bs58::encode(enhanced_channel.channel_id).into_string()
+        );
+        
+        Ok(())
+    }
+}
+
 impl<'info> AddPendingOperation<'info> {
     pub fn process(
         ctx: Context<AddPendingOperation>,
         operation: PendingOperation,
     ) -> Result<()> {
+        ctx.accounts.emergency_state.require_not_paused(EmergencyScope::Channels)?;
+
+        let enhanced_channel = &mut ctx.accounts.enhanced_channel;
+        let participant = ctx.accounts.participant.key();
+        
+        // Verify participant authorization
+        require!(
+            enhanced_channel.is_participant(&participant),
+            VaultError::UnauthorizedAccess
+        );
+        
+        // Verify participant is involved in the operation
+        require!(
+            operation.participants.contains(&participant),
+            VaultError::UnauthorizedAccess
+        );
+        
+        enhanced_channel.add_pending_operation(operation.clone(), participant, Clock::get()?.unix_timestamp)?;
+        
+        msg!(
+            "Pending operation {} added to channel {}",
+            operation.operation_id,
+            bs58::encode(enhanced_channel.channel_id).into_string()
+        );
+        
+        Ok(())
+    }
+}
+
+impl<'info> EvictExpiredOperations<'info> {
+    pub fn process(ctx: Context<EvictExpiredOperations>) -> Result<()> {
+        let enhanced_channel = &mut ctx.accounts.enhanced_channel;
+        
+        let evicted = enhanced_channel.evict_expired_operations(Clock::get()?.unix_timestamp);
+        
+        for operation in &evicted {
+            msg!(
+                "Evicted expired operation {}, released {} reserved by {}",
+                operation.operation_id,
+                operation.reserved_amount,
+                operation.proposer
+            );
+        }
+        
+        Ok(())
+    }
+}
+
+impl<'info> ConfirmOperation<'info> {
+    pub fn process(
+        ctx: Context<ConfirmOperation>,
+        operation_id: u64,
+        signature: [u8; 64],
+    ) -> Result<()> {
+        ctx.accounts.emergency_state.require_not_paused(EmergencyScope::Channels)?;
+
+        let enhanced_channel = &mut ctx.accounts.enhanced_channel;
+        let participant = ctx.accounts.participant.key();
+        
+        // Verify participant authorization
+        require!(
+            enhanced_channel.is_participant(&participant),
+            VaultError::UnauthorizedAccess
+        );
+        
+        enhanced_channel.confirm_operation(operation_id, participant, signature, Clock::get()?.unix_timestamp)?;
+        
+        msg!(
+            "Operation {} confirmed by participant {} in channel {}",
+            operation_id,
+            participant,
+            bs58::encode(enhanced_channel.channel_id).into_string()
+        );
+        
+        Ok(())
+    }
+}
+
+impl<'info> InitiateDispute<'info> {
+    pub fn process(
+        ctx: Context<InitiateDispute>,
+        disputed_state: [u8; 32],
+        evidence: Vec<u8>,
+        dispute_type: DisputeType,
+    ) -> Result<()> {
+        let enhanced_channel = &mut ctx.accounts.enhanced_channel;
+        let challenger = ctx.accounts.challenger.key();
+        
+        // Verify challenger is a participant
+        require!(
+            enhanced_channel.is_participant(&challenger),
+            VaultError::UnauthorizedAccess
+        );
+        
+        // Validate evidence size
+        require!(
+            evidence.len() <= 1024, // Max 1KB evidence
+            VaultError::InvalidAllocation
+        );
+        
+        enhanced_channel.initiate_dispute(
+            challenger,
+            disputed_state,
+            evidence,
+            dispute_type,
+            Clock::get()?.unix_timestamp,
+        )?;
+        
+        msg!(
+            "Dispute initiated by {} in channel {} for type {:?}",
+            challenger,
+            bs58::encode(enhanced_channel.channel_id).into_string(),
+            dispute_type
+        );
+        
+        Ok(())
+    }
+}
+
+impl<'info> ResolveDispute<'info> {
+    pub fn process(ctx: Context<ResolveDispute>) -> Result<()> {
         let enhanced_channel = &mut ctx.accounts.enhanced_channel;
+        let resolver = ctx.accounts.resolver.key();
+        
+        // Verify resolver is authorized (multisig signer)
+        require!(
+            is_multisig_signer(&ctx.accounts.multisig_wallet, &resolver),
+            VaultError::UnauthorizedAccess
+        );
+        
+        // The resolution comes from the evidence, never from the resolver
+        let dispute = enhanced_channel.dispute_info.clone().ok_or(VaultError::SecurityViolation)?;
+        let mut resolution = DisputeResolver::analyze_dispute(
+            enhanced_channel,
+            &dispute,
+            &ctx.accounts.instructions_sysvar.to_account_info(),
+        )?;
+        resolution.resolver = resolver;
+        
+        if let Some(loser) = resolution.loser {
+            let program_id = ctx.program_id;
+            let channel_id = enhanced_channel.channel_id;
+            let loser_info = ctx.accounts.loser_collateral.to_account_info();
+            let mut loser_vault = load_enhanced_collateral(&loser_info, &channel_id, &loser, program_id)?;
+            let available = loser_vault.as_ref().map_or(0, |vault| vault.amount);
+            
+            let outcome = enhanced_channel.apply_slash(
+                &loser,
+                resolution.winner.is_some(),
+                resolution.penalty,
+                available,
+                Treasury::SLASH_INSURANCE_CUT_BPS,
+                resolution.resolved_at,
+            )?;
+            resolution.penalty = outcome.penalty;
+            
+            if let Some(vault) = loser_vault.as_mut() {
+                vault.amount -= outcome.paid;
+                store_enhanced_collateral(&loser_info, vault)?;
+                **loser_info.try_borrow_mut_lamports()? -= outcome.paid;
+            }
+            
+            // A winner who never deposited is paid to their wallet when it is
+            // passed instead of the vault; otherwise their share is insured
+            let mut insurance_cut = outcome.insurance_cut;
+            if outcome.winner_share > 0 {
+                let winner = resolution.winner.ok_or(VaultError::MissingRequiredAccount)?;
+                let winner_info = ctx.accounts.winner_collateral.to_account_info();
+                if winner_info.key() == winner {
+                    **winner_info.try_borrow_mut_lamports()? += outcome.winner_share;
+                } else if let Some(mut winner_vault) = load_enhanced_collateral(&winner_info, &channel_id, &winner, program_id)? {
+                    winner_vault.deposit(outcome.winner_share)?;
+                    store_enhanced_collateral(&winner_info, &winner_vault)?;
+                    **winner_info.try_borrow_mut_lamports()? += outcome.winner_share;
+                } else {
+                    insurance_cut = insurance_cut
+                        .checked_add(outcome.winner_share)
+                        .ok_or(VaultError::ArithmeticOverflow)?;
+                }
+            }
+            
+            let treasury = &mut ctx.accounts.treasury;
+            **treasury.to_account_info().try_borrow_mut_lamports()? += insurance_cut;
+            treasury.insurance_fund = treasury.insurance_fund
+                .checked_add(insurance_cut)
+                .ok_or(VaultError::ArithmeticOverflow)?;
+            
+            if outcome.bad_debt > 0 {
+                emit!(EnhancedChannelBadDebt {
+                    channel_id,
+                    participant: loser,
+                    amount: outcome.bad_debt,
+                    dispute_id: dispute.dispute_id,
+                    security_level: SecurityLevel::Critical,
+                });
+                msg!("Incident: {} slashed {} short of collateral in channel {}",
+                     loser,
+                     outcome.bad_debt,
+                     bs58::encode(channel_id).into_string());
+            }
+        }
+        
+        enhanced_channel.resolve_dispute(resolution.clone(), resolver, resolution.resolved_at)?;
+        
+        msg!(
+            "Dispute resolved by {} in channel {} with type {:?}",
+            resolver,
+            bs58::encode(enhanced_channel.channel_id).into_string(),
+            resolution.resolution_type
+        );
+        
+        Ok(())
+    }
+}
+
+impl<'info> DepositEnhancedCollateral<'info> {
+    pub fn process(ctx: Context<DepositEnhancedCollateral>, amount: u64) -> Result<()> {
+        ctx.accounts.emergency_state.require_not_paused(EmergencyScope::Channels)?;
+        ctx.accounts.kyc_profile.require_not_frozen(FrozenAction::Channels)?;
+
+        let enhanced_channel = &ctx.accounts.enhanced_channel;
+        let participant = ctx.accounts.participant.key();
+        
+        require!(
+            matches!(enhanced_channel.status, EnhancedChannelStatus::Initializing | EnhancedChannelStatus::Active),
+            VaultError::SecurityViolation
+        );
+        require!(
+            enhanced_channel.participants.iter().any(|p| p.pubkey == participant),
+            VaultError::UnauthorizedAccess
+        );
+        
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.participant.to_account_info(),
+                    to: ctx.accounts.collateral.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        
+        let collateral = &mut ctx.accounts.collateral;
+        if collateral.participant == Pubkey::default() {
+            collateral.channel_id = enhanced_channel.channel_id;
+            collateral.participant = participant;
+            collateral.bump = ctx.bumps.collateral;
+        }
+        collateral.deposit(amount)?;
+        
+        msg!("Participant {} deposited {} enhanced channel collateral (total {})", participant, amount, collateral.amount);
+        
+        Ok(())
+    }
+}
+
+impl<'info> DepositEnhancedTokenCollateral<'info> {
+    pub fn process(ctx: Context<DepositEnhancedTokenCollateral>, amount: u64) -> Result<()> {
+        ctx.accounts.emergency_state.require_not_paused(EmergencyScope::Channels)?;
+        ctx.accounts.kyc_profile.require_not_frozen(FrozenAction::Channels)?;
+
+        let enhanced_channel = &ctx.accounts.enhanced_channel;
         let participant = ctx.accounts.participant.key();
         
-        // Verify participant authorization
         require!(
-            enhanced_channel.is_participant(&participant),
-            VaultError::UnauthorizedAccess
+            matches!(enhanced_channel.status, EnhancedChannelStatus::Initializing | EnhancedChannelStatus::Active),
+            VaultError::SecurityViolation
         );
-        
-        // Verify participant is involved in the operation
         require!(
-            operation.participants.contains(&participant),
+            enhanced_channel.participants.iter().any(|p| p.pubkey == participant),
             VaultError::UnauthorizedAccess
         );
         
-        enhanced_channel.add_pending_operation(operation.clone())?;
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.participant_usdc.to_account_info(),
+                    to: ctx.accounts.usdc_vault.to_account_info(),
+                    authority: ctx.accounts.participant.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
         
-        msg!(
-            "Pending operation {} added to channel {}",
-            operation.operation_id,
-            bs58::encode(enhanced_channel.channel_id).into_string()
-        );
+        msg!("Participant {} deposited {} USDC enhanced channel collateral", participant, amount);
         
         Ok(())
     }
 }
 
-impl<'info> ConfirmOperation<'info> {
-    pub fn process(
-        ctx: Context<ConfirmOperation>,
-        operation_id: u64,
-        signature: [u8; 64],
-    ) -> Result<()> {
+impl<'info> CloseEnhancedChannel<'info> {
+    pub fn process(ctx: Context<CloseEnhancedChannel>) -> Result<()> {
         let enhanced_channel = &mut ctx.accounts.enhanced_channel;
-        let participant = ctx.accounts.participant.key();
         
-        // Verify participant authorization
+        // Verify authority is a multisig signer
         require!(
-            enhanced_channel.is_participant(&participant),
+            is_multisig_signer(&ctx.accounts.multisig_wallet, &ctx.accounts.authority.key()),
             VaultError::UnauthorizedAccess
         );
         
-        enhanced_channel.confirm_operation(operation_id, participant, signature)?;
+        let now = Clock::get()?.unix_timestamp;
+        let realized = enhanced_channel.close_channel(now)?;
+        for pnl in realized.iter() {
+            emit!(PnlRealized {
+                channel_id: enhanced_channel.channel_id,
+                participant: pnl.participant,
+                amount: pnl.amount,
+                mark_price: enhanced_channel.mark_price,
+                timestamp: now,
+            });
+        }
         
         msg!(
-            "Operation {} confirmed by participant {} in channel {}",
-            operation_id,
-            participant,
+            "Enhanced state channel {} closed, awaiting settlement",
             bs58::encode(enhanced_channel.channel_id).into_string()
         );
         
@@ -356,94 +1368,292 @@ impl<'info> ConfirmOperation<'info> {
     }
 }
 
-impl<'info> InitiateDispute<'info> {
-    pub fn process(
-        ctx: Context<InitiateDispute>,
-        disputed_state: [u8; 32],
-        evidence: Vec<u8>,
-        dispute_type: DisputeType,
-    ) -> Result<()> {
+impl<'info> SettleEnhancedChannel<'info> {
+    pub fn process(ctx: Context<'_, '_, 'info, 'info, SettleEnhancedChannel<'info>>) -> Result<()> {
+        ctx.accounts.emergency_state.require_not_paused(EmergencyScope::Channels)?;
+
         let enhanced_channel = &mut ctx.accounts.enhanced_channel;
-        let challenger = ctx.accounts.challenger.key();
+        let program_id = ctx.program_id;
+        let channel_id = enhanced_channel.channel_id;
+        let participants: Vec<Pubkey> = enhanced_channel.participants.iter().map(|p| p.pubkey).collect();
+        let n = participants.len();
+        
+        require!(ctx.remaining_accounts.len() == 2 * n, VaultError::MissingRequiredAccount);
+        let (sol_infos, usdc_infos) = ctx.remaining_accounts.split_at(n);
+        
+        // SOL vaults are program-owned, so lamports move directly
+        let mut sol_vaults = Vec::with_capacity(n);
+        for (info, participant) in sol_infos.iter().zip(&participants) {
+            require!(info.is_writable, VaultError::UnauthorizedAccess);
+            sol_vaults.push(load_enhanced_collateral(info, &channel_id, participant, program_id)?);
+        }
+        let deposits: Vec<u64> = sol_vaults.iter().map(|vault| vault.as_ref().map_or(0, |v| v.amount)).collect();
+        let (payouts, sol_fees) = settlement_payouts(enhanced_channel, ChannelAsset::Sol, &deposits)?;
         
-        // Verify challenger is a participant
-        require!(
-            enhanced_channel.is_participant(&challenger),
-            VaultError::UnauthorizedAccess
-        );
+        for ((info, vault), payout) in sol_infos.iter().zip(sol_vaults.iter_mut()).zip(payouts) {
+            let Some(vault) = vault else {
+                require!(payout == 0, VaultError::MissingRequiredAccount);
+                continue;
+            };
+            let deposit = vault.amount;
+            vault.amount = payout;
+            store_enhanced_collateral(info, vault)?;
+            if payout < deposit {
+                **info.try_borrow_mut_lamports()? -= deposit - payout;
+            } else {
+                **info.try_borrow_mut_lamports()? += payout - deposit;
+            }
+        }
         
-        // Validate evidence size
-        require!(
-            evidence.len() <= 1024, // Max 1KB evidence
-            VaultError::InvalidAllocation
-        );
+        let treasury = &mut ctx.accounts.treasury;
+        **treasury.to_account_info().try_borrow_mut_lamports()? += sol_fees;
+        treasury.sol_balance = treasury.sol_balance
+            .checked_add(sol_fees)
+            .ok_or(VaultError::ArithmeticOverflow)?;
+        
+        // USDC vaults belong to the token program; surpluses are transferred
+        // to whoever is owed, the treasury last
+        let mut usdc_vaults = Vec::with_capacity(n);
+        for (info, participant) in usdc_infos.iter().zip(&participants) {
+            let (expected, _) = Pubkey::find_program_address(
+                &[b"enhanced_usdc_vault", channel_id.as_ref(), participant.as_ref()],
+                program_id,
+            );
+            if info.key() != expected || !info.is_writable {
+                return Err(VaultError::UnauthorizedAccess.into());
+            }
+            usdc_vaults.push(if info.data_is_empty() {
+                None
+            } else {
+                Some(Account::<TokenAccount>::try_from(info)?)
+            });
+        }
+        let deposits: Vec<u64> = usdc_vaults.iter().map(|vault| vault.as_ref().map_or(0, |v| v.amount)).collect();
+        let (payouts, usdc_fees) = settlement_payouts(enhanced_channel, ChannelAsset::Usdc, &deposits)?;
+        
+        let mut sources = Vec::new();
+        let mut owed = Vec::new();
+        for ((vault, deposit), payout) in usdc_vaults.iter().zip(&deposits).zip(payouts) {
+            match vault {
+                Some(vault) if payout < *deposit => sources.push((vault.to_account_info(), deposit - payout)),
+                Some(vault) if payout > *deposit => owed.push((vault.to_account_info(), payout - deposit)),
+                Some(_) => {}
+                None => require!(payout == 0, VaultError::MissingRequiredAccount),
+            }
+        }
+        if usdc_fees > 0 {
+            owed.push((ctx.accounts.treasury_usdc.to_account_info(), usdc_fees));
+        }
         
-        enhanced_channel.initiate_dispute(
-            challenger,
-            disputed_state,
-            evidence,
-            dispute_type.clone(),
-        )?;
+        let bump = [enhanced_channel.bump];
+        let seeds: &[&[u8]] = &[b"enhanced_channel", channel_id.as_ref(), &bump];
+        let authority = enhanced_channel.to_account_info();
+        let mut next = 0;
+        for (source, mut surplus) in sources {
+            while surplus > 0 {
+                let (destination, remaining) = owed.get_mut(next).ok_or(VaultError::InvalidAllocation)?;
+                let amount = surplus.min(*remaining);
+                transfer_from_usdc_vault(&ctx.accounts.token_program, &source, destination, &authority, seeds, amount)?;
+                surplus -= amount;
+                *remaining -= amount;
+                if *remaining == 0 {
+                    next += 1;
+                }
+            }
+        }
+        
+        enhanced_channel.complete_settlement(Clock::get()?.unix_timestamp)?;
+        
+        emit!(EnhancedChannelSettled {
+            channel_id,
+            sol_fees,
+            usdc_fees,
+        });
         
         msg!(
-            "Dispute initiated by {} in channel {} for type {:?}",
-            challenger,
-            bs58::encode(enhanced_channel.channel_id).into_string(),
-            dispute_type
+            "Enhanced state channel {} settled ({} SOL and {} USDC in fees)",
+            bs58::encode(channel_id).into_string(),
+            sol_fees,
+            usdc_fees
         );
         
         Ok(())
     }
 }
 
-impl<'info> ResolveDispute<'info> {
+impl<'info> ClaimEnhancedSettlement<'info> {
+    pub fn process(ctx: Context<ClaimEnhancedSettlement>) -> Result<()> {
+        ctx.accounts.emergency_state.require_not_paused(EmergencyScope::Channels)?;
+        ctx.accounts.kyc_profile.require_not_frozen(FrozenAction::Channels)?;
+
+        let enhanced_channel = &ctx.accounts.enhanced_channel;
+        let participant = ctx.accounts.participant.key();
+        
+        let collateral = &mut ctx.accounts.collateral;
+        let sol_amount = collateral.amount;
+        collateral.withdraw(sol_amount)?;
+        **collateral.to_account_info().try_borrow_mut_lamports()? -= sol_amount;
+        **ctx.accounts.participant.to_account_info().try_borrow_mut_lamports()? += sol_amount;
+        
+        let mut usdc_amount = 0;
+        if let Some(usdc_vault) = &ctx.accounts.usdc_vault {
+            let destination = ctx.accounts.participant_usdc.as_ref().ok_or(VaultError::MissingTokenAccount)?;
+            usdc_amount = usdc_vault.amount;
+            let bump = [enhanced_channel.bump];
+            let seeds: &[&[u8]] = &[b"enhanced_channel", enhanced_channel.channel_id.as_ref(), &bump];
+            transfer_from_usdc_vault(
+                &ctx.accounts.token_program,
+                &usdc_vault.to_account_info(),
+                &destination.to_account_info(),
+                &enhanced_channel.to_account_info(),
+                seeds,
+                usdc_amount,
+            )?;
+        }
+        
+        msg!("Participant {} claimed {} SOL and {} USDC from enhanced channel settlement", participant, sol_amount, usdc_amount);
+        
+        Ok(())
+    }
+}
+
+impl<'info> RequestChannelWithdrawal<'info> {
     pub fn process(
-        ctx: Context<ResolveDispute>,
-        resolution: DisputeResolution,
+        ctx: Context<RequestChannelWithdrawal>,
+        amount: u64,
+        asset: ChannelAsset,
+        signatures: Vec<Vec<u8>>,
     ) -> Result<()> {
-        let enhanced_channel = &mut ctx.accounts.enhanced_channel;
-        let resolver = ctx.accounts.resolver.key();
+        ctx.accounts.emergency_state.require_not_paused(EmergencyScope::Channels)?;
+        ctx.accounts.kyc_profile.require_not_frozen(FrozenAction::Channels)?;
+
+        let enhanced_channel = &ctx.accounts.enhanced_channel;
+        let participant = ctx.accounts.participant.key();
+        let now = Clock::get()?.unix_timestamp;
         
-        // Verify resolver is authorized (multisig signer)
-        require!(
-            is_multisig_signer(&ctx.accounts.multisig_wallet, &resolver),
-            VaultError::UnauthorizedAccess
+        let deposit = vaulted_deposit(asset, &ctx.accounts.collateral, &ctx.accounts.usdc_vault)?;
+        enhanced_channel.check_withdrawal(&participant, asset, amount, deposit)?;
+        
+        let withdrawal = &mut ctx.accounts.withdrawal;
+        if withdrawal.participant == Pubkey::default() {
+            withdrawal.channel_id = enhanced_channel.channel_id;
+            withdrawal.participant = participant;
+            withdrawal.bump = ctx.bumps.withdrawal;
+        }
+        // One withdrawal in flight at a time, so none can count the same headroom
+        require!(withdrawal.pending.is_none(), VaultError::PaymentInProgress);
+        
+        let counterparties: Vec<Pubkey> = enhanced_channel.participants
+            .iter()
+            .map(|p| p.pubkey)
+            .filter(|key| *key != participant)
+            .collect();
+        
+        if signatures.is_empty() && !counterparties.is_empty() {
+            withdrawal.request(asset, amount, now)?;
+            let executable_at = enhanced_channel.withdrawal_executable_at(now)?;
+            emit!(ChannelWithdrawalRequested {
+                channel_id: enhanced_channel.channel_id,
+                participant,
+                asset,
+                amount,
+                executable_at,
+            });
+            msg!("Participant {} requested a {} {:?} withdrawal, executable at {}", participant, amount, asset, executable_at);
+            return Ok(());
+        }
+        
+        let message = Ed25519Verifier::enhanced_withdrawal_message(
+            &enhanced_channel.channel_id,
+            &participant,
+            asset as u8,
+            amount,
+            withdrawal.nonce,
         );
+        Ed25519Verifier::verify_participant_signatures(
+            &ctx.accounts.instructions_sysvar.to_account_info(),
+            &counterparties,
+            &signatures,
+            &message,
+        )?;
+        withdrawal.bump_nonce()?;
+        
+        pay_out_withdrawal(
+            enhanced_channel,
+            &mut ctx.accounts.collateral,
+            &ctx.accounts.usdc_vault,
+            &ctx.accounts.participant_usdc,
+            &ctx.accounts.participant.to_account_info(),
+            &ctx.accounts.token_program,
+            &PendingWithdrawal { asset, amount, requested_at: now },
+        )?;
+        
+        msg!("Participant {} withdrew {} {:?} from active enhanced channel", participant, amount, asset);
+        
+        Ok(())
+    }
+}
+
+impl<'info> ExecuteChannelWithdrawal<'info> {
+    pub fn process(ctx: Context<ExecuteChannelWithdrawal>) -> Result<()> {
+        ctx.accounts.emergency_state.require_not_paused(EmergencyScope::Channels)?;
+        ctx.accounts.kyc_profile.require_not_frozen(FrozenAction::Channels)?;
+
+        let enhanced_channel = &ctx.accounts.enhanced_channel;
+        let participant = ctx.accounts.participant.key();
+        let withdrawal = &mut ctx.accounts.withdrawal;
+        let pending = withdrawal.pending.clone().ok_or(VaultError::PaymentNotFound)?;
         
-        // Verify there's an active dispute
         require!(
-            enhanced_channel.dispute_info.is_some(),
-            VaultError::SecurityViolation
+            Clock::get()?.unix_timestamp >= enhanced_channel.withdrawal_executable_at(pending.requested_at)?,
+            VaultError::TimelockNotElapsed
         );
         
-        enhanced_channel.resolve_dispute(resolution.clone(), resolver)?;
+        // Exposure may have grown since the request
+        let deposit = vaulted_deposit(pending.asset, &ctx.accounts.collateral, &ctx.accounts.usdc_vault)?;
+        enhanced_channel.check_withdrawal(&participant, pending.asset, pending.amount, deposit)?;
+        withdrawal.take_pending()?;
+        
+        pay_out_withdrawal(
+            enhanced_channel,
+            &mut ctx.accounts.collateral,
+            &ctx.accounts.usdc_vault,
+            &ctx.accounts.participant_usdc,
+            &ctx.accounts.participant.to_account_info(),
+            &ctx.accounts.token_program,
+            &pending,
+        )?;
         
-        msg!(
-            "Dispute resolved by {} in channel {} with type {:?}",
-            resolver,
-            bs58::encode(enhanced_channel.channel_id).into_string(),
-            resolution.resolution_type
-        );
+        msg!("Participant {} withdrew {} {:?} from active enhanced channel", participant, pending.amount, pending.asset);
         
         Ok(())
     }
 }
 
-impl<'info> CloseEnhancedChannel<'info> {
-    pub fn process(ctx: Context<CloseEnhancedChannel>) -> Result<()> {
-        let enhanced_channel = &mut ctx.accounts.enhanced_channel;
+impl<'info> ObjectChannelWithdrawal<'info> {
+    pub fn process(ctx: Context<ObjectChannelWithdrawal>, participant: Pubkey) -> Result<()> {
+        let enhanced_channel = &ctx.accounts.enhanced_channel;
+        let objector = ctx.accounts.objector.key();
         
-        // Verify authority is a multisig signer
         require!(
-            is_multisig_signer(&ctx.accounts.multisig_wallet, &ctx.accounts.authority.key()),
+            objector != participant && enhanced_channel.is_participant(&objector),
             VaultError::UnauthorizedAccess
         );
         
-        enhanced_channel.close_channel()?;
+        let withdrawal = &mut ctx.accounts.withdrawal;
+        let requested_at = withdrawal.pending.as_ref().ok_or(VaultError::PaymentNotFound)?.requested_at;
+        require!(
+            Clock::get()?.unix_timestamp < enhanced_channel.withdrawal_executable_at(requested_at)?,
+            VaultError::SecurityViolation
+        );
+        let pending = withdrawal.take_pending()?;
         
         msg!(
-            "Enhanced state channel {} closed",
-            bs58::encode(enhanced_channel.channel_id).into_string()
+            "Participant {} objected to {}'s {} {:?} withdrawal",
+            objector,
+            participant,
+            pending.amount,
+            pending.asset
         );
         
         Ok(())
@@ -455,8 +1665,11 @@ impl<'info> BatchProcessOperations<'info> {
         ctx: Context<BatchProcessOperations>,
         operations: Vec<HFTOperation>,
     ) -> Result<()> {
+        ctx.accounts.emergency_state.require_not_paused(EmergencyScope::Channels)?;
+
         let enhanced_channel = &mut ctx.accounts.enhanced_channel;
-        let participant = ctx.accounts.participant.key();
+        let signer = ctx.accounts.participant.key();
+        let participant = operations.first().map_or(signer, |operation| operation.participant);
         
         // Verify participant authorization
         require!(
@@ -470,6 +1683,15 @@ impl<'info> BatchProcessOperations<'info> {
             VaultError::InvalidAllocation
         );
         
+        // Reject replayed or reordered operations before touching state
+        enhanced_channel.check_batch_nonces(&participant, &operations)?;
+        
+        // Every operation in the batch counts toward the rate limit
+        let now = Clock::get()?.unix_timestamp;
+        let index = enhanced_channel.participant_index(&participant).ok_or(VaultError::UnauthorizedAccess)?;
+        enhanced_channel.check_rate_limit(index, operations.len() as u32, now)?;
+        enhanced_channel.stats.record_batch(operations.len());
+        
         // Process each operation in the batch
         for operation in operations.iter() {
             // Verify operation belongs to participant
@@ -477,8 +1699,10 @@ impl<'info> BatchProcessOperations<'info> {
                 operation.participant == participant,
                 VaultError::UnauthorizedAccess
             );
+            enhanced_channel.authorize_operation(operation, &signer, now)?;
             
-            enhanced_channel.process_hft_operation(operation.clone(), participant)?;
+            let fills = enhanced_channel.process_hft_operation(operation.clone(), participant, now)?;
+            emit_fills(enhanced_channel.channel_id, &fills);
         }
         
         msg!(
@@ -492,7 +1716,246 @@ impl<'info> BatchProcessOperations<'info> {
     }
 }
 
+impl<'info> GetChannelStats<'info> {
+    /// Returned to the caller as return data
+    pub fn process(ctx: Context<GetChannelStats>) -> Result<ChannelStats> {
+        Ok(ctx.accounts.enhanced_channel.stats.clone())
+    }
+}
+
+impl<'info> CrankChannelTriggers<'info> {
+    pub fn process(ctx: Context<CrankChannelTriggers>) -> Result<()> {
+        ctx.accounts.emergency_state.require_not_paused(EmergencyScope::Channels)?;
+
+        let enhanced_channel = &mut ctx.accounts.enhanced_channel;
+        let oracle_data = &ctx.accounts.oracle_data;
+        
+        require!(
+            enhanced_channel.status == EnhancedChannelStatus::Active,
+            VaultError::SecurityViolation
+        );
+        
+        // A stale oracle price must not move the mark or fire anything
+        let now = Clock::get()?.unix_timestamp;
+        let oracle_price = if oracle_data.is_active && !oracle_data.is_stale()? {
+            enhanced_channel.update_mark_price(oracle_data.btc_price_usd, now)?;
+            oracle_data.btc_price_usd
+        } else {
+            0
+        };
+        
+        let fills = enhanced_channel.check_triggers(oracle_price, now)?;
+        emit_fills(enhanced_channel.channel_id, &fills);
+        
+        msg!(
+            "Trigger crank produced {} fills in channel {}",
+            fills.len(),
+            bs58::encode(enhanced_channel.channel_id).into_string()
+        );
+        
+        Ok(())
+    }
+}
+
+impl<'info> MigrateEnhancedChannel<'info> {
+    pub fn process(
+        ctx: Context<MigrateEnhancedChannel>,
+        channel_id: [u8; 32],
+        signatures: Vec<Vec<u8>>,
+    ) -> Result<()> {
+        let channel_info = ctx.accounts.enhanced_channel.to_account_info();
+        let legacy = EnhancedStateChannelV1::try_from_account_data(&channel_info.try_borrow_data()?)?;
+        require!(legacy.channel_id == channel_id, VaultError::UnsupportedChannelVersion);
+        
+        // The new layout is consented to by every participant, in participant order
+        let signers: Vec<Pubkey> = legacy.participants.iter().map(|p| p.pubkey).collect();
+        let message = Ed25519Verifier::channel_migration_message(&channel_id, EnhancedStateChannel::VERSION);
+        Ed25519Verifier::verify_participant_signatures(
+            &ctx.accounts.instructions_sysvar.to_account_info(),
+            &signers,
+            &signatures,
+            &message,
+        )?;
+        
+        let rent_shortfall = Rent::get()?
+            .minimum_balance(EnhancedStateChannel::SIZE)
+            .saturating_sub(channel_info.lamports());
+        if rent_shortfall > 0 {
+            anchor_lang::system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: ctx.accounts.payer.to_account_info(),
+                        to: channel_info.clone(),
+                    },
+                ),
+                rent_shortfall,
+            )?;
+        }
+        channel_info.realloc(EnhancedStateChannel::SIZE, false)?;
+        
+        let migrated = legacy.migrate(Clock::get()?.unix_timestamp);
+        migrated.try_serialize(&mut &mut channel_info.try_borrow_mut_data()?[..])?;
+        
+        msg!(
+            "Enhanced channel {} migrated to layout v{}",
+            bs58::encode(channel_id).into_string(),
+            EnhancedStateChannel::VERSION
+        );
+        
+        Ok(())
+    }
+}
+
 // Helper functions
+fn emit_fills(channel_id: [u8; 32], fills: &[TradeFill]) {
+    for fill in fills {
+        emit!(OrderFilled {
+            channel_id,
+            maker_order_id: fill.maker_order_id,
+            taker_order_id: fill.taker_order_id,
+            maker: fill.maker,
+            taker: fill.taker,
+            taker_is_buyer: fill.taker_is_buyer,
+            price: fill.price,
+            amount: fill.amount,
+            fee: fill.fee,
+        });
+    }
+}
+
+/// Load a participant's enhanced channel collateral vault, or `None` if they
+/// never deposited
+fn load_enhanced_collateral(
+    info: &AccountInfo,
+    channel_id: &[u8; 32],
+    participant: &Pubkey,
+    program_id: &Pubkey,
+) -> Result<Option<ChannelCollateral>> {
+    let (expected, _) = Pubkey::find_program_address(
+        &[b"enhanced_collateral", channel_id.as_ref(), participant.as_ref()],
+        program_id,
+    );
+    if info.key() != expected {
+        return Err(VaultError::UnauthorizedAccess.into());
+    }
+    if info.data_is_empty() {
+        return Ok(None);
+    }
+    require!(info.owner == program_id, VaultError::UnauthorizedAccess);
+    let data = info.try_borrow_data()?;
+    Ok(Some(ChannelCollateral::try_deserialize(&mut &data[..])?))
+}
+
+fn store_enhanced_collateral(info: &AccountInfo, collateral: &ChannelCollateral) -> Result<()> {
+    let mut data = info.try_borrow_mut_data()?;
+    collateral.try_serialize(&mut &mut data[..])
+}
+
+/// Split one asset's vaulted deposits between participants by their final
+/// balances. Returns participant payouts and the treasury's share: collected
+/// fees plus any rounding dust, so the vaults always balance.
+fn settlement_payouts(channel: &EnhancedStateChannel, asset: ChannelAsset, deposits: &[u64]) -> Result<(Vec<u64>, u64)> {
+    let entitlements = channel.settlement_entitlements(asset, deposits)?;
+    let mut vaulted = deposits.to_vec();
+    vaulted.push(0);
+    let (mut payouts, undercollateralized) = StateChannel::allocate_collateral(&vaulted, &entitlements)?;
+    
+    let total_collateral: u64 = deposits.iter().sum();
+    if undercollateralized {
+        let total_entitlements: u64 = entitlements.iter().sum();
+        
+        emit!(ChannelUndercollateralized {
+            channel_id: channel.channel_id,
+            total_collateral,
+            total_entitlements,
+            shortfall: total_entitlements - total_collateral,
+        });
+        
+        msg!("Incident: enhanced channel {} under-collateralized in {:?} ({} collateral, {} owed), payouts clamped",
+             bs58::encode(channel.channel_id).into_string(),
+             asset,
+             total_collateral,
+             total_entitlements);
+    }
+    
+    payouts.pop();
+    let paid: u64 = payouts.iter().sum();
+    Ok((payouts, total_collateral - paid))
+}
+
+/// Move USDC out of a channel-owned vault, signed by the channel PDA
+fn transfer_from_usdc_vault<'info>(
+    token_program: &Program<'info, Token>,
+    vault: &AccountInfo<'info>,
+    destination: &AccountInfo<'info>,
+    channel: &AccountInfo<'info>,
+    seeds: &[&[u8]],
+    amount: u64,
+) -> Result<()> {
+    token::transfer(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            Transfer {
+                from: vault.clone(),
+                to: destination.clone(),
+                authority: channel.clone(),
+            },
+            &[seeds],
+        ),
+        amount,
+    )
+}
+
+/// Vaulted deposit of one asset backing a participant's withdrawal
+fn vaulted_deposit(
+    asset: ChannelAsset,
+    collateral: &Option<Account<ChannelCollateral>>,
+    usdc_vault: &Option<Account<TokenAccount>>,
+) -> Result<u64> {
+    match asset {
+        ChannelAsset::Sol => Ok(collateral.as_ref().ok_or(VaultError::MissingRequiredAccount)?.amount),
+        ChannelAsset::Usdc => Ok(usdc_vault.as_ref().ok_or(VaultError::MissingTokenAccount)?.amount),
+        ChannelAsset::VirtualBtc => Err(VaultError::AssetMismatch.into()),
+    }
+}
+
+fn pay_out_withdrawal<'info>(
+    enhanced_channel: &Account<'info, EnhancedStateChannel>,
+    collateral: &mut Option<Account<'info, ChannelCollateral>>,
+    usdc_vault: &Option<Account<'info, TokenAccount>>,
+    participant_usdc: &Option<Account<'info, TokenAccount>>,
+    participant: &AccountInfo<'info>,
+    token_program: &Program<'info, Token>,
+    withdrawal: &PendingWithdrawal,
+) -> Result<()> {
+    let amount = withdrawal.amount;
+    match withdrawal.asset {
+        ChannelAsset::Sol => {
+            let collateral = collateral.as_mut().ok_or(VaultError::MissingRequiredAccount)?;
+            collateral.withdraw(amount)?;
+            **collateral.to_account_info().try_borrow_mut_lamports()? -= amount;
+            **participant.try_borrow_mut_lamports()? += amount;
+        }
+        ChannelAsset::Usdc => {
+            let vault = usdc_vault.as_ref().ok_or(VaultError::MissingTokenAccount)?;
+            let destination = participant_usdc.as_ref().ok_or(VaultError::MissingTokenAccount)?;
+            let bump = [enhanced_channel.bump];
+            let seeds: &[&[u8]] = &[b"enhanced_channel", enhanced_channel.channel_id.as_ref(), &bump];
+            transfer_from_usdc_vault(
+                token_program,
+                &vault.to_account_info(),
+                &destination.to_account_info(),
+                &enhanced_channel.to_account_info(),
+                seeds,
+                amount,
+            )?;
+        }
+        ChannelAsset::VirtualBtc => return Err(VaultError::AssetMismatch.into()),
+    }
+    Ok(())
+}
+
 fn is_multisig_signer(multisig_wallet: &MultisigWallet, signer: &Pubkey) -> bool {
     multisig_wallet.signers.iter().any(|s| s.pubkey == *signer && s.is_active)
 }
@@ -501,7 +1964,7 @@ fn is_multisig_signer(multisig_wallet: &MultisigWallet, signer: &Pubkey) -> bool
 pub struct HFTEngine;
 
 impl HFTEngine {
-    /// Process market order with optimal execution
+    /// Process market order against the channel order book
     pub fn process_market_order(
         channel: &mut EnhancedStateChannel,
         order: &HFTOperation,
@@ -512,20 +1975,18 @@ impl HFTEngine {
             VaultError::InvalidAllocation
         );
         
-        // Execute market order logic
-        let execution_result = HFTExecutionResult {
-            operation_id: order.id,
-            executed_amount: order.amount,
-            executed_price: order.price,
-            fees: calculate_trading_fees(order.amount, channel.config.fee_config.trade_fee_rate),
-            execution_time: Clock::get()?.unix_timestamp,
-            status: ExecutionStatus::Completed,
-        };
+        let fills = channel.process_hft_operation(order.clone(), order.participant, Clock::get()?.unix_timestamp)?;
+        let mut execution_result = Self::summarize_fills(order, &fills)?;
+        
+        // Whatever the book couldn't fill is dropped
+        if fills.is_empty() {
+            execution_result.status = ExecutionStatus::Failed;
+        }
         
         Ok(execution_result)
     }
     
-    /// Process limit order and add to order book
+    /// Process limit order: match what crosses, rest the remainder on the book
     pub fn process_limit_order(
         channel: &mut EnhancedStateChannel,
         order: &HFTOperation,
@@ -536,29 +1997,66 @@ impl HFTEngine {
             VaultError::InvalidAllocation
         );
         
-        // Add to order book (simplified)
-        let execution_result = HFTExecutionResult {
-            operation_id: order.id,
-            executed_amount: 0, // Not executed yet
-            executed_price: order.price,
-            fees: 0,
-            execution_time: Clock::get()?.unix_timestamp,
-            status: ExecutionStatus::Pending,
-        };
+        let fills = channel.process_hft_operation(order.clone(), order.participant, Clock::get()?.unix_timestamp)?;
+        let mut execution_result = Self::summarize_fills(order, &fills)?;
+        
+        if execution_result.executed_amount < order.amount {
+            execution_result.status = ExecutionStatus::Pending;
+        }
+        
+        Ok(execution_result)
+    }
+    
+    /// Park a stop-loss or take-profit order; any fills it cascades into
+    /// belong to other orders, so the result itself stays pending
+    pub fn process_trigger_order(
+        channel: &mut EnhancedStateChannel,
+        order: &HFTOperation,
+    ) -> Result<HFTExecutionResult> {
+        require!(
+            matches!(order.operation_type, HFTOperationType::StopLoss { .. } | HFTOperationType::TakeProfit { .. }),
+            VaultError::InvalidAllocation
+        );
+        
+        channel.process_hft_operation(order.clone(), order.participant, Clock::get()?.unix_timestamp)?;
+        let mut execution_result = Self::summarize_fills(order, &[])?;
+        execution_result.status = ExecutionStatus::Pending;
         
         Ok(execution_result)
     }
     
+    /// Aggregate fills into a single result at the volume-weighted price
+    fn summarize_fills(order: &HFTOperation, fills: &[TradeFill]) -> Result<HFTExecutionResult> {
+        let executed_amount: u64 = fills.iter().map(|fill| fill.amount).sum();
+        let weighted: u128 = fills.iter().map(|fill| fill.amount as u128 * fill.price as u128).sum();
+        let executed_price = if executed_amount == 0 {
+            0
+        } else {
+            (weighted / executed_amount as u128) as u64
+        };
+        
+        Ok(HFTExecutionResult {
+            operation_id: order.id,
+            executed_amount,
+            executed_price,
+            fees: fills.iter().map(|fill| fill.fee).sum(),
+            execution_time: Clock::get()?.unix_timestamp,
+            status: ExecutionStatus::Completed,
+        })
+    }
+    
     /// Cancel existing order
     pub fn cancel_order(
         channel: &mut EnhancedStateChannel,
         order: &HFTOperation,
     ) -> Result<HFTExecutionResult> {
-        // Find and cancel order (simplified)
+        let owner = channel.participant_index(&order.participant).ok_or(VaultError::UnauthorizedAccess)?;
+        let cancelled = channel.cancel_order(order.id, owner)?;
+        
         let execution_result = HFTExecutionResult {
             operation_id: order.id,
             executed_amount: 0,
-            executed_price: 0,
+            executed_price: cancelled.price,
             fees: 0,
             execution_time: Clock::get()?.unix_timestamp,
             status: ExecutionStatus::Cancelled,
@@ -582,6 +2080,9 @@ impl HFTEngine {
                 HFTOperationType::LimitBuy | HFTOperationType::LimitSell => {
                     Self::process_limit_order(channel, operation)?
                 }
+                HFTOperationType::StopLoss { .. } | HFTOperationType::TakeProfit { .. } => {
+                    Self::process_trigger_order(channel, operation)?
+                }
                 HFTOperationType::Cancel => {
                     Self::cancel_order(channel, operation)?
                 }
@@ -617,11 +2118,12 @@ impl MicroTransactionProcessor {
             VaultError::InvalidAllocation
         );
         
-        // Calculate minimal fee
-        let fee = std::cmp::max(
-            transaction.amount / 1000, // 0.1%
-            100 // Minimum 100 lamports
-        );
+        // Netted in the channel; balances move at the next checkpoint
+        let fee = channel.process_micro_transaction(
+            transaction.clone(),
+            transaction.from,
+            Clock::get()?.unix_timestamp,
+        )?;
         
         let result = MicroTransactionResult {
             transaction_id: transaction.id,
@@ -656,100 +2158,50 @@ impl MicroTransactionProcessor {
 pub struct DisputeResolver;
 
 impl DisputeResolver {
-    /// Analyze dispute and generate resolution
+    /// Derive the resolution from the dispute's evidence. Every signature
+    /// the verdict relies on must be verified by the Ed25519 program
+    /// instruction preceding this one.
     pub fn analyze_dispute(
         channel: &EnhancedStateChannel,
         dispute: &DisputeInfo,
+        instructions_sysvar: &AccountInfo,
     ) -> Result<DisputeResolution> {
         let current_time = Clock::get()?.unix_timestamp;
+        let verdict = channel.evaluate_dispute(current_time)?;
         
-        // Analyze evidence and determine resolution
-        let resolution_type = match dispute.dispute_type {
-            DisputeType::InvalidStateTransition => {
-                // Analyze state transition validity
-                if Self::validate_state_transition(&dispute.evidence) {
-                    ResolutionType::DefenderWins
-                } else {
-                    ResolutionType::ChallengerWins
-                }
-            }
-            DisputeType::DoubleSpending => {
-                // Check for double spending evidence
-                if Self::detect_double_spending(&dispute.evidence) {
-                    ResolutionType::ChallengerWins
-                } else {
-                    ResolutionType::DefenderWins
-                }
-            }
-            DisputeType::UnauthorizedOperation => {
-                // Verify operation authorization
-                if Self::verify_authorization(&dispute.evidence) {
-                    ResolutionType::DefenderWins
-                } else {
-                    ResolutionType::ChallengerWins
-                }
-            }
-            DisputeType::TimeoutViolation => {
-                // Check timeout compliance
-                ResolutionType::SystemIntervention
-            }
-            DisputeType::BalanceInconsistency => {
-                // Verify balance calculations
-                if Self::verify_balances(&dispute.evidence) {
-                    ResolutionType::DefenderWins
-                } else {
-                    ResolutionType::ChallengerWins
-                }
-            }
-        };
+        if !verdict.signature_checks.is_empty() {
+            let signers: Vec<Pubkey> = verdict.signature_checks.iter().map(|check| check.signer).collect();
+            let signatures: Vec<Vec<u8>> = verdict.signature_checks.iter().map(|check| check.signature.clone()).collect();
+            let messages: Vec<Vec<u8>> = verdict.signature_checks.iter().map(|check| check.message.clone()).collect();
+            Ed25519Verifier::verify_signed_messages(instructions_sysvar, &signers, &signatures, &messages)?;
+        }
         
-        let penalty = match resolution_type {
-            ResolutionType::ChallengerWins => {
-                // Penalize the defender
-                channel.config.security_params.slashing_config.min_slash_amount
-            }
-            ResolutionType::DefenderWins => {
-                // Penalize the challenger (false dispute)
-                channel.config.fee_config.dispute_fee
-            }
-            _ => 0,
+        let (winner, loser, penalty) = match verdict.resolution_type {
+            // Penalize the defender
+            ResolutionType::ChallengerWins => (
+                Some(dispute.challenger),
+                verdict.defender,
+                channel.config.security_params.slashing_config.min_slash_amount,
+            ),
+            // Penalize the challenger (false dispute)
+            ResolutionType::DefenderWins => (
+                verdict.defender,
+                Some(dispute.challenger),
+                channel.config.fee_config.dispute_fee,
+            ),
+            _ => (None, None, 0),
         };
         
         Ok(DisputeResolution {
-            resolution_type,
-            winner: None, // Would be set based on resolution type
+            resolution_type: verdict.resolution_type,
+            winner,
+            loser,
             penalty,
             evidence: dispute.evidence.clone(),
             resolver: Pubkey::default(), // Would be set by caller
             resolved_at: current_time,
         })
     }
-    
-    // Helper methods for dispute analysis
-    fn validate_state_transition(_evidence: &[u8]) -> bool {
-        // Implementation would validate state transition
-        true
-    }
-    
-    fn detect_double_spending(_evidence: &[u8]) -> bool {
-        // Implementation would detect double spending
-        false
-    }
-    
-    fn verify_authorization(_evidence: &[u8]) -> bool {
-        // Implementation would verify operation authorization
-        true
-    }
-    
-    fn verify_balances(_evidence: &[u8]) -> bool {
-        // Implementation would verify balance calculations
-        true
-    }
-}
-
-// Helper function to calculate trading fees
-fn calculate_trading_fees(amount: u64, fee_rate: u16) -> u64 {
-    (amount * fee_rate as u64) / 10000
 }
 
 /// HFT execution result
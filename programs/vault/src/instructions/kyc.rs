@@ -1,6 +1,8 @@
 use anchor_lang::prelude::*;
 use crate::state::*;
 use crate::errors::VaultError;
+use crate::instructions::security_monitoring::create_security_alert;
+use crate::state::security_monitoring::{SecurityAlertStore, SecurityEventType, SecurityLevel, SecurityMonitor};
 
 #[derive(Accounts)]
 pub struct InitializeKYCProfile<'info> {
@@ -166,6 +168,40 @@ pub struct SuspendKYC<'info> {
     pub user: AccountInfo<'info>,
 }
 
+#[derive(Accounts)]
+pub struct FreezeAccount<'info> {
+    #[account(
+        mut,
+        seeds = [b"kyc_profile", user.key().as_ref()],
+        bump = kyc_profile.bump
+    )]
+    pub kyc_profile: Account<'info, KYCProfile>,
+    
+    #[account(
+        seeds = [b"multisig_wallet"],
+        bump = multisig_wallet.bump
+    )]
+    pub multisig_wallet: Account<'info, MultisigWallet>,
+    
+    pub compliance_officer: Signer<'info>,
+    
+    /// CHECK: User account being frozen or unfrozen
+    pub user: AccountInfo<'info>,
+}
+
+/// Incident-response freeze over many users; see `batch_freeze_accounts`
+/// for the remaining accounts
+#[derive(Accounts)]
+pub struct BatchFreezeAccounts<'info> {
+    #[account(
+        seeds = [b"multisig_wallet"],
+        bump = multisig_wallet.bump
+    )]
+    pub multisig_wallet: Account<'info, MultisigWallet>,
+    
+    pub compliance_officer: Signer<'info>,
+}
+
 #[derive(Accounts)]
 pub struct GenerateComplianceReport<'info> {
     #[account(
@@ -188,6 +224,243 @@ pub struct GenerateComplianceReport<'info> {
     pub system_program: Program<'info, System>,
 }
 
+/// Remaining accounts are the KYC profiles to include, in ascending user
+/// order
+#[derive(Accounts)]
+#[instruction(snapshot_id: u64)]
+pub struct CommitComplianceSnapshot<'info> {
+    #[account(
+        init,
+        payer = compliance_officer,
+        space = ComplianceSnapshot::LEN,
+        seeds = [b"compliance_snapshot".as_ref(), snapshot_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub compliance_snapshot: Account<'info, ComplianceSnapshot>,
+    
+    #[account(
+        seeds = [b"multisig_wallet"],
+        bump = multisig_wallet.bump
+    )]
+    pub multisig_wallet: Account<'info, MultisigWallet>,
+    
+    #[account(mut)]
+    pub compliance_officer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeCompliance<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = ComplianceConfig::LEN,
+        seeds = [b"compliance_config"],
+        bump
+    )]
+    pub compliance_config: Account<'info, ComplianceConfig>,
+    
+    #[account(
+        seeds = [b"multisig_wallet"],
+        bump = multisig_wallet.bump
+    )]
+    pub multisig_wallet: Account<'info, MultisigWallet>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateComplianceConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"compliance_config"],
+        bump = compliance_config.bump
+    )]
+    pub compliance_config: Account<'info, ComplianceConfig>,
+    
+    #[account(
+        seeds = [b"multisig_wallet"],
+        bump = multisig_wallet.bump
+    )]
+    pub multisig_wallet: Account<'info, MultisigWallet>,
+    
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateKYCStatus<'info> {
+    #[account(
+        mut,
+        seeds = [b"kyc_profile", user.key().as_ref()],
+        bump = kyc_profile.bump
+    )]
+    pub kyc_profile: Account<'info, KYCProfile>,
+    
+    #[account(
+        mut,
+        seeds = [b"compliance_config"],
+        bump = compliance_config.bump
+    )]
+    pub compliance_config: Account<'info, ComplianceConfig>,
+    
+    #[account(
+        seeds = [b"multisig_wallet"],
+        bump = multisig_wallet.bump
+    )]
+    pub multisig_wallet: Account<'info, MultisigWallet>,
+    
+    #[account(mut)]
+    pub compliance_officer: Signer<'info>,
+    
+    /// CHECK: User account being updated
+    pub user: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ValidateTransaction<'info> {
+    #[account(
+        mut,
+        seeds = [b"kyc_profile", user.key().as_ref()],
+        bump = kyc_profile.bump
+    )]
+    pub kyc_profile: Account<'info, KYCProfile>,
+    
+    #[account(
+        seeds = [b"compliance_config"],
+        bump = compliance_config.bump
+    )]
+    pub compliance_config: Account<'info, ComplianceConfig>,
+    
+    /// Required for transactions at or above the high-value threshold
+    #[account(
+        seeds = [b"screening_history", user.key().as_ref()],
+        bump = screening_history.bump
+    )]
+    pub screening_history: Option<Account<'info, ScreeningHistory>>,
+    
+    pub user: Signer<'info>,
+}
+
+/// Read-only view of what `validate_transaction` would decide; anyone can
+/// run it for any user
+#[derive(Accounts)]
+pub struct SimulateComplianceCheck<'info> {
+    #[account(
+        seeds = [b"kyc_profile", user.key().as_ref()],
+        bump = kyc_profile.bump
+    )]
+    pub kyc_profile: Account<'info, KYCProfile>,
+    
+    #[account(
+        seeds = [b"compliance_config"],
+        bump = compliance_config.bump
+    )]
+    pub compliance_config: Account<'info, ComplianceConfig>,
+    
+    #[account(
+        seeds = [b"screening_history", user.key().as_ref()],
+        bump = screening_history.bump
+    )]
+    pub screening_history: Option<Account<'info, ScreeningHistory>>,
+    
+    /// CHECK: User the transaction would be for
+    pub user: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct PerformAMLScreening<'info> {
+    #[account(
+        mut,
+        seeds = [b"kyc_profile", user.key().as_ref()],
+        bump = kyc_profile.bump
+    )]
+    pub kyc_profile: Account<'info, KYCProfile>,
+    
+    #[account(
+        init_if_needed,
+        payer = compliance_officer,
+        space = ScreeningHistory::LEN,
+        seeds = [b"screening_history", user.key().as_ref()],
+        bump
+    )]
+    pub screening_history: Account<'info, ScreeningHistory>,
+    
+    #[account(
+        mut,
+        seeds = [b"compliance_config"],
+        bump = compliance_config.bump
+    )]
+    pub compliance_config: Account<'info, ComplianceConfig>,
+    
+    #[account(
+        seeds = [b"multisig_wallet"],
+        bump = multisig_wallet.bump
+    )]
+    pub multisig_wallet: Account<'info, MultisigWallet>,
+    
+    #[account(mut)]
+    pub compliance_officer: Signer<'info>,
+    
+    /// CHECK: User account being screened
+    pub user: AccountInfo<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct GetScreeningHistory<'info> {
+    #[account(
+        seeds = [b"screening_history", screening_history.user.as_ref()],
+        bump = screening_history.bump
+    )]
+    pub screening_history: Account<'info, ScreeningHistory>,
+}
+
+/// Keeper crank over overdue compliance reviews; see
+/// `escalate_overdue_reviews` for the remaining accounts
+#[derive(Accounts)]
+pub struct EscalateOverdueReviews<'info> {
+    #[account(
+        mut,
+        seeds = [b"security_monitor"],
+        bump
+    )]
+    pub security_monitor: Account<'info, SecurityMonitor>,
+    
+    #[account(
+        mut,
+        seeds = [b"security_alerts", security_monitor.key().as_ref()],
+        bump
+    )]
+    pub alert_store: Account<'info, SecurityAlertStore>,
+    
+    pub keeper: Signer<'info>,
+}
+
+/// Off-chain AML screening of a user's address, e.g. from Chainalysis
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct AMLScreeningData {
+    pub screening: ComplianceScreening,
+    pub provider_id: u8,                        // Registered provider that produced the result
+    pub key_hash: [u8; 32],                     // Hash of the provider key it was produced under
+    pub risk_score: u8,                         // Provider risk score, 0-100
+    pub categories: ScreeningCategories,
+    pub address_hash: [u8; 32],                 // Hash of the screened address, as listed in the sanctions tree
+    pub sanctions_proof: Option<Vec<[u8; 32]>>, // Proof the address is on the sanctions list
+    pub sanctions_adjacent: bool,               // Close exposure to sanctioned parties without a direct match
+    pub high_risk_jurisdiction: bool,           // Tied to a high-risk jurisdiction
+}
+
+/// Kind of transaction checked by `validate_transaction`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub enum TransactionValidationType {
+    Commitment,
+    Payment,
+    Withdrawal,
+    Claim,
+}
+
 /// Initialize a KYC profile for a user
 pub fn initialize_kyc_profile(ctx: Context<InitializeKYCProfile>) -> Result<()> {
     let kyc_profile = &mut ctx.accounts.kyc_profile;
@@ -269,6 +542,7 @@ pub fn approve_kyc(
     ctx: Context<ApproveKYC>,
     tier: KYCTier,
     expiry_months: Option<u32>,
+    region: ComplianceRegion,
 ) -> Result<()> {
     let kyc_profile = &mut ctx.accounts.kyc_profile;
     let multisig_wallet = &ctx.accounts.multisig_wallet;
@@ -279,7 +553,7 @@ pub fn approve_kyc(
         return Err(VaultError::UnauthorizedComplianceOfficer.into());
     }
     
-    kyc_profile.approve_kyc(tier, compliance_officer, expiry_months)?;
+    kyc_profile.approve_kyc(tier, compliance_officer, expiry_months, region)?;
     
     Ok(())
 }
@@ -348,6 +622,144 @@ pub fn suspend_kyc(
     Ok(())
 }
 
+/// Block the actions in `scope` for a user (compliance officer only). No
+/// scope freezes everything; scopes add to an existing freeze.
+pub fn freeze_account(
+    ctx: Context<FreezeAccount>,
+    reason: String,
+    scope: Option<FreezeScope>,
+) -> Result<()> {
+    let kyc_profile = &mut ctx.accounts.kyc_profile;
+    let compliance_officer = ctx.accounts.compliance_officer.key();
+    
+    if !is_compliance_officer(&ctx.accounts.multisig_wallet, &compliance_officer)? {
+        return Err(VaultError::UnauthorizedComplianceOfficer.into());
+    }
+    
+    if reason.len() > KYCProfile::MAX_NOTES_LENGTH {
+        return Err(VaultError::ReasonTooLong.into());
+    }
+    
+    let now = Clock::get()?.unix_timestamp;
+    kyc_profile.freeze(&scope.unwrap_or_else(FreezeScope::full), compliance_officer, reason, now)?;
+    
+    emit!(AccountFreezeChanged {
+        user: kyc_profile.user,
+        freeze_scope: kyc_profile.freeze_scope,
+        changed_by: compliance_officer,
+        timestamp: now,
+    });
+    
+    Ok(())
+}
+
+/// Lift the actions in `scope` from a user's freeze (compliance officer
+/// only). No scope lifts the whole freeze.
+pub fn unfreeze_account(
+    ctx: Context<FreezeAccount>,
+    scope: Option<FreezeScope>,
+) -> Result<()> {
+    let kyc_profile = &mut ctx.accounts.kyc_profile;
+    let compliance_officer = ctx.accounts.compliance_officer.key();
+    
+    if !is_compliance_officer(&ctx.accounts.multisig_wallet, &compliance_officer)? {
+        return Err(VaultError::UnauthorizedComplianceOfficer.into());
+    }
+    
+    let now = Clock::get()?.unix_timestamp;
+    kyc_profile.unfreeze(&scope.unwrap_or_else(FreezeScope::full), compliance_officer, now)?;
+    
+    emit!(AccountFreezeChanged {
+        user: kyc_profile.user,
+        freeze_scope: kyc_profile.freeze_scope,
+        changed_by: compliance_officer,
+        timestamp: now,
+    });
+    
+    Ok(())
+}
+
+/// Freeze up to `MAX_BATCH_FREEZE` users at once during incident response
+/// (compliance officer only). Remaining accounts are their writable KYC
+/// profiles. An account that isn't a profile is skipped and reported as
+/// failed in `AccountsBatchFrozen`.
+pub fn batch_freeze_accounts<'info>(
+    ctx: Context<'_, '_, 'info, 'info, BatchFreezeAccounts<'info>>,
+    reason: String,
+    scope: Option<FreezeScope>,
+) -> Result<()> {
+    if reason.len() > KYCProfile::MAX_NOTES_LENGTH {
+        return Err(VaultError::ReasonTooLong.into());
+    }
+    batch_change_freeze(ctx, Some(reason), scope.unwrap_or_else(FreezeScope::full))
+}
+
+/// Lift `scope` from up to `MAX_BATCH_FREEZE` users' freezes at once
+/// (compliance officer only); see `batch_freeze_accounts`
+pub fn batch_unfreeze_accounts<'info>(
+    ctx: Context<'_, '_, 'info, 'info, BatchFreezeAccounts<'info>>,
+    scope: Option<FreezeScope>,
+) -> Result<()> {
+    batch_change_freeze(ctx, None, scope.unwrap_or_else(FreezeScope::full))
+}
+
+/// Freeze with `reason`, or unfreeze without one, each profile in the
+/// remaining accounts
+fn batch_change_freeze<'info>(
+    ctx: Context<'_, '_, 'info, 'info, BatchFreezeAccounts<'info>>,
+    reason: Option<String>,
+    scope: FreezeScope,
+) -> Result<()> {
+    let compliance_officer = ctx.accounts.compliance_officer.key();
+    
+    if !is_compliance_officer(&ctx.accounts.multisig_wallet, &compliance_officer)? {
+        return Err(VaultError::UnauthorizedComplianceOfficer.into());
+    }
+    let count = ctx.remaining_accounts.len();
+    if count == 0 || count > KYCProfile::MAX_BATCH_FREEZE {
+        return Err(VaultError::InvalidBatchSize.into());
+    }
+    
+    let now = Clock::get()?.unix_timestamp;
+    let mut profiles = Vec::with_capacity(count);
+    let mut succeeded = Vec::with_capacity(count);
+    for account in ctx.remaining_accounts {
+        profiles.push(account.key());
+        let Ok(mut kyc_profile) = Account::<KYCProfile>::try_from(account) else {
+            succeeded.push(false);
+            continue;
+        };
+        let changed = match &reason {
+            Some(reason) => kyc_profile.freeze(&scope, compliance_officer, reason.clone(), now).is_ok(),
+            None => kyc_profile.unfreeze(&scope, compliance_officer, now).is_ok(),
+        };
+        if changed {
+            emit!(AccountFreezeChanged {
+                user: kyc_profile.user,
+                freeze_scope: kyc_profile.freeze_scope,
+                changed_by: compliance_officer,
+                timestamp: now,
+            });
+            kyc_profile.exit(ctx.program_id)?;
+        }
+        succeeded.push(changed);
+    }
+    
+    msg!("{} of {} profiles {} by officer {}", succeeded.iter().filter(|ok| **ok).count(), count,
+         if reason.is_some() { "frozen" } else { "unfrozen" }, compliance_officer);
+    
+    emit!(AccountsBatchFrozen {
+        profiles,
+        succeeded,
+        frozen: reason.is_some(),
+        scope,
+        changed_by: compliance_officer,
+        timestamp: now,
+    });
+    
+    Ok(())
+}
+
 /// Generate compliance report (compliance officer only)
 pub fn generate_compliance_report(
     ctx: Context<GenerateComplianceReport>,
@@ -390,6 +802,448 @@ pub fn generate_compliance_report(
     Ok(())
 }
 
+/// Commit a Merkle root over the KYC profiles passed as remaining accounts
+/// (compliance officer only). Each leaf is the profile's canonical
+/// serialization; `ComplianceSnapshot::verify_profile_inclusion` later
+/// proves a profile handed to a regulator was part of it.
+pub fn commit_compliance_snapshot<'info>(
+    ctx: Context<'_, '_, 'info, 'info, CommitComplianceSnapshot<'info>>,
+    snapshot_id: u64,
+) -> Result<()> {
+    let compliance_officer = ctx.accounts.compliance_officer.key();
+    if !is_compliance_officer(&ctx.accounts.multisig_wallet, &compliance_officer)? {
+        return Err(VaultError::UnauthorizedComplianceOfficer.into());
+    }
+    if ctx.remaining_accounts.is_empty() {
+        return Err(VaultError::InvalidSnapshotProfiles.into());
+    }
+    
+    // Ascending user order keeps the leaves canonical and rules out duplicates
+    let mut leaves = Vec::with_capacity(ctx.remaining_accounts.len());
+    let mut last_user: Option<Pubkey> = None;
+    for account in ctx.remaining_accounts {
+        let kyc_profile = Account::<KYCProfile>::try_from(account)?;
+        if last_user.is_some_and(|last| last >= kyc_profile.user) {
+            return Err(VaultError::InvalidSnapshotProfiles.into());
+        }
+        last_user = Some(kyc_profile.user);
+        leaves.push(ComplianceSnapshot::leaf(&ComplianceSnapshot::profile_data(&kyc_profile)?));
+    }
+    
+    let now = Clock::get()?.unix_timestamp;
+    let compliance_snapshot = &mut ctx.accounts.compliance_snapshot;
+    compliance_snapshot.snapshot_id = snapshot_id;
+    compliance_snapshot.root = ComplianceSnapshot::merkle_root(&leaves);
+    compliance_snapshot.profile_count = u32::try_from(leaves.len()).map_err(|_| VaultError::ArithmeticOverflow)?;
+    compliance_snapshot.taken_at = now;
+    compliance_snapshot.taken_by = compliance_officer;
+    compliance_snapshot.bump = ctx.bumps.compliance_snapshot;
+    
+    emit!(ComplianceSnapshotCommitted {
+        snapshot_id,
+        root: compliance_snapshot.root,
+        profile_count: compliance_snapshot.profile_count,
+        taken_by: compliance_officer,
+        timestamp: now,
+    });
+    
+    msg!("Compliance snapshot {} committed over {} profiles", snapshot_id, compliance_snapshot.profile_count);
+    
+    Ok(())
+}
+
+/// Create the compliance config with the default KYC tier limits (admin only)
+pub fn initialize_compliance(ctx: Context<InitializeCompliance>) -> Result<()> {
+    if !is_compliance_admin(&ctx.accounts.multisig_wallet, &ctx.accounts.authority.key()) {
+        return Err(VaultError::UnauthorizedAccess.into());
+    }
+    
+    let compliance_config = &mut ctx.accounts.compliance_config;
+    compliance_config.tier_limits = ComplianceConfig::default_tier_limits();
+    compliance_config.audit_sequence = 0;
+    // Screenings need manual review until the multisig sets a sanctions root
+    compliance_config.sanctions_root = [0; 32];
+    compliance_config.sanctions_root_updated_at = 0;
+    compliance_config.sanctions_max_staleness = 0;
+    compliance_config.travel_rule_thresholds = ComplianceConfig::default_travel_rule_thresholds();
+    compliance_config.region_policies = ComplianceConfig::default_region_policies();
+    compliance_config.high_value_threshold = ComplianceConfig::DEFAULT_HIGH_VALUE_THRESHOLD;
+    compliance_config.screening_max_age = ComplianceConfig::DEFAULT_SCREENING_MAX_AGE;
+    (compliance_config.kyc_trigger_thresholds, compliance_config.kyc_trigger_grace_periods) =
+        ComplianceConfig::default_kyc_triggers();
+    // Screenings are rejected until the multisig registers a provider
+    compliance_config.screening_providers = Vec::new();
+    compliance_config.bump = ctx.bumps.compliance_config;
+    
+    msg!("Compliance config initialized");
+    
+    Ok(())
+}
+
+/// Replace the per-tier transaction limits (admin only)
+pub fn set_kyc_tier_limits(
+    ctx: Context<UpdateComplianceConfig>,
+    tier_limits: [TierLimits; 3],
+) -> Result<()> {
+    if !is_compliance_admin(&ctx.accounts.multisig_wallet, &ctx.accounts.authority.key()) {
+        return Err(VaultError::UnauthorizedAccess.into());
+    }
+    
+    ctx.accounts.compliance_config.set_tier_limits(tier_limits)?;
+    
+    msg!("KYC tier limits updated by {}", ctx.accounts.authority.key());
+    
+    Ok(())
+}
+
+/// Replace the per-region travel rule thresholds, indexed by
+/// `ComplianceRegion::index` (admin only). Zero lifts the rule for a region.
+pub fn set_travel_rule_thresholds(
+    ctx: Context<UpdateComplianceConfig>,
+    thresholds: [u64; ComplianceRegion::COUNT],
+) -> Result<()> {
+    if !is_compliance_admin(&ctx.accounts.multisig_wallet, &ctx.accounts.authority.key()) {
+        return Err(VaultError::UnauthorizedAccess.into());
+    }
+    
+    ctx.accounts.compliance_config.travel_rule_thresholds = thresholds;
+    
+    msg!("Travel rule thresholds updated by {}", ctx.accounts.authority.key());
+    
+    Ok(())
+}
+
+/// Replace the per-region KYC trigger thresholds and grace periods, indexed
+/// by `ComplianceRegion::index` (admin only). A zero threshold turns the
+/// trigger off for a region.
+pub fn set_kyc_triggers(
+    ctx: Context<UpdateComplianceConfig>,
+    thresholds: [u64; ComplianceRegion::COUNT],
+    grace_periods: [i64; ComplianceRegion::COUNT],
+) -> Result<()> {
+    if !is_compliance_admin(&ctx.accounts.multisig_wallet, &ctx.accounts.authority.key()) {
+        return Err(VaultError::UnauthorizedAccess.into());
+    }
+    
+    ctx.accounts.compliance_config.set_kyc_triggers(thresholds, grace_periods)?;
+    
+    msg!("KYC triggers updated by {}", ctx.accounts.authority.key());
+    
+    Ok(())
+}
+
+/// Set the USD value at which a transaction needs a screening no older than
+/// `max_age` seconds (admin only). A zero threshold lifts the requirement.
+pub fn set_screening_freshness(
+    ctx: Context<UpdateComplianceConfig>,
+    high_value_threshold: u64,
+    max_age: i64,
+) -> Result<()> {
+    if !is_compliance_admin(&ctx.accounts.multisig_wallet, &ctx.accounts.authority.key()) {
+        return Err(VaultError::UnauthorizedAccess.into());
+    }
+    if max_age <= 0 {
+        return Err(VaultError::InvalidScreeningMaxAge.into());
+    }
+    
+    let compliance_config = &mut ctx.accounts.compliance_config;
+    compliance_config.high_value_threshold = high_value_threshold;
+    compliance_config.screening_max_age = max_age;
+    
+    msg!("Screening freshness set to {}s above {} by {}",
+         max_age, high_value_threshold, ctx.accounts.authority.key());
+    
+    Ok(())
+}
+
+/// Change a user's KYC status and tier (compliance officer only). Tier
+/// changes need the verification backing them; every change is recorded
+/// as a `ComplianceAuditEntry`.
+pub fn update_kyc_status(
+    ctx: Context<UpdateKYCStatus>,
+    new_status: KYCStatus,
+    new_tier: KycTier,
+    verification: Option<KYCVerification>,
+) -> Result<()> {
+    let kyc_profile = &mut ctx.accounts.kyc_profile;
+    let compliance_officer = ctx.accounts.compliance_officer.key();
+    
+    // Verify compliance officer is authorized
+    if !is_compliance_officer(&ctx.accounts.multisig_wallet, &compliance_officer)? {
+        return Err(VaultError::UnauthorizedComplianceOfficer.into());
+    }
+    
+    let now = Clock::get()?.unix_timestamp;
+    let old_status = kyc_profile.status.clone();
+    let old_tier = kyc_profile.kyc_tier;
+    kyc_profile.update_kyc_status(new_status.clone(), new_tier, verification.as_ref(), compliance_officer, now)?;
+    
+    emit!(ComplianceAuditEntry {
+        sequence: ctx.accounts.compliance_config.next_audit_sequence()?,
+        user: kyc_profile.user,
+        compliance_officer,
+        old_status,
+        new_status,
+        old_tier,
+        new_tier,
+        document_hash: verification.map(|v| v.document_hash),
+        timestamp: now,
+    });
+    
+    msg!("KYC for user {} moved from {:?} to {:?} by officer {}",
+         kyc_profile.user, old_tier, new_tier, compliance_officer);
+    
+    Ok(())
+}
+
+/// Record an automated AML screening of a user (compliance officer only).
+/// The address is checked against the on-chain sanctions list first: a
+/// proven listing suspends the user, and a stale list leaves them for
+/// manual review. Hits raise the matching compliance flags; a clean
+/// screening never clears one.
+pub fn perform_aml_screening(
+    ctx: Context<PerformAMLScreening>,
+    screening_data: AMLScreeningData,
+) -> Result<()> {
+    let kyc_profile = &mut ctx.accounts.kyc_profile;
+    let compliance_officer = ctx.accounts.compliance_officer.key();
+    
+    // Verify compliance officer is authorized
+    if !is_compliance_officer(&ctx.accounts.multisig_wallet, &compliance_officer)? {
+        return Err(VaultError::UnauthorizedComplianceOfficer.into());
+    }
+    
+    let now = Clock::get()?.unix_timestamp;
+    ctx.accounts.compliance_config.check_screening_source(screening_data.provider_id, &screening_data.key_hash)?;
+    let verdict = ctx.accounts.compliance_config.sanctions_verdict(
+        &screening_data.address_hash,
+        screening_data.sanctions_proof.as_deref(),
+        now,
+    )?;
+    flag_overdue_review(kyc_profile, now);
+    let expires_at = screening_data.screening.expiry_date;
+    kyc_profile.record_aml_screening(screening_data.screening, verdict, now)?;
+    
+    // Record the screening as it stands after the sanctions verdict
+    let screening = kyc_profile.compliance_screening.as_ref().ok_or(VaultError::InvalidScreeningRecord)?;
+    let mut categories = screening_data.categories;
+    categories.sanctions |= screening.sanctions_match;
+    categories.pep |= screening.pep_match;
+    categories.adverse_media |= screening.adverse_media;
+    let screening_history = &mut ctx.accounts.screening_history;
+    if screening_history.user == Pubkey::default() {
+        screening_history.initialize(kyc_profile.user, ctx.bumps.screening_history);
+    }
+    let hits = [
+        (ComplianceFlagKind::Pep, screening.pep_match),
+        (ComplianceFlagKind::AdverseMedia, screening.adverse_media),
+        (ComplianceFlagKind::SanctionsAdjacent, screening_data.sanctions_adjacent),
+        (ComplianceFlagKind::HighRiskJurisdiction, screening_data.high_risk_jurisdiction),
+    ];
+    let sequence = screening_history.record(ScreeningRecord {
+        sequence: 0,
+        provider_id: screening_data.provider_id,
+        key_hash: screening_data.key_hash,
+        risk_score: screening_data.risk_score,
+        risk_level: screening.risk_level.clone(),
+        categories,
+        screened_at: now,
+        expires_at,
+        prev_hash: [0; 32],
+    })?;
+    for (flag, hit) in hits {
+        if hit && kyc_profile.compliance_flags.raise(flag, compliance_officer, now) {
+            emit!(ComplianceFlagAuditEntry {
+                sequence: ctx.accounts.compliance_config.next_audit_sequence()?,
+                user: kyc_profile.user,
+                flag,
+                active: true,
+                changed_by: compliance_officer,
+                justification: None,
+                timestamp: now,
+            });
+        }
+    }
+    
+    msg!("AML screening {} for user {} recorded by officer {}: {:?}",
+         sequence, kyc_profile.user, compliance_officer, verdict);
+    
+    Ok(())
+}
+
+/// Clear a compliance flag on a user (compliance officer only). The
+/// justification goes into the audit trail.
+pub fn clear_compliance_flag(
+    ctx: Context<UpdateKYCStatus>,
+    flag: ComplianceFlagKind,
+    justification: String,
+) -> Result<()> {
+    let kyc_profile = &mut ctx.accounts.kyc_profile;
+    let compliance_officer = ctx.accounts.compliance_officer.key();
+    
+    if !is_compliance_officer(&ctx.accounts.multisig_wallet, &compliance_officer)? {
+        return Err(VaultError::UnauthorizedComplianceOfficer.into());
+    }
+    
+    if justification.trim().is_empty() {
+        return Err(VaultError::JustificationRequired.into());
+    }
+    if justification.len() > KYCProfile::MAX_NOTES_LENGTH {
+        return Err(VaultError::ReasonTooLong.into());
+    }
+    
+    let now = Clock::get()?.unix_timestamp;
+    kyc_profile.compliance_flags.clear(flag, compliance_officer, now)?;
+    
+    emit!(ComplianceFlagAuditEntry {
+        sequence: ctx.accounts.compliance_config.next_audit_sequence()?,
+        user: kyc_profile.user,
+        flag,
+        active: false,
+        changed_by: compliance_officer,
+        justification: Some(justification),
+        timestamp: now,
+    });
+    
+    msg!("{:?} flag cleared for user {} by officer {}", flag, kyc_profile.user, compliance_officer);
+    
+    Ok(())
+}
+
+/// Record a completed periodic compliance review of a user (compliance
+/// officer only). Schedules the next review by the user's risk level and
+/// lifts restrictions from an overdue one.
+pub fn perform_compliance_review(ctx: Context<PerformAMLScreening>) -> Result<()> {
+    let kyc_profile = &mut ctx.accounts.kyc_profile;
+    let compliance_officer = ctx.accounts.compliance_officer.key();
+    
+    // Verify compliance officer is authorized
+    if !is_compliance_officer(&ctx.accounts.multisig_wallet, &compliance_officer)? {
+        return Err(VaultError::UnauthorizedComplianceOfficer.into());
+    }
+    
+    let now = Clock::get()?.unix_timestamp;
+    kyc_profile.complete_compliance_review(compliance_officer, now);
+    
+    msg!("Compliance review for user {} completed by officer {}, next due at {}",
+         kyc_profile.user, compliance_officer, kyc_profile.next_review_due);
+    
+    Ok(())
+}
+
+/// A user's kept AML screenings, oldest first. Fails if the hash chain
+/// doesn't verify.
+pub fn get_screening_history(ctx: Context<GetScreeningHistory>) -> Result<Vec<ScreeningRecord>> {
+    let screening_history = &ctx.accounts.screening_history;
+    screening_history.verify_chain()?;
+    Ok(screening_history.ordered())
+}
+
+/// Keeper crank escalating overdue periodic reviews. Remaining accounts are
+/// writable KYC profiles. A review overdue by more than 30 days raises a
+/// High compliance alert; past 60 days the user is also held to Tier0
+/// limits until an officer completes the review.
+pub fn escalate_overdue_reviews<'info>(
+    ctx: Context<'_, '_, 'info, 'info, EscalateOverdueReviews<'info>>,
+) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    
+    let mut escalated = 0;
+    for account in ctx.remaining_accounts {
+        let mut kyc_profile = Account::<KYCProfile>::try_from(account)?;
+        let Some(stage) = kyc_profile.escalate_overdue_review(now) else {
+            continue;
+        };
+        
+        let description = match stage {
+            ReviewEscalation::Restricted => format!(
+                "Compliance review for user {} overdue since {}; restricted to Tier0 limits",
+                kyc_profile.user, kyc_profile.next_review_due
+            ),
+            _ => format!(
+                "Compliance review for user {} overdue since {}",
+                kyc_profile.user, kyc_profile.next_review_due
+            ),
+        };
+        create_security_alert(
+            &mut ctx.accounts.security_monitor,
+            &mut ctx.accounts.alert_store,
+            SecurityEventType::ComplianceAlert,
+            Some(kyc_profile.user),
+            description,
+            SecurityLevel::High,
+            Vec::new(),
+        )?;
+        kyc_profile.exit(ctx.program_id)?;
+        escalated += 1;
+    }
+    
+    msg!("Escalated {} overdue compliance reviews", escalated);
+    
+    Ok(())
+}
+
+/// Check a transaction worth `amount` USD (6 decimals) against the user's
+/// KYC tier limits and record it towards their monthly and lifetime volume.
+/// Outbound transfers over the region's travel rule threshold need the hash
+/// of their travel rule data, and the user's compliance flags may send them
+/// to manual review or demand a recent screening.
+pub fn validate_transaction(
+    ctx: Context<ValidateTransaction>,
+    transaction_type: TransactionValidationType,
+    amount: u64,
+    destination: Option<String>,
+    travel_rule_data_hash: Option<[u8; 32]>,
+) -> Result<()> {
+    let kyc_profile = &mut ctx.accounts.kyc_profile;
+    let compliance_config = &ctx.accounts.compliance_config;
+    let now = Clock::get()?.unix_timestamp;
+    flag_overdue_review(kyc_profile, now);
+    
+    check_transaction_compliance(
+        kyc_profile,
+        compliance_config,
+        ctx.accounts.screening_history.as_deref(),
+        &transaction_type,
+        amount,
+        travel_rule_data_hash,
+        now,
+    ).enforce()?;
+    kyc_profile.record_transaction(compliance_config, amount, now)?;
+    
+    msg!("{:?} of {} to {:?} validated for user {} at {:?}",
+         transaction_type, amount, destination, kyc_profile.user, kyc_profile.effective_tier(now));
+    
+    Ok(())
+}
+
+/// Run a transaction through the same checks as `validate_transaction`
+/// without consuming limits or writing state. Returns every reason it
+/// would be refused and what would lift each one.
+pub fn simulate_compliance_check(
+    ctx: Context<SimulateComplianceCheck>,
+    transaction_type: TransactionValidationType,
+    amount: u64,
+    destination: Option<String>,
+    travel_rule_data_hash: Option<[u8; 32]>,
+) -> Result<ComplianceCheck> {
+    let now = Clock::get()?.unix_timestamp;
+    let check = check_transaction_compliance(
+        &ctx.accounts.kyc_profile,
+        &ctx.accounts.compliance_config,
+        ctx.accounts.screening_history.as_deref(),
+        &transaction_type,
+        amount,
+        travel_rule_data_hash,
+        now,
+    );
+    
+    msg!("Simulated {:?} of {} to {:?} for user {}: {:?}",
+         transaction_type, amount, destination, ctx.accounts.kyc_profile.user, check.blocking_reasons);
+    
+    Ok(check)
+}
+
 /// Integrate KYC check with BTC commitment
 pub fn validate_btc_commitment_kyc(
     kyc_profile: &KYCProfile,
@@ -436,6 +1290,62 @@ pub fn validate_payment_kyc(
     Ok(())
 }
 
+/// Integrate KYC check with state channel settlement. The channel's purpose
+/// must be allowed by the region policy for the settler's region.
+pub fn validate_channel_settlement_kyc(
+    compliance_config: &ComplianceConfig,
+    kyc_profile: &KYCProfile,
+    purpose: ChannelPurpose,
+) -> Result<()> {
+    if kyc_profile.status != KYCStatus::Approved {
+        return Err(VaultError::KYCRequired.into());
+    }
+    
+    // Without a recorded region we can't tell which restrictions apply
+    if kyc_profile.region.is_none() {
+        return Err(VaultError::KYCRequired.into());
+    }
+    if purpose == ChannelPurpose::HighFrequencyTrading {
+        check_region_policy(compliance_config, Some(kyc_profile), RegionFeature::HftChannel)?;
+    }
+    
+    Ok(())
+}
+
+/// Load each participant's KYC profile from `infos`, in participant order
+pub fn load_participant_kyc_profiles<'info>(
+    infos: &'info [AccountInfo<'info>],
+    participants: &[Pubkey],
+    program_id: &Pubkey,
+) -> Result<Vec<Account<'info, KYCProfile>>> {
+    require!(infos.len() == participants.len(), VaultError::MissingRequiredAccount);
+    infos.iter().zip(participants).map(|(info, participant)| {
+        let (expected, _) = Pubkey::find_program_address(
+            &[b"kyc_profile", participant.as_ref()],
+            program_id,
+        );
+        if info.key() != expected {
+            return Err(VaultError::UnauthorizedAccess.into());
+        }
+        Account::<KYCProfile>::try_from(info)
+    }).collect()
+}
+
+/// Check a region-gated feature against the policy for `kyc_profile`'s
+/// region; users without a profile or recorded region get the strictest one.
+/// Flags the profile if its periodic review is overdue.
+pub fn check_region_policy(
+    compliance_config: &ComplianceConfig,
+    kyc_profile: Option<&KYCProfile>,
+    feature: RegionFeature,
+) -> Result<()> {
+    if let Some(kyc_profile) = kyc_profile {
+        flag_overdue_review(kyc_profile, Clock::get()?.unix_timestamp);
+    }
+    let region = kyc_profile.and_then(|profile| profile.region.as_ref());
+    compliance_config.region_policy(region).check(feature)
+}
+
 /// Chainalysis API integration for compliance screening
 pub fn perform_chainalysis_screening(
     user_address: &Pubkey,
@@ -471,6 +1381,40 @@ pub fn perform_chainalysis_screening(
 
 // Helper functions
 
+/// The compliance checks shared by `validate_transaction` and
+/// `simulate_compliance_check`
+fn check_transaction_compliance(
+    kyc_profile: &KYCProfile,
+    compliance_config: &ComplianceConfig,
+    screening_history: Option<&ScreeningHistory>,
+    transaction_type: &TransactionValidationType,
+    amount: u64,
+    travel_rule_data_hash: Option<[u8; 32]>,
+    now: i64,
+) -> ComplianceCheck {
+    let action = match transaction_type {
+        TransactionValidationType::Commitment => FrozenAction::Commit,
+        TransactionValidationType::Payment => FrozenAction::Payments,
+        TransactionValidationType::Withdrawal => FrozenAction::Decommit,
+        TransactionValidationType::Claim => FrozenAction::Claims,
+    };
+    let attached = travel_rule_data_hash.is_some_and(|hash| hash != [0; 32]);
+    let latest_screening = screening_history.and_then(|history| history.latest_current(compliance_config, now));
+    kyc_profile.check_transaction(compliance_config, action, amount, attached, latest_screening, now)
+}
+
+/// Flag a profile past its periodic review date for the escalation crank
+fn flag_overdue_review(kyc_profile: &KYCProfile, now: i64) {
+    if kyc_profile.review_overdue(now) {
+        emit!(ComplianceReviewOverdue {
+            user: kyc_profile.user,
+            review_due: kyc_profile.next_review_due,
+            escalation: kyc_profile.review_escalation,
+            timestamp: now,
+        });
+    }
+}
+
 fn is_compliance_officer(multisig_wallet: &MultisigWallet, officer: &Pubkey) -> Result<bool> {
     // Check if the officer is an authorized signer with compliance role
     let is_authorized = multisig_wallet.signers
@@ -484,6 +1428,12 @@ fn is_compliance_officer(multisig_wallet: &MultisigWallet, officer: &Pubkey) ->
     Ok(is_authorized)
 }
 
+fn is_compliance_admin(multisig_wallet: &MultisigWallet, authority: &Pubkey) -> bool {
+    multisig_wallet.signers
+        .iter()
+        .any(|signer| signer.pubkey == *authority && signer.is_active && signer.role == SignerRole::Admin)
+}
+
 // Simulation functions for Chainalysis integration (replace with actual API calls in production)
 
 fn simulate_risk_assessment(btc_address: &str) -> RiskLevel {
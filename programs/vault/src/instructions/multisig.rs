@@ -1,6 +1,14 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use solana_program::compute_units::sol_remaining_compute_units;
+use solana_program::sysvar;
+use std::cmp::Reverse;
 use crate::state::*;
+use crate::state::security_monitoring::{SecurityEvent, SecurityEventType};
 use crate::errors::VaultError;
+use crate::crypto::{Ed25519Verifier, HsmAttestationVerifier};
 
 #[derive(Accounts)]
 pub struct InitializeMultisigWallet<'info> {
@@ -13,6 +21,19 @@ pub struct InitializeMultisigWallet<'info> {
     )]
     pub multisig_wallet: Account<'info, MultisigWallet>,
     
+    #[account(
+        init,
+        payer = authority,
+        space = EmergencyState::LEN,
+        seeds = [b"emergency_state"],
+        bump
+    )]
+    pub emergency_state: Account<'info, EmergencyState>,
+    
+    /// CHECK: Deserialized as a multisig wallet in the handler. A parent is
+    /// another deployment's wallet, so it is not owned by this program.
+    pub parent_wallet: Option<UncheckedAccount<'info>>,
+    
     #[account(mut)]
     pub authority: Signer<'info>,
     pub system_program: Program<'info, System>,
@@ -67,6 +88,40 @@ pub struct SignMultisigTransaction<'info> {
     
     #[account(mut)]
     pub signer: Signer<'info>,
+    
+    /// CHECK: Address is constrained to the instructions sysvar
+    #[account(address = sysvar::instructions::ID)]
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+/// Approvals signed offline, submitted by anyone. The submitter pays fees
+/// only and need not be a signer of the wallet.
+#[derive(Accounts)]
+#[instruction(transaction_id: u32)]
+pub struct SubmitAggregatedApprovals<'info> {
+    #[account(
+        mut,
+        seeds = [b"multisig_wallet"],
+        bump = multisig_wallet.bump
+    )]
+    pub multisig_wallet: Account<'info, MultisigWallet>,
+    
+    #[account(
+        mut,
+        seeds = [
+            b"multisig_transaction",
+            multisig_wallet.key().as_ref(),
+            &transaction_id.to_le_bytes()
+        ],
+        bump = multisig_transaction.bump
+    )]
+    pub multisig_transaction: Account<'info, MultisigTransaction>,
+    
+    pub submitter: Signer<'info>,
+    
+    /// CHECK: Address is constrained to the instructions sysvar
+    #[account(address = sysvar::instructions::ID)]
+    pub instructions_sysvar: AccountInfo<'info>,
 }
 
 #[derive(Accounts)]
@@ -89,12 +144,151 @@ pub struct ExecuteMultisigTransaction<'info> {
     )]
     pub multisig_transaction: Account<'info, MultisigTransaction>,
     
+    /// Required only for UpdateRewardRates actions
+    #[account(
+        mut,
+        seeds = [b"reward_pool"],
+        bump = reward_pool.bump
+    )]
+    pub reward_pool: Option<Account<'info, RewardPool>>,
+    
+    /// Required only for OracleFeedChange actions
+    #[account(
+        mut,
+        seeds = [b"oracle"],
+        bump
+    )]
+    pub oracle_data: Option<Account<'info, OracleData>>,
+    
+    /// Required only for UpdateAuthConfig actions
+    #[account(
+        mut,
+        seeds = [b"auth_config"],
+        bump = auth_config.bump
+    )]
+    pub auth_config: Option<Account<'info, AuthConfig>>,
+    
+    /// Required only for FreezeAccount actions
+    #[account(
+        mut,
+        seeds = [b"kyc_profile", kyc_profile.user.as_ref()],
+        bump = kyc_profile.bump
+    )]
+    pub kyc_profile: Option<Account<'info, KYCProfile>>,
+    
+    /// Token account owned by the multisig wallet, for TreasuryTransfer actions
+    #[account(mut)]
+    pub treasury_token_account: Option<Account<'info, TokenAccount>>,
+    
+    /// Required only for TreasuryTransfer actions
+    #[account(mut)]
+    pub destination_token_account: Option<Account<'info, TokenAccount>>,
+    
+    pub token_program: Option<Program<'info, Token>>,
+    
+    /// Required only for TreasuryTransfer actions, which honour a treasury pause
+    #[account(
+        seeds = [b"emergency_state"],
+        bump = emergency_state.bump
+    )]
+    pub emergency_state: Option<Account<'info, EmergencyState>>,
+    
+    /// Required only for PaymentApproval and TopUpPaymentFloat actions
+    #[account(
+        mut,
+        seeds = [b"payment_system"],
+        bump = payment_system.bump
+    )]
+    pub payment_system: Option<Account<'info, PaymentSystem>>,
+    
+    /// Required only for SetSanctionsRoot, SetRegionPolicy and screening
+    /// provider actions
+    #[account(
+        mut,
+        seeds = [b"compliance_config"],
+        bump = compliance_config.bump
+    )]
+    pub compliance_config: Option<Account<'info, ComplianceConfig>>,
+    
+    /// Required only for ApproveTreasuryStream actions
+    #[account(
+        mut,
+        seeds = [b"payment_stream", payment_stream.funder.as_ref(), payment_stream.stream_id.to_le_bytes().as_ref()],
+        bump = payment_stream.bump
+    )]
+    pub payment_stream: Option<Account<'info, PaymentStream>>,
+    
+    /// Required only for ApproveTreasuryStream actions
+    #[account(
+        mut,
+        seeds = [b"treasury"],
+        bump = treasury.bump
+    )]
+    pub treasury: Option<Account<'info, Treasury>>,
+    
+    /// Required only for fast path transfers, which are audit logged
+    #[account(
+        mut,
+        seeds = [b"security_monitor"],
+        bump
+    )]
+    pub security_monitor: Option<Account<'info, SecurityMonitor>>,
+    
+    /// The security monitor's audit store, required alongside it
+    #[account(mut)]
+    pub audit_store: Option<Account<'info, AuditTrailStore>>,
+    
     #[account(mut)]
     pub executor: Signer<'info>,
 }
 
+/// The wallet's guardian vetoing an approved transaction. The security
+/// event log records proposers a veto flags for review.
+#[derive(Accounts)]
+#[instruction(transaction_id: u32)]
+pub struct VetoMultisigTransaction<'info> {
+    #[account(
+        mut,
+        seeds = [b"multisig_wallet"],
+        bump = multisig_wallet.bump
+    )]
+    pub multisig_wallet: Account<'info, MultisigWallet>,
+    
+    #[account(
+        mut,
+        seeds = [
+            b"multisig_transaction",
+            multisig_wallet.key().as_ref(),
+            &transaction_id.to_le_bytes()
+        ],
+        bump = multisig_transaction.bump
+    )]
+    pub multisig_transaction: Account<'info, MultisigTransaction>,
+    
+    #[account(
+        mut,
+        seeds = [b"security_monitor"],
+        bump
+    )]
+    pub security_monitor: Account<'info, SecurityMonitor>,
+    
+    #[account(
+        mut,
+        seeds = [b"security_events", security_monitor.key().as_ref()],
+        bump
+    )]
+    pub event_log: Account<'info, SecurityEventLog>,
+    
+    #[account(
+        constraint = multisig_wallet.guardian == Some(guardian.key()) @ VaultError::UnauthorizedSigner
+    )]
+    pub guardian: Signer<'info>,
+}
+
+/// Remaining accounts are the writable transactions to consider. Only
+/// transactions whose action needs no accounts of its own are executed.
 #[derive(Accounts)]
-pub struct RotateMultisigKeys<'info> {
+pub struct ExecuteApprovedBatch<'info> {
     #[account(
         mut,
         seeds = [b"multisig_wallet"],
@@ -102,8 +296,215 @@ pub struct RotateMultisigKeys<'info> {
     )]
     pub multisig_wallet: Account<'info, MultisigWallet>,
     
+    /// Required only when the batch holds UpdateRewardRates actions
+    #[account(
+        mut,
+        seeds = [b"reward_pool"],
+        bump = reward_pool.bump
+    )]
+    pub reward_pool: Option<Account<'info, RewardPool>>,
+    
+    /// Required only when the batch holds OracleFeedChange actions
+    #[account(
+        mut,
+        seeds = [b"oracle"],
+        bump
+    )]
+    pub oracle_data: Option<Account<'info, OracleData>>,
+    
+    /// Required only when the batch holds UpdateAuthConfig actions
+    #[account(
+        mut,
+        seeds = [b"auth_config"],
+        bump = auth_config.bump
+    )]
+    pub auth_config: Option<Account<'info, AuthConfig>>,
+    
     #[account(mut)]
-    pub authority: Signer<'info>,
+    pub executor: Signer<'info>,
+}
+
+/// Same accounts as execution, read only. Remaining accounts are as for
+/// execution.
+#[derive(Accounts)]
+#[instruction(transaction_id: u32)]
+pub struct SimulateMultisigTransaction<'info> {
+    #[account(
+        seeds = [b"multisig_wallet"],
+        bump = multisig_wallet.bump
+    )]
+    pub multisig_wallet: Account<'info, MultisigWallet>,
+    
+    #[account(
+        seeds = [
+            b"multisig_transaction",
+            multisig_wallet.key().as_ref(),
+            &transaction_id.to_le_bytes()
+        ],
+        bump = multisig_transaction.bump
+    )]
+    pub multisig_transaction: Account<'info, MultisigTransaction>,
+    
+    #[account(
+        seeds = [b"reward_pool"],
+        bump = reward_pool.bump
+    )]
+    pub reward_pool: Option<Account<'info, RewardPool>>,
+    
+    #[account(
+        seeds = [b"oracle"],
+        bump
+    )]
+    pub oracle_data: Option<Account<'info, OracleData>>,
+    
+    #[account(
+        seeds = [b"auth_config"],
+        bump = auth_config.bump
+    )]
+    pub auth_config: Option<Account<'info, AuthConfig>>,
+    
+    #[account(
+        seeds = [b"kyc_profile", kyc_profile.user.as_ref()],
+        bump = kyc_profile.bump
+    )]
+    pub kyc_profile: Option<Account<'info, KYCProfile>>,
+    
+    pub treasury_token_account: Option<Account<'info, TokenAccount>>,
+    
+    pub destination_token_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        seeds = [b"emergency_state"],
+        bump = emergency_state.bump
+    )]
+    pub emergency_state: Option<Account<'info, EmergencyState>>,
+    
+    #[account(
+        seeds = [b"payment_system"],
+        bump = payment_system.bump
+    )]
+    pub payment_system: Option<Account<'info, PaymentSystem>>,
+    
+    #[account(
+        seeds = [b"compliance_config"],
+        bump = compliance_config.bump
+    )]
+    pub compliance_config: Option<Account<'info, ComplianceConfig>>,
+    
+    #[account(
+        seeds = [b"payment_stream", payment_stream.funder.as_ref(), payment_stream.stream_id.to_le_bytes().as_ref()],
+        bump = payment_stream.bump
+    )]
+    pub payment_stream: Option<Account<'info, PaymentStream>>,
+    
+    #[account(
+        seeds = [b"treasury"],
+        bump = treasury.bump
+    )]
+    pub treasury: Option<Account<'info, Treasury>>,
+}
+
+/// Accounts a transaction's action may act on. Each is needed only by the
+/// actions that touch it.
+struct ActionTargets<'a, 'info> {
+    reward_pool: Option<&'a mut Account<'info, RewardPool>>,
+    oracle_data: Option<&'a mut Account<'info, OracleData>>,
+    auth_config: Option<&'a mut Account<'info, AuthConfig>>,
+    kyc_profile: Option<&'a mut Account<'info, KYCProfile>>,
+    treasury_token_account: Option<&'a Account<'info, TokenAccount>>,
+    destination_token_account: Option<&'a Account<'info, TokenAccount>>,
+    token_program: Option<&'a Program<'info, Token>>,
+    emergency_state: Option<&'a Account<'info, EmergencyState>>,
+    payment_system: Option<&'a mut Account<'info, PaymentSystem>>,
+    compliance_config: Option<&'a mut Account<'info, ComplianceConfig>>,
+    payment_stream: Option<&'a mut Account<'info, PaymentStream>>,
+    treasury: Option<&'a mut Account<'info, Treasury>>,
+    remaining_accounts: &'info [AccountInfo<'info>],
+}
+
+#[derive(Accounts)]
+pub struct GetMultisigActivity<'info> {
+    #[account(
+        seeds = [b"multisig_wallet"],
+        bump = multisig_wallet.bump
+    )]
+    pub multisig_wallet: Account<'info, MultisigWallet>,
+}
+
+#[derive(Accounts)]
+pub struct CancelMultisigTransaction<'info> {
+    #[account(
+        mut,
+        seeds = [b"multisig_wallet"],
+        bump = multisig_wallet.bump
+    )]
+    pub multisig_wallet: Account<'info, MultisigWallet>,
+    
+    #[account(
+        mut,
+        seeds = [
+            b"multisig_transaction",
+            multisig_wallet.key().as_ref(),
+            &multisig_transaction.transaction_id.to_le_bytes()
+        ],
+        bump = multisig_transaction.bump
+    )]
+    pub multisig_transaction: Account<'info, MultisigTransaction>,
+    
+    pub proposer: Signer<'info>,
+}
+
+/// Remaining accounts are the writable proposals to sweep
+#[derive(Accounts)]
+pub struct CancelExpiredTransactions<'info> {
+    #[account(
+        seeds = [b"multisig_wallet"],
+        bump = multisig_wallet.bump
+    )]
+    pub multisig_wallet: Account<'info, MultisigWallet>,
+    
+    pub signer: Signer<'info>,
+}
+
+/// A parent wallet replacing its child's signers. The parent signs through
+/// its own execute flow, as a Custom action invoking this program.
+#[derive(Accounts)]
+pub struct ParentRotateChildSigners<'info> {
+    #[account(
+        mut,
+        seeds = [b"multisig_wallet"],
+        bump = multisig_wallet.bump
+    )]
+    pub multisig_wallet: Account<'info, MultisigWallet>,
+    
+    #[account(
+        constraint = multisig_wallet.parent_wallet == Some(parent_wallet.key()) @ VaultError::UnauthorizedSigner
+    )]
+    pub parent_wallet: Signer<'info>,
+}
+
+/// A parent wallet setting or clearing its child's emergency scopes, signed
+/// as for `ParentRotateChildSigners`
+#[derive(Accounts)]
+pub struct ParentOverrideEmergency<'info> {
+    #[account(
+        mut,
+        seeds = [b"multisig_wallet"],
+        bump = multisig_wallet.bump
+    )]
+    pub multisig_wallet: Account<'info, MultisigWallet>,
+    
+    #[account(
+        mut,
+        seeds = [b"emergency_state"],
+        bump = emergency_state.bump
+    )]
+    pub emergency_state: Account<'info, EmergencyState>,
+    
+    #[account(
+        constraint = multisig_wallet.parent_wallet == Some(parent_wallet.key()) @ VaultError::UnauthorizedSigner
+    )]
+    pub parent_wallet: Signer<'info>,
 }
 
 #[derive(Accounts)]
@@ -115,6 +516,13 @@ pub struct EmergencyAction<'info> {
     )]
     pub multisig_wallet: Account<'info, MultisigWallet>,
     
+    #[account(
+        mut,
+        seeds = [b"emergency_state"],
+        bump = emergency_state.bump
+    )]
+    pub emergency_state: Account<'info, EmergencyState>,
+    
     #[account(mut)]
     pub emergency_signer: Signer<'info>,
 }
@@ -123,7 +531,9 @@ pub struct EmergencyAction<'info> {
 pub fn initialize_multisig_wallet(
     ctx: Context<InitializeMultisigWallet>,
     signers: Vec<SignerInfo>,
+    threshold: u8,
     hsm_enabled: bool,
+    hsm_quorum: u8,
 ) -> Result<()> {
     let multisig_wallet = &mut ctx.accounts.multisig_wallet;
     
@@ -133,10 +543,17 @@ pub fn initialize_multisig_wallet(
         return Err(VaultError::UnauthorizedAccess.into());
     }
 
-    multisig_wallet.initialize(signers, hsm_enabled, ctx.bumps.multisig_wallet)?;
+    multisig_wallet.initialize(signers, threshold, hsm_enabled, hsm_quorum, ctx.bumps.multisig_wallet)?;
+    ctx.accounts.emergency_state.initialize(multisig_wallet.key(), ctx.bumps.emergency_state);
+    if let Some(parent_info) = &ctx.accounts.parent_wallet {
+        let parent = MultisigWallet::try_deserialize(&mut &parent_info.try_borrow_data()?[..])
+            .map_err(|_| VaultError::InvalidParentWallet)?;
+        let own_key = multisig_wallet.key();
+        multisig_wallet.set_parent(own_key, parent_info.key(), &parent)?;
+    }
     
-    msg!("Multisig wallet initialized with {} signers, HSM enabled: {}", 
-         multisig_wallet.signers.len(), hsm_enabled);
+    msg!("Multisig wallet initialized with {}-of-{} signers, HSM enabled: {}", 
+         threshold, multisig_wallet.signers.len(), hsm_enabled);
     
     Ok(())
 }
@@ -147,6 +564,7 @@ pub fn propose_transaction(
     transaction_type: TransactionType,
     priority: TransactionPriority,
     transaction_data: Vec<u8>,
+    expires_at: Option<i64>,
 ) -> Result<()> {
     let multisig_wallet = &mut ctx.accounts.multisig_wallet;
     let multisig_transaction = &mut ctx.accounts.multisig_transaction;
@@ -163,7 +581,7 @@ pub fn propose_transaction(
     }
 
     // Get required threshold for this transaction type and priority
-    let required_signatures = multisig_wallet.get_required_threshold(&transaction_type, &priority);
+    let required_weight = multisig_wallet.get_required_threshold(&transaction_type, &priority);
 
     // Initialize transaction
     multisig_transaction.initialize(
@@ -173,17 +591,28 @@ pub fn propose_transaction(
         transaction_type,
         priority.clone(),
         transaction_data,
-        required_signatures,
+        required_weight,
         ctx.bumps.multisig_transaction,
     )?;
+    if let Some(expires_at) = expires_at {
+        let proposed_at = multisig_transaction.created_at;
+        multisig_transaction.set_expiry(expires_at, proposed_at)?;
+    }
 
     // Validate transaction data
     multisig_transaction.validate_transaction_data()?;
+    multisig_transaction.seal_digest();
+    if let Some(new_threshold) = multisig_transaction.new_threshold() {
+        MultisigWallet::validate_signers(&multisig_wallet.signers, new_threshold, &multisig_wallet.required_roles)?;
+    }
 
     // Increment transaction counter
     multisig_wallet.transaction_count = multisig_wallet.transaction_count
         .checked_add(1).unwrap();
 
+    let proposed_at = multisig_transaction.created_at;
+    log_activity(multisig_wallet, ActivityKind::Propose, proposer_key, multisig_transaction.transaction_id, proposed_at);
+
     msg!("Transaction {} proposed by {} with priority {:?}", 
          multisig_transaction.transaction_id, proposer_key, &priority);
 
@@ -194,7 +623,7 @@ pub fn propose_transaction(
 pub fn sign_transaction(
     ctx: Context<SignMultisigTransaction>,
     signature_data: [u8; 64],
-    hsm_signature: Option<Vec<u8>>,
+    hsm_attestation: Option<HsmAttestation>,
     signature_type: SignatureType,
 ) -> Result<()> {
     let multisig_wallet = &mut ctx.accounts.multisig_wallet;
@@ -212,7 +641,7 @@ pub fn sign_transaction(
     }
 
     // Check if transaction is still valid
-    if multisig_transaction.executed || multisig_transaction.cancelled {
+    if multisig_transaction.is_settled() {
         return Err(VaultError::TransactionAlreadyExecuted.into());
     }
 
@@ -220,14 +649,31 @@ pub fn sign_transaction(
         return Err(VaultError::SecurityViolation.into());
     }
 
-    // Validate HSM signature if HSM is enabled
-    if multisig_wallet.hsm_enabled && signature_type == SignatureType::HSM {
-        if hsm_signature.is_none() {
-            return Err(VaultError::SecurityViolation.into());
-        }
-        
-        // In production, validate HSM signature against Yubico HSM
-        validate_hsm_signature(&signer_key, &signature_data, &hsm_signature.as_ref().unwrap())?;
+    // The signature must be over the sealed digest of this exact proposal,
+    // checked by the Ed25519 program instruction preceding this one. An HSM
+    // attestation is checked by the same instruction, right after it.
+    multisig_transaction.check_digest()?;
+    let proposal_digest = multisig_transaction.proposal_digest;
+    match &hsm_attestation {
+        Some(attestation) => HsmAttestationVerifier::verify_with_signer(
+            &ctx.accounts.instructions_sysvar,
+            &signer_key,
+            &signature_data,
+            multisig_wallet.hsm_key_for(&signer_key),
+            attestation,
+            &proposal_digest,
+        )?,
+        None => {
+            if signature_type == SignatureType::HSM {
+                return Err(VaultError::MissingHsmAttestation.into());
+            }
+            Ed25519Verifier::verify_participant_signatures(
+                &ctx.accounts.instructions_sysvar,
+                &[signer_key],
+                &[signature_data.to_vec()],
+                &Ed25519Verifier::multisig_proposal_message(&proposal_digest),
+            ).map_err(|_| VaultError::InvalidProposalSignature)?;
+        },
     }
 
     // Create signature
@@ -235,16 +681,13 @@ pub fn sign_transaction(
     let multisig_signature = MultisigSignature {
         signer: signer_key,
         signature: signature_data,
-        hsm_signature,
+        digest: proposal_digest,
+        hsm_attestation,
         signed_at: clock.unix_timestamp,
         signature_type: signature_type.clone(),
     };
 
-    // Add signature to transaction
-    multisig_transaction.add_signature(multisig_signature)?;
-
-    // Update signer usage statistics
-    multisig_wallet.update_signer_usage(&signer_key)?;
+    apply_signature(multisig_wallet, multisig_transaction, multisig_signature)?;
 
     msg!("Transaction {} signed by {} (type: {:?})", 
          multisig_transaction.transaction_id, signer_key, &signature_type);
@@ -252,55 +695,159 @@ pub fn sign_transaction(
     Ok(())
 }
 
-/// Execute a multisig transaction
-pub fn execute_transaction(ctx: Context<ExecuteMultisigTransaction>) -> Result<()> {
+/// Apply approvals signed offline over the proposal digest and the wallet's
+/// approval nonce. Anyone may submit the bundle. Every signature must be
+/// covered, in bundle order, by the Ed25519 program instruction preceding
+/// this one, and every approval must carry the current nonce. A bundle with
+/// any approval from a key that is not an active signer or lacks the
+/// transaction's role is rejected outright; approvals from signers who have
+/// already signed are skipped. The nonce advances only when an approval was
+/// applied, so a bundle of throwaway or duplicate approvals can't invalidate
+/// the offline approvals still being collected.
+pub fn submit_aggregated_approvals(
+    ctx: Context<SubmitAggregatedApprovals>,
+    transaction_id: u32,
+    approvals: Vec<OfflineApproval>,
+) -> Result<()> {
     let multisig_wallet = &mut ctx.accounts.multisig_wallet;
     let multisig_transaction = &mut ctx.accounts.multisig_transaction;
 
-    // Validate executor is authorized signer
-    let executor_key = ctx.accounts.executor.key();
-    if !multisig_wallet.signers.iter().any(|s| s.pubkey == executor_key && s.is_active) {
-        return Err(VaultError::UnauthorizedSigner.into());
+    if approvals.is_empty() || approvals.len() > MultisigWallet::MAX_SIGNERS {
+        return Err(VaultError::InvalidAllocation.into());
     }
-
-    // Check if transaction can be executed
-    if multisig_transaction.executed || multisig_transaction.cancelled {
+    if multisig_transaction.is_settled() {
         return Err(VaultError::TransactionAlreadyExecuted.into());
     }
-
     if multisig_transaction.is_expired()? {
         return Err(VaultError::SecurityViolation.into());
     }
 
-    if !multisig_transaction.has_enough_signatures() {
-        return Err(VaultError::MultisigThresholdNotMet.into());
+    let nonce = multisig_wallet.approval_nonce;
+    if approvals.iter().any(|a| a.nonce != nonce) {
+        return Err(VaultError::StaleApprovalNonce.into());
     }
 
-    // Execute transaction based on type
-    let execution_result = match multisig_transaction.transaction_type {
-        TransactionType::TreasuryTransfer => {
-            execute_treasury_transfer(&multisig_transaction.transaction_data)?
-        },
-        TransactionType::StakingOperation => {
-            execute_staking_operation(&multisig_transaction.transaction_data)?
-        },
-        TransactionType::RewardDistribution => {
-            execute_reward_distribution(&multisig_transaction.transaction_data)?
-        },
-        TransactionType::ConfigUpdate => {
-            execute_config_update(&multisig_transaction.transaction_data)?
-        },
-        TransactionType::EmergencyAction => {
-            execute_emergency_action(&multisig_transaction.transaction_data)?
-        },
-        TransactionType::KeyRotation => {
-            execute_key_rotation(multisig_wallet, &multisig_transaction.transaction_data)?
-        },
+    multisig_transaction.check_digest()?;
+    let proposal_digest = multisig_transaction.proposal_digest;
+    let message = Ed25519Verifier::multisig_offline_approval_message(&proposal_digest, nonce);
+    let signers: Vec<Pubkey> = approvals.iter().map(|a| a.signer).collect();
+    let signatures: Vec<Vec<u8>> = approvals.iter().map(|a| a.signature.to_vec()).collect();
+    Ed25519Verifier::verify_participant_signatures(&ctx.accounts.instructions_sysvar, &signers, &signatures, &message)
+        .map_err(|_| VaultError::InvalidProposalSignature)?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let mut applied = Vec::new();
+    let mut skipped = Vec::new();
+    for approval in approvals {
+        let eligible = multisig_wallet.signers.iter().any(|s| s.pubkey == approval.signer && s.is_active)
+            && multisig_wallet.validate_signer_role(&approval.signer, &multisig_transaction.transaction_type)?;
+        if !eligible {
+            return Err(VaultError::UnauthorizedSigner.into());
+        }
+        if multisig_transaction.signatures.iter().any(|s| s.signer == approval.signer) {
+            skipped.push(approval.signer);
+            continue;
+        }
+
+        apply_signature(multisig_wallet, multisig_transaction, MultisigSignature {
+            signer: approval.signer,
+            signature: approval.signature,
+            digest: proposal_digest,
+            hsm_attestation: None,
+            signed_at: now,
+            signature_type: SignatureType::Offline,
+        })?;
+        applied.push(approval.signer);
+    }
+    if !applied.is_empty() {
+        multisig_wallet.approval_nonce = nonce.checked_add(1).ok_or(VaultError::ArithmeticOverflow)?;
+    }
+
+    msg!("Transaction {} received {} offline approvals, {} skipped",
+         transaction_id, applied.len(), skipped.len());
+    emit!(AggregatedApprovalsSubmitted {
+        multisig: multisig_wallet.key(),
+        transaction_id,
+        nonce,
+        applied,
+        skipped,
+    });
+
+    Ok(())
+}
+
+/// Execute a multisig transaction. Remaining accounts are the target
+/// program's accounts for Custom actions, or the wallet's writable pending
+/// proposals for signer removals and replacements.
+///
+/// A treasury transfer short of full approval or its timelock runs on the
+/// fast path instead when the wallet has one configured and the transfer
+/// qualifies. Each approver is charged against their daily limit and the
+/// execution is written to the audit trail.
+pub fn execute_transaction<'info>(
+    ctx: Context<'_, '_, 'info, 'info, ExecuteMultisigTransaction<'info>>,
+) -> Result<()> {
+    let multisig_wallet = &mut ctx.accounts.multisig_wallet;
+    let multisig_transaction = &mut ctx.accounts.multisig_transaction;
+
+    // Validate executor is authorized signer
+    let executor_key = ctx.accounts.executor.key();
+    if !multisig_wallet.signers.iter().any(|s| s.pubkey == executor_key && s.is_active) {
+        return Err(VaultError::UnauthorizedSigner.into());
+    }
+
+    let now = Clock::get()?.unix_timestamp;
+    multisig_transaction.sync_required_weight(multisig_wallet);
+    let fast_path = fast_path_amount(multisig_wallet, multisig_transaction, now);
+    let approvers = multisig_transaction.counted_signers(multisig_wallet);
+    if let Some(amount) = fast_path {
+        multisig_wallet.charge_fast_path(&approvers, amount, now)?;
+    } else {
+        multisig_transaction.check_executable(multisig_wallet, now)?;
+
+        // A lowered threshold may be met without a new signature. The approval
+        // has to be persisted for a timelock to start, so execution stops there.
+        let timelock = multisig_wallet.timelock_for(&multisig_transaction.transaction_type);
+        if multisig_transaction.mark_approved_if_ready(multisig_wallet, now) {
+            emit_timelock_started(multisig_transaction, timelock);
+            if timelock > 0 {
+                msg!("Transaction {} approved, timelock started", multisig_transaction.transaction_id);
+                return Ok(());
+            }
+        }
+        multisig_transaction.check_timelock_elapsed(now, timelock)?;
+    }
+
+    let mut targets = ActionTargets {
+        reward_pool: ctx.accounts.reward_pool.as_mut(),
+        oracle_data: ctx.accounts.oracle_data.as_mut(),
+        auth_config: ctx.accounts.auth_config.as_mut(),
+        kyc_profile: ctx.accounts.kyc_profile.as_mut(),
+        treasury_token_account: ctx.accounts.treasury_token_account.as_ref(),
+        destination_token_account: ctx.accounts.destination_token_account.as_ref(),
+        token_program: ctx.accounts.token_program.as_ref(),
+        emergency_state: ctx.accounts.emergency_state.as_ref(),
+        payment_system: ctx.accounts.payment_system.as_mut(),
+        compliance_config: ctx.accounts.compliance_config.as_mut(),
+        payment_stream: ctx.accounts.payment_stream.as_mut(),
+        treasury: ctx.accounts.treasury.as_mut(),
+        remaining_accounts: ctx.remaining_accounts,
     };
+    let execution_result = run_transaction(multisig_wallet, multisig_transaction, &mut targets, now)?;
+
+    if let Some(amount) = fast_path {
+        let security_monitor = ctx.accounts.security_monitor.as_mut().ok_or(VaultError::MissingRequiredAccount)?;
+        let audit_store = ctx.accounts.audit_store.as_mut().ok_or(VaultError::MissingRequiredAccount)?;
+        if audit_store.monitor != security_monitor.key() {
+            return Err(VaultError::ActionAccountMismatch.into());
+        }
+        record_fast_path_audit(security_monitor, audit_store, executor_key, multisig_transaction, &approvers, amount)?;
+    }
 
     // Mark transaction as executed
     multisig_transaction.mark_executed(Some(execution_result.clone()))?;
     multisig_wallet.executed_count = multisig_wallet.executed_count.checked_add(1).unwrap();
+    log_activity(multisig_wallet, ActivityKind::Execute, executor_key, multisig_transaction.transaction_id, now);
 
     msg!("Transaction {} executed successfully: {}", 
          multisig_transaction.transaction_id, execution_result);
@@ -308,38 +855,258 @@ pub fn execute_transaction(ctx: Context<ExecuteMultisigTransaction>) -> Result<(
     Ok(())
 }
 
-/// Rotate multisig keys with HSM integration
-pub fn rotate_keys(
-    ctx: Context<RotateMultisigKeys>,
-    new_signers: Vec<SignerInfo>,
+/// Dry-run a transaction against current state, returning whether execution
+/// would succeed and what it would change. Runs the same precondition checks
+/// as execution and mutates nothing.
+pub fn simulate_transaction<'info>(
+    ctx: Context<'_, '_, 'info, 'info, SimulateMultisigTransaction<'info>>,
+    transaction_id: u32,
+) -> Result<SimulationResult> {
+    let now = Clock::get()?.unix_timestamp;
+    let targets = ActionTargets {
+        reward_pool: ctx.accounts.reward_pool.as_mut(),
+        oracle_data: ctx.accounts.oracle_data.as_mut(),
+        auth_config: ctx.accounts.auth_config.as_mut(),
+        kyc_profile: ctx.accounts.kyc_profile.as_mut(),
+        treasury_token_account: ctx.accounts.treasury_token_account.as_ref(),
+        destination_token_account: ctx.accounts.destination_token_account.as_ref(),
+        token_program: None,
+        emergency_state: ctx.accounts.emergency_state.as_ref(),
+        payment_system: ctx.accounts.payment_system.as_mut(),
+        compliance_config: ctx.accounts.compliance_config.as_mut(),
+        payment_stream: ctx.accounts.payment_stream.as_mut(),
+        treasury: ctx.accounts.treasury.as_mut(),
+        remaining_accounts: ctx.remaining_accounts,
+    };
+
+    let result = match preview_transaction(&ctx.accounts.multisig_wallet, &ctx.accounts.multisig_transaction, &targets, now) {
+        Ok(effects) => SimulationResult::succeeded(effects),
+        Err(error) => SimulationResult::failed(&error),
+    };
+    msg!("Simulated transaction {}: would succeed: {}", transaction_id, result.would_succeed);
+    Ok(result)
+}
+
+/// Execute up to `max_count` of the given transactions that are approved
+/// and past their timelock, most urgent first. A transaction whose
+/// execution errors is marked failed and its effects rolled back, and the
+/// batch carries on. The batch stops early when compute runs low.
+pub fn execute_approved_batch<'info>(
+    ctx: Context<'_, '_, 'info, 'info, ExecuteApprovedBatch<'info>>,
+    max_count: u8,
 ) -> Result<()> {
     let multisig_wallet = &mut ctx.accounts.multisig_wallet;
-    let authority_key = ctx.accounts.authority.key();
 
-    // Validate authority has admin role
-    let authority_signer = multisig_wallet.signers.iter()
-        .find(|s| s.pubkey == authority_key && s.is_active)
-        .ok_or(VaultError::UnauthorizedAccess)?;
+    let executor_key = ctx.accounts.executor.key();
+    if !multisig_wallet.signers.iter().any(|s| s.pubkey == executor_key && s.is_active) {
+        return Err(VaultError::UnauthorizedSigner.into());
+    }
+
+    let now = Clock::get()?.unix_timestamp;
+    let mut ready: Vec<Account<'info, MultisigTransaction>> = Vec::new();
+    for info in ctx.remaining_accounts.iter() {
+        if ready.iter().any(|t| t.key() == info.key()) {
+            continue;
+        }
+        let mut multisig_transaction = Account::<MultisigTransaction>::try_from(info)?;
+        if multisig_transaction.multisig != multisig_wallet.key() {
+            return Err(VaultError::UnauthorizedAccess.into());
+        }
+        multisig_transaction.sync_required_weight(multisig_wallet);
+        if multisig_transaction.is_ready_to_execute(multisig_wallet, now) && multisig_transaction.is_batchable() {
+            ready.push(multisig_transaction);
+        }
+    }
+    ready.sort_by_key(|t| (Reverse(t.priority.clone() as u8), t.transaction_id));
+
+    let mut executed = Vec::new();
+    let mut failed = Vec::new();
+    let mut budget_exhausted = false;
+    for mut multisig_transaction in ready.into_iter().take(max_count as usize) {
+        if sol_remaining_compute_units() < MultisigTransaction::BATCH_COMPUTE_RESERVE {
+            budget_exhausted = true;
+            break;
+        }
+
+        let wallet_snapshot = (**multisig_wallet).clone();
+        let reward_pool_snapshot = ctx.accounts.reward_pool.as_ref().map(|p| (**p).clone());
+        let oracle_snapshot = ctx.accounts.oracle_data.as_ref().map(|o| (**o).clone());
+        let auth_config_snapshot = ctx.accounts.auth_config.as_ref().map(|c| (**c).clone());
+        let mut targets = ActionTargets {
+            reward_pool: ctx.accounts.reward_pool.as_mut(),
+            oracle_data: ctx.accounts.oracle_data.as_mut(),
+            auth_config: ctx.accounts.auth_config.as_mut(),
+            kyc_profile: None,
+            treasury_token_account: None,
+            destination_token_account: None,
+            token_program: None,
+            emergency_state: None,
+            payment_system: None,
+            compliance_config: None,
+            payment_stream: None,
+            treasury: None,
+            remaining_accounts: &[],
+        };
+        match run_transaction(multisig_wallet, &multisig_transaction, &mut targets, now) {
+            Ok(execution_result) => {
+                multisig_transaction.mark_executed(Some(execution_result))?;
+                multisig_wallet.executed_count = multisig_wallet.executed_count
+                    .checked_add(1)
+                    .ok_or(VaultError::ArithmeticOverflow)?;
+                log_activity(multisig_wallet, ActivityKind::Execute, executor_key, multisig_transaction.transaction_id, now);
+                executed.push(multisig_transaction.transaction_id);
+            },
+            Err(error) => {
+                multisig_wallet.set_inner(wallet_snapshot);
+                if let (Some(reward_pool), Some(snapshot)) = (ctx.accounts.reward_pool.as_mut(), reward_pool_snapshot) {
+                    reward_pool.set_inner(snapshot);
+                }
+                if let (Some(oracle_data), Some(snapshot)) = (ctx.accounts.oracle_data.as_mut(), oracle_snapshot) {
+                    oracle_data.set_inner(snapshot);
+                }
+                if let (Some(auth_config), Some(snapshot)) = (ctx.accounts.auth_config.as_mut(), auth_config_snapshot) {
+                    auth_config.set_inner(snapshot);
+                }
+                multisig_transaction.mark_failed(&error.to_string(), now);
+                failed.push(multisig_transaction.transaction_id);
+            },
+        }
+        multisig_transaction.exit(ctx.program_id)?;
+    }
+
+    msg!("Batch executed {} transactions, {} failed", executed.len(), failed.len());
+    emit!(MultisigBatchExecuted {
+        multisig: multisig_wallet.key(),
+        executed,
+        failed,
+        budget_exhausted,
+    });
+
+    Ok(())
+}
+
+/// Page through the wallet's activity log from sequence number `cursor`
+pub fn get_multisig_activity(ctx: Context<GetMultisigActivity>, cursor: u64) -> Result<ActivityPage> {
+    Ok(ctx.accounts.multisig_wallet.activity_page(cursor))
+}
 
-    if authority_signer.role != SignerRole::Admin {
+/// Withdraw an un-executed proposal. Only its proposer may do so.
+pub fn cancel_transaction(ctx: Context<CancelMultisigTransaction>, reason: String) -> Result<()> {
+    let multisig_transaction = &mut ctx.accounts.multisig_transaction;
+    let proposer_key = ctx.accounts.proposer.key();
+
+    if multisig_transaction.proposer != proposer_key {
         return Err(VaultError::UnauthorizedAccess.into());
     }
 
-    // Check if key rotation is needed or forced
-    if !multisig_wallet.needs_key_rotation()? {
-        msg!("Warning: Key rotation performed before required interval");
+    multisig_transaction.cancel(reason)?;
+    let now = Clock::get()?.unix_timestamp;
+    log_activity(&mut ctx.accounts.multisig_wallet, ActivityKind::Cancel, proposer_key, multisig_transaction.transaction_id, now);
+
+    emit!(MultisigTransactionCancelled {
+        multisig: multisig_transaction.multisig,
+        transaction_id: multisig_transaction.transaction_id,
+        proposer: proposer_key,
+    });
+
+    msg!("Transaction {} cancelled by its proposer", multisig_transaction.transaction_id);
+
+    Ok(())
+}
+
+/// Veto an approved transaction while its timelock runs. The third veto of
+/// one proposer's transactions within 30 days flags their signer entry for
+/// review and logs a security event.
+pub fn veto_transaction(
+    ctx: Context<VetoMultisigTransaction>,
+    transaction_id: u32,
+    reason: String,
+) -> Result<()> {
+    let multisig_wallet = &mut ctx.accounts.multisig_wallet;
+    let multisig_transaction = &mut ctx.accounts.multisig_transaction;
+    let now = Clock::get()?.unix_timestamp;
+
+    let timelock = multisig_wallet.timelock_for(&multisig_transaction.transaction_type);
+    multisig_transaction.veto(reason, now, timelock)?;
+
+    let proposer = multisig_transaction.proposer;
+    let proposer_flagged = multisig_wallet.record_veto(&proposer, now);
+    if proposer_flagged {
+        let security_monitor = &mut ctx.accounts.security_monitor;
+        security_monitor.event_counter = security_monitor.event_counter
+            .checked_add(1)
+            .ok_or(VaultError::ArithmeticOverflow)?;
+
+        let event_type = SecurityEventType::SignerFlaggedForReview;
+        let security_level = crate::instructions::security_monitoring::determine_security_level(&event_type);
+        let event = SecurityEvent::new(
+            security_monitor.event_counter,
+            event_type,
+            Some(proposer),
+            format!("Multisig signer {} flagged for review after repeated vetoes", proposer),
+        )
+        .with_transaction(transaction_id.to_string(), None)
+        .with_security_level(security_level);
+        ctx.accounts.event_log.record(event, now);
+    }
+
+    let guardian = ctx.accounts.guardian.key();
+    log_activity(multisig_wallet, ActivityKind::Veto, guardian, transaction_id, now);
+
+    emit!(MultisigTransactionVetoed {
+        multisig: multisig_wallet.key(),
+        transaction_id,
+        proposer,
+        guardian,
+        proposer_flagged,
+    });
+
+    msg!("Transaction {} vetoed by the guardian", transaction_id);
+
+    Ok(())
+}
+
+/// Archive every passed proposal that expired before it could execute
+pub fn cancel_expired_transactions<'info>(
+    ctx: Context<'_, '_, 'info, 'info, CancelExpiredTransactions<'info>>,
+) -> Result<()> {
+    let multisig_wallet = &ctx.accounts.multisig_wallet;
+    let signer_key = ctx.accounts.signer.key();
+
+    // Any active signer may sweep
+    if !multisig_wallet.signers.iter().any(|s| s.pubkey == signer_key && s.is_active) {
+        return Err(VaultError::UnauthorizedSigner.into());
     }
 
-    // Perform key rotation
-    multisig_wallet.rotate_keys(new_signers)?;
+    let now = Clock::get()?.unix_timestamp;
+    let mut archived = 0;
+    for info in ctx.remaining_accounts.iter() {
+        let mut multisig_transaction = Account::<MultisigTransaction>::try_from(info)?;
+        if multisig_transaction.multisig != multisig_wallet.key() {
+            return Err(VaultError::UnauthorizedAccess.into());
+        }
+        if !multisig_transaction.archive_if_expired(now) {
+            continue;
+        }
+        multisig_transaction.exit(ctx.program_id)?;
+
+        emit!(MultisigTransactionExpired {
+            multisig: multisig_transaction.multisig,
+            transaction_id: multisig_transaction.transaction_id,
+            proposer: multisig_transaction.proposer,
+            expires_at: multisig_transaction.deadline(),
+        });
+        archived += 1;
+    }
 
-    msg!("Multisig keys rotated successfully");
+    msg!("Archived {} expired transactions", archived);
 
     Ok(())
 }
 
-/// Activate emergency mode
-pub fn activate_emergency_mode(ctx: Context<EmergencyAction>) -> Result<()> {
+/// Activate emergency mode, pausing the given scopes on top of any
+/// already paused
+pub fn activate_emergency_mode(ctx: Context<EmergencyAction>, scopes: EmergencyScopes) -> Result<()> {
     let multisig_wallet = &mut ctx.accounts.multisig_wallet;
     let signer_key = ctx.accounts.emergency_signer.key();
 
@@ -352,13 +1119,12 @@ pub fn activate_emergency_mode(ctx: Context<EmergencyAction>) -> Result<()> {
         return Err(VaultError::UnauthorizedAccess.into());
     }
 
-    multisig_wallet.activate_emergency_mode()?;
-
-    Ok(())
+    pause_scopes(multisig_wallet, &mut ctx.accounts.emergency_state, &scopes, signer_key)
 }
 
-/// Deactivate emergency mode
-pub fn deactivate_emergency_mode(ctx: Context<EmergencyAction>) -> Result<()> {
+/// Resume the given scopes. Emergency mode stays on until the last paused
+/// scope is cleared.
+pub fn deactivate_emergency_mode(ctx: Context<EmergencyAction>, scopes: EmergencyScopes) -> Result<()> {
     let multisig_wallet = &mut ctx.accounts.multisig_wallet;
     let signer_key = ctx.accounts.emergency_signer.key();
 
@@ -371,95 +1137,726 @@ pub fn deactivate_emergency_mode(ctx: Context<EmergencyAction>) -> Result<()> {
         return Err(VaultError::UnauthorizedAccess.into());
     }
 
-    multisig_wallet.deactivate_emergency_mode()?;
+    resume_scopes(multisig_wallet, &mut ctx.accounts.emergency_state, &scopes, signer_key)
+}
+
+/// Replace a child wallet's signers on its parent's authority. The new set
+/// must meet the child's threshold, roles and HSM requirements. The child's
+/// own signers change theirs one at a time through KeyRotation proposals.
+pub fn parent_rotate_child_signers(
+    ctx: Context<ParentRotateChildSigners>,
+    new_signers: Vec<SignerInfo>,
+) -> Result<()> {
+    let multisig_wallet = &mut ctx.accounts.multisig_wallet;
+    multisig_wallet.rotate_keys(new_signers)?;
 
+    let parent_wallet = ctx.accounts.parent_wallet.key();
+    msg!("Signers rotated by parent wallet {}", parent_wallet);
+    emit!(ChildSignersRotated {
+        multisig: multisig_wallet.key(),
+        parent_wallet,
+        signers: multisig_wallet.signers.iter().map(|s| s.pubkey).collect(),
+    });
+
+    Ok(())
+}
+
+/// Pause or resume a child wallet's emergency scopes on its parent's
+/// authority, whatever the child's own signers have set
+pub fn parent_override_emergency(
+    ctx: Context<ParentOverrideEmergency>,
+    scopes: EmergencyScopes,
+    pause: bool,
+) -> Result<()> {
+    let parent_wallet = ctx.accounts.parent_wallet.key();
+    let multisig_wallet = &mut ctx.accounts.multisig_wallet;
+    let emergency_state = &mut ctx.accounts.emergency_state;
+    if pause {
+        pause_scopes(multisig_wallet, emergency_state, &scopes, parent_wallet)
+    } else {
+        resume_scopes(multisig_wallet, emergency_state, &scopes, parent_wallet)
+    }
+}
+
+/// Pause scopes on top of any already paused and enter emergency mode
+fn pause_scopes(
+    multisig_wallet: &mut MultisigWallet,
+    emergency_state: &mut EmergencyState,
+    scopes: &EmergencyScopes,
+    updated_by: Pubkey,
+) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    emergency_state.activate(scopes, updated_by, now)?;
+    multisig_wallet.activate_emergency_mode()?;
+    emit_emergency_scopes_changed(emergency_state, updated_by, now);
+    Ok(())
+}
+
+/// Resume scopes, leaving emergency mode once none remain paused
+fn resume_scopes(
+    multisig_wallet: &mut MultisigWallet,
+    emergency_state: &mut EmergencyState,
+    scopes: &EmergencyScopes,
+    updated_by: Pubkey,
+) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let still_active = emergency_state.clear(scopes, updated_by, now)?;
+    if !still_active {
+        multisig_wallet.deactivate_emergency_mode()?;
+    }
+    emit_emergency_scopes_changed(emergency_state, updated_by, now);
     Ok(())
 }
 
+/// Add a verified signature, approving the transaction if it now meets
+/// threshold, and record it against its signer
+fn apply_signature(
+    multisig_wallet: &mut Account<MultisigWallet>,
+    multisig_transaction: &mut MultisigTransaction,
+    multisig_signature: MultisigSignature,
+) -> Result<()> {
+    let signer_key = multisig_signature.signer;
+    let signed_at = multisig_signature.signed_at;
+    multisig_transaction.add_signature(multisig_signature)?;
+    multisig_transaction.sync_required_weight(multisig_wallet);
+    if multisig_transaction.mark_approved_if_ready(multisig_wallet, signed_at) {
+        let timelock = multisig_wallet.timelock_for(&multisig_transaction.transaction_type);
+        emit_timelock_started(multisig_transaction, timelock);
+    }
+
+    // Update signer usage statistics
+    multisig_wallet.update_signer_usage(&signer_key)?;
+    log_activity(multisig_wallet, ActivityKind::Sign, signer_key, multisig_transaction.transaction_id, signed_at);
+    Ok(())
+}
+
+/// Record an activity log entry and emit it for off-chain followers
+fn log_activity(
+    multisig_wallet: &mut Account<MultisigWallet>,
+    kind: ActivityKind,
+    actor: Pubkey,
+    transaction_id: u32,
+    now: i64,
+) {
+    let sequence = multisig_wallet.record_activity(kind, actor, transaction_id, now);
+    emit!(MultisigActivityRecorded {
+        multisig: multisig_wallet.key(),
+        sequence,
+        kind,
+        actor,
+        transaction_id,
+        timestamp: now,
+    });
+}
+
+fn emit_emergency_scopes_changed(emergency_state: &EmergencyState, updated_by: Pubkey, now: i64) {
+    emit!(EmergencyScopesChanged {
+        multisig: emergency_state.multisig,
+        scopes: emergency_state.scopes,
+        updated_by,
+        timestamp: now,
+    });
+}
+
+/// Announce an approval so watchers can react during the timelock.
+/// Emergency actions skip the announcement along with the timelock.
+fn emit_timelock_started(multisig_transaction: &MultisigTransaction, timelock: i64) {
+    if multisig_transaction.transaction_type == TransactionType::EmergencyAction {
+        return;
+    }
+    let approved_at = multisig_transaction.approved_at.unwrap_or_default();
+    emit!(TimelockStarted {
+        multisig: multisig_transaction.multisig,
+        transaction_id: multisig_transaction.transaction_id,
+        transaction_type: multisig_transaction.transaction_type.clone(),
+        approved_at,
+        executable_at: approved_at.saturating_add(timelock),
+    });
+}
+
 // Transaction execution functions
 
-fn execute_treasury_transfer(transaction_data: &[u8]) -> Result<String> {
-    // Parse treasury transfer data
-    if transaction_data.len() < 40 {
-        return Err(VaultError::InvalidAllocation.into());
+/// Run an approved transaction's effect, returning its result message
+fn run_transaction<'info>(
+    multisig_wallet: &mut Account<'info, MultisigWallet>,
+    multisig_transaction: &MultisigTransaction,
+    targets: &mut ActionTargets<'_, 'info>,
+    now: i64,
+) -> Result<String> {
+    match multisig_transaction.new_threshold() {
+        Some(new_threshold) => {
+            multisig_wallet.change_threshold(new_threshold)?;
+            Ok(format!("Threshold changed to {}", new_threshold))
+        },
+        None => {
+            let action = multisig_transaction.decode_action()?;
+            run_action(multisig_wallet, multisig_transaction.transaction_id, action, targets, now)
+        },
+    }
+}
+
+/// Perform a decoded action. Anything signed on the multisig's behalf is
+/// signed by the wallet PDA.
+fn run_action<'info>(
+    multisig_wallet: &mut Account<'info, MultisigWallet>,
+    transaction_id: u32,
+    action: MultisigAction,
+    targets: &mut ActionTargets<'_, 'info>,
+    now: i64,
+) -> Result<String> {
+    let bump = [multisig_wallet.bump];
+    let seeds: &[&[u8]] = &[b"multisig_wallet", &bump];
+
+    match action {
+        MultisigAction::TreasuryTransfer { to, mint, amount } => {
+            let (source, destination) = treasury_transfer_accounts(multisig_wallet.key(), targets, &to, &mint, amount)?;
+            let token_program = targets.token_program.ok_or(VaultError::MissingRequiredAccount)?;
+            token::transfer(
+                CpiContext::new_with_signer(
+                    token_program.to_account_info(),
+                    Transfer {
+                        from: source.to_account_info(),
+                        to: destination.to_account_info(),
+                        authority: multisig_wallet.to_account_info(),
+                    },
+                    &[seeds],
+                ),
+                amount,
+            )?;
+            Ok(format!("Transferred {} of mint {} to {}", amount, mint, to))
+        },
+        MultisigAction::UpdateRewardRates { bps } => {
+            let reward_pool = targets.reward_pool.as_deref_mut().ok_or(VaultError::MissingRequiredAccount)?;
+            crate::instructions::rewards::apply_reward_rate_change(reward_pool, transaction_id, bps, now)
+        },
+        MultisigAction::OracleFeedChange { btc_usd_feed } => {
+            let oracle_data = targets.oracle_data.as_deref_mut().ok_or(VaultError::MissingRequiredAccount)?;
+            oracle_data.change_feed(btc_usd_feed);
+            Ok(format!("Oracle feed changed to {}", btc_usd_feed))
+        },
+        MultisigAction::FreezeAccount { user, reason, scope } => {
+            let kyc_profile = targets.kyc_profile.as_deref_mut().ok_or(VaultError::MissingRequiredAccount)?;
+            check_freeze_target(kyc_profile, &user)?;
+            let scope = scope.unwrap_or_else(FreezeScope::full);
+            kyc_profile.freeze(&scope, multisig_wallet.key(), reason, now)?;
+            emit!(AccountFreezeChanged {
+                user,
+                freeze_scope: kyc_profile.freeze_scope,
+                changed_by: multisig_wallet.key(),
+                timestamp: now,
+            });
+            Ok(format!("Account {} frozen: {:?}", user, kyc_profile.freeze_scope))
+        },
+        MultisigAction::UpdateAuthConfig {
+            require_2fa_globally,
+            session_timeout_min,
+            session_timeout_max,
+            max_failed_attempts,
+            lockout_duration,
+        } => {
+            // The multisig must be the config's authority
+            let auth_config = targets.auth_config.as_deref_mut().ok_or(VaultError::MissingRequiredAccount)?;
+            auth_config.update_config(
+                multisig_wallet.key(),
+                require_2fa_globally,
+                session_timeout_min,
+                session_timeout_max,
+                max_failed_attempts,
+                lockout_duration,
+            )?;
+            Ok("Authentication config updated".to_string())
+        },
+        MultisigAction::RequireReverification { before_ts, expires_at, exempt_users } => {
+            let auth_config = targets.auth_config.as_deref_mut().ok_or(VaultError::MissingRequiredAccount)?;
+            let exempt = exempt_users.len();
+            auth_config.require_reverification(multisig_wallet.key(), before_ts, expires_at, exempt_users, now)?;
+            Ok(format!(
+                "Re-verification required for factors last used before {} until {} ({} users exempt)",
+                before_ts, expires_at, exempt
+            ))
+        },
+        MultisigAction::Custom { program, data } => {
+            custom_program_account(targets.remaining_accounts, &program)?;
+            let wallet_key = multisig_wallet.key();
+            let accounts = targets.remaining_accounts.iter()
+                .filter(|a| a.key() != program)
+                .map(|a| AccountMeta {
+                    pubkey: a.key(),
+                    is_signer: a.is_signer || a.key() == wallet_key,
+                    is_writable: a.is_writable,
+                })
+                .collect();
+            let mut account_infos = targets.remaining_accounts.to_vec();
+            account_infos.push(multisig_wallet.to_account_info());
+
+            invoke_signed(&Instruction { program_id: program, accounts, data }, &account_infos, &[seeds])?;
+            Ok(format!("Invoked program {}", program))
+        },
+        MultisigAction::AddSigner { signer } => {
+            let pubkey = signer.pubkey;
+            multisig_wallet.add_signer(signer, now)?;
+            Ok(format!("Signer {} added", pubkey))
+        },
+        MultisigAction::RemoveSigner { signer } => {
+            multisig_wallet.remove_signer(&signer)?;
+            revoke_pending_signatures(multisig_wallet, transaction_id, &signer, targets.remaining_accounts)?;
+            Ok(format!("Signer {} removed", signer))
+        },
+        MultisigAction::ReplaceSigner { old, new, hsm_key } => {
+            multisig_wallet.replace_signer(&old, new, hsm_key, now)?;
+            revoke_pending_signatures(multisig_wallet, transaction_id, &old, targets.remaining_accounts)?;
+            Ok(format!("Signer {} replaced by {}", old, new))
+        },
+        MultisigAction::SetFastPathLimits { daily_limit, reduced_threshold } => {
+            multisig_wallet.set_fast_path_limits(daily_limit, reduced_threshold)?;
+            Ok(format!("Fast path limits set to {} daily at threshold {}", daily_limit, reduced_threshold))
+        },
+        MultisigAction::SetGuardian { guardian } => {
+            multisig_wallet.set_guardian(guardian);
+            Ok(format!("Guardian set to {:?}", guardian))
+        },
+        MultisigAction::PaymentApproval { payment_id, approval_round } => {
+            let payment_system = targets.payment_system.as_deref_mut().ok_or(VaultError::MissingRequiredAccount)?;
+            check_payment_system(payment_system, &multisig_wallet.key())?;
+            payment_system.approve_payment(payment_id, approval_round, multisig_wallet.key())?;
+            Ok(format!("Payment {} approved", payment_id))
+        },
+        MultisigAction::ApproveTreasuryStream { stream, recipient, deposit } => {
+            let payment_stream = targets.payment_stream.as_deref_mut().ok_or(VaultError::MissingRequiredAccount)?;
+            check_treasury_stream(payment_stream, &stream, &recipient, deposit)?;
+            targets.treasury.as_deref_mut()
+                .ok_or(VaultError::MissingRequiredAccount)?
+                .withdraw_user_rewards(deposit)?;
+            payment_stream.approve()?;
+            Ok(format!("Treasury stream {} approved for {} ({} deposited)", stream, recipient, deposit))
+        },
+        MultisigAction::TopUpPaymentFloat { method, amount } => {
+            let float_account = {
+                let payment_system = targets.payment_system.as_deref().ok_or(VaultError::MissingRequiredAccount)?;
+                check_payment_system(payment_system, &multisig_wallet.key())?;
+                payment_system.float_account(&method)?
+            };
+            match float_account {
+                Some((to, mint)) => {
+                    let (source, destination) = treasury_transfer_accounts(multisig_wallet.key(), targets, &to, &mint, amount)?;
+                    let token_program = targets.token_program.ok_or(VaultError::MissingRequiredAccount)?;
+                    token::transfer(
+                        CpiContext::new_with_signer(
+                            token_program.to_account_info(),
+                            Transfer {
+                                from: source.to_account_info(),
+                                to: destination.to_account_info(),
+                                authority: multisig_wallet.to_account_info(),
+                            },
+                            &[seeds],
+                        ),
+                        amount,
+                    )?;
+                },
+                None => {
+                    let payment_system = targets.payment_system.as_deref_mut().ok_or(VaultError::MissingRequiredAccount)?;
+                    payment_system.top_up_lightning_float(amount)?;
+                },
+            }
+            Ok(format!("Payment float for {:?} topped up by {}", method, amount))
+        },
+        MultisigAction::SetSanctionsRoot { root, max_staleness } => {
+            let compliance_config = targets.compliance_config.as_deref_mut().ok_or(VaultError::MissingRequiredAccount)?;
+            compliance_config.set_sanctions_root(root, max_staleness, now);
+            Ok(format!("Sanctions root updated, current for {}s", max_staleness))
+        },
+        MultisigAction::SetRegionPolicy { region, policy } => {
+            let compliance_config = targets.compliance_config.as_deref_mut().ok_or(VaultError::MissingRequiredAccount)?;
+            let old_policy = compliance_config.set_region_policy(&region, policy.clone());
+            emit!(RegionPolicyAuditEntry {
+                sequence: compliance_config.next_audit_sequence()?,
+                region: region.clone(),
+                old_policy,
+                new_policy: policy,
+                transaction_id,
+                timestamp: now,
+            });
+            Ok(format!("Region policy for {:?} updated", region))
+        },
+        MultisigAction::SetScreeningProvider { provider_id, key_hash, priority, active } => {
+            let compliance_config = targets.compliance_config.as_deref_mut().ok_or(VaultError::MissingRequiredAccount)?;
+            let old_provider = compliance_config.set_screening_provider(ScreeningProvider {
+                provider_id,
+                key_hash,
+                priority,
+                active,
+                key_rotated_at: now,
+            })?;
+            emit!(ScreeningProviderAuditEntry {
+                sequence: compliance_config.next_audit_sequence()?,
+                provider_id,
+                old_key_hash: old_provider.map(|p| p.key_hash),
+                new_key_hash: key_hash,
+                priority,
+                active,
+                transaction_id,
+                timestamp: now,
+            });
+            Ok(format!("Screening provider {} set", provider_id))
+        },
+        MultisigAction::RotateScreeningProviderKey { provider_id, new_key_hash } => {
+            let compliance_config = targets.compliance_config.as_deref_mut().ok_or(VaultError::MissingRequiredAccount)?;
+            let old_key_hash = compliance_config.rotate_screening_provider_key(provider_id, new_key_hash, now)?;
+            let provider = compliance_config.screening_provider(provider_id)
+                .cloned()
+                .ok_or(VaultError::UnknownScreeningProvider)?;
+            emit!(ScreeningProviderAuditEntry {
+                sequence: compliance_config.next_audit_sequence()?,
+                provider_id,
+                old_key_hash: Some(old_key_hash),
+                new_key_hash,
+                priority: provider.priority,
+                active: provider.active,
+                transaction_id,
+                timestamp: now,
+            });
+            Ok(format!("Screening provider {} key rotated", provider_id))
+        },
     }
+}
 
-    let recipient = Pubkey::try_from(&transaction_data[0..32])
-        .map_err(|_| VaultError::InvalidAllocation)?;
-    let amount = u64::from_le_bytes(
-        transaction_data[32..40].try_into()
-            .map_err(|_| VaultError::InvalidAllocation)?
+/// Log a fast path transfer as a compliance-relevant audit trail entry
+fn record_fast_path_audit(
+    security_monitor: &mut SecurityMonitor,
+    audit_store: &mut AuditTrailStore,
+    executor: Pubkey,
+    multisig_transaction: &MultisigTransaction,
+    approvers: &[Pubkey],
+    amount: u64,
+) -> Result<()> {
+    security_monitor.audit_counter = security_monitor.audit_counter
+        .checked_add(1)
+        .ok_or(VaultError::ArithmeticOverflow)?;
+
+    let approvers: Vec<String> = approvers.iter().map(|a| format!("\"{}\"", a)).collect();
+    let details = format!(
+        "{{\"transaction_id\":{},\"amount\":{},\"approvers\":[{}]}}",
+        multisig_transaction.transaction_id, amount, approvers.join(","),
     );
+    let trail = AuditTrail::new(
+        security_monitor.audit_counter,
+        Some(executor),
+        "multisig_fast_path_transfer".to_string(),
+        format!("multisig_transaction:{}", multisig_transaction.transaction_id),
+        true,
+    )
+    .with_state_change(None, Some(details))
+    .mark_compliance_relevant();
+    audit_store.record(trail);
+    Ok(())
+}
 
-    // In production, execute actual treasury transfer
-    msg!("Treasury transfer: {} lamports to {}", amount, recipient);
-    
-    Ok(format!("Transferred {} lamports to {}", amount, recipient))
+/// Work out what executing a transaction would change, failing where
+/// execution would. State changes are made on copies.
+fn preview_transaction<'info>(
+    multisig_wallet: &Account<'info, MultisigWallet>,
+    multisig_transaction: &MultisigTransaction,
+    targets: &ActionTargets<'_, 'info>,
+    now: i64,
+) -> Result<Vec<Effect>> {
+    let mut transaction = multisig_transaction.clone();
+    transaction.sync_required_weight(multisig_wallet);
+    let mut effects = Vec::new();
+    if let Some(amount) = fast_path_amount(multisig_wallet, &transaction, now) {
+        effects.push(Effect::FastPathSpend { signers: transaction.counted_signers(multisig_wallet), amount });
+    } else {
+        transaction.check_executable(multisig_wallet, now)?;
+        let timelock = multisig_wallet.timelock_for(&transaction.transaction_type);
+        transaction.mark_approved_if_ready(multisig_wallet, now);
+        transaction.check_timelock_elapsed(now, timelock)?;
+    }
+
+    let mut wallet = (**multisig_wallet).clone();
+    if let Some(new_threshold) = transaction.new_threshold() {
+        let old_threshold = wallet.threshold;
+        wallet.change_threshold(new_threshold)?;
+        return Ok(vec![Effect::ThresholdChange { old_threshold, new_threshold }]);
+    }
+
+    match transaction.decode_action()? {
+        MultisigAction::TreasuryTransfer { to, mint, amount } => {
+            let (source, _) = treasury_transfer_accounts(multisig_wallet.key(), targets, &to, &mint, amount)?;
+            effects.push(Effect::TokenTransfer { from: source.key(), to, mint, amount });
+        },
+        MultisigAction::UpdateRewardRates { bps } => {
+            let mut reward_pool = (**targets.reward_pool.as_deref().ok_or(VaultError::MissingRequiredAccount)?).clone();
+            let old_user_share_bps = reward_pool.set_user_share_bps(bps)?;
+            effects.push(Effect::RewardRateChange { old_user_share_bps, new_user_share_bps: bps });
+        },
+        MultisigAction::OracleFeedChange { btc_usd_feed } => {
+            let oracle_data = targets.oracle_data.as_deref().ok_or(VaultError::MissingRequiredAccount)?;
+            effects.push(Effect::OracleFeedChange { old_feed: oracle_data.btc_usd_feed, new_feed: btc_usd_feed });
+        },
+        MultisigAction::FreezeAccount { user, scope, .. } => {
+            let kyc_profile = targets.kyc_profile.as_deref().ok_or(VaultError::MissingRequiredAccount)?;
+            check_freeze_target(kyc_profile, &user)?;
+            effects.push(Effect::AccountFreeze { user, scope: scope.unwrap_or_else(FreezeScope::full) });
+        },
+        MultisigAction::UpdateAuthConfig {
+            require_2fa_globally,
+            session_timeout_min,
+            session_timeout_max,
+            max_failed_attempts,
+            lockout_duration,
+        } => {
+            let mut auth_config = (**targets.auth_config.as_deref().ok_or(VaultError::MissingRequiredAccount)?).clone();
+            auth_config.update_config(
+                multisig_wallet.key(),
+                require_2fa_globally,
+                session_timeout_min,
+                session_timeout_max,
+                max_failed_attempts,
+                lockout_duration,
+            )?;
+            effects.push(Effect::AuthConfigUpdate);
+        },
+        MultisigAction::RequireReverification { before_ts, expires_at, exempt_users } => {
+            let mut auth_config = (**targets.auth_config.as_deref().ok_or(VaultError::MissingRequiredAccount)?).clone();
+            auth_config.require_reverification(multisig_wallet.key(), before_ts, expires_at, exempt_users, now)?;
+            effects.push(Effect::AuthConfigUpdate);
+        },
+        MultisigAction::Custom { program, data } => {
+            custom_program_account(targets.remaining_accounts, &program)?;
+            effects.push(Effect::ProgramInvocation { program, data_len: data.len() as u32 });
+        },
+        MultisigAction::AddSigner { signer } => {
+            let pubkey = signer.pubkey;
+            wallet.add_signer(signer, now)?;
+            effects.push(Effect::SignerAdded { signer: pubkey });
+        },
+        MultisigAction::RemoveSigner { signer } => {
+            wallet.remove_signer(&signer)?;
+            effects.push(Effect::SignerRemoved { signer });
+            preview_revocations(&wallet, multisig_wallet.key(), &transaction, &signer, targets, &mut effects)?;
+        },
+        MultisigAction::ReplaceSigner { old, new, hsm_key } => {
+            wallet.replace_signer(&old, new, hsm_key, now)?;
+            effects.push(Effect::SignerReplaced { old, new });
+            preview_revocations(&wallet, multisig_wallet.key(), &transaction, &old, targets, &mut effects)?;
+        },
+        MultisigAction::SetFastPathLimits { daily_limit, reduced_threshold } => {
+            wallet.set_fast_path_limits(daily_limit, reduced_threshold)?;
+            effects.push(Effect::FastPathLimitsChange { daily_limit, reduced_threshold });
+        },
+        MultisigAction::SetGuardian { guardian } => {
+            effects.push(Effect::GuardianChange { old_guardian: wallet.guardian, new_guardian: guardian });
+        },
+        MultisigAction::PaymentApproval { payment_id, approval_round } => {
+            let payment_system = targets.payment_system.as_deref().ok_or(VaultError::MissingRequiredAccount)?;
+            check_payment_system(payment_system, &multisig_wallet.key())?;
+            let amount = payment_system.approvable_payment(payment_id, approval_round)?.amount;
+            effects.push(Effect::PaymentApproved { payment_id, amount });
+        },
+        MultisigAction::ApproveTreasuryStream { stream, recipient, deposit } => {
+            let payment_stream = targets.payment_stream.as_deref().ok_or(VaultError::MissingRequiredAccount)?;
+            check_treasury_stream(payment_stream, &stream, &recipient, deposit)?;
+            let treasury = targets.treasury.as_deref().ok_or(VaultError::MissingRequiredAccount)?;
+            if treasury.user_rewards_pool < deposit {
+                return Err(VaultError::InsufficientBalance.into());
+            }
+            effects.push(Effect::TreasuryStreamApproved { stream, recipient, deposit });
+        },
+        MultisigAction::TopUpPaymentFloat { method, amount } => {
+            let payment_system = targets.payment_system.as_deref().ok_or(VaultError::MissingRequiredAccount)?;
+            check_payment_system(payment_system, &multisig_wallet.key())?;
+            if let Some((to, mint)) = payment_system.float_account(&method)? {
+                treasury_transfer_accounts(multisig_wallet.key(), targets, &to, &mint, amount)?;
+            }
+            effects.push(Effect::PaymentFloatTopUp { method, amount });
+        },
+        MultisigAction::SetSanctionsRoot { root, .. } => {
+            let compliance_config = targets.compliance_config.as_deref().ok_or(VaultError::MissingRequiredAccount)?;
+            effects.push(Effect::SanctionsRootChange { old_root: compliance_config.sanctions_root, new_root: root });
+        },
+        MultisigAction::SetRegionPolicy { region, policy } => {
+            let compliance_config = targets.compliance_config.as_deref().ok_or(VaultError::MissingRequiredAccount)?;
+            let old_policy = compliance_config.region_policy(Some(&region));
+            effects.push(Effect::RegionPolicyChange { region, old_policy, new_policy: policy });
+        },
+        MultisigAction::SetScreeningProvider { provider_id, key_hash, priority, active } => {
+            let compliance_config = targets.compliance_config.as_deref().ok_or(VaultError::MissingRequiredAccount)?;
+            let old_provider = compliance_config.screening_provider(provider_id).cloned();
+            if old_provider.is_none() && compliance_config.screening_providers.len() >= ComplianceConfig::MAX_SCREENING_PROVIDERS {
+                return Err(VaultError::TooManyScreeningProviders.into());
+            }
+            effects.push(Effect::ScreeningProviderChange {
+                old_provider,
+                new_provider: ScreeningProvider { provider_id, key_hash, priority, active, key_rotated_at: now },
+            });
+        },
+        MultisigAction::RotateScreeningProviderKey { provider_id, new_key_hash } => {
+            let compliance_config = targets.compliance_config.as_deref().ok_or(VaultError::MissingRequiredAccount)?;
+            let provider = compliance_config.screening_provider(provider_id).ok_or(VaultError::UnknownScreeningProvider)?;
+            if provider.key_hash == new_key_hash {
+                return Err(VaultError::InvalidScreeningProvider.into());
+            }
+            effects.push(Effect::ScreeningProviderKeyRotation {
+                provider_id,
+                old_key_hash: provider.key_hash,
+                new_key_hash,
+            });
+        },
+    }
+    Ok(effects)
 }
 
-fn execute_staking_operation(transaction_data: &[u8]) -> Result<String> {
-    // Parse staking operation data
-    msg!("Executing staking operation with {} bytes of data", transaction_data.len());
-    
-    // In production, execute actual staking operation
-    Ok("Staking operation completed".to_string())
+/// Effects of `revoke_pending_signatures`, worked out on copies
+fn preview_revocations<'info>(
+    wallet: &MultisigWallet,
+    multisig: Pubkey,
+    executing: &MultisigTransaction,
+    signer: &Pubkey,
+    targets: &ActionTargets<'_, 'info>,
+    effects: &mut Vec<Effect>,
+) -> Result<()> {
+    for proposal in pending_proposals(multisig, executing.transaction_id, targets.remaining_accounts)? {
+        let mut proposal = (*proposal).clone();
+        if proposal.invalidate_signatures_from(signer, wallet) {
+            effects.push(Effect::SignaturesRevoked {
+                transaction_id: proposal.transaction_id,
+                still_approved: proposal.approved_at.is_some(),
+            });
+        }
+    }
+    Ok(())
 }
 
-fn execute_reward_distribution(transaction_data: &[u8]) -> Result<String> {
-    // Parse reward distribution data
-    msg!("Executing reward distribution with {} bytes of data", transaction_data.len());
-    
-    // In production, execute actual reward distribution
-    Ok("Reward distribution completed".to_string())
+/// Strip a departed signer's signatures from the wallet's pending proposals
+/// passed as remaining accounts, revoking approval where threshold is no
+/// longer met. Signatures on proposals not passed in still count for
+/// nothing once the signer is gone.
+fn revoke_pending_signatures<'info>(
+    multisig_wallet: &Account<'info, MultisigWallet>,
+    executing_transaction_id: u32,
+    signer: &Pubkey,
+    pending: &'info [AccountInfo<'info>],
+) -> Result<()> {
+    let multisig = multisig_wallet.key();
+    for mut proposal in pending_proposals(multisig, executing_transaction_id, pending)? {
+        if proposal.invalidate_signatures_from(signer, multisig_wallet) {
+            let still_approved = proposal.approved_at.is_some();
+            proposal.exit(&crate::ID)?;
+            emit!(SignerSignaturesRevoked {
+                multisig,
+                transaction_id: proposal.transaction_id,
+                signer: *signer,
+                still_approved,
+            });
+        }
+    }
+    Ok(())
 }
 
-fn execute_config_update(transaction_data: &[u8]) -> Result<String> {
-    // Parse configuration update data
-    msg!("Executing config update with {} bytes of data", transaction_data.len());
-    
-    // In production, execute actual configuration update
-    Ok("Configuration updated".to_string())
+/// The wallet's unsettled proposals among `pending`, other than the one
+/// executing
+fn pending_proposals<'info>(
+    multisig: Pubkey,
+    executing_transaction_id: u32,
+    pending: &'info [AccountInfo<'info>],
+) -> Result<Vec<Account<'info, MultisigTransaction>>> {
+    let mut proposals = Vec::new();
+    for info in pending {
+        let proposal = Account::<MultisigTransaction>::try_from(info)?;
+        if proposal.multisig == multisig
+            && proposal.transaction_id != executing_transaction_id
+            && !proposal.is_settled()
+        {
+            proposals.push(proposal);
+        }
+    }
+    Ok(proposals)
 }
 
-fn execute_emergency_action(transaction_data: &[u8]) -> Result<String> {
-    // Parse emergency action data
-    msg!("Executing emergency action with {} bytes of data", transaction_data.len());
-    
-    // In production, execute actual emergency action
-    Ok("Emergency action completed".to_string())
+// Action preconditions, shared by execution and simulation
+
+/// Transfer amount of a transaction taking the fast path: one not ready on
+/// full approval and timelock that qualifies under the reduced threshold
+fn fast_path_amount(
+    multisig_wallet: &MultisigWallet,
+    multisig_transaction: &MultisigTransaction,
+    now: i64,
+) -> Option<u64> {
+    if multisig_transaction.is_ready_to_execute(multisig_wallet, now) {
+        return None;
+    }
+    multisig_transaction.check_fast_path(multisig_wallet, now).ok()
 }
 
-fn execute_key_rotation(_multisig_wallet: &mut MultisigWallet, transaction_data: &[u8]) -> Result<String> {
-    // Parse new signer data
-    if transaction_data.len() < 96 { // Minimum for 3 pubkeys
-        return Err(VaultError::InvalidAllocation.into());
+/// Source and destination of a treasury transfer, checked against the
+/// action and the treasury pause, with enough balance to cover it
+fn treasury_transfer_accounts<'a, 'info>(
+    wallet_key: Pubkey,
+    targets: &ActionTargets<'a, 'info>,
+    to: &Pubkey,
+    mint: &Pubkey,
+    amount: u64,
+) -> Result<(&'a Account<'info, TokenAccount>, &'a Account<'info, TokenAccount>)> {
+    targets.emergency_state
+        .ok_or(VaultError::MissingRequiredAccount)?
+        .require_not_paused(EmergencyScope::Treasury)?;
+    let source = targets.treasury_token_account.ok_or(VaultError::MissingRequiredAccount)?;
+    let destination = targets.destination_token_account.ok_or(VaultError::MissingRequiredAccount)?;
+    if source.owner != wallet_key
+        || source.mint != *mint
+        || destination.key() != *to
+        || destination.mint != *mint
+    {
+        return Err(VaultError::ActionAccountMismatch.into());
     }
+    if source.amount < amount {
+        return Err(VaultError::InsufficientBalance.into());
+    }
+    Ok((source, destination))
+}
 
-    // In production, parse and validate new signers
-    msg!("Executing key rotation with {} bytes of data", transaction_data.len());
-    
-    Ok("Key rotation completed".to_string())
+fn check_freeze_target(kyc_profile: &KYCProfile, user: &Pubkey) -> Result<()> {
+    if kyc_profile.user != *user {
+        return Err(VaultError::ActionAccountMismatch.into());
+    }
+    Ok(())
 }
 
-/// Validate HSM signature against Yubico HSM
-fn validate_hsm_signature(
-    signer_pubkey: &Pubkey,
-    _signature: &[u8; 64],
-    hsm_signature: &[u8],
-) -> Result<()> {
-    // In production, this would:
-    // 1. Connect to Yubico HSM
-    // 2. Validate the HSM signature using the stored HSM key
-    // 3. Verify the signature matches the expected format
-    // 4. Check HSM key usage limits and policies
-    
-    msg!("Validating HSM signature for signer: {}", signer_pubkey);
-    
-    // Simulate HSM validation
-    if hsm_signature.len() < 64 {
-        return Err(VaultError::SecurityViolation.into());
+fn check_payment_system(payment_system: &PaymentSystem, multisig: &Pubkey) -> Result<()> {
+    if payment_system.multisig_wallet != *multisig {
+        return Err(VaultError::ActionAccountMismatch.into());
     }
+    Ok(())
+}
 
-    // In reality, would perform cryptographic validation
+/// The stream passed in must be the one the signers approved, on the terms
+/// they saw, and still waiting for its funding
+fn check_treasury_stream(payment_stream: &Account<PaymentStream>, stream: &Pubkey, recipient: &Pubkey, deposit: u64) -> Result<()> {
+    if payment_stream.key() != *stream
+        || payment_stream.recipient != *recipient
+        || payment_stream.deposited != deposit
+        || payment_stream.funding != StreamFunding::Treasury
+    {
+        return Err(VaultError::ActionAccountMismatch.into());
+    }
+    if payment_stream.cancelled_at.is_some() {
+        return Err(VaultError::PaymentStreamCancelled.into());
+    }
+    if payment_stream.approved {
+        return Err(VaultError::InvalidPaymentStream.into());
+    }
     Ok(())
 }
+
+/// Remaining accounts are the instruction's accounts, plus the program itself
+fn custom_program_account<'a, 'info>(
+    remaining_accounts: &'a [AccountInfo<'info>],
+    program: &Pubkey,
+) -> Result<&'a AccountInfo<'info>> {
+    let program_info = remaining_accounts.iter()
+        .find(|a| a.key() == *program)
+        .ok_or(VaultError::MissingRequiredAccount)?;
+    if !program_info.executable {
+        return Err(VaultError::ActionAccountMismatch.into());
+    }
+    Ok(program_info)
+}
@@ -1,5 +1,6 @@
 use anchor_lang::prelude::*;
 use crate::state::{oracle::*, btc_commitment::BTCCommitment, user_account::UserAccount};
+use crate::state::emergency_state::{EmergencyScope, EmergencyState};
 use crate::errors::VaultError;
 
 /// Initialize oracle with Chainlink feed address
@@ -33,6 +34,12 @@ pub struct UpdateBTCPrice<'info> {
     )]
     pub oracle_data: Account<'info, OracleData>,
     
+    #[account(
+        seeds = [b"emergency_state"],
+        bump = emergency_state.bump
+    )]
+    pub emergency_state: Account<'info, EmergencyState>,
+    
     /// Chainlink oracle account (in production, this would be the actual Chainlink feed)
     /// CHECK: This is the Chainlink BTC/USD price feed account
     pub chainlink_feed: AccountInfo<'info>,
@@ -53,6 +60,12 @@ pub struct VerifyBTCBalance<'info> {
     )]
     pub oracle_data: Account<'info, OracleData>,
     
+    #[account(
+        seeds = [b"emergency_state"],
+        bump = emergency_state.bump
+    )]
+    pub emergency_state: Account<'info, EmergencyState>,
+    
     #[account(
         mut,
         seeds = [b"btc_commitment", user.key().as_ref()],
@@ -93,6 +106,8 @@ impl<'info> UpdateBTCPrice<'info> {
         round_id: u64,
         timestamp: i64,
     ) -> Result<()> {
+        ctx.accounts.emergency_state.require_not_paused(EmergencyScope::OracleWrites)?;
+
         let oracle_data = &mut ctx.accounts.oracle_data;
         
         // Validate timestamp is recent (within 5 minutes)
@@ -116,6 +131,8 @@ impl<'info> VerifyBTCBalance<'info> {
         expected_balance: u64,
         ecdsa_proof: Vec<u8>,
     ) -> Result<()> {
+        ctx.accounts.emergency_state.require_not_paused(EmergencyScope::OracleWrites)?;
+
         let oracle_data = &mut ctx.accounts.oracle_data;
         let btc_commitment = &mut ctx.accounts.btc_commitment;
         let user_account = &mut ctx.accounts.user_account;
@@ -1,7 +1,10 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use anchor_spl::associated_token::{self, AssociatedToken};
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
 use crate::state::*;
 use crate::errors::VaultError;
+use crate::instructions::authentication::{compute_operation_risk, record_operation_risk, require_cosigner, require_session_permission, sync_reverification};
+use crate::instructions::kyc::check_region_policy;
 
 #[derive(Accounts)]
 pub struct InitializePaymentSystem<'info> {
@@ -50,6 +53,12 @@ pub struct CreatePaymentRequest<'info> {
     )]
     pub payment_system: Account<'info, PaymentSystem>,
     
+    #[account(
+        seeds = [b"emergency_state"],
+        bump = emergency_state.bump
+    )]
+    pub emergency_state: Account<'info, EmergencyState>,
+    
     #[account(
         seeds = [b"user_preferences", user.key().as_ref()],
         bump = user_preferences.bump
@@ -63,8 +72,98 @@ pub struct CreatePaymentRequest<'info> {
     )]
     pub user_rewards: Account<'info, UserRewards>,
     
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = UserPaymentHistory::LEN,
+        seeds = [b"payment_history", user.key().as_ref()],
+        bump
+    )]
+    pub payment_history: Account<'info, UserPaymentHistory>,
+    
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = UserPayoutVelocity::LEN,
+        seeds = [b"payout_velocity", user.key().as_ref()],
+        bump
+    )]
+    pub payout_velocity: Account<'info, UserPayoutVelocity>,
+    
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = PaymentIdempotency::LEN,
+        seeds = [b"payment_idempotency", user.key().as_ref()],
+        bump
+    )]
+    pub payment_idempotency: Account<'info, PaymentIdempotency>,
+    
+    /// Raises the daily payout limit for Enhanced KYC users; its region
+    /// decides whether Lightning payouts are allowed and its freeze can
+    /// block payments
+    #[account(
+        seeds = [b"kyc_profile", user.key().as_ref()],
+        bump = kyc_profile.bump
+    )]
+    pub kyc_profile: Account<'info, KYCProfile>,
+    
+    #[account(
+        seeds = [b"compliance_config"],
+        bump = compliance_config.bump
+    )]
+    pub compliance_config: Account<'info, ComplianceConfig>,
+    
+    /// The user's latest quote, required when the request references one
+    #[account(
+        seeds = [b"payment_quote", user.key().as_ref()],
+        bump = payment_quote.bump
+    )]
+    pub payment_quote: Option<Account<'info, PaymentQuote>>,
+    
+    /// The session requesting the payout; the request's risk score is recorded against it
+    #[account(
+        mut,
+        seeds = [b"user_auth", user.key().as_ref()],
+        bump = user_auth.bump
+    )]
+    pub user_auth: Account<'info, UserAuth>,
+    
+    /// Holds the risk score above which requests need multisig approval
+    #[account(
+        seeds = [b"auth_config"],
+        bump = auth_config.bump
+    )]
+    pub auth_config: Account<'info, AuthConfig>,
+    
+    /// The user's hardware wallet, for requests above their co-sign threshold
+    pub cosigner: Option<Signer<'info>>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct QuotePayment<'info> {
+    #[account(
+        seeds = [b"payment_system"],
+        bump = payment_system.bump
+    )]
+    pub payment_system: Account<'info, PaymentSystem>,
+    
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = PaymentQuote::LEN,
+        seeds = [b"payment_quote", user.key().as_ref()],
+        bump
+    )]
+    pub payment_quote: Account<'info, PaymentQuote>,
+    
     #[account(mut)]
     pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
 }#
 [derive(Accounts)]
 pub struct ProcessPayment<'info> {
@@ -75,6 +174,12 @@ pub struct ProcessPayment<'info> {
     )]
     pub payment_system: Account<'info, PaymentSystem>,
     
+    #[account(
+        seeds = [b"emergency_state"],
+        bump = emergency_state.bump
+    )]
+    pub emergency_state: Account<'info, EmergencyState>,
+    
     #[account(
         mut,
         seeds = [b"treasury"],
@@ -89,13 +194,209 @@ pub struct ProcessPayment<'info> {
     #[account(mut)]
     pub recipient_usdc_ata: Option<Account<'info, TokenAccount>>,
     
+    #[account(mut)]
+    pub fee_usdc_ata: Option<Account<'info, TokenAccount>>,
+    
+    pub usdc_mint: Option<Account<'info, Mint>>,
+    
+    /// Required if a USDC payment exhausts its retries in this instruction
+    #[account(
+        mut,
+        seeds = [b"dead_letter_queue"],
+        bump = dead_letter_queue.bump
+    )]
+    pub dead_letter_queue: Option<Account<'info, DeadLetterQueue>>,
+    
+    /// History of the paying user, required once a USDC payment finishes
+    #[account(
+        mut,
+        seeds = [b"payment_history", payment_history.user.as_ref()],
+        bump = payment_history.bump
+    )]
+    pub payment_history: Option<Account<'info, UserPaymentHistory>>,
+    
+    /// Required to release a request held by the daily payout limits
+    #[account(
+        mut,
+        seeds = [b"payout_velocity", payout_velocity.user.as_ref()],
+        bump = payout_velocity.bump
+    )]
+    pub payout_velocity: Option<Account<'info, UserPayoutVelocity>>,
+    
+    #[account(
+        seeds = [b"kyc_profile", kyc_profile.user.as_ref()],
+        bump = kyc_profile.bump
+    )]
+    pub kyc_profile: Option<Account<'info, KYCProfile>>,
+    
+    /// Travel rule thresholds, checked against the user's KYC region
+    #[account(
+        seeds = [b"compliance_config"],
+        bump = compliance_config.bump
+    )]
+    pub compliance_config: Account<'info, ComplianceConfig>,
+    
+    /// Screening of the payment's destination, required while the screening
+    /// policy is enabled
+    #[account(
+        seeds = [b"destination_screening", destination_screening.destination_hash.as_ref()],
+        bump = destination_screening.bump
+    )]
+    pub destination_screening: Option<Account<'info, DestinationScreening>>,
+    
+    /// SPL token accounts (optional, only for SPL token payments)
+    pub payment_mint: Option<Account<'info, Mint>>,
+    
+    #[account(mut)]
+    pub treasury_token_ata: Option<Account<'info, TokenAccount>>,
+    
+    /// CHECK: Recipient's associated token account for the payment mint,
+    /// created if missing and checked against its derived address
+    #[account(mut)]
+    pub recipient_token_ata: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: Wallet named as the payment destination, checked in the handler
+    pub recipient: Option<UncheckedAccount<'info>>,
+    
     #[account(mut)]
     pub processor: Signer<'info>,
     pub token_program: Option<Program<'info, Token>>,
+    pub associated_token_program: Option<Program<'info, AssociatedToken>>,
+    pub system_program: Option<Program<'info, System>>,
+}
+
+impl<'info> ProcessPayment<'info> {
+    /// Pay a USDC request from the treasury USDC account, which the payment
+    /// system PDA owns, sending the configured fee to the fee account.
+    /// Returns false without moving funds when the treasury balance is short.
+    fn transfer_usdc(&self, config: &UsdcConfig, payment: &PaymentRequest) -> Result<bool> {
+        let mint = self.usdc_mint.as_ref().ok_or(VaultError::MissingTokenAccount)?;
+        let treasury_ata = self.treasury_usdc_ata.as_ref().ok_or(VaultError::MissingTokenAccount)?;
+        let recipient_ata = self.recipient_usdc_ata.as_ref().ok_or(VaultError::MissingTokenAccount)?;
+        let fee_ata = self.fee_usdc_ata.as_ref().ok_or(VaultError::MissingTokenAccount)?;
+        let token_program = self.token_program.as_ref().ok_or(VaultError::MissingTokenProgram)?;
+        
+        let destination = payment.destination.parse::<Pubkey>()
+            .map_err(|_| VaultError::InvalidSolanaAddress)?;
+        if mint.key() != config.mint_address
+            || treasury_ata.key() != config.treasury_ata
+            || fee_ata.key() != config.fee_ata
+            || treasury_ata.mint != config.mint_address
+            || recipient_ata.mint != config.mint_address
+            || recipient_ata.owner != destination
+        {
+            return Err(VaultError::InvalidPaymentTokenAccount.into());
+        }
+        
+        if treasury_ata.amount < payment.amount {
+            msg!("Treasury USDC balance {} short of payment {}", treasury_ata.amount, payment.amount);
+            return Ok(false);
+        }
+        let fee = payment.protocol_fee(config.fee_basis_points)?;
+        let net_amount = payment.amount - fee;
+        
+        let bump = [self.payment_system.bump];
+        let payment_system_seeds: &[&[u8]] = &[b"payment_system", &bump];
+        let signer_seeds = &[payment_system_seeds];
+        token::transfer(
+            CpiContext::new_with_signer(
+                token_program.to_account_info(),
+                Transfer {
+                    from: treasury_ata.to_account_info(),
+                    to: recipient_ata.to_account_info(),
+                    authority: self.payment_system.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            net_amount,
+        )?;
+        if fee > 0 {
+            token::transfer(
+                CpiContext::new_with_signer(
+                    token_program.to_account_info(),
+                    Transfer {
+                        from: treasury_ata.to_account_info(),
+                        to: fee_ata.to_account_info(),
+                        authority: self.payment_system.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                fee,
+            )?;
+        }
+        
+        msg!("USDC transfer completed: {} USDC to {} (fee: {})", net_amount, destination, fee);
+        
+        Ok(true)
+    }
+    
+    /// Pay an SPL token request from the treasury's token account for the
+    /// mint, creating the recipient's associated token account if needed.
+    /// Unlike USDC, which has a dedicated fee account the payment system
+    /// signs for, SPL mints are listed with only a treasury-owned token
+    /// account: the configured fee is withheld there as protocol revenue, so
+    /// only the net amount moves and the treasury PDA signs.
+    fn transfer_spl_token(&self, config: &SplTokenConfig, payment: &PaymentRequest) -> Result<()> {
+        let mint = self.payment_mint.as_ref().ok_or(VaultError::MissingTokenAccount)?;
+        let treasury_ata = self.treasury_token_ata.as_ref().ok_or(VaultError::MissingTokenAccount)?;
+        let recipient_ata = self.recipient_token_ata.as_ref().ok_or(VaultError::MissingTokenAccount)?;
+        let recipient = self.recipient.as_ref().ok_or(VaultError::MissingRequiredAccount)?;
+        let token_program = self.token_program.as_ref().ok_or(VaultError::MissingTokenProgram)?;
+        let associated_token_program = self.associated_token_program.as_ref()
+            .ok_or(VaultError::MissingRequiredAccount)?;
+        let system_program = self.system_program.as_ref().ok_or(VaultError::MissingRequiredAccount)?;
+        
+        let destination = payment.destination.parse::<Pubkey>()
+            .map_err(|_| VaultError::InvalidSolanaAddress)?;
+        if mint.key() != config.mint
+            || treasury_ata.key() != config.treasury_ata
+            || treasury_ata.mint != config.mint
+            || recipient.key() != destination
+            || recipient_ata.key() != associated_token::get_associated_token_address(&destination, &config.mint)
+        {
+            return Err(VaultError::InvalidPaymentTokenAccount.into());
+        }
+        
+        let amount = payment.amount - payment.protocol_fee(config.fee_basis_points)?;
+        if treasury_ata.amount < amount {
+            return Err(VaultError::InsufficientBalance.into());
+        }
+        
+        associated_token::create_idempotent(CpiContext::new(
+            associated_token_program.to_account_info(),
+            associated_token::Create {
+                payer: self.processor.to_account_info(),
+                associated_token: recipient_ata.to_account_info(),
+                authority: recipient.to_account_info(),
+                mint: mint.to_account_info(),
+                system_program: system_program.to_account_info(),
+                token_program: token_program.to_account_info(),
+            },
+        ))?;
+        
+        let bump = [self.treasury.bump];
+        let treasury_seeds: &[&[u8]] = &[b"treasury", &bump];
+        token::transfer(
+            CpiContext::new_with_signer(
+                token_program.to_account_info(),
+                Transfer {
+                    from: treasury_ata.to_account_info(),
+                    to: recipient_ata.to_account_info(),
+                    authority: self.treasury.to_account_info(),
+                },
+                &[treasury_seeds],
+            ),
+            amount,
+        )?;
+        
+        msg!("SPL token transfer completed: {} of mint {} to {}", amount, config.mint, destination);
+        
+        Ok(())
+    }
 }
 
 #[derive(Accounts)]
-pub struct ApprovePayment<'info> {
+pub struct CompletePayment<'info> {
     #[account(
         mut,
         seeds = [b"payment_system"],
@@ -103,19 +404,40 @@ pub struct ApprovePayment<'info> {
     )]
     pub payment_system: Account<'info, PaymentSystem>,
     
+    /// History of the paying user, receiving the payment once it finishes
     #[account(
         mut,
+        seeds = [b"payment_history", payment_history.user.as_ref()],
+        bump = payment_history.bump
+    )]
+    pub payment_history: Account<'info, UserPaymentHistory>,
+    
+    /// Receives the payment once it exhausts its retries
+    #[account(
+        init_if_needed,
+        payer = processor,
+        space = DeadLetterQueue::LEN,
+        seeds = [b"dead_letter_queue"],
+        bump
+    )]
+    pub dead_letter_queue: Account<'info, DeadLetterQueue>,
+    
+    /// Payment processors are its admin, operator and compliance signers
+    #[account(
         seeds = [b"multisig_wallet"],
         bump = multisig_wallet.bump
     )]
     pub multisig_wallet: Account<'info, MultisigWallet>,
     
     #[account(mut)]
-    pub approver: Signer<'info>,
+    pub processor: Signer<'info>,
+    pub system_program: Program<'info, System>,
 }
 
+/// Put a dead-lettered payment back in the queue, signed by a payment admin,
+/// operator or compliance signer
 #[derive(Accounts)]
-pub struct UpdatePaymentConfig<'info> {
+pub struct RequeueDeadLetter<'info> {
     #[account(
         mut,
         seeds = [b"payment_system"],
@@ -125,30 +447,112 @@ pub struct UpdatePaymentConfig<'info> {
     
     #[account(
         mut,
+        seeds = [b"dead_letter_queue"],
+        bump = dead_letter_queue.bump
+    )]
+    pub dead_letter_queue: Account<'info, DeadLetterQueue>,
+    
+    #[account(
         seeds = [b"multisig_wallet"],
         bump = multisig_wallet.bump
     )]
     pub multisig_wallet: Account<'info, MultisigWallet>,
     
-    #[account(mut)]
     pub authority: Signer<'info>,
 }
 
+/// Refund a dead-lettered payment to its user, signed by a payment admin,
+/// operator or compliance signer
 #[derive(Accounts)]
-pub struct UpdateUserPreferences<'info> {
+pub struct RefundDeadLetter<'info> {
+    #[account(
+        mut,
+        seeds = [b"payment_system"],
+        bump = payment_system.bump
+    )]
+    pub payment_system: Account<'info, PaymentSystem>,
+    
+    #[account(
+        mut,
+        seeds = [b"dead_letter_queue"],
+        bump = dead_letter_queue.bump
+    )]
+    pub dead_letter_queue: Account<'info, DeadLetterQueue>,
+    
+    #[account(
+        mut,
+        seeds = [b"rewards", user_rewards.user.as_ref()],
+        bump = user_rewards.bump
+    )]
+    pub user_rewards: Account<'info, UserRewards>,
+    
+    #[account(
+        mut,
+        seeds = [b"payment_history", user_rewards.user.as_ref()],
+        bump = payment_history.bump
+    )]
+    pub payment_history: Account<'info, UserPaymentHistory>,
+    
+    #[account(
+        seeds = [b"multisig_wallet"],
+        bump = multisig_wallet.bump
+    )]
+    pub multisig_wallet: Account<'info, MultisigWallet>,
+    
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelPayment<'info> {
+    #[account(
+        mut,
+        seeds = [b"payment_system"],
+        bump = payment_system.bump
+    )]
+    pub payment_system: Account<'info, PaymentSystem>,
+    
+    pub user: Signer<'info>,
+}
+
+/// Replace the destination of a pending payment. The new destination's
+/// `DestinationScreening` goes in remaining accounts while screening is on.
+#[derive(Accounts)]
+pub struct ReplacePaymentDestination<'info> {
     #[account(
         mut,
+        seeds = [b"payment_system"],
+        bump = payment_system.bump
+    )]
+    pub payment_system: Account<'info, PaymentSystem>,
+    
+    #[account(
+        seeds = [b"emergency_state"],
+        bump = emergency_state.bump
+    )]
+    pub emergency_state: Account<'info, EmergencyState>,
+    
+    #[account(
         seeds = [b"user_preferences", user.key().as_ref()],
         bump = user_preferences.bump
     )]
     pub user_preferences: Account<'info, UserPaymentPreferences>,
     
-    #[account(mut)]
+    /// Holds the destination change step-up proof
+    #[account(
+        mut,
+        seeds = [b"user_auth", user.key().as_ref()],
+        bump = user_auth.bump
+    )]
+    pub user_auth: Account<'info, UserAuth>,
+    
     pub user: Signer<'info>,
 }
 
+/// Attach travel rule data to a payment. The payment's user or a payment
+/// admin, operator or compliance signer may attach it; the multisig wallet
+/// is needed for the latter.
 #[derive(Accounts)]
-pub struct ProcessReinvestment<'info> {
+pub struct AttachTravelRuleData<'info> {
     #[account(
         mut,
         seeds = [b"payment_system"],
@@ -156,12 +560,23 @@ pub struct ProcessReinvestment<'info> {
     )]
     pub payment_system: Account<'info, PaymentSystem>,
     
+    #[account(
+        seeds = [b"multisig_wallet"],
+        bump = multisig_wallet.bump
+    )]
+    pub multisig_wallet: Option<Account<'info, MultisigWallet>>,
+    
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RefundPayment<'info> {
     #[account(
         mut,
-        seeds = [b"user_preferences", user.key().as_ref()],
-        bump = user_preferences.bump
+        seeds = [b"payment_system"],
+        bump = payment_system.bump
     )]
-    pub user_preferences: Account<'info, UserPaymentPreferences>,
+    pub payment_system: Account<'info, PaymentSystem>,
     
     #[account(
         mut,
@@ -172,160 +587,1934 @@ pub struct ProcessReinvestment<'info> {
     
     #[account(
         mut,
-        seeds = [b"staking_pool"],
-        bump = staking_pool.bump
+        seeds = [b"payment_history", user.key().as_ref()],
+        bump = payment_history.bump
     )]
-    pub staking_pool: Account<'info, StakingPool>,
+    pub payment_history: Account<'info, UserPaymentHistory>,
     
-    /// CHECK: User account for reinvestment
-    pub user: AccountInfo<'info>,
+    pub user: Signer<'info>,
 }
 
-/// Initialize the payment system with Lightning and USDC configurations
-pub fn initialize_payment_system(
-    ctx: Context<InitializePaymentSystem>,
-    lightning_config: LightningConfig,
-    usdc_config: UsdcConfig,
-) -> Result<()> {
-    let payment_system = &mut ctx.accounts.payment_system;
-    let multisig_wallet = ctx.accounts.multisig_wallet.key();
-    
-    payment_system.initialize(
-        lightning_config,
-        usdc_config,
-        multisig_wallet,
-        ctx.bumps.payment_system,
-    )?;
-    
-    msg!("Payment system initialized with Lightning and USDC support");
-    
-    Ok(())
+#[derive(Accounts)]
+pub struct GetUserPaymentHistory<'info> {
+    #[account(
+        seeds = [b"payment_history", payment_history.user.as_ref()],
+        bump = payment_history.bump
+    )]
+    pub payment_history: Account<'info, UserPaymentHistory>,
 }
 
-/// Initialize user payment preferences
-pub fn initialize_user_preferences(
-    ctx: Context<InitializeUserPreferences>,
-    default_method: PaymentMethod,
-) -> Result<()> {
-    let user_preferences = &mut ctx.accounts.user_preferences;
-    let user = ctx.accounts.user.key();
-    
-    user_preferences.initialize(
-        user,
-        default_method,
-        ctx.bumps.user_preferences,
-    )?;
-    
-    msg!("User payment preferences initialized for {}", user);
-    
-    Ok(())
+#[derive(Accounts)]
+pub struct GetPaymentRollups<'info> {
+    #[account(
+        seeds = [b"payment_system"],
+        bump = payment_system.bump
+    )]
+    pub payment_system: Account<'info, PaymentSystem>,
 }
 
-/// Create a payment request for reward distribution
-pub fn create_payment_request(
-    ctx: Context<CreatePaymentRequest>,
-    method: Option<PaymentMethod>,
-    amount: u64,
-    destination: String,
-) -> Result<()> {
-    let payment_system = &mut ctx.accounts.payment_system;
-    let user_preferences = &ctx.accounts.user_preferences;
-    let user_rewards = &mut ctx.accounts.user_rewards;
-    let user = ctx.accounts.user.key();
+/// Moves a user's finished requests left in the shared queue from before
+/// per-user histories into their history. Anyone may pay for the history.
+#[derive(Accounts)]
+pub struct MigratePaymentHistory<'info> {
+    #[account(
+        mut,
+        seeds = [b"payment_system"],
+        bump = payment_system.bump
+    )]
+    pub payment_system: Account<'info, PaymentSystem>,
     
-    // Verify user has sufficient rewards
-    if user_rewards.pending_rewards < amount {
-        return Err(VaultError::InsufficientRewards.into());
-    }
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = UserPaymentHistory::LEN,
+        seeds = [b"payment_history", user.key().as_ref()],
+        bump
+    )]
+    pub payment_history: Account<'info, UserPaymentHistory>,
     
-    // Use provided method or user's default
-    let payment_method = method.unwrap_or(user_preferences.default_method.clone());
+    /// CHECK: Only keys the history account
+    pub user: UncheckedAccount<'info>,
     
-    // Validate destination based on method and user preferences
-    let final_destination = match payment_method {
-        PaymentMethod::Lightning => {
-            if destination.is_empty() {
-                user_preferences.lightning_address.clone()
-                    .ok_or(VaultError::NoPaymentDestination)?
-            } else {
-                destination
-            }
-        },
-        PaymentMethod::USDC => {
-            if destination.is_empty() {
-                user_preferences.usdc_address
-                    .ok_or(VaultError::NoPaymentDestination)?
-                    .to_string()
-            } else {
-                destination
-            }
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePaymentConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"payment_system"],
+        bump = payment_system.bump
+    )]
+    pub payment_system: Account<'info, PaymentSystem>,
+    
+    #[account(
+        mut,
+        seeds = [b"multisig_wallet"],
+        bump = multisig_wallet.bump
+    )]
+    pub multisig_wallet: Account<'info, MultisigWallet>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+/// Record an AML screening of a payout destination, signed by a payment
+/// admin, operator or compliance signer
+#[derive(Accounts)]
+#[instruction(destination_hash: [u8; 32])]
+pub struct ScreenDestination<'info> {
+    #[account(
+        seeds = [b"payment_system"],
+        bump = payment_system.bump
+    )]
+    pub payment_system: Account<'info, PaymentSystem>,
+    
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = DestinationScreening::LEN,
+        seeds = [b"destination_screening", destination_hash.as_ref()],
+        bump
+    )]
+    pub destination_screening: Account<'info, DestinationScreening>,
+    
+    #[account(
+        seeds = [b"compliance_config"],
+        bump = compliance_config.bump
+    )]
+    pub compliance_config: Account<'info, ComplianceConfig>,
+    
+    #[account(
+        seeds = [b"multisig_wallet"],
+        bump = multisig_wallet.bump
+    )]
+    pub multisig_wallet: Account<'info, MultisigWallet>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreatePaymentSchedule<'info> {
+    #[account(
+        init,
+        payer = user,
+        space = PaymentSchedule::LEN,
+        seeds = [b"payment_schedule", user.key().as_ref()],
+        bump
+    )]
+    pub payment_schedule: Account<'info, PaymentSchedule>,
+    
+    #[account(
+        seeds = [b"payment_system"],
+        bump = payment_system.bump
+    )]
+    pub payment_system: Account<'info, PaymentSystem>,
+    
+    /// Created here so scheduled payments have a history to finish into
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = UserPaymentHistory::LEN,
+        seeds = [b"payment_history", user.key().as_ref()],
+        bump
+    )]
+    pub payment_history: Account<'info, UserPaymentHistory>,
+    
+    /// Created here so the crank can charge scheduled payments to the limits
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = UserPayoutVelocity::LEN,
+        seeds = [b"payout_velocity", user.key().as_ref()],
+        bump
+    )]
+    pub payout_velocity: Account<'info, UserPayoutVelocity>,
+    
+    /// The destination is resolved against the address book
+    #[account(
+        seeds = [b"user_preferences", user.key().as_ref()],
+        bump = user_preferences.bump
+    )]
+    pub user_preferences: Account<'info, UserPaymentPreferences>,
+    
+    /// The session setting up the schedule
+    #[account(
+        mut,
+        seeds = [b"user_auth", user.key().as_ref()],
+        bump = user_auth.bump
+    )]
+    pub user_auth: Account<'info, UserAuth>,
+    
+    #[account(
+        seeds = [b"auth_config"],
+        bump = auth_config.bump
+    )]
+    pub auth_config: Account<'info, AuthConfig>,
+    
+    /// The user's hardware wallet, for a per-run cap above their co-sign threshold
+    pub cosigner: Option<Signer<'info>>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePaymentSchedule<'info> {
+    #[account(
+        mut,
+        seeds = [b"payment_schedule", user.key().as_ref()],
+        bump = payment_schedule.bump
+    )]
+    pub payment_schedule: Account<'info, PaymentSchedule>,
+    
+    #[account(
+        seeds = [b"payment_system"],
+        bump = payment_system.bump
+    )]
+    pub payment_system: Account<'info, PaymentSystem>,
+    
+    #[account(
+        seeds = [b"user_preferences", user.key().as_ref()],
+        bump = user_preferences.bump
+    )]
+    pub user_preferences: Account<'info, UserPaymentPreferences>,
+    
+    /// The session changing the schedule
+    #[account(
+        mut,
+        seeds = [b"user_auth", user.key().as_ref()],
+        bump = user_auth.bump
+    )]
+    pub user_auth: Account<'info, UserAuth>,
+    
+    #[account(
+        seeds = [b"auth_config"],
+        bump = auth_config.bump
+    )]
+    pub auth_config: Account<'info, AuthConfig>,
+    
+    /// The user's hardware wallet, for a per-run cap above their co-sign threshold
+    pub cosigner: Option<Signer<'info>>,
+    
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelPaymentSchedule<'info> {
+    #[account(
+        mut,
+        close = user,
+        seeds = [b"payment_schedule", user.key().as_ref()],
+        bump = payment_schedule.bump
+    )]
+    pub payment_schedule: Account<'info, PaymentSchedule>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+/// Open a payment stream. Reward-funded streams need the funder's rewards,
+/// auth, KYC and payout velocity accounts, and pass the same gates as a
+/// claim. Treasury-funded ones are opened by a payment admin and wait for an
+/// `ApproveTreasuryStream` multisig transaction to fund them.
+#[derive(Accounts)]
+#[instruction(stream_id: u64)]
+pub struct CreatePaymentStream<'info> {
+    #[account(
+        init,
+        payer = funder,
+        space = PaymentStream::LEN,
+        seeds = [b"payment_stream", funder.key().as_ref(), stream_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub payment_stream: Account<'info, PaymentStream>,
+    
+    #[account(
+        mut,
+        seeds = [b"payment_system"],
+        bump = payment_system.bump
+    )]
+    pub payment_system: Account<'info, PaymentSystem>,
+    
+    #[account(
+        seeds = [b"emergency_state"],
+        bump = emergency_state.bump
+    )]
+    pub emergency_state: Account<'info, EmergencyState>,
+    
+    #[account(
+        mut,
+        seeds = [b"rewards", funder.key().as_ref()],
+        bump = funder_rewards.bump
+    )]
+    pub funder_rewards: Option<Account<'info, UserRewards>>,
+    
+    /// The funder's session must allow payments; large deposits use up a
+    /// ClaimRewards step-up proof
+    #[account(
+        mut,
+        seeds = [b"user_auth", funder.key().as_ref()],
+        bump = funder_auth.bump
+    )]
+    pub funder_auth: Option<Account<'info, UserAuth>>,
+    
+    #[account(
+        seeds = [b"auth_config"],
+        bump = auth_config.bump
+    )]
+    pub auth_config: Option<Account<'info, AuthConfig>>,
+    
+    /// A payments freeze stops the funder streaming rewards away
+    #[account(
+        seeds = [b"kyc_profile", funder.key().as_ref()],
+        bump = funder_kyc_profile.bump
+    )]
+    pub funder_kyc_profile: Option<Account<'info, KYCProfile>>,
+    
+    /// Created with the funder's first payment request or schedule; the
+    /// whole deposit counts against their daily payout limit
+    #[account(
+        mut,
+        seeds = [b"payout_velocity", funder.key().as_ref()],
+        bump = funder_velocity.bump
+    )]
+    pub funder_velocity: Option<Account<'info, UserPayoutVelocity>>,
+    
+    #[account(
+        seeds = [b"multisig_wallet"],
+        bump = multisig_wallet.bump
+    )]
+    pub multisig_wallet: Option<Account<'info, MultisigWallet>>,
+    
+    #[account(mut)]
+    pub funder: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawFromStream<'info> {
+    #[account(
+        mut,
+        seeds = [b"payment_stream", payment_stream.funder.as_ref(), payment_stream.stream_id.to_le_bytes().as_ref()],
+        bump = payment_stream.bump,
+        constraint = payment_stream.recipient == recipient.key() @ VaultError::UnauthorizedAccess
+    )]
+    pub payment_stream: Account<'info, PaymentStream>,
+    
+    #[account(
+        mut,
+        seeds = [b"payment_system"],
+        bump = payment_system.bump
+    )]
+    pub payment_system: Account<'info, PaymentSystem>,
+    
+    #[account(
+        seeds = [b"emergency_state"],
+        bump = emergency_state.bump
+    )]
+    pub emergency_state: Account<'info, EmergencyState>,
+    
+    #[account(
+        mut,
+        seeds = [b"rewards", recipient.key().as_ref()],
+        bump = recipient_rewards.bump
+    )]
+    pub recipient_rewards: Account<'info, UserRewards>,
+    
+    /// The recipient's session must allow payments; large withdrawals use
+    /// up a ClaimRewards step-up proof
+    #[account(
+        mut,
+        seeds = [b"user_auth", recipient.key().as_ref()],
+        bump = user_auth.bump
+    )]
+    pub user_auth: Account<'info, UserAuth>,
+    
+    #[account(
+        seeds = [b"auth_config"],
+        bump = auth_config.bump
+    )]
+    pub auth_config: Account<'info, AuthConfig>,
+    
+    /// A claims freeze stops the recipient withdrawing
+    #[account(
+        seeds = [b"kyc_profile", recipient.key().as_ref()],
+        bump = kyc_profile.bump
+    )]
+    pub kyc_profile: Account<'info, KYCProfile>,
+    
+    #[account(
+        init_if_needed,
+        payer = recipient,
+        space = UserPayoutVelocity::LEN,
+        seeds = [b"payout_velocity", recipient.key().as_ref()],
+        bump
+    )]
+    pub payout_velocity: Account<'info, UserPayoutVelocity>,
+    
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Cancel a payment stream, signed by its funder or, for treasury-funded
+/// streams, any payment admin. Takes the same optional funding accounts as
+/// `CreatePaymentStream`.
+#[derive(Accounts)]
+pub struct CancelPaymentStream<'info> {
+    #[account(
+        mut,
+        seeds = [b"payment_stream", payment_stream.funder.as_ref(), payment_stream.stream_id.to_le_bytes().as_ref()],
+        bump = payment_stream.bump
+    )]
+    pub payment_stream: Account<'info, PaymentStream>,
+    
+    #[account(
+        seeds = [b"payment_system"],
+        bump = payment_system.bump
+    )]
+    pub payment_system: Account<'info, PaymentSystem>,
+    
+    #[account(
+        seeds = [b"emergency_state"],
+        bump = emergency_state.bump
+    )]
+    pub emergency_state: Account<'info, EmergencyState>,
+    
+    #[account(
+        mut,
+        seeds = [b"rewards", payment_stream.recipient.as_ref()],
+        bump = recipient_rewards.bump
+    )]
+    pub recipient_rewards: Account<'info, UserRewards>,
+    
+    #[account(
+        mut,
+        seeds = [b"rewards", payment_stream.funder.as_ref()],
+        bump = funder_rewards.bump
+    )]
+    pub funder_rewards: Option<Account<'info, UserRewards>>,
+    
+    #[account(
+        mut,
+        seeds = [b"treasury"],
+        bump = treasury.bump
+    )]
+    pub treasury: Option<Account<'info, Treasury>>,
+    
+    #[account(
+        seeds = [b"multisig_wallet"],
+        bump = multisig_wallet.bump
+    )]
+    pub multisig_wallet: Option<Account<'info, MultisigWallet>>,
+    
+    pub authority: Signer<'info>,
+}
+
+/// Keeper crank over due schedules. Remaining accounts come in groups of
+/// five per schedule: the schedule, the user's rewards account, the user's
+/// KYC profile, the user's payout velocity and the user's payment
+/// preferences; the schedule, rewards and velocity are writable.
+#[derive(Accounts)]
+pub struct RunDueSchedules<'info> {
+    #[account(
+        mut,
+        seeds = [b"payment_system"],
+        bump = payment_system.bump
+    )]
+    pub payment_system: Account<'info, PaymentSystem>,
+    
+    #[account(
+        seeds = [b"emergency_state"],
+        bump = emergency_state.bump
+    )]
+    pub emergency_state: Account<'info, EmergencyState>,
+    
+    #[account(
+        seeds = [b"compliance_config"],
+        bump = compliance_config.bump
+    )]
+    pub compliance_config: Account<'info, ComplianceConfig>,
+    
+    pub keeper: Signer<'info>,
+}
+
+/// Keeper crank expiring overdue pending requests; see
+/// `expire_payment_requests` for the remaining accounts
+#[derive(Accounts)]
+pub struct ExpirePaymentRequests<'info> {
+    #[account(
+        mut,
+        seeds = [b"payment_system"],
+        bump = payment_system.bump
+    )]
+    pub payment_system: Account<'info, PaymentSystem>,
+    
+    pub keeper: Signer<'info>,
+}
+
+/// Keeper crank over due reinvestments; see `run_due_reinvestments` for the
+/// remaining accounts
+#[derive(Accounts)]
+pub struct RunDueReinvestments<'info> {
+    #[account(
+        seeds = [b"payment_system"],
+        bump = payment_system.bump
+    )]
+    pub payment_system: Account<'info, PaymentSystem>,
+    
+    #[account(
+        seeds = [b"emergency_state"],
+        bump = emergency_state.bump
+    )]
+    pub emergency_state: Account<'info, EmergencyState>,
+    
+    #[account(
+        seeds = [b"oracle"],
+        bump
+    )]
+    pub oracle_data: Account<'info, OracleData>,
+    
+    #[account(
+        seeds = [b"compliance_config"],
+        bump = compliance_config.bump
+    )]
+    pub compliance_config: Account<'info, ComplianceConfig>,
+    
+    pub keeper: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateUserPreferences<'info> {
+    #[account(
+        mut,
+        seeds = [b"user_preferences", user.key().as_ref()],
+        bump = user_preferences.bump
+    )]
+    pub user_preferences: Account<'info, UserPaymentPreferences>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+/// Address book changes that need a second factor from the user's `UserAuth`
+#[derive(Accounts)]
+pub struct ManageSavedDestinations<'info> {
+    #[account(
+        mut,
+        seeds = [b"user_preferences", user.key().as_ref()],
+        bump = user_preferences.bump
+    )]
+    pub user_preferences: Account<'info, UserPaymentPreferences>,
+    
+    #[account(
+        mut,
+        seeds = [b"user_auth", user.key().as_ref()],
+        bump = user_auth.bump
+    )]
+    pub user_auth: Account<'info, UserAuth>,
+    
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ProcessReinvestment<'info> {
+    #[account(
+        mut,
+        seeds = [b"payment_system"],
+        bump = payment_system.bump
+    )]
+    pub payment_system: Account<'info, PaymentSystem>,
+    
+    #[account(
+        seeds = [b"emergency_state"],
+        bump = emergency_state.bump
+    )]
+    pub emergency_state: Account<'info, EmergencyState>,
+    
+    #[account(
+        mut,
+        seeds = [b"user_preferences", user.key().as_ref()],
+        bump = user_preferences.bump
+    )]
+    pub user_preferences: Account<'info, UserPaymentPreferences>,
+    
+    #[account(
+        mut,
+        seeds = [b"rewards", user.key().as_ref()],
+        bump = user_rewards.bump
+    )]
+    pub user_rewards: Account<'info, UserRewards>,
+    
+    #[account(
+        mut,
+        seeds = [b"staking_pool"],
+        bump = staking_pool.bump
+    )]
+    pub staking_pool: Account<'info, StakingPool>,
+    
+    #[account(
+        mut,
+        seeds = [b"btc_commitment", user.key().as_ref()],
+        bump = btc_commitment.bump,
+        constraint = btc_commitment.user_address == user.key() @ VaultError::UnauthorizedSigner
+    )]
+    pub btc_commitment: Account<'info, BTCCommitment>,
+    
+    #[account(
+        seeds = [b"oracle"],
+        bump
+    )]
+    pub oracle_data: Account<'info, OracleData>,
+    
+    #[account(
+        seeds = [b"compliance_config"],
+        bump = compliance_config.bump
+    )]
+    pub compliance_config: Account<'info, ComplianceConfig>,
+    
+    /// Its region decides whether reinvestment is allowed
+    #[account(
+        seeds = [b"kyc_profile", user.key().as_ref()],
+        bump = kyc_profile.bump
+    )]
+    pub kyc_profile: Option<Account<'info, KYCProfile>>,
+    
+    /// CHECK: User account for reinvestment
+    pub user: AccountInfo<'info>,
+}
+
+/// Pay reinvested exposure back out as pending rewards
+#[derive(Accounts)]
+pub struct ReleaseReinvestment<'info> {
+    #[account(
+        seeds = [b"emergency_state"],
+        bump = emergency_state.bump
+    )]
+    pub emergency_state: Account<'info, EmergencyState>,
+    
+    #[account(
+        mut,
+        seeds = [b"rewards", user.key().as_ref()],
+        bump = user_rewards.bump
+    )]
+    pub user_rewards: Account<'info, UserRewards>,
+    
+    #[account(
+        mut,
+        seeds = [b"btc_commitment", user.key().as_ref()],
+        bump = btc_commitment.bump,
+        constraint = btc_commitment.user_address == user.key() @ VaultError::UnauthorizedSigner
+    )]
+    pub btc_commitment: Account<'info, BTCCommitment>,
+    
+    #[account(
+        seeds = [b"oracle"],
+        bump
+    )]
+    pub oracle_data: Account<'info, OracleData>,
+    
+    /// A claims freeze keeps reinvested rewards in place
+    #[account(
+        seeds = [b"kyc_profile", user.key().as_ref()],
+        bump = kyc_profile.bump
+    )]
+    pub kyc_profile: Account<'info, KYCProfile>,
+    
+    pub user: Signer<'info>,
+}
+
+/// Initialize the payment system with Lightning and USDC configurations
+pub fn initialize_payment_system(
+    ctx: Context<InitializePaymentSystem>,
+    lightning_config: LightningConfig,
+    usdc_config: UsdcConfig,
+) -> Result<()> {
+    let payment_system = &mut ctx.accounts.payment_system;
+    let multisig_wallet = ctx.accounts.multisig_wallet.key();
+    
+    payment_system.initialize(
+        lightning_config,
+        usdc_config,
+        multisig_wallet,
+        ctx.bumps.payment_system,
+    )?;
+    
+    msg!("Payment system initialized with Lightning and USDC support");
+    
+    Ok(())
+}
+
+/// Initialize user payment preferences
+pub fn initialize_user_preferences(
+    ctx: Context<InitializeUserPreferences>,
+    default_method: PaymentMethod,
+) -> Result<()> {
+    let user_preferences = &mut ctx.accounts.user_preferences;
+    let user = ctx.accounts.user.key();
+    
+    user_preferences.initialize(
+        user,
+        default_method,
+        ctx.bumps.user_preferences,
+    )?;
+    
+    msg!("User payment preferences initialized for {}", user);
+    
+    Ok(())
+}
+
+/// Save a payout destination. Consumes a destination change step-up proof,
+/// and the entry only becomes usable after a 24h delay.
+pub fn add_saved_destination(
+    ctx: Context<ManageSavedDestinations>,
+    method: PaymentMethod,
+    destination: String,
+) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    require_step_up(&mut ctx.accounts.user_auth, StepUpOperation::DestinationChange, 0, now)?;
+    
+    let user_preferences = &mut ctx.accounts.user_preferences;
+    let index = user_preferences.add_saved_destination(method, destination, now)?;
+    
+    msg!("Saved destination {} added for user {}, active from {}",
+         index, user_preferences.user,
+         user_preferences.saved_destinations[index as usize].active_from);
+    
+    Ok(())
+}
+
+/// Delete a saved destination. Takes effect immediately.
+pub fn remove_saved_destination(
+    ctx: Context<UpdateUserPreferences>,
+    index: u8,
+) -> Result<()> {
+    let user_preferences = &mut ctx.accounts.user_preferences;
+    user_preferences.remove_saved_destination(index)?;
+    
+    msg!("Saved destination {} removed for user {}", index, user_preferences.user);
+    
+    Ok(())
+}
+
+/// Turn the saved-destinations-only lock on or off. Consumes a destination
+/// change step-up proof either way, so a stolen key alone cannot lift it.
+pub fn set_saved_destinations_only(
+    ctx: Context<ManageSavedDestinations>,
+    enabled: bool,
+) -> Result<()> {
+    require_step_up(&mut ctx.accounts.user_auth, StepUpOperation::DestinationChange, 0, Clock::get()?.unix_timestamp)?;
+    
+    let user_preferences = &mut ctx.accounts.user_preferences;
+    user_preferences.saved_destinations_only = enabled;
+    
+    msg!("Saved destinations only {} for user {}",
+         if enabled { "enabled" } else { "disabled" }, user_preferences.user);
+    
+    Ok(())
+}
+
+fn require_step_up(
+    user_auth: &mut UserAuth,
+    operation: StepUpOperation,
+    amount: u64,
+    now: i64,
+) -> Result<()> {
+    require!(!user_auth.is_locked(), VaultError::AccountLocked);
+    user_auth.consume_step_up(operation, amount, now)?;
+    Ok(())
+}
+
+/// Gates a claim passes, for moving `amount` of a user's rewards through a
+/// stream: a session allowed to make payments, a step-up proof above the
+/// high-value threshold, and room under both daily payout limits
+fn require_stream_payout(
+    payment_system: &mut PaymentSystem,
+    user_auth: &mut UserAuth,
+    kyc_profile: &Account<KYCProfile>,
+    payout_velocity: &mut UserPayoutVelocity,
+    session_id: &str,
+    amount: u64,
+    now: i64,
+) -> Result<()> {
+    require_session_permission(user_auth, session_id, SessionPermission::Payment)?;
+    if user_auth.requires_2fa_for_operation("high_value", Some(amount)) {
+        require_step_up(user_auth, StepUpOperation::ClaimRewards, amount, now)?;
+    }
+    let enhanced_kyc = has_enhanced_kyc(Some(kyc_profile), user_auth.user)?;
+    payment_system.charge_stream_velocity(amount, &mut payout_velocity.window, enhanced_kyc, now)
+}
+
+/// Create a payment request for reward distribution. USDC and SPL token
+/// requests pass their method's treasury ATA as a remaining account, so the
+/// request can be checked against its float, along with the destination's
+/// screening while screening is enabled.
+///
+/// A client retrying with the same `client_idempotency_key` and parameters
+/// within a day gets the payment id it was first given, and nothing is
+/// claimed again. Returns the payment id.
+pub fn create_payment_request<'info>(
+    ctx: Context<'_, '_, 'info, 'info, CreatePaymentRequest<'info>>,
+    method: Option<PaymentMethod>,
+    amount: u64,
+    destination: String,
+    use_saved_destination: Option<u8>,
+    quote_id: Option<u64>,
+    client_idempotency_key: Option<[u8; 32]>,
+    session_id: String,
+) -> Result<u64> {
+    sync_reverification(&mut ctx.accounts.user_auth, &ctx.accounts.auth_config, Clock::get()?.unix_timestamp);
+    require_session_permission(&ctx.accounts.user_auth, &session_id, SessionPermission::Payment)?;
+    require_cosigner(&ctx.accounts.user_auth, ctx.accounts.cosigner.as_ref(), Some(amount))?;
+    ctx.accounts.emergency_state.require_not_paused(EmergencyScope::Payments)?;
+    ctx.accounts.kyc_profile.require_not_frozen(FrozenAction::Payments)?;
+
+    let payment_system = &mut ctx.accounts.payment_system;
+    let user_preferences = &ctx.accounts.user_preferences;
+    let user_rewards = &mut ctx.accounts.user_rewards;
+    let user = ctx.accounts.user.key();
+    
+    if ctx.accounts.payment_history.user == Pubkey::default() {
+        ctx.accounts.payment_history.initialize(user, ctx.bumps.payment_history);
+    }
+    if ctx.accounts.payout_velocity.user == Pubkey::default() {
+        ctx.accounts.payout_velocity.initialize(user, ctx.bumps.payout_velocity);
+    }
+    if ctx.accounts.payment_idempotency.user == Pubkey::default() {
+        ctx.accounts.payment_idempotency.initialize(user, ctx.bumps.payment_idempotency);
+    }
+    
+    let now = Clock::get()?.unix_timestamp;
+    let idempotency = match client_idempotency_key {
+        Some(key) => {
+            let params_hash = PaymentIdempotency::params_hash(
+                &method,
+                amount,
+                &destination,
+                use_saved_destination,
+                quote_id,
+            )?;
+            if let Some(payment_id) = ctx.accounts.payment_idempotency.lookup(&key, &params_hash, now)? {
+                msg!("Payment request {} already created for this idempotency key", payment_id);
+                return Ok(payment_id);
+            }
+            Some((key, params_hash))
+        },
+        None => None,
+    };
+    
+    // Deduct from pending rewards and tag the claim so a failed payment
+    // can be restored exactly once
+    let claim_id = user_rewards.begin_claim(amount)?;
+    
+    // Use provided method or user's default
+    let payment_method = method.unwrap_or(user_preferences.default_method.clone());
+    require_region_allows_method(&ctx.accounts.compliance_config, Some(&ctx.accounts.kyc_profile), &payment_method)?;
+    
+    // Resolve the destination from the address book or user preferences
+    let final_destination = user_preferences.resolve_destination(
+        &payment_method,
+        destination,
+        use_saved_destination,
+        now,
+    )?;
+    
+    // Honor the quoted protocol fee while the quote is valid
+    let quoted_fee_bps = match quote_id {
+        Some(quote_id) => {
+            let quote = ctx.accounts.payment_quote.as_ref()
+                .ok_or(VaultError::MissingRequiredAccount)?;
+            let honored = quote.honored_fee_bps(quote_id, &payment_method, amount, now)?;
+            if honored.is_none() {
+                msg!("Quote {} expired, using live pricing", quote_id);
+            }
+            honored
+        },
+        None => None,
+    };
+    
+    // High-risk destinations go through multisig approval whatever the amount
+    let high_risk = screen_payout_destination(payment_system, &final_destination, ctx.remaining_accounts, now)?;
+    
+    // So do risky operations
+    let destination_known = user_preferences.is_known_destination(&payment_method, &final_destination, now);
+    let risk = payment_risk(&mut ctx.accounts.user_auth, &ctx.accounts.auth_config, &session_id, amount, destination_known, now)?;
+    
+    // Create payment request
+    let payment_id = payment_system.create_payment_request(
+        user,
+        payment_method.clone(),
+        amount,
+        final_destination,
+        claim_id,
+        RequestTerms { quoted_fee_bps, high_risk, risk },
+    )?;
+    require_payment_float(payment_system, &payment_method, ctx.remaining_accounts)?;
+    
+    // Requests over a daily payout limit are held, not rejected
+    let enhanced_kyc = has_enhanced_kyc(Some(&ctx.accounts.kyc_profile), user)?;
+    payment_system.charge_velocity(payment_id, &mut ctx.accounts.payout_velocity.window, enhanced_kyc, now)?;
+    
+    user_rewards.last_claim_request = now;
+    
+    if let Some((key, params_hash)) = idempotency {
+        ctx.accounts.payment_idempotency.remember(key, params_hash, payment_id, now);
+    }
+    
+    msg!("Payment request {} created for user {} (amount: {}, claim: {})", 
+         payment_id, user, amount, claim_id);
+    
+    Ok(payment_id)
+}
+
+/// Split one claim across two or three payment methods, e.g. 70% over
+/// Lightning and 30% in USDC. Each part becomes its own payment request
+/// linked by a parent id, so a failed part refunds only its own slice.
+/// Treasury ATAs for token parts and the parts' destination screenings are
+/// passed as remaining accounts.
+pub fn create_split_payment_request<'info>(
+    ctx: Context<'_, '_, 'info, 'info, CreatePaymentRequest<'info>>,
+    amount: u64,
+    parts: Vec<SplitPart>,
+    session_id: String,
+) -> Result<()> {
+    sync_reverification(&mut ctx.accounts.user_auth, &ctx.accounts.auth_config, Clock::get()?.unix_timestamp);
+    require_session_permission(&ctx.accounts.user_auth, &session_id, SessionPermission::Payment)?;
+    require_cosigner(&ctx.accounts.user_auth, ctx.accounts.cosigner.as_ref(), Some(amount))?;
+    ctx.accounts.emergency_state.require_not_paused(EmergencyScope::Payments)?;
+    ctx.accounts.kyc_profile.require_not_frozen(FrozenAction::Payments)?;
+
+    let payment_system = &mut ctx.accounts.payment_system;
+    let user_preferences = &ctx.accounts.user_preferences;
+    let user = ctx.accounts.user.key();
+    
+    if ctx.accounts.payment_history.user == Pubkey::default() {
+        ctx.accounts.payment_history.initialize(user, ctx.bumps.payment_history);
+    }
+    if ctx.accounts.payout_velocity.user == Pubkey::default() {
+        ctx.accounts.payout_velocity.initialize(user, ctx.bumps.payout_velocity);
+    }
+    
+    // The parts share one claim; each restores only its own amount
+    let claim_id = ctx.accounts.user_rewards.begin_claim(amount)?;
+    
+    let now = Clock::get()?.unix_timestamp;
+    let parts = parts.into_iter()
+        .map(|part| {
+            let destination = user_preferences.resolve_destination(
+                &part.method,
+                part.destination,
+                part.use_saved_destination,
+                now,
+            )?;
+            Ok(SplitPart { destination, ..part })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let mut methods = Vec::<PaymentMethod>::new();
+    let mut high_risk = Vec::with_capacity(parts.len());
+    for part in &parts {
+        if !methods.contains(&part.method) {
+            require_region_allows_method(&ctx.accounts.compliance_config, Some(&ctx.accounts.kyc_profile), &part.method)?;
+            methods.push(part.method.clone());
+        }
+        high_risk.push(screen_payout_destination(payment_system, &part.destination, ctx.remaining_accounts, now)?);
+    }
+    
+    let destination_known = parts.iter()
+        .all(|part| user_preferences.is_known_destination(&part.method, &part.destination, now));
+    let risk = payment_risk(&mut ctx.accounts.user_auth, &ctx.accounts.auth_config, &session_id, amount, destination_known, now)?;
+    
+    let parent_id = payment_system.create_split_payment_request(user, amount, parts, claim_id, &high_risk, risk)?;
+    for method in &methods {
+        require_payment_float(payment_system, method, ctx.remaining_accounts)?;
+    }
+    
+    // Each part is charged to the payout limits, and held, on its own
+    let enhanced_kyc = has_enhanced_kyc(Some(&ctx.accounts.kyc_profile), user)?;
+    let part_ids = payment_system.payment_requests.iter()
+        .filter(|p| p.parent_id == Some(parent_id))
+        .map(|p| p.id)
+        .collect::<Vec<_>>();
+    for payment_id in part_ids {
+        payment_system.charge_velocity(payment_id, &mut ctx.accounts.payout_velocity.window, enhanced_kyc, now)?;
+    }
+    
+    ctx.accounts.user_rewards.last_claim_request = now;
+    
+    msg!("Split payment {} created for user {} (amount: {}, claim: {})",
+         parent_id, user, amount, claim_id);
+    
+    Ok(())
+}
+
+/// Score a payout request's operation risk and log it; at or above the
+/// configured threshold the request needs multisig approval
+fn payment_risk(
+    user_auth: &mut UserAuth,
+    auth_config: &AuthConfig,
+    session_id: &str,
+    amount: u64,
+    destination_known: bool,
+    now: i64,
+) -> Result<RiskAssessment> {
+    let score = compute_operation_risk(user_auth, session_id, "payment", amount, destination_known, now)?;
+    record_operation_risk(user_auth, session_id, "payment", score)?;
+    Ok(RiskAssessment {
+        score,
+        requires_approval: score >= auth_config.payment_approval_risk_threshold,
+    })
+}
+
+/// Price a payout before requesting it. The quote is returned and kept for
+/// `create_payment_request`, which honors its protocol fee until it expires.
+pub fn quote_payment(
+    ctx: Context<QuotePayment>,
+    method: PaymentMethod,
+    amount: u64,
+) -> Result<PaymentQuote> {
+    let payment_quote = &mut ctx.accounts.payment_quote;
+    payment_quote.user = ctx.accounts.user.key();
+    payment_quote.bump = ctx.bumps.payment_quote;
+    payment_quote.quote(&ctx.accounts.payment_system, method, amount, Clock::get()?.unix_timestamp)?;
+    
+    msg!("Quote {} for user {}: {} gross, {} fee, {} estimated network fee, valid until {}",
+         payment_quote.quote_id, payment_quote.user, payment_quote.gross_amount,
+         payment_quote.protocol_fee, payment_quote.network_fee, payment_quote.valid_until);
+    
+    Ok((**payment_quote).clone())
+}
+
+/// Process a payment request (Lightning or USDC)
+pub fn process_payment(
+    ctx: Context<ProcessPayment>,
+    payment_id: u64,
+) -> Result<()> {
+    ctx.accounts.emergency_state.require_not_paused(EmergencyScope::Payments)?;
+    ctx.accounts.emergency_state.require_not_paused(EmergencyScope::Treasury)?;
+
+    let payment_system = &mut ctx.accounts.payment_system;
+    
+    // Get payment request
+    let mut payment = payment_system.get_payment_request(payment_id)
+        .ok_or(VaultError::PaymentNotFound)?
+        .clone();
+    
+    // A request held by the daily payout limits goes ahead once its window rolls
+    if payment.status == PaymentStatus::PendingLimitReset {
+        let payout_velocity = ctx.accounts.payout_velocity.as_mut()
+            .ok_or(VaultError::MissingRequiredAccount)?;
+        if payout_velocity.user != payment.user {
+            return Err(VaultError::UnauthorizedAccess.into());
+        }
+        let enhanced_kyc = has_enhanced_kyc(ctx.accounts.kyc_profile.as_ref(), payment.user)?;
+        payment_system.release_held_payment(
+            payment_id,
+            &mut payout_velocity.window,
+            enhanced_kyc,
+            Clock::get()?.unix_timestamp,
+        )?;
+        payment.status = payment_system.get_payment_request(payment_id)
+            .ok_or(VaultError::PaymentNotFound)?
+            .status
+            .clone();
+    }
+    
+    // No funds move to an unscreened or sanctioned destination. One that has
+    // since screened high risk goes back for multisig approval.
+    let policy = payment_system.screening_policy.clone();
+    if policy.is_enabled() {
+        let screening = ctx.accounts.destination_screening.as_ref()
+            .ok_or(VaultError::DestinationNotScreened)?;
+        let high_risk = screening.requires_approval(&payment.destination_hash(), &policy, Clock::get()?.unix_timestamp)?;
+        if high_risk && !payment.multisig_required {
+            return payment_system.route_for_approval(payment_id);
+        }
+    }
+    
+    // Verify payment is ready for processing. Large payments wait for an
+    // executed multisig PaymentApproval transaction.
+    if payment.status == PaymentStatus::Pending && payment.multisig_required {
+        return Err(VaultError::PaymentApprovalRequired.into());
+    }
+    if !payment.is_processable() || payment.tokens_sent() {
+        return Err(VaultError::InvalidPaymentStatus.into());
+    }
+    payment.require_retry_due(Clock::get()?.unix_timestamp)?;
+    require_travel_rule_data(&ctx.accounts.compliance_config, ctx.accounts.kyc_profile.as_ref(), &payment)?;
+    
+    // Process based on payment method
+    let mut transferred_on_chain = None;
+    match payment.method {
+        PaymentMethod::Lightning => {
+            process_lightning_payment(payment_system, &payment)?;
+        },
+        PaymentMethod::USDC => {
+            let config = payment_system.usdc_config.clone();
+            transferred_on_chain = Some(ctx.accounts.transfer_usdc(&config, &payment)?);
+        },
+        PaymentMethod::SplToken { mint } => {
+            let config = payment_system.spl_token_config(&mint)?.clone();
+            ctx.accounts.transfer_spl_token(&config, &payment)?;
+            transferred_on_chain = Some(true);
+        },
+    }
+    
+    // Mark payment as processing
+    ctx.accounts.payment_system.process_payment(payment_id)?;
+    
+    // USDC and SPL tokens settle on-chain in this instruction, so record the
+    // outcome now. A short USDC treasury is a failed attempt the payment can
+    // retry; a short SPL treasury fails the whole instruction instead.
+    if let Some(transferred) = transferred_on_chain {
+        let failure_reason = (!transferred).then(|| "Insufficient treasury USDC balance".to_string());
+        let outcome = ctx.accounts.payment_system.complete_payment(
+            payment_id,
+            payment.claim_id,
+            transferred,
+            failure_reason,
+        )?;
+        if outcome == CompletionOutcome::DeadLettered {
+            let dead_letter_queue = ctx.accounts.dead_letter_queue.as_mut()
+                .ok_or(VaultError::MissingRequiredAccount)?;
+            let request = ctx.accounts.payment_system.take_dead_lettered(payment_id)
+                .ok_or(VaultError::InvalidPaymentStatus)?;
+            return dead_letter_queue.push(request, Clock::get()?.unix_timestamp);
+        }
+        if let Some(payment_history) = ctx.accounts.payment_history.as_mut() {
+            archive_if_final(&mut ctx.accounts.payment_system, payment_history, payment_id)?;
+        } else if ctx.accounts.payment_system.get_payment_request(payment_id)
+            .is_some_and(|p| p.status.is_final())
+        {
+            return Err(VaultError::MissingRequiredAccount.into());
+        }
+    }
+    
+    Ok(())
+}
+
+/// Record the result of a payment attempt. Completing the same claim twice is
+/// a no-op; failures back off before the next attempt, and once retries are
+/// exhausted the payment moves to the dead-letter queue.
+pub fn complete_payment(
+    ctx: Context<CompletePayment>,
+    payment_id: u64,
+    claim_id: u64,
+    success: bool,
+    failure_reason: Option<String>,
+) -> Result<()> {
+    require_payment_ops(&ctx.accounts.payment_system, &ctx.accounts.multisig_wallet, &ctx.accounts.processor)?;
+    
+    let payment_system = &mut ctx.accounts.payment_system;
+    let payment_history = &mut ctx.accounts.payment_history;
+    let dead_letter_queue = &mut ctx.accounts.dead_letter_queue;
+    
+    dead_letter_queue.bump = ctx.bumps.dead_letter_queue;
+    
+    match payment_system.get_payment_request(payment_id) {
+        Some(_) => {},
+        None => {
+            // Already finished and moved to the user's history
+            let record = payment_history.find(payment_id).ok_or(VaultError::PaymentNotFound)?;
+            if record.claim_id != claim_id {
+                return Err(VaultError::ClaimIdMismatch.into());
+            }
+            msg!("Payment {} already finalized, completion ignored", payment_id);
+            return Ok(());
+        },
+    }
+    
+    let outcome = payment_system.complete_payment(payment_id, claim_id, success, failure_reason)?;
+    
+    if outcome == CompletionOutcome::DeadLettered {
+        let request = payment_system.take_dead_lettered(payment_id)
+            .ok_or(VaultError::InvalidPaymentStatus)?;
+        return dead_letter_queue.push(request, Clock::get()?.unix_timestamp);
+    }
+    archive_if_final(payment_system, payment_history, payment_id)
+}
+
+/// Give a dead-lettered payment a fresh set of retries
+pub fn requeue_dead_letter(ctx: Context<RequeueDeadLetter>, payment_id: u64) -> Result<()> {
+    require_payment_ops(&ctx.accounts.payment_system, &ctx.accounts.multisig_wallet, &ctx.accounts.authority)?;
+    
+    let request = ctx.accounts.dead_letter_queue.take(payment_id)?;
+    ctx.accounts.payment_system.requeue(request)
+}
+
+/// Restore a dead-lettered payment's claimed amount to its user and move the
+/// payment to their history
+pub fn refund_dead_letter(ctx: Context<RefundDeadLetter>, payment_id: u64) -> Result<()> {
+    require_payment_ops(&ctx.accounts.payment_system, &ctx.accounts.multisig_wallet, &ctx.accounts.authority)?;
+    
+    let mut request = ctx.accounts.dead_letter_queue.take(payment_id)?;
+    let user_rewards = &mut ctx.accounts.user_rewards;
+    if request.user != user_rewards.user {
+        return Err(VaultError::UnauthorizedAccess.into());
+    }
+    
+    if let Some(amount) = request.refund()? {
+        user_rewards.refund_claim(amount)?;
+        msg!("Dead-lettered payment {} refunded, restored {} to user {}", payment_id, amount, request.user);
+        ctx.accounts.payment_system.settle_split_part(request.parent_id, false)?;
+    }
+    ctx.accounts.payment_history.record(&request);
+    
+    Ok(())
+}
+
+/// Cancel a payment request still awaiting approval. The claimed amount is
+/// restored by `refund_payment`.
+pub fn cancel_payment(ctx: Context<CancelPayment>, payment_id: u64) -> Result<()> {
+    ctx.accounts.payment_system.cancel_payment(payment_id, ctx.accounts.user.key())
+}
+
+/// Correct the destination of a payment still pending, without cancelling
+/// and claiming again. The destination resolves and screens as it would for
+/// a new request, so the saved-destinations lock still applies, and a
+/// destination change step-up proof covering the payment amount is consumed.
+pub fn replace_payment_destination<'info>(
+    ctx: Context<'_, '_, 'info, 'info, ReplacePaymentDestination<'info>>,
+    payment_id: u64,
+    new_destination: String,
+    use_saved_destination: Option<u8>,
+) -> Result<()> {
+    ctx.accounts.emergency_state.require_not_paused(EmergencyScope::Payments)?;
+    
+    let payment_system = &mut ctx.accounts.payment_system;
+    let user = ctx.accounts.user.key();
+    let now = Clock::get()?.unix_timestamp;
+    let (method, amount) = payment_system.get_payment_request(payment_id)
+        .filter(|p| p.user == user)
+        .map(|p| (p.method.clone(), p.amount))
+        .ok_or(VaultError::PaymentNotFound)?;
+    require_step_up(&mut ctx.accounts.user_auth, StepUpOperation::DestinationChange, amount, now)?;
+    
+    let destination = ctx.accounts.user_preferences.resolve_destination(
+        &method,
+        new_destination,
+        use_saved_destination,
+        now,
+    )?;
+    let high_risk = screen_payout_destination(payment_system, &destination, ctx.remaining_accounts, now)?;
+    payment_system.replace_payment_destination(payment_id, user, destination, high_risk, now)
+}
+
+/// Attach the hash of a payment's encrypted originator and beneficiary
+/// data, held off-chain by travel rule provider `provider_id`. Payouts over
+/// the user's regional threshold can't process without it.
+pub fn attach_travel_rule_data(
+    ctx: Context<AttachTravelRuleData>,
+    payment_id: u64,
+    data_hash: [u8; 32],
+    provider_id: u32,
+) -> Result<()> {
+    let payment_system = &mut ctx.accounts.payment_system;
+    let payment_user = payment_system.get_payment_request(payment_id)
+        .ok_or(VaultError::PaymentNotFound)?
+        .user;
+    if ctx.accounts.authority.key() != payment_user {
+        let multisig_wallet = ctx.accounts.multisig_wallet.as_ref()
+            .ok_or(VaultError::MissingRequiredAccount)?;
+        require_payment_ops(payment_system, multisig_wallet, &ctx.accounts.authority)?;
+    }
+    
+    payment_system.attach_travel_rule_data(payment_id, data_hash, provider_id, Clock::get()?.unix_timestamp)
+}
+
+/// Restore the claimed amount of a failed or cancelled payment to the user's
+/// pending rewards and move the payment to their history. Payments that
+/// exhaust their retries go to the dead-letter queue instead, so this is
+/// for cancelled and expired payments; refunding twice is a no-op.
+pub fn refund_payment(ctx: Context<RefundPayment>, payment_id: u64) -> Result<()> {
+    let payment_system = &mut ctx.accounts.payment_system;
+    let payment_history = &mut ctx.accounts.payment_history;
+    let user = ctx.accounts.user.key();
+    
+    match payment_system.get_payment_request(payment_id) {
+        Some(payment) if payment.user != user => {
+            return Err(VaultError::UnauthorizedAccess.into());
+        },
+        Some(_) => {},
+        None => {
+            // Already refunded and moved to the user's history
+            let record = payment_history.find(payment_id).ok_or(VaultError::PaymentNotFound)?;
+            if record.status != PaymentStatus::Refunded {
+                return Err(VaultError::InvalidPaymentStatus.into());
+            }
+            msg!("Payment {} already refunded", payment_id);
+            return Ok(());
         },
+    }
+    
+    if let Some(amount) = payment_system.refund_payment(payment_id)? {
+        ctx.accounts.user_rewards.refund_claim(amount)?;
+        msg!("Refund of payment {} restored {} to user {}", payment_id, amount, user);
+    }
+    archive_if_final(payment_system, payment_history, payment_id)
+}
+
+/// Page through a user's finished payments from sequence number `cursor`
+pub fn get_user_payment_history(ctx: Context<GetUserPaymentHistory>, cursor: u64) -> Result<PaymentHistoryPage> {
+    Ok(ctx.accounts.payment_history.page(cursor))
+}
+
+/// Daily per-method payment rollups from UTC day `from_day` on, a page at a
+/// time. Page on from the last returned day plus one.
+pub fn get_payment_rollups(ctx: Context<GetPaymentRollups>, from_day: i64) -> Result<Vec<DailyRollup>> {
+    Ok(ctx.accounts.payment_system.rollups_page(from_day, Clock::get()?.unix_timestamp))
+}
+
+/// Move a user's finished requests out of the shared queue into their
+/// history, oldest first. In-flight requests stay queued and move as they
+/// finish.
+pub fn migrate_payment_history(ctx: Context<MigratePaymentHistory>) -> Result<()> {
+    let user = ctx.accounts.user.key();
+    let payment_history = &mut ctx.accounts.payment_history;
+    if payment_history.user == Pubkey::default() {
+        payment_history.initialize(user, ctx.bumps.payment_history);
+    }
+    
+    let finalized = ctx.accounts.payment_system.take_finalized_for(user);
+    for request in &finalized {
+        payment_history.record(request);
+    }
+    
+    msg!("Moved {} finished payments to the history of user {}", finalized.len(), user);
+    
+    Ok(())
+}
+
+/// Pay out pending rewards on a fixed schedule, first at `first_run_at`.
+/// The destination is a saved entry if one is referenced, else the given
+/// wallet or the user's preferred one, as for a payment request. Needs a
+/// session with the Payment permission, and the co-signer when
+/// `max_per_period` is above the user's co-sign threshold.
+pub fn create_payment_schedule(
+    ctx: Context<CreatePaymentSchedule>,
+    method: PaymentMethod,
+    destination: Option<Pubkey>,
+    use_saved_destination: Option<u8>,
+    frequency_secs: i64,
+    max_per_period: u64,
+    first_run_at: i64,
+    session_id: String,
+) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    sync_reverification(&mut ctx.accounts.user_auth, &ctx.accounts.auth_config, now);
+    require_session_permission(&ctx.accounts.user_auth, &session_id, SessionPermission::Payment)?;
+    require_cosigner(&ctx.accounts.user_auth, ctx.accounts.cosigner.as_ref(), Some(max_per_period))?;
+    
+    let payment_schedule = &mut ctx.accounts.payment_schedule;
+    let user = ctx.accounts.user.key();
+    
+    if ctx.accounts.payment_history.user == Pubkey::default() {
+        ctx.accounts.payment_history.initialize(user, ctx.bumps.payment_history);
+    }
+    if ctx.accounts.payout_velocity.user == Pubkey::default() {
+        ctx.accounts.payout_velocity.initialize(user, ctx.bumps.payout_velocity);
+    }
+    
+    let destination = resolve_schedule_destination(
+        &ctx.accounts.user_preferences,
+        &method,
+        destination,
+        use_saved_destination,
+        now,
+    )?;
+    
+    payment_schedule.user = user;
+    payment_schedule.last_payment_id = None;
+    payment_schedule.created_at = now;
+    payment_schedule.bump = ctx.bumps.payment_schedule;
+    payment_schedule.configure(
+        &ctx.accounts.payment_system,
+        method,
+        destination,
+        frequency_secs,
+        max_per_period,
+        first_run_at,
+    )?;
+    
+    msg!("Payment schedule created for user {} (every {}s, first run at {})",
+         user, frequency_secs, first_run_at);
+    
+    Ok(())
+}
+
+/// Change any of a schedule's terms, leaving the rest as they are. A new
+/// method or destination is resolved against the address book again, and
+/// the co-signer is checked against the resulting `max_per_period`.
+pub fn update_payment_schedule(
+    ctx: Context<UpdatePaymentSchedule>,
+    method: Option<PaymentMethod>,
+    destination: Option<Pubkey>,
+    use_saved_destination: Option<u8>,
+    frequency_secs: Option<i64>,
+    max_per_period: Option<u64>,
+    next_run_at: Option<i64>,
+    session_id: String,
+) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    sync_reverification(&mut ctx.accounts.user_auth, &ctx.accounts.auth_config, now);
+    require_session_permission(&ctx.accounts.user_auth, &session_id, SessionPermission::Payment)?;
+    
+    let payment_schedule = &mut ctx.accounts.payment_schedule;
+    let retarget = method.is_some() || destination.is_some() || use_saved_destination.is_some();
+    let method = method.unwrap_or(payment_schedule.method.clone());
+    let destination = if retarget {
+        resolve_schedule_destination(
+            &ctx.accounts.user_preferences,
+            &method,
+            Some(destination.unwrap_or(payment_schedule.destination)),
+            use_saved_destination,
+            now,
+        )?
+    } else {
+        payment_schedule.destination
     };
+    let frequency_secs = frequency_secs.unwrap_or(payment_schedule.frequency_secs);
+    let max_per_period = max_per_period.unwrap_or(payment_schedule.max_per_period);
+    require_cosigner(&ctx.accounts.user_auth, ctx.accounts.cosigner.as_ref(), Some(max_per_period))?;
+    let next_run_at = next_run_at.unwrap_or(payment_schedule.next_run_at);
     
-    // Create payment request
-    let payment_id = payment_system.create_payment_request(
-        user,
-        payment_method,
-        amount,
-        final_destination,
+    payment_schedule.configure(
+        &ctx.accounts.payment_system,
+        method,
+        destination,
+        frequency_secs,
+        max_per_period,
+        next_run_at,
     )?;
     
-    // Deduct from pending rewards
-    user_rewards.pending_rewards = user_rewards.pending_rewards
-        .checked_sub(amount).ok_or(VaultError::ArithmeticOverflow)?;
-    user_rewards.last_claim_request = Clock::get()?.unix_timestamp;
+    msg!("Payment schedule updated for user {}", payment_schedule.user);
     
-    msg!("Payment request {} created for user {} (amount: {})", 
-         payment_id, user, amount);
+    Ok(())
+}
+
+/// Resolve a schedule's destination wallet through the user's address book,
+/// honoring the saved-destinations lock
+fn resolve_schedule_destination(
+    user_preferences: &UserPaymentPreferences,
+    method: &PaymentMethod,
+    destination: Option<Pubkey>,
+    use_saved_destination: Option<u8>,
+    now: i64,
+) -> Result<Pubkey> {
+    let destination = user_preferences.resolve_destination(
+        method,
+        destination.map(|wallet| wallet.to_string()).unwrap_or_default(),
+        use_saved_destination,
+        now,
+    )?;
+    destination.parse::<Pubkey>()
+        .map_err(|_| VaultError::InvalidSolanaAddress.into())
+}
+
+/// Stop scheduled payouts and reclaim the schedule's rent
+pub fn cancel_payment_schedule(ctx: Context<CancelPaymentSchedule>) -> Result<()> {
+    msg!("Payment schedule cancelled for user {}", ctx.accounts.user.key());
     
     Ok(())
 }
 
-/// Process a payment request (Lightning or USDC)
-pub fn process_payment(
-    ctx: Context<ProcessPayment>,
-    payment_id: u64,
+/// Open a stream unlocking `rate_per_second` to `recipient` between `start`
+/// and `end`. The full deposit is taken from the funding source up front.
+pub fn create_payment_stream(
+    ctx: Context<CreatePaymentStream>,
+    stream_id: u64,
+    recipient: Pubkey,
+    funding: StreamFunding,
+    rate_per_second: u64,
+    start: i64,
+    end: i64,
+    session_id: String,
 ) -> Result<()> {
-    let payment_system = &mut ctx.accounts.payment_system;
-    let treasury = &mut ctx.accounts.treasury;
+    ctx.accounts.emergency_state.require_not_paused(EmergencyScope::Payments)?;
     
-    // Get payment request
-    let payment = payment_system.get_payment_request(payment_id)
-        .ok_or(VaultError::PaymentNotFound)?
-        .clone();
+    let now = Clock::get()?.unix_timestamp;
+    let payment_stream = &mut ctx.accounts.payment_stream;
+    payment_stream.funder = ctx.accounts.funder.key();
+    payment_stream.stream_id = stream_id;
+    payment_stream.bump = ctx.bumps.payment_stream;
+    let deposited = payment_stream.open(funding.clone(), recipient, rate_per_second, start, end, now)?;
     
-    // Verify payment is ready for processing
-    if payment.status != PaymentStatus::Pending && payment.status != PaymentStatus::Processing {
-        return Err(VaultError::InvalidPaymentStatus.into());
+    match funding {
+        StreamFunding::Rewards => {
+            let user_auth = ctx.accounts.funder_auth.as_mut().ok_or(VaultError::MissingRequiredAccount)?;
+            let auth_config = ctx.accounts.auth_config.as_ref().ok_or(VaultError::MissingRequiredAccount)?;
+            let kyc_profile = ctx.accounts.funder_kyc_profile.as_ref().ok_or(VaultError::MissingRequiredAccount)?;
+            let payout_velocity = ctx.accounts.funder_velocity.as_mut().ok_or(VaultError::MissingRequiredAccount)?;
+            sync_reverification(user_auth, auth_config, now);
+            kyc_profile.require_not_frozen(FrozenAction::Payments)?;
+            require_stream_payout(
+                &mut ctx.accounts.payment_system,
+                user_auth,
+                kyc_profile,
+                payout_velocity,
+                &session_id,
+                deposited,
+                now,
+            )?;
+            ctx.accounts.funder_rewards.as_mut()
+                .ok_or(VaultError::MissingRequiredAccount)?
+                .debit(deposited)?;
+        },
+        StreamFunding::Treasury => {
+            // Funded from the treasury only once the multisig approves it
+            let multisig_wallet = ctx.accounts.multisig_wallet.as_ref()
+                .ok_or(VaultError::MissingRequiredAccount)?;
+            require_payment_admin(&ctx.accounts.payment_system, multisig_wallet, &ctx.accounts.funder)?;
+        },
     }
     
-    // Process based on payment method
-    match payment.method {
-        PaymentMethod::Lightning => {
-            process_lightning_payment(payment_system, &payment)?;
+    msg!("Payment stream {} opened by {} for {}: {} per second from {} to {} ({} deposited)",
+         stream_id, payment_stream.funder, recipient, rate_per_second, start, end, deposited);
+    
+    Ok(())
+}
+
+/// Move unlocked stream funds into the recipient's pending rewards. Frozen
+/// during a payments pause; unlocking carries on meanwhile.
+pub fn withdraw_from_stream(
+    ctx: Context<WithdrawFromStream>,
+    amount: u64,
+    session_id: String,
+) -> Result<()> {
+    ctx.accounts.emergency_state.require_not_paused(EmergencyScope::Payments)?;
+    
+    let now = Clock::get()?.unix_timestamp;
+    let recipient = ctx.accounts.recipient.key();
+    if ctx.accounts.payout_velocity.user == Pubkey::default() {
+        ctx.accounts.payout_velocity.initialize(recipient, ctx.bumps.payout_velocity);
+    }
+    sync_reverification(&mut ctx.accounts.user_auth, &ctx.accounts.auth_config, now);
+    ctx.accounts.kyc_profile.require_not_frozen(FrozenAction::Claims)?;
+    require_stream_payout(
+        &mut ctx.accounts.payment_system,
+        &mut ctx.accounts.user_auth,
+        &ctx.accounts.kyc_profile,
+        &mut ctx.accounts.payout_velocity,
+        &session_id,
+        amount,
+        now,
+    )?;
+    
+    let payment_stream = &mut ctx.accounts.payment_stream;
+    payment_stream.withdraw(amount, now)?;
+    ctx.accounts.recipient_rewards.credit(amount)?;
+    
+    msg!("Withdrew {} from payment stream {} ({} of {} withdrawn)",
+         amount, payment_stream.stream_id, payment_stream.withdrawn, payment_stream.deposited);
+    
+    Ok(())
+}
+
+/// Stop a stream: the recipient keeps everything unlocked so far and the
+/// still-locked remainder goes back to the funding source
+pub fn cancel_payment_stream(ctx: Context<CancelPaymentStream>) -> Result<()> {
+    ctx.accounts.emergency_state.require_not_paused(EmergencyScope::Payments)?;
+    
+    let payment_stream = &mut ctx.accounts.payment_stream;
+    let authority = ctx.accounts.authority.key();
+    if authority != payment_stream.funder {
+        if payment_stream.funding != StreamFunding::Treasury {
+            return Err(VaultError::UnauthorizedAccess.into());
+        }
+        let multisig_wallet = ctx.accounts.multisig_wallet.as_ref()
+            .ok_or(VaultError::MissingRequiredAccount)?;
+        require_payment_admin(&ctx.accounts.payment_system, multisig_wallet, &ctx.accounts.authority)?;
+    }
+    
+    let (recipient_due, funder_refund) = payment_stream.cancel(Clock::get()?.unix_timestamp)?;
+    ctx.accounts.recipient_rewards.credit(recipient_due)?;
+    match payment_stream.funding {
+        StreamFunding::Rewards => {
+            ctx.accounts.funder_rewards.as_mut()
+                .ok_or(VaultError::MissingRequiredAccount)?
+                .credit(funder_refund)?;
         },
-        PaymentMethod::USDC => {
-            process_usdc_payment(
-                ctx.accounts.treasury_usdc_ata.as_ref()
-                    .ok_or(VaultError::MissingTokenAccount)?,
-                ctx.accounts.recipient_usdc_ata.as_ref()
-                    .ok_or(VaultError::MissingTokenAccount)?,
-                ctx.accounts.token_program.as_ref()
-                    .ok_or(VaultError::MissingTokenProgram)?,
-                &payment,
-                treasury,
-            )?;
+        StreamFunding::Treasury => {
+            ctx.accounts.treasury.as_mut()
+                .ok_or(VaultError::MissingRequiredAccount)?
+                .return_user_rewards(funder_refund)?;
         },
     }
     
-    // Mark payment as processing
-    payment_system.process_payment(payment_id)?;
+    msg!("Payment stream {} cancelled: {} to recipient {}, {} back to funder",
+         payment_stream.stream_id, recipient_due, payment_stream.recipient, funder_refund);
+    
+    Ok(())
+}
+
+/// Create a payment request for every due schedule passed in. Schedules
+/// whose user's payments are frozen, or whose method the user's region doesn't
+/// allow, are left due; those whose pending rewards are
+/// below the method minimum skip this period.
+pub fn run_due_schedules<'info>(
+    ctx: Context<'_, '_, 'info, 'info, RunDueSchedules<'info>>,
+) -> Result<()> {
+    ctx.accounts.emergency_state.require_not_paused(EmergencyScope::Payments)?;
+    
+    let payment_system = &mut ctx.accounts.payment_system;
+    let now = Clock::get()?.unix_timestamp;
+    
+    let groups = ctx.remaining_accounts.chunks_exact(5);
+    if !groups.remainder().is_empty() {
+        return Err(VaultError::MissingRequiredAccount.into());
+    }
+    
+    let mut created = 0;
+    for group in groups {
+        let mut payment_schedule = Account::<PaymentSchedule>::try_from(&group[0])?;
+        let mut user_rewards = Account::<UserRewards>::try_from(&group[1])?;
+        let kyc_profile = Account::<KYCProfile>::try_from(&group[2])?;
+        let mut payout_velocity = Account::<UserPayoutVelocity>::try_from(&group[3])?;
+        let user_preferences = Account::<UserPaymentPreferences>::try_from(&group[4])?;
+        let user = payment_schedule.user;
+        if user_rewards.user != user
+            || kyc_profile.user != user
+            || payout_velocity.user != user
+            || user_preferences.user != user
+        {
+            return Err(VaultError::UnauthorizedAccess.into());
+        }
+        
+        if !payment_schedule.is_due(now) {
+            continue;
+        }
+        if kyc_profile.require_not_frozen(FrozenAction::Payments).is_err() {
+            msg!("Schedule for user {} held: payments frozen", user);
+            continue;
+        }
+        if require_region_allows_method(&ctx.accounts.compliance_config, Some(&kyc_profile), &payment_schedule.method).is_err() {
+            msg!("Schedule for user {} held: {:?} payouts not allowed in the user's region", user, payment_schedule.method);
+            continue;
+        }
+        // The lock may have been turned on, or the entry deleted, since the
+        // schedule was set up
+        if !user_preferences.allows_destination(&payment_schedule.method, &payment_schedule.destination.to_string(), now) {
+            msg!("Schedule for user {} held: destination is not a saved destination", user);
+            continue;
+        }
+        
+        let limits = payment_system.payment_limits(&payment_schedule.method)?;
+        match payment_schedule.payout_amount(user_rewards.pending_rewards, limits) {
+            Some(amount) => {
+                let claim_id = user_rewards.begin_claim(amount)?;
+                // Screened when processed, which routes a high-risk
+                // destination for approval
+                let payment_id = payment_system.create_payment_request(
+                    user,
+                    payment_schedule.method.clone(),
+                    amount,
+                    payment_schedule.destination.to_string(),
+                    claim_id,
+                    RequestTerms::default(),
+                )?;
+                let enhanced_kyc = has_enhanced_kyc(Some(&kyc_profile), user)?;
+                payment_system.charge_velocity(payment_id, &mut payout_velocity.window, enhanced_kyc, now)?;
+                user_rewards.last_claim_request = now;
+                payment_schedule.last_payment_id = Some(payment_id);
+                created += 1;
+                
+                msg!("Scheduled payment {} created for user {} (amount: {}, claim: {})",
+                     payment_id, user, amount, claim_id);
+            },
+            None => {
+                msg!("Schedule for user {} skipped: {} pending is below the minimum",
+                     user, user_rewards.pending_rewards);
+            },
+        }
+        
+        payment_schedule.advance(now);
+        payment_schedule.exit(ctx.program_id)?;
+        user_rewards.exit(ctx.program_id)?;
+        payout_velocity.exit(ctx.program_id)?;
+    }
+    
+    msg!("Created {} scheduled payment requests", created);
+    
+    Ok(())
+}
+
+/// Accept payouts in an SPL token mint, or update its limits, fee and
+/// treasury account. Admin signers only.
+pub fn configure_spl_token_payment(
+    ctx: Context<UpdatePaymentConfig>,
+    config: SplTokenConfig,
+) -> Result<()> {
+    require_payment_admin(&ctx.accounts.payment_system, &ctx.accounts.multisig_wallet, &ctx.accounts.authority)?;
+    
+    let mint = config.mint;
+    ctx.accounts.payment_system.set_spl_token_config(config)?;
+    
+    msg!("SPL token payments configured for mint {}", mint);
+    
+    Ok(())
+}
+
+/// Stop accepting payouts in an SPL token mint. Admin signers only.
+pub fn remove_spl_token_payment(
+    ctx: Context<UpdatePaymentConfig>,
+    mint: Pubkey,
+) -> Result<()> {
+    require_payment_admin(&ctx.accounts.payment_system, &ctx.accounts.multisig_wallet, &ctx.accounts.authority)?;
+    
+    ctx.accounts.payment_system.remove_spl_token_config(&mint)?;
+    
+    msg!("SPL token payments removed for mint {}", mint);
+    
+    Ok(())
+}
+
+/// Set the daily payout limits per user, per Enhanced KYC user and across
+/// the protocol. Admin signers only.
+pub fn set_payout_velocity_limits(
+    ctx: Context<UpdatePaymentConfig>,
+    limits: VelocityLimits,
+) -> Result<()> {
+    require_payment_admin(&ctx.accounts.payment_system, &ctx.accounts.multisig_wallet, &ctx.accounts.authority)?;
+    
+    msg!("Daily payout limits set: {} per user, {} per Enhanced KYC user, {} protocol-wide",
+         limits.user_daily_limit, limits.enhanced_user_daily_limit, limits.protocol_daily_limit);
+    ctx.accounts.payment_system.set_velocity_limits(limits)
+}
+
+/// Set how long destination screenings stay valid and the risk score from
+/// which payouts need multisig approval. A zero TTL turns screening off.
+pub fn set_screening_policy(
+    ctx: Context<UpdatePaymentConfig>,
+    policy: ScreeningPolicy,
+) -> Result<()> {
+    require_payment_admin(&ctx.accounts.payment_system, &ctx.accounts.multisig_wallet, &ctx.accounts.authority)?;
+    
+    msg!("Destination screening policy set: {}s TTL, risk threshold {}",
+         policy.ttl_seconds, policy.risk_threshold);
+    ctx.accounts.payment_system.set_screening_policy(policy)
+}
+
+/// Cache an AML screening of the payout destination hashing to
+/// `destination_hash`, e.g. from Chainalysis. A `sanctions_proof` proving
+/// the destination is on the sanctions list blocks it whatever its score.
+pub fn screen_destination(
+    ctx: Context<ScreenDestination>,
+    destination_hash: [u8; 32],
+    screening: AMLScreening,
+    sanctions_proof: Option<Vec<[u8; 32]>>,
+) -> Result<()> {
+    require_payment_ops(&ctx.accounts.payment_system, &ctx.accounts.multisig_wallet, &ctx.accounts.authority)?;
+    
+    let now = Clock::get()?.unix_timestamp;
+    let sanctions = ctx.accounts.compliance_config.sanctions_verdict(&destination_hash, sanctions_proof.as_deref(), now)?;
+    let destination_screening = &mut ctx.accounts.destination_screening;
+    destination_screening.bump = ctx.bumps.destination_screening;
+    destination_screening.record(
+        destination_hash,
+        &screening,
+        sanctions,
+        &ctx.accounts.payment_system.screening_policy,
+        ctx.accounts.authority.key(),
+        now,
+    )?;
+    
+    msg!("Destination screened by {}: risk score {}, sanctions match {}, manual review {}, valid until {}",
+         destination_screening.screened_by, destination_screening.risk_score,
+         destination_screening.sanctions_match, destination_screening.manual_review,
+         destination_screening.expires_at);
     
     Ok(())
 }
 
 // Helper functions for payment processing
 
+/// Reinvest the configured share of pending rewards as virtual committed
+/// sats at `btc_price`, clamped to the commitment's reinvestment cap. Only
+/// the rewards actually converted are spent.
+fn reinvest_rewards(
+    user_preferences: &UserPaymentPreferences,
+    user_rewards: &mut UserRewards,
+    btc_commitment: &mut BTCCommitment,
+    btc_price: u64,
+    now: i64,
+) -> Result<()> {
+    let config = &user_preferences.reinvestment_config;
+    let requested_amount = config.reinvestable_amount(user_rewards.pending_rewards)
+        .ok_or(VaultError::InsufficientReinvestmentAmount)?;
+    
+    let requested_sats = BTCCommitment::rewards_to_sats(requested_amount, btc_price)?;
+    let sats = requested_sats.min(btc_commitment.reinvestment_headroom());
+    if sats == 0 {
+        return Err(VaultError::CommitmentLimitExceeded.into());
+    }
+    let reinvested_amount = if sats == requested_sats {
+        requested_amount
+    } else {
+        BTCCommitment::sats_to_rewards(sats, btc_price)?
+    };
+    
+    btc_commitment.add_reinvested_sats(sats, now)?;
+    
+    user_rewards.pending_rewards = user_rewards.pending_rewards
+        .checked_sub(reinvested_amount).ok_or(VaultError::ArithmeticOverflow)?;
+    user_rewards.total_reinvested = user_rewards.total_reinvested
+        .checked_add(reinvested_amount).ok_or(VaultError::ArithmeticOverflow)?;
+    
+    emit!(ReinvestmentExecuted {
+        user: user_preferences.user,
+        reinvested_amount,
+        sats,
+        total_reinvested_sats: btc_commitment.reinvested_sats,
+        timestamp: now,
+    });
+    if user_preferences.notification_preferences.reinvestment_executed {
+        emit!(ReinvestmentNotification {
+            user: user_preferences.user,
+            reinvested_amount,
+            sats,
+            pending_rewards: user_rewards.pending_rewards,
+            next_reinvestment_at: now.saturating_add(config.compound_frequency as i64),
+        });
+    }
+    
+    msg!("Reinvested {} rewards as {} virtual sats for user {} (total reinvested sats: {})",
+         reinvested_amount, sats, user_preferences.user, btc_commitment.reinvested_sats);
+    
+    Ok(())
+}
+
+/// Move a payment that reached a final state out of the shared queue and
+/// into its user's history
+fn archive_if_final(
+    payment_system: &mut PaymentSystem,
+    payment_history: &mut UserPaymentHistory,
+    payment_id: u64,
+) -> Result<()> {
+    if let Some(request) = payment_system.take_finalized(payment_id) {
+        if request.user != payment_history.user {
+            return Err(VaultError::UnauthorizedAccess.into());
+        }
+        payment_history.record(&request);
+    }
+    Ok(())
+}
+
+/// Whether payouts to `destination` need multisig approval, from its
+/// `DestinationScreening` found among `accounts`. Always false while
+/// screening is off.
+fn screen_payout_destination<'info>(
+    payment_system: &PaymentSystem,
+    destination: &str,
+    accounts: &'info [AccountInfo<'info>],
+    now: i64,
+) -> Result<bool> {
+    let policy = &payment_system.screening_policy;
+    if !policy.is_enabled() {
+        return Ok(false);
+    }
+    let destination_hash = PaymentRequest::hash_destination(destination);
+    let (address, _) = Pubkey::find_program_address(
+        &[b"destination_screening", destination_hash.as_ref()],
+        &crate::ID,
+    );
+    let info = accounts.iter()
+        .find(|a| a.key() == address)
+        .ok_or(VaultError::DestinationNotScreened)?;
+    Account::<DestinationScreening>::try_from(info)?.requires_approval(&destination_hash, policy, now)
+}
+
+/// Check the requests queued against `method` fit its float. Token methods
+/// read the live balance of their treasury ATA from `float_accounts`.
+fn require_payment_float<'info>(
+    payment_system: &mut PaymentSystem,
+    method: &PaymentMethod,
+    float_accounts: &'info [AccountInfo<'info>],
+) -> Result<()> {
+    let token_balance = match payment_system.float_account(method)? {
+        Some((treasury_ata, _)) => {
+            let info = float_accounts.iter()
+                .find(|a| a.key() == treasury_ata)
+                .ok_or(VaultError::MissingRequiredAccount)?;
+            Some(Account::<TokenAccount>::try_from(info)?.amount)
+        },
+        None => None,
+    };
+    payment_system.require_float(method, token_balance)?;
+    Ok(())
+}
+
+/// Reject payout methods the user's region doesn't allow
+fn require_region_allows_method(
+    compliance_config: &ComplianceConfig,
+    kyc_profile: Option<&Account<KYCProfile>>,
+    method: &PaymentMethod,
+) -> Result<()> {
+    if *method == PaymentMethod::Lightning {
+        check_region_policy(compliance_config, kyc_profile.map(|p| &**p), RegionFeature::LightningPayout)?;
+    }
+    Ok(())
+}
+
+/// Whether `kyc_profile` grants `user` the Enhanced KYC payout limit
+fn has_enhanced_kyc(kyc_profile: Option<&Account<KYCProfile>>, user: Pubkey) -> Result<bool> {
+    match kyc_profile {
+        Some(profile) if profile.user != user => Err(VaultError::UnauthorizedAccess.into()),
+        Some(profile) => Ok(profile.status == KYCStatus::Approved && profile.tier >= KYCTier::Enhanced),
+        None => Ok(false),
+    }
+}
+
+/// Reject a payout over its user's travel rule threshold that has no travel
+/// rule data attached. Without the user's KYC profile the region is unknown
+/// and the lowest threshold applies.
+fn require_travel_rule_data(
+    compliance_config: &ComplianceConfig,
+    kyc_profile: Option<&Account<KYCProfile>>,
+    payment: &PaymentRequest,
+) -> Result<()> {
+    let region = match kyc_profile {
+        Some(profile) if profile.user != payment.user => return Err(VaultError::UnauthorizedAccess.into()),
+        Some(profile) => profile.region.as_ref(),
+        None => None,
+    };
+    if compliance_config.requires_travel_rule(region, payment.amount) && payment.travel_rule.is_none() {
+        return Err(VaultError::TravelRuleDataRequired.into());
+    }
+    Ok(())
+}
+
+fn require_payment_admin(
+    payment_system: &PaymentSystem,
+    multisig_wallet: &Account<MultisigWallet>,
+    authority: &Signer,
+) -> Result<()> {
+    let authority_key = authority.key();
+    let is_admin = multisig_wallet.signers.iter()
+        .any(|s| s.pubkey == authority_key && s.is_active && s.role == SignerRole::Admin);
+    if payment_system.multisig_wallet != multisig_wallet.key() || !is_admin {
+        return Err(VaultError::UnauthorizedAccess.into());
+    }
+    Ok(())
+}
+
+/// Admins, operators and compliance signers may process payments and work
+/// the dead-letter queue
+fn require_payment_ops(
+    payment_system: &PaymentSystem,
+    multisig_wallet: &Account<MultisigWallet>,
+    authority: &Signer,
+) -> Result<()> {
+    if payment_system.multisig_wallet != multisig_wallet.key() || !multisig_wallet.is_payment_operator(&authority.key()) {
+        return Err(VaultError::UnauthorizedAccess.into());
+    }
+    Ok(())
+}
+
 fn process_lightning_payment(
     payment_system: &mut PaymentSystem,
     payment: &PaymentRequest,
@@ -357,45 +2546,179 @@ fn process_lightning_payment(
     Ok(())
 }
 
-fn process_usdc_payment(
-    treasury_ata: &Account<TokenAccount>,
-    recipient_ata: &Account<TokenAccount>,
-    token_program: &Program<Token>,
-    payment: &PaymentRequest,
-    treasury: &mut Account<Treasury>,
+/// Reinvest pending rewards into virtual committed sats at the oracle price
+pub fn process_reinvestment(
+    ctx: Context<ProcessReinvestment>,
 ) -> Result<()> {
-    msg!("Processing USDC payment: {} USDC to {}", 
-         payment.amount, payment.destination);
-    
-    // Verify sufficient USDC balance in treasury
-    if treasury_ata.amount < payment.amount {
-        return Err(VaultError::InsufficientBalance.into());
+    ctx.accounts.emergency_state.require_not_paused(EmergencyScope::Payments)?;
+    ctx.accounts.emergency_state.require_not_paused(EmergencyScope::Staking)?;
+
+    if ctx.accounts.payment_system.emergency_pause {
+        return Err(VaultError::PaymentSystemPaused.into());
     }
     
-    // Create transfer instruction
-    let transfer_instruction = Transfer {
-        from: treasury_ata.to_account_info(),
-        to: recipient_ata.to_account_info(),
-        authority: treasury.to_account_info(),
-    };
+    let user_preferences = &ctx.accounts.user_preferences;
+    let config = &user_preferences.reinvestment_config;
+    let user_rewards = &mut ctx.accounts.user_rewards;
+    let btc_commitment = &mut ctx.accounts.btc_commitment;
+    let oracle_data = &ctx.accounts.oracle_data;
+    let now = Clock::get()?.unix_timestamp;
     
-    let treasury_seeds = &[
-        b"treasury".as_ref(),
-        &[treasury.bump],
-    ];
-    let signer_seeds = &[&treasury_seeds[..]];
-    
-    // Execute USDC transfer
-    token::transfer(
-        CpiContext::new_with_signer(
-            token_program.to_account_info(),
-            transfer_instruction,
-            signer_seeds,
-        ),
-        payment.amount,
+    if !config.enabled {
+        return Err(VaultError::ReinvestmentNotEnabled.into());
+    }
+    
+    check_region_policy(
+        &ctx.accounts.compliance_config,
+        ctx.accounts.kyc_profile.as_deref(),
+        RegionFeature::Reinvestment,
     )?;
     
-    msg!("USDC transfer completed: {} USDC", payment.amount);
+    // The commitment's last reinvestment time paces both this and the crank
+    if !btc_commitment.is_reinvestment_due(config.compound_frequency, now) {
+        return Err(VaultError::ReinvestmentTooFrequent.into());
+    }
+    
+    if config.reinvestable_amount(user_rewards.pending_rewards).is_none() {
+        return Err(VaultError::InsufficientReinvestmentAmount.into());
+    }
+    
+    if oracle_data.is_stale()? {
+        return Err(VaultError::OraclePriceUnavailable.into());
+    }
+    
+    reinvest_rewards(user_preferences, user_rewards, btc_commitment, oracle_data.btc_price_usd, now)
+}
+
+/// Convert `sats` of reinvested exposure back into pending rewards at the
+/// oracle price. This is the only way reinvested value leaves the commitment;
+/// it is then claimed like any other reward.
+pub fn release_reinvestment(
+    ctx: Context<ReleaseReinvestment>,
+    sats: u64,
+) -> Result<()> {
+    ctx.accounts.emergency_state.require_not_paused(EmergencyScope::Payments)?;
+    ctx.accounts.kyc_profile.require_not_frozen(FrozenAction::Claims)?;
+    
+    let oracle_data = &ctx.accounts.oracle_data;
+    if oracle_data.is_stale()? {
+        return Err(VaultError::OraclePriceUnavailable.into());
+    }
+    
+    let btc_commitment = &mut ctx.accounts.btc_commitment;
+    btc_commitment.release_reinvested_sats(sats)?;
+    let released = BTCCommitment::sats_to_rewards(sats, oracle_data.btc_price_usd)?;
+    ctx.accounts.user_rewards.credit(released)?;
+    
+    msg!("Released {} reinvested sats as {} rewards for user {} (remaining reinvested sats: {})",
+         sats, released, ctx.accounts.user.key(), btc_commitment.reinvested_sats);
+    
+    Ok(())
+}
+
+/// Keeper crank expiring every request left pending past the payment
+/// timeout, then refunding the expired requests of each user passed in and
+/// moving them to the user's history. Remaining accounts come in pairs per
+/// user: the writable rewards and payment history accounts. Requests already
+/// processing are never expired.
+pub fn expire_payment_requests<'info>(
+    ctx: Context<'_, '_, 'info, 'info, ExpirePaymentRequests<'info>>,
+) -> Result<()> {
+    let payment_system = &mut ctx.accounts.payment_system;
+    let expired = payment_system.expire_overdue(Clock::get()?.unix_timestamp)?;
+    
+    let pairs = ctx.remaining_accounts.chunks_exact(2);
+    if !pairs.remainder().is_empty() {
+        return Err(VaultError::MissingRequiredAccount.into());
+    }
+    
+    for pair in pairs {
+        let mut user_rewards = Account::<UserRewards>::try_from(&pair[0])?;
+        let mut payment_history = Account::<UserPaymentHistory>::try_from(&pair[1])?;
+        let user = user_rewards.user;
+        if payment_history.user != user {
+            return Err(VaultError::UnauthorizedAccess.into());
+        }
+        
+        let (refunded, amount) = payment_system.take_expired_for(user)?;
+        user_rewards.refund_claim(amount)?;
+        for request in &refunded {
+            payment_history.record(request);
+        }
+        user_rewards.exit(ctx.program_id)?;
+        payment_history.exit(ctx.program_id)?;
+        
+        if !refunded.is_empty() {
+            msg!("Refunded {} expired payments of user {} (amount: {})", refunded.len(), user, amount);
+        }
+    }
+    
+    msg!("Expired {} payment requests", expired);
+    
+    Ok(())
+}
+
+/// Keeper crank reinvesting for every user passed in whose reinvestment is
+/// due. Remaining accounts come in groups of three per user: payment
+/// preferences, then the writable rewards and BTC commitment accounts. Users
+/// not due, below their threshold or at their reinvestment cap are skipped,
+/// and nobody is while any region's policy restricts reinvestment.
+pub fn run_due_reinvestments<'info>(
+    ctx: Context<'_, '_, 'info, 'info, RunDueReinvestments<'info>>,
+) -> Result<()> {
+    ctx.accounts.emergency_state.require_not_paused(EmergencyScope::Payments)?;
+    ctx.accounts.emergency_state.require_not_paused(EmergencyScope::Staking)?;
+    
+    if ctx.accounts.payment_system.emergency_pause {
+        return Err(VaultError::PaymentSystemPaused.into());
+    }
+    if ctx.accounts.oracle_data.is_stale()? {
+        return Err(VaultError::OraclePriceUnavailable.into());
+    }
+    // KYC profiles aren't passed in, so the crank only runs while
+    // reinvestment is allowed in every region
+    if check_region_policy(&ctx.accounts.compliance_config, None, RegionFeature::Reinvestment).is_err() {
+        msg!("Reinvestment restricted in some regions; users must reinvest individually");
+        return Ok(());
+    }
+    let btc_price = ctx.accounts.oracle_data.btc_price_usd;
+    let now = Clock::get()?.unix_timestamp;
+    
+    let groups = ctx.remaining_accounts.chunks_exact(3);
+    if !groups.remainder().is_empty() {
+        return Err(VaultError::MissingRequiredAccount.into());
+    }
+    
+    let mut executed = 0;
+    for group in groups {
+        let user_preferences = Account::<UserPaymentPreferences>::try_from(&group[0])?;
+        let mut user_rewards = Account::<UserRewards>::try_from(&group[1])?;
+        let mut btc_commitment = Account::<BTCCommitment>::try_from(&group[2])?;
+        let user = user_preferences.user;
+        if user_rewards.user != user || btc_commitment.user_address != user {
+            return Err(VaultError::UnauthorizedAccess.into());
+        }
+        
+        let config = &user_preferences.reinvestment_config;
+        if !config.enabled || !btc_commitment.is_reinvestment_due(config.compound_frequency, now) {
+            continue;
+        }
+        if config.reinvestable_amount(user_rewards.pending_rewards).is_none() {
+            msg!("Reinvestment for user {} skipped: below the threshold", user);
+            continue;
+        }
+        if btc_commitment.reinvestment_headroom() == 0 {
+            msg!("Reinvestment for user {} skipped: reinvestment cap reached", user);
+            continue;
+        }
+        
+        reinvest_rewards(&user_preferences, &mut user_rewards, &mut btc_commitment, btc_price, now)?;
+        user_rewards.exit(ctx.program_id)?;
+        btc_commitment.exit(ctx.program_id)?;
+        executed += 1;
+    }
+    
+    msg!("Executed {} due reinvestments", executed);
     
     Ok(())
 }
@@ -2,6 +2,7 @@ use anchor_lang::prelude::*;
 use crate::state::*;
 use crate::errors::VaultError;
 use crate::traits::PaymentType;
+use crate::instructions::authentication::{require_cosigner, require_session_permission};
 
 #[derive(Accounts)]
 pub struct CalculateRewards<'info> {
@@ -19,8 +20,43 @@ pub struct CalculateRewards<'info> {
     )]
     pub treasury: Account<'info, Treasury>,
     
+    #[account(
+        mut,
+        seeds = [b"reward_pool"],
+        bump = reward_pool.bump
+    )]
+    pub reward_pool: Account<'info, RewardPool>,
+    
+    #[account(
+        seeds = [b"multisig_wallet"],
+        bump = multisig_wallet.bump
+    )]
+    pub multisig_wallet: Account<'info, MultisigWallet>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeRewardPool<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = RewardPool::LEN,
+        seeds = [b"reward_pool"],
+        bump
+    )]
+    pub reward_pool: Account<'info, RewardPool>,
+    
+    #[account(
+        seeds = [b"multisig_wallet"],
+        bump = multisig_wallet.bump
+    )]
+    pub multisig_wallet: Account<'info, MultisigWallet>,
+    
     #[account(mut)]
     pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
@@ -46,6 +82,20 @@ pub struct DistributeRewards<'info> {
     )]
     pub user_account: Account<'info, UserAccount>,
     
+    #[account(
+        seeds = [b"btc_commitment", user.key().as_ref()],
+        bump = btc_commitment.bump
+    )]
+    pub btc_commitment: Account<'info, BTCCommitment>,
+    
+    /// Optional protocol-wide stats; distribution proceeds without it
+    #[account(
+        mut,
+        seeds = [b"reward_stats"],
+        bump = reward_stats.bump
+    )]
+    pub reward_stats: Option<Account<'info, RewardStats>>,
+    
     pub user: Signer<'info>,
     pub authority: Signer<'info>,
 }
@@ -66,7 +116,93 @@ pub struct ClaimRewards<'info> {
     )]
     pub treasury: Account<'info, Treasury>,
     
+    /// Optional protocol-wide stats; claims proceed without it
+    #[account(
+        mut,
+        seeds = [b"reward_stats"],
+        bump = reward_stats.bump
+    )]
+    pub reward_stats: Option<Account<'info, RewardStats>>,
+    
+    #[account(
+        seeds = [b"kyc_profile", user.key().as_ref()],
+        bump = kyc_profile.bump
+    )]
+    pub kyc_profile: Account<'info, KYCProfile>,
+    
+    /// Holds the claiming session, the co-sign threshold and any high-value step-up
+    #[account(
+        mut,
+        seeds = [b"user_auth", user.key().as_ref()],
+        bump = user_auth.bump
+    )]
+    pub user_auth: Account<'info, UserAuth>,
+    
+    /// The user's hardware wallet, for claims above their co-sign threshold
+    pub cosigner: Option<Signer<'info>>,
+    
+    /// Required only for AutoReinvest claims, which add virtual sats to it
+    #[account(
+        mut,
+        seeds = [b"btc_commitment", user.key().as_ref()],
+        bump = btc_commitment.bump,
+        constraint = btc_commitment.user_address == user.key() @ VaultError::UnauthorizedSigner
+    )]
+    pub btc_commitment: Option<Account<'info, BTCCommitment>>,
+    
+    /// Required only for AutoReinvest claims, priced at the oracle rate
+    #[account(
+        seeds = [b"oracle"],
+        bump
+    )]
+    pub oracle_data: Option<Account<'info, OracleData>>,
+    
+    /// Required only for AutoReinvest claims, paced by its reinvestment config
+    #[account(
+        seeds = [b"user_preferences", user.key().as_ref()],
+        bump = user_preferences.bump
+    )]
+    pub user_preferences: Option<Account<'info, UserPaymentPreferences>>,
+    
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeUserRewards<'info> {
+    #[account(
+        init,
+        payer = user,
+        space = UserRewards::LEN,
+        seeds = [b"rewards", user.key().as_ref()],
+        bump
+    )]
+    pub user_rewards: Account<'info, UserRewards>,
+    
+    #[account(mut)]
     pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeRewardStats<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = RewardStats::LEN,
+        seeds = [b"reward_stats"],
+        bump
+    )]
+    pub reward_stats: Account<'info, RewardStats>,
+    
+    #[account(
+        seeds = [b"multisig_wallet"],
+        bump = multisig_wallet.bump
+    )]
+    pub multisig_wallet: Account<'info, MultisigWallet>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
@@ -78,27 +214,90 @@ pub struct UpdateRewardRates<'info> {
     )]
     pub staking_pool: Account<'info, StakingPool>,
     
+    #[account(
+        seeds = [b"reward_pool"],
+        bump = reward_pool.bump
+    )]
+    pub reward_pool: Account<'info, RewardPool>,
+    
     #[account(mut)]
     pub authority: Signer<'info>,
 }
 
+#[derive(Accounts)]
+pub struct EnableMultisigRateControl<'info> {
+    #[account(
+        mut,
+        seeds = [b"reward_pool"],
+        bump = reward_pool.bump
+    )]
+    pub reward_pool: Account<'info, RewardPool>,
+    
+    #[account(
+        seeds = [b"multisig_wallet"],
+        bump = multisig_wallet.bump
+    )]
+    pub multisig_wallet: Account<'info, MultisigWallet>,
+    
+    pub authority: Signer<'info>,
+}
+
+/// Initialize the protocol-wide reward statistics account (multisig admins only)
+pub fn initialize_reward_stats(ctx: Context<InitializeRewardStats>) -> Result<()> {
+    require_multisig_admin(&ctx.accounts.multisig_wallet, &ctx.accounts.authority)?;
+
+    let reward_stats = &mut ctx.accounts.reward_stats;
+    reward_stats.initialize(Clock::get()?.epoch, ctx.bumps.reward_stats)?;
+
+    msg!("Reward stats initialized");
+
+    Ok(())
+}
+
+/// Initialize a user's pending reward balance
+pub fn initialize_user_rewards(ctx: Context<InitializeUserRewards>) -> Result<()> {
+    let user_rewards = &mut ctx.accounts.user_rewards;
+    user_rewards.user = ctx.accounts.user.key();
+    user_rewards.pending_rewards = 0;
+    user_rewards.total_reinvested = 0;
+    user_rewards.last_claim_request = 0;
+    user_rewards.claim_nonce = 0;
+    user_rewards.bump = ctx.bumps.user_rewards;
+
+    Ok(())
+}
+
+/// Initialize the reward pool configuration and epoch history
+pub fn initialize_reward_pool(ctx: Context<InitializeRewardPool>) -> Result<()> {
+    require_multisig_admin(&ctx.accounts.multisig_wallet, &ctx.accounts.authority)?;
+
+    let reward_pool = &mut ctx.accounts.reward_pool;
+    reward_pool.initialize(5000, Clock::get()?.unix_timestamp, ctx.bumps.reward_pool)?;
+
+    msg!("Reward pool initialized");
+
+    Ok(())
+}
+
 /// Calculate rewards based on staking performance and distribute according to 1:2 ratio
 pub fn calculate_rewards(
     ctx: Context<CalculateRewards>,
     total_staking_rewards: u64,
-    _total_btc_commitments: u64,
+    total_btc_commitments: u64,
 ) -> Result<()> {
+    require_multisig_admin(&ctx.accounts.multisig_wallet, &ctx.accounts.authority)?;
+
     let staking_pool = &mut ctx.accounts.staking_pool;
     let treasury = &mut ctx.accounts.treasury;
+    let reward_pool = &mut ctx.accounts.reward_pool;
 
     // Validate inputs
     if total_staking_rewards == 0 {
         return Ok(()); // No rewards to calculate
     }
 
-    // Calculate protocol share (50%) and user share (50%)
-    let protocol_share = total_staking_rewards.checked_div(2).unwrap();
-    let user_share = total_staking_rewards.checked_sub(protocol_share).unwrap();
+    // Split by the configured user share
+    let (user_share, protocol_share) = reward_pool.split_rewards(total_staking_rewards)?;
 
     // Update staking pool rewards
     staking_pool.rewards_accumulated = staking_pool.rewards_accumulated
@@ -114,6 +313,22 @@ pub fn calculate_rewards(
     let clock = Clock::get()?;
     staking_pool.last_reward_calculation = clock.unix_timestamp;
 
+    // Close out the epoch so frontends can chart APY from a single account
+    let summary = reward_pool.finalize_epoch(
+        total_staking_rewards,
+        total_btc_commitments,
+        clock.unix_timestamp,
+    )?;
+
+    emit!(EpochFinalized {
+        epoch: summary.epoch,
+        total_pot: summary.total_pot,
+        total_commitments: summary.total_commitments,
+        reward_per_sat: summary.reward_per_sat,
+        user_share_bps: summary.user_share_bps,
+        duration: summary.duration,
+    });
+
     msg!("Calculated rewards: Total {}, Protocol {}, Users {}", 
          total_staking_rewards, protocol_share, user_share);
 
@@ -126,8 +341,9 @@ pub fn distribute_rewards(ctx: Context<DistributeRewards>) -> Result<()> {
     let treasury = &mut ctx.accounts.treasury;
     let user_account = &mut ctx.accounts.user_account;
 
-    // Get user's BTC commitment amount
-    let user_btc_commitment = user_account.btc_commitment_amount;
+    // Get user's BTC commitment amount, including reinvested virtual sats
+    // while the commitment is verified
+    let user_btc_commitment = ctx.accounts.btc_commitment.effective_amount();
     
     if user_btc_commitment == 0 {
         return Err(VaultError::InsufficientBalance.into());
@@ -168,17 +384,33 @@ pub fn distribute_rewards(ctx: Context<DistributeRewards>) -> Result<()> {
     staking_pool.rewards_distributed = staking_pool.rewards_distributed
         .checked_add(user_rewards).unwrap();
 
+    user_account.total_rewards_earned = user_account.total_rewards_earned
+        .checked_add(user_rewards).ok_or(VaultError::ArithmeticOverflow)?;
+
+    if let Some(reward_stats) = ctx.accounts.reward_stats.as_mut() {
+        reward_stats.record_distribution(
+            user_account.owner,
+            user_rewards,
+            user_account.total_rewards_earned,
+            Clock::get()?.unix_timestamp,
+        )?;
+    }
+
     msg!("Distributed {} rewards to user with {} BTC commitment", 
          user_rewards, user_btc_commitment);
 
     Ok(())
 }
 
-/// Allow users to claim their accumulated rewards
+/// Allow users to claim their accumulated rewards. High-value claims
+/// consume a claim step-up proof covering the amount.
 pub fn claim_rewards(
     ctx: Context<ClaimRewards>,
     payment_type: PaymentType,
+    session_id: String,
 ) -> Result<()> {
+    require_session_permission(&ctx.accounts.user_auth, &session_id, SessionPermission::Payment)?;
+    ctx.accounts.kyc_profile.require_not_frozen(FrozenAction::Claims)?;
     let user_account = &mut ctx.accounts.user_account;
     let _treasury = &mut ctx.accounts.treasury;
 
@@ -187,6 +419,14 @@ pub fn claim_rewards(
     if claimable_rewards == 0 {
         return Err(VaultError::InsufficientBalance.into());
     }
+    
+    // Reward balances are USD with 6 decimals
+    require_cosigner(&ctx.accounts.user_auth, ctx.accounts.cosigner.as_ref(), Some(claimable_rewards))?;
+    
+    let user_auth = &mut ctx.accounts.user_auth;
+    if user_auth.requires_2fa_for_operation("high_value", Some(claimable_rewards)) {
+        user_auth.consume_step_up(StepUpOperation::ClaimRewards, claimable_rewards, Clock::get()?.unix_timestamp)?;
+    }
 
     // Process payment based on user preference
     match payment_type {
@@ -200,12 +440,35 @@ pub fn claim_rewards(
         },
         PaymentType::AutoReinvest => {
             // Auto-reinvest rewards back into the protocol
-            process_auto_reinvestment(user_account, claimable_rewards)?;
+            process_auto_reinvestment(
+                ctx.accounts.btc_commitment.as_mut(),
+                ctx.accounts.oracle_data.as_ref(),
+                ctx.accounts.user_preferences.as_ref(),
+                claimable_rewards,
+                Clock::get()?.unix_timestamp,
+            )?;
         }
     }
 
     // Clear user's reward balance
     user_account.reward_balance = 0;
+    user_account.total_rewards_claimed = user_account.total_rewards_claimed
+        .checked_add(claimable_rewards).ok_or(VaultError::ArithmeticOverflow)?;
+
+    let clock = Clock::get()?;
+    let first_claim_this_epoch = user_account.last_claim_epoch != clock.epoch;
+    user_account.last_claim_epoch = clock.epoch;
+
+    if let Some(reward_stats) = ctx.accounts.reward_stats.as_mut() {
+        reward_stats.record_claim(
+            user_account.owner,
+            claimable_rewards,
+            payment_type,
+            clock.epoch,
+            first_claim_this_epoch,
+            clock.unix_timestamp,
+        )?;
+    }
 
     // Update user's payment preference for future rewards
     user_account.payment_preference = payment_type;
@@ -222,6 +485,11 @@ pub fn update_reward_rates(
 ) -> Result<()> {
     let _staking_pool = &mut ctx.accounts.staking_pool;
 
+    // Once migrated, the only path is a timelocked RewardRateChange multisig transaction
+    if ctx.accounts.reward_pool.multisig_rate_control {
+        return Err(VaultError::MultisigRateControlRequired.into());
+    }
+
     // Validate basis points (max 10000 = 100%)
     if new_user_share_bps > 10000 {
         return Err(VaultError::InvalidAllocation.into());
@@ -238,6 +506,39 @@ pub fn update_reward_rates(
     Ok(())
 }
 
+/// One-way migration: after this, update_reward_rates rejects direct calls
+pub fn enable_multisig_rate_control(ctx: Context<EnableMultisigRateControl>) -> Result<()> {
+    require_multisig_admin(&ctx.accounts.multisig_wallet, &ctx.accounts.authority)?;
+
+    ctx.accounts.reward_pool.multisig_rate_control = true;
+
+    msg!("Reward rate changes now require multisig approval");
+
+    Ok(())
+}
+
+/// Apply an approved RewardRateChange; only reachable from execute_multisig_transaction
+pub(crate) fn apply_reward_rate_change(
+    reward_pool: &mut RewardPool,
+    transaction_id: u32,
+    new_user_share_bps: u16,
+    now: i64,
+) -> Result<String> {
+    let old_protocol_share_bps = reward_pool.protocol_share_bps;
+    let old_user_share_bps = reward_pool.set_user_share_bps(new_user_share_bps)?;
+
+    emit!(RewardRatesUpdated {
+        transaction_id,
+        old_user_share_bps,
+        new_user_share_bps,
+        old_protocol_share_bps,
+        new_protocol_share_bps: reward_pool.protocol_share_bps,
+        effective_at: now,
+    });
+
+    Ok(format!("User share changed from {} to {} bps", old_user_share_bps, new_user_share_bps))
+}
+
 /// Process BTC payment via Lightning Network with fallback
 fn process_btc_payment(amount: u64) -> Result<()> {
     // In production, this would:
@@ -265,17 +566,43 @@ fn process_usdc_payment(amount: u64) -> Result<()> {
     Ok(())
 }
 
-/// Process auto-reinvestment of rewards
-fn process_auto_reinvestment(_user_account: &mut UserAccount, amount: u64) -> Result<()> {
-    // In production, this would:
-    // 1. Add rewards to user's effective BTC commitment
-    // 2. Update staking allocations accordingly
-    // 3. Compound future reward calculations
+/// Only an active Admin signer of the multisig wallet may run reward pool
+/// administration
+pub(crate) fn require_multisig_admin(multisig_wallet: &MultisigWallet, authority: &Signer) -> Result<()> {
+    let authority_key = authority.key();
+    let is_admin = multisig_wallet.signers.iter()
+        .any(|s| s.pubkey == authority_key && s.is_active && s.role == SignerRole::Admin);
+    if !is_admin {
+        return Err(VaultError::UnauthorizedAccess.into());
+    }
+    Ok(())
+}
+
+/// Reinvest a claim as virtual committed sats at the oracle price. The whole
+/// claim must fit under the commitment's reinvestment cap, and the user's
+/// compound frequency paces it as it does the reinvestment crank.
+fn process_auto_reinvestment(
+    btc_commitment: Option<&mut Account<BTCCommitment>>,
+    oracle_data: Option<&Account<OracleData>>,
+    user_preferences: Option<&Account<UserPaymentPreferences>>,
+    amount: u64,
+    now: i64,
+) -> Result<()> {
+    let btc_commitment = btc_commitment.ok_or(VaultError::MissingRequiredAccount)?;
+    let oracle_data = oracle_data.ok_or(VaultError::MissingRequiredAccount)?;
+    let config = &user_preferences.ok_or(VaultError::MissingRequiredAccount)?.reinvestment_config;
+    if !btc_commitment.is_reinvestment_due(config.compound_frequency, now) {
+        return Err(VaultError::ReinvestmentTooFrequent.into());
+    }
+    if oracle_data.is_stale()? {
+        return Err(VaultError::OraclePriceUnavailable.into());
+    }
+    
+    let sats = BTCCommitment::rewards_to_sats(amount, oracle_data.btc_price_usd)?;
+    btc_commitment.add_reinvested_sats(sats, now)?;
     
-    msg!("Auto-reinvesting {} rewards for user", amount);
+    msg!("Auto-reinvested {} rewards as {} virtual sats (total {})", amount, sats, btc_commitment.reinvested_sats);
     
-    // For now, we simulate by keeping rewards in the system
-    // In full implementation, this would increase user's effective stake
     Ok(())
 }
 
@@ -163,7 +163,7 @@ pub struct UpdateAnomalyRules<'info> {
 }
 
 impl SecurityMonitor {
-    pub const MAX_SIZE: usize = 32 + 8 + 8 + 8 + 1 + 4 + 4 + 1 + 4 + 100 + 32 * 10 + 8 + 8; // ~500 bytes
+    pub const MAX_SIZE: usize = 32 + 8 + 8 + 8 + 1 + 4 + 4 + 1 + 4 + 100 + 32 * 10 + 8 + 8 + 32; // ~500 bytes
 }
 
 impl SecurityEventLog {
@@ -187,7 +187,7 @@ impl SecurityAlertStore {
 }
 
 impl SecurityAlert {
-    pub const MAX_SIZE: usize = 8 + 1 + 33 + 8 + 8 + 1 + 1 + 4 + 200 + 4 + (8 * 50) + 4 + (200 * 10) + 33 + 1 + 8 + 1; // ~1KB per alert
+    pub const MAX_SIZE: usize = 8 + 1 + 33 + 8 + 8 + 1 + 1 + 4 + 200 + 4 + (8 * 50) + 4 + (200 * 10) + 33 + 1 + 8 + 1 + 8; // ~1KB per alert
 }
 
 impl AnomalyRuleStore {
@@ -229,6 +229,7 @@ pub fn initialize_security_monitor(ctx: Context<InitializeSecurityMonitor>) -> R
     security_monitor.emergency_contacts = Vec::new();
     security_monitor.created_at = now;
     security_monitor.last_maintenance = now;
+    security_monitor.compliance_authority = ctx.accounts.authority.key();
     
     // Initialize event log
     event_log.monitor = security_monitor.key();
@@ -349,11 +350,7 @@ pub fn log_security_event(
     )?;
     
     // Add event to log
-    if event_log.events.len() >= event_log.max_size as usize {
-        event_log.events.remove(0); // Remove oldest event
-    }
-    event_log.events.push(event);
-    event_log.last_updated = Clock::get()?.unix_timestamp;
+    event_log.record(event, Clock::get()?.unix_timestamp);
     
     Ok(())
 }
@@ -393,52 +390,132 @@ pub fn create_audit_trail(
     
     if compliance_relevant {
         trail = trail.mark_compliance_relevant();
-        audit_store.compliance_trails.push(trail.clone());
     }
     
-    audit_store.trails.push(trail);
+    audit_store.record(trail);
     
     Ok(())
 }
 
+/// Move an alert case to `new_state`, noting it on the alert. Only the
+/// monitor authority, the compliance authority or the alert's assignee may.
+/// Cases past their SLA are escalated first.
+pub fn transition_alert(
+    ctx: Context<ManageSecurityAlert>,
+    alert_id: u64,
+    new_state: AlertStatus,
+    notes: String,
+) -> Result<()> {
+    let security_monitor = &ctx.accounts.security_monitor;
+    let alert_store = &mut ctx.accounts.alert_store;
+    let officer = ctx.accounts.security_officer.key();
+    let now = Clock::get()?.unix_timestamp;
+    
+    alert_store.escalate_breached(security_monitor.compliance_authority, now);
+    
+    let alert = alert_store.alerts.iter()
+        .find(|a| a.alert_id == alert_id)
+        .ok_or(VaultError::AlertNotFound)?;
+    let may_handle = officer == security_monitor.authority
+        || officer == security_monitor.compliance_authority
+        || alert.assigned_to == Some(officer);
+    if !may_handle {
+        return Err(VaultError::UnauthorizedAccess.into());
+    }
+    
+    let old_state = alert_store.transition_alert(alert_id, new_state, officer, &notes, now)?;
+    
+    msg!("Alert {} moved from {:?} to {:?} by {}", alert_id, old_state, new_state, officer);
+    
+    Ok(())
+}
+
+/// Close an alert as resolved, or dismissed as a false positive
 pub fn resolve_security_alert(
     ctx: Context<ManageSecurityAlert>,
     alert_id: u64,
     false_positive: bool,
     resolution_notes: String,
 ) -> Result<()> {
+    let new_state = if false_positive { AlertStatus::Dismissed } else { AlertStatus::Resolved };
+    transition_alert(ctx, alert_id, new_state, resolution_notes)
+}
+
+/// Close up to `MAX_BATCH_ALERTS` alerts at once during incident response
+/// (monitor or compliance authority only). Alerts that can't be closed,
+/// e.g. unknown or still Open, are skipped and reported as failed in
+/// `AlertsBatchResolved`.
+pub fn batch_resolve_alerts(
+    ctx: Context<ManageSecurityAlert>,
+    alert_ids: Vec<u64>,
+    false_positive: bool,
+    resolution_notes: String,
+) -> Result<()> {
+    let security_monitor = &ctx.accounts.security_monitor;
     let alert_store = &mut ctx.accounts.alert_store;
+    let officer = ctx.accounts.security_officer.key();
+    let now = Clock::get()?.unix_timestamp;
     
-    if let Some(alert) = alert_store.alerts.iter_mut().find(|a| a.alert_id == alert_id) {
-        alert.resolve(false_positive);
-        alert.add_investigation_note(resolution_notes);
-        
-        if alert.status == AlertStatus::Resolved {
-            alert_store.resolved_count += 1;
-            alert_store.active_count = alert_store.active_count.saturating_sub(1);
-        }
-        
-        alert_store.last_updated = Clock::get()?.unix_timestamp;
-    } else {
-        return Err(VaultError::AlertNotFound.into());
+    if officer != security_monitor.authority && officer != security_monitor.compliance_authority {
+        return Err(VaultError::UnauthorizedAccess.into());
+    }
+    if alert_ids.is_empty() || alert_ids.len() > SecurityAlertStore::MAX_BATCH_ALERTS {
+        return Err(VaultError::InvalidBatchSize.into());
     }
+    if resolution_notes.len() > SecurityAlert::MAX_NOTE_LENGTH {
+        return Err(VaultError::ReasonTooLong.into());
+    }
+    
+    alert_store.escalate_breached(security_monitor.compliance_authority, now);
+    
+    let new_status = if false_positive { AlertStatus::Dismissed } else { AlertStatus::Resolved };
+    let succeeded: Vec<bool> = alert_ids.iter()
+        .map(|alert_id| alert_store.transition_alert(*alert_id, new_status, officer, &resolution_notes, now).is_ok())
+        .collect();
+    
+    msg!("{} of {} alerts moved to {:?} by {}",
+         succeeded.iter().filter(|ok| **ok).count(), alert_ids.len(), new_status, officer);
+    
+    emit!(AlertsBatchResolved {
+        alert_ids,
+        succeeded,
+        new_status,
+        resolved_by: officer,
+        timestamp: now,
+    });
     
     Ok(())
 }
 
+/// Hand an alert to `officer` (monitor or compliance authority only). An
+/// alert not yet under investigation moves to InvestigationInProgress.
 pub fn assign_security_alert(
     ctx: Context<ManageSecurityAlert>,
     alert_id: u64,
     officer: Pubkey,
 ) -> Result<()> {
+    let security_monitor = &ctx.accounts.security_monitor;
     let alert_store = &mut ctx.accounts.alert_store;
+    let authority = ctx.accounts.security_officer.key();
+    let now = Clock::get()?.unix_timestamp;
+    
+    if authority != security_monitor.authority && authority != security_monitor.compliance_authority {
+        return Err(VaultError::UnauthorizedAccess.into());
+    }
     
-    if let Some(alert) = alert_store.alerts.iter_mut().find(|a| a.alert_id == alert_id) {
-        alert.assign_to(officer);
-        alert_store.last_updated = Clock::get()?.unix_timestamp;
+    alert_store.escalate_breached(security_monitor.compliance_authority, now);
+    
+    let alert = alert_store.alerts.iter_mut()
+        .find(|a| a.alert_id == alert_id)
+        .ok_or(VaultError::AlertNotFound)?;
+    let note = format!("Assigned to {}", officer);
+    if alert.status.can_transition_to(AlertStatus::InvestigationInProgress) {
+        alert.transition(AlertStatus::InvestigationInProgress, authority, &note, now)?;
     } else {
-        return Err(VaultError::AlertNotFound.into());
+        alert.add_investigation_note(format!("{} {}: {}", now, authority, note));
     }
+    alert.assign_to(officer);
+    alert_store.last_updated = now;
     
     Ok(())
 }
@@ -483,6 +560,7 @@ pub fn update_security_config(
     max_events_per_user: Option<u32>,
     auto_block_enabled: Option<bool>,
     notification_webhook: Option<String>,
+    compliance_authority: Option<Pubkey>,
 ) -> Result<()> {
     let security_monitor = &mut ctx.accounts.security_monitor;
     
@@ -502,6 +580,10 @@ pub fn update_security_config(
         security_monitor.notification_webhook = Some(webhook);
     }
     
+    if let Some(compliance_authority) = compliance_authority {
+        security_monitor.compliance_authority = compliance_authority;
+    }
+    
     Ok(())
 }
 
@@ -572,10 +654,12 @@ fn create_default_anomaly_rules() -> Vec<AnomalyDetectionRule> {
     ]
 }
 
-fn determine_security_level(event_type: &SecurityEventType) -> SecurityLevel {
+pub(crate) fn determine_security_level(event_type: &SecurityEventType) -> SecurityLevel {
     match event_type {
         SecurityEventType::SecurityViolation => SecurityLevel::Critical,
-        SecurityEventType::EmergencyMode | SecurityEventType::AccountFrozen => SecurityLevel::High,
+        SecurityEventType::EmergencyMode
+        | SecurityEventType::AccountFrozen
+        | SecurityEventType::SignerFlaggedForReview => SecurityLevel::High,
         SecurityEventType::LoginFailure | SecurityEventType::TwoFactorFailure | 
         SecurityEventType::ComplianceAlert | SecurityEventType::SuspiciousPattern => SecurityLevel::Medium,
         _ => SecurityLevel::Low,
@@ -694,7 +778,7 @@ fn check_anomaly_rules(
     Ok(())
 }
 
-fn create_security_alert(
+pub(crate) fn create_security_alert(
     security_monitor: &mut Account<SecurityMonitor>,
     alert_store: &mut Account<SecurityAlertStore>,
     alert_type: SecurityEventType,
@@ -703,6 +787,9 @@ fn create_security_alert(
     security_level: SecurityLevel,
     related_events: Vec<u64>,
 ) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    alert_store.escalate_breached(security_monitor.compliance_authority, now);
+    
     security_monitor.alert_counter += 1;
     
     let mut alert = SecurityAlert::new(
@@ -719,7 +806,7 @@ fn create_security_alert(
     
     alert_store.alerts.push(alert);
     alert_store.active_count += 1;
-    alert_store.last_updated = Clock::get()?.unix_timestamp;
+    alert_store.last_updated = now;
     
     Ok(())
 }
@@ -27,6 +27,12 @@ pub struct StakeProtocolAssets<'info> {
     )]
     pub staking_pool: Account<'info, StakingPool>,
     
+    #[account(
+        seeds = [b"emergency_state"],
+        bump = emergency_state.bump
+    )]
+    pub emergency_state: Account<'info, EmergencyState>,
+    
     #[account(
         mut,
         seeds = [b"treasury"],
@@ -47,6 +53,12 @@ pub struct RebalanceAllocations<'info> {
     )]
     pub staking_pool: Account<'info, StakingPool>,
     
+    #[account(
+        seeds = [b"emergency_state"],
+        bump = emergency_state.bump
+    )]
+    pub emergency_state: Account<'info, EmergencyState>,
+    
     #[account(
         mut,
         seeds = [b"treasury"],
@@ -85,6 +97,8 @@ pub fn stake_protocol_assets(
     ctx: Context<StakeProtocolAssets>,
     total_treasury_usd: u64,
 ) -> Result<()> {
+    ctx.accounts.emergency_state.require_not_paused(EmergencyScope::Staking)?;
+
     let staking_pool = &mut ctx.accounts.staking_pool;
     let treasury = &mut ctx.accounts.treasury;
 
@@ -164,6 +178,8 @@ pub fn stake_protocol_assets(
 
 /// Rebalance allocations to maintain target percentages
 pub fn rebalance_allocations(ctx: Context<RebalanceAllocations>) -> Result<()> {
+    ctx.accounts.emergency_state.require_not_paused(EmergencyScope::Staking)?;
+
     let staking_pool = &mut ctx.accounts.staking_pool;
     let treasury = &mut ctx.accounts.treasury;
 
@@ -1,6 +1,11 @@
 use anchor_lang::prelude::*;
 use crate::state::*;
 use crate::errors::VaultError;
+use crate::crypto::Ed25519Verifier;
+use crate::instructions::authentication::require_session_permission;
+use crate::instructions::kyc::{check_region_policy, load_participant_kyc_profiles, validate_channel_settlement_kyc};
+use crate::instructions::rewards::require_multisig_admin;
+use solana_program::sysvar;
 
 #[derive(Accounts)]
 #[instruction(channel_id: [u8; 32])]
@@ -14,9 +19,54 @@ pub struct InitializeStateChannel<'info> {
     )]
     pub state_channel: Account<'info, StateChannel>,
     
+    #[account(
+        seeds = [b"emergency_state"],
+        bump = emergency_state.bump
+    )]
+    pub emergency_state: Account<'info, EmergencyState>,
+    
+    #[account(
+        seeds = [b"treasury"],
+        bump = treasury.bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+    
+    #[account(
+        seeds = [b"compliance_config"],
+        bump = compliance_config.bump
+    )]
+    pub compliance_config: Account<'info, ComplianceConfig>,
+    
+    /// Its freeze can block opening channels
+    #[account(
+        seeds = [b"kyc_profile", authority.key().as_ref()],
+        bump = kyc_profile.bump
+    )]
+    pub kyc_profile: Account<'info, KYCProfile>,
+    
     #[account(mut)]
     pub authority: Signer<'info>,
     pub system_program: Program<'info, System>,
+    // Remaining accounts (high-frequency trading channels only): every
+    // participant's KYC profile, in participant order
+}
+
+#[derive(Accounts)]
+pub struct UpdateChannelFee<'info> {
+    #[account(
+        mut,
+        seeds = [b"treasury"],
+        bump = treasury.bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+    
+    #[account(
+        seeds = [b"multisig_wallet"],
+        bump = multisig_wallet.bump
+    )]
+    pub multisig_wallet: Account<'info, MultisigWallet>,
+    
+    pub authority: Signer<'info>,
 }
 
 #[derive(Accounts)]
@@ -28,8 +78,18 @@ pub struct UpdateStateChannel<'info> {
     )]
     pub state_channel: Account<'info, StateChannel>,
     
+    #[account(
+        seeds = [b"emergency_state"],
+        bump = emergency_state.bump
+    )]
+    pub emergency_state: Account<'info, EmergencyState>,
+    
     #[account(mut)]
     pub participant: Signer<'info>,
+    
+    /// CHECK: Address is constrained to the instructions sysvar
+    #[account(address = sysvar::instructions::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
 }
 
 #[derive(Accounts)]
@@ -41,6 +101,12 @@ pub struct SettleStateChannel<'info> {
     )]
     pub state_channel: Account<'info, StateChannel>,
     
+    #[account(
+        seeds = [b"emergency_state"],
+        bump = emergency_state.bump
+    )]
+    pub emergency_state: Account<'info, EmergencyState>,
+    
     #[account(
         mut,
         seeds = [b"staking_pool"],
@@ -55,8 +121,144 @@ pub struct SettleStateChannel<'info> {
     )]
     pub treasury: Account<'info, Treasury>,
     
+    #[account(
+        seeds = [b"compliance_config"],
+        bump = compliance_config.bump
+    )]
+    pub compliance_config: Account<'info, ComplianceConfig>,
+    
+    #[account(
+        seeds = [b"kyc_profile", authority.key().as_ref()],
+        bump = kyc_profile.bump
+    )]
+    pub kyc_profile: Account<'info, KYCProfile>,
+    
     #[account(mut)]
     pub authority: Signer<'info>,
+    // Remaining accounts (optional): every participant's collateral vault, or their
+    // wallet if they never deposited, in participant order
+}
+
+#[derive(Accounts)]
+pub struct SettleChannelBatch<'info> {
+    #[account(
+        mut,
+        seeds = [b"state_channel", state_channel.channel_id.as_ref()],
+        bump = state_channel.bump
+    )]
+    pub state_channel: Account<'info, StateChannel>,
+    
+    #[account(
+        seeds = [b"emergency_state"],
+        bump = emergency_state.bump
+    )]
+    pub emergency_state: Account<'info, EmergencyState>,
+    
+    #[account(
+        mut,
+        seeds = [b"staking_pool"],
+        bump = staking_pool.bump
+    )]
+    pub staking_pool: Account<'info, StakingPool>,
+    
+    #[account(
+        mut,
+        seeds = [b"treasury"],
+        bump = treasury.bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+    
+    pub authority: Signer<'info>,
+    // Remaining accounts: collateral vaults as for settlement, needed on the final batch
+}
+
+#[derive(Accounts)]
+pub struct DepositChannelCollateral<'info> {
+    #[account(
+        mut,
+        seeds = [b"state_channel", state_channel.channel_id.as_ref()],
+        bump = state_channel.bump
+    )]
+    pub state_channel: Account<'info, StateChannel>,
+    
+    #[account(
+        seeds = [b"emergency_state"],
+        bump = emergency_state.bump
+    )]
+    pub emergency_state: Account<'info, EmergencyState>,
+    
+    #[account(
+        init_if_needed,
+        payer = participant,
+        space = ChannelCollateral::LEN,
+        seeds = [b"channel_collateral", state_channel.channel_id.as_ref(), participant.key().as_ref()],
+        bump
+    )]
+    pub collateral: Account<'info, ChannelCollateral>,
+    
+    /// A participant with channels frozen can't lock new collateral
+    #[account(
+        seeds = [b"kyc_profile", participant.key().as_ref()],
+        bump = kyc_profile.bump
+    )]
+    pub kyc_profile: Account<'info, KYCProfile>,
+    
+    /// The participant's session, which needs the ChannelOps permission
+    #[account(
+        mut,
+        seeds = [b"user_auth", participant.key().as_ref()],
+        bump = user_auth.bump
+    )]
+    pub user_auth: Account<'info, UserAuth>,
+    
+    #[account(mut)]
+    pub participant: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawChannelCollateral<'info> {
+    #[account(
+        mut,
+        seeds = [b"channel_collateral", collateral.channel_id.as_ref(), participant.key().as_ref()],
+        bump = collateral.bump,
+        constraint = collateral.participant == participant.key() @ VaultError::UnauthorizedAccess
+    )]
+    pub collateral: Account<'info, ChannelCollateral>,
+    
+    #[account(
+        seeds = [b"emergency_state"],
+        bump = emergency_state.bump
+    )]
+    pub emergency_state: Account<'info, EmergencyState>,
+    
+    /// CHECK: Address is the collateral's channel PDA, deserialized in the handler
+    #[account(
+        seeds = [b"state_channel", collateral.channel_id.as_ref()],
+        bump
+    )]
+    pub state_channel: UncheckedAccount<'info>,
+    
+    #[account(
+        seeds = [b"kyc_profile", participant.key().as_ref()],
+        bump = kyc_profile.bump
+    )]
+    pub kyc_profile: Account<'info, KYCProfile>,
+    
+    /// Collateral only leaves through a session allowed channel operations
+    #[account(
+        mut,
+        seeds = [b"user_auth", participant.key().as_ref()],
+        bump = user_auth.bump
+    )]
+    pub user_auth: Account<'info, UserAuth>,
+    
+    #[account(mut)]
+    pub participant: Signer<'info>,
+    
+    /// CHECK: Address is constrained to the instructions sysvar
+    #[account(address = sysvar::instructions::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
 }
 
 #[derive(Accounts)]
@@ -70,19 +272,131 @@ pub struct ChallengeStateChannel<'info> {
     
     #[account(mut)]
     pub challenger: Signer<'info>,
+    
+    /// CHECK: Address is constrained to the instructions sysvar
+    #[account(address = sysvar::instructions::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RespondToChallenge<'info> {
+    #[account(
+        mut,
+        seeds = [b"state_channel", state_channel.channel_id.as_ref()],
+        bump = state_channel.bump
+    )]
+    pub state_channel: Account<'info, StateChannel>,
+    
+    #[account(mut)]
+    pub responder: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ForceSettleExpiredChannel<'info> {
+    #[account(
+        mut,
+        seeds = [b"state_channel", state_channel.channel_id.as_ref()],
+        bump = state_channel.bump
+    )]
+    pub state_channel: Account<'info, StateChannel>,
+    
+    #[account(
+        seeds = [b"emergency_state"],
+        bump = emergency_state.bump
+    )]
+    pub emergency_state: Account<'info, EmergencyState>,
+    
+    #[account(
+        mut,
+        seeds = [b"staking_pool"],
+        bump = staking_pool.bump
+    )]
+    pub staking_pool: Account<'info, StakingPool>,
+    
+    #[account(
+        mut,
+        seeds = [b"treasury"],
+        bump = treasury.bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+    
+    /// Anyone may crank an expired channel; pays rent for any rewards
+    /// accounts the crank has to create and earns the crank fee
+    #[account(mut)]
+    pub cranker: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CheckpointState<'info> {
+    #[account(
+        mut,
+        seeds = [b"state_channel", state_channel.channel_id.as_ref()],
+        bump = state_channel.bump
+    )]
+    pub state_channel: Account<'info, StateChannel>,
+    
+    #[account(mut)]
+    pub participant: Signer<'info>,
+    
+    /// CHECK: Address is constrained to the instructions sysvar
+    #[account(address = sysvar::instructions::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeParticipantChange<'info> {
+    #[account(
+        mut,
+        seeds = [b"state_channel", state_channel.channel_id.as_ref()],
+        bump = state_channel.bump
+    )]
+    pub state_channel: Account<'info, StateChannel>,
+    
+    #[account(
+        seeds = [b"emergency_state"],
+        bump = emergency_state.bump
+    )]
+    pub emergency_state: Account<'info, EmergencyState>,
+    
+    #[account(
+        mut,
+        seeds = [b"staking_pool"],
+        bump = staking_pool.bump
+    )]
+    pub staking_pool: Account<'info, StakingPool>,
+    
+    #[account(
+        mut,
+        seeds = [b"treasury"],
+        bump = treasury.bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+    
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+    // Remaining accounts: signers for every current and newly added participant,
+    // then one payout account per removed participant in `remove` order: their
+    // collateral vault for collateralized channels, otherwise their rewards account
 }
 
 /// Initialize a new state channel for off-chain reward calculations
-pub fn initialize_state_channel(
-    ctx: Context<InitializeStateChannel>,
+pub fn initialize_state_channel<'info>(
+    ctx: Context<'_, '_, 'info, 'info, InitializeStateChannel<'info>>,
     channel_id: [u8; 32],
     participants: Vec<Pubkey>,
     timeout_seconds: i64,
+    challenge_extension: Option<i64>,
+    max_participants: Option<u8>,
+    purpose: ChannelPurpose,
 ) -> Result<()> {
+    ctx.accounts.emergency_state.require_not_paused(EmergencyScope::Channels)?;
+    ctx.accounts.kyc_profile.require_not_frozen(FrozenAction::Channels)?;
+
     let state_channel = &mut ctx.accounts.state_channel;
     
     // Validate participants
-    if participants.is_empty() || participants.len() > 10 {
+    if participants.is_empty() || participants.len() > StateChannel::MAX_PARTICIPANTS {
         return Err(VaultError::InvalidAllocation.into());
     }
     
@@ -91,10 +405,22 @@ pub fn initialize_state_channel(
         return Err(VaultError::UnauthorizedAccess.into());
     }
     
+    // Every participant's region must allow high-frequency trading channels
+    if purpose == ChannelPurpose::HighFrequencyTrading {
+        let kyc_profiles = load_participant_kyc_profiles(ctx.remaining_accounts, &participants, ctx.program_id)?;
+        for kyc_profile in &kyc_profiles {
+            check_region_policy(&ctx.accounts.compliance_config, Some(kyc_profile), RegionFeature::HftChannel)?;
+        }
+    }
+    
     state_channel.initialize(
         channel_id,
         participants,
         timeout_seconds,
+        challenge_extension,
+        max_participants,
+        ctx.accounts.treasury.channel_fee_bps,
+        purpose,
         ctx.bumps.state_channel,
     )?;
     
@@ -105,12 +431,25 @@ pub fn initialize_state_channel(
     Ok(())
 }
 
+/// Change the channel fee for channels opened from now on
+pub fn update_channel_fee(ctx: Context<UpdateChannelFee>, fee_bps: u16) -> Result<()> {
+    require_multisig_admin(&ctx.accounts.multisig_wallet, &ctx.accounts.authority)?;
+    
+    ctx.accounts.treasury.set_channel_fee_bps(fee_bps)?;
+    
+    msg!("State channel fee set to {} bps for new channels", fee_bps);
+    
+    Ok(())
+}
+
 /// Update state channel with new reward calculations
 pub fn update_state_channel(
     ctx: Context<UpdateStateChannel>,
     update: StateChannelUpdate,
     signatures: Vec<Vec<u8>>,
 ) -> Result<()> {
+    ctx.accounts.emergency_state.require_not_paused(EmergencyScope::Channels)?;
+
     let state_channel = &mut ctx.accounts.state_channel;
     
     // Verify participant is authorized
@@ -118,74 +457,712 @@ pub fn update_state_channel(
         return Err(VaultError::UnauthorizedAccess.into());
     }
     
-    // Validate signatures
-    if !state_channel.verify_signatures(update.new_state_hash, &signatures)? {
-        return Err(VaultError::SecurityViolation.into());
-    }
+    // Every participant must sign, in order, over the domain-separated update
+    let message = Ed25519Verifier::state_update_message(
+        &state_channel.channel_id,
+        update.nonce,
+        &update.new_state_hash,
+    );
+    Ed25519Verifier::verify_participant_signatures(
+        &ctx.accounts.instructions_sysvar.to_account_info(),
+        &state_channel.participants,
+        &signatures,
+        &message,
+    )?;
     
-    state_channel.update_state(update, signatures)?;
+    state_channel.update_state(update, signatures, ctx.accounts.participant.key())?;
     
     msg!("State channel updated to nonce {}", state_channel.nonce);
     
     Ok(())
 }
 
-/// Settle state channel and apply final reward calculations on-chain
-pub fn settle_state_channel(
-    ctx: Context<SettleStateChannel>,
-    final_calculations: Vec<RewardCalculation>,
+/// Settle state channel and apply final reward calculations on-chain.
+/// `settlement_root` and `leaf_count` must match the signed state hash;
+/// settlement opens once the channel's timeout has passed. When collateral
+/// vaults are supplied the settlement is funded from them; otherwise it is
+/// paid from the treasury user rewards pool.
+pub fn settle_state_channel<'info>(
+    ctx: Context<'_, '_, 'info, 'info, SettleStateChannel<'info>>,
+    settlement_root: [u8; 32],
+    leaf_count: u32,
+    first_batch: Vec<SettlementLeaf>,
+    proofs: Vec<Vec<[u8; 32]>>,
 ) -> Result<()> {
+    ctx.accounts.emergency_state.require_not_paused(EmergencyScope::Channels)?;
+    ctx.accounts.kyc_profile.require_not_frozen(FrozenAction::Channels)?;
+
     let state_channel = &mut ctx.accounts.state_channel;
-    let staking_pool = &mut ctx.accounts.staking_pool;
-    let treasury = &mut ctx.accounts.treasury;
     
-    // Validate channel can be settled
-    state_channel.validate_state()?;
+    // Only a channel participant can commit the settlement root
+    if !state_channel.participants.contains(&ctx.accounts.authority.key()) {
+        return Err(VaultError::UnauthorizedAccess.into());
+    }
+    
+    // The channel's declared purpose must be allowed in the settler's region
+    validate_channel_settlement_kyc(&ctx.accounts.compliance_config, &ctx.accounts.kyc_profile, state_channel.purpose)?;
     
-    // Validate calculations
-    let total_rewards: u64 = final_calculations
-        .iter()
-        .map(|calc| calc.calculated_reward)
-        .sum();
+    // Channels funded with collateral settle from their vaults; otherwise
+    // rewards are paid from the treasury as leaves are applied
+    let from_collateral = state_channel.collateralized;
+    state_channel.begin_settlement(
+        settlement_root,
+        leaf_count,
+        from_collateral,
+        Clock::get()?.unix_timestamp,
+    )?;
     
-    if total_rewards > treasury.user_rewards_pool {
-        return Err(VaultError::InsufficientBalance.into());
+    msg!("State channel {} committed settlement root over {} reward calculations", 
+         bs58::encode(state_channel.channel_id).into_string(), leaf_count);
+    
+    apply_settlement_batch(
+        state_channel,
+        &mut ctx.accounts.staking_pool,
+        &mut ctx.accounts.treasury,
+        ctx.remaining_accounts,
+        &first_batch,
+        &proofs,
+        ctx.program_id,
+    )
+}
+
+/// Apply a further batch of committed reward calculations
+pub fn settle_batch<'info>(
+    ctx: Context<'_, '_, 'info, 'info, SettleChannelBatch<'info>>,
+    leaves: Vec<SettlementLeaf>,
+    proofs: Vec<Vec<[u8; 32]>>,
+) -> Result<()> {
+    ctx.accounts.emergency_state.require_not_paused(EmergencyScope::Channels)?;
+
+    apply_settlement_batch(
+        &mut ctx.accounts.state_channel,
+        &mut ctx.accounts.staking_pool,
+        &mut ctx.accounts.treasury,
+        ctx.remaining_accounts,
+        &leaves,
+        &proofs,
+        ctx.program_id,
+    )
+}
+
+/// Verify and apply settlement leaves, then pay out and close the channel
+/// once the last committed leaf has landed
+fn apply_settlement_batch<'info>(
+    state_channel: &mut Account<'info, StateChannel>,
+    staking_pool: &mut Account<'info, StakingPool>,
+    treasury: &mut Account<'info, Treasury>,
+    remaining_accounts: &'info [AccountInfo<'info>],
+    leaves: &[SettlementLeaf],
+    proofs: &[Vec<[u8; 32]>],
+    program_id: &Pubkey,
+) -> Result<()> {
+    if leaves.len() != proofs.len() {
+        return Err(VaultError::InvalidSettlementProof.into());
+    }
+    let from_collateral = state_channel.settlement
+        .as_ref()
+        .map_or(false, |settlement| settlement.from_collateral);
+    
+    // Collateral channels pass every participant's vault on each call;
+    // treasury-paid channels pass none
+    let expected_accounts = if from_collateral { state_channel.participants.len() } else { 0 };
+    if remaining_accounts.len() != expected_accounts {
+        return Err(VaultError::MissingRequiredAccount.into());
     }
     
-    // Apply calculations to on-chain state
-    for calculation in &final_calculations {
+    let mut batch_rewards = 0u64;
+    for (leaf, proof) in leaves.iter().zip(proofs) {
+        let reward = state_channel.apply_settlement_leaf(leaf, proof)?;
+        batch_rewards = batch_rewards
+            .checked_add(reward).ok_or(VaultError::ArithmeticOverflow)?;
+    }
+    
+    // Channels without collateral vaults are paid from the treasury
+    if !from_collateral {
+        if batch_rewards > treasury.user_rewards_pool {
+            return Err(VaultError::InsufficientBalance.into());
+        }
+        
         // In production, this would update individual user accounts
         // For now, we update the aggregate tracking
         staking_pool.rewards_distributed = staking_pool.rewards_distributed
-            .checked_add(calculation.calculated_reward).unwrap();
+            .checked_add(batch_rewards).ok_or(VaultError::ArithmeticOverflow)?;
+        treasury.user_rewards_pool -= batch_rewards;
+    }
+    
+    msg!("Applied {} settlement leaves totaling {}", leaves.len(), batch_rewards);
+    
+    if !state_channel.settlement_complete() {
+        return Ok(());
     }
     
-    // Deduct from treasury user rewards pool
-    treasury.user_rewards_pool = treasury.user_rewards_pool
-        .checked_sub(total_rewards).unwrap();
+    let entitlements = state_channel.complete_settlement()?;
+    let total_fees = if from_collateral {
+        settle_from_collateral(state_channel, treasury, remaining_accounts, &entitlements, program_id)?
+    } else {
+        0
+    };
     
-    // Settle the channel
-    state_channel.settle_channel(final_calculations.clone())?;
+    emit!(ChannelSettled {
+        channel_id: state_channel.channel_id,
+        total_rewards: state_channel.settlement_amount,
+        fee_bps: state_channel.settlement_fee_bps(),
+        total_fees,
+        cooperative: state_channel.dispute.is_none(),
+    });
     
-    msg!("State channel settled with {} reward calculations totaling {}", 
-         final_calculations.len(), total_rewards);
+    msg!("State channel settled with {} total rewards ({} in fees)", 
+         state_channel.settlement_amount, total_fees);
     
     Ok(())
 }
 
+/// Reassign each participant's collateral to their final entitlement, less
+/// the channel fee which is moved to the treasury. Lamports are moved between
+/// the vaults to match, and released through `withdraw_channel_collateral`
+/// once closed. A participant who never deposited counts as zero collateral:
+/// pass their wallet in place of the vault to pay them directly. Returns the
+/// fees taken.
+fn settle_from_collateral<'info>(
+    state_channel: &StateChannel,
+    treasury: &mut Account<'info, Treasury>,
+    vault_infos: &'info [AccountInfo<'info>],
+    entitlements: &[u64],
+    program_id: &Pubkey,
+) -> Result<u64> {
+    if vault_infos.len() != state_channel.participants.len() {
+        return Err(VaultError::MissingRequiredAccount.into());
+    }
+    
+    let mut vaults = Vec::with_capacity(vault_infos.len());
+    for (info, participant) in vault_infos.iter().zip(&state_channel.participants) {
+        if !info.is_writable {
+            return Err(VaultError::UnauthorizedAccess.into());
+        }
+        if info.key() == *participant {
+            vaults.push(None);
+            continue;
+        }
+        let (expected, _) = Pubkey::find_program_address(
+            &[b"channel_collateral", state_channel.channel_id.as_ref(), participant.as_ref()],
+            program_id,
+        );
+        if info.key() != expected {
+            return Err(VaultError::UnauthorizedAccess.into());
+        }
+        if info.data_is_empty() {
+            vaults.push(None);
+        } else {
+            vaults.push(Some(Account::<ChannelCollateral>::try_from(info)?));
+        }
+    }
+    
+    let deposits: Vec<u64> = vaults.iter().map(|vault| vault.as_ref().map_or(0, |v| v.amount)).collect();
+    let (payouts, undercollateralized) = StateChannel::allocate_collateral(&deposits, entitlements)?;
+    
+    if undercollateralized {
+        let total_collateral: u64 = deposits.iter().sum();
+        let total_entitlements: u64 = entitlements.iter().sum();
+        
+        emit!(ChannelUndercollateralized {
+            channel_id: state_channel.channel_id,
+            total_collateral,
+            total_entitlements,
+            shortfall: total_entitlements - total_collateral,
+        });
+        
+        msg!("Incident: state channel {} under-collateralized ({} collateral, {} owed), payouts clamped", 
+             bs58::encode(state_channel.channel_id).into_string(),
+             total_collateral,
+             total_entitlements);
+    }
+    
+    let (net_payouts, fees) = StateChannel::apply_settlement_fees(
+        &deposits,
+        &payouts,
+        state_channel.settlement_fee_bps(),
+    )?;
+    
+    let total_fees = fees
+        .iter()
+        .try_fold(0u64, |total, fee| total.checked_add(*fee))
+        .ok_or(VaultError::ArithmeticOverflow)?;
+    
+    // Move lamports between the vaults so each holds its participant's net
+    // payout; what the payers give up beyond that is the fee plus rounding
+    // dust, which goes to the treasury
+    let total_collateral: u64 = deposits.iter().sum();
+    let total_paid: u64 = net_payouts.iter().sum();
+    let to_treasury = total_collateral
+        .checked_sub(total_paid).ok_or(VaultError::InsufficientBalance)?;
+    let slots = vault_infos.iter().zip(&state_channel.participants).zip(vaults.iter_mut());
+    for (((info, participant), vault), (deposit, payout)) in slots.zip(deposits.iter().zip(net_payouts)) {
+        let Some(vault) = vault else {
+            // Without a vault only the participant's own wallet can be paid
+            if payout > 0 {
+                if info.key() != *participant {
+                    return Err(VaultError::MissingRequiredAccount.into());
+                }
+                **info.try_borrow_mut_lamports()? += payout;
+            }
+            continue;
+        };
+        if payout < *deposit {
+            **info.try_borrow_mut_lamports()? -= deposit - payout;
+        } else {
+            **info.try_borrow_mut_lamports()? += payout - deposit;
+        }
+        vault.amount = payout;
+        vault.exit(program_id)?;
+    }
+    
+    **treasury.to_account_info().try_borrow_mut_lamports()? += to_treasury;
+    treasury.sol_balance = treasury.sol_balance
+        .checked_add(to_treasury).ok_or(VaultError::ArithmeticOverflow)?;
+    
+    Ok(total_fees)
+}
+
+/// Lock collateral into a state channel
+pub fn deposit_channel_collateral(
+    ctx: Context<DepositChannelCollateral>,
+    amount: u64,
+    session_id: String,
+) -> Result<()> {
+    require_session_permission(&ctx.accounts.user_auth, &session_id, SessionPermission::ChannelOps)?;
+    ctx.accounts.emergency_state.require_not_paused(EmergencyScope::Channels)?;
+    ctx.accounts.kyc_profile.require_not_frozen(FrozenAction::Channels)?;
+
+    let state_channel = &mut ctx.accounts.state_channel;
+    let participant = ctx.accounts.participant.key();
+    
+    if !state_channel.is_active {
+        return Err(VaultError::SecurityViolation.into());
+    }
+    if !state_channel.participants.contains(&participant) {
+        return Err(VaultError::UnauthorizedAccess.into());
+    }
+    
+    anchor_lang::system_program::transfer(
+        CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: ctx.accounts.participant.to_account_info(),
+                to: ctx.accounts.collateral.to_account_info(),
+            },
+        ),
+        amount,
+    )?;
+    
+    let collateral = &mut ctx.accounts.collateral;
+    if collateral.participant == Pubkey::default() {
+        collateral.channel_id = state_channel.channel_id;
+        collateral.participant = participant;
+        collateral.bump = ctx.bumps.collateral;
+    }
+    collateral.deposit(amount)?;
+    state_channel.collateralized = true;
+    
+    msg!("Participant {} deposited {} collateral (total {})", participant, amount, collateral.amount);
+    
+    Ok(())
+}
+
+/// Withdraw collateral. While the channel is open every counterparty must
+/// sign an approval bound to the amount and the vault's withdrawal nonce.
+/// Once it is settled, cooperatively or by a forced settlement, each vault
+/// already holds its participant's final allocation and is freely withdrawable.
+pub fn withdraw_channel_collateral(
+    ctx: Context<WithdrawChannelCollateral>,
+    amount: u64,
+    signed_approval: Vec<Vec<u8>>,
+    session_id: String,
+) -> Result<()> {
+    require_session_permission(&ctx.accounts.user_auth, &session_id, SessionPermission::ChannelOps)?;
+    ctx.accounts.emergency_state.require_not_paused(EmergencyScope::Channels)?;
+    ctx.accounts.kyc_profile.require_not_frozen(FrozenAction::Channels)?;
+
+    let participant = ctx.accounts.participant.key();
+    let channel_info = &ctx.accounts.state_channel;
+    
+    let state_channel = StateChannel::try_deserialize(&mut &channel_info.try_borrow_data()?[..])?;
+    
+    // Challenged channels stay locked until the dispute is resolved
+    if !state_channel.is_active && !state_channel.is_settled {
+        return Err(VaultError::SecurityViolation.into());
+    }
+    
+    if state_channel.is_active {
+        let counterparties: Vec<Pubkey> = state_channel.participants
+            .iter()
+            .filter(|key| **key != participant)
+            .cloned()
+            .collect();
+        let message = Ed25519Verifier::collateral_withdrawal_message(
+            &state_channel.channel_id,
+            &participant,
+            amount,
+            ctx.accounts.collateral.withdrawal_nonce,
+        );
+        Ed25519Verifier::verify_participant_signatures(
+            &ctx.accounts.instructions_sysvar.to_account_info(),
+            &counterparties,
+            &signed_approval,
+            &message,
+        )?;
+    }
+    
+    let collateral = &mut ctx.accounts.collateral;
+    collateral.withdraw(amount)?;
+    
+    let vault_info = collateral.to_account_info();
+    **vault_info.try_borrow_mut_lamports()? -= amount;
+    **ctx.accounts.participant.to_account_info().try_borrow_mut_lamports()? += amount;
+    
+    msg!("Participant {} withdrew {} collateral (remaining {})", participant, amount, collateral.amount);
+    
+    Ok(())
+}
+
+/// Permissionlessly settle a channel abandoned past its timeout, paying
+/// each participant's latest balance. Remaining accounts are every
+/// participant's collateral vault for collateralized channels, settled as in
+/// `settle_from_collateral`, and otherwise every participant's rewards
+/// account, credited from the treasury and created by the cranker if it
+/// doesn't exist yet. Both in participant order. The cranker is paid a fixed
+/// fee from the treasury, capped by its SOL balance.
+pub fn force_settle_expired_channel<'info>(
+    ctx: Context<'_, '_, 'info, 'info, ForceSettleExpiredChannel<'info>>,
+) -> Result<()> {
+    ctx.accounts.emergency_state.require_not_paused(EmergencyScope::Channels)?;
+
+    let state_channel = &mut ctx.accounts.state_channel;
+    let treasury = &mut ctx.accounts.treasury;
+    
+    let entitlements = state_channel.force_settle_expired(Clock::get()?.unix_timestamp)?;
+    
+    let total_fees = if state_channel.collateralized {
+        settle_from_collateral(state_channel, treasury, ctx.remaining_accounts, &entitlements, ctx.program_id)?
+    } else {
+        credit_from_treasury(
+            state_channel,
+            &mut ctx.accounts.staking_pool,
+            treasury,
+            ctx.remaining_accounts,
+            &entitlements,
+            &ctx.accounts.cranker,
+            &ctx.accounts.system_program,
+            ctx.program_id,
+        )?;
+        0
+    };
+    
+    let crank_fee = StateChannel::FORCE_SETTLE_CRANK_FEE.min(treasury.sol_balance);
+    if crank_fee > 0 {
+        **treasury.to_account_info().try_borrow_mut_lamports()? -= crank_fee;
+        **ctx.accounts.cranker.to_account_info().try_borrow_mut_lamports()? += crank_fee;
+        treasury.sol_balance -= crank_fee;
+    }
+    
+    emit!(ChannelSettled {
+        channel_id: state_channel.channel_id,
+        total_rewards: state_channel.settlement_amount,
+        fee_bps: state_channel.settlement_fee_bps(),
+        total_fees,
+        cooperative: false,
+    });
+    
+    msg!("Expired state channel {} force-settled at nonce {} with {} total rewards by {} (crank fee {})", 
+         bs58::encode(state_channel.channel_id).into_string(),
+         state_channel.nonce,
+         state_channel.settlement_amount,
+         ctx.accounts.cranker.key(),
+         crank_fee);
+    
+    Ok(())
+}
+
+/// Credit each participant's entitlement to their pending rewards, paid out
+/// of the treasury user rewards pool. Rewards accounts a participant never
+/// opened are created with `payer` funding the rent, or skipped when nothing
+/// is owed.
+fn credit_from_treasury<'info>(
+    state_channel: &StateChannel,
+    staking_pool: &mut Account<'info, StakingPool>,
+    treasury: &mut Account<'info, Treasury>,
+    rewards_infos: &'info [AccountInfo<'info>],
+    entitlements: &[u64],
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    program_id: &Pubkey,
+) -> Result<()> {
+    if rewards_infos.len() != state_channel.participants.len() {
+        return Err(VaultError::MissingRequiredAccount.into());
+    }
+    
+    let total = entitlements
+        .iter()
+        .try_fold(0u64, |total, amount| total.checked_add(*amount))
+        .ok_or(VaultError::ArithmeticOverflow)?;
+    treasury.withdraw_user_rewards(total)?;
+    staking_pool.rewards_distributed = staking_pool.rewards_distributed
+        .checked_add(total).ok_or(VaultError::ArithmeticOverflow)?;
+    
+    for ((info, participant), amount) in rewards_infos.iter().zip(&state_channel.participants).zip(entitlements) {
+        if info.data_is_empty() {
+            if *amount == 0 {
+                continue;
+            }
+            create_participant_rewards(info, participant, payer, system_program, program_id)?;
+        }
+        credit_participant_rewards(info, participant, *amount, program_id)?;
+    }
+    
+    Ok(())
+}
+
+/// Open an empty rewards account on a participant's behalf
+fn create_participant_rewards<'info>(
+    rewards_info: &'info AccountInfo<'info>,
+    participant: &Pubkey,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    program_id: &Pubkey,
+) -> Result<()> {
+    let (expected, bump) = Pubkey::find_program_address(&[b"rewards", participant.as_ref()], program_id);
+    if rewards_info.key() != expected || !rewards_info.is_writable {
+        return Err(VaultError::UnauthorizedAccess.into());
+    }
+    
+    let seeds: &[&[u8]] = &[b"rewards", participant.as_ref(), &[bump]];
+    anchor_lang::system_program::create_account(
+        CpiContext::new_with_signer(
+            system_program.to_account_info(),
+            anchor_lang::system_program::CreateAccount {
+                from: payer.to_account_info(),
+                to: rewards_info.clone(),
+            },
+            &[seeds],
+        ),
+        Rent::get()?.minimum_balance(UserRewards::LEN),
+        UserRewards::LEN as u64,
+        program_id,
+    )?;
+    
+    let user_rewards = UserRewards {
+        user: *participant,
+        pending_rewards: 0,
+        total_reinvested: 0,
+        last_claim_request: 0,
+        claim_nonce: 0,
+        bump,
+    };
+    let mut data = rewards_info.try_borrow_mut_data()?;
+    user_rewards.try_serialize(&mut &mut data[..])
+}
+
+/// Record a mutually signed checkpoint to bound dispute evidence
+pub fn checkpoint_state(
+    ctx: Context<CheckpointState>,
+    state_hash: [u8; 32],
+    version: u64,
+    signatures: Vec<Vec<u8>>,
+) -> Result<()> {
+    let state_channel = &mut ctx.accounts.state_channel;
+    
+    // Verify participant is authorized
+    if !state_channel.participants.contains(&ctx.accounts.participant.key()) {
+        return Err(VaultError::UnauthorizedAccess.into());
+    }
+    
+    // Every participant must have signed this exact checkpoint
+    let message = Ed25519Verifier::checkpoint_message(&state_channel.channel_id, version, &state_hash);
+    Ed25519Verifier::verify_participant_signatures(
+        &ctx.accounts.instructions_sysvar.to_account_info(),
+        &state_channel.participants,
+        &signatures,
+        &message,
+    )?;
+    
+    state_channel.record_checkpoint(
+        state_hash,
+        version,
+        signatures,
+        Clock::get()?.unix_timestamp,
+    )?;
+    
+    msg!("State channel checkpointed at version {}", version);
+    
+    Ok(())
+}
+
+/// Add or remove participants on a live state channel. Departing
+/// participants are paid their pending slice on the way out: from their own
+/// collateral vault on collateralized channels, otherwise credited to their
+/// rewards account from the treasury.
+pub fn propose_participant_change<'info>(
+    ctx: Context<'_, '_, 'info, 'info, ProposeParticipantChange<'info>>,
+    add: Vec<Pubkey>,
+    remove: Vec<Pubkey>,
+) -> Result<()> {
+    ctx.accounts.emergency_state.require_not_paused(EmergencyScope::Channels)?;
+
+    let state_channel = &mut ctx.accounts.state_channel;
+    let staking_pool = &mut ctx.accounts.staking_pool;
+    let treasury = &mut ctx.accounts.treasury;
+    let program_id = ctx.program_id;
+    
+    if ctx.remaining_accounts.len() < remove.len() {
+        return Err(VaultError::MissingRequiredAccount.into());
+    }
+    let (signer_infos, payout_infos) = ctx.remaining_accounts
+        .split_at(ctx.remaining_accounts.len() - remove.len());
+    
+    let mut signers: Vec<Pubkey> = signer_infos
+        .iter()
+        .filter(|account| account.is_signer)
+        .map(|account| account.key())
+        .collect();
+    signers.push(ctx.accounts.proposer.key());
+    
+    let settled = state_channel.change_participants(
+        &add,
+        &remove,
+        &signers,
+        Clock::get()?.unix_timestamp,
+    )?;
+    
+    // Settle departing participants' slices before they leave the channel
+    let mut total_settled = 0u64;
+    for (participant, amount) in settled {
+        let index = remove.iter().position(|key| *key == participant)
+            .ok_or(VaultError::InvalidAllocation)?;
+        let payout_info = &payout_infos[index];
+        
+        if state_channel.collateralized {
+            let recipient = signer_infos
+                .iter()
+                .chain(std::iter::once(&ctx.accounts.proposer.to_account_info()))
+                .find(|info| info.key() == participant && info.is_writable)
+                .cloned()
+                .ok_or(VaultError::MissingRequiredAccount)?;
+            pay_from_collateral(state_channel, payout_info, &recipient, &participant, amount, program_id)?;
+        } else {
+            treasury.withdraw_user_rewards(amount)?;
+            staking_pool.rewards_distributed = staking_pool.rewards_distributed
+                .checked_add(amount).ok_or(VaultError::ArithmeticOverflow)?;
+            credit_participant_rewards(payout_info, &participant, amount, program_id)?;
+        }
+        
+        total_settled = total_settled
+            .checked_add(amount).ok_or(VaultError::ArithmeticOverflow)?;
+    }
+    
+    msg!("State channel participants changed (+{} -{}), {} settled on removal, nonce {}", 
+         add.len(), remove.len(), total_settled, state_channel.nonce);
+    
+    Ok(())
+}
+
+/// Release `amount` from a participant's collateral vault straight to them
+fn pay_from_collateral<'info>(
+    state_channel: &StateChannel,
+    vault_info: &'info AccountInfo<'info>,
+    recipient: &AccountInfo<'info>,
+    participant: &Pubkey,
+    amount: u64,
+    program_id: &Pubkey,
+) -> Result<()> {
+    let (expected, _) = Pubkey::find_program_address(
+        &[b"channel_collateral", state_channel.channel_id.as_ref(), participant.as_ref()],
+        program_id,
+    );
+    if vault_info.key() != expected || !vault_info.is_writable {
+        return Err(VaultError::UnauthorizedAccess.into());
+    }
+    
+    let mut vault = Account::<ChannelCollateral>::try_from(vault_info)?;
+    if vault.amount < amount {
+        return Err(VaultError::InsufficientBalance.into());
+    }
+    vault.amount -= amount;
+    vault.exit(program_id)?;
+    
+    **vault_info.try_borrow_mut_lamports()? -= amount;
+    **recipient.try_borrow_mut_lamports()? += amount;
+    
+    Ok(())
+}
+
+/// Credit `amount` to a participant's pending rewards
+fn credit_participant_rewards<'info>(
+    rewards_info: &'info AccountInfo<'info>,
+    participant: &Pubkey,
+    amount: u64,
+    program_id: &Pubkey,
+) -> Result<()> {
+    let (expected, _) = Pubkey::find_program_address(&[b"rewards", participant.as_ref()], program_id);
+    if rewards_info.key() != expected || !rewards_info.is_writable {
+        return Err(VaultError::UnauthorizedAccess.into());
+    }
+    let mut user_rewards = Account::<UserRewards>::try_from(rewards_info)?;
+    user_rewards.credit(amount)?;
+    user_rewards.exit(program_id)
+}
+
 /// Challenge a state channel update (dispute mechanism)
 pub fn challenge_state_channel(
     ctx: Context<ChallengeStateChannel>,
     disputed_state_hash: [u8; 32],
+    disputed_version: u64,
     evidence: Vec<u8>,
+    newer_state: Option<SignedStateProof>,
 ) -> Result<()> {
     let state_channel = &mut ctx.accounts.state_channel;
     let challenger = ctx.accounts.challenger.key();
     
+    // A newer fully signed state settles the dispute outright
+    if let Some(proof) = newer_state {
+        let message = Ed25519Verifier::state_update_message(
+            &state_channel.channel_id,
+            proof.update.nonce,
+            &proof.update.new_state_hash,
+        );
+        Ed25519Verifier::verify_participant_signatures(
+            &ctx.accounts.instructions_sysvar.to_account_info(),
+            &state_channel.participants,
+            &proof.signatures,
+            &message,
+        )?;
+        
+        let stale_version = state_channel.nonce;
+        let (stale_submitter, penalty) = state_channel.override_with_newer_state(
+            challenger,
+            proof.update,
+            proof.signatures,
+            Clock::get()?.unix_timestamp,
+        )?;
+        
+        emit!(StaleStateOverridden {
+            channel_id: state_channel.channel_id,
+            stale_version,
+            new_version: state_channel.nonce,
+            stale_submitter,
+            challenger,
+            penalty,
+        });
+        
+        msg!("Stale state {} overridden by version {} from {}", 
+             stale_version, state_channel.nonce, challenger);
+        
+        return Ok(());
+    }
+    
     let dispute_data = DisputeData {
         challenger,
         disputed_state_hash,
         evidence,
+        disputed_version,
         challenge_timestamp: Clock::get()?.unix_timestamp,
     };
     
@@ -196,6 +1173,24 @@ pub fn challenge_state_channel(
     Ok(())
 }
 
+/// Answer an open challenge with counter-evidence
+pub fn respond_to_challenge(
+    ctx: Context<RespondToChallenge>,
+    evidence: Vec<u8>,
+) -> Result<()> {
+    let state_channel = &mut ctx.accounts.state_channel;
+    let responder = ctx.accounts.responder.key();
+    
+    state_channel.respond_to_challenge(responder, &evidence, Clock::get()?.unix_timestamp)?;
+    
+    if let Some(dispute) = &state_channel.dispute {
+        msg!("Challenge answered by {}, dispute window now closes at {} ({} extensions)", 
+             responder, dispute.deadline, dispute.extensions);
+    }
+    
+    Ok(())
+}
+
 /// Process off-chain reward calculations and create state channel update
 pub fn process_off_chain_rewards(
     users_and_commitments: Vec<(Pubkey, u64)>,
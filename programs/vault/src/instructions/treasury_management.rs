@@ -8,6 +8,7 @@ use anchor_spl::token::{self, Token, TokenAccount, Transfer};
 use crate::state::treasury_management::*;
 use crate::state::treasury::Treasury;
 use crate::state::multisig_wallet::MultisigWallet;
+use crate::state::emergency_state::{EmergencyScope, EmergencyState};
 use crate::errors::VaultError;
 
 /// Initialize a new treasury vault for advanced management
@@ -50,6 +51,12 @@ pub struct AddYieldStrategy<'info> {
     )]
     pub treasury_vault: Account<'info, TreasuryVault>,
     
+    #[account(
+        seeds = [b"emergency_state"],
+        bump = emergency_state.bump
+    )]
+    pub emergency_state: Account<'info, EmergencyState>,
+    
     #[account(mut)]
     pub authority: Signer<'info>,
     
@@ -68,6 +75,12 @@ pub struct AddLiquidityPool<'info> {
     )]
     pub treasury_vault: Account<'info, TreasuryVault>,
     
+    #[account(
+        seeds = [b"emergency_state"],
+        bump = emergency_state.bump
+    )]
+    pub emergency_state: Account<'info, EmergencyState>,
+    
     #[account(mut)]
     pub authority: Signer<'info>,
     
@@ -91,6 +104,12 @@ pub struct ExecuteAdvancedRebalancing<'info> {
     )]
     pub treasury_vault: Account<'info, TreasuryVault>,
     
+    #[account(
+        seeds = [b"emergency_state"],
+        bump = emergency_state.bump
+    )]
+    pub emergency_state: Account<'info, EmergencyState>,
+    
     #[account(
         mut,
         seeds = [b"treasury"],
@@ -243,6 +262,8 @@ impl<'info> AddYieldStrategy<'info> {
         risk_level: u8,
         parameters: Vec<u8>,
     ) -> Result<()> {
+        ctx.accounts.emergency_state.require_not_paused(EmergencyScope::Treasury)?;
+
         let treasury_vault = &mut ctx.accounts.treasury_vault;
         
         // Verify authority is a multisig signer
@@ -316,6 +337,8 @@ impl<'info> AddLiquidityPool<'info> {
         dex_protocol: String,
         liquidity_amount: u64,
     ) -> Result<()> {
+        ctx.accounts.emergency_state.require_not_paused(EmergencyScope::Treasury)?;
+
         let treasury_vault = &mut ctx.accounts.treasury_vault;
         
         // Verify authority is a multisig signer
@@ -367,6 +390,8 @@ impl<'info> ExecuteAdvancedRebalancing<'info> {
         amount: u64,
         strategy_id: Option<u64>,
     ) -> Result<()> {
+        ctx.accounts.emergency_state.require_not_paused(EmergencyScope::Treasury)?;
+
         let treasury_vault = &mut ctx.accounts.treasury_vault;
         let treasury = &ctx.accounts.treasury;
         
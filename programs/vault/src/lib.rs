@@ -21,10 +21,9 @@ use instructions::authentication::*;
 use instructions::treasury_management::*;
 use instructions::security_monitoring::*;
 use crate::traits::PaymentType;
-use crate::state::{StateChannelUpdate, SignerInfo, TransactionType, TransactionPriority, SignatureType, PaymentMethod, LightningConfig, UsdcConfig, ReinvestmentConfig};
-use crate::state::rewards::RewardCalculation;
-use crate::state::kyc_compliance::{KYCStatus, ComplianceRegion, KYCVerification, AMLScreening};
-use crate::state::authentication::{AuthMethod, SessionStatus, SecurityEventType};
+use crate::state::{StateChannelUpdate, SignedStateProof, ChannelPurpose, SettlementLeaf, SignerInfo, TransactionType, TransactionPriority, SignatureType, PaymentMethod, LightningConfig, UsdcConfig, SplTokenConfig, ReinvestmentConfig, PaymentHistoryPage, DailyRollup, SplitPart, PaymentQuote, VelocityLimits, ScreeningPolicy, StreamFunding, ActivityPage, OfflineApproval, EmergencyScopes, HsmAttestation, SimulationResult};
+use crate::state::kyc_compliance::{KYCStatus, KycTier, TierLimits, ComplianceRegion, KYCVerification, AMLScreening, FreezeScope, ScreeningRecord, ComplianceCheck, ComplianceFlagKind};
+use crate::state::authentication::{AuthMethod, SessionStatus, SecurityEventType, EventArchiveRoot, SessionEvictionPolicy, WebAuthnAssertion, SessionPermission, StepUpOperation};
 use crate::state::security_monitoring::{SecurityEventType as MonitoringEventType, SecurityLevel, AlertStatus};
 
 declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
@@ -125,6 +124,18 @@ pub mod vault {
     }
 
     // Reward instructions
+    pub fn initialize_reward_pool(ctx: Context<InitializeRewardPool>) -> Result<()> {
+        instructions::rewards::initialize_reward_pool(ctx)
+    }
+
+    pub fn initialize_reward_stats(ctx: Context<InitializeRewardStats>) -> Result<()> {
+        instructions::rewards::initialize_reward_stats(ctx)
+    }
+
+    pub fn initialize_user_rewards(ctx: Context<InitializeUserRewards>) -> Result<()> {
+        instructions::rewards::initialize_user_rewards(ctx)
+    }
+
     pub fn calculate_rewards(
         ctx: Context<CalculateRewards>,
         total_staking_rewards: u64,
@@ -140,8 +151,9 @@ pub mod vault {
     pub fn claim_rewards(
         ctx: Context<ClaimRewards>,
         payment_type: PaymentType,
+        session_id: String,
     ) -> Result<()> {
-        instructions::rewards::claim_rewards(ctx, payment_type)
+        instructions::rewards::claim_rewards(ctx, payment_type, session_id)
     }
 
     pub fn update_reward_rates(
@@ -151,14 +163,28 @@ pub mod vault {
         instructions::rewards::update_reward_rates(ctx, new_user_share_bps)
     }
 
+    pub fn enable_multisig_rate_control(ctx: Context<EnableMultisigRateControl>) -> Result<()> {
+        instructions::rewards::enable_multisig_rate_control(ctx)
+    }
+
     // State channel instructions
-    pub fn initialize_state_channel(
-        ctx: Context<InitializeStateChannel>,
+    pub fn initialize_state_channel<'info>(
+        ctx: Context<'_, '_, 'info, 'info, InitializeStateChannel<'info>>,
         channel_id: [u8; 32],
         participants: Vec<Pubkey>,
         timeout_seconds: i64,
+        challenge_extension: Option<i64>,
+        max_participants: Option<u8>,
+        purpose: ChannelPurpose,
+    ) -> Result<()> {
+        instructions::state_channel::initialize_state_channel(ctx, channel_id, participants, timeout_seconds, challenge_extension, max_participants, purpose)
+    }
+
+    pub fn update_channel_fee(
+        ctx: Context<UpdateChannelFee>,
+        fee_bps: u16,
     ) -> Result<()> {
-        instructions::state_channel::initialize_state_channel(ctx, channel_id, participants, timeout_seconds)
+        instructions::state_channel::update_channel_fee(ctx, fee_bps)
     }
 
     pub fn update_state_channel(
@@ -169,28 +195,90 @@ pub mod vault {
         instructions::state_channel::update_state_channel(ctx, update, signatures)
     }
 
-    pub fn settle_state_channel(
-        ctx: Context<SettleStateChannel>,
-        final_calculations: Vec<RewardCalculation>,
+    pub fn settle_state_channel<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SettleStateChannel<'info>>,
+        settlement_root: [u8; 32],
+        leaf_count: u32,
+        first_batch: Vec<SettlementLeaf>,
+        proofs: Vec<Vec<[u8; 32]>>,
     ) -> Result<()> {
-        instructions::state_channel::settle_state_channel(ctx, final_calculations)
+        instructions::state_channel::settle_state_channel(ctx, settlement_root, leaf_count, first_batch, proofs)
+    }
+
+    pub fn settle_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SettleChannelBatch<'info>>,
+        leaves: Vec<SettlementLeaf>,
+        proofs: Vec<Vec<[u8; 32]>>,
+    ) -> Result<()> {
+        instructions::state_channel::settle_batch(ctx, leaves, proofs)
     }
 
     pub fn challenge_state_channel(
         ctx: Context<ChallengeStateChannel>,
         disputed_state_hash: [u8; 32],
+        disputed_version: u64,
+        evidence: Vec<u8>,
+        newer_state: Option<SignedStateProof>,
+    ) -> Result<()> {
+        instructions::state_channel::challenge_state_channel(ctx, disputed_state_hash, disputed_version, evidence, newer_state)
+    }
+
+    pub fn deposit_channel_collateral(
+        ctx: Context<DepositChannelCollateral>,
+        amount: u64,
+        session_id: String,
+    ) -> Result<()> {
+        instructions::state_channel::deposit_channel_collateral(ctx, amount, session_id)
+    }
+
+    pub fn withdraw_channel_collateral(
+        ctx: Context<WithdrawChannelCollateral>,
+        amount: u64,
+        signed_approval: Vec<Vec<u8>>,
+        session_id: String,
+    ) -> Result<()> {
+        instructions::state_channel::withdraw_channel_collateral(ctx, amount, signed_approval, session_id)
+    }
+
+    pub fn force_settle_expired_channel<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ForceSettleExpiredChannel<'info>>,
+    ) -> Result<()> {
+        instructions::state_channel::force_settle_expired_channel(ctx)
+    }
+
+    pub fn respond_to_challenge(
+        ctx: Context<RespondToChallenge>,
         evidence: Vec<u8>,
     ) -> Result<()> {
-        instructions::state_channel::challenge_state_channel(ctx, disputed_state_hash, evidence)
+        instructions::state_channel::respond_to_challenge(ctx, evidence)
+    }
+
+    pub fn checkpoint_state(
+        ctx: Context<CheckpointState>,
+        state_hash: [u8; 32],
+        version: u64,
+        signatures: Vec<Vec<u8>>,
+    ) -> Result<()> {
+        instructions::state_channel::checkpoint_state(ctx, state_hash, version, signatures)
+    }
+
+    pub fn propose_participant_change<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ProposeParticipantChange<'info>>,
+        add: Vec<Pubkey>,
+        remove: Vec<Pubkey>,
+    ) -> Result<()> {
+        instructions::state_channel::propose_participant_change(ctx, add, remove)
     }
 
     // Multisig instructions
     pub fn initialize_multisig_wallet(
         ctx: Context<InitializeMultisigWallet>,
         signers: Vec<SignerInfo>,
+        threshold: u8,
         hsm_enabled: bool,
+        hsm_quorum: u8,
     ) -> Result<()> {
-        instructions::multisig::initialize_multisig_wallet(ctx, signers, hsm_enabled)
+        instructions::multisig::initialize_multisig_wallet(ctx, signers, threshold, hsm_enabled, hsm_quorum)
     }
 
     pub fn propose_multisig_transaction(
@@ -198,42 +286,103 @@ pub mod vault {
         transaction_type: TransactionType,
         priority: TransactionPriority,
         transaction_data: Vec<u8>,
+        expires_at: Option<i64>,
     ) -> Result<()> {
-        instructions::multisig::propose_transaction(ctx, transaction_type, priority, transaction_data)
+        instructions::multisig::propose_transaction(ctx, transaction_type, priority, transaction_data, expires_at)
     }
 
     pub fn sign_multisig_transaction(
         ctx: Context<SignMultisigTransaction>,
         signature_data: [u8; 64],
-        hsm_signature: Option<Vec<u8>>,
+        hsm_attestation: Option<HsmAttestation>,
         signature_type: SignatureType,
     ) -> Result<()> {
-        instructions::multisig::sign_transaction(ctx, signature_data, hsm_signature, signature_type)
+        instructions::multisig::sign_transaction(ctx, signature_data, hsm_attestation, signature_type)
+    }
+
+    pub fn submit_aggregated_approvals(
+        ctx: Context<SubmitAggregatedApprovals>,
+        transaction_id: u32,
+        approvals: Vec<OfflineApproval>,
+    ) -> Result<()> {
+        instructions::multisig::submit_aggregated_approvals(ctx, transaction_id, approvals)
     }
 
-    pub fn execute_multisig_transaction(
-        ctx: Context<ExecuteMultisigTransaction>,
+    pub fn execute_multisig_transaction<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ExecuteMultisigTransaction<'info>>,
     ) -> Result<()> {
         instructions::multisig::execute_transaction(ctx)
     }
 
-    pub fn rotate_multisig_keys(
-        ctx: Context<RotateMultisigKeys>,
-        new_signers: Vec<SignerInfo>,
+    pub fn simulate_multisig_transaction<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SimulateMultisigTransaction<'info>>,
+        transaction_id: u32,
+    ) -> Result<SimulationResult> {
+        instructions::multisig::simulate_transaction(ctx, transaction_id)
+    }
+
+    pub fn cancel_multisig_transaction(
+        ctx: Context<CancelMultisigTransaction>,
+        reason: String,
+    ) -> Result<()> {
+        instructions::multisig::cancel_transaction(ctx, reason)
+    }
+
+    pub fn get_multisig_activity(
+        ctx: Context<GetMultisigActivity>,
+        cursor: u64,
+    ) -> Result<ActivityPage> {
+        instructions::multisig::get_multisig_activity(ctx, cursor)
+    }
+
+    pub fn veto_multisig_transaction(
+        ctx: Context<VetoMultisigTransaction>,
+        transaction_id: u32,
+        reason: String,
+    ) -> Result<()> {
+        instructions::multisig::veto_transaction(ctx, transaction_id, reason)
+    }
+
+    pub fn execute_approved_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ExecuteApprovedBatch<'info>>,
+        max_count: u8,
+    ) -> Result<()> {
+        instructions::multisig::execute_approved_batch(ctx, max_count)
+    }
+
+    pub fn cancel_expired_transactions<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CancelExpiredTransactions<'info>>,
     ) -> Result<()> {
-        instructions::multisig::rotate_keys(ctx, new_signers)
+        instructions::multisig::cancel_expired_transactions(ctx)
     }
 
     pub fn activate_emergency_mode(
         ctx: Context<EmergencyAction>,
+        scopes: EmergencyScopes,
     ) -> Result<()> {
-        instructions::multisig::activate_emergency_mode(ctx)
+        instructions::multisig::activate_emergency_mode(ctx, scopes)
     }
 
     pub fn deactivate_emergency_mode(
         ctx: Context<EmergencyAction>,
+        scopes: EmergencyScopes,
+    ) -> Result<()> {
+        instructions::multisig::deactivate_emergency_mode(ctx, scopes)
+    }
+
+    pub fn parent_rotate_child_signers(
+        ctx: Context<ParentRotateChildSigners>,
+        new_signers: Vec<SignerInfo>,
+    ) -> Result<()> {
+        instructions::multisig::parent_rotate_child_signers(ctx, new_signers)
+    }
+
+    pub fn parent_override_emergency(
+        ctx: Context<ParentOverrideEmergency>,
+        scopes: EmergencyScopes,
+        pause: bool,
     ) -> Result<()> {
-        instructions::multisig::deactivate_emergency_mode(ctx)
+        instructions::multisig::parent_override_emergency(ctx, scopes, pause)
     }
 
     // Payment system instructions
@@ -252,13 +401,34 @@ pub mod vault {
         instructions::payment::initialize_user_preferences(ctx, default_method)
     }
 
-    pub fn create_payment_request(
-        ctx: Context<CreatePaymentRequest>,
+    pub fn create_payment_request<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CreatePaymentRequest<'info>>,
         method: Option<PaymentMethod>,
         amount: u64,
         destination: String,
+        use_saved_destination: Option<u8>,
+        quote_id: Option<u64>,
+        client_idempotency_key: Option<[u8; 32]>,
+        session_id: String,
+    ) -> Result<u64> {
+        instructions::payment::create_payment_request(ctx, method, amount, destination, use_saved_destination, quote_id, client_idempotency_key, session_id)
+    }
+
+    pub fn create_split_payment_request<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CreatePaymentRequest<'info>>,
+        amount: u64,
+        parts: Vec<SplitPart>,
+        session_id: String,
     ) -> Result<()> {
-        instructions::payment::create_payment_request(ctx, method, amount, destination)
+        instructions::payment::create_split_payment_request(ctx, amount, parts, session_id)
+    }
+
+    pub fn quote_payment(
+        ctx: Context<QuotePayment>,
+        method: PaymentMethod,
+        amount: u64,
+    ) -> Result<PaymentQuote> {
+        instructions::payment::quote_payment(ctx, method, amount)
     }
 
     pub fn process_payment(
@@ -268,29 +438,82 @@ pub mod vault {
         instructions::payment::process_payment(ctx, payment_id)
     }
 
-    pub fn approve_payment(
-        ctx: Context<ApprovePayment>,
-        payment_id: u64,
-    ) -> Result<()> {
-        instructions::payment::approve_payment(ctx, payment_id)
-    }
-
     pub fn complete_payment(
-        ctx: Context<ProcessPayment>,
+        ctx: Context<CompletePayment>,
         payment_id: u64,
+        claim_id: u64,
         success: bool,
         failure_reason: Option<String>,
     ) -> Result<()> {
-        instructions::payment::complete_payment(ctx, payment_id, success, failure_reason)
+        instructions::payment::complete_payment(ctx, payment_id, claim_id, success, failure_reason)
     }
 
     pub fn cancel_payment(
-        ctx: Context<CreatePaymentRequest>,
+        ctx: Context<CancelPayment>,
         payment_id: u64,
     ) -> Result<()> {
         instructions::payment::cancel_payment(ctx, payment_id)
     }
 
+    pub fn attach_travel_rule_data(
+        ctx: Context<AttachTravelRuleData>,
+        payment_id: u64,
+        data_hash: [u8; 32],
+        provider_id: u32,
+    ) -> Result<()> {
+        instructions::payment::attach_travel_rule_data(ctx, payment_id, data_hash, provider_id)
+    }
+
+    pub fn replace_payment_destination<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ReplacePaymentDestination<'info>>,
+        payment_id: u64,
+        new_destination: String,
+        use_saved_destination: Option<u8>,
+    ) -> Result<()> {
+        instructions::payment::replace_payment_destination(ctx, payment_id, new_destination, use_saved_destination)
+    }
+
+    pub fn requeue_dead_letter(
+        ctx: Context<RequeueDeadLetter>,
+        payment_id: u64,
+    ) -> Result<()> {
+        instructions::payment::requeue_dead_letter(ctx, payment_id)
+    }
+
+    pub fn refund_dead_letter(
+        ctx: Context<RefundDeadLetter>,
+        payment_id: u64,
+    ) -> Result<()> {
+        instructions::payment::refund_dead_letter(ctx, payment_id)
+    }
+
+    pub fn refund_payment(
+        ctx: Context<RefundPayment>,
+        payment_id: u64,
+    ) -> Result<()> {
+        instructions::payment::refund_payment(ctx, payment_id)
+    }
+
+    pub fn get_user_payment_history(
+        ctx: Context<GetUserPaymentHistory>,
+        cursor: u64,
+    ) -> Result<PaymentHistoryPage> {
+        instructions::payment::get_user_payment_history(ctx, cursor)
+    }
+
+    pub fn get_payment_rollups(
+        ctx: Context<GetPaymentRollups>,
+        from_day: i64,
+    ) -> Result<Vec<DailyRollup>> {
+        instructions::payment::get_payment_rollups(ctx, from_day)
+    }
+
+    pub fn migrate_payment_history(
+        ctx: Context<MigratePaymentHistory>,
+    ) -> Result<()> {
+        instructions::payment::migrate_payment_history(ctx)
+    }
+
     pub fn update_user_preferences(
         ctx: Context<UpdateUserPreferences>,
         default_method: Option<PaymentMethod>,
@@ -301,12 +524,80 @@ pub mod vault {
         instructions::payment::update_user_preferences(ctx, default_method, lightning_address, usdc_address, reinvestment_config)
     }
 
+    pub fn create_payment_stream(
+        ctx: Context<CreatePaymentStream>,
+        stream_id: u64,
+        recipient: Pubkey,
+        funding: StreamFunding,
+        rate_per_second: u64,
+        start: i64,
+        end: i64,
+        session_id: String,
+    ) -> Result<()> {
+        instructions::payment::create_payment_stream(ctx, stream_id, recipient, funding, rate_per_second, start, end, session_id)
+    }
+
+    pub fn withdraw_from_stream(
+        ctx: Context<WithdrawFromStream>,
+        amount: u64,
+        session_id: String,
+    ) -> Result<()> {
+        instructions::payment::withdraw_from_stream(ctx, amount, session_id)
+    }
+
+    pub fn cancel_payment_stream(
+        ctx: Context<CancelPaymentStream>,
+    ) -> Result<()> {
+        instructions::payment::cancel_payment_stream(ctx)
+    }
+
+    pub fn add_saved_destination(
+        ctx: Context<ManageSavedDestinations>,
+        method: PaymentMethod,
+        destination: String,
+    ) -> Result<()> {
+        instructions::payment::add_saved_destination(ctx, method, destination)
+    }
+
+    pub fn remove_saved_destination(
+        ctx: Context<UpdateUserPreferences>,
+        index: u8,
+    ) -> Result<()> {
+        instructions::payment::remove_saved_destination(ctx, index)
+    }
+
+    pub fn set_saved_destinations_only(
+        ctx: Context<ManageSavedDestinations>,
+        enabled: bool,
+    ) -> Result<()> {
+        instructions::payment::set_saved_destinations_only(ctx, enabled)
+    }
+
     pub fn process_reinvestment(
         ctx: Context<ProcessReinvestment>,
     ) -> Result<()> {
         instructions::payment::process_reinvestment(ctx)
     }
 
+    pub fn release_reinvestment(
+        ctx: Context<ReleaseReinvestment>,
+        sats: u64,
+    ) -> Result<()> {
+        instructions::payment::release_reinvestment(ctx, sats)
+    }
+
+    pub fn expire_payment_requests<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ExpirePaymentRequests<'info>>,
+    ) -> Result<()> {
+        instructions::payment::expire_payment_requests(ctx)
+    }
+
+    pub fn run_due_reinvestments<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RunDueReinvestments<'info>>,
+    ) -> Result<()> {
+        instructions::payment::run_due_reinvestments(ctx)
+    }
+
     pub fn set_emergency_pause(
         ctx: Context<UpdatePaymentConfig>,
         paused: bool,
@@ -314,12 +605,116 @@ pub mod vault {
         instructions::payment::set_emergency_pause(ctx, paused)
     }
 
+    pub fn configure_spl_token_payment(
+        ctx: Context<UpdatePaymentConfig>,
+        config: SplTokenConfig,
+    ) -> Result<()> {
+        instructions::payment::configure_spl_token_payment(ctx, config)
+    }
+
+    pub fn remove_spl_token_payment(
+        ctx: Context<UpdatePaymentConfig>,
+        mint: Pubkey,
+    ) -> Result<()> {
+        instructions::payment::remove_spl_token_payment(ctx, mint)
+    }
+
+    pub fn set_payout_velocity_limits(
+        ctx: Context<UpdatePaymentConfig>,
+        limits: VelocityLimits,
+    ) -> Result<()> {
+        instructions::payment::set_payout_velocity_limits(ctx, limits)
+    }
+
+    pub fn set_screening_policy(
+        ctx: Context<UpdatePaymentConfig>,
+        policy: ScreeningPolicy,
+    ) -> Result<()> {
+        instructions::payment::set_screening_policy(ctx, policy)
+    }
+
+    pub fn screen_destination(
+        ctx: Context<ScreenDestination>,
+        destination_hash: [u8; 32],
+        screening: AMLScreening,
+        sanctions_proof: Option<Vec<[u8; 32]>>,
+    ) -> Result<()> {
+        instructions::payment::screen_destination(ctx, destination_hash, screening, sanctions_proof)
+    }
+
+    pub fn create_payment_schedule(
+        ctx: Context<CreatePaymentSchedule>,
+        method: PaymentMethod,
+        destination: Option<Pubkey>,
+        use_saved_destination: Option<u8>,
+        frequency_secs: i64,
+        max_per_period: u64,
+        first_run_at: i64,
+        session_id: String,
+    ) -> Result<()> {
+        instructions::payment::create_payment_schedule(ctx, method, destination, use_saved_destination, frequency_secs, max_per_period, first_run_at, session_id)
+    }
+
+    pub fn update_payment_schedule(
+        ctx: Context<UpdatePaymentSchedule>,
+        method: Option<PaymentMethod>,
+        destination: Option<Pubkey>,
+        use_saved_destination: Option<u8>,
+        frequency_secs: Option<i64>,
+        max_per_period: Option<u64>,
+        next_run_at: Option<i64>,
+        session_id: String,
+    ) -> Result<()> {
+        instructions::payment::update_payment_schedule(ctx, method, destination, use_saved_destination, frequency_secs, max_per_period, next_run_at, session_id)
+    }
+
+    pub fn cancel_payment_schedule(
+        ctx: Context<CancelPaymentSchedule>,
+    ) -> Result<()> {
+        instructions::payment::cancel_payment_schedule(ctx)
+    }
+
+    pub fn run_due_schedules<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RunDueSchedules<'info>>,
+    ) -> Result<()> {
+        instructions::payment::run_due_schedules(ctx)
+    }
+
     // KYC and compliance instructions
     pub fn initialize_compliance(
         ctx: Context<InitializeCompliance>,
-        chainalysis_api_key: String,
     ) -> Result<()> {
-        instructions::kyc::initialize_compliance(ctx, chainalysis_api_key)
+        instructions::kyc::initialize_compliance(ctx)
+    }
+
+    pub fn set_kyc_tier_limits(
+        ctx: Context<UpdateComplianceConfig>,
+        tier_limits: [TierLimits; 3],
+    ) -> Result<()> {
+        instructions::kyc::set_kyc_tier_limits(ctx, tier_limits)
+    }
+
+    pub fn set_travel_rule_thresholds(
+        ctx: Context<UpdateComplianceConfig>,
+        thresholds: [u64; ComplianceRegion::COUNT],
+    ) -> Result<()> {
+        instructions::kyc::set_travel_rule_thresholds(ctx, thresholds)
+    }
+
+    pub fn set_kyc_triggers(
+        ctx: Context<UpdateComplianceConfig>,
+        thresholds: [u64; ComplianceRegion::COUNT],
+        grace_periods: [i64; ComplianceRegion::COUNT],
+    ) -> Result<()> {
+        instructions::kyc::set_kyc_triggers(ctx, thresholds, grace_periods)
+    }
+
+    pub fn set_screening_freshness(
+        ctx: Context<UpdateComplianceConfig>,
+        high_value_threshold: u64,
+        max_age: i64,
+    ) -> Result<()> {
+        instructions::kyc::set_screening_freshness(ctx, high_value_threshold, max_age)
     }
 
     pub fn initialize_user_compliance(
@@ -332,9 +727,10 @@ pub mod vault {
     pub fn update_kyc_status(
         ctx: Context<UpdateKYCStatus>,
         new_status: KYCStatus,
+        new_tier: KycTier,
         verification: Option<KYCVerification>,
     ) -> Result<()> {
-        instructions::kyc::update_kyc_status(ctx, new_status, verification)
+        instructions::kyc::update_kyc_status(ctx, new_status, new_tier, verification)
     }
 
     pub fn perform_aml_screening(
@@ -344,13 +740,37 @@ pub mod vault {
         instructions::kyc::perform_aml_screening(ctx, screening_data)
     }
 
+    pub fn commit_compliance_snapshot<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CommitComplianceSnapshot<'info>>,
+        snapshot_id: u64,
+    ) -> Result<()> {
+        instructions::kyc::commit_compliance_snapshot(ctx, snapshot_id)
+    }
+
+    pub fn get_screening_history(
+        ctx: Context<GetScreeningHistory>,
+    ) -> Result<Vec<ScreeningRecord>> {
+        instructions::kyc::get_screening_history(ctx)
+    }
+
     pub fn validate_transaction(
         ctx: Context<ValidateTransaction>,
         transaction_type: crate::instructions::kyc::TransactionValidationType,
         amount: u64,
         destination: Option<String>,
+        travel_rule_data_hash: Option<[u8; 32]>,
     ) -> Result<()> {
-        instructions::kyc::validate_transaction(ctx, transaction_type, amount, destination)
+        instructions::kyc::validate_transaction(ctx, transaction_type, amount, destination, travel_rule_data_hash)
+    }
+
+    pub fn simulate_compliance_check(
+        ctx: Context<SimulateComplianceCheck>,
+        transaction_type: crate::instructions::kyc::TransactionValidationType,
+        amount: u64,
+        destination: Option<String>,
+        travel_rule_data_hash: Option<[u8; 32]>,
+    ) -> Result<ComplianceCheck> {
+        instructions::kyc::simulate_compliance_check(ctx, transaction_type, amount, destination, travel_rule_data_hash)
     }
 
     pub fn resolve_alert(
@@ -364,14 +784,39 @@ pub mod vault {
     pub fn freeze_account(
         ctx: Context<FreezeAccount>,
         reason: String,
+        scope: Option<FreezeScope>,
     ) -> Result<()> {
-        instructions::kyc::freeze_account(ctx, reason)
+        instructions::kyc::freeze_account(ctx, reason, scope)
     }
 
     pub fn unfreeze_account(
         ctx: Context<FreezeAccount>,
+        scope: Option<FreezeScope>,
+    ) -> Result<()> {
+        instructions::kyc::unfreeze_account(ctx, scope)
+    }
+
+    pub fn batch_freeze_accounts<'info>(
+        ctx: Context<'_, '_, 'info, 'info, BatchFreezeAccounts<'info>>,
+        reason: String,
+        scope: Option<FreezeScope>,
+    ) -> Result<()> {
+        instructions::kyc::batch_freeze_accounts(ctx, reason, scope)
+    }
+
+    pub fn batch_unfreeze_accounts<'info>(
+        ctx: Context<'_, '_, 'info, 'info, BatchFreezeAccounts<'info>>,
+        scope: Option<FreezeScope>,
     ) -> Result<()> {
-        instructions::kyc::unfreeze_account(ctx)
+        instructions::kyc::batch_unfreeze_accounts(ctx, scope)
+    }
+
+    pub fn clear_compliance_flag(
+        ctx: Context<UpdateKYCStatus>,
+        flag: ComplianceFlagKind,
+        justification: String,
+    ) -> Result<()> {
+        instructions::kyc::clear_compliance_flag(ctx, flag, justification)
     }
 
     pub fn update_compliance_config(
@@ -390,6 +835,12 @@ pub mod vault {
         instructions::kyc::perform_compliance_review(ctx)
     }
 
+    pub fn escalate_overdue_reviews<'info>(
+        ctx: Context<'_, '_, 'info, 'info, EscalateOverdueReviews<'info>>,
+    ) -> Result<()> {
+        instructions::kyc::escalate_overdue_reviews(ctx)
+    }
+
     pub fn get_compliance_summary(
         ctx: Context<ValidateTransaction>,
     ) -> Result<()> {
@@ -414,9 +865,8 @@ pub mod vault {
         method: AuthMethod,
         identifier: String,
         secret_hash: [u8; 32],
-        backup_codes: Vec<String>,
     ) -> Result<()> {
-        instructions::authentication::add_auth_factor(ctx, method, identifier, secret_hash, backup_codes)
+        instructions::authentication::add_auth_factor(ctx, method, identifier, secret_hash)
     }
 
     pub fn verify_auth_factor(
@@ -424,8 +874,78 @@ pub mod vault {
         method: AuthMethod,
         identifier: String,
         provided_code: String,
+        step_up_operation: Option<StepUpOperation>,
+        amount_cap: u64,
+    ) -> Result<bool> {
+        instructions::authentication::verify_auth_factor(ctx, method, identifier, provided_code, step_up_operation, amount_cap)
+    }
+
+    pub fn add_webauthn_factor(
+        ctx: Context<AddAuthFactor>,
+        method: AuthMethod,
+        identifier: String,
+        credential_id: Vec<u8>,
+        cose_public_key: Vec<u8>,
+    ) -> Result<()> {
+        instructions::authentication::add_webauthn_factor(ctx, method, identifier, credential_id, cose_public_key)
+    }
+
+    pub fn add_passkey_factor(
+        ctx: Context<AddAuthFactor>,
+        identifier: String,
+        authenticator_data: Vec<u8>,
+        attestation_object_hash: [u8; 32],
+    ) -> Result<()> {
+        instructions::authentication::add_passkey_factor(ctx, identifier, authenticator_data, attestation_object_hash)
+    }
+
+    pub fn remove_auth_factor(
+        ctx: Context<AddAuthFactor>,
+        method: AuthMethod,
+        identifier: String,
+    ) -> Result<()> {
+        instructions::authentication::remove_auth_factor(ctx, method, identifier)
+    }
+
+    pub fn set_auth_factor_enabled(
+        ctx: Context<AddAuthFactor>,
+        method: AuthMethod,
+        identifier: String,
+        enabled: bool,
+    ) -> Result<()> {
+        instructions::authentication::set_auth_factor_enabled(ctx, method, identifier, enabled)
+    }
+
+    pub fn verify_webauthn_assertion(
+        ctx: Context<VerifyWebAuthnAssertion>,
+        method: AuthMethod,
+        identifier: String,
+        assertion: WebAuthnAssertion,
+        step_up_operation: Option<StepUpOperation>,
+        amount_cap: u64,
+    ) -> Result<()> {
+        instructions::authentication::verify_webauthn_assertion(ctx, method, identifier, assertion, step_up_operation, amount_cap)
+    }
+
+    pub fn verify_passkey_assertion(
+        ctx: Context<VerifyWebAuthnAssertion>,
+        identifier: String,
+        assertion: WebAuthnAssertion,
+        step_up_operation: Option<StepUpOperation>,
+        amount_cap: u64,
+    ) -> Result<()> {
+        instructions::authentication::verify_passkey_assertion(ctx, identifier, assertion, step_up_operation, amount_cap)
+    }
+
+    pub fn verify_totp_attestation(
+        ctx: Context<VerifyTotpAttestation>,
+        identifier: String,
+        counter: u64,
+        signature: Vec<u8>,
+        step_up_operation: Option<StepUpOperation>,
+        amount_cap: u64,
     ) -> Result<()> {
-        instructions::authentication::verify_auth_factor(ctx, method, identifier, provided_code)
+        instructions::authentication::verify_totp_attestation(ctx, identifier, counter, signature, step_up_operation, amount_cap)
     }
 
     pub fn create_session(
@@ -452,6 +972,51 @@ pub mod vault {
         instructions::authentication::revoke_session(ctx, session_id)
     }
 
+    pub fn renew_session(
+        ctx: Context<RenewSession>,
+        session_id: String,
+    ) -> Result<()> {
+        instructions::authentication::renew_session(ctx, session_id)
+    }
+
+    pub fn self_unlock(
+        ctx: Context<SelfUnlock>,
+        backup_code: Option<String>,
+    ) -> Result<bool> {
+        instructions::authentication::self_unlock(ctx, backup_code)
+    }
+
+    pub fn request_cosigner_change(
+        ctx: Context<ManageCosigner>,
+        cosigner: Option<Pubkey>,
+        threshold_usd: u64,
+    ) -> Result<()> {
+        instructions::authentication::request_cosigner_change(ctx, cosigner, threshold_usd)
+    }
+
+    pub fn cancel_cosigner_change(ctx: Context<ManageCosigner>) -> Result<()> {
+        instructions::authentication::cancel_cosigner_change(ctx)
+    }
+
+    pub fn apply_cosigner_change(ctx: Context<ManageCosigner>) -> Result<()> {
+        instructions::authentication::apply_cosigner_change(ctx)
+    }
+
+    pub fn revoke_all_sessions(
+        ctx: Context<RevokeSession>,
+        except_session_id: Option<String>,
+    ) -> Result<()> {
+        instructions::authentication::revoke_all_sessions(ctx, except_session_id)
+    }
+
+    pub fn upgrade_session_permissions(
+        ctx: Context<UpgradeSessionPermissions>,
+        session_id: String,
+        permissions: Vec<SessionPermission>,
+    ) -> Result<()> {
+        instructions::authentication::upgrade_session_permissions(ctx, session_id, permissions)
+    }
+
     pub fn lock_account(
         ctx: Context<LockAccount>,
         reason: String,
@@ -476,6 +1041,29 @@ pub mod vault {
         instructions::authentication::update_auth_config(ctx, require_2fa_globally, session_timeout_min, session_timeout_max, max_failed_attempts, lockout_duration)
     }
 
+    pub fn set_webauthn_rp_id(
+        ctx: Context<UpdateAuthConfig>,
+        rp_id: String,
+    ) -> Result<()> {
+        instructions::authentication::set_webauthn_rp_id(ctx, rp_id)
+    }
+
+    pub fn set_totp_verifier(
+        ctx: Context<UpdateAuthConfig>,
+        verifier: Pubkey,
+    ) -> Result<()> {
+        instructions::authentication::set_totp_verifier(ctx, verifier)
+    }
+
+    pub fn set_risk_thresholds(
+        ctx: Context<UpdateAuthConfig>,
+        payment_approval: u8,
+        session_key: u8,
+        suspicious_session: u8,
+    ) -> Result<()> {
+        instructions::authentication::set_risk_thresholds(ctx, payment_approval, session_key, suspicious_session)
+    }
+
     pub fn check_2fa_requirement(
         ctx: Context<ValidateSession>,
         operation_type: String,
@@ -490,10 +1078,18 @@ pub mod vault {
         instructions::authentication::get_security_status(ctx)
     }
 
+    pub fn get_event_archive(
+        ctx: Context<GetEventArchive>,
+    ) -> Result<EventArchiveRoot> {
+        instructions::authentication::get_event_archive(ctx)
+    }
+
     pub fn generate_backup_codes(
-        ctx: Context<AddAuthFactor>,
-    ) -> Result<Vec<String>> {
-        instructions::authentication::generate_backup_codes(ctx)
+        ctx: Context<GenerateBackupCodes>,
+        code_digests: Vec<[u8; 32]>,
+        client_entropy: [u8; 32],
+    ) -> Result<()> {
+        instructions::authentication::generate_backup_codes(ctx, code_digests, client_entropy)
     }
 
     pub fn verify_backup_code(
@@ -503,6 +1099,70 @@ pub mod vault {
         instructions::authentication::verify_backup_code(ctx, backup_code)
     }
 
+    pub fn register_device(
+        ctx: Context<ManageDevice>,
+        device_id: String,
+        name: String,
+        attestation_hash: [u8; 32],
+    ) -> Result<()> {
+        instructions::authentication::register_device(ctx, device_id, name, attestation_hash)
+    }
+
+    pub fn trust_device(
+        ctx: Context<ManageDevice>,
+        device_id: String,
+    ) -> Result<()> {
+        instructions::authentication::trust_device(ctx, device_id)
+    }
+
+    pub fn rename_device(
+        ctx: Context<ManageDevice>,
+        device_id: String,
+        name: String,
+    ) -> Result<()> {
+        instructions::authentication::rename_device(ctx, device_id, name)
+    }
+
+    pub fn revoke_device(
+        ctx: Context<ManageDevice>,
+        device_id: String,
+    ) -> Result<()> {
+        instructions::authentication::revoke_device(ctx, device_id)
+    }
+
+    pub fn set_recovery_guardians(
+        ctx: Context<ManageRecovery>,
+        guardians: Vec<Pubkey>,
+    ) -> Result<()> {
+        instructions::authentication::set_recovery_guardians(ctx, guardians)
+    }
+
+    pub fn initiate_recovery(
+        ctx: Context<GuardianRecovery>,
+        new_primary_factor_hash: [u8; 32],
+    ) -> Result<()> {
+        instructions::authentication::initiate_recovery(ctx, new_primary_factor_hash)
+    }
+
+    pub fn cancel_recovery(
+        ctx: Context<ManageRecovery>,
+    ) -> Result<()> {
+        instructions::authentication::cancel_recovery(ctx)
+    }
+
+    pub fn complete_recovery(
+        ctx: Context<GuardianRecovery>,
+    ) -> Result<()> {
+        instructions::authentication::complete_recovery(ctx)
+    }
+
+    pub fn add_ip_to_whitelist(
+        ctx: Context<AddAuthFactor>,
+        ip_hash: [u8; 32],
+    ) -> Result<()> {
+        instructions::authentication::add_ip_to_whitelist(ctx, ip_hash)
+    }
+
     pub fn update_security_settings(
         ctx: Context<AddAuthFactor>,
         require_2fa_for_all: Option<bool>,
@@ -511,8 +1171,10 @@ pub mod vault {
         session_timeout: Option<u32>,
         max_concurrent_sessions: Option<u8>,
         auto_lock_on_suspicious: Option<bool>,
+        session_eviction: Option<SessionEvictionPolicy>,
+        session_id: String,
     ) -> Result<()> {
-        instructions::authentication::update_security_settings(ctx, require_2fa_for_all, require_2fa_for_payments, require_2fa_for_high_value, session_timeout, max_concurrent_sessions, auto_lock_on_suspicious)
+        instructions::authentication::update_security_settings(ctx, require_2fa_for_all, require_2fa_for_payments, require_2fa_for_high_value, session_timeout, max_concurrent_sessions, auto_lock_on_suspicious, session_eviction, session_id)
     }
 
     // Treasury Management instructions
@@ -599,13 +1261,13 @@ pub mod vault {
     }
 
     // Enhanced State Channel instructions
-    pub fn initialize_enhanced_state_channel(
-        ctx: Context<InitializeEnhancedStateChannel>,
+    pub fn initialize_enhanced_state_channel<'info>(
+        ctx: Context<'_, '_, 'info, 'info, InitializeEnhancedStateChannel<'info>>,
         channel_id: [u8; 32],
         participants: Vec<crate::state::enhanced_state_channel::ChannelParticipant>,
         config: crate::state::enhanced_state_channel::ChannelConfig,
     ) -> Result<()> {
-        instructions::enhanced_state_channel::InitializeEnhancedStateChannel::process(ctx, channel_id, participants, config, ctx.bumps.enhanced_channel)
+        instructions::enhanced_state_channel::InitializeEnhancedStateChannel::process(ctx, channel_id, participants, config)
     }
 
     pub fn activate_enhanced_channel(
@@ -628,6 +1290,42 @@ pub mod vault {
         instructions::enhanced_state_channel::ProcessMicroTransaction::process(ctx, transaction)
     }
 
+    pub fn checkpoint_micro_transactions(
+        ctx: Context<CheckpointMicroTransactions>,
+    ) -> Result<()> {
+        instructions::enhanced_state_channel::CheckpointMicroTransactions::process(ctx)
+    }
+
+    pub fn raise_exposure_limit(
+        ctx: Context<RaiseExposureLimit>,
+        participant: Pubkey,
+        asset: crate::state::enhanced_state_channel::ChannelAsset,
+        new_limit: u64,
+        signatures: Vec<Vec<u8>>,
+    ) -> Result<()> {
+        instructions::enhanced_state_channel::RaiseExposureLimit::process(ctx, participant, asset, new_limit, signatures)
+    }
+
+    pub fn register_session_key(
+        ctx: Context<RegisterSessionKey>,
+        delegate: Pubkey,
+        expires_at: i64,
+        max_operation_value: u64,
+        method: AuthMethod,
+        identifier: String,
+        code: String,
+        session_id: String,
+    ) -> Result<()> {
+        instructions::enhanced_state_channel::RegisterSessionKey::process(ctx, delegate, expires_at, max_operation_value, method, identifier, code, session_id)
+    }
+
+    pub fn revoke_session_key(
+        ctx: Context<RevokeSessionKey>,
+        participant: Pubkey,
+    ) -> Result<()> {
+        instructions::enhanced_state_channel::RevokeSessionKey::process(ctx, participant)
+    }
+
     pub fn add_pending_operation(
         ctx: Context<AddPendingOperation>,
         operation: crate::state::enhanced_state_channel::PendingOperation,
@@ -635,6 +1333,12 @@ pub mod vault {
         instructions::enhanced_state_channel::AddPendingOperation::process(ctx, operation)
     }
 
+    pub fn evict_expired_operations(
+        ctx: Context<EvictExpiredOperations>,
+    ) -> Result<()> {
+        instructions::enhanced_state_channel::EvictExpiredOperations::process(ctx)
+    }
+
     pub fn confirm_operation(
         ctx: Context<ConfirmOperation>,
         operation_id: u64,
@@ -654,9 +1358,44 @@ pub mod vault {
 
     pub fn resolve_dispute(
         ctx: Context<ResolveDispute>,
-        resolution: crate::state::enhanced_state_channel::DisputeResolution,
     ) -> Result<()> {
-        instructions::enhanced_state_channel::ResolveDispute::process(ctx, resolution)
+        instructions::enhanced_state_channel::ResolveDispute::process(ctx)
+    }
+
+    pub fn deposit_enhanced_collateral(
+        ctx: Context<DepositEnhancedCollateral>,
+        amount: u64,
+    ) -> Result<()> {
+        instructions::enhanced_state_channel::DepositEnhancedCollateral::process(ctx, amount)
+    }
+
+    pub fn deposit_enhanced_token_collateral(
+        ctx: Context<DepositEnhancedTokenCollateral>,
+        amount: u64,
+    ) -> Result<()> {
+        instructions::enhanced_state_channel::DepositEnhancedTokenCollateral::process(ctx, amount)
+    }
+
+    pub fn request_channel_withdrawal(
+        ctx: Context<RequestChannelWithdrawal>,
+        amount: u64,
+        asset: crate::state::enhanced_state_channel::ChannelAsset,
+        signatures: Vec<Vec<u8>>,
+    ) -> Result<()> {
+        instructions::enhanced_state_channel::RequestChannelWithdrawal::process(ctx, amount, asset, signatures)
+    }
+
+    pub fn execute_channel_withdrawal(
+        ctx: Context<ExecuteChannelWithdrawal>,
+    ) -> Result<()> {
+        instructions::enhanced_state_channel::ExecuteChannelWithdrawal::process(ctx)
+    }
+
+    pub fn object_channel_withdrawal(
+        ctx: Context<ObjectChannelWithdrawal>,
+        participant: Pubkey,
+    ) -> Result<()> {
+        instructions::enhanced_state_channel::ObjectChannelWithdrawal::process(ctx, participant)
     }
 
     pub fn close_enhanced_channel(
@@ -665,6 +1404,18 @@ pub mod vault {
         instructions::enhanced_state_channel::CloseEnhancedChannel::process(ctx)
     }
 
+    pub fn settle_enhanced_channel<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SettleEnhancedChannel<'info>>,
+    ) -> Result<()> {
+        instructions::enhanced_state_channel::SettleEnhancedChannel::process(ctx)
+    }
+
+    pub fn claim_enhanced_settlement(
+        ctx: Context<ClaimEnhancedSettlement>,
+    ) -> Result<()> {
+        instructions::enhanced_state_channel::ClaimEnhancedSettlement::process(ctx)
+    }
+
     pub fn batch_process_operations(
         ctx: Context<BatchProcessOperations>,
         operations: Vec<crate::state::enhanced_state_channel::HFTOperation>,
@@ -672,6 +1423,26 @@ pub mod vault {
         instructions::enhanced_state_channel::BatchProcessOperations::process(ctx, operations)
     }
 
+    pub fn get_channel_stats(
+        ctx: Context<GetChannelStats>,
+    ) -> Result<crate::state::enhanced_state_channel::ChannelStats> {
+        instructions::enhanced_state_channel::GetChannelStats::process(ctx)
+    }
+
+    pub fn crank_channel_triggers(
+        ctx: Context<CrankChannelTriggers>,
+    ) -> Result<()> {
+        instructions::enhanced_state_channel::CrankChannelTriggers::process(ctx)
+    }
+
+    pub fn migrate_channel_v2(
+        ctx: Context<MigrateEnhancedChannel>,
+        channel_id: [u8; 32],
+        signatures: Vec<Vec<u8>>,
+    ) -> Result<()> {
+        instructions::enhanced_state_channel::MigrateEnhancedChannel::process(ctx, channel_id, signatures)
+    }
+
     // Security monitoring instructions
     pub fn initialize_security_monitor(
         ctx: Context<InitializeSecurityMonitor>,
@@ -718,6 +1489,15 @@ pub mod vault {
         )
     }
 
+    pub fn transition_alert(
+        ctx: Context<ManageSecurityAlert>,
+        alert_id: u64,
+        new_state: AlertStatus,
+        notes: String,
+    ) -> Result<()> {
+        instructions::security_monitoring::transition_alert(ctx, alert_id, new_state, notes)
+    }
+
     pub fn resolve_security_alert(
         ctx: Context<ManageSecurityAlert>,
         alert_id: u64,
@@ -727,6 +1507,15 @@ pub mod vault {
         instructions::security_monitoring::resolve_security_alert(ctx, alert_id, false_positive, resolution_notes)
     }
 
+    pub fn batch_resolve_alerts(
+        ctx: Context<ManageSecurityAlert>,
+        alert_ids: Vec<u64>,
+        false_positive: bool,
+        resolution_notes: String,
+    ) -> Result<()> {
+        instructions::security_monitoring::batch_resolve_alerts(ctx, alert_ids, false_positive, resolution_notes)
+    }
+
     pub fn assign_security_alert(
         ctx: Context<ManageSecurityAlert>,
         alert_id: u64,
@@ -757,9 +1546,10 @@ pub mod vault {
         max_events_per_user: Option<u32>,
         auto_block_enabled: Option<bool>,
         notification_webhook: Option<String>,
+        compliance_authority: Option<Pubkey>,
     ) -> Result<()> {
         instructions::security_monitoring::update_security_config(
-            ctx, retention_days, max_events_per_user, auto_block_enabled, notification_webhook
+            ctx, retention_days, max_events_per_user, auto_block_enabled, notification_webhook, compliance_authority
         )
     }
 }
@@ -1,4 +1,6 @@
 use anchor_lang::prelude::*;
+use solana_program::hash::hashv;
+use crate::crypto::{AttestedCredential, AuthenticatorData, BackupCodeHasher, EventArchive, TotpVerifier, WebAuthnVerifier};
 use crate::errors::VaultError;
 
 /// Authentication methods supported by the system
@@ -11,6 +13,13 @@ pub enum AuthMethod {
     Passkey,        // Platform passkeys (iOS/Android/Windows)
 }
 
+impl AuthMethod {
+    /// Factors backed by a WebAuthn credential rather than a shared secret
+    pub fn is_webauthn(&self) -> bool {
+        matches!(self, AuthMethod::WebAuthn | AuthMethod::Passkey)
+    }
+}
+
 /// Session status for user authentication
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
 pub enum SessionStatus {
@@ -21,6 +30,32 @@ pub enum SessionStatus {
     Locked,         // Account locked due to security issues
 }
 
+/// What a session may be used for
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum SessionPermission {
+    Read,           // View account state
+    Write,          // Change settings
+    Payment,        // Claim rewards and create payments
+    Admin,          // Account administration
+    ChannelOps,     // Move state channel collateral
+}
+
+/// What happens when a new session would go over the user's limit
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum SessionEvictionPolicy {
+    Reject,           // Refuse the new session
+    EvictOldest,      // Evict the least recently active session
+    EvictLowestTrust, // Evict sessions on untrusted devices, then the riskiest
+}
+
+/// Why a session was evicted to make room for a new one
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum EvictionReason {
+    Inactive,    // Expired, revoked or from an earlier epoch
+    Oldest,      // Least recently active
+    LowestTrust, // Untrusted device or highest risk score
+}
+
 /// Security event types for logging
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
 pub enum SecurityEventType {
@@ -41,6 +76,112 @@ pub enum SecurityEventType {
     DeviceRevoked,          // Device access revoked
     CompromiseDetected,     // Wallet compromise detected
     RecoveryInitiated,      // Account recovery initiated
+    RecoveryCancelled,      // Account recovery cancelled by the user
+    RecoveryCompleted,      // Auth factors replaced by guardian recovery
+    GuardiansUpdated,       // Recovery guardians changed
+    OperationRiskScored,    // Risk score computed for a payment or channel operation
+    CosignerChanged,        // Hardware wallet co-signer change requested, applied or cancelled
+}
+
+/// Sensitive operations that need a fresh second factor
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum StepUpOperation {
+    ClaimRewards,       // Claims above the high-value threshold
+    DestinationChange,  // Saved destinations and pending payment destinations
+    DeviceTrust,        // Trusting a registered device
+    SessionRenewal,     // Pushing back a session's absolute expiry
+    FactorManagement,   // Disabling, enabling or removing another factor
+    AccountUnlock,      // Self-service unlock after a lockout
+    CosignerChange,     // Setting or removing the hardware wallet co-signer
+}
+
+/// A single-use record of a factor verified for one operation, up to an
+/// amount
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct StepUpProof {
+    pub operation_type: StepUpOperation,  // Operation the proof may be used for
+    pub amount_cap: u64,                  // Largest amount it covers
+    pub verified_at: i64,                 // Verification time
+    pub expires_at: i64,                  // Unusable after this time
+    pub factor_used: AuthMethod,          // Factor that was verified
+    pub factor_identifier: String,        // Identifier of that factor
+}
+
+impl StepUpProof {
+    pub const LEN: usize = 1 + 8 + 8 + 8 + 1 + 4 + 64;
+}
+
+/// Account-wide throttle on failed factor verifications. Failures of any
+/// factor share one window, so rotating between factors doesn't reset it.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default)]
+pub struct VerificationThrottle {
+    pub recent_failures: Vec<i64>,        // Failure times within the window
+    pub cooldown_until: i64,              // Verifications refused until then
+    pub cooldown_level: u8,               // Cool-downs since the last success
+}
+
+impl VerificationThrottle {
+    pub const WINDOW: i64 = 600; // 10 minutes
+    pub const MAX_FAILURES: usize = 5;
+    pub const BASE_COOLDOWN: i64 = 60; // Doubles with each cool-down
+    pub const MAX_COOLDOWN: i64 = 86_400; // 24 hours
+    pub const LEN: usize = 4 + Self::MAX_FAILURES * 8 + 8 + 1;
+    
+    pub fn check(&self, now: i64) -> Result<()> {
+        if now < self.cooldown_until {
+            return Err(VaultError::VerificationThrottled.into());
+        }
+        Ok(())
+    }
+    
+    /// Record a failure, returning the end of the cool-down it started if
+    /// the window is now full
+    pub fn record_failure(&mut self, now: i64) -> Option<i64> {
+        self.recent_failures.retain(|at| now.saturating_sub(*at) < Self::WINDOW);
+        self.recent_failures.push(now);
+        if self.recent_failures.len() < Self::MAX_FAILURES {
+            return None;
+        }
+        
+        let cooldown = Self::BASE_COOLDOWN
+            .saturating_mul(1i64 << self.cooldown_level.min(32))
+            .min(Self::MAX_COOLDOWN);
+        self.cooldown_until = now.saturating_add(cooldown);
+        self.cooldown_level = self.cooldown_level.saturating_add(1);
+        self.recent_failures.clear();
+        Some(self.cooldown_until)
+    }
+    
+    pub fn record_success(&mut self) {
+        self.recent_failures.clear();
+        self.cooldown_level = 0;
+    }
+}
+
+/// A guardian-initiated recovery waiting out its delay
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct PendingRecovery {
+    pub initiated_by: Pubkey,             // Guardian who started the recovery
+    pub new_factor_hash: [u8; 32],        // Commitment to the TOTP key to install on completion
+    pub initiated_at: i64,                // Start time
+    pub executable_at: i64,               // Earliest completion time
+    pub previous_status: AccountStatus,   // Restored if the user cancels
+}
+
+impl PendingRecovery {
+    pub const LEN: usize = 32 + 32 + 8 + 8 + 1;
+}
+
+/// A co-signer change waiting out its delay
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct PendingCosignerChange {
+    pub cosigner: Option<Pubkey>,         // New co-signer; None removes it
+    pub threshold_usd: u64,               // New threshold, USD with 6 decimals
+    pub effective_at: i64,                // Earliest time the change applies
+}
+
+impl PendingCosignerChange {
+    pub const LEN: usize = 33 + 8 + 8;
 }
 
 /// Authentication factor for multi-factor authentication
@@ -48,14 +189,77 @@ pub enum SecurityEventType {
 pub struct AuthFactor {
     pub method: AuthMethod,         // Authentication method
     pub identifier: String,         // Method-specific identifier (phone, email, device ID)
-    pub secret_hash: [u8; 32],     // Hashed secret (for TOTP seed, etc.)
-    pub backup_codes: Vec<String>,  // Backup recovery codes
+    pub secret_hash: [u8; 32],     // SHA-256 commitment to the factor secret; never the secret itself
     pub enabled: bool,              // Whether this factor is enabled
     pub verified: bool,             // Whether this factor is verified
     pub created_at: i64,           // Factor creation timestamp
     pub last_used: i64,            // Last successful use timestamp
     pub failure_count: u32,        // Consecutive failure count
     pub locked_until: Option<i64>, // Lock expiry timestamp
+    pub last_totp_counter: u64,    // Last TOTP step accepted; earlier steps are replays
+    pub webauthn: Option<WebAuthnCredential>, // WebAuthn and Passkey factors only
+    pub compromised: bool,         // Evidence of a cloned authenticator; disabled for good
+}
+
+/// Credential registered for a WebAuthn or Passkey factor
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct WebAuthnCredential {
+    pub credential_id: Vec<u8>, // Authenticator's credential ID
+    pub public_key: [u8; 32],   // Ed25519 key from the credential's COSE key
+    pub sign_count: u32,        // Last sign count the authenticator reported
+    pub passkey: Option<PasskeyMetadata>, // Passkey factors only
+}
+
+/// Registration metadata kept for a passkey
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct PasskeyMetadata {
+    pub aaguid: [u8; 16],           // Authenticator model
+    pub attestation_hash: [u8; 32], // SHA-256 of the attestation object
+    pub backup_eligible: bool,      // Credential may be synced across devices
+    pub backed_up: bool,            // Synced as of the last assertion
+}
+
+impl PasskeyMetadata {
+    pub const LEN: usize = 16 + 32 + 1 + 1;
+}
+
+impl AuthFactor {
+    fn new(
+        method: AuthMethod,
+        identifier: String,
+        secret_hash: [u8; 32],
+        webauthn: Option<WebAuthnCredential>,
+        now: i64,
+    ) -> Self {
+        Self {
+            method,
+            identifier,
+            secret_hash,
+            enabled: true,
+            verified: false, // Requires verification
+            created_at: now,
+            last_used: 0,
+            failure_count: 0,
+            locked_until: None,
+            last_totp_counter: 0,
+            webauthn,
+            compromised: false,
+        }
+    }
+}
+
+impl WebAuthnCredential {
+    pub const MAX_CREDENTIAL_ID_LEN: usize = 128;
+    pub const LEN: usize = 4 + Self::MAX_CREDENTIAL_ID_LEN + 32 + 4 + 1 + PasskeyMetadata::LEN;
+}
+
+/// A WebAuthn assertion as returned by `navigator.credentials.get`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct WebAuthnAssertion {
+    pub credential_id: Vec<u8>,
+    pub authenticator_data: Vec<u8>,
+    pub client_data_hash: [u8; 32], // SHA-256 of the client data JSON
+    pub signature: [u8; 64],
 }
 
 /// User session information
@@ -64,17 +268,185 @@ pub struct UserSession {
     pub session_id: String,         // Unique session identifier
     pub user: Pubkey,              // User public key
     pub device_id: String,         // Device identifier
-    pub ip_address: String,        // IP address (hashed for privacy)
-    pub user_agent_hash: [u8; 32], // User agent hash
+    pub ip_address_hash: [u8; 32], // Salted IP address hash
+    pub user_agent_hash: [u8; 32], // Salted user agent hash
     pub status: SessionStatus,      // Current session status
     pub created_at: i64,           // Session creation time
     pub last_activity: i64,        // Last activity timestamp
-    pub expires_at: i64,           // Session expiry time
+    pub expires_at: i64,           // Sliding expiry, extended on activity
+    pub absolute_expiry: i64,      // Hard expiry; only renewal with a step-up moves it
+    pub epoch: u32,                // User's session epoch when created
     pub auth_methods_used: Vec<AuthMethod>, // Methods used for this session
-    pub permissions: Vec<String>,   // Session-specific permissions
+    pub permissions: Vec<SessionPermission>, // Session-specific permissions
     pub risk_score: u8,            // Risk assessment score (0-100)
 }
 
+impl UserSession {
+    /// Whether the session is usable at `now`. Sessions from before the
+    /// user's current epoch were revoked by `revoke_all_sessions`.
+    pub fn is_live(&self, session_epoch: u32, now: i64) -> bool {
+        self.status == SessionStatus::Active
+            && self.epoch == session_epoch
+            && now <= self.expires_at
+            && now <= self.absolute_expiry
+    }
+}
+
+/// A device the user has signed in from
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct Device {
+    pub device_id: String,         // Device identifier
+    pub name: String,              // User-chosen label
+    pub attestation_hash: [u8; 32], // Hash of the device's platform attestation
+    pub first_seen: i64,           // Registration time
+    pub last_seen: i64,            // Last registration or session time
+    pub trusted_at: Option<i64>,   // When the user trusted the device
+    pub sessions_created: u32,     // Sessions started from the device
+}
+
+impl Device {
+    pub const MAX_DEVICE_ID_LEN: usize = 64;
+    pub const MAX_NAME_LEN: usize = 32;
+    pub const LEN: usize = 4 + Self::MAX_DEVICE_ID_LEN + 4 + Self::MAX_NAME_LEN + 32 + 8 + 8 + 9 + 4;
+    
+    pub fn is_trusted(&self) -> bool {
+        self.trusted_at.is_some()
+    }
+}
+
+/// Device lifecycle changes
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub enum DeviceChange {
+    Registered,
+    Trusted,
+    Renamed,
+    Revoked,
+    Evicted,    // Least recently seen untrusted device dropped to make room
+}
+
+#[event]
+pub struct DeviceLifecycleEvent {
+    pub user: Pubkey,
+    pub device_id: String,
+    pub change: DeviceChange,
+    pub revoked_sessions: u32,
+    pub timestamp: i64,
+}
+
+/// Emitted when the oldest security events are pruned into the archive
+/// chain. Off-chain archives replay the pruned events against
+/// `archived_events_root`.
+#[event]
+pub struct EventsArchived {
+    pub user: Pubkey,
+    pub batch_hash: [u8; 32],
+    pub first_index: u64,
+    pub count: u64,
+    pub archived_events_root: [u8; 32],
+    pub timestamp: i64,
+}
+
+/// Channels the user wants security notifications on, from their
+/// `SecuritySettings`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct NotificationChannels {
+    pub email: bool,
+    pub sms: bool,
+}
+
+/// Emitted when a session is created so the notification service can alert
+/// the user without polling. Identifiers are hashed with the user's privacy
+/// salt.
+#[event]
+pub struct SessionCreated {
+    pub user: Pubkey,
+    pub session_id_hash: [u8; 32],
+    pub device_id_hash: [u8; 32],
+    pub ip_hash: [u8; 32],
+    pub risk_score: u8,
+    pub auth_methods: Vec<AuthMethod>,
+    pub notifications: NotificationChannels,
+    pub timestamp: i64,
+}
+
+/// Emitted instead of `SessionCreated` when the session's risk score is at
+/// or above `AuthConfig::suspicious_session_risk_threshold`
+#[event]
+pub struct SuspiciousSessionCreated {
+    pub user: Pubkey,
+    pub session_id_hash: [u8; 32],
+    pub device_id_hash: [u8; 32],
+    pub ip_hash: [u8; 32],
+    pub risk_score: u8,
+    pub threshold: u8,
+    pub auth_methods: Vec<AuthMethod>,
+    pub indicators: Vec<CompromiseType>,
+    pub notifications: NotificationChannels,
+    pub timestamp: i64,
+}
+
+/// How a locked account was unlocked
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum UnlockMethod {
+    Admin,            // Global authority
+    SelfService,      // Two factors, or a factor and a backup code, after the lockout
+    BackupCode,       // Backup code recovery
+    GuardianRecovery, // Completed guardian recovery
+}
+
+/// Emitted whenever a locked account is unlocked, whichever path was used
+#[event]
+pub struct AccountUnlocked {
+    pub user: Pubkey,
+    pub method: UnlockMethod,
+    pub unlocked_by: Pubkey,
+    pub self_unlocks_used: u8, // Self-unlocks within the current window
+    pub timestamp: i64,
+}
+
+/// Emitted when a session is evicted to stay within the user's concurrent
+/// session limit
+#[event]
+pub struct SessionEvicted {
+    pub user: Pubkey,
+    pub session_id_hash: [u8; 32],
+    pub device_id_hash: [u8; 32],
+    pub reason: EvictionReason,
+    pub risk_score: u8,
+    pub timestamp: i64,
+}
+
+/// The event to emit for a new session
+pub enum SessionNotification {
+    Created(SessionCreated),
+    Suspicious(SuspiciousSessionCreated),
+}
+
+/// Archive chain state returned by the `get_event_archive` view
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct EventArchiveRoot {
+    pub archived_events_root: [u8; 32],
+    pub archived_count: u64,
+}
+
+/// A program-wide demand that users verify their factors again, e.g. after
+/// a credential-stuffing wave. It lapses by itself at `expires_at`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct ReverificationCampaign {
+    pub before_ts: i64,             // Factors last used before this need a fresh verification
+    pub expires_at: i64,            // Campaign end
+    pub exempt_users: Vec<Pubkey>,  // Users the campaign doesn't apply to
+}
+
+impl ReverificationCampaign {
+    pub const MAX_EXEMPT_USERS: usize = 20;
+    pub const LEN: usize = 8 + 8 + 4 + Self::MAX_EXEMPT_USERS * 32;
+    
+    pub fn applies_to(&self, user: &Pubkey, now: i64) -> bool {
+        now < self.expires_at && !self.exempt_users.contains(user)
+    }
+}
+
 /// Security event log entry
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
 pub struct SecurityEvent {
@@ -99,9 +471,24 @@ pub struct UserAuth {
     pub auth_factors: Vec<AuthFactor>,     // Configured authentication factors
     pub active_sessions: Vec<UserSession>, // Active user sessions
     pub security_events: Vec<SecurityEvent>, // Security event history
+    pub archived_events_root: [u8; 32],      // Hash chain over pruned events
+    pub archived_count: u64,                 // Events folded into the chain
     pub account_status: AccountStatus,     // Current account status
     pub security_settings: SecuritySettings, // User security preferences
     pub compromise_indicators: Vec<CompromiseIndicator>, // Compromise detection data
+    pub backup_codes: Vec<BackupCode>,     // Salted hashes of 2FA recovery codes
+    pub backup_code_salt: [u8; 32],        // Salt for the current backup code set
+    pub devices: Vec<Device>,              // Registered devices
+    pub privacy_salt: [u8; 32],            // Salt for IP address and user agent hashes
+    pub guardians: Vec<Pubkey>,            // Keys allowed to start account recovery
+    pub pending_recovery: Option<PendingRecovery>, // Recovery waiting out its delay
+    pub pending_cosigner_change: Option<PendingCosignerChange>, // Co-signer change waiting out its delay
+    pub verification_throttle: VerificationThrottle, // Account-wide failed verification throttle
+    pub step_up_proofs: Vec<StepUpProof>,  // Unused step-up verifications
+    pub session_epoch: u32,                // Bumped to invalidate every earlier session
+    pub self_unlocks: Vec<i64>,            // Self-service unlock times within the window
+    pub reverify_before: i64,              // Factors last used before this need a fresh verification...
+    pub reverify_until: i64,               // ...until the re-verification campaign ends
     pub last_password_change: i64,         // Last credential change
     pub failed_attempts: u32,              // Recent failed login attempts
     pub locked_until: Option<i64>,         // Account lock expiry
@@ -129,14 +516,36 @@ pub struct SecuritySettings {
     pub require_2fa_for_high_value: bool,  // Require 2FA for high-value operations
     pub session_timeout: u32,              // Session timeout in seconds
     pub max_concurrent_sessions: u8,       // Maximum concurrent sessions
+    pub session_eviction: SessionEvictionPolicy, // What to do when a new session goes over the limit
+    pub cosigner: Option<Pubkey>,          // Hardware wallet that must co-sign high-value operations
+    pub cosign_threshold_usd: u64,         // Operations worth more than this need the co-signer (USD, 6 decimals)
     pub enable_email_notifications: bool,  // Email security notifications
     pub enable_sms_notifications: bool,    // SMS security notifications
-    pub trusted_devices: Vec<String>,      // Trusted device IDs
-    pub ip_whitelist: Vec<String>,         // Whitelisted IP addresses (hashed)
+    pub ip_whitelist: Vec<[u8; 32]>,       // Whitelisted IP addresses (salted hashes)
     pub auto_lock_on_suspicious: bool,    // Auto-lock on suspicious activity
     pub backup_codes_generated: bool,     // Whether backup codes exist
 }
 
+impl SecuritySettings {
+    pub fn notification_channels(&self) -> NotificationChannels {
+        NotificationChannels {
+            email: self.enable_email_notifications,
+            sms: self.enable_sms_notifications,
+        }
+    }
+}
+
+/// A single-use 2FA recovery code, kept only as SHA-256(salt || SHA-256(code))
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct BackupCode {
+    pub code_hash: [u8; 32],              // Salted hash of the code
+    pub consumed: bool,                   // Whether the code has been used
+}
+
+impl BackupCode {
+    pub const LEN: usize = 32 + 1;
+}
+
 /// Compromise detection indicators
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
 pub struct CompromiseIndicator {
@@ -161,17 +570,33 @@ pub enum CompromiseType {
     PhishingAttempt,       // Phishing attempt detected
     BruteForceAttack,      // Brute force attack detected
     SessionHijacking,      // Session hijacking attempt
+    ClonedAuthenticator,   // WebAuthn sign count went backwards
 }
 
 impl UserAuth {
     pub const LEN: usize = 8 + // discriminator
         32 + // user
-        4 + 10 * (1 + 4 + 64 + 32 + 4 + 10 * 64 + 1 + 1 + 8 + 8 + 4 + 9) + // auth_factors (max 10)
-        4 + 5 * (4 + 64 + 32 + 4 + 64 + 4 + 64 + 32 + 1 + 8 + 8 + 8 + 4 + 10 * 1 + 4 + 10 * 64 + 1) + // active_sessions (max 5)
+        4 + 10 * (1 + 4 + 64 + 32 + 1 + 1 + 8 + 8 + 4 + 9 + 8 + 1 + WebAuthnCredential::LEN + 1) + // auth_factors (max 10)
+        4 + 5 * (4 + 64 + 32 + 4 + 64 + 32 + 32 + 1 + 8 + 8 + 8 + 8 + 4 + 4 + 10 * 1 + 4 + 5 * 1 + 1) + // active_sessions (max 5)
         4 + 100 * (4 + 64 + 32 + 1 + 9 + 4 + 64 + 4 + 64 + 32 + 8 + 4 + 256 + 1 + 1 + 9 + 33) + // security_events (max 100)
+        32 + // archived_events_root
+        8 + // archived_count
         1 + // account_status
-        (1 + 1 + 1 + 4 + 1 + 1 + 1 + 4 + 10 * 64 + 4 + 10 * 64 + 1 + 1) + // security_settings
+        (1 + 1 + 1 + 4 + 1 + 1 + 33 + 8 + 1 + 1 + 4 + Self::MAX_IP_WHITELIST * 32 + 1 + 1) + // security_settings
         4 + 20 * (1 + 8 + 1 + 4 + 256 + 1 + 1) + // compromise_indicators (max 20)
+        4 + Self::MAX_BACKUP_CODES * BackupCode::LEN + // backup_codes
+        32 + // backup_code_salt
+        4 + Self::MAX_DEVICES * Device::LEN + // devices
+        32 + // privacy_salt
+        4 + Self::MAX_GUARDIANS * 32 + // guardians
+        1 + PendingRecovery::LEN + // pending_recovery
+        1 + PendingCosignerChange::LEN + // pending_cosigner_change
+        VerificationThrottle::LEN + // verification_throttle
+        4 + Self::MAX_STEP_UP_PROOFS * StepUpProof::LEN + // step_up_proofs
+        4 + // session_epoch
+        4 + Self::MAX_SELF_UNLOCKS * 8 + // self_unlocks
+        8 + // reverify_before
+        8 + // reverify_until
         8 + // last_password_change
         4 + // failed_attempts
         9 + // locked_until (optional)
@@ -186,6 +611,21 @@ impl UserAuth {
     pub const SESSION_TIMEOUT_DEFAULT: u32 = 3600; // 1 hour
     pub const MAX_FAILED_ATTEMPTS: u32 = 5;
     pub const LOCKOUT_DURATION: i64 = 900; // 15 minutes
+    pub const MAX_BACKUP_CODES: usize = 10;
+    pub const PRIMARY_VERIFICATION_WINDOW: i64 = 300; // 5 minutes
+    pub const MAX_DEVICES: usize = 20;
+    pub const MAX_IP_WHITELIST: usize = 10;
+    pub const MAX_GUARDIANS: usize = 3;
+    pub const RECOVERY_DELAY: i64 = 72 * 3600; // 72 hours
+    pub const RECOVERY_FACTOR_ID: &'static str = "recovery";
+    pub const MAX_STEP_UP_PROOFS: usize = 5;
+    pub const STEP_UP_TTL: i64 = 300; // 5 minutes
+    pub const SESSION_ABSOLUTE_LIFETIME: i64 = 86_400; // 24 hours
+    pub const SESSION_RENEWAL_WINDOW: i64 = 3600; // Last hour before absolute expiry
+    pub const MAX_SELF_UNLOCKS: usize = 2; // Per window; admin unlock beyond that
+    pub const COSIGNER_CHANGE_DELAY: i64 = 86_400; // 24 hours
+    pub const SELF_UNLOCK_WINDOW: i64 = 30 * 86_400; // 30 days
+    pub const PRIMARY_METHODS: [AuthMethod; 3] = [AuthMethod::TOTP, AuthMethod::WebAuthn, AuthMethod::Passkey];
 
     /// Initialize user authentication profile
     pub fn initialize(
@@ -199,6 +639,8 @@ impl UserAuth {
         self.auth_factors = Vec::new();
         self.active_sessions = Vec::new();
         self.security_events = Vec::new();
+        self.archived_events_root = [0; 32];
+        self.archived_count = 0;
         self.account_status = AccountStatus::PendingVerification;
         
         // Default security settings
@@ -208,15 +650,35 @@ impl UserAuth {
             require_2fa_for_high_value: true,
             session_timeout: Self::SESSION_TIMEOUT_DEFAULT,
             max_concurrent_sessions: 3,
+            session_eviction: SessionEvictionPolicy::EvictOldest,
+            cosigner: None,
+            cosign_threshold_usd: 0,
             enable_email_notifications: true,
             enable_sms_notifications: false,
-            trusted_devices: Vec::new(),
             ip_whitelist: Vec::new(),
             auto_lock_on_suspicious: true,
             backup_codes_generated: false,
         };
         
         self.compromise_indicators = Vec::new();
+        self.backup_codes = Vec::new();
+        self.backup_code_salt = [0; 32];
+        self.devices = Vec::new();
+        self.privacy_salt = hashv(&[
+            b"user_auth_privacy_salt",
+            user.as_ref(),
+            &clock.slot.to_le_bytes(),
+            &clock.unix_timestamp.to_le_bytes(),
+        ]).to_bytes();
+        self.guardians = Vec::new();
+        self.pending_recovery = None;
+        self.pending_cosigner_change = None;
+        self.verification_throttle = VerificationThrottle::default();
+        self.step_up_proofs = Vec::new();
+        self.session_epoch = 0;
+        self.self_unlocks = Vec::new();
+        self.reverify_before = 0;
+        self.reverify_until = 0;
         self.last_password_change = clock.unix_timestamp;
         self.failed_attempts = 0;
         self.locked_until = None;
@@ -238,13 +700,189 @@ impl UserAuth {
         Ok(())
     }
     
-    /// Add a new authentication factor
+    /// Add a new authentication factor. For TOTP `secret_hash` is the
+    /// `TotpVerifier::commitment` of the key the off-chain verifier shares
+    /// with the authenticator app. WebAuthn factors
+    /// go through `add_webauthn_factor` and passkeys through
+    /// `add_passkey_factor`.
     pub fn add_auth_factor(
         &mut self,
         method: AuthMethod,
         identifier: String,
         secret_hash: [u8; 32],
-        backup_codes: Vec<String>,
+    ) -> Result<()> {
+        if method.is_webauthn() {
+            return Err(VaultError::InvalidWebAuthnCredential.into());
+        }
+        self.register_factor(method, identifier, secret_hash, None)
+    }
+    
+    /// Add a WebAuthn factor for a credential with an Ed25519 COSE public key
+    pub fn add_webauthn_factor(
+        &mut self,
+        method: AuthMethod,
+        identifier: String,
+        credential_id: Vec<u8>,
+        cose_public_key: &[u8],
+    ) -> Result<()> {
+        if method != AuthMethod::WebAuthn {
+            return Err(VaultError::InvalidWebAuthnCredential.into());
+        }
+        self.check_new_credential_id(&credential_id)?;
+        let credential = WebAuthnCredential {
+            credential_id,
+            public_key: WebAuthnVerifier::ed25519_public_key(cose_public_key)?,
+            sign_count: 0,
+            passkey: None,
+        };
+        self.register_factor(method, identifier, [0; 32], Some(credential))
+    }
+    
+    /// Add a passkey from the authenticator data of its registration. The
+    /// credential ID, Ed25519 key, AAGUID and backup flags are read from the
+    /// attested credential data; `attestation_object_hash` is kept with
+    /// them so the full attestation can be checked off-chain.
+    pub fn add_passkey_factor(
+        &mut self,
+        identifier: String,
+        authenticator_data: &[u8],
+        attestation_object_hash: [u8; 32],
+        expected_rp_id_hash: &[u8; 32],
+    ) -> Result<()> {
+        let attested = AttestedCredential::parse(authenticator_data)?;
+        WebAuthnVerifier::check_authenticator_data(&attested.authenticator_data, expected_rp_id_hash)?;
+        if !attested.authenticator_data.user_verified() {
+            return Err(VaultError::UserVerificationRequired.into());
+        }
+        self.check_new_credential_id(&attested.credential_id)?;
+        
+        let credential = WebAuthnCredential {
+            credential_id: attested.credential_id,
+            public_key: attested.public_key,
+            sign_count: attested.authenticator_data.sign_count,
+            passkey: Some(PasskeyMetadata {
+                aaguid: attested.aaguid,
+                attestation_hash: attestation_object_hash,
+                backup_eligible: attested.authenticator_data.backup_eligible(),
+                backed_up: attested.authenticator_data.backed_up(),
+            }),
+        };
+        self.register_factor(AuthMethod::Passkey, identifier, [0; 32], Some(credential))
+    }
+    
+    /// Credential IDs must fit and be unique across the user's WebAuthn and
+    /// Passkey factors
+    fn check_new_credential_id(&self, credential_id: &[u8]) -> Result<()> {
+        if credential_id.is_empty() || credential_id.len() > WebAuthnCredential::MAX_CREDENTIAL_ID_LEN {
+            return Err(VaultError::InvalidWebAuthnCredential.into());
+        }
+        if self.auth_factors.iter()
+            .filter_map(|f| f.webauthn.as_ref())
+            .any(|c| c.credential_id == credential_id)
+        {
+            return Err(VaultError::DuplicateCredentialId.into());
+        }
+        Ok(())
+    }
+    
+    /// Remove an authentication factor, consuming a factor management
+    /// step-up from a different factor. Returns how many sessions were
+    /// downgraded to read-only.
+    pub fn remove_auth_factor(&mut self, method: AuthMethod, identifier: &str, require_2fa_globally: bool, now: i64) -> Result<u32> {
+        let index = self.factor_index(&method, identifier)?;
+        self.check_not_last_strong_factor(index, require_2fa_globally, now)?;
+        self.consume_factor_step_up(&method, identifier, now)?;
+        
+        self.auth_factors.remove(index);
+        self.updated_at = now;
+        Ok(self.downgrade_sessions_for(&method))
+    }
+    
+    /// Enable or disable an authentication factor, consuming a factor
+    /// management step-up from a different factor. Compromised factors stay
+    /// disabled. Returns how many sessions were downgraded to read-only.
+    pub fn set_auth_factor_enabled(
+        &mut self,
+        method: AuthMethod,
+        identifier: &str,
+        enabled: bool,
+        require_2fa_globally: bool,
+        now: i64,
+    ) -> Result<u32> {
+        let index = self.factor_index(&method, identifier)?;
+        if enabled && self.auth_factors[index].compromised {
+            return Err(VaultError::AuthFactorDisabled.into());
+        }
+        if !enabled {
+            self.check_not_last_strong_factor(index, require_2fa_globally, now)?;
+        }
+        self.consume_factor_step_up(&method, identifier, now)?;
+        
+        self.auth_factors[index].enabled = enabled;
+        self.updated_at = now;
+        Ok(if enabled { 0 } else { self.downgrade_sessions_for(&method) })
+    }
+    
+    fn factor_index(&self, method: &AuthMethod, identifier: &str) -> Result<usize> {
+        self.auth_factors.iter()
+            .position(|f| f.method == *method && f.identifier == identifier)
+            .ok_or_else(|| VaultError::AuthFactorNotFound.into())
+    }
+    
+    /// While the program or the user requires 2FA, the last verified TOTP,
+    /// WebAuthn or Passkey factor has to stay
+    fn check_not_last_strong_factor(&self, index: usize, require_2fa_globally: bool, now: i64) -> Result<()> {
+        let settings = &self.security_settings;
+        let requires_2fa = require_2fa_globally
+            || settings.require_2fa_for_all
+            || settings.require_2fa_for_payments
+            || settings.require_2fa_for_high_value;
+        let is_strong = |f: &AuthFactor| {
+            Self::PRIMARY_METHODS.contains(&f.method) && f.enabled && self.factor_verified(f, now) && !f.compromised
+        };
+        if requires_2fa
+            && is_strong(&self.auth_factors[index])
+            && self.auth_factors.iter().filter(|f| is_strong(f)).count() == 1
+        {
+            return Err(VaultError::LastStrongFactor.into());
+        }
+        Ok(())
+    }
+    
+    /// Consume a factor management proof from any factor other than the
+    /// one being changed
+    fn consume_factor_step_up(&mut self, method: &AuthMethod, identifier: &str, now: i64) -> Result<StepUpProof> {
+        self.take_step_up(now, |p| {
+            p.operation_type == StepUpOperation::FactorManagement
+                && (p.factor_used != *method || p.factor_identifier != identifier)
+        })
+    }
+    
+    /// Active sessions created with `method` alone drop to read-only once
+    /// no enabled factor of that method is left
+    fn downgrade_sessions_for(&mut self, method: &AuthMethod) -> u32 {
+        if self.auth_factors.iter().any(|f| f.method == *method && f.enabled && !f.compromised) {
+            return 0;
+        }
+        let mut downgraded = 0;
+        for session in self.active_sessions.iter_mut() {
+            if session.status == SessionStatus::Active
+                && !session.auth_methods_used.is_empty()
+                && session.auth_methods_used.iter().all(|m| m == method)
+            {
+                session.permissions = vec![SessionPermission::Read];
+                downgraded += 1;
+            }
+        }
+        downgraded
+    }
+    
+    fn register_factor(
+        &mut self,
+        method: AuthMethod,
+        identifier: String,
+        secret_hash: [u8; 32],
+        webauthn: Option<WebAuthnCredential>,
     ) -> Result<()> {
         if self.auth_factors.len() >= Self::MAX_AUTH_FACTORS {
             return Err(VaultError::TooManyAuthFactors.into());
@@ -257,18 +895,7 @@ impl UserAuth {
         
         let clock = Clock::get()?;
         
-        let factor = AuthFactor {
-            method: method.clone(),
-            identifier: identifier.clone(),
-            secret_hash,
-            backup_codes,
-            enabled: true,
-            verified: false, // Requires verification
-            created_at: clock.unix_timestamp,
-            last_used: 0,
-            failure_count: 0,
-            locked_until: None,
-        };
+        let factor = AuthFactor::new(method.clone(), identifier, secret_hash, webauthn, clock.unix_timestamp);
         
         self.auth_factors.push(factor);
         self.updated_at = clock.unix_timestamp;
@@ -296,6 +923,9 @@ impl UserAuth {
     ) -> Result<bool> {
         let clock = Clock::get()?;
         
+        // Refuse outright during a cool-down, before any verification work
+        self.verification_throttle.check(clock.unix_timestamp)?;
+        
         // Find the authentication factor
         let factor = self.auth_factors.iter_mut()
             .find(|f| f.method == method && f.identifier == identifier)
@@ -310,13 +940,14 @@ impl UserAuth {
             }
         }
         
-        // Verify the code (simplified - in production would use proper TOTP/WebAuthn verification)
-        let is_valid = self.verify_code(&factor.secret_hash, &provided_code, &method)?;
+        let is_valid = Self::verify_code(factor, &provided_code);
         
         if is_valid {
             factor.verified = true;
             factor.last_used = clock.unix_timestamp;
             factor.failure_count = 0;
+            self.verification_throttle.record_success();
+            self.failed_attempts = 0;
             
             // Update account status if this was the first verification
             if self.account_status == AccountStatus::PendingVerification {
@@ -341,6 +972,9 @@ impl UserAuth {
                 factor.locked_until = Some(clock.unix_timestamp + 900); // 15 minutes
             }
             
+            self.failed_attempts = self.failed_attempts.saturating_add(1);
+            let cooldown_until = self.verification_throttle.record_failure(clock.unix_timestamp);
+            
             self.add_security_event(
                 SecurityEventType::TwoFactorFailure,
                 None,
@@ -349,6 +983,16 @@ impl UserAuth {
                 60, // High risk
             )?;
             
+            if let Some(until) = cooldown_until {
+                self.add_security_event(
+                    SecurityEventType::SuspiciousActivity,
+                    None,
+                    None,
+                    format!("Factor verification throttled until {}", until),
+                    80, // High risk
+                )?;
+            }
+            
             msg!("2FA verification failed for user {}: {:?}", self.user, method);
         }
         
@@ -357,6 +1001,542 @@ impl UserAuth {
         Ok(is_valid)
     }
     
+    /// Public key to check an assertion for `credential_id` against. The
+    /// factor must be enabled and not marked compromised.
+    pub fn webauthn_public_key(&self, method: &AuthMethod, identifier: &str, credential_id: &[u8]) -> Result<[u8; 32]> {
+        let factor = self.auth_factors.iter()
+            .find(|f| f.method == *method && f.identifier == identifier)
+            .ok_or(VaultError::AuthFactorNotFound)?;
+        if !factor.enabled || factor.compromised {
+            return Err(VaultError::AuthFactorDisabled.into());
+        }
+        let credential = factor.webauthn.as_ref().ok_or(VaultError::AuthFactorNotFound)?;
+        if credential.credential_id != credential_id {
+            return Err(VaultError::InvalidWebAuthnAssertion.into());
+        }
+        Ok(credential.public_key)
+    }
+    
+    /// Key commitment of a TOTP factor, which verifier attestations are bound to
+    pub fn totp_commitment(&self, identifier: &str) -> Result<[u8; 32]> {
+        self.auth_factors.iter()
+            .find(|f| f.method == AuthMethod::TOTP && f.identifier == identifier)
+            .map(|f| f.secret_hash)
+            .ok_or(VaultError::AuthFactorNotFound.into())
+    }
+    
+    /// Record a TOTP verifier attestation whose signature has been verified.
+    /// The factor must be enabled and not compromised, and the attested step
+    /// must be within the skew window and later than the last step accepted,
+    /// so an attestation can't be replayed.
+    pub fn record_totp_attestation(&mut self, identifier: String, counter: u64) -> Result<()> {
+        let clock = Clock::get()?;
+        self.verification_throttle.check(clock.unix_timestamp)?;
+        
+        let factor = self.auth_factors.iter_mut()
+            .find(|f| f.method == AuthMethod::TOTP && f.identifier == identifier)
+            .ok_or(VaultError::AuthFactorNotFound)?;
+        if !factor.enabled || factor.compromised {
+            return Err(VaultError::AuthFactorDisabled.into());
+        }
+        if factor.locked_until.map_or(false, |until| clock.unix_timestamp < until) {
+            return Err(VaultError::AuthFactorLocked.into());
+        }
+        if !TotpVerifier::accepts_step(counter, clock.unix_timestamp, factor.last_totp_counter) {
+            return Err(VaultError::InvalidAuthCode.into());
+        }
+        
+        factor.last_totp_counter = counter;
+        factor.locked_until = None;
+        factor.verified = true;
+        factor.last_used = clock.unix_timestamp;
+        factor.failure_count = 0;
+        self.verification_throttle.record_success();
+        self.failed_attempts = 0;
+        if self.account_status == AccountStatus::PendingVerification {
+            self.account_status = AccountStatus::Active;
+        }
+        self.add_security_event(
+            SecurityEventType::TwoFactorSuccess,
+            None,
+            None,
+            format!("2FA verification successful: {:?}", AuthMethod::TOTP),
+            10, // Low risk
+        )?;
+        self.updated_at = clock.unix_timestamp;
+        
+        msg!("TOTP attestation verified for user {}", self.user);
+        
+        Ok(())
+    }
+    
+    /// Record an assertion whose signature has been verified. A sign count
+    /// that didn't move forward means the authenticator has been cloned: the
+    /// factor is marked compromised and disabled and `false` is returned.
+    pub fn record_webauthn_assertion(
+        &mut self,
+        method: AuthMethod,
+        identifier: String,
+        authenticator_data: &AuthenticatorData,
+        expected_rp_id_hash: &[u8; 32],
+    ) -> Result<bool> {
+        let clock = Clock::get()?;
+        self.verification_throttle.check(clock.unix_timestamp)?;
+        WebAuthnVerifier::check_authenticator_data(authenticator_data, expected_rp_id_hash)?;
+        if method == AuthMethod::Passkey && !authenticator_data.user_verified() {
+            return Err(VaultError::UserVerificationRequired.into());
+        }
+        
+        let factor = self.auth_factors.iter_mut()
+            .find(|f| f.method == method && f.identifier == identifier)
+            .ok_or(VaultError::AuthFactorNotFound)?;
+        let credential = factor.webauthn.as_mut().ok_or(VaultError::AuthFactorNotFound)?;
+        let stored_count = credential.sign_count;
+        
+        if WebAuthnVerifier::sign_count_regressed(stored_count, authenticator_data.sign_count) {
+            factor.compromised = true;
+            factor.enabled = false;
+            let details = format!(
+                "{:?} factor {} sign count went from {} to {}",
+                method, identifier, stored_count, authenticator_data.sign_count
+            );
+            if self.compromise_indicators.len() < Self::MAX_COMPROMISE_INDICATORS {
+                self.compromise_indicators.push(CompromiseIndicator {
+                    indicator_type: CompromiseType::ClonedAuthenticator,
+                    detected_at: clock.unix_timestamp,
+                    confidence: 90,
+                    details: details.clone(),
+                    resolved: false,
+                    false_positive: false,
+                });
+            }
+            self.add_security_event(SecurityEventType::CompromiseDetected, None, None, details, 90)?;
+            self.updated_at = clock.unix_timestamp;
+            
+            msg!("WebAuthn factor for user {} marked compromised", self.user);
+            return Ok(false);
+        }
+        
+        credential.sign_count = authenticator_data.sign_count;
+        if let Some(passkey) = credential.passkey.as_mut() {
+            passkey.backed_up = authenticator_data.backed_up();
+        }
+        factor.verified = true;
+        factor.last_used = clock.unix_timestamp;
+        factor.failure_count = 0;
+        self.verification_throttle.record_success();
+        self.failed_attempts = 0;
+        if self.account_status == AccountStatus::PendingVerification {
+            self.account_status = AccountStatus::Active;
+        }
+        self.add_security_event(
+            SecurityEventType::TwoFactorSuccess,
+            None,
+            None,
+            format!("2FA verification successful: {:?}", method),
+            10, // Low risk
+        )?;
+        self.updated_at = clock.unix_timestamp;
+        
+        msg!("WebAuthn assertion verified for user {}: {:?}", self.user, method);
+        
+        Ok(true)
+    }
+    
+    /// Whether an enabled TOTP, WebAuthn or Passkey factor was verified
+    /// within the last five minutes
+    pub fn has_recent_primary_verification(&self, now: i64) -> bool {
+        Self::PRIMARY_METHODS.iter().any(|method| self.recently_verified(method, now))
+    }
+    
+    /// Whether `factor` counts as verified. During a re-verification
+    /// campaign, factors last used before its cut-off don't until they are
+    /// verified again.
+    pub fn factor_verified(&self, factor: &AuthFactor, now: i64) -> bool {
+        factor.verified && !(now < self.reverify_until && factor.last_used < self.reverify_before)
+    }
+    
+    /// Pick up the program's re-verification campaign, if it covers this
+    /// user, and downgrade sessions created before its cut-off to
+    /// read-only. Returns how many sessions were downgraded.
+    pub fn sync_reverification(&mut self, campaign: Option<&ReverificationCampaign>, now: i64) -> u32 {
+        let (before, until) = match campaign {
+            Some(campaign) if campaign.applies_to(&self.user, now) => (campaign.before_ts, campaign.expires_at),
+            _ => (0, 0),
+        };
+        self.reverify_before = before;
+        self.reverify_until = until;
+        
+        let mut downgraded = 0;
+        for session in self.active_sessions.iter_mut() {
+            if session.status == SessionStatus::Active
+                && session.created_at < before
+                && session.permissions != [SessionPermission::Read]
+            {
+                session.permissions = vec![SessionPermission::Read];
+                downgraded += 1;
+            }
+        }
+        downgraded
+    }
+    
+    /// Whether an enabled factor using `method` was verified within the
+    /// last five minutes
+    pub fn recently_verified(&self, method: &AuthMethod, now: i64) -> bool {
+        self.auth_factors.iter().any(|f| {
+            f.method == *method
+                && f.enabled
+                && self.factor_verified(f, now)
+                && !f.compromised
+                && f.last_used > 0
+                && now.saturating_sub(f.last_used) <= Self::PRIMARY_VERIFICATION_WINDOW
+        })
+    }
+    
+    /// Replace every backup code with a new set. `code_digests` are the
+    /// SHA-256 of each client-generated code; only their salted hashes are
+    /// kept, and all earlier codes stop working.
+    pub fn replace_backup_codes(&mut self, code_digests: &[[u8; 32]], salt: [u8; 32], now: i64) -> Result<()> {
+        if !self.has_recent_primary_verification(now) {
+            return Err(VaultError::RecentPrimaryVerificationRequired.into());
+        }
+        if code_digests.is_empty()
+            || code_digests.len() > Self::MAX_BACKUP_CODES
+            || code_digests.iter().enumerate().any(|(i, d)| code_digests[..i].contains(d))
+        {
+            return Err(VaultError::InvalidBackupCodeSet.into());
+        }
+        
+        self.backup_code_salt = salt;
+        self.backup_codes = code_digests.iter()
+            .map(|digest| BackupCode {
+                code_hash: BackupCodeHasher::salted_hash(&salt, digest),
+                consumed: false,
+            })
+            .collect();
+        self.security_settings.backup_codes_generated = true;
+        self.updated_at = now;
+        Ok(())
+    }
+    
+    /// Burn the unused backup code matching `code`, returning whether there
+    /// was one. Every stored hash is compared so timing doesn't reveal which
+    /// code matched.
+    pub fn consume_backup_code(&mut self, code: &str) -> bool {
+        let code_hash = BackupCodeHasher::salted_hash(&self.backup_code_salt, &BackupCodeHasher::code_digest(code));
+        let mut matched = None;
+        for (i, stored) in self.backup_codes.iter().enumerate() {
+            if TotpVerifier::constant_time_eq(&stored.code_hash, &code_hash) && !stored.consumed {
+                matched = Some(i);
+            }
+        }
+        match matched {
+            Some(i) => {
+                self.backup_codes[i].consumed = true;
+                self.security_settings.backup_codes_generated =
+                    self.backup_codes.iter().any(|c| !c.consumed);
+                true
+            }
+            None => false,
+        }
+    }
+    
+    /// Register a device. When the registry is full the least recently
+    /// seen untrusted device is evicted and its ID returned; trusted
+    /// devices are never evicted.
+    pub fn register_device(
+        &mut self,
+        device_id: String,
+        name: String,
+        attestation_hash: [u8; 32],
+        now: i64,
+    ) -> Result<Option<String>> {
+        Self::check_device_label(&device_id, Device::MAX_DEVICE_ID_LEN)?;
+        Self::check_device_label(&name, Device::MAX_NAME_LEN)?;
+        if self.devices.iter().any(|d| d.device_id == device_id) {
+            return Err(VaultError::DeviceAlreadyRegistered.into());
+        }
+        
+        let mut evicted = None;
+        if self.devices.len() >= Self::MAX_DEVICES {
+            let (oldest, _) = self.devices.iter().enumerate()
+                .filter(|(_, d)| !d.is_trusted())
+                .min_by_key(|(_, d)| d.last_seen)
+                .ok_or(VaultError::TooManyDevices)?;
+            evicted = Some(self.devices.remove(oldest).device_id);
+        }
+        
+        self.devices.push(Device {
+            device_id,
+            name,
+            attestation_hash,
+            first_seen: now,
+            last_seen: now,
+            trusted_at: None,
+            sessions_created: 0,
+        });
+        self.updated_at = now;
+        Ok(evicted)
+    }
+    
+    /// Trust a registered device. Consumes a device trust step-up proof
+    /// from a TOTP, WebAuthn or Passkey factor.
+    pub fn trust_device(&mut self, device_id: &str, now: i64) -> Result<()> {
+        let proof = self.consume_step_up(StepUpOperation::DeviceTrust, 0, now)?;
+        if !Self::PRIMARY_METHODS.contains(&proof.factor_used) {
+            return Err(VaultError::RecentPrimaryVerificationRequired.into());
+        }
+        let device = self.device_mut(device_id)?;
+        if device.trusted_at.is_none() {
+            device.trusted_at = Some(now);
+        }
+        self.updated_at = now;
+        Ok(())
+    }
+    
+    pub fn rename_device(&mut self, device_id: &str, name: String, now: i64) -> Result<()> {
+        Self::check_device_label(&name, Device::MAX_NAME_LEN)?;
+        self.device_mut(device_id)?.name = name;
+        self.updated_at = now;
+        Ok(())
+    }
+    
+    /// Remove a device and revoke every active session started from it,
+    /// returning how many sessions were revoked
+    pub fn revoke_device(&mut self, device_id: &str, now: i64) -> Result<u32> {
+        let index = self.devices.iter()
+            .position(|d| d.device_id == device_id)
+            .ok_or(VaultError::DeviceNotFound)?;
+        self.devices.remove(index);
+        
+        let mut revoked = 0;
+        for session in self.active_sessions.iter_mut()
+            .filter(|s| s.device_id == device_id && s.status == SessionStatus::Active)
+        {
+            session.status = SessionStatus::Revoked;
+            revoked += 1;
+        }
+        self.updated_at = now;
+        Ok(revoked)
+    }
+    
+    /// Record a step-up proof after `factor_used` verified for `operation`.
+    /// The oldest proof is dropped when the list is full.
+    pub fn record_step_up(
+        &mut self,
+        operation: StepUpOperation,
+        amount_cap: u64,
+        factor_used: AuthMethod,
+        factor_identifier: String,
+        now: i64,
+    ) {
+        self.step_up_proofs.retain(|p| p.expires_at >= now);
+        if self.step_up_proofs.len() >= Self::MAX_STEP_UP_PROOFS {
+            self.step_up_proofs.remove(0);
+        }
+        self.step_up_proofs.push(StepUpProof {
+            operation_type: operation,
+            amount_cap,
+            verified_at: now,
+            expires_at: now.saturating_add(Self::STEP_UP_TTL),
+            factor_used,
+            factor_identifier,
+        });
+    }
+    
+    /// Use up an unexpired proof for `operation` covering `amount`
+    pub fn consume_step_up(&mut self, operation: StepUpOperation, amount: u64, now: i64) -> Result<StepUpProof> {
+        self.take_step_up(now, |p| p.operation_type == operation && amount <= p.amount_cap)
+    }
+    
+    fn take_step_up(&mut self, now: i64, usable: impl Fn(&StepUpProof) -> bool) -> Result<StepUpProof> {
+        self.step_up_proofs.retain(|p| p.expires_at >= now);
+        let index = self.step_up_proofs.iter()
+            .position(usable)
+            .ok_or(VaultError::TwoFactorRequired)?;
+        self.updated_at = now;
+        Ok(self.step_up_proofs.remove(index))
+    }
+    
+    /// Whitelist an IP address by its `hash_ip` hash, so the raw address
+    /// never reaches the chain. A primary factor must have been verified
+    /// within the last five minutes.
+    pub fn add_ip_to_whitelist(&mut self, ip_hash: [u8; 32], now: i64) -> Result<bool> {
+        if !self.has_recent_primary_verification(now) {
+            return Err(VaultError::RecentPrimaryVerificationRequired.into());
+        }
+        if self.security_settings.ip_whitelist.contains(&ip_hash) {
+            return Ok(false);
+        }
+        if self.security_settings.ip_whitelist.len() >= Self::MAX_IP_WHITELIST {
+            return Err(VaultError::IpWhitelistFull.into());
+        }
+        self.security_settings.ip_whitelist.push(ip_hash);
+        self.updated_at = now;
+        Ok(true)
+    }
+    
+    /// Replace the recovery guardians. A primary factor must have been
+    /// verified within the last five minutes, and guardians can't change
+    /// while a recovery is pending.
+    pub fn set_guardians(&mut self, guardians: Vec<Pubkey>, now: i64) -> Result<()> {
+        if !self.has_recent_primary_verification(now) {
+            return Err(VaultError::RecentPrimaryVerificationRequired.into());
+        }
+        if self.pending_recovery.is_some() {
+            return Err(VaultError::RecoveryAlreadyPending.into());
+        }
+        if guardians.len() > Self::MAX_GUARDIANS
+            || guardians.contains(&self.user)
+            || guardians.iter().enumerate().any(|(i, g)| guardians[..i].contains(g))
+        {
+            return Err(VaultError::InvalidGuardians.into());
+        }
+        self.guardians = guardians;
+        self.updated_at = now;
+        Ok(())
+    }
+    
+    /// Start recovery on behalf of the user. It can complete once
+    /// `RECOVERY_DELAY` has passed unless the user cancels first.
+    pub fn initiate_recovery(&mut self, guardian: Pubkey, new_factor_hash: [u8; 32], now: i64) -> Result<i64> {
+        if !self.guardians.contains(&guardian) {
+            return Err(VaultError::NotAGuardian.into());
+        }
+        if self.pending_recovery.is_some() {
+            return Err(VaultError::RecoveryAlreadyPending.into());
+        }
+        if new_factor_hash == [0; 32] {
+            return Err(VaultError::InvalidRecoveryFactor.into());
+        }
+        let executable_at = now.checked_add(Self::RECOVERY_DELAY).ok_or(VaultError::MathOverflow)?;
+        self.pending_recovery = Some(PendingRecovery {
+            initiated_by: guardian,
+            new_factor_hash,
+            initiated_at: now,
+            executable_at,
+            previous_status: self.account_status.clone(),
+        });
+        self.account_status = AccountStatus::Recovery;
+        self.updated_at = now;
+        Ok(executable_at)
+    }
+    
+    /// Cancel a pending recovery. Any enabled factor verified within the
+    /// last five minutes proves the user still has access.
+    pub fn cancel_recovery(&mut self, now: i64) -> Result<()> {
+        let pending = self.pending_recovery.as_ref().ok_or(VaultError::NoRecoveryPending)?;
+        let has_verified_factor = self.auth_factors.iter()
+            .any(|f| self.recently_verified(&f.method, now));
+        if !has_verified_factor {
+            return Err(VaultError::RecentPrimaryVerificationRequired.into());
+        }
+        self.account_status = pending.previous_status.clone();
+        self.pending_recovery = None;
+        self.updated_at = now;
+        Ok(())
+    }
+    
+    /// Finish a recovery whose delay has passed: every factor, session and
+    /// backup code is dropped and a TOTP factor for the recovery key
+    /// commitment installed in their place, pending verification.
+    pub fn complete_recovery(&mut self, guardian: &Pubkey, now: i64) -> Result<()> {
+        if !self.guardians.contains(guardian) {
+            return Err(VaultError::NotAGuardian.into());
+        }
+        let pending = self.pending_recovery.as_ref().ok_or(VaultError::NoRecoveryPending)?;
+        if now < pending.executable_at {
+            return Err(VaultError::TimelockNotElapsed.into());
+        }
+        let new_factor = AuthFactor::new(
+            AuthMethod::TOTP,
+            Self::RECOVERY_FACTOR_ID.to_string(),
+            pending.new_factor_hash,
+            None,
+            now,
+        );
+        
+        self.auth_factors = vec![new_factor];
+        self.active_sessions.clear();
+        self.backup_codes.clear();
+        self.security_settings.backup_codes_generated = false;
+        self.pending_recovery = None;
+        // A lost hardware wallet is replaced through recovery
+        self.security_settings.cosigner = None;
+        self.pending_cosigner_change = None;
+        self.clear_lock(UnlockMethod::GuardianRecovery, *guardian, now);
+        self.account_status = AccountStatus::PendingVerification;
+        self.last_password_change = now;
+        self.updated_at = now;
+        Ok(())
+    }
+    
+    /// Queue a co-signer change, consuming a `CosignerChange` step-up. The
+    /// change applies `COSIGNER_CHANGE_DELAY` later; a co-signer lost in the
+    /// meantime is replaced through guardian recovery.
+    pub fn request_cosigner_change(&mut self, cosigner: Option<Pubkey>, threshold_usd: u64, now: i64) -> Result<i64> {
+        if cosigner == Some(self.user) || cosigner == Some(Pubkey::default()) {
+            return Err(VaultError::InvalidCosigner.into());
+        }
+        self.consume_step_up(StepUpOperation::CosignerChange, 0, now)?;
+        
+        let effective_at = now.saturating_add(Self::COSIGNER_CHANGE_DELAY);
+        self.pending_cosigner_change = Some(PendingCosignerChange { cosigner, threshold_usd, effective_at });
+        self.updated_at = now;
+        Ok(effective_at)
+    }
+    
+    pub fn cancel_cosigner_change(&mut self, now: i64) -> Result<()> {
+        self.pending_cosigner_change.take().ok_or(VaultError::NoCosignerChangePending)?;
+        self.updated_at = now;
+        Ok(())
+    }
+    
+    /// Move a queued co-signer change whose delay has passed into the
+    /// security settings
+    pub fn apply_cosigner_change(&mut self, now: i64) -> Result<()> {
+        let pending = self.pending_cosigner_change.as_ref().ok_or(VaultError::NoCosignerChangePending)?;
+        if now < pending.effective_at {
+            return Err(VaultError::TimelockNotElapsed.into());
+        }
+        self.security_settings.cosigner = pending.cosigner;
+        self.security_settings.cosign_threshold_usd = pending.threshold_usd;
+        self.pending_cosigner_change = None;
+        self.updated_at = now;
+        Ok(())
+    }
+    
+    /// The co-signer an operation worth `usd_value` (USD, 6 decimals) needs,
+    /// if any. A queued change counts once its delay has passed, applied or
+    /// not. An unknown value, e.g. while the oracle is stale, needs it.
+    pub fn required_cosigner(&self, usd_value: Option<u64>, now: i64) -> Option<Pubkey> {
+        let (cosigner, threshold_usd) = match &self.pending_cosigner_change {
+            Some(pending) if now >= pending.effective_at => (pending.cosigner, pending.threshold_usd),
+            _ => (self.security_settings.cosigner, self.security_settings.cosign_threshold_usd),
+        };
+        match usd_value {
+            Some(value) if value <= threshold_usd => None,
+            _ => cosigner,
+        }
+    }
+    
+    pub fn is_trusted_device(&self, device_id: &str) -> bool {
+        self.devices.iter().any(|d| d.device_id == device_id && d.is_trusted())
+    }
+    
+    fn device_mut(&mut self, device_id: &str) -> Result<&mut Device> {
+        self.devices.iter_mut()
+            .find(|d| d.device_id == device_id)
+            .ok_or_else(|| VaultError::DeviceNotFound.into())
+    }
+    
+    fn check_device_label(label: &str, max_len: usize) -> Result<()> {
+        if label.is_empty() || label.len() > max_len {
+            return Err(VaultError::InvalidDeviceLabel.into());
+        }
+        Ok(())
+    }
+    
     /// Create a new user session
     pub fn create_session(
         &mut self,
@@ -365,34 +1545,66 @@ impl UserAuth {
         user_agent: String,
         auth_methods: Vec<AuthMethod>,
     ) -> Result<String> {
-        if self.active_sessions.len() >= self.security_settings.max_concurrent_sessions as usize {
-            // Remove oldest session
-            self.active_sessions.sort_by_key(|s| s.last_activity);
-            self.active_sessions.remove(0);
+        let clock = Clock::get()?;
+        while !self.active_sessions.is_empty()
+            && self.active_sessions.len() >= self.security_settings.max_concurrent_sessions as usize
+        {
+            let (index, reason) = self.session_to_evict(clock.unix_timestamp)?;
+            let evicted = self.active_sessions.remove(index);
+            emit!(SessionEvicted {
+                user: self.user,
+                session_id_hash: self.hash_identifier(&evicted.session_id),
+                device_id_hash: self.hash_identifier(&evicted.device_id),
+                reason,
+                risk_score: evicted.risk_score,
+                timestamp: clock.unix_timestamp,
+            });
+            if reason != EvictionReason::Inactive {
+                self.add_security_event(
+                    SecurityEventType::SessionRevoked,
+                    Some(evicted.session_id),
+                    Some(evicted.device_id),
+                    format!("Session evicted for a new session: {:?}", reason),
+                    20, // Medium risk
+                )?;
+            }
         }
         
-        let clock = Clock::get()?;
         let session_id = format!("{}_{}", self.user.to_string()[..8].to_string(), clock.unix_timestamp);
         
         // Calculate risk score
         let risk_score = self.calculate_session_risk(&device_id, &ip_address, &user_agent)?;
         
+        let absolute_expiry = clock.unix_timestamp + Self::SESSION_ABSOLUTE_LIFETIME;
+        
+        // Only factors actually verified just now grant permissions
+        let verified_methods: Vec<AuthMethod> = auth_methods.iter()
+            .filter(|method| self.recently_verified(method, clock.unix_timestamp))
+            .cloned()
+            .collect();
+        
         let session = UserSession {
             session_id: session_id.clone(),
             user: self.user,
             device_id: device_id.clone(),
-            ip_address: self.hash_ip(&ip_address),
+            ip_address_hash: self.hash_ip(&ip_address),
             user_agent_hash: self.hash_user_agent(&user_agent),
             status: SessionStatus::Active,
             created_at: clock.unix_timestamp,
             last_activity: clock.unix_timestamp,
-            expires_at: clock.unix_timestamp + self.security_settings.session_timeout as i64,
+            expires_at: absolute_expiry.min(clock.unix_timestamp + self.security_settings.session_timeout as i64),
+            absolute_expiry,
+            epoch: self.session_epoch,
             auth_methods_used: auth_methods.clone(),
-            permissions: self.get_session_permissions(&auth_methods),
+            permissions: Self::permissions_for(&verified_methods),
             risk_score,
         };
         
         self.active_sessions.push(session);
+        if let Some(device) = self.devices.iter_mut().find(|d| d.device_id == device_id) {
+            device.last_seen = clock.unix_timestamp;
+            device.sessions_created = device.sessions_created.saturating_add(1);
+        }
         self.updated_at = clock.unix_timestamp;
         
         // Log session creation
@@ -412,19 +1624,28 @@ impl UserAuth {
     /// Validate a user session
     pub fn validate_session(&mut self, session_id: &str) -> Result<bool> {
         let clock = Clock::get()?;
+        let session_epoch = self.session_epoch;
+        let session_timeout = self.security_settings.session_timeout as i64;
         
         let session = self.active_sessions.iter_mut()
             .find(|s| s.session_id == session_id)
             .ok_or(VaultError::SessionNotFound)?;
         
+        // Sessions from before the last revoke_all_sessions are dead
+        if session.epoch != session_epoch {
+            session.status = SessionStatus::Revoked;
+            return Ok(false);
+        }
+        
         // Check if session is expired
-        if clock.unix_timestamp > session.expires_at {
+        if clock.unix_timestamp > session.expires_at || clock.unix_timestamp > session.absolute_expiry {
             session.status = SessionStatus::Expired;
+            let device_id = session.device_id.clone();
             
             self.add_security_event(
                 SecurityEventType::SessionExpired,
                 Some(session_id.to_string()),
-                Some(session.device_id.clone()),
+                Some(device_id),
                 "Session expired".to_string(),
                 30, // Medium risk
             )?;
@@ -437,15 +1658,122 @@ impl UserAuth {
             return Ok(false);
         }
         
-        // Update last activity
+        // Update last activity; the sliding expiry never passes the absolute one
         session.last_activity = clock.unix_timestamp;
-        session.expires_at = clock.unix_timestamp + self.security_settings.session_timeout as i64;
+        session.expires_at = session.absolute_expiry.min(clock.unix_timestamp + session_timeout);
         
         self.updated_at = clock.unix_timestamp;
         
         Ok(true)
     }
     
+    /// Renew a session in the last hour before its absolute expiry,
+    /// consuming a session renewal step-up proof. Returns the new absolute
+    /// expiry.
+    pub fn renew_session(&mut self, session_id: &str, now: i64) -> Result<i64> {
+        let session_epoch = self.session_epoch;
+        let session = self.active_sessions.iter()
+            .find(|s| s.session_id == session_id)
+            .ok_or(VaultError::SessionNotFound)?;
+        if !session.is_live(session_epoch, now) {
+            return Err(VaultError::InvalidSession.into());
+        }
+        if now < session.absolute_expiry - Self::SESSION_RENEWAL_WINDOW {
+            return Err(VaultError::SessionRenewalNotDue.into());
+        }
+        self.consume_step_up(StepUpOperation::SessionRenewal, 0, now)?;
+        
+        let session_timeout = self.security_settings.session_timeout as i64;
+        let session = self.active_sessions.iter_mut()
+            .find(|s| s.session_id == session_id)
+            .ok_or(VaultError::SessionNotFound)?;
+        session.absolute_expiry = now + Self::SESSION_ABSOLUTE_LIFETIME;
+        session.expires_at = session.absolute_expiry.min(now + session_timeout);
+        session.last_activity = now;
+        self.updated_at = now;
+        Ok(session.absolute_expiry)
+    }
+    
+    /// Revoke every active session, optionally keeping `except_session_id`.
+    /// The session epoch moves on, so sessions created before it fail
+    /// validation even if they weren't found here. Returns how many active
+    /// sessions were revoked.
+    pub fn revoke_all_sessions(&mut self, except_session_id: Option<&str>, now: i64) -> u32 {
+        self.session_epoch = self.session_epoch.wrapping_add(1);
+        let session_epoch = self.session_epoch;
+        
+        let mut revoked = 0;
+        for session in self.active_sessions.iter_mut().filter(|s| s.status == SessionStatus::Active) {
+            if except_session_id == Some(session.session_id.as_str()) {
+                session.epoch = session_epoch;
+            } else {
+                session.status = SessionStatus::Revoked;
+                revoked += 1;
+            }
+        }
+        self.updated_at = now;
+        revoked
+    }
+    
+    /// Whether an active, unexpired session holds `permission`
+    pub fn session_has_permission(&self, session_id: &str, permission: SessionPermission, now: i64) -> Result<bool> {
+        let session = self.active_sessions.iter()
+            .find(|s| s.session_id == session_id)
+            .ok_or(VaultError::SessionNotFound)?;
+        if !session.is_live(self.session_epoch, now) {
+            return Err(VaultError::InvalidSession.into());
+        }
+        Ok(session.permissions.contains(&permission))
+    }
+    
+    /// Add permissions to an active session. A strong factor able to grant
+    /// every requested permission must have been verified within the last
+    /// five minutes.
+    pub fn upgrade_session_permissions(
+        &mut self,
+        session_id: &str,
+        requested: &[SessionPermission],
+        now: i64,
+    ) -> Result<()> {
+        let verified_methods: Vec<AuthMethod> = Self::PRIMARY_METHODS.iter()
+            .filter(|method| self.recently_verified(method, now))
+            .cloned()
+            .collect();
+        if verified_methods.is_empty() {
+            return Err(VaultError::RecentPrimaryVerificationRequired.into());
+        }
+        let grantable = Self::permissions_for(&verified_methods);
+        if requested.iter().any(|p| !grantable.contains(p)) {
+            return Err(VaultError::PermissionUpgradeNotAllowed.into());
+        }
+        
+        let session_epoch = self.session_epoch;
+        let reverifying = now < self.reverify_until;
+        let reverify_before = self.reverify_before;
+        let session = self.active_sessions.iter_mut()
+            .find(|s| s.session_id == session_id)
+            .ok_or(VaultError::SessionNotFound)?;
+        if !session.is_live(session_epoch, now) {
+            return Err(VaultError::InvalidSession.into());
+        }
+        // Sessions from before a re-verification campaign stay read-only
+        if reverifying && session.created_at < reverify_before {
+            return Err(VaultError::PermissionUpgradeNotAllowed.into());
+        }
+        for permission in requested {
+            if !session.permissions.contains(permission) {
+                session.permissions.push(*permission);
+            }
+        }
+        for method in verified_methods {
+            if !session.auth_methods_used.contains(&method) {
+                session.auth_methods_used.push(method);
+            }
+        }
+        self.updated_at = now;
+        Ok(())
+    }
+    
     /// Revoke a user session
     pub fn revoke_session(&mut self, session_id: &str) -> Result<()> {
         let session = self.active_sessions.iter_mut()
@@ -484,7 +1812,7 @@ impl UserAuth {
         }
         
         // Check for unusual device
-        if !self.security_settings.trusted_devices.contains(&device_id.to_string()) {
+        if !self.is_trusted_device(device_id) {
             indicators.push(CompromiseType::UnusualDevice);
         }
         
@@ -535,10 +1863,12 @@ impl UserAuth {
         }
         
         if !indicators.is_empty() {
-            self.add_security_event(
+            let ip_address_hash = self.hash_ip(ip_address);
+            self.record_security_event(
                 SecurityEventType::CompromiseDetected,
                 None,
                 Some(device_id.to_string()),
+                ip_address_hash,
                 format!("Compromise indicators: {:?}", indicators),
                 80, // High risk
             )?;
@@ -576,9 +1906,8 @@ impl UserAuth {
     
     /// Unlock the user account
     pub fn unlock_account(&mut self, admin: Pubkey) -> Result<()> {
+        self.clear_lock(UnlockMethod::Admin, admin, Clock::get()?.unix_timestamp);
         self.account_status = AccountStatus::Active;
-        self.locked_until = None;
-        self.failed_attempts = 0;
         
         self.add_security_event(
             SecurityEventType::AccountUnlocked,
@@ -595,7 +1924,104 @@ impl UserAuth {
         Ok(())
     }
     
+    /// Unlock the account without the authority once its lockout has run
+    /// out. Needs `AccountUnlock` step-up proofs from two distinct factors,
+    /// or one proof and a backup code, and is limited to `MAX_SELF_UNLOCKS`
+    /// per `SELF_UNLOCK_WINDOW`. A wrong backup code returns false so the
+    /// failure counts toward the verification throttle; the proof it was
+    /// paired with is spent.
+    pub fn self_unlock(&mut self, backup_code: Option<String>, now: i64) -> Result<bool> {
+        // Compromised and suspended accounts, and locks without an expiry,
+        // stay with the authority
+        let locked_until = match (&self.account_status, self.locked_until) {
+            (AccountStatus::Locked, Some(locked_until)) => locked_until,
+            (AccountStatus::Locked | AccountStatus::Compromised | AccountStatus::Suspended, _) => {
+                return Err(VaultError::AdminUnlockRequired.into());
+            },
+            _ => return Err(VaultError::AccountNotLocked.into()),
+        };
+        if now < locked_until {
+            return Err(VaultError::AccountLocked.into());
+        }
+        self.self_unlocks.retain(|at| now.saturating_sub(*at) < Self::SELF_UNLOCK_WINDOW);
+        if self.self_unlocks.len() >= Self::MAX_SELF_UNLOCKS {
+            return Err(VaultError::SelfUnlockLimitReached.into());
+        }
+        self.verification_throttle.check(now)?;
+        
+        let first = self.take_step_up(now, |p| p.operation_type == StepUpOperation::AccountUnlock)?;
+        match backup_code {
+            Some(code) => {
+                if !self.consume_backup_code(&code) {
+                    let cooldown_until = self.verification_throttle.record_failure(now);
+                    self.add_security_event(
+                        SecurityEventType::LoginFailure,
+                        None,
+                        None,
+                        "Invalid backup code used for self-unlock".to_string(),
+                        70, // High risk
+                    )?;
+                    if let Some(until) = cooldown_until {
+                        self.add_security_event(
+                            SecurityEventType::SuspiciousActivity,
+                            None,
+                            None,
+                            format!("Factor verification throttled until {}", until),
+                            80, // High risk
+                        )?;
+                    }
+                    self.updated_at = now;
+                    return Ok(false);
+                }
+            },
+            None => {
+                self.take_step_up(now, |p| {
+                    p.operation_type == StepUpOperation::AccountUnlock
+                        && (p.factor_used != first.factor_used || p.factor_identifier != first.factor_identifier)
+                })?;
+            },
+        }
+        
+        self.self_unlocks.push(now);
+        self.clear_lock(UnlockMethod::SelfService, self.user, now);
+        self.account_status = AccountStatus::Active;
+        self.add_security_event(
+            SecurityEventType::AccountUnlocked,
+            None,
+            None,
+            format!(
+                "Account self-unlocked with {:?} ({} of {} self-unlocks in 30 days)",
+                first.factor_used,
+                self.self_unlocks.len(),
+                Self::MAX_SELF_UNLOCKS
+            ),
+            40, // Medium-high risk
+        )?;
+        self.updated_at = now;
+        
+        Ok(true)
+    }
+    
+    /// Clear the lockout and failure count, emitting `AccountUnlocked` if
+    /// the account was locked. Callers set the resulting status.
+    pub fn clear_lock(&mut self, method: UnlockMethod, unlocked_by: Pubkey, now: i64) {
+        let was_locked = self.locked_until.is_some()
+            || matches!(self.account_status, AccountStatus::Locked | AccountStatus::Compromised | AccountStatus::Suspended);
+        self.locked_until = None;
+        self.failed_attempts = 0;
+        if was_locked {
+            emit!(AccountUnlocked {
+                user: self.user,
+                method,
+                unlocked_by,
+                self_unlocks_used: self.self_unlocks.len() as u8,
+                timestamp: now,
+            });
+        }
+    }
+    
     /// Add a security event to the log
+    /// Log a security event, tagged with the IP hash of its session if any
     pub fn add_security_event(
         &mut self,
         event_type: SecurityEventType,
@@ -604,12 +2030,40 @@ impl UserAuth {
         details: String,
         risk_level: u8,
     ) -> Result<()> {
-        if self.security_events.len() >= Self::MAX_SECURITY_EVENTS {
-            // Remove oldest event to make space
-            self.security_events.remove(0);
+        let ip_address_hash = session_id.as_ref()
+            .and_then(|id| self.active_sessions.iter().find(|s| s.session_id == *id))
+            .map_or([0; 32], |s| s.ip_address_hash);
+        self.record_security_event(event_type, session_id, device_id, ip_address_hash, details, risk_level)
+    }
+    
+    fn record_security_event(
+        &mut self,
+        event_type: SecurityEventType,
+        session_id: Option<String>,
+        device_id: Option<String>,
+        ip_address_hash: [u8; 32],
+        details: String,
+        risk_level: u8,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        
+        // Fold the oldest events into the archive chain to make space
+        if let Some(batch) = EventArchive::prune(
+            &mut self.security_events,
+            Self::MAX_SECURITY_EVENTS,
+            &mut self.archived_events_root,
+            &mut self.archived_count,
+        )? {
+            emit!(EventsArchived {
+                user: self.user,
+                batch_hash: batch.batch_hash,
+                first_index: self.archived_count - batch.count,
+                count: batch.count,
+                archived_events_root: self.archived_events_root,
+                timestamp: clock.unix_timestamp,
+            });
         }
         
-        let clock = Clock::get()?;
         let event_id = format!("{}_{}", self.user.to_string()[..8].to_string(), clock.unix_timestamp);
         
         let event = SecurityEvent {
@@ -618,7 +2072,7 @@ impl UserAuth {
             event_type,
             session_id,
             device_id,
-            ip_address_hash: [0; 32], // Would be populated with actual IP hash
+            ip_address_hash,
             timestamp: clock.unix_timestamp,
             details,
             risk_level,
@@ -648,9 +2102,9 @@ impl UserAuth {
     }
     
     /// Get active 2FA methods for user
-    pub fn get_active_2fa_methods(&self) -> Vec<AuthMethod> {
+    pub fn get_active_2fa_methods(&self, now: i64) -> Vec<AuthMethod> {
         self.auth_factors.iter()
-            .filter(|f| f.enabled && f.verified)
+            .filter(|f| f.enabled && self.factor_verified(f, now))
             .map(|f| f.method.clone())
             .collect()
     }
@@ -671,22 +2125,14 @@ impl UserAuth {
     
     // Helper methods
     
-    fn verify_code(&self, secret_hash: &[u8; 32], provided_code: &str, method: &AuthMethod) -> Result<bool> {
-        // Simplified verification - in production would implement proper TOTP/WebAuthn
-        match method {
-            AuthMethod::TOTP => {
-                // Would use TOTP library to verify time-based code
-                Ok(provided_code.len() == 6 && provided_code.chars().all(|c| c.is_ascii_digit()))
-            },
-            AuthMethod::WebAuthn => {
-                // Would use WebAuthn library to verify signature
-                Ok(provided_code.len() > 10)
-            },
-            AuthMethod::Passkey => {
-                // Would use platform passkey verification
-                Ok(provided_code.len() > 10)
-            },
-            _ => Ok(provided_code.len() >= 4), // Simplified for SMS/Email
+    /// Check a code for `factor`
+    fn verify_code(factor: &mut AuthFactor, provided_code: &str) -> bool {
+        match factor.method {
+            // Checked off-chain and attested through `record_totp_attestation`
+            AuthMethod::TOTP => false,
+            // Checked as signed assertions through `record_webauthn_assertion`
+            AuthMethod::WebAuthn | AuthMethod::Passkey => false,
+            _ => provided_code.len() >= 4, // Simplified for SMS/Email
         }
     }
     
@@ -694,7 +2140,7 @@ impl UserAuth {
         let mut risk_score = 0u8;
         
         // Unknown device adds risk
-        if !self.security_settings.trusted_devices.contains(&device_id.to_string()) {
+        if !self.is_trusted_device(device_id) {
             risk_score += 30;
         }
         
@@ -718,37 +2164,120 @@ impl UserAuth {
         Ok(risk_score.min(100))
     }
     
-    fn hash_ip(&self, ip_address: &str) -> String {
-        // Would use proper hashing in production
-        format!("hashed_{}", ip_address.len())
+    /// SHA-256(privacy_salt || ip_address), the form `ip_whitelist`
+    /// entries are submitted in
+    pub fn hash_ip(&self, ip_address: &str) -> [u8; 32] {
+        hashv(&[&self.privacy_salt, ip_address.as_bytes()]).to_bytes()
     }
     
     fn hash_user_agent(&self, user_agent: &str) -> [u8; 32] {
-        // Would use proper hashing in production
-        let mut hash = [0u8; 32];
-        let bytes = user_agent.as_bytes();
-        for (i, &byte) in bytes.iter().take(32).enumerate() {
-            hash[i] = byte;
+        hashv(&[&self.privacy_salt, user_agent.as_bytes()]).to_bytes()
+    }
+    
+    /// Salted hash of a session or device ID, as events report them
+    fn hash_identifier(&self, value: &str) -> [u8; 32] {
+        hashv(&[&self.privacy_salt, value.as_bytes()]).to_bytes()
+    }
+    
+    /// The session to evict for a new one under the user's eviction policy.
+    /// Sessions that are no longer live go first under any policy. Ties are
+    /// broken by last activity, then creation time, then session ID.
+    fn session_to_evict(&self, now: i64) -> Result<(usize, EvictionReason)> {
+        let by_age = |a: &UserSession, b: &UserSession| {
+            a.last_activity.cmp(&b.last_activity)
+                .then(a.created_at.cmp(&b.created_at))
+                .then_with(|| a.session_id.cmp(&b.session_id))
+        };
+        let pick = |candidates: &mut dyn Iterator<Item = (usize, &UserSession)>,
+                    order: &dyn Fn(&UserSession, &UserSession) -> std::cmp::Ordering| {
+            candidates.min_by(|(_, a), (_, b)| order(a, b)).map(|(index, _)| index)
+        };
+        
+        let mut inactive = self.active_sessions.iter().enumerate()
+            .filter(|(_, s)| !s.is_live(self.session_epoch, now));
+        if let Some(index) = pick(&mut inactive, &by_age) {
+            return Ok((index, EvictionReason::Inactive));
+        }
+        
+        let mut sessions = self.active_sessions.iter().enumerate();
+        let evict = match self.security_settings.session_eviction {
+            SessionEvictionPolicy::Reject => return Err(VaultError::SessionLimitReached.into()),
+            SessionEvictionPolicy::EvictOldest => {
+                pick(&mut sessions, &by_age).map(|index| (index, EvictionReason::Oldest))
+            },
+            SessionEvictionPolicy::EvictLowestTrust => {
+                // Untrusted devices first, then the highest risk score
+                let by_trust = |a: &UserSession, b: &UserSession| {
+                    self.is_trusted_device(&a.device_id).cmp(&self.is_trusted_device(&b.device_id))
+                        .then(b.risk_score.cmp(&a.risk_score))
+                        .then_with(|| by_age(a, b))
+                };
+                pick(&mut sessions, &by_trust).map(|index| (index, EvictionReason::LowestTrust))
+            },
+        };
+        evict.ok_or(VaultError::SessionNotFound.into())
+    }
+    
+    /// Notification for a newly created session, or None if it is not
+    /// active. Sessions scoring at or above `suspicious_threshold` are
+    /// reported with the account's unresolved compromise indicators.
+    pub fn session_created_event(
+        &self,
+        session_id: &str,
+        suspicious_threshold: u8,
+        now: i64,
+    ) -> Option<SessionNotification> {
+        let session = self.active_sessions.iter().find(|s| s.session_id == session_id)?;
+        let session_id_hash = self.hash_identifier(session_id);
+        let device_id_hash = self.hash_identifier(&session.device_id);
+        let notifications = self.security_settings.notification_channels();
+        
+        if session.risk_score < suspicious_threshold {
+            return Some(SessionNotification::Created(SessionCreated {
+                user: self.user,
+                session_id_hash,
+                device_id_hash,
+                ip_hash: session.ip_address_hash,
+                risk_score: session.risk_score,
+                auth_methods: session.auth_methods_used.clone(),
+                notifications,
+                timestamp: now,
+            }));
         }
-        hash
+        
+        Some(SessionNotification::Suspicious(SuspiciousSessionCreated {
+            user: self.user,
+            session_id_hash,
+            device_id_hash,
+            ip_hash: session.ip_address_hash,
+            risk_score: session.risk_score,
+            threshold: suspicious_threshold,
+            auth_methods: session.auth_methods_used.clone(),
+            indicators: self.compromise_indicators.iter()
+                .filter(|i| !i.resolved && !i.false_positive)
+                .map(|i| i.indicator_type.clone())
+                .collect(),
+            notifications,
+            timestamp: now,
+        }))
     }
     
     fn is_known_location(&self, ip_address: &str) -> bool {
-        // Simplified - would use GeoIP and user's known locations
         self.security_settings.ip_whitelist.contains(&self.hash_ip(ip_address))
     }
     
-    fn get_session_permissions(&self, auth_methods: &[AuthMethod]) -> Vec<String> {
-        let mut permissions = vec!["read".to_string()];
+    fn permissions_for(auth_methods: &[AuthMethod]) -> Vec<SessionPermission> {
+        let mut permissions = vec![SessionPermission::Read];
         
         // Grant additional permissions based on auth methods used
         if auth_methods.contains(&AuthMethod::TOTP) || auth_methods.contains(&AuthMethod::WebAuthn) {
-            permissions.push("write".to_string());
-            permissions.push("payment".to_string());
+            permissions.push(SessionPermission::Write);
+            permissions.push(SessionPermission::Payment);
+            permissions.push(SessionPermission::ChannelOps);
         }
         
         if auth_methods.contains(&AuthMethod::WebAuthn) || auth_methods.contains(&AuthMethod::Passkey) {
-            permissions.push("admin".to_string());
+            permissions.push(SessionPermission::Admin);
         }
         
         permissions
@@ -767,9 +2296,15 @@ pub struct AuthConfig {
     pub lockout_duration: i64,            // Lockout duration in seconds
     pub enable_compromise_detection: bool, // Enable automatic compromise detection
     pub security_event_retention: u32,    // Security event retention in days
+    pub webauthn_rp_id_hash: [u8; 32],    // SHA-256 of the WebAuthn relying party ID; unset until configured
+    pub payment_approval_risk_threshold: u8, // Payments scoring at or above this need multisig approval
+    pub session_key_risk_threshold: u8,   // Channel session keys scoring at or above this are refused
+    pub suspicious_session_risk_threshold: u8, // New sessions scoring at or above this are reported as suspicious
+    pub reverification: Option<ReverificationCampaign>, // Forced factor re-verification, if one is running
     pub created_at: i64,                  // Configuration creation time
     pub updated_at: i64,                  // Last update time
     pub bump: u8,                         // PDA bump
+    pub totp_verifier: Pubkey,            // Off-chain TOTP verifier whose attestations are accepted; unset until configured
 }
 
 impl AuthConfig {
@@ -783,9 +2318,17 @@ impl AuthConfig {
         8 + // lockout_duration
         1 + // enable_compromise_detection
         4 + // security_event_retention
+        32 + // webauthn_rp_id_hash
+        1 + // payment_approval_risk_threshold
+        1 + // session_key_risk_threshold
+        1 + // suspicious_session_risk_threshold
+        1 + ReverificationCampaign::LEN + // reverification
         8 + // created_at
         8 + // updated_at
-        1; // bump
+        1 + // bump
+        32; // totp_verifier
+
+    pub const MAX_RP_ID_LEN: usize = 253; // Longest domain name
 
     /// Initialize authentication configuration
     pub fn initialize(
@@ -808,9 +2351,15 @@ impl AuthConfig {
         self.lockout_duration = 900; // 15 minutes
         self.enable_compromise_detection = true;
         self.security_event_retention = 2555; // 7 years
+        self.webauthn_rp_id_hash = [0; 32];
+        self.payment_approval_risk_threshold = 70;
+        self.session_key_risk_threshold = 60;
+        self.suspicious_session_risk_threshold = 50;
+        self.reverification = None;
         self.created_at = clock.unix_timestamp;
         self.updated_at = clock.unix_timestamp;
         self.bump = bump;
+        self.totp_verifier = Pubkey::default();
         
         Ok(())
     }
@@ -853,4 +2402,99 @@ impl AuthConfig {
         
         Ok(())
     }
+    
+    /// Set the relying party ID WebAuthn assertions must be made for
+    pub fn set_webauthn_rp_id(&mut self, authority: Pubkey, rp_id: &str) -> Result<()> {
+        if authority != self.authority {
+            return Err(VaultError::UnauthorizedAccess.into());
+        }
+        if rp_id.is_empty() || rp_id.len() > Self::MAX_RP_ID_LEN {
+            return Err(VaultError::InvalidRpId.into());
+        }
+        
+        self.webauthn_rp_id_hash = WebAuthnVerifier::rp_id_hash(rp_id);
+        self.updated_at = Clock::get()?.unix_timestamp;
+        
+        Ok(())
+    }
+    
+    /// Set the off-chain service key TOTP attestations must be signed by
+    pub fn set_totp_verifier(&mut self, authority: Pubkey, verifier: Pubkey) -> Result<()> {
+        if authority != self.authority {
+            return Err(VaultError::UnauthorizedAccess.into());
+        }
+        if verifier == Pubkey::default() {
+            return Err(VaultError::TotpVerifierNotConfigured.into());
+        }
+        
+        self.totp_verifier = verifier;
+        self.updated_at = Clock::get()?.unix_timestamp;
+        
+        Ok(())
+    }
+    
+    /// The configured TOTP verifier
+    pub fn require_totp_verifier(&self) -> Result<Pubkey> {
+        if self.totp_verifier == Pubkey::default() {
+            return Err(VaultError::TotpVerifierNotConfigured.into());
+        }
+        Ok(self.totp_verifier)
+    }
+    
+    /// Start a re-verification campaign: until `expires_at`, factors last
+    /// used before `before_ts` count as unverified for every user not in
+    /// `exempt_users`. Replaces any running campaign.
+    pub fn require_reverification(
+        &mut self,
+        authority: Pubkey,
+        before_ts: i64,
+        expires_at: i64,
+        exempt_users: Vec<Pubkey>,
+        now: i64,
+    ) -> Result<()> {
+        if authority != self.authority {
+            return Err(VaultError::UnauthorizedAccess.into());
+        }
+        if before_ts <= 0
+            || before_ts > now
+            || expires_at <= now
+            || exempt_users.len() > ReverificationCampaign::MAX_EXEMPT_USERS
+        {
+            return Err(VaultError::InvalidReverificationCampaign.into());
+        }
+        
+        self.reverification = Some(ReverificationCampaign {
+            before_ts,
+            expires_at,
+            exempt_users,
+        });
+        self.updated_at = now;
+        
+        Ok(())
+    }
+    
+    /// Set the risk scores (0-100) at which payments need multisig
+    /// approval, channel session keys are refused and new sessions are
+    /// reported as suspicious
+    pub fn set_risk_thresholds(
+        &mut self,
+        authority: Pubkey,
+        payment_approval: u8,
+        session_key: u8,
+        suspicious_session: u8,
+    ) -> Result<()> {
+        if authority != self.authority {
+            return Err(VaultError::UnauthorizedAccess.into());
+        }
+        if payment_approval > 100 || session_key > 100 || suspicious_session > 100 {
+            return Err(VaultError::InvalidRiskThreshold.into());
+        }
+        
+        self.payment_approval_risk_threshold = payment_approval;
+        self.session_key_risk_threshold = session_key;
+        self.suspicious_session_risk_threshold = suspicious_session;
+        self.updated_at = Clock::get()?.unix_timestamp;
+        
+        Ok(())
+    }
 }
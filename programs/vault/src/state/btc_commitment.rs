@@ -15,6 +15,9 @@ pub struct BTCCommitment {
     pub commitment_hash: [u8; 32],
     pub public_key: Vec<u8>,
     pub bump: u8,
+    // Reinvestment tracking, after `bump` so earlier fields keep their offsets
+    pub reinvested_sats: u64,       // Virtual sats bought with reinvested rewards (not decommittable)
+    pub last_reinvestment: i64,
 }
 
 impl BTCCommitment {
@@ -28,7 +31,15 @@ impl BTCCommitment {
         8 + // last_verification
         32 + // commitment_hash
         4 + 65 + // public_key (compressed: 33 bytes, uncompressed: 65 bytes)
-        1; // bump
+        1 + // bump
+        8 + // reinvested_sats
+        8; // last_reinvestment
+
+    /// Reinvested exposure may not exceed 50% of verified BTC
+    pub const MAX_REINVESTED_BPS: u64 = 5000;
+    /// Rewards are USD with 6 decimals, oracle price is USD with 8 decimals,
+    /// so sats = rewards * 1e8 (sats/BTC) * 1e8 / (price * 1e6)
+    const REWARDS_TO_SATS_SCALE: u128 = 10_000_000_000;
 
     /// Validates the BTC address format
     pub fn validate_btc_address(address: &str) -> Result<()> {
@@ -169,6 +180,85 @@ impl BTCCommitment {
         Ok(())
     }
 
+    /// Commitment that earns rewards: committed BTC plus reinvested virtual
+    /// sats, which only count while the commitment is verified
+    pub fn effective_amount(&self) -> u64 {
+        if !self.verified {
+            return self.amount;
+        }
+        self.amount.saturating_add(self.reinvested_sats)
+    }
+
+    /// Largest reinvested exposure the committed amount supports
+    pub fn reinvestment_cap(&self) -> u64 {
+        (self.amount as u128 * Self::MAX_REINVESTED_BPS as u128 / 10_000) as u64
+    }
+
+    /// Remaining virtual sats that can be added before hitting the reinvestment cap
+    pub fn reinvestment_headroom(&self) -> u64 {
+        if !self.verified {
+            return 0;
+        }
+        self.reinvestment_cap().saturating_sub(self.reinvested_sats)
+    }
+
+    /// Whether enough time has passed since the last reinvestment
+    pub fn is_reinvestment_due(&self, compound_frequency: u32, now: i64) -> bool {
+        self.last_reinvestment == 0
+            || now.saturating_sub(self.last_reinvestment) >= compound_frequency as i64
+    }
+
+    /// Add reinvested exposure. Reinvested sats are never part of `amount`, so
+    /// they cannot be decommitted to BTC and only leave the system as rewards.
+    pub fn add_reinvested_sats(&mut self, sats: u64, now: i64) -> Result<()> {
+        if sats > self.reinvestment_headroom() {
+            return Err(VaultError::CommitmentLimitExceeded.into());
+        }
+        self.reinvested_sats = self.reinvested_sats
+            .checked_add(sats)
+            .ok_or(VaultError::ArithmeticOverflow)?;
+        self.last_reinvestment = now;
+        Ok(())
+    }
+
+    /// Remove reinvested exposure so it can be paid back out as rewards
+    pub fn release_reinvested_sats(&mut self, sats: u64) -> Result<()> {
+        if sats == 0 || sats > self.reinvested_sats {
+            return Err(VaultError::InsufficientBalance.into());
+        }
+        self.reinvested_sats -= sats;
+        Ok(())
+    }
+
+    /// Cut reinvested exposure back to the cap after the committed amount
+    /// changed. Returns the sats released, owed back to the user as rewards.
+    pub fn clamp_reinvested_sats(&mut self) -> u64 {
+        let excess = self.reinvested_sats.saturating_sub(self.reinvestment_cap());
+        self.reinvested_sats -= excess;
+        excess
+    }
+
+    /// Convert a reward amount into sats at the oracle BTC/USD price
+    pub fn rewards_to_sats(reward_amount: u64, btc_price_usd: u64) -> Result<u64> {
+        if btc_price_usd == 0 {
+            return Err(VaultError::OraclePriceUnavailable.into());
+        }
+        let sats = (reward_amount as u128)
+            .checked_mul(Self::REWARDS_TO_SATS_SCALE)
+            .ok_or(VaultError::ArithmeticOverflow)?
+            / btc_price_usd as u128;
+        u64::try_from(sats).map_err(|_| VaultError::ArithmeticOverflow.into())
+    }
+
+    /// Convert sats back into a reward amount at the oracle BTC/USD price
+    pub fn sats_to_rewards(sats: u64, btc_price_usd: u64) -> Result<u64> {
+        let rewards = (sats as u128)
+            .checked_mul(btc_price_usd as u128)
+            .ok_or(VaultError::ArithmeticOverflow)?
+            / Self::REWARDS_TO_SATS_SCALE;
+        u64::try_from(rewards).map_err(|_| VaultError::ArithmeticOverflow.into())
+    }
+
     /// Serializes commitment data for ECDSA signing
     pub fn serialize_for_signing(
         user_address: &Pubkey,
@@ -120,6 +120,8 @@ mod tests {
             commitment_hash: [0; 32],
             public_key: public_key.serialize().to_vec(),
             bump: 0,
+            reinvested_sats: 0,
+            last_reinvestment: 0,
         };
 
         // Create message and signature
@@ -151,6 +153,8 @@ mod tests {
             commitment_hash: [0; 32],
             public_key: public_key.serialize().to_vec(),
             bump: 0,
+            reinvested_sats: 0,
+            last_reinvestment: 0,
         };
 
         let message_data = BTCCommitment::serialize_for_signing(&user_address, btc_address, amount, timestamp);
@@ -180,6 +184,8 @@ mod tests {
             commitment_hash: [0; 32],
             public_key: public_key.serialize().to_vec(),
             bump: 0,
+            reinvested_sats: 0,
+            last_reinvestment: 0,
         };
 
         let message_data = BTCCommitment::serialize_for_signing(&user_address, btc_address, amount, timestamp);
@@ -219,6 +225,8 @@ mod tests {
             commitment_hash,
             public_key: public_key.serialize().to_vec(),
             bump: 0,
+            reinvested_sats: 0,
+            last_reinvestment: 0,
         };
 
         // Mock Clock::get() for testing - in real tests you'd use a test framework that can mock this
@@ -249,6 +257,8 @@ mod tests {
             commitment_hash,
             public_key: vec![1, 2, 3], // Some key
             bump: 0,
+            reinvested_sats: 0,
+            last_reinvestment: 0,
         };
 
         // Should fail due to zero amount
@@ -275,6 +285,8 @@ mod tests {
             commitment_hash,
             public_key: vec![1, 2, 3],
             bump: 0,
+            reinvested_sats: 0,
+            last_reinvestment: 0,
         };
 
         // Should fail due to empty proof
@@ -301,6 +313,8 @@ mod tests {
             commitment_hash: wrong_hash,
             public_key: vec![1, 2, 3],
             bump: 0,
+            reinvested_sats: 0,
+            last_reinvestment: 0,
         };
 
         let expected_hash = BTCCommitment::create_commitment_hash(&user_address, btc_address, amount, timestamp);
@@ -330,4 +344,74 @@ mod tests {
         let data2 = BTCCommitment::serialize_for_signing(&user_address, btc_address, amount, timestamp2);
         assert_ne!(data1, data2);
     }
+
+    fn verified_commitment(amount: u64) -> BTCCommitment {
+        BTCCommitment {
+            user_address: Pubkey::new_unique(),
+            btc_address: "bc1qxy2kgdygjrsqtzq2n0yrf2493p83kkfjhx0wlh".to_string(),
+            amount,
+            ecdsa_proof: vec![],
+            timestamp: 1640995200,
+            verified: true,
+            last_verification: 1640995200,
+            commitment_hash: [0; 32],
+            public_key: vec![],
+            bump: 0,
+            reinvested_sats: 0,
+            last_reinvestment: 0,
+        }
+    }
+
+    #[test]
+    fn test_rewards_to_sats_conversion() {
+        // $50,000.00000000 BTC price, $500 of rewards (6 decimals) buys 0.01 BTC
+        let price = 50_000 * 100_000_000;
+        let sats = BTCCommitment::rewards_to_sats(500_000_000, price).unwrap();
+        assert_eq!(sats, 1_000_000);
+        assert_eq!(BTCCommitment::sats_to_rewards(sats, price).unwrap(), 500_000_000);
+
+        assert!(BTCCommitment::rewards_to_sats(500_000_000, 0).is_err());
+    }
+
+    #[test]
+    fn test_reinvested_exposure_capped_by_verified_btc() {
+        let mut commitment = verified_commitment(100_000_000);
+        assert_eq!(commitment.reinvestment_headroom(), 50_000_000);
+
+        commitment.add_reinvested_sats(30_000_000, 100).unwrap();
+        assert_eq!(commitment.reinvestment_headroom(), 20_000_000);
+        assert_eq!(commitment.effective_amount(), 130_000_000);
+        assert!(commitment.add_reinvested_sats(20_000_001, 200).is_err());
+
+        // Unverified commitments cannot carry reinvested exposure
+        commitment.verified = false;
+        assert_eq!(commitment.reinvestment_headroom(), 0);
+        assert_eq!(commitment.effective_amount(), 100_000_000);
+    }
+
+    #[test]
+    fn test_reinvested_exposure_released_and_clamped() {
+        let mut commitment = verified_commitment(100_000_000);
+        commitment.add_reinvested_sats(40_000_000, 100).unwrap();
+
+        assert!(commitment.release_reinvested_sats(40_000_001).is_err());
+        commitment.release_reinvested_sats(10_000_000).unwrap();
+        assert_eq!(commitment.reinvested_sats, 30_000_000);
+
+        // Shrinking the commitment cuts exposure back to half the new amount
+        commitment.amount = 40_000_000;
+        assert_eq!(commitment.clamp_reinvested_sats(), 10_000_000);
+        assert_eq!(commitment.reinvested_sats, 20_000_000);
+        assert_eq!(commitment.clamp_reinvested_sats(), 0);
+    }
+
+    #[test]
+    fn test_reinvestment_frequency() {
+        let mut commitment = verified_commitment(100_000_000);
+        assert!(commitment.is_reinvestment_due(86400, 1_000));
+
+        commitment.add_reinvested_sats(1_000, 1_000).unwrap();
+        assert!(!commitment.is_reinvestment_due(86400, 1_000 + 86399));
+        assert!(commitment.is_reinvestment_due(86400, 1_000 + 86400));
+    }
 }
@@ -0,0 +1,209 @@
+use anchor_lang::prelude::*;
+use crate::errors::VaultError;
+
+/// Subsystems an emergency can pause. Used both as the stored pause state
+/// and as the set of scopes an activation or deactivation touches.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct EmergencyScopes {
+    pub pause_payments: bool,      // Payment requests, processing and reinvestment
+    pub pause_staking: bool,       // Protocol staking and rebalancing
+    pub pause_treasury: bool,      // Treasury strategies, rebalancing and multisig treasury transfers
+    pub pause_channels: bool,      // Channel opening, updates, funding, settlement and withdrawal
+    pub pause_oracle_writes: bool, // Price updates and balance verification caching
+}
+
+impl EmergencyScopes {
+    pub const LEN: usize = 5;
+
+    pub fn any(&self) -> bool {
+        self.pause_payments
+            || self.pause_staking
+            || self.pause_treasury
+            || self.pause_channels
+            || self.pause_oracle_writes
+    }
+
+    pub fn contains(&self, scope: EmergencyScope) -> bool {
+        match scope {
+            EmergencyScope::Payments => self.pause_payments,
+            EmergencyScope::Staking => self.pause_staking,
+            EmergencyScope::Treasury => self.pause_treasury,
+            EmergencyScope::Channels => self.pause_channels,
+            EmergencyScope::OracleWrites => self.pause_oracle_writes,
+        }
+    }
+}
+
+/// A single subsystem an instruction belongs to
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EmergencyScope {
+    Payments,
+    Staking,
+    Treasury,
+    Channels,
+    OracleWrites,
+}
+
+impl EmergencyScope {
+    fn paused_error(&self) -> VaultError {
+        match self {
+            EmergencyScope::Payments => VaultError::PaymentSystemPaused,
+            EmergencyScope::Staking => VaultError::StakingPaused,
+            EmergencyScope::Treasury => VaultError::TreasuryPaused,
+            EmergencyScope::Channels => VaultError::ChannelsPaused,
+            EmergencyScope::OracleWrites => VaultError::OracleWritesPaused,
+        }
+    }
+}
+
+/// Protocol-wide emergency pause state, written only by the multisig
+/// emergency instructions and checked by every instruction in a pausable
+/// subsystem. Exits from in-flight work (payment completion and
+/// cancellation, channel challenges and disputes) stay open while paused
+/// so participants can still protect themselves.
+#[account]
+#[derive(Debug)]
+pub struct EmergencyState {
+    pub multisig: Pubkey,          // Multisig wallet allowed to change the scopes
+    pub scopes: EmergencyScopes,   // Currently paused subsystems
+    pub activated_at: Option<i64>, // When the first current scope was paused
+    pub updated_at: i64,           // Last scope change
+    pub updated_by: Pubkey,        // Signer of the last scope change
+    pub bump: u8,
+}
+
+impl EmergencyState {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // multisig
+        EmergencyScopes::LEN + // scopes
+        (1 + 8) + // activated_at
+        8 + // updated_at
+        32 + // updated_by
+        1; // bump
+
+    pub fn initialize(&mut self, multisig: Pubkey, bump: u8) {
+        self.multisig = multisig;
+        self.scopes = EmergencyScopes::default();
+        self.activated_at = None;
+        self.updated_at = 0;
+        self.updated_by = Pubkey::default();
+        self.bump = bump;
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.scopes.any()
+    }
+
+    /// Fail with the subsystem's pause error if its scope is paused
+    pub fn require_not_paused(&self, scope: EmergencyScope) -> Result<()> {
+        if self.scopes.contains(scope) {
+            return Err(scope.paused_error().into());
+        }
+        Ok(())
+    }
+
+    /// Pause the given scopes on top of any already paused
+    pub fn activate(&mut self, scopes: &EmergencyScopes, by: Pubkey, now: i64) -> Result<()> {
+        if !scopes.any() {
+            return Err(VaultError::EmptyEmergencyScope.into());
+        }
+        if !self.is_active() {
+            self.activated_at = Some(now);
+        }
+
+        self.scopes.pause_payments |= scopes.pause_payments;
+        self.scopes.pause_staking |= scopes.pause_staking;
+        self.scopes.pause_treasury |= scopes.pause_treasury;
+        self.scopes.pause_channels |= scopes.pause_channels;
+        self.scopes.pause_oracle_writes |= scopes.pause_oracle_writes;
+        self.updated_at = now;
+        self.updated_by = by;
+        Ok(())
+    }
+
+    /// Resume the given scopes, leaving the rest paused. Returns whether any
+    /// scope is still paused.
+    pub fn clear(&mut self, scopes: &EmergencyScopes, by: Pubkey, now: i64) -> Result<bool> {
+        if !scopes.any() {
+            return Err(VaultError::EmptyEmergencyScope.into());
+        }
+
+        self.scopes.pause_payments &= !scopes.pause_payments;
+        self.scopes.pause_staking &= !scopes.pause_staking;
+        self.scopes.pause_treasury &= !scopes.pause_treasury;
+        self.scopes.pause_channels &= !scopes.pause_channels;
+        self.scopes.pause_oracle_writes &= !scopes.pause_oracle_writes;
+        if !self.is_active() {
+            self.activated_at = None;
+        }
+        self.updated_at = now;
+        self.updated_by = by;
+        Ok(self.is_active())
+    }
+}
+
+/// Emitted whenever the paused scopes change
+#[event]
+pub struct EmergencyScopesChanged {
+    pub multisig: Pubkey,
+    pub scopes: EmergencyScopes,
+    pub updated_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state() -> EmergencyState {
+        let mut state = EmergencyState {
+            multisig: Pubkey::default(),
+            scopes: EmergencyScopes::default(),
+            activated_at: None,
+            updated_at: 0,
+            updated_by: Pubkey::default(),
+            bump: 0,
+        };
+        state.initialize(Pubkey::new_unique(), 255);
+        state
+    }
+
+    #[test]
+    fn test_scopes_pause_and_clear_incrementally() {
+        let mut state = state();
+        let admin = Pubkey::new_unique();
+        state.require_not_paused(EmergencyScope::Payments).unwrap();
+        assert!(state.activate(&EmergencyScopes::default(), admin, 10).is_err());
+
+        let payments_and_channels = EmergencyScopes {
+            pause_payments: true,
+            pause_channels: true,
+            ..EmergencyScopes::default()
+        };
+        state.activate(&payments_and_channels, admin, 10).unwrap();
+        state.activate(&EmergencyScopes { pause_oracle_writes: true, ..EmergencyScopes::default() }, admin, 20)
+            .unwrap();
+        assert_eq!(state.activated_at, Some(10));
+        assert!(state.require_not_paused(EmergencyScope::Payments).is_err());
+        assert!(state.require_not_paused(EmergencyScope::OracleWrites).is_err());
+        state.require_not_paused(EmergencyScope::Staking).unwrap();
+        state.require_not_paused(EmergencyScope::Treasury).unwrap();
+
+        // Clearing one scope leaves the others paused
+        let payments = EmergencyScopes { pause_payments: true, ..EmergencyScopes::default() };
+        assert!(state.clear(&payments, admin, 30).unwrap());
+        state.require_not_paused(EmergencyScope::Payments).unwrap();
+        assert!(state.require_not_paused(EmergencyScope::Channels).is_err());
+        assert_eq!(state.activated_at, Some(10));
+
+        let rest = EmergencyScopes {
+            pause_channels: true,
+            pause_oracle_writes: true,
+            ..EmergencyScopes::default()
+        };
+        assert!(!state.clear(&rest, admin, 40).unwrap());
+        assert!(!state.is_active());
+        assert_eq!(state.activated_at, None);
+        assert_eq!(state.updated_at, 40);
+    }
+}
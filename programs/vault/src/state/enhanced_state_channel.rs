@@ -0,0 +1,3058 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+use solana_program::hash::hashv;
+use crate::crypto::Ed25519Verifier;
+use crate::errors::VaultError;
+use crate::state::security_monitoring::SecurityLevel;
+use crate::state::state_channel::StateChannel;
+
+/// What an enhanced channel is used for
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum ChannelType {
+    Payment,
+    Trading,
+    Reward,
+    MultiPurpose,
+}
+
+/// Lifecycle of an enhanced channel
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum EnhancedChannelStatus {
+    Initializing,
+    Active,
+    Disputed,
+    Settling,
+    Closed,
+    Expired,
+}
+
+/// Assets a channel holds balances in
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChannelAsset {
+    Sol,
+    Usdc,
+    VirtualBtc, // Synthetic, closed out into the quote asset at settlement
+}
+
+impl ChannelAsset {
+    pub const COUNT: usize = 3;
+    pub const ALL: [ChannelAsset; Self::COUNT] = [ChannelAsset::Sol, ChannelAsset::Usdc, ChannelAsset::VirtualBtc];
+
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Role a participant plays in an enhanced channel
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum ParticipantRole {
+    FullParticipant,
+    Observer,
+    Operator,
+    Validator,
+}
+
+/// Channel participant
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct ChannelParticipant {
+    pub pubkey: Pubkey,
+    pub role: ParticipantRole,
+    pub weight: u16,
+    pub is_active: bool,
+    pub last_activity: i64,
+}
+
+/// A participant's in-channel account, index-aligned with `participants`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct ParticipantBalance {
+    pub assets: [i64; ChannelAsset::COUNT], // Indexed by `ChannelAsset`, negative once owed to the channel
+    pub cost_basis: i64,   // Quote value the virtual BTC position is carried at, re-marked when PnL is realized
+    pub realized_pnl: i64, // Cumulative PnL realized into the quote balance
+    pub last_updated: i64,
+    pub last_nonce: u64, // Nonce of the last accepted HFT operation
+    pub rate_window: i64, // Second the current rate window opened
+    pub window_ops: u32,  // HFT operations accepted within `rate_window`
+}
+
+impl ParticipantBalance {
+    pub fn balance(&self, asset: ChannelAsset) -> i64 {
+        self.assets[asset.index()]
+    }
+
+    /// Net virtual BTC bought (+) or sold (-)
+    pub fn position(&self) -> i64 {
+        self.assets[ChannelAsset::VirtualBtc.index()]
+    }
+}
+
+/// Key a participant delegates HFT and micro-transaction signing to. The
+/// participant stays bound by everything it signs.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct SessionKey {
+    pub delegate: Pubkey,
+    pub expires_at: i64,
+    pub max_operation_value: u64, // Cap on a single operation's quote notional or transfer amount
+    pub first_nonce: u64, // First participant nonce the delegate may sign
+    pub last_nonce: u64,  // Last nonce it signed before revocation, u64::MAX while live
+}
+
+impl SessionKey {
+    pub fn is_revoked(&self) -> bool {
+        self.last_nonce != u64::MAX
+    }
+}
+
+/// Throughput counters for operators. All arithmetic saturates so stats
+/// can never fail an operation.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct ChannelStats {
+    pub total_operations: u64,
+    pub total_volume: [u64; ChannelAsset::COUNT], // Indexed by `ChannelAsset`
+    pub epoch_start: i64, // Start of the current `STATS_EPOCH` window
+    pub epoch_operations: u64,
+    pub max_batch_size: u32,
+    pub last_operation_at: i64,
+    pub dispute_count: u32,
+}
+
+impl ChannelStats {
+    pub const STATS_EPOCH: i64 = 3_600;
+    pub const SIZE: usize = 8 + 8 * ChannelAsset::COUNT + 8 + 8 + 4 + 8 + 4;
+
+    pub fn record_operation(&mut self, timestamp: i64) {
+        let epoch_start = timestamp - timestamp.rem_euclid(Self::STATS_EPOCH);
+        if epoch_start != self.epoch_start {
+            self.epoch_start = epoch_start;
+            self.epoch_operations = 0;
+        }
+        self.total_operations = self.total_operations.saturating_add(1);
+        self.epoch_operations = self.epoch_operations.saturating_add(1);
+        self.last_operation_at = timestamp;
+    }
+
+    pub fn record_volume(&mut self, asset: ChannelAsset, amount: u64) {
+        let volume = &mut self.total_volume[asset.index()];
+        *volume = volume.saturating_add(amount);
+    }
+
+    pub fn record_batch(&mut self, size: usize) {
+        self.max_batch_size = self.max_batch_size.max(u32::try_from(size).unwrap_or(u32::MAX));
+    }
+
+    pub fn record_dispute(&mut self) {
+        self.dispute_count = self.dispute_count.saturating_add(1);
+    }
+}
+
+/// Fee schedule for channel operations
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct FeeConfig {
+    pub base_fee: u64,
+    pub transfer_fee_rate: u16, // Basis points on micro-transactions
+    pub trade_fee_rate: u16,    // Basis points on taker notional
+    pub dispute_fee: u64,
+}
+
+/// Slashing bounds applied on dispute resolution
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct SlashingConfig {
+    pub min_slash_amount: u64,
+    pub max_slash_amount: u64,
+}
+
+impl SlashingConfig {
+    pub fn bound(&self, penalty: u64) -> u64 {
+        penalty.clamp(self.min_slash_amount, self.max_slash_amount)
+    }
+}
+
+/// Security parameters for an enhanced channel
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct SecurityParams {
+    pub max_operation_value: u64,
+    pub rate_limit: u32,
+    pub fraud_detection: bool,
+    pub slashing_config: SlashingConfig,
+}
+
+/// Enhanced channel configuration
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct ChannelConfig {
+    pub channel_type: ChannelType,
+    pub timeout: i64,
+    pub dispute_period: i64,
+    pub challenge_period: i64,
+    pub min_confirmations: u8,
+    pub max_batch_size: u16,
+    pub max_ops_per_second: u32, // Per participant, counting each batched operation
+    pub fee_config: FeeConfig,
+    pub security_params: SecurityParams,
+    pub quote_asset: ChannelAsset, // Asset the order book prices virtual BTC in
+    pub usdc_mint: Pubkey,         // Mint of the USDC collateral vaults
+    /// How far each participant's balance in each asset may go negative,
+    /// backed by their deposited collateral. Index-aligned with
+    /// `participants`, then by `ChannelAsset`.
+    pub max_exposure: Vec<[u64; ChannelAsset::COUNT]>,
+}
+
+/// Side of an order
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum OrderSide {
+    Buy,
+    Sell,
+}
+
+/// High-frequency trading operation types
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum HFTOperationType {
+    MarketBuy,
+    MarketSell,
+    LimitBuy,
+    LimitSell,
+    Cancel,
+    Batch,
+    StopLoss { trigger_price: u64, side: OrderSide },
+    TakeProfit { trigger_price: u64, side: OrderSide },
+}
+
+/// High-frequency trading operation on the channel's virtual BTC market.
+/// For `Cancel`, `id` names the participant's resting or trigger order to
+/// remove. For market and trigger orders a non-zero `price` caps the
+/// execution price.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct HFTOperation {
+    pub id: u64,
+    pub operation_type: HFTOperationType,
+    pub asset: ChannelAsset, // Quote asset, must be the channel's
+    pub amount: u64,
+    pub price: u64, // Quote units per PRICE_PRECISION base units
+    pub participant: Pubkey,
+    pub nonce: u64, // Must be the participant's `last_nonce + 1`
+    pub timestamp: i64,
+}
+
+/// Small-value transfer between two participants
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct MicroTransaction {
+    pub id: u64,
+    pub from: Pubkey,
+    pub to: Pubkey,
+    pub asset: ChannelAsset,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+/// Kinds of operations that need multi-party confirmation
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum OperationType {
+    Transfer,
+    Trade,
+    RewardDistribution,
+    BalanceUpdate,
+    ConfigUpdate,
+    BatchOperation,
+}
+
+/// Confirmation of a pending operation by one participant
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct OperationConfirmation {
+    pub participant: Pubkey,
+    pub timestamp: i64,
+}
+
+/// Operation waiting on confirmations from the participants it involves
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct PendingOperation {
+    pub operation_id: u64,
+    pub operation_type: OperationType,
+    pub participants: Vec<Pubkey>,
+    pub data: Vec<u8>,
+    pub required_confirmations: u8,
+    pub confirmations: Vec<OperationConfirmation>,
+    pub proposer: Pubkey,
+    pub reserved_amount: u64, // Held against the proposer's exposure while pending
+    pub timestamp: i64,
+    pub expires_at: i64, // No confirmations at or after this time
+}
+
+/// Channel state signed by every participant, in participant order, over
+/// its version and the hash of `previous_root || state_root`. `delegated`
+/// is empty or flags, per signature, those made by the participant's
+/// session key.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct SignedChannelState {
+    pub version: u64,
+    pub previous_root: [u8; 32],
+    pub state_root: [u8; 32],
+    pub signatures: Vec<Vec<u8>>,
+    pub delegated: Vec<bool>,
+}
+
+/// HFT operation with its participant's signature, or its session key's
+/// when `delegate` is set
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct SignedOperation {
+    pub operation: HFTOperation,
+    pub signature: Vec<u8>,
+    pub delegate: Option<Pubkey>,
+}
+
+/// One participant's balance as committed in a state root
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct BalanceLeaf {
+    pub index: u8,
+    pub assets: [i64; ChannelAsset::COUNT],
+}
+
+/// Borsh-encoded dispute evidence, one variant per dispute type
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub enum DisputeEvidence {
+    /// Two consecutive signed states; `later` must chain from `earlier`
+    SignedStatePair { earlier: SignedChannelState, later: SignedChannelState },
+    /// Two operations a participant signed under the same nonce
+    ConflictingOperations { first: SignedOperation, second: SignedOperation },
+    /// An applied operation whose authorization is challenged
+    SignatureProof { operation: SignedOperation },
+    /// Balances proven against a signed state, plus every micro-transaction
+    /// netted since, in processing order
+    BalanceSnapshot {
+        state: SignedChannelState,
+        leaves: Vec<BalanceLeaf>,
+        proofs: Vec<Vec<[u8; 32]>>,
+        transfers: Vec<MicroTransaction>,
+    },
+}
+
+impl DisputeEvidence {
+    /// Decode evidence, rejecting trailing bytes
+    pub fn decode(evidence: &[u8]) -> Result<Self> {
+        Self::try_from_slice(evidence).map_err(|_| VaultError::InvalidDisputeEvidence.into())
+    }
+
+    pub fn matches(&self, dispute_type: DisputeType) -> bool {
+        matches!(
+            (self, dispute_type),
+            (DisputeEvidence::SignedStatePair { .. }, DisputeType::InvalidStateTransition)
+                | (DisputeEvidence::ConflictingOperations { .. }, DisputeType::DoubleSpending)
+                | (DisputeEvidence::SignatureProof { .. }, DisputeType::UnauthorizedOperation)
+                | (DisputeEvidence::BalanceSnapshot { .. }, DisputeType::BalanceInconsistency)
+        )
+    }
+
+    /// Operation whose nonce the evidence cites, if any
+    pub fn cited_operation(&self) -> Option<&HFTOperation> {
+        match self {
+            DisputeEvidence::ConflictingOperations { first, .. } => Some(&first.operation),
+            DisputeEvidence::SignatureProof { operation } => Some(&operation.operation),
+            _ => None,
+        }
+    }
+}
+
+/// Signature a resolution depends on, to be checked by the Ed25519 program
+#[derive(Clone, Debug, PartialEq)]
+pub struct SignatureCheck {
+    pub signer: Pubkey,
+    pub signature: Vec<u8>,
+    pub message: Vec<u8>,
+}
+
+/// Outcome the evidence supports, valid only once every signature check passes
+#[derive(Clone, Debug, PartialEq)]
+pub struct DisputeVerdict {
+    pub resolution_type: ResolutionType,
+    pub defender: Option<Pubkey>,
+    pub signature_checks: Vec<SignatureCheck>,
+}
+
+/// Dispute categories
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum DisputeType {
+    InvalidStateTransition,
+    DoubleSpending,
+    UnauthorizedOperation,
+    TimeoutViolation,
+    BalanceInconsistency,
+}
+
+/// Dispute lifecycle
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum DisputeStatus {
+    Open,
+    UnderReview,
+    Resolved,
+    Rejected,
+    TimedOut,
+}
+
+/// Open or most recent dispute on an enhanced channel
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct DisputeInfo {
+    pub dispute_id: u64,
+    pub challenger: Pubkey,
+    pub disputed_state: [u8; 32],
+    pub evidence: Vec<u8>,
+    pub dispute_type: DisputeType,
+    pub status: DisputeStatus,
+    pub challenge_deadline: i64,
+    pub created_at: i64,
+}
+
+/// Outcome of a dispute
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum ResolutionType {
+    ChallengerWins,
+    DefenderWins,
+    SystemIntervention,
+    Dismissed,
+}
+
+/// Dispute resolution submitted by a multisig signer
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct DisputeResolution {
+    pub resolution_type: ResolutionType,
+    pub winner: Option<Pubkey>,
+    pub loser: Option<Pubkey>,
+    pub penalty: u64,
+    pub evidence: Vec<u8>,
+    pub resolver: Pubkey,
+    pub resolved_at: i64,
+}
+
+/// Order resting on the channel book
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct RestingOrder {
+    pub order_id: u64,
+    pub owner: u8, // Index into `participants`
+    pub price: u64,
+    pub remaining: u64,
+}
+
+/// Stop-loss or take-profit order waiting for its trigger price
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct TriggerOrder {
+    pub order_id: u64,
+    pub owner: u8, // Index into `participants`
+    pub side: OrderSide,
+    pub take_profit: bool,
+    pub trigger_price: u64,
+    pub limit_price: u64, // 0 for no limit
+    pub amount: u64,
+}
+
+impl TriggerOrder {
+    /// Sell stops and buy take-profits fire on a falling price, the others
+    /// on a rising one
+    pub fn is_triggered(&self, price: u64) -> bool {
+        let fires_on_rise = (self.side == OrderSide::Buy) != self.take_profit;
+        if fires_on_rise {
+            price >= self.trigger_price
+        } else {
+            price <= self.trigger_price
+        }
+    }
+}
+
+/// Bounded in-channel order book. Each side is kept in price-time priority:
+/// bids highest first, asks lowest first, equal prices in arrival order.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct OrderBook {
+    pub bids: Vec<RestingOrder>,
+    pub asks: Vec<RestingOrder>,
+}
+
+/// One match between an incoming order and a resting order
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct TradeFill {
+    pub maker_order_id: u64,
+    pub taker_order_id: u64,
+    pub maker: Pubkey,
+    pub taker: Pubkey,
+    pub taker_is_buyer: bool,
+    pub price: u64,
+    pub amount: u64,
+    pub fee: u64, // Charged to the taker
+}
+
+/// Penalty a participant owed beyond their collateral
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct BadDebt {
+    pub participant: Pubkey,
+    pub amount: u64,
+    pub dispute_id: u64, // Latest dispute that added to it
+    pub recorded_at: i64,
+}
+
+/// How a penalty was split once collateral was taken into account
+#[derive(Clone, Debug, PartialEq)]
+pub struct SlashOutcome {
+    pub penalty: u64,
+    pub paid: u64,
+    pub insurance_cut: u64,
+    pub winner_share: u64,
+    pub bad_debt: u64,
+}
+
+/// Emitted when a slash leaves bad debt behind
+#[event]
+pub struct EnhancedChannelBadDebt {
+    pub channel_id: [u8; 32],
+    pub participant: Pubkey,
+    pub amount: u64,
+    pub dispute_id: u64,
+    pub security_level: SecurityLevel,
+}
+
+/// Emitted for every order book fill
+#[event]
+pub struct OrderFilled {
+    pub channel_id: [u8; 32],
+    pub maker_order_id: u64,
+    pub taker_order_id: u64,
+    pub maker: Pubkey,
+    pub taker: Pubkey,
+    pub taker_is_buyer: bool,
+    pub price: u64,
+    pub amount: u64,
+    pub fee: u64,
+}
+
+/// Emitted for every participant whose PnL moved at a checkpoint
+#[event]
+pub struct PnlRealized {
+    pub channel_id: [u8; 32],
+    pub participant: Pubkey,
+    pub amount: i64, // Credited (+) or debited (-) quote
+    pub mark_price: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted once a closed channel's collateral has been reallocated
+#[event]
+pub struct EnhancedChannelSettled {
+    pub channel_id: [u8; 32],
+    pub sol_fees: u64,  // Lamports moved to the treasury
+    pub usdc_fees: u64, // USDC base units moved to the treasury
+}
+
+/// Emitted when a participant asks to withdraw without co-signatures, so
+/// counterparties can object before `executable_at`
+#[event]
+pub struct ChannelWithdrawalRequested {
+    pub channel_id: [u8; 32],
+    pub participant: Pubkey,
+    pub asset: ChannelAsset,
+    pub amount: u64,
+    pub executable_at: i64,
+}
+
+/// PnL moved into a participant's balance by `realize_pnl`
+#[derive(Clone, Debug, PartialEq)]
+pub struct RealizedPnl {
+    pub participant: Pubkey,
+    pub amount: i64,
+}
+
+/// Withdrawal waiting out its objection window
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct PendingWithdrawal {
+    pub asset: ChannelAsset,
+    pub amount: u64,
+    pub requested_at: i64,
+}
+
+/// A participant's partial withdrawals from an active enhanced channel
+#[account]
+pub struct ChannelWithdrawal {
+    pub channel_id: [u8; 32],
+    pub participant: Pubkey,
+    pub nonce: u64, // Bumped per withdrawal so co-signatures can't be replayed
+    pub pending: Option<PendingWithdrawal>,
+    pub bump: u8,
+}
+
+impl ChannelWithdrawal {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // channel_id
+        32 + // participant
+        8 + // nonce
+        1 + 1 + 8 + 8 + // pending
+        1; // bump
+
+    /// Queue a withdrawal to execute once its objection window passes
+    pub fn request(&mut self, asset: ChannelAsset, amount: u64, timestamp: i64) -> Result<()> {
+        if self.pending.is_some() {
+            return Err(VaultError::PaymentInProgress.into());
+        }
+        if amount == 0 {
+            return Err(VaultError::InsufficientBalance.into());
+        }
+        self.pending = Some(PendingWithdrawal { asset, amount, requested_at: timestamp });
+        Ok(())
+    }
+
+    /// Clear the pending withdrawal, whether it executed or was objected to
+    pub fn take_pending(&mut self) -> Result<PendingWithdrawal> {
+        let pending = self.pending.take().ok_or(VaultError::PaymentNotFound)?;
+        self.bump_nonce()?;
+        Ok(pending)
+    }
+
+    pub fn bump_nonce(&mut self) -> Result<()> {
+        self.nonce = self.nonce
+            .checked_add(1)
+            .ok_or(VaultError::ArithmeticOverflow)?;
+        Ok(())
+    }
+}
+
+/// State channel supporting high-frequency trading, micro-transactions and
+/// multi-party confirmed operations
+#[account]
+pub struct EnhancedStateChannel {
+    pub version: u8, // Layout version, first so it sits at a fixed offset
+    pub channel_id: [u8; 32],
+    pub participants: Vec<ChannelParticipant>,
+    pub balances: Vec<ParticipantBalance>,
+    pub net_transfers: Vec<[i64; ChannelAsset::COUNT]>, // Unsettled netting per asset, see `net_index`
+    pub state_root: [u8; 32],
+    pub nonce: u64,
+    pub config: ChannelConfig,
+    pub status: EnhancedChannelStatus,
+    pub pending_operations: Vec<PendingOperation>,
+    pub dispute_info: Option<DisputeInfo>,
+    pub order_book: OrderBook,
+    pub bad_debts: Vec<BadDebt>, // At most one entry per participant
+    pub trigger_orders: Vec<TriggerOrder>, // Submission order
+    pub session_keys: Vec<Option<SessionKey>>, // Index-aligned with `participants`
+    pub mark_price: u64, // Last traded or oracle-cranked price, 0 before either
+    pub collected_fees: [u64; ChannelAsset::COUNT],
+    pub stats: ChannelStats,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub bump: u8,
+}
+
+/// Enhanced channel layout from before accounts carried a version. Only
+/// read by `migrate_channel_v2`, which rewrites it as the current layout.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct EnhancedStateChannelV1 {
+    pub channel_id: [u8; 32],
+    pub participants: Vec<ChannelParticipant>,
+    pub balances: Vec<ParticipantBalance>,
+    pub net_transfers: Vec<[i64; ChannelAsset::COUNT]>,
+    pub state_root: [u8; 32],
+    pub nonce: u64,
+    pub config: ChannelConfig,
+    pub status: EnhancedChannelStatus,
+    pub pending_operations: Vec<PendingOperation>,
+    pub dispute_info: Option<DisputeInfo>,
+    pub order_book: OrderBook,
+    pub bad_debts: Vec<BadDebt>,
+    pub trigger_orders: Vec<TriggerOrder>,
+    pub session_keys: Vec<Option<SessionKey>>,
+    pub mark_price: u64,
+    pub collected_fees: [u64; ChannelAsset::COUNT],
+    pub stats: ChannelStats,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub bump: u8,
+}
+
+impl EnhancedStateChannelV1 {
+    /// Allocated size of a v1 account: the current one without `version`
+    pub const SIZE: usize = EnhancedStateChannel::SIZE - 1;
+
+    /// Decode a v1 account's data, discriminator included
+    pub fn try_from_account_data(data: &[u8]) -> Result<Self> {
+        if data.len() != Self::SIZE || data[..8] != EnhancedStateChannel::DISCRIMINATOR {
+            return Err(VaultError::UnsupportedChannelVersion.into());
+        }
+        Self::deserialize(&mut &data[8..]).map_err(|_| VaultError::UnsupportedChannelVersion.into())
+    }
+
+    /// Carry every field over into the current layout
+    pub fn migrate(self, timestamp: i64) -> EnhancedStateChannel {
+        EnhancedStateChannel {
+            version: EnhancedStateChannel::VERSION,
+            channel_id: self.channel_id,
+            participants: self.participants,
+            balances: self.balances,
+            net_transfers: self.net_transfers,
+            state_root: self.state_root,
+            nonce: self.nonce,
+            config: self.config,
+            status: self.status,
+            pending_operations: self.pending_operations,
+            dispute_info: self.dispute_info,
+            order_book: self.order_book,
+            bad_debts: self.bad_debts,
+            trigger_orders: self.trigger_orders,
+            session_keys: self.session_keys,
+            mark_price: self.mark_price,
+            collected_fees: self.collected_fees,
+            stats: self.stats,
+            created_at: self.created_at,
+            updated_at: timestamp,
+            bump: self.bump,
+        }
+    }
+}
+
+impl EnhancedStateChannel {
+    /// Layout every handler operates on; older accounts must be migrated
+    pub const VERSION: u8 = 2;
+    pub const MAX_PARTICIPANTS: usize = 10;
+    pub const MAX_PENDING_OPERATIONS: usize = 4;
+    pub const MAX_OPERATION_DATA: usize = 64;
+    pub const MAX_EVIDENCE_SIZE: usize = 1024;
+    // Bounded so the whole channel still fits one 10KB account allocation
+    pub const MAX_ORDERS_PER_SIDE: usize = 16;
+    pub const MAX_TRIGGER_ORDERS: usize = 16;
+    pub const PRICE_PRECISION: u64 = 1_000_000;
+    // Upper triangle including the diagonal
+    pub const MAX_NET_ENTRIES: usize = Self::MAX_PARTICIPANTS * (Self::MAX_PARTICIPANTS + 1) / 2;
+
+    const PARTICIPANT_SIZE: usize = 32 + 1 + 2 + 1 + 8;
+    const BALANCE_SIZE: usize = 8 * ChannelAsset::COUNT + 8 + 8 + 8 + 8 + 8 + 4;
+    const CONFIG_SIZE: usize = 1 + 8 + 8 + 8 + 1 + 2 + 4 +
+        (8 + 2 + 2 + 8) + // fee_config
+        (8 + 4 + 1 + (8 + 8)) + // security_params
+        1 + 32 + // quote_asset, usdc_mint
+        4 + 8 * ChannelAsset::COUNT * Self::MAX_PARTICIPANTS; // max_exposure
+    const PENDING_OPERATION_SIZE: usize = 8 + 1 +
+        4 + 32 * Self::MAX_PARTICIPANTS + // participants
+        4 + Self::MAX_OPERATION_DATA + // data
+        1 +
+        4 + (32 + 8) * Self::MAX_PARTICIPANTS + // confirmations
+        32 + 8 + // proposer, reserved_amount
+        8 + 8; // timestamp, expires_at
+    const DISPUTE_SIZE: usize = 8 + 32 + 32 + 4 + Self::MAX_EVIDENCE_SIZE + 1 + 1 + 8 + 8;
+    const RESTING_ORDER_SIZE: usize = 8 + 1 + 8 + 8;
+    const TRIGGER_ORDER_SIZE: usize = 8 + 1 + 1 + 1 + 8 + 8 + 8;
+    const SESSION_KEY_SIZE: usize = 32 + 8 + 8 + 8 + 8;
+
+    pub const SIZE: usize = 8 + // discriminator
+        1 + // version
+        32 + // channel_id
+        4 + Self::PARTICIPANT_SIZE * Self::MAX_PARTICIPANTS + // participants
+        4 + Self::BALANCE_SIZE * Self::MAX_PARTICIPANTS + // balances
+        4 + 8 * ChannelAsset::COUNT * Self::MAX_NET_ENTRIES + // net_transfers
+        32 + // state_root
+        8 + // nonce
+        Self::CONFIG_SIZE + // config
+        1 + // status
+        4 + Self::PENDING_OPERATION_SIZE * Self::MAX_PENDING_OPERATIONS + // pending_operations
+        1 + Self::DISPUTE_SIZE + // dispute_info
+        2 * (4 + Self::RESTING_ORDER_SIZE * Self::MAX_ORDERS_PER_SIDE) + // order_book
+        4 + (32 + 8 + 8 + 8) * Self::MAX_PARTICIPANTS + // bad_debts
+        4 + Self::TRIGGER_ORDER_SIZE * Self::MAX_TRIGGER_ORDERS + // trigger_orders
+        4 + (1 + Self::SESSION_KEY_SIZE) * Self::MAX_PARTICIPANTS + // session_keys
+        8 + // mark_price
+        8 * ChannelAsset::COUNT + // collected_fees
+        ChannelStats::SIZE + // stats
+        8 + // created_at
+        8 + // updated_at
+        1; // bump
+
+    /// Initialize a new enhanced channel
+    pub fn initialize(
+        &mut self,
+        channel_id: [u8; 32],
+        participants: Vec<ChannelParticipant>,
+        config: ChannelConfig,
+        bump: u8,
+        timestamp: i64,
+    ) -> Result<()> {
+        if participants.is_empty() || participants.len() > Self::MAX_PARTICIPANTS {
+            return Err(VaultError::InvalidAllocation.into());
+        }
+        if config.max_batch_size == 0 || config.min_confirmations == 0 || config.max_ops_per_second == 0 {
+            return Err(VaultError::InvalidThresholdValue.into());
+        }
+        if config.max_exposure.len() != participants.len() {
+            return Err(VaultError::InvalidAllocation.into());
+        }
+        if config.quote_asset == ChannelAsset::VirtualBtc {
+            return Err(VaultError::AssetMismatch.into());
+        }
+        let slashing = &config.security_params.slashing_config;
+        if slashing.min_slash_amount > slashing.max_slash_amount {
+            return Err(VaultError::InvalidThresholdValue.into());
+        }
+
+        self.version = Self::VERSION;
+        self.channel_id = channel_id;
+        self.balances = vec![ParticipantBalance::default(); participants.len()];
+        self.net_transfers = vec![[0; ChannelAsset::COUNT]; participants.len() * (participants.len() + 1) / 2];
+        self.session_keys = vec![None; participants.len()];
+        self.participants = participants;
+        self.state_root = [0; 32];
+        self.nonce = 0;
+        self.config = config;
+        self.status = EnhancedChannelStatus::Initializing;
+        self.pending_operations = Vec::new();
+        self.dispute_info = None;
+        self.order_book = OrderBook::default();
+        self.bad_debts = Vec::new();
+        self.trigger_orders = Vec::new();
+        self.mark_price = 0;
+        self.collected_fees = [0; ChannelAsset::COUNT];
+        self.stats = ChannelStats::default();
+        self.created_at = timestamp;
+        self.updated_at = timestamp;
+        self.bump = bump;
+
+        Ok(())
+    }
+
+    /// Open the channel for operations
+    pub fn activate(&mut self, timestamp: i64) -> Result<()> {
+        if self.status != EnhancedChannelStatus::Initializing {
+            return Err(VaultError::SecurityViolation.into());
+        }
+        self.status = EnhancedChannelStatus::Active;
+        self.updated_at = timestamp;
+        Ok(())
+    }
+
+    pub fn is_participant(&self, pubkey: &Pubkey) -> bool {
+        self.participant_index(pubkey).is_some()
+    }
+
+    /// Index of an active participant
+    pub fn participant_index(&self, pubkey: &Pubkey) -> Option<usize> {
+        self.participants.iter().position(|p| p.pubkey == *pubkey && p.is_active)
+    }
+
+    /// Apply an HFT operation, matching it against the order book.
+    /// Returns the fills it produced.
+    pub fn process_hft_operation(
+        &mut self,
+        operation: HFTOperation,
+        participant: Pubkey,
+        timestamp: i64,
+    ) -> Result<Vec<TradeFill>> {
+        if self.status != EnhancedChannelStatus::Active {
+            return Err(VaultError::SecurityViolation.into());
+        }
+        if operation.participant != participant {
+            return Err(VaultError::UnauthorizedAccess.into());
+        }
+        if operation.asset != self.config.quote_asset {
+            return Err(VaultError::AssetMismatch.into());
+        }
+        let index = self.participant_index(&participant).ok_or(VaultError::UnauthorizedAccess)?;
+        // Checked first so a throttled operation leaves its nonce unused
+        self.check_rate_limit(index, 1, timestamp)?;
+        self.check_operation_nonce(index, operation.nonce)?;
+
+        let mut fills = match operation.operation_type {
+            HFTOperationType::MarketBuy
+            | HFTOperationType::MarketSell
+            | HFTOperationType::LimitBuy
+            | HFTOperationType::LimitSell => self.match_order(&operation, index, timestamp)?,
+            HFTOperationType::StopLoss { .. } | HFTOperationType::TakeProfit { .. } => {
+                self.submit_trigger_order(&operation, index)?;
+                Vec::new()
+            }
+            HFTOperationType::Cancel => {
+                self.cancel_order(operation.id, index)?;
+                Vec::new()
+            }
+            HFTOperationType::Batch => return Err(VaultError::InvalidAllocation.into()),
+        };
+
+        // Fills may have moved the mark price through resting triggers
+        fills.extend(self.check_triggers(0, timestamp)?);
+        // Checked for everyone: triggered orders trade on their owners' accounts
+        self.enforce_exposure_limits()?;
+
+        self.stats.record_operation(timestamp);
+        for fill in fills.iter() {
+            self.stats.record_volume(ChannelAsset::VirtualBtc, fill.amount);
+            let notional = Self::notional(fill.amount, fill.price).unwrap_or(u64::MAX);
+            self.stats.record_volume(self.config.quote_asset, notional);
+        }
+        self.participants[index].last_activity = timestamp;
+        self.balances[index].last_nonce = operation.nonce;
+        self.record_rate_usage(index, timestamp);
+        self.updated_at = timestamp;
+
+        Ok(fills)
+    }
+
+    /// Reject `count` more operations from a participant if they would exceed
+    /// `max_ops_per_second` within the current one-second window
+    pub fn check_rate_limit(&self, index: usize, count: u32, timestamp: i64) -> Result<()> {
+        let balance = &self.balances[index];
+        let used = if balance.rate_window == timestamp { balance.window_ops } else { 0 };
+        if used.saturating_add(count) > self.config.max_ops_per_second {
+            return Err(VaultError::RateLimitExceeded.into());
+        }
+        Ok(())
+    }
+
+    fn record_rate_usage(&mut self, index: usize, timestamp: i64) {
+        let balance = &mut self.balances[index];
+        if balance.rate_window != timestamp {
+            balance.rate_window = timestamp;
+            balance.window_ops = 0;
+        }
+        balance.window_ops += 1;
+    }
+
+    /// Require the participant's next nonce; anything at or below the last
+    /// accepted one is a replay
+    pub fn check_operation_nonce(&self, index: usize, nonce: u64) -> Result<()> {
+        let last_nonce = self.balances[index].last_nonce;
+        if nonce <= last_nonce {
+            return Err(VaultError::OperationReplayed.into());
+        }
+        if nonce != last_nonce.checked_add(1).ok_or(VaultError::ArithmeticOverflow)? {
+            return Err(VaultError::InvalidOperationNonce.into());
+        }
+        Ok(())
+    }
+
+    /// Validate a batch's nonces up front: strictly increasing and picking
+    /// up exactly where the participant left off
+    pub fn check_batch_nonces(&self, participant: &Pubkey, operations: &[HFTOperation]) -> Result<()> {
+        let index = self.participant_index(participant).ok_or(VaultError::UnauthorizedAccess)?;
+        let mut expected = self.balances[index].last_nonce;
+        for operation in operations {
+            if operation.nonce <= expected {
+                return Err(VaultError::OperationReplayed.into());
+            }
+            expected = expected.checked_add(1).ok_or(VaultError::ArithmeticOverflow)?;
+            if operation.nonce != expected {
+                return Err(VaultError::InvalidOperationNonce.into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Match an incoming market or limit order against the opposite side in
+    /// price-time priority. Fills execute at the resting order's price; a
+    /// limit order's unfilled remainder rests on the book, a market order's
+    /// is dropped. A market order with a non-zero price stops matching past it.
+    pub fn match_order(
+        &mut self,
+        order: &HFTOperation,
+        taker: usize,
+        timestamp: i64,
+    ) -> Result<Vec<TradeFill>> {
+        let (is_buy, is_limit) = match order.operation_type {
+            HFTOperationType::MarketBuy => (true, false),
+            HFTOperationType::MarketSell => (false, false),
+            HFTOperationType::LimitBuy => (true, true),
+            HFTOperationType::LimitSell => (false, true),
+            _ => return Err(VaultError::InvalidAllocation.into()),
+        };
+        if order.amount == 0 || (is_limit && order.price == 0) {
+            return Err(VaultError::InvalidAllocation.into());
+        }
+        if Self::notional(order.amount, order.price)? > self.config.security_params.max_operation_value {
+            return Err(VaultError::InvalidAllocation.into());
+        }
+
+        let fee_rate = self.config.fee_config.trade_fee_rate;
+        let mut remaining = order.amount;
+        let mut fills = Vec::new();
+
+        while remaining > 0 {
+            let book = if is_buy { &mut self.order_book.asks } else { &mut self.order_book.bids };
+            let best = match book.first_mut() {
+                Some(best) => best,
+                None => break,
+            };
+            let bounded = is_limit || order.price > 0;
+            let crosses = !bounded || if is_buy { best.price <= order.price } else { best.price >= order.price };
+            if !crosses {
+                break;
+            }
+
+            let amount = remaining.min(best.remaining);
+            let (maker, maker_order_id, price) = (best.owner as usize, best.order_id, best.price);
+            best.remaining -= amount;
+            if best.remaining == 0 {
+                book.remove(0);
+            }
+            remaining -= amount;
+
+            let notional = Self::notional(amount, price)?;
+            let fee = Self::notional_fee(notional, fee_rate);
+            let (buyer, seller) = if is_buy { (taker, maker) } else { (maker, taker) };
+            let (buyer_fee, seller_fee) = if is_buy { (fee, 0) } else { (0, fee) };
+            self.settle_fill(buyer, seller, amount, notional, buyer_fee, seller_fee, timestamp)?;
+            let quote = self.config.quote_asset.index();
+            self.collected_fees[quote] = self.collected_fees[quote]
+                .checked_add(fee)
+                .ok_or(VaultError::ArithmeticOverflow)?;
+            self.mark_price = price;
+
+            fills.push(TradeFill {
+                maker_order_id,
+                taker_order_id: order.id,
+                maker: self.participants[maker].pubkey,
+                taker: self.participants[taker].pubkey,
+                taker_is_buyer: is_buy,
+                price,
+                amount,
+                fee,
+            });
+        }
+
+        if is_limit && remaining > 0 {
+            self.insert_resting_order(
+                is_buy,
+                RestingOrder { order_id: order.id, owner: taker as u8, price: order.price, remaining },
+            )?;
+        }
+
+        Ok(fills)
+    }
+
+    /// Remove a participant's resting order from either side of the book,
+    /// or a trigger order that hasn't fired yet
+    pub fn cancel_order(&mut self, order_id: u64, owner: usize) -> Result<RestingOrder> {
+        for book in [&mut self.order_book.bids, &mut self.order_book.asks] {
+            if let Some(position) = book.iter().position(|o| o.order_id == order_id && o.owner as usize == owner) {
+                return Ok(book.remove(position));
+            }
+        }
+        if let Some(position) = self.trigger_orders
+            .iter()
+            .position(|o| o.order_id == order_id && o.owner as usize == owner)
+        {
+            let trigger = self.trigger_orders.remove(position);
+            return Ok(RestingOrder {
+                order_id,
+                owner: trigger.owner,
+                price: trigger.limit_price,
+                remaining: trigger.amount,
+            });
+        }
+        Err(VaultError::OrderNotFound.into())
+    }
+
+    /// Park a stop-loss or take-profit order until its trigger price is hit
+    fn submit_trigger_order(&mut self, order: &HFTOperation, owner: usize) -> Result<()> {
+        let (trigger_price, side, take_profit) = match order.operation_type {
+            HFTOperationType::StopLoss { trigger_price, side } => (trigger_price, side, false),
+            HFTOperationType::TakeProfit { trigger_price, side } => (trigger_price, side, true),
+            _ => return Err(VaultError::InvalidAllocation.into()),
+        };
+        if order.amount == 0 || trigger_price == 0 {
+            return Err(VaultError::InvalidAllocation.into());
+        }
+        if Self::notional(order.amount, trigger_price)? > self.config.security_params.max_operation_value {
+            return Err(VaultError::InvalidAllocation.into());
+        }
+
+        // A limit on the wrong side of the trigger could never fill when it fires
+        let conflicts = match side {
+            OrderSide::Buy => order.price != 0 && order.price < trigger_price,
+            OrderSide::Sell => order.price != 0 && order.price > trigger_price,
+        };
+        if conflicts {
+            return Err(VaultError::TriggerLimitConflict.into());
+        }
+
+        if self.trigger_orders.len() >= Self::MAX_TRIGGER_ORDERS {
+            return Err(VaultError::OrderBookFull.into());
+        }
+        self.trigger_orders.push(TriggerOrder {
+            order_id: order.id,
+            owner: owner as u8,
+            side,
+            take_profit,
+            trigger_price,
+            limit_price: order.price,
+            amount: order.amount,
+        });
+        Ok(())
+    }
+
+    /// Fire every trigger order crossed by the reference price, in submission
+    /// order, as market orders. The channel's own mark price is used when it
+    /// has traded; `oracle_price` is the fallback (0 if unavailable). Fills
+    /// move the mark, so triggers can cascade within one call.
+    pub fn check_triggers(&mut self, oracle_price: u64, timestamp: i64) -> Result<Vec<TradeFill>> {
+        let mut fills = Vec::new();
+        loop {
+            let reference = if self.mark_price > 0 { self.mark_price } else { oracle_price };
+            if reference == 0 {
+                break;
+            }
+            let position = match self.trigger_orders.iter().position(|o| o.is_triggered(reference)) {
+                Some(position) => position,
+                None => break,
+            };
+
+            let trigger = self.trigger_orders.remove(position);
+            let owner = trigger.owner as usize;
+            let market_order = HFTOperation {
+                id: trigger.order_id,
+                operation_type: match trigger.side {
+                    OrderSide::Buy => HFTOperationType::MarketBuy,
+                    OrderSide::Sell => HFTOperationType::MarketSell,
+                },
+                asset: self.config.quote_asset,
+                amount: trigger.amount,
+                price: trigger.limit_price,
+                participant: self.participants[owner].pubkey,
+                nonce: 0, // Sequenced when the order was submitted
+                timestamp,
+            };
+            fills.extend(self.match_order(&market_order, owner, timestamp)?);
+        }
+        Ok(fills)
+    }
+
+    /// Place an order behind every resting order at the same or better price
+    fn insert_resting_order(&mut self, is_buy: bool, order: RestingOrder) -> Result<()> {
+        let book = if is_buy { &mut self.order_book.bids } else { &mut self.order_book.asks };
+        if book.len() >= Self::MAX_ORDERS_PER_SIDE {
+            return Err(VaultError::OrderBookFull.into());
+        }
+        let position = book
+            .iter()
+            .position(|resting| if is_buy { resting.price < order.price } else { resting.price > order.price })
+            .unwrap_or(book.len());
+        book.insert(position, order);
+        Ok(())
+    }
+
+    /// Swap virtual BTC for the quote asset between the two sides of a fill,
+    /// with fees taken in the quote asset
+    fn settle_fill(
+        &mut self,
+        buyer: usize,
+        seller: usize,
+        amount: u64,
+        notional: u64,
+        buyer_fee: u64,
+        seller_fee: u64,
+        timestamp: i64,
+    ) -> Result<()> {
+        let amount = i64::try_from(amount).map_err(|_| VaultError::ArithmeticOverflow)?;
+        let notional = i64::try_from(notional).map_err(|_| VaultError::ArithmeticOverflow)?;
+        let (base, quote) = (ChannelAsset::VirtualBtc.index(), self.config.quote_asset.index());
+
+        let legs = [
+            (buyer, amount, notional, -notional - buyer_fee as i64),
+            (seller, -amount, -notional, notional - seller_fee as i64),
+        ];
+        for (index, position_delta, cost_delta, balance_delta) in legs {
+            let account = &mut self.balances[index];
+            account.assets[base] = account.assets[base]
+                .checked_add(position_delta)
+                .ok_or(VaultError::ArithmeticOverflow)?;
+            account.cost_basis = account.cost_basis
+                .checked_add(cost_delta)
+                .ok_or(VaultError::ArithmeticOverflow)?;
+            account.assets[quote] = account.assets[quote]
+                .checked_add(balance_delta)
+                .ok_or(VaultError::ArithmeticOverflow)?;
+            account.last_updated = timestamp;
+        }
+        Ok(())
+    }
+
+    fn notional(amount: u64, price: u64) -> Result<u64> {
+        let notional = amount as u128 * price as u128 / Self::PRICE_PRECISION as u128;
+        u64::try_from(notional).map_err(|_| VaultError::ArithmeticOverflow.into())
+    }
+
+    fn notional_fee(notional: u64, fee_rate: u16) -> u64 {
+        (notional as u128 * fee_rate as u128 / 10000) as u64
+    }
+
+    /// Transfer a small amount between two participants
+    pub fn process_micro_transaction(
+        &mut self,
+        transaction: MicroTransaction,
+        participant: Pubkey,
+        timestamp: i64,
+    ) -> Result<u64> {
+        if self.status != EnhancedChannelStatus::Active {
+            return Err(VaultError::SecurityViolation.into());
+        }
+        if transaction.from != participant || transaction.from == transaction.to {
+            return Err(VaultError::UnauthorizedAccess.into());
+        }
+        let from = self.participant_index(&transaction.from).ok_or(VaultError::UnauthorizedAccess)?;
+        let to = self.participant_index(&transaction.to).ok_or(VaultError::UnauthorizedAccess)?;
+        if transaction.amount == 0 || transaction.amount > self.config.security_params.max_operation_value {
+            return Err(VaultError::InvalidAllocation.into());
+        }
+
+        let asset = transaction.asset;
+        let fee = self.micro_transaction_fee(transaction.amount)?;
+        self.record_net_transfer(asset, from, to, transaction.amount, fee)?;
+        if self.exposure(from, asset)? > self.config.max_exposure[from][asset.index()] as i128 {
+            return Err(VaultError::ExposureLimitExceeded.into());
+        }
+        self.balances[from].last_updated = timestamp;
+        self.balances[to].last_updated = timestamp;
+
+        self.collected_fees[asset.index()] = self.collected_fees[asset.index()]
+            .checked_add(fee)
+            .ok_or(VaultError::ArithmeticOverflow)?;
+        self.stats.record_operation(timestamp);
+        self.stats.record_volume(asset, transaction.amount);
+        self.participants[from].last_activity = timestamp;
+        self.updated_at = timestamp;
+
+        Ok(fee)
+    }
+
+    /// Base fee plus the transfer rate, charged to the sender in the
+    /// transferred asset
+    pub fn micro_transaction_fee(&self, amount: u64) -> Result<u64> {
+        let fee_config = &self.config.fee_config;
+        fee_config.base_fee
+            .checked_add(Self::notional_fee(amount, fee_config.transfer_fee_rate))
+            .ok_or(VaultError::ArithmeticOverflow.into())
+    }
+
+    /// Position of pair `(i, j)`, `i <= j`, in the packed upper triangle of
+    /// an `n`-participant matrix. Off-diagonal entries hold the net amount
+    /// of each asset `i` owes `j` (negative when `j` owes `i`); the diagonal
+    /// holds fees `i` owes the channel.
+    pub fn net_index(n: usize, i: usize, j: usize) -> usize {
+        debug_assert!(i <= j && j < n);
+        i * (2 * n - i + 1) / 2 + (j - i)
+    }
+
+    /// Record a transfer and its fee in a netting matrix
+    fn apply_net_transfer(
+        net: &mut [[i64; ChannelAsset::COUNT]],
+        n: usize,
+        asset: ChannelAsset,
+        from: usize,
+        to: usize,
+        amount: u64,
+        fee: u64,
+    ) -> Result<()> {
+        let asset = asset.index();
+        let amount = i64::try_from(amount).map_err(|_| VaultError::ArithmeticOverflow)?;
+        let fee = i64::try_from(fee).map_err(|_| VaultError::ArithmeticOverflow)?;
+        let (pair, signed) = if from < to {
+            (Self::net_index(n, from, to), amount)
+        } else {
+            (Self::net_index(n, to, from), -amount)
+        };
+        net[pair][asset] = net[pair][asset].checked_add(signed).ok_or(VaultError::ArithmeticOverflow)?;
+        let own = Self::net_index(n, from, from);
+        net[own][asset] = net[own][asset].checked_add(fee).ok_or(VaultError::ArithmeticOverflow)?;
+        Ok(())
+    }
+
+    fn record_net_transfer(&mut self, asset: ChannelAsset, from: usize, to: usize, amount: u64, fee: u64) -> Result<()> {
+        let n = self.participants.len();
+        Self::apply_net_transfer(&mut self.net_transfers, n, asset, from, to, amount, fee)
+    }
+
+    /// Net change in one asset per participant that the next checkpoint
+    /// will apply
+    pub fn pending_net_changes(&self, asset: ChannelAsset) -> Result<Vec<i128>> {
+        let (n, asset) = (self.participants.len(), asset.index());
+        let mut changes = vec![0i128; n];
+        for i in 0..n {
+            let fee = self.net_transfers[Self::net_index(n, i, i)][asset] as i128;
+            changes[i] = changes[i].checked_sub(fee).ok_or(VaultError::ArithmeticOverflow)?;
+            for j in i + 1..n {
+                let owed = self.net_transfers[Self::net_index(n, i, j)][asset] as i128;
+                changes[i] = changes[i].checked_sub(owed).ok_or(VaultError::ArithmeticOverflow)?;
+                changes[j] = changes[j].checked_add(owed).ok_or(VaultError::ArithmeticOverflow)?;
+            }
+        }
+        Ok(changes)
+    }
+
+    /// Fold the netting matrix into participant balances and clear it
+    pub fn checkpoint_net_transfers(&mut self, timestamp: i64) -> Result<()> {
+        for asset in ChannelAsset::ALL {
+            let changes = self.pending_net_changes(asset)?;
+            for (index, change) in changes.into_iter().enumerate() {
+                if change == 0 {
+                    continue;
+                }
+                let account = &mut self.balances[index];
+                let updated = (account.assets[asset.index()] as i128)
+                    .checked_add(change)
+                    .ok_or(VaultError::ArithmeticOverflow)?;
+                account.assets[asset.index()] = i64::try_from(updated).map_err(|_| VaultError::ArithmeticOverflow)?;
+                account.last_updated = timestamp;
+            }
+        }
+        self.net_transfers.iter_mut().for_each(|entry| *entry = [0; ChannelAsset::COUNT]);
+        self.updated_at = timestamp;
+        Ok(())
+    }
+
+    /// Set the mark from a keeper's oracle reading
+    pub fn update_mark_price(&mut self, price: u64, timestamp: i64) -> Result<()> {
+        if price == 0 {
+            return Err(VaultError::InvalidOraclePrice.into());
+        }
+        self.mark_price = price;
+        self.updated_at = timestamp;
+        Ok(())
+    }
+
+    /// Move unrealized PnL at the mark price from losing to winning
+    /// participants through the quote asset's netting matrix, to be folded
+    /// into balances by the next checkpoint. A loser pays at most their remaining exposure
+    /// headroom; whatever they cannot cover stays unrealized, as does
+    /// rounding dust, so the transfers always net to zero.
+    pub fn realize_pnl(&mut self, timestamp: i64) -> Result<Vec<RealizedPnl>> {
+        if self.mark_price == 0 {
+            return Ok(Vec::new());
+        }
+        let (n, quote) = (self.participants.len(), self.config.quote_asset);
+
+        let mut payable = vec![0u64; n];
+        let mut receivable = vec![0u64; n];
+        for index in 0..n {
+            let account = &self.balances[index];
+            let marked = account.position() as i128 * self.mark_price as i128 / Self::PRICE_PRECISION as i128;
+            let pnl = marked - account.cost_basis as i128;
+            if pnl > 0 {
+                receivable[index] = u64::try_from(pnl).map_err(|_| VaultError::ArithmeticOverflow)?;
+            } else if pnl < 0 {
+                let limit = self.config.max_exposure[index][quote.index()] as i128;
+                let headroom = (limit - self.exposure(index, quote)?).max(0);
+                payable[index] = u64::try_from((-pnl).min(headroom)).map_err(|_| VaultError::ArithmeticOverflow)?;
+            }
+        }
+
+        let mut realized = vec![0i128; n];
+        let mut to = 0;
+        for from in 0..n {
+            while payable[from] > 0 {
+                while to < n && receivable[to] == 0 {
+                    to += 1;
+                }
+                if to == n {
+                    break;
+                }
+                let amount = payable[from].min(receivable[to]);
+                self.record_net_transfer(quote, from, to, amount, 0)?;
+                payable[from] -= amount;
+                receivable[to] -= amount;
+                realized[from] -= amount as i128;
+                realized[to] += amount as i128;
+            }
+        }
+
+        let mut moved = Vec::new();
+        for (index, amount) in realized.into_iter().enumerate() {
+            if amount == 0 {
+                continue;
+            }
+            let amount = i64::try_from(amount).map_err(|_| VaultError::ArithmeticOverflow)?;
+            let account = &mut self.balances[index];
+            account.cost_basis = account.cost_basis
+                .checked_add(amount)
+                .ok_or(VaultError::ArithmeticOverflow)?;
+            account.realized_pnl = account.realized_pnl
+                .checked_add(amount)
+                .ok_or(VaultError::ArithmeticOverflow)?;
+            account.last_updated = timestamp;
+            moved.push(RealizedPnl { participant: self.participants[index].pubkey, amount });
+        }
+        self.updated_at = timestamp;
+        Ok(moved)
+    }
+
+    /// Amount of an asset a participant could owe the channel: how far
+    /// their balance, including unsettled micro-transactions, is negative,
+    /// plus what their resting orders may still take from it. Resting bids
+    /// and the amounts reserved by operations they proposed count against
+    /// the quote asset, resting asks against virtual BTC.
+    pub fn exposure(&self, index: usize, asset: ChannelAsset) -> Result<i128> {
+        let (n, slot) = (self.participants.len(), asset.index());
+        let mut effective = self.balances[index].assets[slot] as i128
+            - self.net_transfers[Self::net_index(n, index, index)][slot] as i128;
+        for other in 0..n {
+            if other < index {
+                effective += self.net_transfers[Self::net_index(n, other, index)][slot] as i128;
+            } else if other > index {
+                effective -= self.net_transfers[Self::net_index(n, index, other)][slot] as i128;
+            }
+        }
+
+        let mut committed: i128 = 0;
+        if asset == self.config.quote_asset {
+            for bid in self.order_book.bids.iter().filter(|bid| bid.owner as usize == index) {
+                committed += Self::notional(bid.remaining, bid.price)? as i128;
+            }
+            let owner = self.participants[index].pubkey;
+            for operation in self.pending_operations.iter().filter(|op| op.proposer == owner) {
+                committed += operation.reserved_amount as i128;
+            }
+        } else if asset == ChannelAsset::VirtualBtc {
+            for ask in self.order_book.asks.iter().filter(|ask| ask.owner as usize == index) {
+                committed += ask.remaining as i128;
+            }
+        }
+        Ok((-effective).max(0) + committed)
+    }
+
+    /// Reject any state where a participant owes more of any asset than
+    /// their limit
+    pub fn enforce_exposure_limits(&self) -> Result<()> {
+        for index in 0..self.participants.len() {
+            for asset in ChannelAsset::ALL {
+                if self.exposure(index, asset)? > self.config.max_exposure[index][asset.index()] as i128 {
+                    return Err(VaultError::ExposureLimitExceeded.into());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Reject a withdrawal from an active channel unless the participant's
+    /// remaining vaulted deposit still covers their exposure in that asset.
+    /// Disputed channels are frozen, so nothing can leave them.
+    pub fn check_withdrawal(&self, participant: &Pubkey, asset: ChannelAsset, amount: u64, deposit: u64) -> Result<()> {
+        if self.status != EnhancedChannelStatus::Active {
+            return Err(VaultError::SecurityViolation.into());
+        }
+        if asset == ChannelAsset::VirtualBtc {
+            return Err(VaultError::AssetMismatch.into());
+        }
+        let index = self.participant_index(participant).ok_or(VaultError::UnauthorizedAccess)?;
+        if amount == 0 || amount > deposit {
+            return Err(VaultError::InsufficientBalance.into());
+        }
+        if ((deposit - amount) as i128) < self.exposure(index, asset)? {
+            return Err(VaultError::ExposureLimitExceeded.into());
+        }
+        Ok(())
+    }
+
+    /// When a withdrawal requested at `requested_at` clears its objection
+    /// window. A dispute opened since then freezes it, and the window runs
+    /// again from the end of that dispute's challenge period.
+    pub fn withdrawal_executable_at(&self, requested_at: i64) -> Result<i64> {
+        let start = match &self.dispute_info {
+            Some(dispute) if dispute.created_at >= requested_at => dispute.challenge_deadline,
+            _ => requested_at,
+        };
+        start
+            .checked_add(self.config.challenge_period)
+            .ok_or(VaultError::ArithmeticOverflow.into())
+    }
+
+    /// Raise a participant's exposure limit in one asset once every
+    /// participant has signed off on it. Limits never go down mid-channel;
+    /// `nonce` is bumped so the agreement can't be replayed.
+    pub fn raise_exposure_limit(
+        &mut self,
+        participant: &Pubkey,
+        asset: ChannelAsset,
+        new_limit: u64,
+        timestamp: i64,
+    ) -> Result<()> {
+        if !matches!(self.status, EnhancedChannelStatus::Active | EnhancedChannelStatus::Initializing) {
+            return Err(VaultError::SecurityViolation.into());
+        }
+        let index = self.participants
+            .iter()
+            .position(|p| p.pubkey == *participant)
+            .ok_or(VaultError::UnauthorizedAccess)?;
+        if new_limit <= self.config.max_exposure[index][asset.index()] {
+            return Err(VaultError::InvalidThresholdValue.into());
+        }
+
+        self.config.max_exposure[index][asset.index()] = new_limit;
+        self.nonce = self.nonce.checked_add(1).ok_or(VaultError::ArithmeticOverflow)?;
+        self.updated_at = timestamp;
+        Ok(())
+    }
+
+    /// Let `delegate` sign a participant's operations from their next nonce
+    /// until `expires_at`, each worth at most `max_operation_value`. Replaces
+    /// any key the participant registered before.
+    pub fn register_session_key(
+        &mut self,
+        participant: &Pubkey,
+        delegate: Pubkey,
+        expires_at: i64,
+        max_operation_value: u64,
+        timestamp: i64,
+    ) -> Result<()> {
+        if !matches!(self.status, EnhancedChannelStatus::Active | EnhancedChannelStatus::Initializing) {
+            return Err(VaultError::SecurityViolation.into());
+        }
+        let index = self.participant_index(participant).ok_or(VaultError::UnauthorizedAccess)?;
+        // A participant's own key always signs for itself
+        if self.participants.iter().any(|p| p.pubkey == delegate) {
+            return Err(VaultError::InvalidSession.into());
+        }
+        if expires_at <= timestamp {
+            return Err(VaultError::InvalidSessionTimeout.into());
+        }
+        if max_operation_value == 0 {
+            return Err(VaultError::InvalidThresholdValue.into());
+        }
+
+        self.session_keys[index] = Some(SessionKey {
+            delegate,
+            expires_at,
+            max_operation_value,
+            first_nonce: self.balances[index].last_nonce + 1,
+            last_nonce: u64::MAX,
+        });
+        self.updated_at = timestamp;
+        Ok(())
+    }
+
+    /// Stop a participant's session key signing anything further. What it
+    /// signed up to now stays attributable to the participant.
+    pub fn revoke_session_key(&mut self, participant: &Pubkey, timestamp: i64) -> Result<()> {
+        let index = self.participants
+            .iter()
+            .position(|p| p.pubkey == *participant)
+            .ok_or(VaultError::UnauthorizedAccess)?;
+        let last_nonce = self.balances[index].last_nonce;
+        let key = self.session_keys[index]
+            .as_mut()
+            .filter(|key| !key.is_revoked())
+            .ok_or(VaultError::SessionNotFound)?;
+
+        key.last_nonce = last_nonce;
+        self.updated_at = timestamp;
+        Ok(())
+    }
+
+    /// Check `signer` may submit an HFT operation for its participant: the
+    /// participant itself, or their live session key within its value cap.
+    /// Delegated market orders must carry a price cap to be valued.
+    pub fn authorize_operation(&self, operation: &HFTOperation, signer: &Pubkey, timestamp: i64) -> Result<()> {
+        if *signer == operation.participant {
+            return Ok(());
+        }
+        let value = match operation.operation_type {
+            HFTOperationType::Cancel => 0,
+            _ if operation.price == 0 => return Err(VaultError::SessionKeyLimitExceeded.into()),
+            _ => Self::notional(operation.amount, operation.price)?,
+        };
+        self.check_session_key(&operation.participant, signer, value, timestamp)
+    }
+
+    /// Check `signer` may send a micro-transaction for its sender
+    pub fn authorize_transfer(&self, transaction: &MicroTransaction, signer: &Pubkey, timestamp: i64) -> Result<()> {
+        if *signer == transaction.from {
+            return Ok(());
+        }
+        self.check_session_key(&transaction.from, signer, transaction.amount, timestamp)
+    }
+
+    fn check_session_key(&self, participant: &Pubkey, signer: &Pubkey, value: u64, timestamp: i64) -> Result<()> {
+        let index = self.participant_index(participant).ok_or(VaultError::UnauthorizedAccess)?;
+        let key = self.session_keys[index]
+            .as_ref()
+            .filter(|key| key.delegate == *signer)
+            .ok_or(VaultError::UnauthorizedAccess)?;
+        if key.is_revoked() || timestamp >= key.expires_at {
+            return Err(VaultError::InvalidSession.into());
+        }
+        if value > key.max_operation_value {
+            return Err(VaultError::SessionKeyLimitExceeded.into());
+        }
+        Ok(())
+    }
+
+
+    /// Rebuild the netting matrix from the transfers a challenger submitted
+    /// and compare it with the channel's. Every transfer since the last
+    /// checkpoint must be included for the matrices to match.
+    pub fn verify_net_transfers(&self, transactions: &[MicroTransaction]) -> Result<bool> {
+        let n = self.participants.len();
+        let mut net = vec![[0i64; ChannelAsset::COUNT]; self.net_transfers.len()];
+        for transaction in transactions {
+            let index_of = |key: &Pubkey| self.participants.iter().position(|p| p.pubkey == *key);
+            let (from, to) = match (index_of(&transaction.from), index_of(&transaction.to)) {
+                (Some(from), Some(to)) if from != to => (from, to),
+                _ => return Ok(false),
+            };
+            let fee = self.micro_transaction_fee(transaction.amount)?;
+            Self::apply_net_transfer(&mut net, n, transaction.asset, from, to, transaction.amount, fee)?;
+        }
+        Ok(net == self.net_transfers)
+    }
+
+    /// Queue an operation that needs confirmations from its participants.
+    /// Expired entries are swept first so stale operations can't keep the
+    /// queue full.
+    pub fn add_pending_operation(
+        &mut self,
+        operation: PendingOperation,
+        proposer: Pubkey,
+        timestamp: i64,
+    ) -> Result<()> {
+        if self.status != EnhancedChannelStatus::Active {
+            return Err(VaultError::SecurityViolation.into());
+        }
+        self.evict_expired_operations(timestamp);
+        if self.pending_operations.len() >= Self::MAX_PENDING_OPERATIONS {
+            return Err(VaultError::InvalidAllocation.into());
+        }
+        if operation.data.len() > Self::MAX_OPERATION_DATA
+            || operation.participants.is_empty()
+            || !operation.participants.iter().all(|p| self.is_participant(p))
+        {
+            return Err(VaultError::InvalidAllocation.into());
+        }
+        if operation.required_confirmations < self.config.min_confirmations
+            || operation.required_confirmations as usize > operation.participants.len()
+        {
+            return Err(VaultError::InvalidThresholdValue.into());
+        }
+        if self.pending_operations.iter().any(|op| op.operation_id == operation.operation_id) {
+            return Err(VaultError::SecurityViolation.into());
+        }
+        // Expiry must fall within the channel timeout
+        let latest_expiry = timestamp
+            .checked_add(self.config.timeout)
+            .ok_or(VaultError::ArithmeticOverflow)?;
+        if operation.expires_at <= timestamp || operation.expires_at > latest_expiry {
+            return Err(VaultError::InvalidThresholdValue.into());
+        }
+        let proposer_index = self.participant_index(&proposer).ok_or(VaultError::UnauthorizedAccess)?;
+
+        let mut operation = operation;
+        operation.confirmations = Vec::new();
+        operation.proposer = proposer;
+        operation.timestamp = timestamp;
+        self.pending_operations.push(operation);
+        let quote = self.config.quote_asset;
+        if self.exposure(proposer_index, quote)? > self.config.max_exposure[proposer_index][quote.index()] as i128 {
+            return Err(VaultError::ExposureLimitExceeded.into());
+        }
+        self.updated_at = timestamp;
+        Ok(())
+    }
+
+    /// Drop every pending operation that expired unconfirmed, releasing its
+    /// reservation back to the proposer. Returns the evicted operations.
+    pub fn evict_expired_operations(&mut self, timestamp: i64) -> Vec<PendingOperation> {
+        let (expired, live): (Vec<_>, Vec<_>) = std::mem::take(&mut self.pending_operations)
+            .into_iter()
+            .partition(|op| timestamp >= op.expires_at);
+        self.pending_operations = live;
+        if !expired.is_empty() {
+            self.updated_at = timestamp;
+        }
+        expired
+    }
+
+    /// Confirm a pending operation. Once enough participants have confirmed
+    /// it is executed and removed from the queue; returns whether it was.
+    pub fn confirm_operation(
+        &mut self,
+        operation_id: u64,
+        participant: Pubkey,
+        signature: [u8; 64],
+        timestamp: i64,
+    ) -> Result<bool> {
+        if signature == [0; 64] {
+            return Err(VaultError::InvalidChannelSignature.into());
+        }
+        let position = self.pending_operations
+            .iter()
+            .position(|op| op.operation_id == operation_id)
+            .ok_or(VaultError::SecurityViolation)?;
+        let operation = &mut self.pending_operations[position];
+
+        if timestamp >= operation.expires_at {
+            return Err(VaultError::OperationExpired.into());
+        }
+        if !operation.participants.contains(&participant) {
+            return Err(VaultError::UnauthorizedAccess.into());
+        }
+        if operation.confirmations.iter().any(|c| c.participant == participant) {
+            return Err(VaultError::SecurityViolation.into());
+        }
+        operation.confirmations.push(OperationConfirmation { participant, timestamp });
+
+        // Executing releases the reservation along with the entry
+        let executed = operation.confirmations.len() >= operation.required_confirmations as usize;
+        if executed {
+            self.pending_operations.remove(position);
+            self.stats.record_operation(timestamp);
+        }
+        self.updated_at = timestamp;
+        Ok(executed)
+    }
+
+    /// Open a dispute, freezing normal operation until it is resolved
+    pub fn initiate_dispute(
+        &mut self,
+        challenger: Pubkey,
+        disputed_state: [u8; 32],
+        evidence: Vec<u8>,
+        dispute_type: DisputeType,
+        timestamp: i64,
+    ) -> Result<()> {
+        if self.status != EnhancedChannelStatus::Active {
+            return Err(VaultError::SecurityViolation.into());
+        }
+        if evidence.len() > Self::MAX_EVIDENCE_SIZE {
+            return Err(VaultError::InvalidAllocation.into());
+        }
+        if dispute_type != DisputeType::TimeoutViolation {
+            self.validate_dispute_evidence(dispute_type, &evidence)?;
+        }
+
+        let dispute_id = self.dispute_info.as_ref().map_or(1, |d| d.dispute_id + 1);
+        self.dispute_info = Some(DisputeInfo {
+            dispute_id,
+            challenger,
+            disputed_state,
+            evidence,
+            dispute_type,
+            status: DisputeStatus::Open,
+            challenge_deadline: timestamp
+                .checked_add(self.config.challenge_period)
+                .ok_or(VaultError::ArithmeticOverflow)?,
+            created_at: timestamp,
+        });
+        self.stats.record_dispute();
+        self.status = EnhancedChannelStatus::Disputed;
+        self.updated_at = timestamp;
+        Ok(())
+    }
+
+    /// Evidence must decode as the variant for its dispute type, and a
+    /// dispute over a specific operation must cite one the channel actually
+    /// sequenced: a known participant and a nonce no later than their last
+    pub fn validate_dispute_evidence(&self, dispute_type: DisputeType, evidence: &[u8]) -> Result<DisputeEvidence> {
+        let decoded = DisputeEvidence::decode(evidence)?;
+        if !decoded.matches(dispute_type) {
+            return Err(VaultError::InvalidDisputeEvidence.into());
+        }
+        if let Some(cited) = decoded.cited_operation() {
+            let index = self.participants
+                .iter()
+                .position(|p| p.pubkey == cited.participant)
+                .ok_or(VaultError::InvalidDisputeEvidence)?;
+            if cited.nonce == 0 || cited.nonce > self.balances[index].last_nonce {
+                return Err(VaultError::InvalidDisputeEvidence.into());
+            }
+        }
+        Ok(decoded)
+    }
+
+    /// Work out the resolution the open dispute's evidence supports. Any
+    /// evidence that is malformed or inconsistent with the dispute errors
+    /// instead of producing a verdict.
+    pub fn evaluate_dispute(&self, timestamp: i64) -> Result<DisputeVerdict> {
+        let dispute = self.dispute_info.as_ref().ok_or(VaultError::SecurityViolation)?;
+        if !matches!(dispute.status, DisputeStatus::Open | DisputeStatus::UnderReview) {
+            return Err(VaultError::SecurityViolation.into());
+        }
+        if dispute.dispute_type == DisputeType::TimeoutViolation {
+            return Ok(DisputeVerdict {
+                resolution_type: ResolutionType::SystemIntervention,
+                defender: None,
+                signature_checks: Vec::new(),
+            });
+        }
+
+        match self.validate_dispute_evidence(dispute.dispute_type, &dispute.evidence)? {
+            DisputeEvidence::SignedStatePair { earlier, later } => {
+                let next_version = earlier.version.checked_add(1).ok_or(VaultError::ArithmeticOverflow)?;
+                if later.version != next_version || later.state_root != dispute.disputed_state {
+                    return Err(VaultError::InvalidDisputeEvidence.into());
+                }
+                let mut signature_checks = self.state_signature_checks(&earlier)?;
+                signature_checks.extend(self.state_signature_checks(&later)?);
+
+                // Both states are fully signed, so a broken link is a fork
+                let resolution_type = if later.previous_root == earlier.state_root {
+                    ResolutionType::DefenderWins
+                } else {
+                    ResolutionType::ChallengerWins
+                };
+                Ok(DisputeVerdict { resolution_type, defender: None, signature_checks })
+            }
+            DisputeEvidence::ConflictingOperations { first, second } => {
+                if first.operation.participant != second.operation.participant {
+                    return Err(VaultError::InvalidDisputeEvidence.into());
+                }
+                let signature_checks = vec![
+                    self.operation_signature_check(&first)?,
+                    self.operation_signature_check(&second)?,
+                ];
+
+                let conflicting = first.operation.nonce == second.operation.nonce
+                    && first.operation != second.operation;
+                let resolution_type = if conflicting {
+                    ResolutionType::ChallengerWins
+                } else {
+                    ResolutionType::DefenderWins
+                };
+                Ok(DisputeVerdict {
+                    resolution_type,
+                    defender: Some(first.operation.participant),
+                    signature_checks,
+                })
+            }
+            DisputeEvidence::SignatureProof { operation } => {
+                // Authorization has to be proven within the challenge period
+                let defender = Some(operation.operation.participant);
+                if timestamp < dispute.challenge_deadline {
+                    Ok(DisputeVerdict {
+                        resolution_type: ResolutionType::DefenderWins,
+                        defender,
+                        signature_checks: vec![self.operation_signature_check(&operation)?],
+                    })
+                } else {
+                    Ok(DisputeVerdict {
+                        resolution_type: ResolutionType::ChallengerWins,
+                        defender,
+                        signature_checks: Vec::new(),
+                    })
+                }
+            }
+            DisputeEvidence::BalanceSnapshot { state, leaves, proofs, transfers } => {
+                let n = self.participants.len();
+                if state.state_root != dispute.disputed_state || leaves.is_empty() || leaves.len() != proofs.len() {
+                    return Err(VaultError::InvalidDisputeEvidence.into());
+                }
+                for (position, (leaf, proof)) in leaves.iter().zip(&proofs).enumerate() {
+                    let index = leaf.index as usize;
+                    if index >= n || leaves[..position].iter().any(|l| l.index == leaf.index) {
+                        return Err(VaultError::InvalidDisputeEvidence.into());
+                    }
+                    let hash = Self::balance_leaf_hash(&self.participants[index].pubkey, &leaf.assets);
+                    if !Self::verify_balance_proof(&state.state_root, n, index, hash, proof) {
+                        return Err(VaultError::InvalidDisputeEvidence.into());
+                    }
+                }
+                // An incomplete transfer list can't show anything either way
+                if !self.verify_net_transfers(&transfers)? {
+                    return Err(VaultError::InvalidDisputeEvidence.into());
+                }
+
+                let consistent = leaves.iter().all(|leaf| self.balances[leaf.index as usize].assets == leaf.assets);
+                let resolution_type = if consistent {
+                    ResolutionType::DefenderWins
+                } else {
+                    ResolutionType::ChallengerWins
+                };
+                Ok(DisputeVerdict {
+                    resolution_type,
+                    defender: None,
+                    signature_checks: self.state_signature_checks(&state)?,
+                })
+            }
+        }
+    }
+
+    /// One check per participant, in participant order. A session key's
+    /// signature binds its participant to the state even once revoked, as
+    /// every other participant has to co-sign it too.
+    fn state_signature_checks(&self, state: &SignedChannelState) -> Result<Vec<SignatureCheck>> {
+        let n = self.participants.len();
+        if state.signatures.len() != n || !(state.delegated.is_empty() || state.delegated.len() == n) {
+            return Err(VaultError::InvalidDisputeEvidence.into());
+        }
+        let commitment = hashv(&[&state.previous_root, &state.state_root]).to_bytes();
+        let message = Ed25519Verifier::state_update_message(&self.channel_id, state.version, &commitment);
+
+        let mut checks = Vec::with_capacity(n);
+        for (index, signature) in state.signatures.iter().enumerate() {
+            let signer = match (state.delegated.get(index), &self.session_keys[index]) {
+                (Some(true), Some(key)) => key.delegate,
+                (Some(true), None) => return Err(VaultError::InvalidDisputeEvidence.into()),
+                _ => self.participants[index].pubkey,
+            };
+            checks.push(SignatureCheck { signer, signature: signature.clone(), message: message.clone() });
+        }
+        Ok(checks)
+    }
+
+    fn operation_signature_check(&self, signed: &SignedOperation) -> Result<SignatureCheck> {
+        let index = self.participants
+            .iter()
+            .position(|p| p.pubkey == signed.operation.participant)
+            .ok_or(VaultError::InvalidDisputeEvidence)?;
+        // Delegated signatures bind only within the nonces the key was live for
+        let signer = match &signed.delegate {
+            None => signed.operation.participant,
+            Some(delegate) => {
+                let key = self.session_keys[index]
+                    .as_ref()
+                    .filter(|key| key.delegate == *delegate)
+                    .ok_or(VaultError::InvalidDisputeEvidence)?;
+                let nonce = signed.operation.nonce;
+                if nonce < key.first_nonce || nonce > key.last_nonce {
+                    return Err(VaultError::InvalidDisputeEvidence.into());
+                }
+                key.delegate
+            }
+        };
+        let operation = signed.operation.try_to_vec().map_err(|_| VaultError::InvalidDisputeEvidence)?;
+        Ok(SignatureCheck {
+            signer,
+            signature: signed.signature.clone(),
+            message: Ed25519Verifier::hft_operation_message(&self.channel_id, &operation),
+        })
+    }
+
+    /// Merkle root over participant balances, leaves in participant order
+    pub fn balance_root(&self) -> [u8; 32] {
+        StateChannel::merkle_root(
+            self.participants
+                .iter()
+                .zip(&self.balances)
+                .map(|(participant, account)| Self::balance_leaf_hash(&participant.pubkey, &account.assets))
+                .collect(),
+        )
+    }
+
+    /// Check a balance leaf against a root. The proof holds one sibling per
+    /// level where the node has one, bottom-up.
+    pub fn verify_balance_proof(
+        root: &[u8; 32],
+        leaf_count: usize,
+        index: usize,
+        leaf: [u8; 32],
+        proof: &[[u8; 32]],
+    ) -> bool {
+        StateChannel::verify_merkle_proof(root, leaf_count, index, leaf, proof)
+    }
+
+    pub fn balance_leaf_hash(participant: &Pubkey, assets: &[i64; ChannelAsset::COUNT]) -> [u8; 32] {
+        let encoded: Vec<u8> = assets.iter().flat_map(|balance| balance.to_le_bytes()).collect();
+        hashv(&[&[0u8], participant.as_ref(), &encoded]).to_bytes()
+    }
+
+    /// Apply a dispute penalty against the loser's SOL collateral. The
+    /// penalty is held to the slashing bounds; what the collateral covers is
+    /// split between the insurance fund and the winner (all to the fund
+    /// without one), and the loser's SOL exposure limit shrinks with their
+    /// collateral. Any shortfall is recorded as bad debt.
+    pub fn apply_slash(
+        &mut self,
+        loser: &Pubkey,
+        has_winner: bool,
+        penalty: u64,
+        collateral: u64,
+        insurance_cut_bps: u16,
+        timestamp: i64,
+    ) -> Result<SlashOutcome> {
+        let index = self.participants
+            .iter()
+            .position(|p| p.pubkey == *loser)
+            .ok_or(VaultError::UnauthorizedAccess)?;
+        let dispute_id = self.dispute_info.as_ref().map_or(0, |d| d.dispute_id);
+
+        let penalty = self.config.security_params.slashing_config.bound(penalty);
+        let paid = penalty.min(collateral);
+        let bad_debt = penalty - paid;
+        let insurance_cut = if has_winner {
+            Self::notional_fee(paid, insurance_cut_bps)
+        } else {
+            paid
+        };
+
+        let limit = &mut self.config.max_exposure[index][ChannelAsset::Sol.index()];
+        *limit = limit.saturating_sub(paid);
+        if bad_debt > 0 {
+            match self.bad_debts.iter_mut().find(|debt| debt.participant == *loser) {
+                Some(debt) => {
+                    debt.amount = debt.amount.checked_add(bad_debt).ok_or(VaultError::ArithmeticOverflow)?;
+                    debt.dispute_id = dispute_id;
+                    debt.recorded_at = timestamp;
+                }
+                None => self.bad_debts.push(BadDebt {
+                    participant: *loser,
+                    amount: bad_debt,
+                    dispute_id,
+                    recorded_at: timestamp,
+                }),
+            }
+        }
+        self.updated_at = timestamp;
+
+        Ok(SlashOutcome {
+            penalty,
+            paid,
+            insurance_cut,
+            winner_share: paid - insurance_cut,
+            bad_debt,
+        })
+    }
+
+    /// Close the open dispute with the resolution its evidence produced and
+    /// return the channel to normal operation
+    pub fn resolve_dispute(
+        &mut self,
+        resolution: DisputeResolution,
+        resolver: Pubkey,
+        timestamp: i64,
+    ) -> Result<()> {
+        let dispute = self.dispute_info.as_mut().ok_or(VaultError::SecurityViolation)?;
+        if !matches!(dispute.status, DisputeStatus::Open | DisputeStatus::UnderReview) {
+            return Err(VaultError::SecurityViolation.into());
+        }
+
+        dispute.status = match resolution.resolution_type {
+            ResolutionType::Dismissed => DisputeStatus::Rejected,
+            _ => DisputeStatus::Resolved,
+        };
+        self.status = EnhancedChannelStatus::Active;
+        self.updated_at = timestamp;
+
+        msg!("Dispute {} resolved by {} as {:?}", dispute.dispute_id, resolver, resolution.resolution_type);
+        Ok(())
+    }
+
+    /// Stop trading once nothing is left pending: PnL is realized at the
+    /// mark, balances are made final and the channel waits for settlement.
+    /// Returns the PnL realized.
+    pub fn close_channel(&mut self, timestamp: i64) -> Result<Vec<RealizedPnl>> {
+        if !matches!(self.status, EnhancedChannelStatus::Active | EnhancedChannelStatus::Disputed) {
+            return Err(VaultError::SecurityViolation.into());
+        }
+        if !self.pending_operations.is_empty() {
+            return Err(VaultError::SecurityViolation.into());
+        }
+
+        // Resting orders can no longer fill
+        self.order_book = OrderBook::default();
+        self.trigger_orders = Vec::new();
+        let realized = self.realize_pnl(timestamp)?;
+        self.checkpoint_net_transfers(timestamp)?;
+        self.status = EnhancedChannelStatus::Settling;
+        self.updated_at = timestamp;
+        Ok(realized)
+    }
+
+    /// Each participant's final claim on a vaulted asset given their
+    /// deposits: the deposit plus their in-channel balance, floored at zero.
+    /// Virtual BTC has no vault; positions are closed out into the quote
+    /// asset at the cost they were traded at, since price moves since then
+    /// were realized as PnL. One more entry is appended for the fees the
+    /// treasury is owed.
+    pub fn settlement_entitlements(&self, asset: ChannelAsset, deposits: &[u64]) -> Result<Vec<u64>> {
+        if self.status != EnhancedChannelStatus::Settling {
+            return Err(VaultError::SecurityViolation.into());
+        }
+        if asset == ChannelAsset::VirtualBtc {
+            return Err(VaultError::AssetMismatch.into());
+        }
+        if deposits.len() != self.participants.len() {
+            return Err(VaultError::InvalidAllocation.into());
+        }
+
+        let mut entitlements = Vec::with_capacity(deposits.len() + 1);
+        for (account, deposit) in self.balances.iter().zip(deposits) {
+            let mut claim = *deposit as i128 + account.balance(asset) as i128;
+            if asset == self.config.quote_asset {
+                claim += account.cost_basis as i128 - account.realized_pnl as i128;
+            }
+            entitlements.push(u64::try_from(claim.max(0)).map_err(|_| VaultError::ArithmeticOverflow)?);
+        }
+        entitlements.push(self.collected_fees[asset.index()]);
+        Ok(entitlements)
+    }
+
+    /// Mark collateral as reallocated; participants may now withdraw it
+    pub fn complete_settlement(&mut self, timestamp: i64) -> Result<()> {
+        if self.status != EnhancedChannelStatus::Settling {
+            return Err(VaultError::SecurityViolation.into());
+        }
+        self.status = EnhancedChannelStatus::Closed;
+        self.updated_at = timestamp;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> ChannelConfig {
+        ChannelConfig {
+            channel_type: ChannelType::Trading,
+            timeout: 86400,
+            dispute_period: 3600,
+            challenge_period: 1800,
+            min_confirmations: 1,
+            max_batch_size: 100,
+            max_ops_per_second: 100,
+            fee_config: FeeConfig {
+                base_fee: 0,
+                transfer_fee_rate: 10,
+                trade_fee_rate: 30,
+                dispute_fee: 100_000,
+            },
+            security_params: SecurityParams {
+                max_operation_value: u64::MAX,
+                rate_limit: 100,
+                fraud_detection: true,
+                slashing_config: SlashingConfig { min_slash_amount: 1_000, max_slash_amount: 1_000_000 },
+            },
+            quote_asset: ChannelAsset::Sol,
+            usdc_mint: Pubkey::default(),
+            max_exposure: Vec::new(),
+        }
+    }
+
+    fn participant(pubkey: Pubkey) -> ChannelParticipant {
+        ChannelParticipant {
+            pubkey,
+            role: ParticipantRole::FullParticipant,
+            weight: 1,
+            is_active: true,
+            last_activity: 0,
+        }
+    }
+
+    fn active_channel(count: usize) -> (EnhancedStateChannel, Vec<Pubkey>) {
+        limited_channel(vec![u64::MAX; count])
+    }
+
+    fn limited_channel(max_exposure: Vec<u64>) -> (EnhancedStateChannel, Vec<Pubkey>) {
+        let count = max_exposure.len();
+        let keys: Vec<Pubkey> = (0..count).map(|_| Pubkey::new_unique()).collect();
+        let mut channel = EnhancedStateChannel {
+            version: 0,
+            channel_id: [0; 32],
+            participants: Vec::new(),
+            balances: Vec::new(),
+            net_transfers: Vec::new(),
+            state_root: [0; 32],
+            nonce: 0,
+            config: config(),
+            status: EnhancedChannelStatus::Initializing,
+            pending_operations: Vec::new(),
+            dispute_info: None,
+            order_book: OrderBook::default(),
+            bad_debts: Vec::new(),
+            trigger_orders: Vec::new(),
+            session_keys: Vec::new(),
+            mark_price: 0,
+            collected_fees: [0; ChannelAsset::COUNT],
+            stats: ChannelStats::default(),
+            created_at: 0,
+            updated_at: 0,
+            bump: 0,
+        };
+        // Limits apply to SOL; the other assets are left unbounded
+        let max_exposure = max_exposure.into_iter().map(|limit| [limit, u64::MAX, u64::MAX]).collect();
+        let config = ChannelConfig { max_exposure, ..config() };
+        channel.initialize([7; 32], keys.iter().cloned().map(participant).collect(), config, 255, 0).unwrap();
+        channel.activate(0).unwrap();
+        (channel, keys)
+    }
+
+    fn order(id: u64, participant: Pubkey, operation_type: HFTOperationType, amount: u64, price: u64) -> HFTOperation {
+        HFTOperation { id, operation_type, asset: ChannelAsset::Sol, amount, price, participant, nonce: 0, timestamp: 0 }
+    }
+
+    /// Process an operation under the participant's next nonce
+    fn submit(
+        channel: &mut EnhancedStateChannel,
+        mut operation: HFTOperation,
+        participant: Pubkey,
+        timestamp: i64,
+    ) -> Result<Vec<TradeFill>> {
+        let index = channel.participant_index(&participant).unwrap();
+        operation.nonce = channel.balances[index].last_nonce + 1;
+        channel.process_hft_operation(operation, participant, timestamp)
+    }
+
+    const PRICE: u64 = EnhancedStateChannel::PRICE_PRECISION;
+
+    #[test]
+    fn test_limit_orders_rest_in_price_time_priority() {
+        let (mut channel, keys) = active_channel(2);
+        let (a, b) = (keys[0], keys[1]);
+
+        submit(&mut channel, order(1, a, HFTOperationType::LimitBuy, 10, 100 * PRICE), a, 1).unwrap();
+        submit(&mut channel, order(2, b, HFTOperationType::LimitBuy, 10, 101 * PRICE), b, 2).unwrap();
+        submit(&mut channel, order(3, b, HFTOperationType::LimitBuy, 10, 100 * PRICE), b, 3).unwrap();
+        let bid_ids: Vec<u64> = channel.order_book.bids.iter().map(|o| o.order_id).collect();
+        assert_eq!(bid_ids, vec![2, 1, 3]);
+
+        submit(&mut channel, order(4, a, HFTOperationType::LimitSell, 10, 105 * PRICE), a, 4).unwrap();
+        submit(&mut channel, order(5, b, HFTOperationType::LimitSell, 10, 103 * PRICE), b, 5).unwrap();
+        let ask_ids: Vec<u64> = channel.order_book.asks.iter().map(|o| o.order_id).collect();
+        assert_eq!(ask_ids, vec![5, 4]);
+    }
+
+    #[test]
+    fn test_incoming_order_partially_fills_against_best_prices() {
+        let (mut channel, keys) = active_channel(2);
+        let (maker, taker) = (keys[0], keys[1]);
+
+        submit(&mut channel, order(1, maker, HFTOperationType::LimitSell, 5, 100 * PRICE), maker, 1).unwrap();
+        submit(&mut channel, order(2, maker, HFTOperationType::LimitSell, 5, 100 * PRICE), maker, 2).unwrap();
+        submit(&mut channel, order(3, maker, HFTOperationType::LimitSell, 5, 102 * PRICE), maker, 3).unwrap();
+
+        // Crosses both orders at 100 (oldest first) but not the one at 102
+        let fills = submit(&mut channel, order(9, taker, HFTOperationType::LimitBuy, 12, 101 * PRICE), taker, 4).unwrap();
+        assert_eq!(fills.iter().map(|f| (f.maker_order_id, f.amount)).collect::<Vec<_>>(), vec![(1, 5), (2, 5)]);
+        assert!(fills.iter().all(|f| f.price == 100 * PRICE && f.taker_is_buyer));
+
+        // The unfilled 2 rest as the best bid; the 102 ask is untouched
+        assert_eq!(channel.order_book.bids, vec![RestingOrder { order_id: 9, owner: 1, price: 101 * PRICE, remaining: 2 }]);
+        assert_eq!(channel.order_book.asks.len(), 1);
+
+        // Market sell partially fills the resting bid
+        let fills = submit(&mut channel, order(4, maker, HFTOperationType::MarketSell, 1, 0), maker, 5).unwrap();
+        assert_eq!(fills[0].price, 101 * PRICE);
+        assert_eq!(channel.order_book.bids[0].remaining, 1);
+    }
+
+    #[test]
+    fn test_fills_update_balances_and_charge_taker_fee() {
+        let (mut channel, keys) = active_channel(2);
+        let (maker, taker) = (keys[0], keys[1]);
+
+        submit(&mut channel, order(1, maker, HFTOperationType::LimitSell, 10, 100 * PRICE), maker, 1).unwrap();
+        let fills = submit(&mut channel, order(2, taker, HFTOperationType::MarketBuy, 10, 0), taker, 2).unwrap();
+
+        // 10 units at 100 = 1000 notional, 30 bps taker fee = 3
+        assert_eq!(fills[0].fee, 3);
+        assert_eq!(channel.balances[1], ParticipantBalance { assets: [-1_003, 0, 10], cost_basis: 1_000, realized_pnl: 0, last_updated: 2, last_nonce: 1, rate_window: 2, window_ops: 1 });
+        assert_eq!(channel.balances[0], ParticipantBalance { assets: [1_000, 0, -10], cost_basis: -1_000, realized_pnl: 0, last_updated: 2, last_nonce: 1, rate_window: 1, window_ops: 1 });
+        assert_eq!(channel.collected_fees, [3, 0, 0]);
+        assert_eq!(channel.stats.total_volume, [1_000, 0, 10]);
+
+        // Nothing left to buy: a market order is dropped, not rested
+        assert!(submit(&mut channel, order(3, taker, HFTOperationType::MarketBuy, 10, 0), taker, 3).unwrap().is_empty());
+        assert!(channel.order_book.bids.is_empty());
+    }
+
+    #[test]
+    fn test_cancel_only_removes_own_orders() {
+        let (mut channel, keys) = active_channel(2);
+        let (a, b) = (keys[0], keys[1]);
+
+        submit(&mut channel, order(1, a, HFTOperationType::LimitBuy, 10, 100 * PRICE), a, 1).unwrap();
+        assert!(submit(&mut channel, order(1, b, HFTOperationType::Cancel, 0, 0), b, 2).is_err());
+        submit(&mut channel, order(1, a, HFTOperationType::Cancel, 0, 0), a, 3).unwrap();
+        assert!(channel.order_book.bids.is_empty());
+    }
+
+    #[test]
+    fn test_full_book_serializes_within_account_size() {
+        let (mut channel, keys) = active_channel(EnhancedStateChannel::MAX_PARTICIPANTS);
+        let owner = keys[0];
+
+        for i in 0..EnhancedStateChannel::MAX_ORDERS_PER_SIDE as u64 {
+            submit(&mut channel, order(i, owner, HFTOperationType::LimitBuy, 1, (i + 1) * PRICE), owner, 1).unwrap();
+            submit(&mut channel, order(1_000 + i, owner, HFTOperationType::LimitSell, 1, (1_000 + i) * PRICE), owner, 1).unwrap();
+        }
+        let overflow = order(2_000, owner, HFTOperationType::LimitBuy, 1, PRICE);
+        assert!(submit(&mut channel, overflow, owner, 1).is_err());
+
+        for i in 0..EnhancedStateChannel::MAX_TRIGGER_ORDERS as u64 {
+            let stop = HFTOperationType::StopLoss { trigger_price: PRICE / 2, side: OrderSide::Sell };
+            submit(&mut channel, order(3_000 + i, owner, stop, 1, 0), owner, 1).unwrap();
+        }
+
+        for id in 0..EnhancedStateChannel::MAX_PENDING_OPERATIONS as u64 {
+            channel.add_pending_operation(PendingOperation {
+                operation_id: id,
+                operation_type: OperationType::Transfer,
+                participants: keys.clone(),
+                data: vec![1; EnhancedStateChannel::MAX_OPERATION_DATA],
+                required_confirmations: keys.len() as u8,
+                confirmations: Vec::new(),
+                proposer: Pubkey::default(),
+                reserved_amount: 0,
+                timestamp: 0,
+                expires_at: 100,
+            }, owner, 1).unwrap();
+            for key in &keys[..keys.len() - 1] {
+                channel.confirm_operation(id, *key, [1; 64], 1).unwrap();
+            }
+        }
+        for key in &keys {
+            channel.apply_slash(key, true, 1_000, 0, 0, 1).unwrap();
+        }
+        channel.initiate_dispute(owner, [1; 32], vec![1; EnhancedStateChannel::MAX_EVIDENCE_SIZE], DisputeType::TimeoutViolation, 1).unwrap();
+
+        let serialized = channel.try_to_vec().unwrap();
+        assert!(8 + serialized.len() <= EnhancedStateChannel::SIZE);
+        assert!(EnhancedStateChannel::SIZE <= 10 * 1024);
+    }
+
+    fn stop(side: OrderSide, trigger_price: u64) -> HFTOperationType {
+        HFTOperationType::StopLoss { trigger_price, side }
+    }
+
+    fn take_profit(side: OrderSide, trigger_price: u64) -> HFTOperationType {
+        HFTOperationType::TakeProfit { trigger_price, side }
+    }
+
+    #[test]
+    fn test_trigger_orders_reject_conflicting_limits() {
+        let (mut channel, keys) = active_channel(1);
+        let a = keys[0];
+
+        // Sell stop at 90 with a 95 floor could never fill once 90 trades
+        let conflicting = order(1, a, stop(OrderSide::Sell, 90 * PRICE), 1, 95 * PRICE);
+        assert!(submit(&mut channel, conflicting, a, 1).is_err());
+        let conflicting = order(2, a, take_profit(OrderSide::Buy, 90 * PRICE), 1, 85 * PRICE);
+        assert!(submit(&mut channel, conflicting, a, 1).is_err());
+
+        submit(&mut channel, order(3, a, stop(OrderSide::Sell, 90 * PRICE), 1, 88 * PRICE), a, 1).unwrap();
+        submit(&mut channel, order(4, a, take_profit(OrderSide::Sell, 110 * PRICE), 1, 0), a, 1).unwrap();
+        assert_eq!(channel.trigger_orders.len(), 2);
+
+        // Cancel reaches trigger orders too
+        submit(&mut channel, order(4, a, HFTOperationType::Cancel, 0, 0), a, 2).unwrap();
+        assert_eq!(channel.trigger_orders.len(), 1);
+    }
+
+    #[test]
+    fn test_oracle_crank_fires_triggers_without_mark_price() {
+        let (mut channel, keys) = active_channel(2);
+        let (trader, maker) = (keys[0], keys[1]);
+
+        submit(&mut channel, order(1, maker, HFTOperationType::LimitBuy, 5, 89 * PRICE), maker, 1).unwrap();
+        submit(&mut channel, order(2, trader, stop(OrderSide::Sell, 90 * PRICE), 5, 0), trader, 1).unwrap();
+        assert_eq!(channel.mark_price, 0);
+
+        assert!(channel.check_triggers(95 * PRICE, 2).unwrap().is_empty());
+        let fills = channel.check_triggers(90 * PRICE, 3).unwrap();
+        assert_eq!(fills.len(), 1);
+        assert_eq!((fills[0].taker_order_id, fills[0].amount, fills[0].price), (2, 5, 89 * PRICE));
+        assert!(channel.trigger_orders.is_empty());
+        assert_eq!(channel.balances[0].position(), -5);
+    }
+
+    #[test]
+    fn test_mark_price_takes_precedence_and_triggers_cascade() {
+        let (mut channel, keys) = active_channel(3);
+        let (a, b, c) = (keys[0], keys[1], keys[2]);
+
+        // Resting bids for the stops to sell into
+        submit(&mut channel, order(1, c, HFTOperationType::LimitBuy, 1, 95 * PRICE), c, 1).unwrap();
+        submit(&mut channel, order(2, c, HFTOperationType::LimitBuy, 1, 90 * PRICE), c, 1).unwrap();
+        submit(&mut channel, order(3, c, HFTOperationType::LimitBuy, 1, 80 * PRICE), c, 1).unwrap();
+
+        submit(&mut channel, order(10, a, stop(OrderSide::Sell, 95 * PRICE), 1, 0), a, 1).unwrap();
+        submit(&mut channel, order(11, b, stop(OrderSide::Sell, 90 * PRICE), 1, 0), b, 1).unwrap();
+
+        // A trade at 100 sets the mark; a far lower oracle price is ignored
+        submit(&mut channel, order(4, c, HFTOperationType::LimitSell, 1, 100 * PRICE), c, 2).unwrap();
+        submit(&mut channel, order(5, a, HFTOperationType::MarketBuy, 1, 0), a, 2).unwrap();
+        assert_eq!(channel.mark_price, 100 * PRICE);
+        assert!(channel.check_triggers(50 * PRICE, 3).unwrap().is_empty());
+
+        // Selling into the 95 bid fires a's stop, whose fill at 90 fires b's
+        let fills = submit(&mut channel, order(6, c, HFTOperationType::MarketSell, 1, 0), c, 4).unwrap();
+        let filled: Vec<u64> = fills.iter().map(|f| f.taker_order_id).collect();
+        assert_eq!(filled, vec![6, 10, 11]);
+        assert_eq!(channel.mark_price, 80 * PRICE);
+        assert!(channel.trigger_orders.is_empty());
+    }
+
+    #[test]
+    fn test_operation_nonces_reject_replays_and_gaps() {
+        let (mut channel, keys) = active_channel(2);
+        let (a, b) = (keys[0], keys[1]);
+
+        let mut first = order(1, a, HFTOperationType::LimitBuy, 1, 100 * PRICE);
+        first.nonce = 1;
+        channel.process_hft_operation(first.clone(), a, 1).unwrap();
+        assert_eq!(channel.balances[0].last_nonce, 1);
+
+        // Same operation again, even under a fresh id
+        let err = channel.process_hft_operation(first.clone(), a, 2).unwrap_err();
+        assert_eq!(err, VaultError::OperationReplayed.into());
+        first.id = 2;
+        assert_eq!(channel.process_hft_operation(first.clone(), a, 2).unwrap_err(), VaultError::OperationReplayed.into());
+
+        first.nonce = 3;
+        assert_eq!(channel.process_hft_operation(first, a, 2).unwrap_err(), VaultError::InvalidOperationNonce.into());
+
+        // Nonces are tracked per participant
+        let mut other = order(3, b, HFTOperationType::LimitBuy, 1, 100 * PRICE);
+        other.nonce = 1;
+        channel.process_hft_operation(other, b, 3).unwrap();
+    }
+
+    #[test]
+    fn test_rate_limit_per_second_window() {
+        let (mut channel, keys) = active_channel(2);
+        let (a, b) = (keys[0], keys[1]);
+        channel.config.max_ops_per_second = 3;
+
+        for id in 1..=3 {
+            submit(&mut channel, order(id, a, HFTOperationType::LimitBuy, 1, PRICE), a, 10).unwrap();
+        }
+        assert_eq!(channel.balances[0].window_ops, 3);
+
+        // One over the limit fails without consuming the nonce
+        let err = submit(&mut channel.clone(), order(4, a, HFTOperationType::LimitBuy, 1, PRICE), a, 10).unwrap_err();
+        assert_eq!(err, VaultError::RateLimitExceeded.into());
+        assert_eq!(channel.balances[0].last_nonce, 3);
+
+        // Other participants have their own window
+        submit(&mut channel, order(5, b, HFTOperationType::LimitBuy, 1, PRICE), b, 10).unwrap();
+
+        // The next second opens a fresh window, continuing the nonce sequence
+        submit(&mut channel, order(4, a, HFTOperationType::LimitBuy, 1, PRICE), a, 11).unwrap();
+        assert_eq!(channel.balances[0].last_nonce, 4);
+        assert_eq!((channel.balances[0].rate_window, channel.balances[0].window_ops), (11, 1));
+
+        // A batch counts every contained operation
+        assert!(channel.check_rate_limit(0, 2, 11).is_ok());
+        assert_eq!(channel.check_rate_limit(0, 3, 11).unwrap_err(), VaultError::RateLimitExceeded.into());
+        assert!(channel.check_rate_limit(0, 3, 12).is_ok());
+    }
+
+    #[test]
+    fn test_batch_nonces_must_strictly_increase() {
+        let (mut channel, keys) = active_channel(1);
+        let a = keys[0];
+        submit(&mut channel, order(1, a, HFTOperationType::LimitBuy, 1, 100 * PRICE), a, 1).unwrap();
+
+        let batch = |nonces: &[u64]| -> Vec<HFTOperation> {
+            nonces.iter().map(|&nonce| HFTOperation { nonce, ..order(nonce, a, HFTOperationType::LimitBuy, 1, PRICE) }).collect()
+        };
+        assert!(channel.check_batch_nonces(&a, &batch(&[2, 3, 4])).is_ok());
+        assert_eq!(channel.check_batch_nonces(&a, &batch(&[2, 2])).unwrap_err(), VaultError::OperationReplayed.into());
+        assert_eq!(channel.check_batch_nonces(&a, &batch(&[3, 2])).unwrap_err(), VaultError::InvalidOperationNonce.into());
+        assert_eq!(channel.check_batch_nonces(&a, &batch(&[1, 2])).unwrap_err(), VaultError::OperationReplayed.into());
+    }
+
+    fn signed(operation: HFTOperation) -> SignedOperation {
+        SignedOperation { operation, signature: vec![1; 64], delegate: None }
+    }
+
+    fn sequenced(id: u64, participant: Pubkey, nonce: u64) -> HFTOperation {
+        HFTOperation { nonce, ..order(id, participant, HFTOperationType::LimitBuy, 1, 100 * PRICE) }
+    }
+
+    fn conflicting(first: HFTOperation, second: HFTOperation) -> Vec<u8> {
+        DisputeEvidence::ConflictingOperations { first: signed(first), second: signed(second) }.try_to_vec().unwrap()
+    }
+
+    #[test]
+    fn test_dispute_evidence_must_cite_sequenced_nonce() {
+        let (mut channel, keys) = active_channel(2);
+        let (a, b) = (keys[0], keys[1]);
+        submit(&mut channel, order(1, a, HFTOperationType::LimitBuy, 1, 100 * PRICE), a, 1).unwrap();
+
+        for (participant, nonce) in [(a, 2), (a, 0), (Pubkey::new_unique(), 1)] {
+            let evidence = conflicting(sequenced(1, participant, nonce), sequenced(2, participant, nonce));
+            let result = channel.initiate_dispute(b, [0; 32], evidence, DisputeType::DoubleSpending, 2);
+            assert_eq!(result.unwrap_err(), VaultError::InvalidDisputeEvidence.into());
+        }
+        assert!(channel.initiate_dispute(b, [0; 32], vec![1, 2, 3], DisputeType::UnauthorizedOperation, 2).is_err());
+
+        let evidence = conflicting(sequenced(1, a, 1), sequenced(2, a, 1));
+        channel.initiate_dispute(b, [0; 32], evidence, DisputeType::DoubleSpending, 2).unwrap();
+        assert_eq!(channel.status, EnhancedChannelStatus::Disputed);
+    }
+
+    #[test]
+    fn test_malformed_evidence_is_rejected_not_defaulted() {
+        let (mut channel, keys) = active_channel(2);
+        let (a, b) = (keys[0], keys[1]);
+        submit(&mut channel, order(1, a, HFTOperationType::LimitBuy, 1, 100 * PRICE), a, 1).unwrap();
+
+        // Wrong variant for the dispute type, and trailing bytes
+        let evidence = conflicting(sequenced(1, a, 1), sequenced(2, a, 1));
+        assert!(channel.clone().initiate_dispute(b, [0; 32], evidence.clone(), DisputeType::UnauthorizedOperation, 2).is_err());
+        let mut padded = evidence.clone();
+        padded.push(0);
+        assert!(channel.clone().initiate_dispute(b, [0; 32], padded, DisputeType::DoubleSpending, 2).is_err());
+
+        // Operations from different participants prove nothing
+        submit(&mut channel, order(2, b, HFTOperationType::LimitBuy, 1, 100 * PRICE), b, 1).unwrap();
+        let evidence = conflicting(sequenced(1, a, 1), sequenced(2, b, 1));
+        channel.initiate_dispute(b, [0; 32], evidence, DisputeType::DoubleSpending, 2).unwrap();
+        assert_eq!(channel.evaluate_dispute(3).unwrap_err(), VaultError::InvalidDisputeEvidence.into());
+    }
+
+    #[test]
+    fn test_conflicting_operations_under_one_nonce() {
+        let (mut channel, keys) = active_channel(2);
+        let (a, b) = (keys[0], keys[1]);
+        submit(&mut channel, order(1, a, HFTOperationType::LimitBuy, 1, 100 * PRICE), a, 1).unwrap();
+        submit(&mut channel, order(2, a, HFTOperationType::LimitBuy, 1, 100 * PRICE), a, 1).unwrap();
+
+        let mut double_spent = channel.clone();
+        double_spent.initiate_dispute(b, [0; 32], conflicting(sequenced(1, a, 1), sequenced(9, a, 1)), DisputeType::DoubleSpending, 2).unwrap();
+        let verdict = double_spent.evaluate_dispute(3).unwrap();
+        assert_eq!(verdict.resolution_type, ResolutionType::ChallengerWins);
+        assert_eq!(verdict.defender, Some(a));
+        assert_eq!(verdict.signature_checks.len(), 2);
+        assert!(verdict.signature_checks.iter().all(|check| check.signer == a));
+        assert_ne!(verdict.signature_checks[0].message, verdict.signature_checks[1].message);
+
+        for (first, second) in [(sequenced(1, a, 1), sequenced(1, a, 1)), (sequenced(1, a, 1), sequenced(2, a, 2))] {
+            let mut honest = channel.clone();
+            honest.initiate_dispute(b, [0; 32], conflicting(first, second), DisputeType::DoubleSpending, 2).unwrap();
+            assert_eq!(honest.evaluate_dispute(3).unwrap().resolution_type, ResolutionType::DefenderWins);
+        }
+    }
+
+    #[test]
+    fn test_authorization_must_be_proven_before_deadline() {
+        let (mut channel, keys) = active_channel(2);
+        let (a, b) = (keys[0], keys[1]);
+        submit(&mut channel, order(1, a, HFTOperationType::LimitBuy, 1, 100 * PRICE), a, 1).unwrap();
+
+        let evidence = DisputeEvidence::SignatureProof { operation: signed(sequenced(1, a, 1)) };
+        channel.initiate_dispute(b, [0; 32], evidence.try_to_vec().unwrap(), DisputeType::UnauthorizedOperation, 100).unwrap();
+
+        // 1800s challenge period
+        let verdict = channel.evaluate_dispute(1_899).unwrap();
+        assert_eq!(verdict.resolution_type, ResolutionType::DefenderWins);
+        assert_eq!(verdict.signature_checks.len(), 1);
+        let verdict = channel.evaluate_dispute(1_900).unwrap();
+        assert_eq!(verdict.resolution_type, ResolutionType::ChallengerWins);
+        assert!(verdict.signature_checks.is_empty());
+    }
+
+    #[test]
+    fn test_session_key_authorizes_within_cap_until_revoked() {
+        let (mut channel, keys) = active_channel(2);
+        let (a, b) = (keys[0], keys[1]);
+        let session = Pubkey::new_unique();
+
+        assert_eq!(channel.clone().register_session_key(&a, session, 1, 1_000, 1).unwrap_err(), VaultError::InvalidSessionTimeout.into());
+        assert_eq!(channel.clone().register_session_key(&a, b, 100, 1_000, 1).unwrap_err(), VaultError::InvalidSession.into());
+        channel.register_session_key(&a, session, 100, 1_000, 1).unwrap();
+
+        // Valued at the order's notional; uncapped market orders can't be valued
+        assert!(channel.authorize_operation(&order(1, a, HFTOperationType::LimitBuy, 10, 100 * PRICE), &session, 2).is_ok());
+        let over_cap = order(2, a, HFTOperationType::LimitBuy, 11, 100 * PRICE);
+        assert_eq!(channel.authorize_operation(&over_cap, &session, 2).unwrap_err(), VaultError::SessionKeyLimitExceeded.into());
+        let uncapped = order(3, a, HFTOperationType::MarketBuy, 1, 0);
+        assert_eq!(channel.authorize_operation(&uncapped, &session, 2).unwrap_err(), VaultError::SessionKeyLimitExceeded.into());
+        assert!(channel.authorize_operation(&order(1, a, HFTOperationType::Cancel, 0, 0), &session, 2).is_ok());
+
+        assert!(channel.authorize_transfer(&transfer(4, a, b, 1_000), &session, 2).is_ok());
+        assert!(channel.authorize_transfer(&transfer(5, a, b, 1_001), &session, 2).is_err());
+        assert_eq!(channel.authorize_transfer(&transfer(6, b, a, 1), &session, 2).unwrap_err(), VaultError::UnauthorizedAccess.into());
+        assert_eq!(channel.authorize_transfer(&transfer(7, a, b, 1), &session, 100).unwrap_err(), VaultError::InvalidSession.into());
+
+        // Revocation takes effect immediately
+        channel.revoke_session_key(&a, 3).unwrap();
+        assert_eq!(channel.authorize_transfer(&transfer(8, a, b, 1), &session, 3).unwrap_err(), VaultError::InvalidSession.into());
+        assert_eq!(channel.revoke_session_key(&a, 4).unwrap_err(), VaultError::SessionNotFound.into());
+        assert!(channel.authorize_transfer(&transfer(9, a, b, 1), &a, 4).is_ok());
+    }
+
+    #[test]
+    fn test_session_key_signatures_bind_delegator() {
+        let (mut channel, keys) = active_channel(2);
+        let (a, b) = (keys[0], keys[1]);
+        let session = Pubkey::new_unique();
+        submit(&mut channel, order(1, a, HFTOperationType::LimitBuy, 1, 100 * PRICE), a, 1).unwrap();
+        channel.register_session_key(&a, session, 100, 1_000, 1).unwrap();
+        submit(&mut channel, order(2, a, HFTOperationType::LimitBuy, 1, 100 * PRICE), a, 2).unwrap();
+        channel.revoke_session_key(&a, 3).unwrap();
+        submit(&mut channel, order(3, a, HFTOperationType::LimitBuy, 1, 100 * PRICE), a, 4).unwrap();
+
+        let delegated = |operation: HFTOperation| SignedOperation { delegate: Some(session), ..signed(operation) };
+        let dispute = |first: SignedOperation, second: SignedOperation| {
+            let mut disputed = channel.clone();
+            let evidence = DisputeEvidence::ConflictingOperations { first, second }.try_to_vec().unwrap();
+            disputed.initiate_dispute(b, [0; 32], evidence, DisputeType::DoubleSpending, 5).unwrap();
+            disputed.evaluate_dispute(6)
+        };
+
+        // The session key double-signed nonce 2, which is on its delegator
+        let verdict = dispute(delegated(sequenced(1, a, 2)), delegated(sequenced(9, a, 2))).unwrap();
+        assert_eq!(verdict.resolution_type, ResolutionType::ChallengerWins);
+        assert_eq!(verdict.defender, Some(a));
+        assert!(verdict.signature_checks.iter().all(|check| check.signer == session));
+
+        // Nonces outside the key's lifetime aren't attributable to it
+        for nonce in [1, 3] {
+            let result = dispute(delegated(sequenced(1, a, nonce)), delegated(sequenced(9, a, nonce)));
+            assert_eq!(result.unwrap_err(), VaultError::InvalidDisputeEvidence.into());
+        }
+
+        let state = SignedChannelState { delegated: vec![true, false], ..signed_state(1, [0; 32], [1; 32], 2) };
+        let checks = channel.state_signature_checks(&state).unwrap();
+        assert_eq!((checks[0].signer, checks[1].signer), (session, b));
+        let state = SignedChannelState { delegated: vec![false, true], ..signed_state(1, [0; 32], [1; 32], 2) };
+        assert!(channel.state_signature_checks(&state).is_err());
+    }
+
+    fn signed_state(version: u64, previous_root: [u8; 32], state_root: [u8; 32], signers: usize) -> SignedChannelState {
+        SignedChannelState { version, previous_root, state_root, signatures: vec![vec![2; 64]; signers], delegated: Vec::new() }
+    }
+
+    #[test]
+    fn test_state_transition_hash_chain() {
+        let (channel, keys) = active_channel(2);
+        let dispute = |earlier: SignedChannelState, later: SignedChannelState| {
+            let mut disputed = channel.clone();
+            let disputed_state = later.state_root;
+            let evidence = DisputeEvidence::SignedStatePair { earlier, later }.try_to_vec().unwrap();
+            disputed.initiate_dispute(keys[1], disputed_state, evidence, DisputeType::InvalidStateTransition, 1).unwrap();
+            disputed.evaluate_dispute(2)
+        };
+
+        let verdict = dispute(signed_state(4, [0; 32], [1; 32], 2), signed_state(5, [1; 32], [2; 32], 2)).unwrap();
+        assert_eq!(verdict.resolution_type, ResolutionType::DefenderWins);
+        assert_eq!(verdict.signature_checks.len(), 4);
+        assert_eq!(verdict.signature_checks[1].signer, keys[1]);
+
+        let verdict = dispute(signed_state(4, [0; 32], [1; 32], 2), signed_state(5, [3; 32], [2; 32], 2)).unwrap();
+        assert_eq!(verdict.resolution_type, ResolutionType::ChallengerWins);
+
+        // Non-consecutive versions and missing signatures are malformed
+        assert!(dispute(signed_state(4, [0; 32], [1; 32], 2), signed_state(6, [1; 32], [2; 32], 2)).is_err());
+        assert!(dispute(signed_state(4, [0; 32], [1; 32], 1), signed_state(5, [1; 32], [2; 32], 2)).is_err());
+    }
+
+    fn balance_proof(leaves: &[[u8; 32]], index: usize) -> Vec<[u8; 32]> {
+        let mut level = leaves.to_vec();
+        let mut index = index;
+        let mut proof = Vec::new();
+        while level.len() > 1 {
+            if let Some(sibling) = level.get(index ^ 1) {
+                proof.push(*sibling);
+            }
+            level = level
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => StateChannel::merkle_node(left, right),
+                    _ => pair[0],
+                })
+                .collect();
+            index /= 2;
+        }
+        proof
+    }
+
+    #[test]
+    fn test_balance_snapshot_against_signed_root() {
+        let (mut channel, keys) = active_channel(3);
+        let (a, b) = (keys[0], keys[1]);
+        submit(&mut channel, order(1, a, HFTOperationType::LimitSell, 10, 100 * PRICE), a, 1).unwrap();
+        submit(&mut channel, order(2, b, HFTOperationType::MarketBuy, 10, 0), b, 2).unwrap();
+
+        let root = channel.balance_root();
+        let leaves: Vec<[u8; 32]> = channel.participants
+            .iter()
+            .zip(&channel.balances)
+            .map(|(p, account)| EnhancedStateChannel::balance_leaf_hash(&p.pubkey, &account.assets))
+            .collect();
+        let transfers = vec![transfer(3, a, b, 500)];
+        channel.process_micro_transaction(transfers[0].clone(), a, 3).unwrap();
+
+        let snapshot = |channel: &EnhancedStateChannel, balance: i64, transfers: Vec<MicroTransaction>| {
+            let mut disputed = channel.clone();
+            let evidence = DisputeEvidence::BalanceSnapshot {
+                state: signed_state(1, [0; 32], root, 3),
+                leaves: vec![BalanceLeaf { index: 1, assets: [balance, 0, 10] }],
+                proofs: vec![balance_proof(&leaves, 1)],
+                transfers,
+            };
+            disputed.initiate_dispute(keys[2], root, evidence.try_to_vec().unwrap(), DisputeType::BalanceInconsistency, 4).unwrap();
+            disputed.evaluate_dispute(5)
+        };
+
+        // Proven balance matches the channel and the transfers rebuild the matrix
+        let verdict = snapshot(&channel, -1_003, transfers.clone()).unwrap();
+        assert_eq!(verdict.resolution_type, ResolutionType::DefenderWins);
+        assert_eq!(verdict.signature_checks.len(), 3);
+
+        // Signed root disagrees with the channel's balances
+        let mut tampered = channel.clone();
+        tampered.balances[1].assets[0] = 0;
+        assert_eq!(snapshot(&tampered, -1_003, transfers.clone()).unwrap().resolution_type, ResolutionType::ChallengerWins);
+
+        // A leaf the root doesn't commit to, or missing transfers, is malformed
+        assert!(snapshot(&channel, 0, transfers).is_err());
+        assert!(snapshot(&channel, -1_003, Vec::new()).is_err());
+    }
+
+    fn transfer(id: u64, from: Pubkey, to: Pubkey, amount: u64) -> MicroTransaction {
+        MicroTransaction { id, from, to, asset: ChannelAsset::Sol, amount, timestamp: 0 }
+    }
+
+    #[test]
+    fn test_micro_transactions_net_until_checkpoint() {
+        let (mut channel, keys) = active_channel(3);
+        let (a, b, c) = (keys[0], keys[1], keys[2]);
+
+        // 10 bps transfer fee
+        let transfers = vec![
+            transfer(1, a, b, 10_000),
+            transfer(2, b, a, 4_000),
+            transfer(3, c, a, 1_000),
+            transfer(4, b, c, 2_000),
+        ];
+        for tx in &transfers {
+            channel.process_micro_transaction(tx.clone(), tx.from, 1).unwrap();
+        }
+
+        // Nothing materializes until the checkpoint
+        assert!(channel.balances.iter().all(|b| b.assets == [0; ChannelAsset::COUNT]));
+        assert_eq!(channel.net_transfers[EnhancedStateChannel::net_index(3, 0, 1)][0], 6_000);
+        assert_eq!(channel.net_transfers[EnhancedStateChannel::net_index(3, 0, 2)][0], -1_000);
+        assert_eq!(channel.net_transfers[EnhancedStateChannel::net_index(3, 1, 1)][0], 6);
+        assert_eq!(channel.pending_net_changes(ChannelAsset::Sol).unwrap(), vec![-5_010, 3_994, 999]);
+
+        assert!(channel.verify_net_transfers(&transfers).unwrap());
+        assert!(!channel.verify_net_transfers(&transfers[..3]).unwrap());
+
+        channel.checkpoint_net_transfers(2).unwrap();
+        let balances: Vec<i64> = channel.balances.iter().map(|b| b.balance(ChannelAsset::Sol)).collect();
+        assert_eq!(balances, vec![-5_010, 3_994, 999]);
+        assert!(channel.net_transfers.iter().all(|entry| *entry == [0; ChannelAsset::COUNT]));
+        assert_eq!(channel.collected_fees, [17, 0, 0]);
+    }
+
+    #[test]
+    fn test_close_materializes_net_transfers() {
+        let (mut channel, keys) = active_channel(2);
+        channel.process_micro_transaction(transfer(1, keys[1], keys[0], 5_000), keys[1], 1).unwrap();
+        channel.close_channel(2).unwrap();
+        assert_eq!(channel.balances[0].balance(ChannelAsset::Sol), 5_000);
+        assert_eq!(channel.balances[1].balance(ChannelAsset::Sol), -5_005);
+    }
+
+    #[test]
+    fn test_exposure_limits_cover_trades_and_transfers() {
+        let (mut channel, keys) = limited_channel(vec![1_000, 500]);
+        let (a, b) = (keys[0], keys[1]);
+
+        // Resting bids count against the limit before they fill. Failed
+        // operations revert on-chain, so probe them on a copy.
+        let too_big = order(1, b, HFTOperationType::LimitBuy, 6, 100 * PRICE);
+        assert_eq!(submit(&mut channel.clone(), too_big, b, 1).unwrap_err(), VaultError::ExposureLimitExceeded.into());
+        submit(&mut channel, order(2, b, HFTOperationType::LimitBuy, 4, 100 * PRICE), b, 1).unwrap();
+        assert_eq!(channel.exposure(1, ChannelAsset::Sol).unwrap(), 400);
+
+        // Filling the bid moves the exposure from committed to owed
+        submit(&mut channel, order(3, a, HFTOperationType::MarketSell, 4, 0), a, 2).unwrap();
+        assert_eq!(channel.exposure(1, ChannelAsset::Sol).unwrap(), 400);
+
+        // b can't transfer past the remaining 100 of headroom
+        let result = channel.clone().process_micro_transaction(transfer(4, b, a, 101), b, 3);
+        assert_eq!(result.unwrap_err(), VaultError::ExposureLimitExceeded.into());
+        channel.process_micro_transaction(transfer(5, b, a, 90), b, 3).unwrap();
+        assert_eq!(channel.exposure(1, ChannelAsset::Sol).unwrap(), 490);
+    }
+
+    #[test]
+    fn test_realize_pnl_at_mark_through_netting() {
+        let (mut channel, keys) = active_channel(3);
+        let (a, b, c) = (keys[0], keys[1], keys[2]);
+
+        // a sells 10 to b and 5 to c at 100
+        submit(&mut channel, order(1, a, HFTOperationType::LimitSell, 15, 100 * PRICE), a, 1).unwrap();
+        submit(&mut channel, order(2, b, HFTOperationType::MarketBuy, 10, 0), b, 2).unwrap();
+        submit(&mut channel, order(3, c, HFTOperationType::MarketBuy, 5, 0), c, 3).unwrap();
+        let before: Vec<i64> = channel.balances.iter().map(|account| account.balance(ChannelAsset::Sol)).collect();
+
+        channel.update_mark_price(110 * PRICE, 4).unwrap();
+        let realized = channel.realize_pnl(4).unwrap();
+        assert_eq!(realized, vec![
+            RealizedPnl { participant: a, amount: -150 },
+            RealizedPnl { participant: b, amount: 100 },
+            RealizedPnl { participant: c, amount: 50 },
+        ]);
+        assert_eq!(channel.pending_net_changes(ChannelAsset::Sol).unwrap(), vec![-150, 100, 50]);
+
+        channel.checkpoint_net_transfers(5).unwrap();
+        let after: Vec<i64> = channel.balances.iter().map(|account| account.balance(ChannelAsset::Sol)).collect();
+        assert_eq!(after, vec![before[0] - 150, before[1] + 100, before[2] + 50]);
+
+        // Already re-marked, nothing left to realize at the same price
+        assert!(channel.realize_pnl(6).unwrap().is_empty());
+        assert_eq!(channel.update_mark_price(0, 6).unwrap_err(), VaultError::InvalidOraclePrice.into());
+    }
+
+    #[test]
+    fn test_realized_loss_clamped_to_exposure_headroom() {
+        let (mut channel, keys) = limited_channel(vec![u64::MAX, 1_100]);
+        let (a, b) = (keys[0], keys[1]);
+
+        // b buys 10 at 100, owing 1_003 against a 1_100 limit
+        submit(&mut channel, order(1, a, HFTOperationType::LimitSell, 10, 100 * PRICE), a, 1).unwrap();
+        submit(&mut channel, order(2, b, HFTOperationType::MarketBuy, 10, 0), b, 2).unwrap();
+
+        channel.update_mark_price(80 * PRICE, 3).unwrap();
+        let realized = channel.realize_pnl(3).unwrap();
+        assert_eq!(realized, vec![
+            RealizedPnl { participant: a, amount: 97 },
+            RealizedPnl { participant: b, amount: -97 },
+        ]);
+        channel.checkpoint_net_transfers(3).unwrap();
+        assert_eq!(channel.exposure(1, ChannelAsset::Sol).unwrap(), 1_100);
+
+        // The uncovered 103 stays unrealized until the limit rises
+        channel.raise_exposure_limit(&b, ChannelAsset::Sol, 2_000, 4).unwrap();
+        assert_eq!(channel.realize_pnl(4).unwrap()[1].amount, -103);
+    }
+
+    #[test]
+    fn test_exposure_limits_only_rise() {
+        let (mut channel, keys) = limited_channel(vec![1_000, 500]);
+        assert!(channel.raise_exposure_limit(&keys[1], ChannelAsset::Sol, 500, 1).is_err());
+        assert!(channel.raise_exposure_limit(&keys[1], ChannelAsset::Sol, 400, 1).is_err());
+        assert!(channel.raise_exposure_limit(&Pubkey::new_unique(), ChannelAsset::Sol, 5_000, 1).is_err());
+
+        channel.raise_exposure_limit(&keys[1], ChannelAsset::Sol, 2_000, 1).unwrap();
+        let limits: Vec<u64> = channel.config.max_exposure.iter().map(|limits| limits[0]).collect();
+        assert_eq!(limits, vec![1_000, 2_000]);
+        assert_eq!(channel.nonce, 1);
+    }
+
+    #[test]
+    fn test_stats_count_operations_per_epoch() {
+        let (mut channel, keys) = active_channel(2);
+        let (a, b) = (keys[0], keys[1]);
+        submit(&mut channel, order(1, a, HFTOperationType::LimitSell, 10, 100 * PRICE), a, 3_599).unwrap();
+        submit(&mut channel, order(2, b, HFTOperationType::MarketBuy, 4, 0), b, 3_599).unwrap();
+        assert_eq!((channel.stats.epoch_start, channel.stats.epoch_operations), (0, 2));
+
+        // A new hourly epoch restarts the epoch count only
+        channel.process_micro_transaction(MicroTransaction { asset: ChannelAsset::Usdc, ..transfer(3, a, b, 50) }, a, 3_600).unwrap();
+        assert_eq!(channel.stats.total_operations, 3);
+        assert_eq!((channel.stats.epoch_start, channel.stats.epoch_operations), (3_600, 1));
+        assert_eq!(channel.stats.total_volume, [400, 50, 4]);
+        assert_eq!(channel.stats.last_operation_at, 3_600);
+
+        channel.stats.record_batch(7);
+        channel.stats.record_batch(3);
+        assert_eq!(channel.stats.max_batch_size, 7);
+
+        channel.stats.total_volume[0] = u64::MAX - 1;
+        channel.stats.record_volume(ChannelAsset::Sol, 10);
+        assert_eq!(channel.stats.total_volume[0], u64::MAX);
+
+        channel.initiate_dispute(b, [0; 32], Vec::new(), DisputeType::TimeoutViolation, 3_601).unwrap();
+        assert_eq!(channel.stats.dispute_count, 1);
+    }
+
+    #[test]
+    fn test_usdc_quoted_trades_settle_against_virtual_btc() {
+        let (mut channel, keys) = active_channel(2);
+        let (a, b) = (keys[0], keys[1]);
+        channel.config.quote_asset = ChannelAsset::Usdc;
+        let usdc = |operation: HFTOperation| HFTOperation { asset: ChannelAsset::Usdc, ..operation };
+
+        let sol_quoted = order(1, a, HFTOperationType::LimitSell, 10, 100 * PRICE);
+        assert_eq!(submit(&mut channel.clone(), sol_quoted, a, 1).unwrap_err(), VaultError::AssetMismatch.into());
+
+        submit(&mut channel, usdc(order(1, a, HFTOperationType::LimitSell, 10, 100 * PRICE)), a, 1).unwrap();
+        submit(&mut channel, usdc(order(2, b, HFTOperationType::MarketBuy, 10, 0)), b, 2).unwrap();
+        assert_eq!(channel.balances[0].assets, [0, 1_000, -10]);
+        assert_eq!(channel.balances[1].assets, [0, -1_003, 10]);
+        assert_eq!(channel.collected_fees, [0, 3, 0]);
+    }
+
+    #[test]
+    fn test_exposure_limits_apply_per_asset() {
+        let (mut channel, keys) = limited_channel(vec![1_000, 500]);
+        let (a, b) = (keys[0], keys[1]);
+        channel.config.max_exposure[1][ChannelAsset::Usdc.index()] = 200;
+
+        let usdc = MicroTransaction { asset: ChannelAsset::Usdc, ..transfer(1, b, a, 300) };
+        let result = channel.clone().process_micro_transaction(usdc, b, 1);
+        assert_eq!(result.unwrap_err(), VaultError::ExposureLimitExceeded.into());
+
+        channel.process_micro_transaction(transfer(2, b, a, 300), b, 1).unwrap();
+        assert_eq!(channel.exposure(1, ChannelAsset::Sol).unwrap(), 300);
+        assert_eq!(channel.exposure(1, ChannelAsset::Usdc).unwrap(), 0);
+
+        // Resting asks count against virtual BTC, not the quote asset
+        channel.config.max_exposure[0][ChannelAsset::VirtualBtc.index()] = 5;
+        let too_big = order(3, a, HFTOperationType::LimitSell, 6, 100 * PRICE);
+        assert_eq!(submit(&mut channel.clone(), too_big, a, 2).unwrap_err(), VaultError::ExposureLimitExceeded.into());
+        submit(&mut channel, order(4, a, HFTOperationType::LimitSell, 5, 100 * PRICE), a, 2).unwrap();
+        assert_eq!(channel.exposure(0, ChannelAsset::Sol).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_settlement_closes_out_virtual_btc_at_cost() {
+        let (mut channel, keys) = active_channel(2);
+        let (a, b) = (keys[0], keys[1]);
+
+        // a sells 10 to b at 100, then the mark moves to 110
+        submit(&mut channel, order(1, a, HFTOperationType::LimitSell, 10, 100 * PRICE), a, 1).unwrap();
+        submit(&mut channel, order(2, b, HFTOperationType::MarketBuy, 10, 0), b, 2).unwrap();
+        channel.update_mark_price(110 * PRICE, 3).unwrap();
+        assert!(channel.settlement_entitlements(ChannelAsset::Sol, &[5_000, 5_000]).is_err());
+
+        let realized = channel.close_channel(4).unwrap();
+        assert_eq!(realized, vec![
+            RealizedPnl { participant: a, amount: -100 },
+            RealizedPnl { participant: b, amount: 100 },
+        ]);
+        assert_eq!(channel.status, EnhancedChannelStatus::Settling);
+
+        // Deposits plus balances, positions unwound at the traded notional,
+        // and the taker fee for the treasury
+        let sol = channel.settlement_entitlements(ChannelAsset::Sol, &[5_000, 5_000]).unwrap();
+        assert_eq!(sol, vec![4_900, 5_097, 3]);
+        let usdc = channel.settlement_entitlements(ChannelAsset::Usdc, &[700, 0]).unwrap();
+        assert_eq!(usdc, vec![700, 0, 0]);
+        assert!(channel.settlement_entitlements(ChannelAsset::VirtualBtc, &[0, 0]).is_err());
+        assert!(channel.settlement_entitlements(ChannelAsset::Sol, &[5_000]).is_err());
+
+        channel.complete_settlement(5).unwrap();
+        assert_eq!(channel.status, EnhancedChannelStatus::Closed);
+        assert!(channel.complete_settlement(6).is_err());
+    }
+
+    fn pending(operation_id: u64, participants: Vec<Pubkey>, reserved_amount: u64, expires_at: i64) -> PendingOperation {
+        PendingOperation {
+            operation_id,
+            operation_type: OperationType::Transfer,
+            required_confirmations: participants.len() as u8,
+            participants,
+            data: Vec::new(),
+            confirmations: Vec::new(),
+            proposer: Pubkey::default(),
+            reserved_amount,
+            timestamp: 0,
+            expires_at,
+        }
+    }
+
+    #[test]
+    fn test_pending_operation_expiry_bounds() {
+        let (mut channel, keys) = active_channel(2);
+        // Already expired, or past the 86400s channel timeout
+        assert!(channel.add_pending_operation(pending(1, keys.clone(), 0, 10), keys[0], 10).is_err());
+        assert!(channel.add_pending_operation(pending(1, keys.clone(), 0, 86_411), keys[0], 10).is_err());
+        channel.add_pending_operation(pending(1, keys.clone(), 0, 86_410), keys[0], 10).unwrap();
+        assert_eq!(channel.pending_operations[0].proposer, keys[0]);
+    }
+
+    #[test]
+    fn test_eviction_races_late_confirmation() {
+        let (mut channel, keys) = limited_channel(vec![1_000, 1_000]);
+        let (a, b) = (keys[0], keys[1]);
+        channel.add_pending_operation(pending(1, keys.clone(), 800, 100), a, 1).unwrap();
+        channel.add_pending_operation(pending(2, keys.clone(), 0, 100), b, 1).unwrap();
+        channel.confirm_operation(1, a, [1; 64], 50).unwrap();
+        assert_eq!(channel.exposure(0, ChannelAsset::Sol).unwrap(), 800);
+
+        // The reservation holds a's headroom while the operation is pending
+        let result = channel.clone().process_micro_transaction(transfer(3, a, b, 300), a, 60);
+        assert_eq!(result.unwrap_err(), VaultError::ExposureLimitExceeded.into());
+
+        // Confirmation landing at expiry, before the sweep, is rejected
+        let late = channel.confirm_operation(1, b, [1; 64], 100);
+        assert_eq!(late.unwrap_err(), VaultError::OperationExpired.into());
+
+        // Confirmation landing after the sweep finds nothing to confirm
+        let evicted = channel.evict_expired_operations(100);
+        assert_eq!(evicted.iter().map(|op| op.operation_id).collect::<Vec<_>>(), vec![1, 2]);
+        assert!(channel.confirm_operation(1, b, [1; 64], 101).is_err());
+        assert_eq!(channel.exposure(0, ChannelAsset::Sol).unwrap(), 0);
+        channel.process_micro_transaction(transfer(3, a, b, 300), a, 101).unwrap();
+
+        // Confirmation that beats expiry executes and leaves nothing to evict
+        channel.add_pending_operation(pending(4, keys.clone(), 0, 200), a, 150).unwrap();
+        channel.confirm_operation(4, a, [1; 64], 199).unwrap();
+        assert!(channel.confirm_operation(4, b, [1; 64], 199).unwrap());
+        assert!(channel.evict_expired_operations(200).is_empty());
+    }
+
+    #[test]
+    fn test_full_queue_sweeps_expired_operations() {
+        let (mut channel, keys) = active_channel(2);
+        for id in 0..EnhancedStateChannel::MAX_PENDING_OPERATIONS as u64 {
+            channel.add_pending_operation(pending(id, keys.clone(), 0, 10 + id as i64), keys[0], 1).unwrap();
+        }
+        assert!(channel.clone().add_pending_operation(pending(99, keys.clone(), 0, 50), keys[0], 5).is_err());
+
+        channel.add_pending_operation(pending(99, keys.clone(), 0, 50), keys[0], 11).unwrap();
+        let ids: Vec<u64> = channel.pending_operations.iter().map(|op| op.operation_id).collect();
+        assert_eq!(ids, vec![2, 3, 99]);
+    }
+
+    #[test]
+    fn test_slash_bounds_split_and_exposure() {
+        let (mut channel, keys) = limited_channel(vec![50_000, 50_000]);
+        let (a, b) = (keys[0], keys[1]);
+
+        // 1_000..=1_000_000 bounds; 10% insurance cut
+        let outcome = channel.apply_slash(&a, true, 10, 40_000, 1_000, 1).unwrap();
+        assert_eq!(outcome, SlashOutcome { penalty: 1_000, paid: 1_000, insurance_cut: 100, winner_share: 900, bad_debt: 0 });
+        assert_eq!(channel.config.max_exposure[0], [49_000, u64::MAX, u64::MAX]);
+
+        let outcome = channel.apply_slash(&b, false, 5_000_000, 40_000, 1_000, 2).unwrap();
+        assert_eq!(outcome.penalty, 1_000_000);
+        assert_eq!((outcome.paid, outcome.insurance_cut, outcome.winner_share), (40_000, 40_000, 0));
+        assert_eq!(outcome.bad_debt, 960_000);
+        assert_eq!(channel.config.max_exposure[1][0], 10_000);
+
+        // Shortfalls accumulate in one entry per participant
+        channel.apply_slash(&b, true, 1_000, 0, 1_000, 3).unwrap();
+        assert_eq!(channel.bad_debts.len(), 1);
+        assert_eq!((channel.bad_debts[0].amount, channel.bad_debts[0].recorded_at), (961_000, 3));
+
+        assert!(channel.apply_slash(&Pubkey::new_unique(), true, 1_000, 0, 1_000, 4).is_err());
+    }
+
+    #[test]
+    fn test_v1_account_migrates_without_losing_state() {
+        let (mut channel, keys) = active_channel(2);
+        submit(&mut channel, order(1, keys[0], HFTOperationType::LimitBuy, 10, PRICE), keys[0], 5).unwrap();
+        assert_eq!(channel.version, EnhancedStateChannel::VERSION);
+        let current = channel.try_to_vec().unwrap();
+        
+        // A v1 account is the same encoding without the leading version byte
+        let mut data = EnhancedStateChannel::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(&current[1..]);
+        data.resize(EnhancedStateChannelV1::SIZE, 0);
+        
+        let legacy = EnhancedStateChannelV1::try_from_account_data(&data).unwrap();
+        let migrated = legacy.migrate(channel.updated_at);
+        assert_eq!(migrated.version, EnhancedStateChannel::VERSION);
+        assert_eq!(migrated.try_to_vec().unwrap(), current);
+        
+        // Already-migrated accounts are the wrong size to be read as v1
+        data.push(0);
+        assert!(EnhancedStateChannelV1::try_from_account_data(&data).is_err());
+    }
+
+    #[test]
+    fn test_withdrawal_leaves_exposure_covered_and_freezes_on_dispute() {
+        let (mut channel, keys) = active_channel(2);
+        // A resting bid commits 10 SOL of the deposit
+        submit(&mut channel, order(1, keys[0], HFTOperationType::LimitBuy, 10, PRICE), keys[0], 1).unwrap();
+        assert_eq!(channel.exposure(0, ChannelAsset::Sol).unwrap(), 10);
+        channel.check_withdrawal(&keys[0], ChannelAsset::Sol, 90, 100).unwrap();
+        assert!(channel.check_withdrawal(&keys[0], ChannelAsset::Sol, 91, 100).is_err());
+        assert!(channel.check_withdrawal(&keys[0], ChannelAsset::VirtualBtc, 1, 100).is_err());
+        
+        let mut withdrawal = ChannelWithdrawal { channel_id: channel.channel_id, participant: keys[0], nonce: 0, pending: None, bump: 0 };
+        withdrawal.request(ChannelAsset::Sol, 90, 10).unwrap();
+        assert!(withdrawal.request(ChannelAsset::Sol, 1, 10).is_err());
+        let window = channel.config.challenge_period;
+        assert_eq!(channel.withdrawal_executable_at(10).unwrap(), 10 + window);
+        
+        // The dispute freezes it, then the window runs again after the challenge period
+        channel.initiate_dispute(keys[1], [1; 32], Vec::new(), DisputeType::TimeoutViolation, 20).unwrap();
+        assert!(channel.check_withdrawal(&keys[0], ChannelAsset::Sol, 90, 100).is_err());
+        assert_eq!(channel.withdrawal_executable_at(10).unwrap(), 20 + 2 * window);
+        assert_eq!(channel.withdrawal_executable_at(30).unwrap(), 30 + window);
+        
+        assert_eq!(withdrawal.take_pending().unwrap().amount, 90);
+        assert_eq!(withdrawal.nonce, 1);
+        assert!(withdrawal.take_pending().is_err());
+    }
+}
@@ -10,6 +10,265 @@ pub enum KYCTier {
     Institutional,  // Institutional KYC - unlimited
 }
 
+/// Transaction limit tier, set only through `update_kyc_status`. Limits for
+/// each tier come from `ComplianceConfig`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+pub enum KycTier {
+    #[default]
+    Tier0,  // Unverified - $1k lifetime by default
+    Tier1,  // Basic - $10k per month by default
+    Tier2,  // Enhanced - unlimited by default
+}
+
+/// How far an overdue periodic review has been escalated
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+pub enum ReviewEscalation {
+    #[default]
+    None,
+    Alerted,     // Compliance alert raised
+    Restricted,  // Held to Tier0 limits until reviewed
+}
+
+/// One structured screening flag. A screening can only raise it; clearing
+/// takes a compliance officer and a justification.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct ComplianceFlag {
+    pub active: bool,
+    pub set_at: i64,        // When last raised; zero if never
+    pub cleared_at: i64,    // When last cleared; zero if never
+    pub changed_by: Pubkey, // Officer who last raised or cleared it
+}
+
+impl ComplianceFlag {
+    pub const LEN: usize = 1 + 8 + 8 + 32;
+}
+
+/// Which structured flag an instruction touches
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum ComplianceFlagKind {
+    Pep,
+    AdverseMedia,
+    SanctionsAdjacent,
+    HighRiskJurisdiction,
+}
+
+/// Structured screening flags, each with its own policy in
+/// `validate_transaction`:
+/// - PEP: outbound transfers over $5k need manual review
+/// - adverse media: every transaction needs a recent screening
+/// - sanctions-adjacent: every outbound transfer needs manual review
+/// - high-risk jurisdiction: held to Tier1 limits at most
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct ComplianceFlags {
+    pub is_pep: ComplianceFlag,
+    pub adverse_media: ComplianceFlag,
+    pub sanctions_adjacent: ComplianceFlag,
+    pub high_risk_jurisdiction: ComplianceFlag,
+}
+
+impl ComplianceFlags {
+    pub const LEN: usize = 4 * ComplianceFlag::LEN;
+    pub const PEP_REVIEW_THRESHOLD: u64 = 5_000_000_000; // $5k
+
+    pub fn get(&self, kind: ComplianceFlagKind) -> &ComplianceFlag {
+        match kind {
+            ComplianceFlagKind::Pep => &self.is_pep,
+            ComplianceFlagKind::AdverseMedia => &self.adverse_media,
+            ComplianceFlagKind::SanctionsAdjacent => &self.sanctions_adjacent,
+            ComplianceFlagKind::HighRiskJurisdiction => &self.high_risk_jurisdiction,
+        }
+    }
+
+    fn get_mut(&mut self, kind: ComplianceFlagKind) -> &mut ComplianceFlag {
+        match kind {
+            ComplianceFlagKind::Pep => &mut self.is_pep,
+            ComplianceFlagKind::AdverseMedia => &mut self.adverse_media,
+            ComplianceFlagKind::SanctionsAdjacent => &mut self.sanctions_adjacent,
+            ComplianceFlagKind::HighRiskJurisdiction => &mut self.high_risk_jurisdiction,
+        }
+    }
+
+    /// Raise `kind`. Returns whether it was newly raised.
+    pub fn raise(&mut self, kind: ComplianceFlagKind, officer: Pubkey, now: i64) -> bool {
+        let flag = self.get_mut(kind);
+        if flag.active {
+            return false;
+        }
+        flag.active = true;
+        flag.set_at = now;
+        flag.changed_by = officer;
+        true
+    }
+
+    pub fn clear(&mut self, kind: ComplianceFlagKind, officer: Pubkey, now: i64) -> Result<()> {
+        let flag = self.get_mut(kind);
+        if !flag.active {
+            return Err(VaultError::ComplianceFlagNotSet.into());
+        }
+        flag.active = false;
+        flag.cleared_at = now;
+        flag.changed_by = officer;
+        Ok(())
+    }
+
+    /// Whether the flags send an outbound transfer of `amount` USD to
+    /// manual review instead
+    pub fn requires_manual_review(&self, amount: u64) -> bool {
+        self.sanctions_adjacent.active || (self.is_pep.active && amount > Self::PEP_REVIEW_THRESHOLD)
+    }
+}
+
+/// User actions a compliance freeze can block. Stored on the profile as the
+/// current freeze and used as the set of bits a freeze or unfreeze touches.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct FreezeScope {
+    pub block_commit: bool,   // New or increased BTC commitments
+    pub block_decommit: bool, // Reduced commitments and withdrawals
+    pub block_claims: bool,   // Reward claims
+    pub block_payments: bool, // Payment requests, splits and scheduled payouts
+    pub block_channels: bool, // Opening, funding, settling and withdrawing from channels
+}
+
+impl FreezeScope {
+    pub const LEN: usize = 5;
+
+    /// Every bit set; what a freeze without an explicit scope applies
+    pub fn full() -> Self {
+        Self {
+            block_commit: true,
+            block_decommit: true,
+            block_claims: true,
+            block_payments: true,
+            block_channels: true,
+        }
+    }
+
+    pub fn any(&self) -> bool {
+        self.block_commit
+            || self.block_decommit
+            || self.block_claims
+            || self.block_payments
+            || self.block_channels
+    }
+
+    pub fn contains(&self, action: FrozenAction) -> bool {
+        match action {
+            FrozenAction::Commit => self.block_commit,
+            FrozenAction::Decommit => self.block_decommit,
+            FrozenAction::Claims => self.block_claims,
+            FrozenAction::Payments => self.block_payments,
+            FrozenAction::Channels => self.block_channels,
+        }
+    }
+}
+
+/// A single action a gated instruction performs
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FrozenAction {
+    Commit,
+    Decommit,
+    Claims,
+    Payments,
+    Channels,
+}
+
+/// Why compliance would refuse a transaction
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum ComplianceBlock {
+    AccountFrozen,
+    KycRequired,
+    TravelRuleDataRequired,
+    ManualReviewRequired,
+    RecentScreeningRequired,
+    TransactionLimitExceeded,
+    MonthlyLimitExceeded,
+    LifetimeLimitExceeded,
+}
+
+impl ComplianceBlock {
+    /// The error enforcement fails with
+    pub fn error(self) -> VaultError {
+        match self {
+            ComplianceBlock::AccountFrozen => VaultError::AccountFrozen,
+            ComplianceBlock::KycRequired => VaultError::KYCRequired,
+            ComplianceBlock::TravelRuleDataRequired => VaultError::TravelRuleDataRequired,
+            ComplianceBlock::ManualReviewRequired => VaultError::ManualReviewRequired,
+            ComplianceBlock::RecentScreeningRequired => VaultError::RecentScreeningRequired,
+            ComplianceBlock::TransactionLimitExceeded => VaultError::KycTransactionLimitExceeded,
+            ComplianceBlock::MonthlyLimitExceeded => VaultError::KycMonthlyLimitExceeded,
+            ComplianceBlock::LifetimeLimitExceeded => VaultError::KycLifetimeLimitExceeded,
+        }
+    }
+
+    /// What would lift the block
+    pub fn required_action(self) -> ComplianceAction {
+        match self {
+            ComplianceBlock::AccountFrozen => ComplianceAction::ContactCompliance,
+            ComplianceBlock::KycRequired => ComplianceAction::CompleteKyc,
+            ComplianceBlock::TravelRuleDataRequired => ComplianceAction::AttachTravelRuleData,
+            ComplianceBlock::ManualReviewRequired => ComplianceAction::RequestManualReview,
+            ComplianceBlock::RecentScreeningRequired => ComplianceAction::RequestScreening,
+            ComplianceBlock::TransactionLimitExceeded => ComplianceAction::ReduceAmount,
+            ComplianceBlock::MonthlyLimitExceeded => ComplianceAction::WaitForMonthlyReset,
+            ComplianceBlock::LifetimeLimitExceeded => ComplianceAction::UpgradeKycTier,
+        }
+    }
+}
+
+/// Something the user or an officer has to do before a blocked
+/// transaction can go through
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum ComplianceAction {
+    ContactCompliance,
+    CompleteKyc,
+    AttachTravelRuleData,
+    RequestManualReview,
+    RequestScreening,
+    ReduceAmount,
+    WaitForMonthlyReset,
+    UpgradeKycTier,
+}
+
+/// Outcome of running a transaction through the compliance checks, with
+/// every reason it would be refused rather than just the first
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct ComplianceCheck {
+    pub allowed: bool,
+    pub blocking_reasons: Vec<ComplianceBlock>,
+    pub required_actions: Vec<ComplianceAction>,
+}
+
+impl Default for ComplianceCheck {
+    fn default() -> Self {
+        Self {
+            allowed: true,
+            blocking_reasons: Vec::new(),
+            required_actions: Vec::new(),
+        }
+    }
+}
+
+impl ComplianceCheck {
+    fn block(&mut self, reason: ComplianceBlock) {
+        self.allowed = false;
+        if !self.blocking_reasons.contains(&reason) {
+            self.blocking_reasons.push(reason);
+        }
+        let action = reason.required_action();
+        if !self.required_actions.contains(&action) {
+            self.required_actions.push(action);
+        }
+    }
+
+    /// Fail with the first blocking reason's error
+    pub fn enforce(&self) -> Result<()> {
+        match self.blocking_reasons.first() {
+            Some(reason) => Err(reason.error().into()),
+            None => Ok(()),
+        }
+    }
+}
+
 /// KYC verification status
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
 pub enum KYCStatus {
@@ -19,6 +278,7 @@ pub enum KYCStatus {
     Rejected,       // KYC rejected
     Expired,        // KYC expired (needs renewal)
     Suspended,      // KYC suspended due to compliance issues
+    RequiredPending, // Activity crossed the KYC trigger; verification needed to keep transacting
 }
 
 /// Document types for KYC verification
@@ -84,6 +344,16 @@ pub struct KYCProfile {
     pub updated_at: i64,
     pub compliance_officer: Option<Pubkey>,
     pub notes: String,
+    pub region: Option<ComplianceRegion>, // Jurisdiction recorded at approval
+    pub kyc_tier: KycTier,          // Transaction limit tier
+    pub month_start: i64,           // Start of the 30-day window `monthly_volume` counts
+    pub lifetime_volume: u64,       // USD value of all validated transactions
+    pub review_required: bool,      // Screened against a stale sanctions list; Tier0 until an officer reviews
+    pub next_review_due: i64,       // Periodic compliance review date; zero until first approved
+    pub review_escalation: ReviewEscalation,
+    pub freeze_scope: FreezeScope,  // Actions blocked by a compliance freeze
+    pub kyc_required_at: i64,       // When activity crossed the KYC trigger; zero until it does
+    pub compliance_flags: ComplianceFlags,
     pub bump: u8,
 }
 
@@ -103,10 +373,25 @@ impl KYCProfile {
         8 + // updated_at
         33 + // compliance_officer (optional)
         4 + 512 + // notes (max 512 chars)
+        1 + (1 + 4 + 32) + // region (optional, Other name max 32 chars)
+        1 + // kyc_tier
+        8 + // month_start
+        8 + // lifetime_volume
+        1 + // review_required
+        8 + // next_review_due
+        1 + // review_escalation
+        FreezeScope::LEN + // freeze_scope
+        8 + // kyc_required_at
+        ComplianceFlags::LEN + // compliance_flags
         1; // bump
 
+    pub const MONTH_SECONDS: i64 = 30 * 24 * 3600;
+    pub const REVIEW_ALERT_AFTER: i64 = 30 * 24 * 3600;    // Overdue this long raises an alert
+    pub const REVIEW_RESTRICT_AFTER: i64 = 60 * 24 * 3600; // Overdue this long restricts to Tier0
+
     pub const MAX_DOCUMENTS: usize = 10;
     pub const MAX_NOTES_LENGTH: usize = 512;
+    pub const MAX_BATCH_FREEZE: usize = 20; // Profiles per batch freeze or unfreeze
 
     /// Initialize a new KYC profile
     pub fn initialize(
@@ -130,6 +415,16 @@ impl KYCProfile {
         self.updated_at = clock.unix_timestamp;
         self.compliance_officer = None;
         self.notes = String::new();
+        self.region = None;
+        self.kyc_tier = KycTier::Tier0;
+        self.month_start = 0;
+        self.lifetime_volume = 0;
+        self.review_required = false;
+        self.next_review_due = 0;
+        self.review_escalation = ReviewEscalation::None;
+        self.freeze_scope = FreezeScope::default();
+        self.kyc_required_at = 0;
+        self.compliance_flags = ComplianceFlags::default();
         self.bump = bump;
 
         Ok(())
@@ -242,6 +537,7 @@ impl KYCProfile {
         tier: KYCTier,
         compliance_officer: Pubkey,
         expiry_months: Option<u32>,
+        region: ComplianceRegion,
     ) -> Result<()> {
         if self.status != KYCStatus::Pending {
             return Err(VaultError::InvalidKYCStatus.into());
@@ -254,6 +550,7 @@ impl KYCProfile {
         self.tier = tier.clone();
         self.status = KYCStatus::Approved;
         self.compliance_officer = Some(compliance_officer);
+        self.region = Some(region);
         self.updated_at = clock.unix_timestamp;
 
         // Set expiry date if specified
@@ -352,6 +649,303 @@ impl KYCProfile {
         Ok(())
     }
 
+    /// Block the actions in `scope` on top of any already frozen. The
+    /// profile's KYC status is left alone, so lifting the freeze restores
+    /// exactly what the user could do before.
+    pub fn freeze(&mut self, scope: &FreezeScope, frozen_by: Pubkey, reason: String, now: i64) -> Result<()> {
+        if !scope.any() {
+            return Err(VaultError::InvalidFreezeScope.into());
+        }
+        self.freeze_scope.block_commit |= scope.block_commit;
+        self.freeze_scope.block_decommit |= scope.block_decommit;
+        self.freeze_scope.block_claims |= scope.block_claims;
+        self.freeze_scope.block_payments |= scope.block_payments;
+        self.freeze_scope.block_channels |= scope.block_channels;
+        self.compliance_officer = Some(frozen_by);
+        self.notes = reason;
+        self.updated_at = now;
+
+        msg!("Account {} frozen by {}: {:?}", self.user, frozen_by, self.freeze_scope);
+
+        Ok(())
+    }
+
+    /// Lift the actions in `scope`, leaving any other frozen bits in place.
+    /// Returns whether the account is still frozen for anything.
+    pub fn unfreeze(&mut self, scope: &FreezeScope, unfrozen_by: Pubkey, now: i64) -> Result<bool> {
+        if !scope.any() {
+            return Err(VaultError::InvalidFreezeScope.into());
+        }
+        self.freeze_scope.block_commit &= !scope.block_commit;
+        self.freeze_scope.block_decommit &= !scope.block_decommit;
+        self.freeze_scope.block_claims &= !scope.block_claims;
+        self.freeze_scope.block_payments &= !scope.block_payments;
+        self.freeze_scope.block_channels &= !scope.block_channels;
+        self.compliance_officer = Some(unfrozen_by);
+        self.updated_at = now;
+
+        msg!("Account {} unfrozen by {}: {:?} still blocked", self.user, unfrozen_by, self.freeze_scope);
+
+        Ok(self.freeze_scope.any())
+    }
+
+    /// Fails for an action the current freeze blocks. A suspended KYC
+    /// status still blocks everything.
+    pub fn require_not_frozen(&self, action: FrozenAction) -> Result<()> {
+        if self.status == KYCStatus::Suspended || self.freeze_scope.contains(action) {
+            return Err(VaultError::AccountFrozen.into());
+        }
+        Ok(())
+    }
+
+    /// Move the profile to `new_status` and `new_tier`. Changing tier needs
+    /// a verification made by the officer applying it, and upgrades need an
+    /// approved status. Limits above Tier0 only apply while approved.
+    pub fn update_kyc_status(
+        &mut self,
+        new_status: KYCStatus,
+        new_tier: KycTier,
+        verification: Option<&KYCVerification>,
+        compliance_officer: Pubkey,
+        now: i64,
+    ) -> Result<()> {
+        if new_tier > self.kyc_tier && new_status != KYCStatus::Approved {
+            return Err(VaultError::InvalidKYCStatus.into());
+        }
+        if new_tier != self.kyc_tier {
+            let verification = verification.ok_or(VaultError::KYCVerificationRequired)?;
+            if verification.verified_by != compliance_officer {
+                return Err(VaultError::UnauthorizedComplianceOfficer.into());
+            }
+            if verification.verification_date > now || verification.expiry_date.is_some_and(|e| e <= now) {
+                return Err(VaultError::KYCVerificationRequired.into());
+            }
+            self.kyc_expiry_date = verification.expiry_date;
+        }
+
+        // An approval counts as a completed review
+        if new_status == KYCStatus::Approved {
+            self.next_review_due = now.saturating_add(self.review_interval());
+            self.review_escalation = ReviewEscalation::None;
+        }
+        self.status = new_status;
+        self.kyc_tier = new_tier;
+        self.review_required = false;
+        self.compliance_officer = Some(compliance_officer);
+        self.updated_at = now;
+
+        Ok(())
+    }
+
+    /// Seconds between periodic compliance reviews; riskier users are
+    /// reviewed more often, and unscreened ones as medium risk
+    pub fn review_interval(&self) -> i64 {
+        let days = match self.compliance_screening.as_ref().map(|s| &s.risk_level) {
+            Some(RiskLevel::Low) => 365,
+            Some(RiskLevel::Medium) | None => 180,
+            Some(RiskLevel::High) => 90,
+            Some(RiskLevel::Prohibited) => 30,
+        };
+        days * 24 * 3600
+    }
+
+    pub fn review_overdue(&self, now: i64) -> bool {
+        self.next_review_due != 0 && now > self.next_review_due
+    }
+
+    /// Raise the escalation to match how long the review has been overdue.
+    /// Returns the new stage if it moved.
+    pub fn escalate_overdue_review(&mut self, now: i64) -> Option<ReviewEscalation> {
+        if !self.review_overdue(now) {
+            return None;
+        }
+        let overdue = now - self.next_review_due;
+        let stage = if overdue > Self::REVIEW_RESTRICT_AFTER {
+            ReviewEscalation::Restricted
+        } else if overdue > Self::REVIEW_ALERT_AFTER {
+            ReviewEscalation::Alerted
+        } else {
+            ReviewEscalation::None
+        };
+        if stage <= self.review_escalation {
+            return None;
+        }
+        self.review_escalation = stage;
+        self.updated_at = now;
+        Some(stage)
+    }
+
+    /// Record a completed periodic review: schedules the next one and lifts
+    /// any overdue or stale-screening restriction
+    pub fn complete_compliance_review(&mut self, compliance_officer: Pubkey, now: i64) {
+        self.next_review_due = now.saturating_add(self.review_interval());
+        self.review_escalation = ReviewEscalation::None;
+        self.review_required = false;
+        self.compliance_officer = Some(compliance_officer);
+        self.updated_at = now;
+    }
+
+    /// The tier whose limits apply: Tier0 unless KYC is approved, unexpired,
+    /// not awaiting review and not restricted for an overdue one
+    pub fn effective_tier(&self, now: i64) -> KycTier {
+        let expired = self.kyc_expiry_date.is_some_and(|e| now > e);
+        let restricted = self.review_escalation == ReviewEscalation::Restricted;
+        if self.status != KYCStatus::Approved || expired || self.review_required || restricted {
+            KycTier::Tier0
+        } else if self.compliance_flags.high_risk_jurisdiction.active && self.kyc_tier > KycTier::Tier1 {
+            KycTier::Tier1
+        } else {
+            self.kyc_tier
+        }
+    }
+
+    /// Record an automated AML screening. A sanctions match, proven or
+    /// reported, suspends the profile whatever its risk level; a screening
+    /// against a stale sanctions list leaves it for manual review.
+    pub fn record_aml_screening(
+        &mut self,
+        mut screening: ComplianceScreening,
+        verdict: SanctionsVerdict,
+        now: i64,
+    ) -> Result<()> {
+        if verdict == SanctionsVerdict::Listed {
+            screening.sanctions_match = true;
+        }
+        if screening.sanctions_match || screening.risk_level == RiskLevel::Prohibited {
+            screening.risk_level = RiskLevel::Prohibited;
+            self.status = KYCStatus::Suspended;
+            msg!("User {} suspended on a sanctions match", self.user);
+        } else if verdict == SanctionsVerdict::ManualReview {
+            self.review_required = true;
+            msg!("User {} screened against a stale sanctions list, manual review required", self.user);
+        }
+
+        self.compliance_screening = Some(screening);
+        // A higher risk level can bring the next review forward
+        if self.next_review_due != 0 {
+            self.next_review_due = self.next_review_due.min(now.saturating_add(self.review_interval()));
+        }
+        self.last_screening_date = now;
+        self.updated_at = now;
+
+        Ok(())
+    }
+
+    /// Run a transaction worth `amount` USD for `action` through every
+    /// compliance check without touching the profile. Outbound actions
+    /// are held to the travel rule and the flags' manual review policies.
+    /// `validate_transaction` enforces the result and simulation returns
+    /// it, so the two can't drift apart.
+    pub fn check_transaction(
+        &self,
+        config: &ComplianceConfig,
+        action: FrozenAction,
+        amount: u64,
+        travel_rule_attached: bool,
+        latest_screening: Option<&ScreeningRecord>,
+        now: i64,
+    ) -> ComplianceCheck {
+        let mut check = ComplianceCheck::default();
+        if self.require_not_frozen(action).is_err() {
+            check.block(ComplianceBlock::AccountFrozen);
+        }
+        let outbound = action != FrozenAction::Commit;
+        if outbound && !travel_rule_attached && config.requires_travel_rule(self.region.as_ref(), amount) {
+            check.block(ComplianceBlock::TravelRuleDataRequired);
+        }
+        if outbound && self.compliance_flags.requires_manual_review(amount) {
+            check.block(ComplianceBlock::ManualReviewRequired);
+        }
+        if config.requires_rescreening(latest_screening, amount, &self.compliance_flags, now) {
+            check.block(ComplianceBlock::RecentScreeningRequired);
+        }
+        self.check_limits(config, amount, now, &mut check);
+        check
+    }
+
+    /// Check a transaction worth `amount` USD against the profile's tier
+    /// limits and count it towards its monthly and lifetime volume. A
+    /// monthly window opens with the first transaction after the previous
+    /// one has closed. An unverified user whose lifetime volume crosses the
+    /// region's KYC trigger is moved to `RequiredPending` and can't
+    /// transact once the grace period runs out until approved.
+    pub fn charge_transaction(&mut self, config: &ComplianceConfig, amount: u64, now: i64) -> Result<()> {
+        let mut check = ComplianceCheck::default();
+        self.check_limits(config, amount, now, &mut check);
+        check.enforce()?;
+        self.record_transaction(config, amount, now)
+    }
+
+    fn check_limits(&self, config: &ComplianceConfig, amount: u64, now: i64, check: &mut ComplianceCheck) {
+        if self.status == KYCStatus::Suspended {
+            check.block(ComplianceBlock::AccountFrozen);
+        }
+        if self.kyc_required_at != 0
+            && self.status != KYCStatus::Approved
+            && now > self.kyc_required_at.saturating_add(config.kyc_trigger_grace_period(self.region.as_ref()))
+        {
+            check.block(ComplianceBlock::KycRequired);
+        }
+        let limits = config.limits_for(self.effective_tier(now));
+
+        if limits.per_transaction != 0 && amount > limits.per_transaction {
+            check.block(ComplianceBlock::TransactionLimitExceeded);
+        }
+        let monthly_volume = self.monthly_volume_at(now).saturating_add(amount);
+        if limits.monthly != 0 && monthly_volume > limits.monthly {
+            check.block(ComplianceBlock::MonthlyLimitExceeded);
+        }
+        let lifetime_volume = self.lifetime_volume.saturating_add(amount);
+        if limits.lifetime != 0 && lifetime_volume > limits.lifetime {
+            check.block(ComplianceBlock::LifetimeLimitExceeded);
+        }
+    }
+
+    fn monthly_volume_at(&self, now: i64) -> u64 {
+        if now < self.month_start.saturating_add(Self::MONTH_SECONDS) {
+            self.monthly_volume
+        } else {
+            0
+        }
+    }
+
+    /// Count a transaction that passed its checks towards the profile's
+    /// volume, and start the KYC grace period if it crosses the trigger
+    pub fn record_transaction(&mut self, config: &ComplianceConfig, amount: u64, now: i64) -> Result<()> {
+        let window_open = now < self.month_start.saturating_add(Self::MONTH_SECONDS);
+        let monthly_volume = self.monthly_volume_at(now).checked_add(amount).ok_or(VaultError::ArithmeticOverflow)?;
+        let lifetime_volume = self.lifetime_volume.checked_add(amount).ok_or(VaultError::ArithmeticOverflow)?;
+
+        if !window_open {
+            self.month_start = now;
+        }
+        self.monthly_volume = monthly_volume;
+        self.lifetime_volume = lifetime_volume;
+        self.updated_at = now;
+
+        let threshold = config.kyc_trigger_threshold(self.region.as_ref());
+        if self.kyc_required_at == 0
+            && self.status != KYCStatus::Approved
+            && threshold != 0
+            && lifetime_volume >= threshold
+        {
+            self.kyc_required_at = now;
+            if self.status == KYCStatus::NotStarted {
+                self.status = KYCStatus::RequiredPending;
+            }
+            emit!(KycVerificationRequired {
+                user: self.user,
+                lifetime_volume,
+                threshold,
+                deadline: now.saturating_add(config.kyc_trigger_grace_period(self.region.as_ref())),
+                timestamp: now,
+            });
+            msg!("User {} crossed the KYC trigger at {} USD volume", self.user, lifetime_volume);
+        }
+
+        Ok(())
+    }
+
     // Private helper methods
 
     fn validate_tier_requirements(&self, tier: &KYCTier) -> Result<()> {
@@ -451,6 +1045,631 @@ impl KYCProfile {
     }
 }
 
+/// Transaction caps for one KYC tier in USD with 6 decimals, like USDC.
+/// Zero disables a cap.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct TierLimits {
+    pub per_transaction: u64,
+    pub monthly: u64,           // Per 30-day window
+    pub lifetime: u64,
+}
+
+impl TierLimits {
+    pub const LEN: usize = 8 + 8 + 8;
+
+    /// Caps as enforced, zero read as unlimited: no transaction can exceed
+    /// the monthly cap, nor a month the lifetime cap
+    fn effective_caps(&self) -> [u64; 3] {
+        let cap = |limit: u64| if limit == 0 { u64::MAX } else { limit };
+        let lifetime = cap(self.lifetime);
+        let monthly = cap(self.monthly).min(lifetime);
+        [cap(self.per_transaction).min(monthly), monthly, lifetime]
+    }
+
+    /// Whether every cap here is at least as loose as the matching one in `other`
+    fn at_least(&self, other: &TierLimits) -> bool {
+        self.effective_caps().iter()
+            .zip(other.effective_caps())
+            .all(|(cap, other_cap)| *cap >= other_cap)
+    }
+}
+
+/// Outcome of checking an address against the sanctions list
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SanctionsVerdict {
+    Listed,       // Proven to be on the list
+    Clear,        // No proof of listing against a current root
+    ManualReview, // No proof, and the root is missing or stale
+}
+
+/// Protocol features a region's policy can switch off or cap
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RegionFeature {
+    HftChannel,
+    LightningPayout,
+    Reinvestment,
+    Commitment {
+        sats: u64,
+        btc_price_usd: Option<u64>, // Oracle price with 8 decimals; None while stale
+    },
+}
+
+/// What users in one region may do. A zero commitment cap is no cap.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct RegionPolicy {
+    pub allow_hft_channels: bool,
+    pub allow_lightning_payouts: bool,
+    pub allow_reinvestment: bool,
+    pub max_commitment_usd: u64, // USD with 6 decimals
+}
+
+impl RegionPolicy {
+    pub const LEN: usize = 1 + 1 + 1 + 8;
+
+    pub fn unrestricted() -> Self {
+        Self {
+            allow_hft_channels: true,
+            allow_lightning_payouts: true,
+            allow_reinvestment: true,
+            max_commitment_usd: 0,
+        }
+    }
+
+    /// The strictest setting of every policy, field by field
+    fn strictest(policies: &[RegionPolicy]) -> Self {
+        Self {
+            allow_hft_channels: policies.iter().all(|p| p.allow_hft_channels),
+            allow_lightning_payouts: policies.iter().all(|p| p.allow_lightning_payouts),
+            allow_reinvestment: policies.iter().all(|p| p.allow_reinvestment),
+            max_commitment_usd: policies.iter()
+                .map(|p| p.max_commitment_usd)
+                .filter(|cap| *cap != 0)
+                .min()
+                .unwrap_or(0),
+        }
+    }
+
+    pub fn check(&self, feature: RegionFeature) -> Result<()> {
+        let allowed = match feature {
+            RegionFeature::HftChannel => self.allow_hft_channels,
+            RegionFeature::LightningPayout => self.allow_lightning_payouts,
+            RegionFeature::Reinvestment => self.allow_reinvestment,
+            RegionFeature::Commitment { .. } if self.max_commitment_usd == 0 => true,
+            RegionFeature::Commitment { sats, btc_price_usd } => {
+                let btc_price_usd = btc_price_usd.ok_or(VaultError::OraclePriceUnavailable)?;
+                // sats * price(8 dp) / 1e8 sats per BTC is USD with 8 dp; drop 2
+                let usd = sats as u128 * btc_price_usd as u128 / 10_000_000_000;
+                if usd > self.max_commitment_usd as u128 {
+                    return Err(VaultError::RegionCommitmentCapExceeded.into());
+                }
+                true
+            },
+        };
+        if !allowed {
+            return Err(VaultError::RegionFeatureRestricted.into());
+        }
+        Ok(())
+    }
+}
+
+/// Protocol-wide compliance settings
+#[account]
+pub struct ComplianceConfig {
+    pub tier_limits: [TierLimits; 3], // Indexed by `KycTier`
+    pub audit_sequence: u64,          // Sequence of the last `ComplianceAuditEntry`
+    pub sanctions_root: [u8; 32],     // Merkle root of sanctioned address hashes; zero until first set
+    pub sanctions_root_updated_at: i64,
+    pub sanctions_max_staleness: i64, // Seconds the root stays current after an update
+    pub travel_rule_thresholds: [u64; ComplianceRegion::COUNT], // USD by `ComplianceRegion::index`; zero where the rule doesn't apply
+    pub region_policies: [RegionPolicy; ComplianceRegion::COUNT], // By `ComplianceRegion::index`
+    pub high_value_threshold: u64,    // USD at which a transaction needs a recent screening; zero disables
+    pub screening_max_age: i64,       // Seconds a screening counts as recent for high-value transactions
+    pub kyc_trigger_thresholds: [u64; ComplianceRegion::COUNT], // Lifetime USD volume pushing unverified users to KYC; zero disables
+    pub kyc_trigger_grace_periods: [i64; ComplianceRegion::COUNT], // Seconds unverified users may keep transacting after the trigger
+    pub screening_providers: Vec<ScreeningProvider>, // Registered AML providers, max 4
+    pub bump: u8,
+}
+
+impl ComplianceConfig {
+    pub const LEN: usize = 8 + // discriminator
+        3 * TierLimits::LEN + // tier_limits
+        8 + // audit_sequence
+        32 + // sanctions_root
+        8 + // sanctions_root_updated_at
+        8 + // sanctions_max_staleness
+        8 * ComplianceRegion::COUNT + // travel_rule_thresholds
+        RegionPolicy::LEN * ComplianceRegion::COUNT + // region_policies
+        8 + // high_value_threshold
+        8 + // screening_max_age
+        8 * ComplianceRegion::COUNT + // kyc_trigger_thresholds
+        8 * ComplianceRegion::COUNT + // kyc_trigger_grace_periods
+        4 + (Self::MAX_SCREENING_PROVIDERS * ScreeningProvider::LEN) + // screening_providers
+        1; // bump
+
+    pub const MAX_SCREENING_PROVIDERS: usize = 4;
+
+    pub const DEFAULT_HIGH_VALUE_THRESHOLD: u64 = 10_000_000_000; // $10k
+    pub const DEFAULT_SCREENING_MAX_AGE: i64 = 30 * 24 * 3600;
+
+    pub fn default_tier_limits() -> [TierLimits; 3] {
+        [
+            TierLimits { lifetime: 1_000_000_000, ..Default::default() }, // $1k
+            TierLimits { monthly: 10_000_000_000, ..Default::default() }, // $10k
+            TierLimits::default(),
+        ]
+    }
+
+    /// FATF's $1k travel rule threshold, and FinCEN's $3k in the US
+    pub fn default_travel_rule_thresholds() -> [u64; ComplianceRegion::COUNT] {
+        let mut thresholds = [1_000_000_000; ComplianceRegion::COUNT];
+        thresholds[ComplianceRegion::US.index()] = 3_000_000_000;
+        thresholds
+    }
+
+    /// Unverified users are pushed to KYC at $500 and get a week to finish
+    pub fn default_kyc_triggers() -> ([u64; ComplianceRegion::COUNT], [i64; ComplianceRegion::COUNT]) {
+        ([500_000_000; ComplianceRegion::COUNT], [7 * 24 * 3600; ComplianceRegion::COUNT])
+    }
+
+    /// Replace the per-region KYC trigger thresholds and grace periods
+    pub fn set_kyc_triggers(
+        &mut self,
+        thresholds: [u64; ComplianceRegion::COUNT],
+        grace_periods: [i64; ComplianceRegion::COUNT],
+    ) -> Result<()> {
+        if grace_periods.iter().any(|grace| *grace < 0) {
+            return Err(VaultError::InvalidKycTrigger.into());
+        }
+        self.kyc_trigger_thresholds = thresholds;
+        self.kyc_trigger_grace_periods = grace_periods;
+        Ok(())
+    }
+
+    /// Lifetime volume at which an unverified user in `region` must verify.
+    /// An unknown region gets the lowest threshold in force anywhere.
+    pub fn kyc_trigger_threshold(&self, region: Option<&ComplianceRegion>) -> u64 {
+        match region {
+            Some(region) => self.kyc_trigger_thresholds[region.index()],
+            None => self.kyc_trigger_thresholds.iter().copied().filter(|t| *t != 0).min().unwrap_or(0),
+        }
+    }
+
+    /// Grace period after the KYC trigger; the shortest anywhere for an
+    /// unknown region
+    pub fn kyc_trigger_grace_period(&self, region: Option<&ComplianceRegion>) -> i64 {
+        match region {
+            Some(region) => self.kyc_trigger_grace_periods[region.index()],
+            None => self.kyc_trigger_grace_periods.iter().copied().min().unwrap_or(0),
+        }
+    }
+
+    /// Whether a transfer of `amount` USD needs travel rule data attached.
+    /// An unknown region gets the lowest threshold in force anywhere.
+    pub fn requires_travel_rule(&self, region: Option<&ComplianceRegion>, amount: u64) -> bool {
+        let threshold = match region {
+            Some(region) => self.travel_rule_thresholds[region.index()],
+            None => self.travel_rule_thresholds.iter().copied().filter(|t| *t != 0).min().unwrap_or(0),
+        };
+        threshold != 0 && amount > threshold
+    }
+
+    /// Everything allowed and uncapped, except high-frequency trading
+    /// channels where we don't hold the licence
+    pub fn default_region_policies() -> [RegionPolicy; ComplianceRegion::COUNT] {
+        let mut policies: [RegionPolicy; ComplianceRegion::COUNT] =
+            core::array::from_fn(|_| RegionPolicy::unrestricted());
+        for region in [ComplianceRegion::US, ComplianceRegion::UK, ComplianceRegion::Japan] {
+            policies[region.index()].allow_hft_channels = false;
+        }
+        policies
+    }
+
+    /// Policy for users in `region`. An unknown region gets the strictest
+    /// setting in force anywhere.
+    pub fn region_policy(&self, region: Option<&ComplianceRegion>) -> RegionPolicy {
+        match region {
+            Some(region) => self.region_policies[region.index()].clone(),
+            None => RegionPolicy::strictest(&self.region_policies),
+        }
+    }
+
+    /// Replace one region's policy, returning the old one. Only an executed
+    /// multisig `SetRegionPolicy` transaction calls this.
+    pub fn set_region_policy(&mut self, region: &ComplianceRegion, policy: RegionPolicy) -> RegionPolicy {
+        core::mem::replace(&mut self.region_policies[region.index()], policy)
+    }
+
+    pub fn limits_for(&self, tier: KycTier) -> &TierLimits {
+        &self.tier_limits[tier as usize]
+    }
+
+    /// Replace the tier limit tables. A higher tier can't be tighter than
+    /// the one below it.
+    pub fn set_tier_limits(&mut self, tier_limits: [TierLimits; 3]) -> Result<()> {
+        if !tier_limits.windows(2).all(|pair| pair[1].at_least(&pair[0])) {
+            return Err(VaultError::InvalidTierLimits.into());
+        }
+        self.tier_limits = tier_limits;
+        Ok(())
+    }
+
+    /// Install a new sanctions list root. Only an executed multisig
+    /// `SetSanctionsRoot` transaction calls this.
+    pub fn set_sanctions_root(&mut self, root: [u8; 32], max_staleness: i64, now: i64) {
+        self.sanctions_root = root;
+        self.sanctions_root_updated_at = now;
+        self.sanctions_max_staleness = max_staleness;
+    }
+
+    /// Whether the sanctions root is missing or past its max staleness
+    pub fn sanctions_root_stale(&self, now: i64) -> bool {
+        self.sanctions_root == [0; 32]
+            || now > self.sanctions_root_updated_at.saturating_add(self.sanctions_max_staleness)
+    }
+
+    /// Whether `merkle_proof` proves `address_hash` is on the sanctions
+    /// list. Inner nodes hash their children in sorted order, so the proof
+    /// is just the sibling hashes, bottom-up.
+    pub fn check_sanctions(&self, address_hash: &[u8; 32], merkle_proof: &[[u8; 32]]) -> bool {
+        if self.sanctions_root == [0; 32] {
+            return false;
+        }
+        merkle_proof_root(merkle_leaf(address_hash), merkle_proof) == self.sanctions_root
+    }
+
+    /// Check an address for screening. A proof that fails to verify is
+    /// rejected rather than read as a clear result.
+    pub fn sanctions_verdict(
+        &self,
+        address_hash: &[u8; 32],
+        merkle_proof: Option<&[[u8; 32]]>,
+        now: i64,
+    ) -> Result<SanctionsVerdict> {
+        match merkle_proof {
+            Some(proof) if self.check_sanctions(address_hash, proof) => Ok(SanctionsVerdict::Listed),
+            Some(_) => Err(VaultError::InvalidSanctionsProof.into()),
+            None if self.sanctions_root_stale(now) => Ok(SanctionsVerdict::ManualReview),
+            None => Ok(SanctionsVerdict::Clear),
+        }
+    }
+
+    pub fn next_audit_sequence(&mut self) -> Result<u64> {
+        self.audit_sequence = self.audit_sequence.checked_add(1)
+            .ok_or(VaultError::ArithmeticOverflow)?;
+        Ok(self.audit_sequence)
+    }
+
+    /// Register a screening provider or change an existing one. Only an
+    /// executed multisig `SetScreeningProvider` transaction calls this.
+    /// Returns the provider as it was, if registered.
+    pub fn set_screening_provider(
+        &mut self,
+        provider: ScreeningProvider,
+    ) -> Result<Option<ScreeningProvider>> {
+        if provider.key_hash == [0; 32] {
+            return Err(VaultError::InvalidScreeningProvider.into());
+        }
+        if let Some(existing) = self.screening_providers.iter_mut().find(|p| p.provider_id == provider.provider_id) {
+            return Ok(Some(core::mem::replace(existing, provider)));
+        }
+        if self.screening_providers.len() >= Self::MAX_SCREENING_PROVIDERS {
+            return Err(VaultError::TooManyScreeningProviders.into());
+        }
+        self.screening_providers.push(provider);
+        Ok(None)
+    }
+
+    /// Replace a provider's key hash. Results submitted under the old key
+    /// stop counting as current. Only an executed multisig
+    /// `RotateScreeningProviderKey` transaction calls this. Returns the old
+    /// key hash.
+    pub fn rotate_screening_provider_key(
+        &mut self,
+        provider_id: u8,
+        new_key_hash: [u8; 32],
+        now: i64,
+    ) -> Result<[u8; 32]> {
+        let provider = self.screening_providers.iter_mut()
+            .find(|p| p.provider_id == provider_id)
+            .ok_or(VaultError::UnknownScreeningProvider)?;
+        if new_key_hash == [0; 32] || new_key_hash == provider.key_hash {
+            return Err(VaultError::InvalidScreeningProvider.into());
+        }
+        provider.key_rotated_at = now;
+        Ok(core::mem::replace(&mut provider.key_hash, new_key_hash))
+    }
+
+    pub fn screening_provider(&self, provider_id: u8) -> Option<&ScreeningProvider> {
+        self.screening_providers.iter().find(|p| p.provider_id == provider_id)
+    }
+
+    /// Whether a result from `provider_id` under `key_hash` comes from an
+    /// active provider's current key
+    pub fn is_current_screening_source(&self, provider_id: u8, key_hash: &[u8; 32]) -> bool {
+        self.screening_provider(provider_id)
+            .is_some_and(|p| p.active && p.key_hash == *key_hash)
+    }
+
+    /// Reject a screening submitted under an unknown, inactive or rotated key
+    pub fn check_screening_source(&self, provider_id: u8, key_hash: &[u8; 32]) -> Result<()> {
+        if self.screening_provider(provider_id).is_none() {
+            return Err(VaultError::UnknownScreeningProvider.into());
+        }
+        if !self.is_current_screening_source(provider_id, key_hash) {
+            return Err(VaultError::StaleScreeningKey.into());
+        }
+        Ok(())
+    }
+
+    /// Whether a transaction worth `amount` USD needs the user screened
+    /// again first. High-value transactions, and any by a user flagged for
+    /// adverse media, need a latest current screening no older than
+    /// `screening_max_age`.
+    pub fn requires_rescreening(
+        &self,
+        latest: Option<&ScreeningRecord>,
+        amount: u64,
+        flags: &ComplianceFlags,
+        now: i64,
+    ) -> bool {
+        let high_value = self.high_value_threshold != 0 && amount >= self.high_value_threshold;
+        if !high_value && !flags.adverse_media.active {
+            return false;
+        }
+        !matches!(latest, Some(record) if now <= record.screened_at.saturating_add(self.screening_max_age))
+    }
+}
+
+/// A registered AML screening provider. Only a hash of its API key is
+/// kept; the key itself stays with the off-chain screening service.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct ScreeningProvider {
+    pub provider_id: u8,
+    pub key_hash: [u8; 32],   // Hash of the provider's current API key
+    pub priority: u8,         // Lower is tried first
+    pub active: bool,
+    pub key_rotated_at: i64,  // When `key_hash` last changed
+}
+
+impl ScreeningProvider {
+    pub const LEN: usize = 1 + 32 + 1 + 1 + 8;
+}
+
+/// Screening categories a provider flagged
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct ScreeningCategories {
+    pub sanctions: bool,
+    pub pep: bool,            // Politically Exposed Person
+    pub adverse_media: bool,
+    pub darknet_market: bool,
+    pub mixer: bool,
+    pub fraud: bool,
+    pub ransomware: bool,
+}
+
+impl ScreeningCategories {
+    pub const LEN: usize = 7;
+}
+
+/// One AML screening in a user's `ScreeningHistory`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct ScreeningRecord {
+    pub sequence: u64,
+    pub provider_id: u8,               // Registered provider that produced the result
+    pub key_hash: [u8; 32],            // Provider key hash the result was submitted under
+    pub risk_score: u8,                // Provider risk score, 0-100
+    pub risk_level: RiskLevel,
+    pub categories: ScreeningCategories,
+    pub screened_at: i64,
+    pub expires_at: i64,
+    pub prev_hash: [u8; 32],           // Hash of the record before it; zero for the first
+}
+
+impl ScreeningRecord {
+    pub const MAX_RISK_SCORE: u8 = 100;
+
+    pub const LEN: usize = 8 + // sequence
+        1 + // provider_id
+        32 + // key_hash
+        1 + // risk_score
+        1 + // risk_level
+        ScreeningCategories::LEN + // categories
+        8 + // screened_at
+        8 + // expires_at
+        32; // prev_hash
+
+    /// Hash over the whole record, `prev_hash` included, so changing any
+    /// earlier record breaks every link after it
+    pub fn hash(&self) -> Result<[u8; 32]> {
+        let data = self.try_to_vec().map_err(|_| VaultError::InvalidScreeningRecord)?;
+        Ok(solana_program::hash::hash(&data).to_bytes())
+    }
+
+    pub fn is_current(&self, now: i64) -> bool {
+        now < self.expires_at
+    }
+}
+
+/// A user's latest `HISTORY_LEN` AML screenings, each hash-linked to the
+/// one before it. `head_hash` covers the newest record, so the chain still
+/// verifies from the oldest kept record once earlier ones are dropped.
+#[account]
+pub struct ScreeningHistory {
+    pub user: Pubkey,
+    pub records: Vec<ScreeningRecord>, // Ring buffer indexed by sequence number
+    pub record_count: u64,             // Screenings ever recorded; the next sequence number
+    pub head_hash: [u8; 32],           // Hash of the newest record
+    pub bump: u8,
+}
+
+impl ScreeningHistory {
+    pub const HISTORY_LEN: usize = 12;
+
+    pub const LEN: usize = 8 + // discriminator
+        32 + // user
+        4 + (Self::HISTORY_LEN * ScreeningRecord::LEN) + // records
+        8 + // record_count
+        32 + // head_hash
+        1; // bump
+
+    pub fn initialize(&mut self, user: Pubkey, bump: u8) {
+        self.user = user;
+        self.records = Vec::new();
+        self.record_count = 0;
+        self.head_hash = [0; 32];
+        self.bump = bump;
+    }
+
+    /// Append a screening linked to the current head, overwriting the
+    /// oldest once full. Its sequence and `prev_hash` are filled in here.
+    /// Returns the record's sequence number.
+    pub fn record(&mut self, mut record: ScreeningRecord) -> Result<u64> {
+        if record.risk_score > ScreeningRecord::MAX_RISK_SCORE || record.expires_at <= record.screened_at {
+            return Err(VaultError::InvalidScreeningRecord.into());
+        }
+        let sequence = self.record_count;
+        record.sequence = sequence;
+        record.prev_hash = self.head_hash;
+        self.head_hash = record.hash()?;
+        if self.records.len() < Self::HISTORY_LEN {
+            self.records.push(record);
+        } else {
+            self.records[(sequence % Self::HISTORY_LEN as u64) as usize] = record;
+        }
+        self.record_count = sequence.checked_add(1).ok_or(VaultError::ArithmeticOverflow)?;
+        Ok(sequence)
+    }
+
+    /// Kept records, oldest first
+    pub fn ordered(&self) -> Vec<ScreeningRecord> {
+        let mut records = self.records.clone();
+        records.sort_by_key(|record| record.sequence);
+        records
+    }
+
+    /// Check every kept record links to the one before it and the newest
+    /// to `head_hash`
+    pub fn verify_chain(&self) -> Result<()> {
+        let mut expected_prev = None;
+        for record in self.ordered() {
+            if expected_prev.is_some_and(|prev| prev != record.prev_hash) {
+                return Err(VaultError::ScreeningHistoryTampered.into());
+            }
+            expected_prev = Some(record.hash()?);
+        }
+        if expected_prev.unwrap_or([0; 32]) != self.head_hash {
+            return Err(VaultError::ScreeningHistoryTampered.into());
+        }
+        Ok(())
+    }
+
+    /// The most recent screening that hasn't expired and was submitted
+    /// under its provider's current key
+    pub fn latest_current(&self, config: &ComplianceConfig, now: i64) -> Option<&ScreeningRecord> {
+        self.records.iter()
+            .filter(|record| record.is_current(now))
+            .filter(|record| config.is_current_screening_source(record.provider_id, &record.key_hash))
+            .max_by_key(|record| record.sequence)
+    }
+}
+
+/// Emitted for every KYC status or tier change
+#[event]
+pub struct ComplianceAuditEntry {
+    pub sequence: u64,
+    pub user: Pubkey,
+    pub compliance_officer: Pubkey,
+    pub old_status: KYCStatus,
+    pub new_status: KYCStatus,
+    pub old_tier: KycTier,
+    pub new_tier: KycTier,
+    pub document_hash: Option<[u8; 32]>, // Verification backing the change
+    pub timestamp: i64,
+}
+
+/// Emitted when a compliance-gated instruction touches a profile whose
+/// periodic review is overdue
+#[event]
+pub struct ComplianceReviewOverdue {
+    pub user: Pubkey,
+    pub review_due: i64,
+    pub escalation: ReviewEscalation,
+    pub timestamp: i64,
+}
+
+/// Emitted when a multisig transaction changes a region's policy; shares
+/// its sequence with `ComplianceAuditEntry`
+#[event]
+pub struct RegionPolicyAuditEntry {
+    pub sequence: u64,
+    pub region: ComplianceRegion,
+    pub old_policy: RegionPolicy,
+    pub new_policy: RegionPolicy,
+    pub transaction_id: u32, // Multisig transaction that made the change
+    pub timestamp: i64,
+}
+
+/// Emitted when a multisig transaction registers, changes or rotates the
+/// key of a screening provider; shares its sequence with
+/// `ComplianceAuditEntry`
+#[event]
+pub struct ScreeningProviderAuditEntry {
+    pub sequence: u64,
+    pub provider_id: u8,
+    pub old_key_hash: Option<[u8; 32]>, // None when first registered
+    pub new_key_hash: [u8; 32],
+    pub priority: u8,
+    pub active: bool,
+    pub transaction_id: u32,
+    pub timestamp: i64,
+}
+
+/// Emitted when a screening raises a compliance flag or an officer clears
+/// one; shares its sequence with `ComplianceAuditEntry`
+#[event]
+pub struct ComplianceFlagAuditEntry {
+    pub sequence: u64,
+    pub user: Pubkey,
+    pub flag: ComplianceFlagKind,
+    pub active: bool,
+    pub changed_by: Pubkey,
+    pub justification: Option<String>, // Required when clearing
+    pub timestamp: i64,
+}
+
+/// Emitted when an unverified user's lifetime volume crosses the KYC
+/// trigger for their region
+#[event]
+pub struct KycVerificationRequired {
+    pub user: Pubkey,
+    pub lifetime_volume: u64,
+    pub threshold: u64,
+    pub deadline: i64,       // Transactions are blocked after this until approved
+    pub timestamp: i64,
+}
+
+/// Emitted whenever a compliance freeze is applied or lifted
+#[event]
+pub struct AccountFreezeChanged {
+    pub user: Pubkey,
+    pub freeze_scope: FreezeScope, // Actions blocked after the change
+    pub changed_by: Pubkey,
+    pub timestamp: i64,
+}
+
+/// Emitted by a batch freeze or unfreeze; `succeeded[i]` says whether
+/// `profiles[i]` was changed. Each changed profile also gets its own
+/// `AccountFreezeChanged`.
+#[event]
+pub struct AccountsBatchFrozen {
+    pub profiles: Vec<Pubkey>,
+    pub succeeded: Vec<bool>,
+    pub frozen: bool, // False for an unfreeze
+    pub scope: FreezeScope,
+    pub changed_by: Pubkey,
+    pub timestamp: i64,
+}
+
 /// Compliance monitoring and reporting
 #[account]
 pub struct ComplianceReport {
@@ -492,6 +1711,105 @@ impl ComplianceReport {
         32 + // data_hash
         1; // bump
 }
+
+/// Merkle root over users' KYC profiles at a point in time, so the
+/// compliance state handed to a regulator can be proven against the chain
+#[account]
+pub struct ComplianceSnapshot {
+    pub snapshot_id: u64,
+    pub root: [u8; 32],      // Over `leaf` of each profile, in ascending user order
+    pub profile_count: u32,
+    pub taken_at: i64,
+    pub taken_by: Pubkey,    // Compliance officer who committed it
+    pub bump: u8,
+}
+
+impl ComplianceSnapshot {
+    pub const LEN: usize = 8 + // discriminator
+        8 + // snapshot_id
+        32 + // root
+        4 + // profile_count
+        8 + // taken_at
+        32 + // taken_by
+        1; // bump
+
+    /// Canonical serialization of a profile: its Borsh encoding, without
+    /// the account discriminator or trailing space
+    pub fn profile_data(profile: &KYCProfile) -> Result<Vec<u8>> {
+        profile.try_to_vec().map_err(|_| VaultError::InvalidSnapshotProfiles.into())
+    }
+
+    /// Root over `leaves`. Inner nodes hash their children in sorted order
+    /// and an unpaired node moves up a level as is.
+    pub fn merkle_root(leaves: &[[u8; 32]]) -> [u8; 32] {
+        let mut level = leaves.to_vec();
+        while level.len() > 1 {
+            level = Self::next_level(&level);
+        }
+        level.first().copied().unwrap_or([0; 32])
+    }
+
+    /// Sibling hashes proving `leaves[index]`, bottom-up
+    pub fn merkle_proof(leaves: &[[u8; 32]], mut index: usize) -> Vec<[u8; 32]> {
+        let mut proof = Vec::new();
+        let mut level = leaves.to_vec();
+        while level.len() > 1 {
+            if let Some(sibling) = level.get(index ^ 1) {
+                proof.push(*sibling);
+            }
+            level = Self::next_level(&level);
+            index /= 2;
+        }
+        proof
+    }
+
+    /// Whether `proof` shows a profile serialized as `profile_data` was in
+    /// this snapshot
+    pub fn verify_profile_inclusion(&self, profile_data: &[u8], proof: &[[u8; 32]]) -> bool {
+        merkle_proof_root(Self::leaf(profile_data), proof) == self.root
+    }
+
+    /// Leaf for a profile serialized by `profile_data`
+    pub fn leaf(profile_data: &[u8]) -> [u8; 32] {
+        merkle_leaf(profile_data)
+    }
+
+    fn next_level(level: &[[u8; 32]]) -> Vec<[u8; 32]> {
+        level.chunks(2)
+            .map(|pair| match pair {
+                [a, b] => merkle_node(a, b),
+                _ => pair[0],
+            })
+            .collect()
+    }
+}
+
+// Sorted-pair Merkle hashing for the sanctions list and compliance
+// snapshots. Leaves and inner nodes are domain-separated so one can't pose
+// as the other.
+fn merkle_leaf(data: &[u8]) -> [u8; 32] {
+    solana_program::hash::hashv(&[&[0u8], data]).to_bytes()
+}
+
+fn merkle_node(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (left, right) = if a <= b { (a, b) } else { (b, a) };
+    solana_program::hash::hashv(&[&[1u8], left, right]).to_bytes()
+}
+
+/// Root reached by hashing `leaf` with each sibling in `proof`, bottom-up
+fn merkle_proof_root(leaf: [u8; 32], proof: &[[u8; 32]]) -> [u8; 32] {
+    proof.iter().fold(leaf, |hash, sibling| merkle_node(&hash, sibling))
+}
+
+/// Emitted when a compliance snapshot root is committed
+#[event]
+pub struct ComplianceSnapshotCommitted {
+    pub snapshot_id: u64,
+    pub root: [u8; 32],
+    pub profile_count: u32,
+    pub taken_by: Pubkey,
+    pub timestamp: i64,
+}
 /// Compliance region for regulatory requirements
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
 pub enum ComplianceRegion {
@@ -505,6 +1823,24 @@ pub enum ComplianceRegion {
     Other(String),
 }
 
+impl ComplianceRegion {
+    pub const COUNT: usize = 8;
+
+    /// Position in per-region tables; all `Other` regions share the last slot
+    pub fn index(&self) -> usize {
+        match self {
+            ComplianceRegion::US => 0,
+            ComplianceRegion::EU => 1,
+            ComplianceRegion::UK => 2,
+            ComplianceRegion::Canada => 3,
+            ComplianceRegion::Australia => 4,
+            ComplianceRegion::Japan => 5,
+            ComplianceRegion::Singapore => 6,
+            ComplianceRegion::Other(_) => 7,
+        }
+    }
+}
+
 /// KYC verification data
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
 pub struct KYCVerification {
@@ -524,4 +1860,502 @@ pub struct AMLScreening {
     pub alerts: Vec<String>,
     pub sanctions_match: bool,
     pub pep_match: bool,
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile() -> KYCProfile {
+        KYCProfile {
+            user: Pubkey::new_unique(),
+            tier: KYCTier::None,
+            status: KYCStatus::NotStarted,
+            documents: Vec::new(),
+            compliance_screening: None,
+            commitment_limit: 100_000_000,
+            daily_limit: 10_000_000,
+            monthly_volume: 0,
+            last_screening_date: 0,
+            kyc_expiry_date: None,
+            created_at: 0,
+            updated_at: 0,
+            compliance_officer: None,
+            notes: String::new(),
+            region: None,
+            kyc_tier: KycTier::Tier0,
+            month_start: 0,
+            lifetime_volume: 0,
+            review_required: false,
+            next_review_due: 0,
+            review_escalation: ReviewEscalation::None,
+            freeze_scope: FreezeScope::default(),
+            kyc_required_at: 0,
+            compliance_flags: ComplianceFlags::default(),
+            bump: 0,
+        }
+    }
+
+    fn config() -> ComplianceConfig {
+        ComplianceConfig {
+            tier_limits: ComplianceConfig::default_tier_limits(),
+            audit_sequence: 0,
+            sanctions_root: [0; 32],
+            sanctions_root_updated_at: 0,
+            sanctions_max_staleness: 0,
+            travel_rule_thresholds: ComplianceConfig::default_travel_rule_thresholds(),
+            region_policies: ComplianceConfig::default_region_policies(),
+            high_value_threshold: ComplianceConfig::DEFAULT_HIGH_VALUE_THRESHOLD,
+            screening_max_age: ComplianceConfig::DEFAULT_SCREENING_MAX_AGE,
+            // Off here so tier limit tests aren't cut short; see the trigger test
+            kyc_trigger_thresholds: [0; ComplianceRegion::COUNT],
+            kyc_trigger_grace_periods: ComplianceConfig::default_kyc_triggers().1,
+            screening_providers: Vec::new(),
+            bump: 0,
+        }
+    }
+
+    fn verification(verified_by: Pubkey) -> KYCVerification {
+        KYCVerification {
+            document_type: DocumentType::Passport,
+            document_hash: [7; 32],
+            verification_date: 100,
+            verified_by,
+            expiry_date: None,
+        }
+    }
+
+    #[test]
+    fn test_tier_changes_need_a_verification_by_the_officer() {
+        let mut profile = profile();
+        let officer = Pubkey::new_unique();
+
+        assert!(profile.update_kyc_status(KYCStatus::Approved, KycTier::Tier1, None, officer, 200).is_err());
+        let other = verification(Pubkey::new_unique());
+        assert!(profile.update_kyc_status(KYCStatus::Approved, KycTier::Tier1, Some(&other), officer, 200).is_err());
+        assert!(profile.update_kyc_status(KYCStatus::Pending, KycTier::Tier1, Some(&verification(officer)), officer, 200).is_err());
+
+        profile.update_kyc_status(KYCStatus::Approved, KycTier::Tier2, Some(&verification(officer)), officer, 200).unwrap();
+        assert_eq!(profile.effective_tier(200), KycTier::Tier2);
+
+        // Suspending keeps the tier, but its limits stop applying
+        profile.update_kyc_status(KYCStatus::Suspended, KycTier::Tier2, None, officer, 300).unwrap();
+        assert_eq!(profile.effective_tier(300), KycTier::Tier0);
+        assert!(profile.update_kyc_status(KYCStatus::Approved, KycTier::Tier1, None, officer, 300).is_err());
+        let expiring = KYCVerification { expiry_date: Some(1_000), ..verification(officer) };
+        profile.update_kyc_status(KYCStatus::Approved, KycTier::Tier1, Some(&expiring), officer, 300).unwrap();
+        assert_eq!(profile.effective_tier(300), KycTier::Tier1);
+        assert_eq!(profile.effective_tier(1_001), KycTier::Tier0);
+    }
+
+    #[test]
+    fn test_transactions_are_held_to_tier_limits() {
+        let mut config = config();
+        let mut profile = profile();
+
+        // Tier0: $1k over the account's lifetime
+        profile.charge_transaction(&config, 600_000_000, 0).unwrap();
+        assert!(profile.charge_transaction(&config, 500_000_000, 100 * KYCProfile::MONTH_SECONDS).is_err());
+        profile.charge_transaction(&config, 400_000_000, 100 * KYCProfile::MONTH_SECONDS).unwrap();
+
+        // Tier1: $10k per 30-day window
+        let officer = Pubkey::new_unique();
+        profile.update_kyc_status(KYCStatus::Approved, KycTier::Tier1, Some(&verification(officer)), officer, 200).unwrap();
+        let start = 200 * KYCProfile::MONTH_SECONDS;
+        profile.charge_transaction(&config, 9_000_000_000, start).unwrap();
+        assert!(profile.charge_transaction(&config, 2_000_000_000, start + 10).is_err());
+        profile.charge_transaction(&config, 2_000_000_000, start + KYCProfile::MONTH_SECONDS).unwrap();
+        assert_eq!(profile.monthly_volume, 2_000_000_000);
+
+        config.tier_limits[1].per_transaction = 1_000_000_000;
+        assert!(profile.charge_transaction(&config, 1_500_000_000, start + KYCProfile::MONTH_SECONDS).is_err());
+        assert_eq!(profile.lifetime_volume, 12_000_000_000);
+
+        profile.status = KYCStatus::Suspended;
+        assert!(profile.charge_transaction(&config, 1, start + KYCProfile::MONTH_SECONDS).is_err());
+    }
+
+    fn screening(risk_level: RiskLevel) -> ComplianceScreening {
+        ComplianceScreening {
+            screening_id: "CHA_1".to_string(),
+            risk_level,
+            sanctions_match: false,
+            pep_match: false,
+            adverse_media: false,
+            screening_date: 100,
+            expiry_date: 1_000,
+            notes: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_sanctions_proofs_and_stale_roots() {
+        let mut config = config();
+        let listed = [[1; 32], [2; 32], [3; 32]];
+        let leaves = listed.map(|address| merkle_leaf(&address));
+        let pair = merkle_node(&leaves[0], &leaves[1]);
+        let root = merkle_node(&pair, &leaves[2]);
+
+        // No root yet: nothing is provable and every screening needs review
+        assert!(!config.check_sanctions(&listed[2], &[pair]));
+        assert_eq!(config.sanctions_verdict(&[9; 32], None, 100).unwrap(), SanctionsVerdict::ManualReview);
+
+        config.set_sanctions_root(root, 3_600, 100);
+        assert!(config.check_sanctions(&listed[0], &[leaves[1], leaves[2]]));
+        assert!(config.check_sanctions(&listed[1], &[leaves[0], leaves[2]]));
+        assert!(config.check_sanctions(&listed[2], &[pair]));
+        assert!(!config.check_sanctions(&[9; 32], &[pair]));
+        assert!(!config.check_sanctions(&listed[2], &[]));
+
+        assert_eq!(config.sanctions_verdict(&listed[2], Some(&[pair]), 200).unwrap(), SanctionsVerdict::Listed);
+        assert!(config.sanctions_verdict(&[9; 32], Some(&[pair]), 200).is_err());
+        assert_eq!(config.sanctions_verdict(&[9; 32], None, 200).unwrap(), SanctionsVerdict::Clear);
+        assert_eq!(config.sanctions_verdict(&[9; 32], None, 3_701).unwrap(), SanctionsVerdict::ManualReview);
+        assert_eq!(config.sanctions_verdict(&listed[2], Some(&[pair]), 3_701).unwrap(), SanctionsVerdict::Listed);
+    }
+
+    #[test]
+    fn test_aml_screening_suspends_listed_users_and_flags_stale_ones() {
+        let officer = Pubkey::new_unique();
+        let mut profile = profile();
+        profile.update_kyc_status(KYCStatus::Approved, KycTier::Tier2, Some(&verification(officer)), officer, 100).unwrap();
+
+        profile.record_aml_screening(screening(RiskLevel::Low), SanctionsVerdict::ManualReview, 200).unwrap();
+        assert!(profile.review_required);
+        assert_eq!(profile.effective_tier(200), KycTier::Tier0);
+        profile.update_kyc_status(KYCStatus::Approved, KycTier::Tier2, None, officer, 300).unwrap();
+        assert_eq!(profile.effective_tier(300), KycTier::Tier2);
+
+        // A proven listing blocks even a low-risk screening
+        profile.record_aml_screening(screening(RiskLevel::Low), SanctionsVerdict::Listed, 400).unwrap();
+        assert_eq!(profile.status, KYCStatus::Suspended);
+        let recorded = profile.compliance_screening.as_ref().unwrap();
+        assert!(recorded.sanctions_match);
+        assert_eq!(recorded.risk_level, RiskLevel::Prohibited);
+        assert!(profile.charge_transaction(&config(), 1, 400).is_err());
+    }
+
+    #[test]
+    fn test_travel_rule_thresholds_by_region() {
+        let mut config = config();
+        let other = ComplianceRegion::Other("Brazil".to_string());
+        assert!(!config.requires_travel_rule(Some(&ComplianceRegion::US), 2_000_000_000));
+        assert!(config.requires_travel_rule(Some(&ComplianceRegion::US), 3_000_000_001));
+        assert!(config.requires_travel_rule(Some(&ComplianceRegion::EU), 2_000_000_000));
+        assert!(config.requires_travel_rule(Some(&other), 2_000_000_000));
+
+        // Unknown regions get the lowest threshold in force
+        config.travel_rule_thresholds = [0; ComplianceRegion::COUNT];
+        config.travel_rule_thresholds[ComplianceRegion::Japan.index()] = 500_000_000;
+        assert!(!config.requires_travel_rule(Some(&ComplianceRegion::EU), u64::MAX));
+        assert!(config.requires_travel_rule(None, 600_000_000));
+        config.travel_rule_thresholds = [0; ComplianceRegion::COUNT];
+        assert!(!config.requires_travel_rule(None, u64::MAX));
+    }
+
+    #[test]
+    fn test_overdue_reviews_escalate_until_completed() {
+        const DAY: i64 = 24 * 3600;
+        let mut profile = profile();
+        let officer = Pubkey::new_unique();
+        assert!(!profile.review_overdue(i64::MAX));
+
+        // Approval schedules the first review; unscreened users count as medium risk
+        profile.update_kyc_status(KYCStatus::Approved, KycTier::Tier2, Some(&verification(officer)), officer, 200).unwrap();
+        let due = 200 + 180 * DAY;
+        assert_eq!(profile.next_review_due, due);
+        assert_eq!(profile.escalate_overdue_review(due + 30 * DAY), None);
+
+        assert_eq!(profile.escalate_overdue_review(due + 31 * DAY), Some(ReviewEscalation::Alerted));
+        assert_eq!(profile.escalate_overdue_review(due + 32 * DAY), None);
+        assert_eq!(profile.effective_tier(due + 32 * DAY), KycTier::Tier2);
+        assert_eq!(profile.escalate_overdue_review(due + 61 * DAY), Some(ReviewEscalation::Restricted));
+        assert_eq!(profile.effective_tier(due + 61 * DAY), KycTier::Tier0);
+
+        // A high-risk screening shortens the interval
+        profile.record_aml_screening(screening(RiskLevel::High), SanctionsVerdict::Clear, due + 62 * DAY).unwrap();
+        profile.complete_compliance_review(officer, due + 62 * DAY);
+        assert_eq!(profile.review_escalation, ReviewEscalation::None);
+        assert_eq!(profile.next_review_due, due + 152 * DAY);
+        assert_eq!(profile.effective_tier(due + 62 * DAY), KycTier::Tier2);
+    }
+
+    #[test]
+    fn test_freeze_scopes_block_only_their_actions() {
+        let mut profile = profile();
+        let officer = Pubkey::new_unique();
+        assert!(profile.freeze(&FreezeScope::default(), officer, String::new(), 10).is_err());
+
+        // Block withdrawals only; deposits and claims still go through
+        let decommit = FreezeScope { block_decommit: true, ..FreezeScope::default() };
+        profile.freeze(&decommit, officer, "Investigation".to_string(), 10).unwrap();
+        assert!(profile.require_not_frozen(FrozenAction::Decommit).is_err());
+        profile.require_not_frozen(FrozenAction::Commit).unwrap();
+        profile.require_not_frozen(FrozenAction::Claims).unwrap();
+
+        // Freezes add up and unfreezing clears only the bits asked for
+        profile.freeze(&FreezeScope { block_payments: true, ..FreezeScope::default() }, officer, String::new(), 20)
+            .unwrap();
+        assert!(profile.require_not_frozen(FrozenAction::Payments).is_err());
+        assert!(profile.unfreeze(&decommit, officer, 30).unwrap());
+        profile.require_not_frozen(FrozenAction::Decommit).unwrap();
+        assert!(profile.require_not_frozen(FrozenAction::Payments).is_err());
+        assert!(!profile.unfreeze(&FreezeScope::full(), officer, 40).unwrap());
+        assert!(!profile.freeze_scope.any());
+
+        // A full freeze blocks everything, as does a suspended KYC status
+        profile.freeze(&FreezeScope::full(), officer, String::new(), 50).unwrap();
+        assert!(profile.require_not_frozen(FrozenAction::Channels).is_err());
+        profile.unfreeze(&FreezeScope::full(), officer, 60).unwrap();
+        profile.status = KYCStatus::Suspended;
+        assert!(profile.require_not_frozen(FrozenAction::Claims).is_err());
+    }
+
+    #[test]
+    fn test_screening_history_is_bounded_and_hash_chained() {
+        const DAY: i64 = 24 * 3600;
+        let mut config = config();
+        let provider = |key_hash| ScreeningProvider {
+            provider_id: 1,
+            key_hash,
+            priority: 0,
+            active: true,
+            key_rotated_at: 0,
+        };
+        assert_eq!(config.set_screening_provider(provider([1; 32])).unwrap(), None);
+        let record = |risk_score, screened_at| ScreeningRecord {
+            sequence: 0,
+            provider_id: 1,
+            key_hash: [1; 32],
+            risk_score,
+            risk_level: RiskLevel::Low,
+            categories: ScreeningCategories::default(),
+            screened_at,
+            expires_at: screened_at + 90 * DAY,
+            prev_hash: [0; 32],
+        };
+        let mut history = ScreeningHistory {
+            user: Pubkey::default(),
+            records: Vec::new(),
+            record_count: 0,
+            head_hash: [0; 32],
+            bump: 0,
+        };
+        history.initialize(Pubkey::new_unique(), 255);
+        history.verify_chain().unwrap();
+        assert!(history.record(record(101, 0)).is_err());
+
+        for i in 0..14 {
+            history.record(record(10, i * DAY)).unwrap();
+        }
+        let records = history.ordered();
+        assert_eq!(records.len(), ScreeningHistory::HISTORY_LEN);
+        assert_eq!(records[0].sequence, 2);
+        history.verify_chain().unwrap();
+
+        // Editing a kept record breaks the link from the one after it
+        let mut tampered = ScreeningHistory { records: history.records.clone(), ..history };
+        tampered.records.iter_mut().find(|r| r.sequence == 5).unwrap().risk_score = 0;
+        assert!(tampered.verify_chain().is_err());
+
+        // High-value transactions need a current screening within the max age
+        let flags = ComplianceFlags::default();
+        let latest = history.latest_current(&config, 13 * DAY).unwrap();
+        assert_eq!(latest.sequence, 13);
+        assert!(!config.requires_rescreening(Some(latest), 50_000_000_000, &flags, 43 * DAY));
+        assert!(config.requires_rescreening(Some(latest), 50_000_000_000, &flags, 44 * DAY));
+        assert!(!config.requires_rescreening(Some(latest), 1_000_000_000, &flags, 44 * DAY));
+        assert!(config.requires_rescreening(None, 50_000_000_000, &flags, 0));
+        assert!(history.latest_current(&config, 200 * DAY).is_none());
+
+        // Rotating the provider's key leaves earlier results stale
+        config.check_screening_source(1, &[1; 32]).unwrap();
+        assert_eq!(config.rotate_screening_provider_key(1, [2; 32], 14 * DAY).unwrap(), [1; 32]);
+        assert!(config.check_screening_source(1, &[1; 32]).is_err());
+        assert!(config.check_screening_source(2, &[2; 32]).is_err());
+        assert!(history.latest_current(&config, 14 * DAY).is_none());
+        assert!(config.rotate_screening_provider_key(1, [2; 32], 15 * DAY).is_err());
+    }
+
+    #[test]
+    fn test_screening_provider_registry_is_bounded() {
+        let mut config = config();
+        let provider = |provider_id, priority| ScreeningProvider {
+            provider_id,
+            key_hash: [provider_id + 1; 32],
+            priority,
+            active: true,
+            key_rotated_at: 0,
+        };
+        for id in 0..ComplianceConfig::MAX_SCREENING_PROVIDERS as u8 {
+            config.set_screening_provider(provider(id, id)).unwrap();
+        }
+        assert!(config.set_screening_provider(provider(9, 0)).is_err());
+        assert!(config.set_screening_provider(ScreeningProvider { key_hash: [0; 32], ..provider(0, 0) }).is_err());
+
+        // Deactivating a provider stops its results counting
+        let old = config.set_screening_provider(ScreeningProvider { active: false, ..provider(2, 5) }).unwrap();
+        assert_eq!(old, Some(provider(2, 2)));
+        assert!(!config.is_current_screening_source(2, &[3; 32]));
+        assert!(config.is_current_screening_source(3, &[4; 32]));
+    }
+
+    #[test]
+    fn test_snapshot_proves_each_profile_included() {
+        let profiles = (0..5).map(|_| profile()).collect::<Vec<_>>();
+        let data = profiles.iter()
+            .map(|p| ComplianceSnapshot::profile_data(p).unwrap())
+            .collect::<Vec<_>>();
+        let leaves = data.iter().map(|d| ComplianceSnapshot::leaf(d)).collect::<Vec<_>>();
+        let snapshot = ComplianceSnapshot {
+            snapshot_id: 1,
+            root: ComplianceSnapshot::merkle_root(&leaves),
+            profile_count: 5,
+            taken_at: 0,
+            taken_by: Pubkey::default(),
+            bump: 0,
+        };
+
+        // The odd profile out moves up unpaired, so its proof is shorter
+        for (index, profile_data) in data.iter().enumerate() {
+            let proof = ComplianceSnapshot::merkle_proof(&leaves, index);
+            assert!(snapshot.verify_profile_inclusion(profile_data, &proof));
+        }
+        assert_eq!(ComplianceSnapshot::merkle_proof(&leaves, 4).len(), 1);
+
+        // A profile changed since the snapshot doesn't verify
+        let mut changed = profiles[2].clone();
+        changed.lifetime_volume = 1;
+        let proof = ComplianceSnapshot::merkle_proof(&leaves, 2);
+        assert!(!snapshot.verify_profile_inclusion(&ComplianceSnapshot::profile_data(&changed).unwrap(), &proof));
+    }
+
+    #[test]
+    fn test_activity_pushes_unverified_users_to_kyc() {
+        const DAY: i64 = 24 * 3600;
+        let mut config = config();
+        let (thresholds, grace_periods) = ComplianceConfig::default_kyc_triggers();
+        let mut uk = thresholds;
+        uk[ComplianceRegion::UK.index()] = 300_000_000;
+        config.set_kyc_triggers(uk, grace_periods).unwrap();
+        assert!(config.set_kyc_triggers(thresholds, [-1; ComplianceRegion::COUNT]).is_err());
+        assert_eq!(config.kyc_trigger_threshold(None), 300_000_000);
+
+        // Crossing $500 starts the week's grace
+        let mut profile = profile();
+        profile.region = Some(ComplianceRegion::EU);
+        profile.charge_transaction(&config, 400_000_000, 0).unwrap();
+        assert_eq!(profile.status, KYCStatus::NotStarted);
+        profile.charge_transaction(&config, 100_000_000, 10).unwrap();
+        assert_eq!(profile.status, KYCStatus::RequiredPending);
+        assert_eq!(profile.kyc_required_at, 10);
+        profile.charge_transaction(&config, 100_000_000, 10 + 7 * DAY).unwrap();
+        assert!(profile.charge_transaction(&config, 1, 11 + 7 * DAY).is_err());
+
+        // Verification in progress doesn't lift the block; approval does
+        profile.status = KYCStatus::Pending;
+        assert!(profile.charge_transaction(&config, 1, 11 + 7 * DAY).is_err());
+        let officer = Pubkey::new_unique();
+        profile.update_kyc_status(KYCStatus::Approved, KycTier::Tier1, Some(&verification(officer)), officer, 200)
+            .unwrap();
+        profile.charge_transaction(&config, 1, 11 + 7 * DAY).unwrap();
+    }
+
+    #[test]
+    fn test_compliance_flags_apply_their_own_policies() {
+        let mut profile = profile();
+        let officer = Pubkey::new_unique();
+        profile.update_kyc_status(KYCStatus::Approved, KycTier::Tier2, Some(&verification(officer)), officer, 200).unwrap();
+        let flags = &mut profile.compliance_flags;
+        assert!(!flags.requires_manual_review(u64::MAX));
+        assert!(flags.clear(ComplianceFlagKind::Pep, officer, 300).is_err());
+
+        // PEPs go to manual review above $5k whatever their tier
+        assert!(flags.raise(ComplianceFlagKind::Pep, officer, 300));
+        assert!(!flags.raise(ComplianceFlagKind::Pep, officer, 310));
+        assert_eq!(flags.is_pep.set_at, 300);
+        assert!(!flags.requires_manual_review(ComplianceFlags::PEP_REVIEW_THRESHOLD));
+        assert!(flags.requires_manual_review(ComplianceFlags::PEP_REVIEW_THRESHOLD + 1));
+        flags.clear(ComplianceFlagKind::Pep, officer, 400).unwrap();
+        assert_eq!((flags.is_pep.active, flags.is_pep.cleared_at), (false, 400));
+        assert!(!flags.requires_manual_review(u64::MAX));
+
+        flags.raise(ComplianceFlagKind::SanctionsAdjacent, officer, 500);
+        assert!(flags.requires_manual_review(1));
+
+        // Adverse media needs a recent screening for any amount
+        let config = config();
+        flags.raise(ComplianceFlagKind::AdverseMedia, officer, 500);
+        assert!(config.requires_rescreening(None, 1, flags, 500));
+
+        // High-risk jurisdictions are held to Tier1
+        assert_eq!(profile.effective_tier(600), KycTier::Tier2);
+        profile.compliance_flags.raise(ComplianceFlagKind::HighRiskJurisdiction, officer, 600);
+        assert_eq!(profile.effective_tier(600), KycTier::Tier1);
+    }
+
+    #[test]
+    fn test_compliance_check_collects_every_blocking_reason() {
+        let config = config();
+        let mut profile = profile();
+        let officer = Pubkey::new_unique();
+        let check = profile.check_transaction(&config, FrozenAction::Payments, 1, false, None, 0);
+        assert!(check.allowed);
+        check.enforce().unwrap();
+
+        // A frozen PEP over Tier0's lifetime limit fails three ways at once
+        profile.compliance_flags.raise(ComplianceFlagKind::Pep, officer, 0);
+        profile.freeze(&FreezeScope::full(), officer, "Investigation".to_string(), 0).unwrap();
+        let amount = ComplianceFlags::PEP_REVIEW_THRESHOLD + 1;
+        let check = profile.check_transaction(&config, FrozenAction::Payments, amount, true, None, 0);
+        assert!(!check.allowed);
+        assert_eq!(check.blocking_reasons, vec![
+            ComplianceBlock::AccountFrozen,
+            ComplianceBlock::ManualReviewRequired,
+            ComplianceBlock::LifetimeLimitExceeded,
+        ]);
+        assert!(check.required_actions.contains(&ComplianceAction::ContactCompliance));
+        assert!(check.enforce().is_err());
+
+        // Checking is read-only; manual review only applies to outbound actions
+        assert_eq!(profile.lifetime_volume, 0);
+        let check = profile.check_transaction(&config, FrozenAction::Commit, amount, true, None, 0);
+        assert!(!check.blocking_reasons.contains(&ComplianceBlock::ManualReviewRequired));
+    }
+
+    #[test]
+    fn test_region_policies_gate_features() {
+        let mut config = config();
+        let us = config.region_policy(Some(&ComplianceRegion::US));
+        assert!(us.check(RegionFeature::HftChannel).is_err());
+        assert!(us.check(RegionFeature::LightningPayout).is_ok());
+        assert!(config.region_policy(Some(&ComplianceRegion::EU)).check(RegionFeature::HftChannel).is_ok());
+        // Unknown regions get the strictest policy anywhere
+        assert!(config.region_policy(None).check(RegionFeature::HftChannel).is_err());
+
+        // 2 BTC at $50k is $100k
+        let commitment = |btc_price_usd| RegionFeature::Commitment { sats: 200_000_000, btc_price_usd };
+        let old = config.set_region_policy(
+            &ComplianceRegion::Other("Brazil".to_string()),
+            RegionPolicy { max_commitment_usd: 100_000_000_000, ..RegionPolicy::unrestricted() },
+        );
+        assert_eq!(old, RegionPolicy::unrestricted());
+        let brazil = config.region_policy(Some(&ComplianceRegion::Other("Chile".to_string())));
+        assert!(brazil.check(commitment(Some(5_000_000_000_000))).is_ok());
+        assert!(brazil.check(commitment(Some(5_000_001_000_000))).is_err());
+        // A capped region can't value a commitment without a fresh price
+        assert!(brazil.check(commitment(None)).is_err());
+        assert!(config.region_policy(Some(&ComplianceRegion::EU)).check(commitment(None)).is_ok());
+        assert_eq!(config.region_policy(None).max_commitment_usd, 100_000_000_000);
+    }
+
+    #[test]
+    fn test_higher_tiers_cannot_have_tighter_limits() {
+        let mut config = config();
+        let mut limits = ComplianceConfig::default_tier_limits();
+        limits[2].monthly = 5_000_000_000;
+        assert!(config.set_tier_limits(limits.clone()).is_err());
+        limits[2].monthly = 50_000_000_000;
+        config.set_tier_limits(limits).unwrap();
+        assert_eq!(config.limits_for(KycTier::Tier2).monthly, 50_000_000_000);
+    }
+}
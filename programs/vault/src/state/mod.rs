@@ -5,6 +5,7 @@ pub mod rewards;
 pub mod state_channel;
 pub mod enhanced_state_channel;
 pub mod multisig_wallet;
+pub mod emergency_state;
 pub mod payment_system;
 pub mod kyc_compliance;
 pub mod authentication;
@@ -20,6 +21,7 @@ pub use rewards::*;
 pub use state_channel::*;
 pub use enhanced_state_channel::*;
 pub use multisig_wallet::*;
+pub use emergency_state::*;
 pub use payment_system::*;
 pub use kyc_compliance::*;
 pub use authentication::*;
@@ -1,27 +1,93 @@
 use anchor_lang::prelude::*;
+use solana_program::hash::{hash, hashv};
+use crate::crypto::HsmAttestationVerifier;
 use crate::errors::VaultError;
+use crate::state::kyc_compliance::{ComplianceRegion, FreezeScope, KYCProfile, RegionPolicy, ScreeningProvider};
+use crate::state::payment_system::PaymentMethod;
+use crate::state::rewards::RewardPool;
 
 /// HSM key information for Yubico HSM integration
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
 pub struct HSMKeyInfo {
     pub key_id: u16,           // HSM key slot ID
     pub public_key: [u8; 32],  // Public key derived from HSM
     pub key_label: String,     // Human-readable key label
+    pub vendor: HsmVendor,     // Manufacturer of the HSM holding the key
+    pub attestation_chain_hash: [u8; 32], // Hash of the vendor attestation certificate chain
     pub created_at: i64,       // Key creation timestamp
     pub last_used: i64,        // Last usage timestamp
     pub is_active: bool,       // Whether key is active
     pub usage_count: u64,      // Number of times key has been used
 }
 
-/// Signer information with HSM integration
+/// HSM manufacturers whose attestations are accepted
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub enum HsmVendor {
+    Yubico,      // YubiHSM 2
+    Thales,      // Thales Luna
+    AwsCloudHsm, // AWS CloudHSM
+}
+
+/// Proof that a signer's configured HSM key approved a proposal digest
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct HsmAttestation {
+    pub vendor: HsmVendor,
+    pub cert_chain_hash: [u8; 32], // Must match the signer's configured chain
+    pub signature: [u8; 64],       // HSM key's signature over the attestation message
+}
+
+/// Signer information with HSM integration
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
 pub struct SignerInfo {
     pub pubkey: Pubkey,        // Solana public key
     pub hsm_key: Option<HSMKeyInfo>, // Associated HSM key (if any)
     pub role: SignerRole,      // Role of the signer
+    pub weight: u8,            // Signing power counted toward the threshold
     pub added_at: i64,         // When signer was added
     pub last_signature: i64,   // Last signature timestamp
     pub is_active: bool,       // Whether signer is active
+    pub fast_path_day: i64,    // UTC day (days since the epoch) `fast_path_spent` covers
+    pub fast_path_spent: u64,  // Fast path transfers approved on `fast_path_day`
+    pub vetoed_at: [i64; 3],   // Latest guardian vetoes of the signer's proposals, oldest first
+    pub flagged_for_review: bool, // Set when vetoes of the signer's proposals pile up
+}
+
+impl SignerInfo {
+    /// Reset signing statistics for a key joining the wallet at `now`
+    fn start_history(&mut self, now: i64) {
+        self.added_at = now;
+        self.last_signature = 0;
+        self.fast_path_day = 0;
+        self.fast_path_spent = 0;
+        self.vetoed_at = [0; 3];
+        self.flagged_for_review = false;
+        if let Some(hsm_key) = self.hsm_key.as_mut() {
+            hsm_key.last_used = 0;
+            hsm_key.usage_count = 0;
+        }
+    }
+
+    /// Fast path spend counted against the signer's limit on `day`
+    fn fast_path_spent_on(&self, day: i64) -> u64 {
+        if self.fast_path_day == day { self.fast_path_spent } else { 0 }
+    }
+
+    /// Keep a continuing signer's statistics across a rotation. HSM usage
+    /// carries over only when the HSM key itself is unchanged.
+    fn carry_history_from(&mut self, previous: &SignerInfo) {
+        self.added_at = previous.added_at;
+        self.last_signature = previous.last_signature;
+        self.fast_path_day = previous.fast_path_day;
+        self.fast_path_spent = previous.fast_path_spent;
+        self.vetoed_at = previous.vetoed_at;
+        self.flagged_for_review = previous.flagged_for_review;
+        if let (Some(hsm_key), Some(previous_key)) = (self.hsm_key.as_mut(), previous.hsm_key.as_ref()) {
+            if hsm_key.public_key == previous_key.public_key {
+                hsm_key.last_used = previous_key.last_used;
+                hsm_key.usage_count = previous_key.usage_count;
+            }
+        }
+    }
 }
 
 /// Roles for multisig signers
@@ -30,6 +96,7 @@ pub enum SignerRole {
     Admin,      // Full administrative access
     Operator,   // Operational transactions only
     Emergency,  // Emergency operations only
+    Compliance, // Account freezes and other compliance actions
 }
 
 /// Transaction types for different operations
@@ -41,6 +108,17 @@ pub enum TransactionType {
     ConfigUpdate,        // Protocol configuration updates
     EmergencyAction,     // Emergency operations
     KeyRotation,         // Key rotation operations
+    RewardRateChange,    // User reward share change (timelocked)
+    ThresholdChange,     // Approval threshold change (timelocked)
+    AccountFreeze,       // Freeze a user account (compliance co-signed)
+}
+
+impl TransactionType {
+    pub const COUNT: usize = 9;
+
+    pub fn index(&self) -> usize {
+        self.clone() as usize
+    }
 }
 
 /// Transaction priority levels
@@ -52,69 +130,678 @@ pub enum TransactionPriority {
     Emergency, // Emergency operations
 }
 
+/// Typed payload of a multisig transaction, Borsh-encoded in
+/// `transaction_data`. Threshold changes keep their single-byte payload and
+/// carry no action; other threshold-change payloads set the fast path.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub enum MultisigAction {
+    TreasuryTransfer {
+        to: Pubkey,   // Destination token account
+        mint: Pubkey,
+        amount: u64,
+    },
+    UpdateRewardRates {
+        bps: u16,     // New user share in basis points
+    },
+    OracleFeedChange {
+        btc_usd_feed: Pubkey,
+    },
+    FreezeAccount {
+        user: Pubkey,
+        reason: String,
+        scope: Option<FreezeScope>, // None freezes everything
+    },
+    UpdateAuthConfig {
+        require_2fa_globally: Option<bool>,
+        session_timeout_min: Option<u32>,
+        session_timeout_max: Option<u32>,
+        max_failed_attempts: Option<u32>,
+        lockout_duration: Option<i64>,
+    },
+    RequireReverification {
+        before_ts: i64,             // Factors last used before this must be verified again
+        expires_at: i64,            // Campaign end; normal verification resumes by itself
+        exempt_users: Vec<Pubkey>,  // Users left out of the campaign
+    },
+    Custom {
+        program: Pubkey, // Invoked with the multisig wallet as signer
+        data: Vec<u8>,
+    },
+    AddSigner {
+        signer: SignerInfo,
+    },
+    RemoveSigner {
+        signer: Pubkey,
+    },
+    ReplaceSigner {
+        old: Pubkey,
+        new: Pubkey,
+        hsm_key: Option<HSMKeyInfo>, // New signer's HSM key; takes over role and weight
+    },
+    SetFastPathLimits {
+        daily_limit: u64,       // Per-signer daily cap on fast path transfers
+        reduced_threshold: u8,  // Weight a fast path transfer needs; both zero disables
+    },
+    SetGuardian {
+        guardian: Option<Pubkey>, // None removes the guardian
+    },
+    PaymentApproval {
+        payment_id: u64,     // Payment request awaiting multisig approval
+        approval_round: u8,  // Must match the request's; a risk tier or destination change makes the approval stale
+    },
+    TopUpPaymentFloat {
+        method: PaymentMethod, // Lightning adds channel budget; token methods fund their treasury ATA
+        amount: u64,
+    },
+    SetSanctionsRoot {
+        root: [u8; 32],      // Merkle root of sanctioned address hashes
+        max_staleness: i64,  // Seconds before screenings fall back to manual review
+    },
+    SetRegionPolicy {
+        region: ComplianceRegion, // Every `Other` region shares one policy
+        policy: RegionPolicy,
+    },
+    SetScreeningProvider {
+        provider_id: u8,
+        key_hash: [u8; 32], // Hash of the provider's API key; the key stays off-chain
+        priority: u8,
+        active: bool,
+    },
+    RotateScreeningProviderKey {
+        provider_id: u8,
+        new_key_hash: [u8; 32],
+    },
+    ApproveTreasuryStream {
+        stream: Pubkey,     // Treasury-funded payment stream awaiting approval
+        recipient: Pubkey,  // Must match the stream's
+        deposit: u64,       // Must match the stream's; taken from the user rewards pool
+    },
+}
+
+impl MultisigAction {
+    /// Whether a transaction of the given type may carry this action
+    pub fn is_permitted_for(&self, tx_type: &TransactionType) -> bool {
+        matches!(
+            (tx_type, self),
+            (
+                TransactionType::TreasuryTransfer,
+                MultisigAction::TreasuryTransfer { .. } | MultisigAction::TopUpPaymentFloat { .. }
+            )
+                | (TransactionType::RewardRateChange, MultisigAction::UpdateRewardRates { .. })
+                | (
+                    TransactionType::ConfigUpdate,
+                    MultisigAction::OracleFeedChange { .. }
+                        | MultisigAction::UpdateAuthConfig { .. }
+                        | MultisigAction::RequireReverification { .. }
+                        | MultisigAction::SetSanctionsRoot { .. }
+                        | MultisigAction::SetRegionPolicy { .. }
+                        | MultisigAction::SetScreeningProvider { .. }
+                        | MultisigAction::RotateScreeningProviderKey { .. }
+                )
+                | (TransactionType::AccountFreeze, MultisigAction::FreezeAccount { .. })
+                | (
+                    TransactionType::RewardDistribution,
+                    MultisigAction::PaymentApproval { .. } | MultisigAction::ApproveTreasuryStream { .. }
+                )
+                | (TransactionType::ThresholdChange, MultisigAction::SetFastPathLimits { .. })
+                | (
+                    TransactionType::KeyRotation,
+                    MultisigAction::AddSigner { .. }
+                        | MultisigAction::RemoveSigner { .. }
+                        | MultisigAction::ReplaceSigner { .. }
+                        | MultisigAction::SetGuardian { .. }
+                )
+                | (
+                    TransactionType::StakingOperation
+                    | TransactionType::RewardDistribution
+                    | TransactionType::EmergencyAction,
+                    MultisigAction::Custom { .. }
+                )
+        )
+    }
+
+    /// Whether the action touches only wallet-wide accounts, so a batch can
+    /// run it without accounts specific to the transaction
+    pub fn is_batchable(&self) -> bool {
+        matches!(
+            self,
+            MultisigAction::UpdateRewardRates { .. }
+                | MultisigAction::OracleFeedChange { .. }
+                | MultisigAction::UpdateAuthConfig { .. }
+                | MultisigAction::RequireReverification { .. }
+                | MultisigAction::SetFastPathLimits { .. }
+                | MultisigAction::SetGuardian { .. }
+        )
+    }
+
+    /// Check the action's own parameters
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            MultisigAction::TreasuryTransfer { amount: 0, .. }
+            | MultisigAction::TopUpPaymentFloat { amount: 0, .. }
+            | MultisigAction::ApproveTreasuryStream { deposit: 0, .. } => {
+                Err(VaultError::InvalidAllocation.into())
+            },
+            MultisigAction::UpdateRewardRates { bps } => RewardPool::validate_user_share_bps(*bps),
+            MultisigAction::FreezeAccount { reason, .. } if reason.len() > KYCProfile::MAX_NOTES_LENGTH => {
+                Err(VaultError::ReasonTooLong.into())
+            },
+            MultisigAction::FreezeAccount { scope: Some(scope), .. } if !scope.any() => {
+                Err(VaultError::InvalidFreezeScope.into())
+            },
+            MultisigAction::UpdateAuthConfig {
+                session_timeout_min: Some(min),
+                session_timeout_max: Some(max),
+                ..
+            } if min > max => Err(VaultError::InvalidSessionTimeout.into()),
+            MultisigAction::RequireReverification { before_ts, expires_at, .. } if before_ts >= expires_at => {
+                Err(VaultError::InvalidReverificationCampaign.into())
+            },
+            // The fast path is either fully configured or off
+            MultisigAction::SetFastPathLimits { daily_limit, reduced_threshold }
+                if (*daily_limit == 0) != (*reduced_threshold == 0) =>
+            {
+                Err(VaultError::InvalidThresholdValue.into())
+            },
+            MultisigAction::SetSanctionsRoot { root, max_staleness } if *root == [0; 32] || *max_staleness <= 0 => {
+                Err(VaultError::InvalidSanctionsRoot.into())
+            },
+            MultisigAction::SetScreeningProvider { key_hash, .. }
+            | MultisigAction::RotateScreeningProviderKey { new_key_hash: key_hash, .. }
+                if *key_hash == [0; 32] =>
+            {
+                Err(VaultError::InvalidScreeningProvider.into())
+            },
+            // No re-entering the vault program with the multisig's signature
+            MultisigAction::Custom { program, .. } if *program == crate::ID => {
+                Err(VaultError::InvalidMultisigAction.into())
+            },
+            _ => Ok(()),
+        }
+    }
+}
+
+/// One state change a multisig transaction would make
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub enum Effect {
+    TokenTransfer {
+        from: Pubkey,
+        to: Pubkey,
+        mint: Pubkey,
+        amount: u64,
+    },
+    RewardRateChange {
+        old_user_share_bps: u16,
+        new_user_share_bps: u16,
+    },
+    OracleFeedChange {
+        old_feed: Pubkey,
+        new_feed: Pubkey,
+    },
+    AccountFreeze {
+        user: Pubkey,
+        scope: FreezeScope,
+    },
+    AuthConfigUpdate,
+    ProgramInvocation {
+        program: Pubkey, // The invoked program's own checks are not simulated
+        data_len: u32,
+    },
+    ThresholdChange {
+        old_threshold: u8,
+        new_threshold: u8,
+    },
+    SignerAdded {
+        signer: Pubkey,
+    },
+    SignerRemoved {
+        signer: Pubkey,
+    },
+    SignerReplaced {
+        old: Pubkey,
+        new: Pubkey,
+    },
+    SignaturesRevoked {
+        transaction_id: u32,
+        still_approved: bool,
+    },
+    FastPathLimitsChange {
+        daily_limit: u64,
+        reduced_threshold: u8,
+    },
+    FastPathSpend {
+        signers: Vec<Pubkey>, // Approvers charged against their daily limits
+        amount: u64,
+    },
+    GuardianChange {
+        old_guardian: Option<Pubkey>,
+        new_guardian: Option<Pubkey>,
+    },
+    PaymentApproved {
+        payment_id: u64,
+        amount: u64,
+    },
+    PaymentFloatTopUp {
+        method: PaymentMethod,
+        amount: u64,
+    },
+    SanctionsRootChange {
+        old_root: [u8; 32],
+        new_root: [u8; 32],
+    },
+    RegionPolicyChange {
+        region: ComplianceRegion,
+        old_policy: RegionPolicy,
+        new_policy: RegionPolicy,
+    },
+    ScreeningProviderChange {
+        old_provider: Option<ScreeningProvider>,
+        new_provider: ScreeningProvider,
+    },
+    ScreeningProviderKeyRotation {
+        provider_id: u8,
+        old_key_hash: [u8; 32],
+        new_key_hash: [u8; 32],
+    },
+    TreasuryStreamApproved {
+        stream: Pubkey,
+        recipient: Pubkey,
+        deposit: u64,
+    },
+}
+
+/// Dry-run outcome of a multisig transaction, returned to the caller
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct SimulationResult {
+    pub would_succeed: bool,
+    pub failure_reason: Option<String>, // First precondition execution would fail on
+    pub effects: Vec<Effect>,           // Empty unless execution would succeed
+}
+
+impl SimulationResult {
+    pub fn succeeded(effects: Vec<Effect>) -> Self {
+        Self {
+            would_succeed: true,
+            failure_reason: None,
+            effects,
+        }
+    }
+
+    pub fn failed(error: &Error) -> Self {
+        Self {
+            would_succeed: false,
+            failure_reason: Some(MultisigTransaction::bounded_result(error.to_string())),
+            effects: Vec::new(),
+        }
+    }
+}
+
+/// What a signer did to a transaction, as kept in the activity log
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum ActivityKind {
+    Propose,
+    Sign,
+    Execute,
+    Cancel,
+    Veto,
+}
+
+/// One entry in a wallet's activity log
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct ActivityEntry {
+    pub kind: ActivityKind,
+    pub actor: Pubkey,        // Proposer, signer, executor or guardian
+    pub transaction_id: u32,
+    pub timestamp: i64,
+}
+
+impl ActivityEntry {
+    pub const LEN: usize = 1 + 32 + 4 + 8;
+}
+
+/// A page of the activity log, returned to the caller
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct ActivityPage {
+    pub start: u64,          // Sequence number of the first entry, past the cursor if those were overwritten
+    pub entries: Vec<ActivityEntry>,
+    pub next_cursor: u64,    // Cursor for the following page, `total` once caught up
+    pub total: u64,          // Entries ever recorded
+}
+
 #[account]
 pub struct MultisigWallet {
     pub signers: Vec<SignerInfo>,
-    pub threshold: u8,              // 2-of-3 requirement
+    pub threshold: u8,              // Signing weight required, between 1 and the active signers' total weight
     pub transaction_count: u32,     // Total transactions proposed
     pub executed_count: u32,        // Total transactions executed
     pub hsm_enabled: bool,          // Whether HSM is required
+    pub hsm_quorum: u8,             // HSM-attested approvals required when HSM is enabled
     pub emergency_mode: bool,       // Emergency mode status
     pub last_key_rotation: i64,     // Last key rotation timestamp
     pub key_rotation_interval: i64, // Required rotation interval (seconds)
     pub created_at: i64,           // Wallet creation timestamp
+    pub timelock_secs: [i64; TransactionType::COUNT], // Approval-to-execution delay, indexed by `TransactionType`
+    pub required_roles: [Option<SignerRole>; TransactionType::COUNT], // Role that must co-sign, indexed by `TransactionType`
+    pub fast_path_daily_limit: u64, // Per-signer daily cap on fast path treasury transfers
+    pub fast_path_threshold: u8,    // Weight a fast path transfer needs, zero when disabled
+    pub guardian: Option<Pubkey>,   // May veto approved transactions during their timelock
+    pub activity: Vec<ActivityEntry>, // Latest activity, entry `n` at `n % ACTIVITY_LOG_LEN`
+    pub activity_count: u64,        // Activity entries ever recorded
+    pub approval_nonce: u64,        // Bound into offline approvals, advanced per applied bundle
+    pub parent_wallet: Option<Pubkey>, // Wallet that may rotate signers and override emergencies; set at initialization only
     pub bump: u8,
 }
 
 impl MultisigWallet {
     pub const LEN: usize = 8 + // discriminator
-        4 + (3 * (32 + (2 + 32 + 32 + 8 + 8 + 1 + 8) + 1 + 8 + 8 + 1)) + // signers with HSM info
+        4 + (3 * (32 + (2 + 32 + 32 + 1 + 32 + 8 + 8 + 1 + 8) + 1 + 1 + 8 + 8 + 1 + 8 + 8 + 8 * 3 + 1)) + // signers with HSM info
         1 + // threshold
         4 + // transaction_count
         4 + // executed_count
         1 + // hsm_enabled
+        1 + // hsm_quorum
         1 + // emergency_mode
         8 + // last_key_rotation
         8 + // key_rotation_interval
         8 + // created_at
+        8 * TransactionType::COUNT + // timelock_secs
+        2 * TransactionType::COUNT + // required_roles
+        8 + // fast_path_daily_limit
+        1 + // fast_path_threshold
+        (1 + 32) + // guardian
+        4 + Self::ACTIVITY_LOG_LEN * ActivityEntry::LEN + // activity
+        8 + // activity_count
+        8 + // approval_nonce
+        (1 + 32) + // parent_wallet
         1; // bump
 
     pub const MAX_SIGNERS: usize = 3;
-    pub const REQUIRED_THRESHOLD: u8 = 2;
     pub const DEFAULT_KEY_ROTATION_INTERVAL: i64 = 7776000; // 90 days in seconds
-    pub const EMERGENCY_THRESHOLD: u8 = 1; // Emergency operations need only 1 signature
+    pub const EMERGENCY_THRESHOLD: u8 = 1; // Emergency operations need only 1 unit of weight
+    pub const SECONDS_PER_DAY: i64 = 86400;
+    pub const VETO_REVIEW_WINDOW: i64 = 30 * 86400; // Three vetoes of one proposer within this flag them
+    pub const ACTIVITY_LOG_LEN: usize = 128;
+    pub const ACTIVITY_PAGE_LEN: usize = 20; // Entries per page, keeping a page within return data limits
+    pub const DEFAULT_TIMELOCK_SECS: [i64; TransactionType::COUNT] = [
+        24 * 3600, // TreasuryTransfer
+        0,         // StakingOperation
+        0,         // RewardDistribution
+        0,         // ConfigUpdate
+        0,         // EmergencyAction, always exempt
+        24 * 3600, // KeyRotation
+        48 * 3600, // RewardRateChange
+        48 * 3600, // ThresholdChange
+        0,         // AccountFreeze
+    ];
+    pub const DEFAULT_REQUIRED_ROLES: [Option<SignerRole>; TransactionType::COUNT] = [
+        None, None, None, None, None, None, None, None,
+        Some(SignerRole::Compliance), // AccountFreeze
+    ];
 
     /// Initialize multisig wallet with HSM configuration
     pub fn initialize(
         &mut self,
         signers: Vec<SignerInfo>,
+        threshold: u8,
         hsm_enabled: bool,
+        hsm_quorum: u8,
         bump: u8,
     ) -> Result<()> {
         if signers.len() > Self::MAX_SIGNERS {
             return Err(VaultError::InvalidAllocation.into());
         }
 
-        if signers.len() < Self::REQUIRED_THRESHOLD as usize {
-            return Err(VaultError::MultisigThresholdNotMet.into());
+        Self::validate_signers(&signers, threshold, &Self::DEFAULT_REQUIRED_ROLES)?;
+        if hsm_enabled {
+            Self::validate_hsm_quorum(&signers, hsm_quorum)?;
         }
 
         let clock = Clock::get()?;
+        let mut signers = signers;
+        for signer in &mut signers {
+            signer.start_history(clock.unix_timestamp);
+        }
         
         self.signers = signers;
-        self.threshold = Self::REQUIRED_THRESHOLD;
+        self.threshold = threshold;
         self.transaction_count = 0;
         self.executed_count = 0;
         self.hsm_enabled = hsm_enabled;
+        self.hsm_quorum = hsm_quorum;
         self.emergency_mode = false;
         self.last_key_rotation = clock.unix_timestamp;
         self.key_rotation_interval = Self::DEFAULT_KEY_ROTATION_INTERVAL;
         self.created_at = clock.unix_timestamp;
+        self.timelock_secs = Self::DEFAULT_TIMELOCK_SECS;
+        self.required_roles = Self::DEFAULT_REQUIRED_ROLES;
+        self.fast_path_daily_limit = 0;
+        self.fast_path_threshold = 0;
+        self.guardian = None;
+        self.activity = Vec::new();
+        self.activity_count = 0;
+        self.approval_nonce = 0;
+        self.parent_wallet = None;
         self.bump = bump;
 
         Ok(())
     }
 
+    /// The active signers alone must be able to reach the weight threshold
+    /// and supply every role a transaction type requires
+    pub fn validate_signers(
+        signers: &[SignerInfo],
+        threshold: u8,
+        required_roles: &[Option<SignerRole>; TransactionType::COUNT],
+    ) -> Result<()> {
+        if signers.iter().any(|s| s.weight == 0) {
+            return Err(VaultError::InvalidAllocation.into());
+        }
+
+        let active_weight: u16 = signers.iter()
+            .filter(|s| s.is_active)
+            .map(|s| s.weight as u16)
+            .sum();
+        if threshold == 0 || threshold as u16 > active_weight {
+            return Err(VaultError::InvalidThresholdValue.into());
+        }
+
+        for role in required_roles.iter().flatten() {
+            if !signers.iter().any(|s| s.is_active && s.role == *role) {
+                return Err(VaultError::MultisigThresholdNotMet.into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Enough active signers must hold active HSM keys to meet the quorum
+    pub fn validate_hsm_quorum(signers: &[SignerInfo], hsm_quorum: u8) -> Result<()> {
+        let hsm_signers = signers.iter()
+            .filter(|s| s.is_active && s.hsm_key.as_ref().is_some_and(|k| k.is_active))
+            .count();
+        if hsm_quorum == 0 || hsm_quorum as usize > hsm_signers {
+            return Err(VaultError::InvalidThresholdValue.into());
+        }
+        Ok(())
+    }
+
+    /// HSM-attested approvals a transaction needs, zero when HSM is off
+    pub fn required_hsm_quorum(&self) -> u8 {
+        if self.hsm_enabled { self.hsm_quorum } else { 0 }
+    }
+
+    /// HSM key configured for an active signer
+    pub fn hsm_key_for(&self, signer: &Pubkey) -> Option<&HSMKeyInfo> {
+        self.signers.iter()
+            .find(|s| s.pubkey == *signer && s.is_active)
+            .and_then(|s| s.hsm_key.as_ref())
+    }
+
+    /// Weight a signature counts for: its signer's weight if still active
+    /// and the signature postdates the signer joining, zero otherwise
+    pub fn signature_weight(&self, signature: &MultisigSignature) -> u16 {
+        self.signers.iter()
+            .find(|s| s.pubkey == signature.signer && s.is_active && signature.signed_at >= s.added_at)
+            .map_or(0, |s| s.weight as u16)
+    }
+
+    /// Signing power of an active signer, zero for anyone else
+    pub fn signer_weight(&self, signer: &Pubkey) -> u16 {
+        self.signers.iter()
+            .find(|s| s.pubkey == *signer && s.is_active)
+            .map_or(0, |s| s.weight as u16)
+    }
+
+    /// Whether `signer` is an active admin, operator or compliance signer,
+    /// the roles that may process payments and work the dead-letter queue
+    pub fn is_payment_operator(&self, signer: &Pubkey) -> bool {
+        self.signers.iter().any(|s| {
+            s.pubkey == *signer
+                && s.is_active
+                && matches!(s.role, SignerRole::Admin | SignerRole::Operator | SignerRole::Compliance)
+        })
+    }
+
+    /// Role that must be among a transaction type's signers, if any
+    pub fn required_role_for(&self, tx_type: &TransactionType) -> Option<&SignerRole> {
+        self.required_roles[tx_type.index()].as_ref()
+    }
+
+    /// Apply an approved, timelocked threshold change
+    pub fn change_threshold(&mut self, new_threshold: u8) -> Result<()> {
+        Self::validate_signers(&self.signers, new_threshold, &self.required_roles)?;
+        if self.fast_path_enabled() && new_threshold <= self.fast_path_threshold {
+            return Err(VaultError::InvalidThresholdValue.into());
+        }
+        self.threshold = new_threshold;
+        msg!("Multisig threshold changed to {}", new_threshold);
+        Ok(())
+    }
+
+    /// Apply approved, timelocked fast path limits. The reduced threshold
+    /// must stay below the full one; zero for both disables the fast path.
+    pub fn set_fast_path_limits(&mut self, daily_limit: u64, reduced_threshold: u8) -> Result<()> {
+        if reduced_threshold >= self.threshold {
+            return Err(VaultError::InvalidThresholdValue.into());
+        }
+        self.fast_path_daily_limit = daily_limit;
+        self.fast_path_threshold = reduced_threshold;
+        msg!("Fast path limits set to {} daily at threshold {}", daily_limit, reduced_threshold);
+        Ok(())
+    }
+
+    pub fn fast_path_enabled(&self) -> bool {
+        self.fast_path_threshold > 0
+    }
+
+    /// Check every approver can take `amount` more on the fast path today
+    pub fn check_fast_path_spend(&self, approvers: &[Pubkey], amount: u64, now: i64) -> Result<()> {
+        let today = now.div_euclid(Self::SECONDS_PER_DAY);
+        for approver in approvers {
+            let signer = self.signers.iter()
+                .find(|s| s.pubkey == *approver)
+                .ok_or(VaultError::UnauthorizedSigner)?;
+            let spent = signer.fast_path_spent_on(today)
+                .checked_add(amount)
+                .ok_or(VaultError::FastPathLimitExceeded)?;
+            if spent > self.fast_path_daily_limit {
+                return Err(VaultError::FastPathLimitExceeded.into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Charge a fast path transfer to each approver's spend for the current
+    /// UTC day. Nothing is charged unless every approver stays within the
+    /// daily limit.
+    pub fn charge_fast_path(&mut self, approvers: &[Pubkey], amount: u64, now: i64) -> Result<()> {
+        self.check_fast_path_spend(approvers, amount, now)?;
+
+        let today = now.div_euclid(Self::SECONDS_PER_DAY);
+        for signer in self.signers.iter_mut().filter(|s| approvers.contains(&s.pubkey)) {
+            signer.fast_path_spent = signer.fast_path_spent_on(today) + amount;
+            signer.fast_path_day = today;
+        }
+        Ok(())
+    }
+
+    /// Place this wallet, at `own_key`, under a parent wallet. A wallet can
+    /// be neither its own parent nor its parent's parent. Parents must
+    /// already exist when a child is configured, so no longer cycle can form.
+    pub fn set_parent(&mut self, own_key: Pubkey, parent_key: Pubkey, parent: &MultisigWallet) -> Result<()> {
+        if self.parent_wallet.is_some() || parent_key == own_key || parent.parent_wallet == Some(own_key) {
+            return Err(VaultError::InvalidParentWallet.into());
+        }
+        self.parent_wallet = Some(parent_key);
+        Ok(())
+    }
+
+    /// Apply an approved, timelocked guardian change
+    pub fn set_guardian(&mut self, guardian: Option<Pubkey>) -> Option<Pubkey> {
+        let old_guardian = self.guardian;
+        self.guardian = guardian;
+        msg!("Multisig guardian set to {:?}", guardian);
+        old_guardian
+    }
+
+    /// Record a guardian veto of one of `proposer`'s transactions. Returns
+    /// whether this flags the proposer for review, which happens the first
+    /// time three of their proposals are vetoed within the review window.
+    /// Proposers no longer in the signer set are not tracked.
+    pub fn record_veto(&mut self, proposer: &Pubkey, now: i64) -> bool {
+        let Some(signer) = self.signers.iter_mut().find(|s| s.pubkey == *proposer) else {
+            return false;
+        };
+        signer.vetoed_at.rotate_left(1);
+        signer.vetoed_at[2] = now;
+
+        let oldest = signer.vetoed_at[0];
+        if signer.flagged_for_review || oldest == 0 || now - oldest > Self::VETO_REVIEW_WINDOW {
+            return false;
+        }
+        signer.flagged_for_review = true;
+        true
+    }
+
+    /// Append to the activity log, overwriting the oldest entry once it is
+    /// full. Returns the entry's sequence number.
+    pub fn record_activity(&mut self, kind: ActivityKind, actor: Pubkey, transaction_id: u32, now: i64) -> u64 {
+        let sequence = self.activity_count;
+        let entry = ActivityEntry { kind, actor, transaction_id, timestamp: now };
+        if self.activity.len() < Self::ACTIVITY_LOG_LEN {
+            self.activity.push(entry);
+        } else {
+            self.activity[(sequence % Self::ACTIVITY_LOG_LEN as u64) as usize] = entry;
+        }
+        self.activity_count = sequence.saturating_add(1);
+        sequence
+    }
+
+    /// Up to a page of activity from sequence number `cursor` on. Entries
+    /// already overwritten are skipped.
+    pub fn activity_page(&self, cursor: u64) -> ActivityPage {
+        let oldest = self.activity_count - self.activity.len() as u64;
+        let start = cursor.clamp(oldest, self.activity_count);
+        let end = start.saturating_add(Self::ACTIVITY_PAGE_LEN as u64).min(self.activity_count);
+        let entries = (start..end)
+            .map(|sequence| self.activity[(sequence % Self::ACTIVITY_LOG_LEN as u64) as usize].clone())
+            .collect();
+        ActivityPage {
+            start,
+            entries,
+            next_cursor: end,
+            total: self.activity_count,
+        }
+    }
+
+    /// Delay between a transaction reaching threshold and executing.
+    /// Emergency actions are never held back.
+    pub fn timelock_for(&self, tx_type: &TransactionType) -> i64 {
+        match tx_type {
+            TransactionType::EmergencyAction => 0,
+            _ => self.timelock_secs[tx_type.index()],
+        }
+    }
+
     /// Check if key rotation is required
     pub fn needs_key_rotation(&self) -> Result<bool> {
         let clock = Clock::get()?;
@@ -132,7 +819,13 @@ impl MultisigWallet {
             TransactionType::EmergencyAction => {
                 signer_info.role == SignerRole::Admin || signer_info.role == SignerRole::Emergency
             },
-            TransactionType::KeyRotation | TransactionType::ConfigUpdate => {
+            TransactionType::AccountFreeze => {
+                signer_info.role == SignerRole::Admin || signer_info.role == SignerRole::Compliance
+            },
+            TransactionType::KeyRotation
+            | TransactionType::ConfigUpdate
+            | TransactionType::RewardRateChange
+            | TransactionType::ThresholdChange => {
                 signer_info.role == SignerRole::Admin
             },
             _ => true, // All active signers can sign other transaction types
@@ -175,23 +868,19 @@ impl MultisigWallet {
             return Err(VaultError::InvalidAllocation.into());
         }
 
-        // Validate all new signers have HSM keys if HSM is enabled
-        if self.hsm_enabled {
-            for signer in &new_signers {
-                if signer.hsm_key.is_none() {
-                    return Err(VaultError::SecurityViolation.into());
-                }
-            }
-        }
+        self.check_signer_set(&new_signers)?;
 
         let clock = Clock::get()?;
-        
-        // Deactivate old signers
-        for signer in &mut self.signers {
-            signer.is_active = false;
+
+        // Continuing signers keep their history, new ones start afresh
+        let mut new_signers = new_signers;
+        for signer in &mut new_signers {
+            match self.signers.iter().find(|s| s.pubkey == signer.pubkey) {
+                Some(previous) => signer.carry_history_from(previous),
+                None => signer.start_history(clock.unix_timestamp),
+            }
         }
 
-        // Add new signers
         self.signers = new_signers;
         self.last_key_rotation = clock.unix_timestamp;
 
@@ -199,6 +888,75 @@ impl MultisigWallet {
         Ok(())
     }
 
+    /// Check a proposed signer set can approve every transaction type and,
+    /// with HSM enabled, that every signer has an HSM key and the quorum is
+    /// reachable
+    fn check_signer_set(&self, signers: &[SignerInfo]) -> Result<()> {
+        if signers.len() > Self::MAX_SIGNERS {
+            return Err(VaultError::InvalidAllocation.into());
+        }
+        if self.hsm_enabled && signers.iter().any(|s| s.hsm_key.is_none()) {
+            return Err(VaultError::SecurityViolation.into());
+        }
+        Self::validate_signers(signers, self.threshold, &self.required_roles)?;
+        if self.hsm_enabled {
+            Self::validate_hsm_quorum(signers, self.hsm_quorum)?;
+        }
+        Ok(())
+    }
+
+    /// Add one signer, starting its history at `now`
+    pub fn add_signer(&mut self, mut signer: SignerInfo, now: i64) -> Result<()> {
+        if self.signers.iter().any(|s| s.pubkey == signer.pubkey) {
+            return Err(VaultError::InvalidAllocation.into());
+        }
+        signer.start_history(now);
+
+        let mut signers = self.signers.clone();
+        signers.push(signer);
+        self.check_signer_set(&signers)?;
+        self.signers = signers;
+        Ok(())
+    }
+
+    /// Remove one signer. The rest must still meet threshold and roles.
+    pub fn remove_signer(&mut self, signer: &Pubkey) -> Result<()> {
+        let mut signers = self.signers.clone();
+        let index = signers.iter()
+            .position(|s| s.pubkey == *signer)
+            .ok_or(VaultError::UnauthorizedSigner)?;
+        signers.remove(index);
+        self.check_signer_set(&signers)?;
+        self.signers = signers;
+        Ok(())
+    }
+
+    /// Swap one signer's key for another. The new key takes over the slot's
+    /// role and weight with a fresh history; other signers are untouched.
+    pub fn replace_signer(
+        &mut self,
+        old: &Pubkey,
+        new: Pubkey,
+        hsm_key: Option<HSMKeyInfo>,
+        now: i64,
+    ) -> Result<()> {
+        if self.signers.iter().any(|s| s.pubkey == new) {
+            return Err(VaultError::InvalidAllocation.into());
+        }
+        let mut signers = self.signers.clone();
+        let slot = signers.iter_mut()
+            .find(|s| s.pubkey == *old)
+            .ok_or(VaultError::UnauthorizedSigner)?;
+        slot.pubkey = new;
+        slot.hsm_key = hsm_key;
+        slot.is_active = true;
+        slot.start_history(now);
+
+        self.check_signer_set(&signers)?;
+        self.signers = signers;
+        Ok(())
+    }
+
     /// Update signer's last signature timestamp
     pub fn update_signer_usage(&mut self, signer_pubkey: &Pubkey) -> Result<()> {
         let clock = Clock::get()?;
@@ -226,22 +984,115 @@ pub struct MultisigTransaction {
     pub priority: TransactionPriority,
     pub transaction_data: Vec<u8>,
     pub signatures: Vec<MultisigSignature>,
-    pub required_signatures: u8,
+    pub required_weight: u8,       // Summed signer weight needed to approve
     pub executed: bool,
     pub cancelled: bool,
+    pub failed: bool,              // Execution was attempted in a batch and errored
+    pub vetoed: bool,              // Vetoed by the guardian during its timelock
     pub expires_at: i64,           // Transaction expiration
     pub created_at: i64,
     pub executed_at: Option<i64>,  // When transaction was executed
     pub execution_result: Option<String>, // Execution result or error
+    pub approved_at: Option<i64>,  // When the signature threshold was reached
+    pub execution_deadline: Option<i64>, // Set when a timelock pushes execution past `expires_at`
+    pub proposal_digest: [u8; 32], // What signers sign, fixed at proposal
     pub bump: u8,
 }
 
+/// Emitted when a transaction reaches threshold, so watchers can react
+/// before `executable_at`
+#[event]
+pub struct TimelockStarted {
+    pub multisig: Pubkey,
+    pub transaction_id: u32,
+    pub transaction_type: TransactionType,
+    pub approved_at: i64,
+    pub executable_at: i64,
+}
+
+/// Emitted when a sweep archives a proposal that expired unexecuted
+#[event]
+pub struct MultisigTransactionExpired {
+    pub multisig: Pubkey,
+    pub transaction_id: u32,
+    pub proposer: Pubkey,
+    pub expires_at: i64,
+}
+
+/// Emitted after a batch execution with the transaction ids it settled
+#[event]
+pub struct MultisigBatchExecuted {
+    pub multisig: Pubkey,
+    pub executed: Vec<u32>,
+    pub failed: Vec<u32>,
+    pub budget_exhausted: bool, // Stopped early to stay within compute limits
+}
+
+/// Emitted when a signer change strips a departed signer's signature from
+/// a pending proposal
+#[event]
+pub struct SignerSignaturesRevoked {
+    pub multisig: Pubkey,
+    pub transaction_id: u32,
+    pub signer: Pubkey,
+    pub still_approved: bool,
+}
+
+/// Emitted with every activity log entry, so the full history can be
+/// followed off-chain past what the log keeps
+#[event]
+pub struct MultisigActivityRecorded {
+    pub multisig: Pubkey,
+    pub sequence: u64,
+    pub kind: ActivityKind,
+    pub actor: Pubkey,
+    pub transaction_id: u32,
+    pub timestamp: i64,
+}
+
+/// Emitted when a bundle of offline approvals is applied
+#[event]
+pub struct AggregatedApprovalsSubmitted {
+    pub multisig: Pubkey,
+    pub transaction_id: u32,
+    pub nonce: u64,
+    pub applied: Vec<Pubkey>,
+    pub skipped: Vec<Pubkey>, // Not active signers, lacking the role, or already signed
+}
+
+/// Emitted when a parent wallet replaces its child's signers
+#[event]
+pub struct ChildSignersRotated {
+    pub multisig: Pubkey,
+    pub parent_wallet: Pubkey,
+    pub signers: Vec<Pubkey>,
+}
+
+/// Emitted when the guardian vetoes an approved transaction
+#[event]
+pub struct MultisigTransactionVetoed {
+    pub multisig: Pubkey,
+    pub transaction_id: u32,
+    pub proposer: Pubkey,
+    pub guardian: Pubkey,
+    pub proposer_flagged: bool, // The veto flagged the proposer for review
+}
+
+/// Emitted when a proposer withdraws their own proposal
+#[event]
+pub struct MultisigTransactionCancelled {
+    pub multisig: Pubkey,
+    pub transaction_id: u32,
+    pub proposer: Pubkey,
+}
+
 /// Signature information with HSM validation
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
 pub struct MultisigSignature {
     pub signer: Pubkey,
     pub signature: [u8; 64],
-    pub hsm_signature: Option<Vec<u8>>, // HSM signature if applicable
+    pub digest: [u8; 32],               // Proposal digest the signature was verified against
+    pub hsm_attestation: Option<HsmAttestation>, // Verified HSM attestation, if one was supplied
     pub signed_at: i64,
     pub signature_type: SignatureType,
 }
@@ -252,6 +1103,16 @@ pub enum SignatureType {
     Standard,  // Standard Ed25519 signature
     HSM,       // HSM-generated signature
     Emergency, // Emergency signature (relaxed validation)
+    Offline,   // Signed offline and submitted in an approval bundle
+}
+
+/// One signer's approval of a proposal, signed offline over the proposal
+/// digest and the wallet's approval nonce
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct OfflineApproval {
+    pub signer: Pubkey,
+    pub nonce: u64,
+    pub signature: [u8; 64],
 }
 
 impl MultisigTransaction {
@@ -262,17 +1123,27 @@ impl MultisigTransaction {
         1 + // transaction_type
         1 + // priority
         4 + 2048 + // transaction_data (max 2KB)
-        4 + (3 * (32 + 64 + 4 + 64 + 8 + 1)) + // signatures with HSM data
-        1 + // required_signatures
+        4 + (3 * (32 + 64 + 32 + (1 + 1 + 32 + 64) + 8 + 1)) + // signatures with HSM attestations
+        1 + // required_weight
         1 + // executed
         1 + // cancelled
+        1 + // failed
+        1 + // vetoed
         8 + // expires_at
         8 + // created_at
         9 + // executed_at (Option<i64>)
-        4 + 256 + // execution_result (max 256 chars)
+        4 + Self::MAX_EXECUTION_RESULT_LEN + // execution_result
+        9 + // approved_at (Option<i64>)
+        9 + // execution_deadline (Option<i64>)
+        32 + // proposal_digest
         1; // bump
 
-    pub const DEFAULT_EXPIRATION_HOURS: i64 = 24; // 24 hours default expiration
+    pub const DEFAULT_EXPIRATION_HOURS: i64 = 24; // Execution window once a timelock elapses
+    pub const CRITICAL_EXPIRATION: i64 = 6 * 3600; // Emergency priority proposals
+    pub const HIGH_EXPIRATION: i64 = 24 * 3600;
+    pub const NORMAL_EXPIRATION: i64 = 7 * 24 * 3600; // Low and medium priority, and the longest allowed
+    pub const MAX_EXECUTION_RESULT_LEN: usize = 256;
+    pub const BATCH_COMPUTE_RESERVE: u64 = 50_000; // Compute units a batch needs left to start another execution
 
     /// Initialize transaction with proper validation
     pub fn initialize(
@@ -283,7 +1154,7 @@ impl MultisigTransaction {
         transaction_type: TransactionType,
         priority: TransactionPriority,
         transaction_data: Vec<u8>,
-        required_signatures: u8,
+        required_weight: u8,
         bump: u8,
     ) -> Result<()> {
         let clock = Clock::get()?;
@@ -295,27 +1166,189 @@ impl MultisigTransaction {
         self.priority = priority;
         self.transaction_data = transaction_data;
         self.signatures = Vec::new();
-        self.required_signatures = required_signatures;
+        self.required_weight = required_weight;
         self.executed = false;
         self.cancelled = false;
-        self.expires_at = clock.unix_timestamp + (Self::DEFAULT_EXPIRATION_HOURS * 3600);
+        self.failed = false;
+        self.expires_at = clock.unix_timestamp + Self::default_expiration(&self.priority);
         self.created_at = clock.unix_timestamp;
         self.executed_at = None;
         self.execution_result = None;
+        self.approved_at = None;
+        self.execution_deadline = None;
+        self.proposal_digest = [0; 32];
         self.bump = bump;
 
         Ok(())
     }
 
+    /// How long a proposal of this priority stays open by default
+    pub fn default_expiration(priority: &TransactionPriority) -> i64 {
+        match priority {
+            TransactionPriority::Emergency => Self::CRITICAL_EXPIRATION,
+            TransactionPriority::High => Self::HIGH_EXPIRATION,
+            TransactionPriority::Low | TransactionPriority::Medium => Self::NORMAL_EXPIRATION,
+        }
+    }
+
+    /// Replace the default expiry with one chosen by the proposer
+    pub fn set_expiry(&mut self, expires_at: i64, now: i64) -> Result<()> {
+        if expires_at <= now || expires_at > now.saturating_add(Self::NORMAL_EXPIRATION) {
+            return Err(VaultError::InvalidTimeWindow.into());
+        }
+        self.expires_at = expires_at;
+        Ok(())
+    }
+
     /// Check if transaction has expired
     pub fn is_expired(&self) -> Result<bool> {
         let clock = Clock::get()?;
-        Ok(clock.unix_timestamp > self.expires_at)
+        Ok(self.is_expired_at(clock.unix_timestamp))
+    }
+
+    pub fn is_expired_at(&self, now: i64) -> bool {
+        now > self.deadline()
+    }
+
+    /// Last moment the transaction can execute
+    pub fn deadline(&self) -> i64 {
+        self.execution_deadline.unwrap_or(self.expires_at)
+    }
+
+    /// Canonical digest of everything a signer approves: the wallet, the
+    /// transaction index and type, the payload and the proposed expiry
+    pub fn compute_digest(&self) -> [u8; 32] {
+        hashv(&[
+            self.multisig.as_ref(),
+            &self.transaction_id.to_le_bytes(),
+            &[self.transaction_type.index() as u8],
+            &hash(&self.transaction_data).to_bytes(),
+            &self.expires_at.to_le_bytes(),
+        ]).to_bytes()
+    }
+
+    /// Fix the digest signers sign. Called once the proposal is complete.
+    pub fn seal_digest(&mut self) {
+        self.proposal_digest = self.compute_digest();
+    }
+
+    /// Check the proposal still hashes to the digest it was sealed with,
+    /// and that every signature was verified against that digest
+    pub fn check_digest(&self) -> Result<()> {
+        if self.compute_digest() != self.proposal_digest
+            || self.signatures.iter().any(|s| s.digest != self.proposal_digest)
+        {
+            return Err(VaultError::ProposalDigestMismatch.into());
+        }
+        Ok(())
+    }
+
+    /// Archive the proposal if it expired without executing. Returns
+    /// whether it was archived by this call.
+    pub fn archive_if_expired(&mut self, now: i64) -> bool {
+        if self.is_settled() || !self.is_expired_at(now) {
+            return false;
+        }
+        self.cancelled = true;
+        self.execution_result = Some("Expired".to_string());
+        true
+    }
+
+    /// Re-read the wallet's live threshold, so a threshold change also
+    /// applies to transactions already in flight
+    pub fn sync_required_weight(&mut self, multisig_wallet: &MultisigWallet) {
+        self.required_weight = multisig_wallet.get_required_threshold(&self.transaction_type, &self.priority);
+    }
+
+    /// Check the signers' summed live weight against the requirement, and
+    /// that any role this transaction type requires has signed. Signatures
+    /// from removed signers, or from before a signer joined, count for
+    /// nothing.
+    pub fn has_enough_signatures(&self, multisig_wallet: &MultisigWallet) -> bool {
+        let signed_weight: u16 = self.signatures.iter()
+            .map(|s| multisig_wallet.signature_weight(s))
+            .sum();
+        let role_signed = match multisig_wallet.required_role_for(&self.transaction_type) {
+            Some(role) => self.signatures.iter().any(|s| {
+                multisig_wallet.signature_weight(s) > 0
+                    && multisig_wallet.signers.iter().any(|i| i.pubkey == s.signer && i.role == *role)
+            }),
+            None => true,
+        };
+        signed_weight >= self.required_weight as u16
+            && role_signed
+            && self.check_hsm_quorum(multisig_wallet).is_ok()
+    }
+
+    /// Check enough signatures carry an attestation from their signer's
+    /// current HSM key. An attestation that no longer matches the wallet's
+    /// HSM configuration fails the check outright. The quorum never exceeds
+    /// the transaction's required weight, so emergency mode stays usable.
+    /// Signatures that no longer count toward approval are ignored.
+    pub fn check_hsm_quorum(&self, multisig_wallet: &MultisigWallet) -> Result<()> {
+        let quorum = multisig_wallet.required_hsm_quorum().min(self.required_weight);
+        let mut attested: u8 = 0;
+        for signature in &self.signatures {
+            if multisig_wallet.signature_weight(signature) == 0 {
+                continue;
+            }
+            if let Some(attestation) = &signature.hsm_attestation {
+                HsmAttestationVerifier::check_key_binding(multisig_wallet.hsm_key_for(&signature.signer), attestation)?;
+                attested = attested.saturating_add(1);
+            }
+        }
+        if attested < quorum {
+            return Err(VaultError::MissingHsmAttestation.into());
+        }
+        Ok(())
+    }
+
+    /// Record the approval time once the threshold is reached, returning
+    /// whether this call did so. Timelocked transactions get an execution
+    /// deadline past the timelock so they stay executable; the signed
+    /// `expires_at` is left alone.
+    pub fn mark_approved_if_ready(&mut self, multisig_wallet: &MultisigWallet, now: i64) -> bool {
+        if self.approved_at.is_some() || !self.has_enough_signatures(multisig_wallet) {
+            return false;
+        }
+
+        self.approved_at = Some(now);
+
+        let timelock = multisig_wallet.timelock_for(&self.transaction_type);
+        if timelock > 0 {
+            let execution_window_end = now
+                .saturating_add(timelock)
+                .saturating_add(Self::DEFAULT_EXPIRATION_HOURS * 3600);
+            self.execution_deadline = Some(self.expires_at.max(execution_window_end));
+        }
+        true
+    }
+
+    /// Drop a departed signer's signatures and, if the proposal no longer
+    /// meets threshold, revoke its approval so a timelock restarts on
+    /// re-approval. Returns whether any signature was dropped.
+    pub fn invalidate_signatures_from(&mut self, signer: &Pubkey, multisig_wallet: &MultisigWallet) -> bool {
+        let before = self.signatures.len();
+        self.signatures.retain(|s| s.signer != *signer);
+        if self.signatures.len() == before {
+            return false;
+        }
+
+        self.sync_required_weight(multisig_wallet);
+        if self.approved_at.is_some() && !self.has_enough_signatures(multisig_wallet) {
+            self.approved_at = None;
+            self.execution_deadline = None;
+        }
+        true
     }
 
-    /// Check if transaction has enough signatures
-    pub fn has_enough_signatures(&self) -> bool {
-        self.signatures.len() >= self.required_signatures as usize
+    /// Check that the approval timelock has elapsed
+    pub fn check_timelock_elapsed(&self, now: i64, timelock: i64) -> Result<()> {
+        let approved_at = self.approved_at.ok_or(VaultError::MultisigThresholdNotMet)?;
+        if now < approved_at.saturating_add(timelock) {
+            return Err(VaultError::TimelockNotElapsed.into());
+        }
+        Ok(())
     }
 
     /// Add signature to transaction
@@ -345,18 +1378,168 @@ impl MultisigTransaction {
         Ok(())
     }
 
+    /// Record a failed execution attempt, keeping as much of the error as
+    /// fits in the result
+    pub fn mark_failed(&mut self, error: &str, now: i64) {
+        self.failed = true;
+        self.executed_at = Some(now);
+        self.execution_result = Some(Self::bounded_result(format!("Failed: {}", error)));
+    }
+
+    /// Truncate a result message to what a transaction can store
+    pub fn bounded_result(mut result: String) -> String {
+        if result.len() > Self::MAX_EXECUTION_RESULT_LEN {
+            let mut end = Self::MAX_EXECUTION_RESULT_LEN;
+            while !result.is_char_boundary(end) {
+                end -= 1;
+            }
+            result.truncate(end);
+        }
+        result
+    }
+
+    /// Whether the transaction has left the pending state for good
+    pub fn is_settled(&self) -> bool {
+        self.executed || self.cancelled || self.failed || self.vetoed
+    }
+
+    /// Check the transaction is pending, unexpired, unchanged since it was
+    /// signed, and carries enough signatures and HSM attestations. Approval
+    /// and the timelock are checked separately.
+    pub fn check_executable(&self, multisig_wallet: &MultisigWallet, now: i64) -> Result<()> {
+        if self.is_settled() {
+            return Err(VaultError::TransactionAlreadyExecuted.into());
+        }
+        if self.is_expired_at(now) {
+            return Err(VaultError::SecurityViolation.into());
+        }
+
+        // Refuse to act on a payload that changed after it was signed
+        self.check_digest()?;
+        self.check_hsm_quorum(multisig_wallet)?;
+        if !self.has_enough_signatures(multisig_wallet) {
+            return Err(VaultError::MultisigThresholdNotMet.into());
+        }
+        Ok(())
+    }
+
+    /// Signers whose signatures count toward approval
+    pub fn counted_signers(&self, multisig_wallet: &MultisigWallet) -> Vec<Pubkey> {
+        self.signatures.iter()
+            .filter(|s| multisig_wallet.signature_weight(s) > 0)
+            .map(|s| s.signer)
+            .collect()
+    }
+
+    /// Check the transaction can run on the fast path: a treasury transfer
+    /// meeting the reduced threshold whose amount fits every approver's
+    /// remaining daily limit. No timelock applies. Returns the amount.
+    pub fn check_fast_path(&self, multisig_wallet: &MultisigWallet, now: i64) -> Result<u64> {
+        if !multisig_wallet.fast_path_enabled() || self.transaction_type != TransactionType::TreasuryTransfer {
+            return Err(VaultError::FastPathUnavailable.into());
+        }
+        let amount = match self.decode_action()? {
+            MultisigAction::TreasuryTransfer { amount, .. } => amount,
+            _ => return Err(VaultError::FastPathUnavailable.into()),
+        };
+
+        let mut reduced = self.clone();
+        reduced.required_weight = multisig_wallet.fast_path_threshold;
+        reduced.check_executable(multisig_wallet, now)?;
+        multisig_wallet.check_fast_path_spend(&self.counted_signers(multisig_wallet), amount, now)?;
+        Ok(amount)
+    }
+
+    /// Whether the transaction is approved, past its timelock, unexpired and
+    /// still matches what its signers approved
+    pub fn is_ready_to_execute(&self, multisig_wallet: &MultisigWallet, now: i64) -> bool {
+        let timelock = multisig_wallet.timelock_for(&self.transaction_type);
+        self.check_executable(multisig_wallet, now).is_ok()
+            && self.check_timelock_elapsed(now, timelock).is_ok()
+    }
+
     /// Cancel transaction
     pub fn cancel(&mut self, reason: String) -> Result<()> {
-        if self.executed {
+        if self.is_settled() {
             return Err(VaultError::TransactionAlreadyExecuted.into());
         }
 
+        let result = format!("Cancelled: {}", reason);
+        if result.len() > Self::MAX_EXECUTION_RESULT_LEN {
+            return Err(VaultError::ReasonTooLong.into());
+        }
+
         self.cancelled = true;
-        self.execution_result = Some(format!("Cancelled: {}", reason));
+        self.execution_result = Some(result);
+
+        Ok(())
+    }
+
+    /// Veto an approved transaction while its timelock is still running
+    pub fn veto(&mut self, reason: String, now: i64, timelock: i64) -> Result<()> {
+        if self.is_settled() {
+            return Err(VaultError::TransactionAlreadyExecuted.into());
+        }
+        if self.approved_at.is_none() {
+            return Err(VaultError::MultisigThresholdNotMet.into());
+        }
+        if self.is_expired_at(now) || self.check_timelock_elapsed(now, timelock).is_ok() {
+            return Err(VaultError::VetoWindowClosed.into());
+        }
+
+        let result = format!("Vetoed: {}", reason);
+        if result.len() > Self::MAX_EXECUTION_RESULT_LEN {
+            return Err(VaultError::ReasonTooLong.into());
+        }
 
+        self.vetoed = true;
+        self.execution_result = Some(result);
         Ok(())
     }
 
+    /// Payload of a threshold change is the new threshold as a single byte
+    pub fn decode_threshold_change(transaction_data: &[u8]) -> Result<u8> {
+        match transaction_data {
+            [threshold] => Ok(*threshold),
+            _ => Err(VaultError::InvalidAllocation.into()),
+        }
+    }
+
+    /// New threshold of a threshold change. Threshold-change payloads other
+    /// than a single byte are typed actions.
+    pub fn new_threshold(&self) -> Option<u8> {
+        match self.transaction_type {
+            TransactionType::ThresholdChange => Self::decode_threshold_change(&self.transaction_data).ok(),
+            _ => None,
+        }
+    }
+
+    /// Decode and check the transaction's action. Payloads that don't
+    /// decode exactly, or whose action doesn't fit the transaction type,
+    /// are rejected.
+    pub fn decode_action(&self) -> Result<MultisigAction> {
+        let action = MultisigAction::try_from_slice(&self.transaction_data)
+            .map_err(|_| VaultError::InvalidMultisigAction)?;
+        if !action.is_permitted_for(&self.transaction_type) {
+            return Err(VaultError::InvalidMultisigAction.into());
+        }
+        action.validate()?;
+        Ok(action)
+    }
+
+    /// Whether a batch can execute this transaction with wallet-wide
+    /// accounts only. Undecodable payloads count as batchable so a batch
+    /// marks them failed.
+    pub fn is_batchable(&self) -> bool {
+        match self.transaction_type {
+            TransactionType::ThresholdChange => true,
+            _ => match self.decode_action() {
+                Ok(action) => action.is_batchable(),
+                Err(_) => true,
+            },
+        }
+    }
+
     /// Validate transaction data integrity
     pub fn validate_transaction_data(&self) -> Result<()> {
         // Basic validation
@@ -369,24 +1552,1074 @@ impl MultisigTransaction {
         }
 
         // Type-specific validation
-        match self.transaction_type {
-            TransactionType::TreasuryTransfer => {
-                // Validate treasury transfer data format
-                if self.transaction_data.len() < 40 { // Minimum: recipient (32) + amount (8)
-                    return Err(VaultError::InvalidAllocation.into());
-                }
-            },
-            TransactionType::KeyRotation => {
-                // Validate key rotation data
-                if self.transaction_data.len() < 96 { // Minimum: 3 pubkeys (32 each)
-                    return Err(VaultError::InvalidAllocation.into());
+        match self.new_threshold() {
+            Some(new_threshold) => {
+                // Bounds against the active signers are checked at proposal and execution
+                if new_threshold == 0 {
+                    return Err(VaultError::InvalidThresholdValue.into());
                 }
             },
-            _ => {
-                // Other transaction types have basic validation
+            None => {
+                // Everything else carries a typed action
+                self.decode_action()?;
             }
         }
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signer(is_active: bool) -> SignerInfo {
+        weighted_signer(SignerRole::Admin, 1, is_active)
+    }
+
+    fn weighted_signer(role: SignerRole, weight: u8, is_active: bool) -> SignerInfo {
+        SignerInfo {
+            pubkey: Pubkey::new_unique(),
+            hsm_key: None,
+            role,
+            weight,
+            added_at: 0,
+            last_signature: 0,
+            is_active,
+            fast_path_day: 0,
+            fast_path_spent: 0,
+            vetoed_at: [0; 3],
+            flagged_for_review: false,
+        }
+    }
+
+    #[test]
+    fn test_threshold_bounded_by_active_signers() {
+        let signers = vec![signer(true), signer(true), signer(false)];
+        let roles = [None, None, None, None, None, None, None, None, None];
+        assert!(MultisigWallet::validate_signers(&signers, 0, &roles).is_err());
+        MultisigWallet::validate_signers(&signers, 1, &roles).unwrap();
+        MultisigWallet::validate_signers(&signers, 2, &roles).unwrap();
+        assert!(MultisigWallet::validate_signers(&signers, 3, &roles).is_err());
+    }
+
+    #[test]
+    fn test_threshold_change_is_timelocked_single_byte() {
+        assert_eq!(MultisigTransaction::decode_threshold_change(&[2]).unwrap(), 2);
+        assert!(MultisigTransaction::decode_threshold_change(&[]).is_err());
+        assert!(MultisigTransaction::decode_threshold_change(&[2, 0]).is_err());
+
+        let mut wallet = MultisigWallet {
+            signers: vec![signer(true), signer(true), signer(true)],
+            threshold: 2,
+            transaction_count: 0,
+            executed_count: 0,
+            hsm_enabled: false,
+            hsm_quorum: 0,
+            emergency_mode: false,
+            last_key_rotation: 0,
+            key_rotation_interval: MultisigWallet::DEFAULT_KEY_ROTATION_INTERVAL,
+            created_at: 0,
+            timelock_secs: MultisigWallet::DEFAULT_TIMELOCK_SECS,
+            required_roles: [None, None, None, None, None, None, None, None, None],
+            fast_path_daily_limit: 0,
+            fast_path_threshold: 0,
+            guardian: None,
+            activity: Vec::new(),
+            activity_count: 0,
+            approval_nonce: 0,
+            parent_wallet: None,
+            bump: 0,
+        };
+        assert!(wallet.change_threshold(4).is_err());
+        wallet.change_threshold(3).unwrap();
+        assert_eq!(wallet.get_required_threshold(&TransactionType::TreasuryTransfer, &TransactionPriority::Low), 3);
+    }
+
+    #[test]
+    fn test_expired_proposals_archive_once() {
+        let mut transaction = MultisigTransaction {
+            multisig: Pubkey::new_unique(),
+            transaction_id: 0,
+            proposer: Pubkey::new_unique(),
+            transaction_type: TransactionType::ConfigUpdate,
+            priority: TransactionPriority::Emergency,
+            transaction_data: vec![1],
+            signatures: Vec::new(),
+            required_weight: 2,
+            executed: false,
+            cancelled: false,
+            failed: false,
+            vetoed: false,
+            expires_at: 0,
+            created_at: 0,
+            executed_at: None,
+            execution_result: None,
+            approved_at: None,
+            execution_deadline: None,
+            proposal_digest: [0; 32],
+            bump: 0,
+        };
+        assert_eq!(MultisigTransaction::default_expiration(&transaction.priority), 6 * 3600);
+        assert_eq!(MultisigTransaction::default_expiration(&TransactionPriority::Medium), 7 * 24 * 3600);
+        assert!(transaction.set_expiry(0, 0).is_err());
+        assert!(transaction.set_expiry(MultisigTransaction::NORMAL_EXPIRATION + 1, 0).is_err());
+        transaction.set_expiry(100, 0).unwrap();
+
+        assert!(!transaction.archive_if_expired(100));
+        assert!(transaction.archive_if_expired(101));
+        assert!(!transaction.archive_if_expired(102));
+        assert!(transaction.cancel("late".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_only_active_ops_roles_operate_payments() {
+        let operator = weighted_signer(SignerRole::Operator, 1, true);
+        let compliance = weighted_signer(SignerRole::Compliance, 1, true);
+        let emergency = weighted_signer(SignerRole::Emergency, 1, true);
+        let inactive = weighted_signer(SignerRole::Admin, 1, false);
+        let wallet = MultisigWallet {
+            signers: vec![operator.clone(), compliance.clone(), emergency.clone(), inactive.clone()],
+            threshold: 2,
+            transaction_count: 0,
+            executed_count: 0,
+            hsm_enabled: false,
+            hsm_quorum: 0,
+            emergency_mode: false,
+            last_key_rotation: 0,
+            key_rotation_interval: MultisigWallet::DEFAULT_KEY_ROTATION_INTERVAL,
+            created_at: 0,
+            timelock_secs: MultisigWallet::DEFAULT_TIMELOCK_SECS,
+            required_roles: [None, None, None, None, None, None, None, None, None],
+            fast_path_daily_limit: 0,
+            fast_path_threshold: 0,
+            guardian: None,
+            activity: Vec::new(),
+            activity_count: 0,
+            approval_nonce: 0,
+            parent_wallet: None,
+            bump: 0,
+        };
+        assert!(wallet.is_payment_operator(&operator.pubkey));
+        assert!(wallet.is_payment_operator(&compliance.pubkey));
+        assert!(!wallet.is_payment_operator(&emergency.pubkey));
+        assert!(!wallet.is_payment_operator(&inactive.pubkey));
+        // An arbitrary signer can't complete or dead-letter someone's payment
+        assert!(!wallet.is_payment_operator(&Pubkey::new_unique()));
+    }
+
+    #[test]
+    fn test_timelocks_by_transaction_type() {
+        let mut timelock_secs = MultisigWallet::DEFAULT_TIMELOCK_SECS;
+        timelock_secs[TransactionType::EmergencyAction.index()] = 3600;
+        let wallet = MultisigWallet {
+            signers: vec![signer(true), signer(true)],
+            threshold: 2,
+            transaction_count: 0,
+            executed_count: 0,
+            hsm_enabled: false,
+            hsm_quorum: 0,
+            emergency_mode: false,
+            last_key_rotation: 0,
+            key_rotation_interval: MultisigWallet::DEFAULT_KEY_ROTATION_INTERVAL,
+            created_at: 0,
+            timelock_secs,
+            required_roles: [None, None, None, None, None, None, None, None, None],
+            fast_path_daily_limit: 0,
+            fast_path_threshold: 0,
+            guardian: None,
+            activity: Vec::new(),
+            activity_count: 0,
+            approval_nonce: 0,
+            parent_wallet: None,
+            bump: 0,
+        };
+        assert_eq!(wallet.timelock_for(&TransactionType::EmergencyAction), 0);
+        assert_eq!(wallet.timelock_for(&TransactionType::StakingOperation), 0);
+        let timelock = wallet.timelock_for(&TransactionType::KeyRotation);
+        assert_eq!(timelock, 24 * 3600);
+
+        let mut transaction = MultisigTransaction {
+            multisig: Pubkey::new_unique(),
+            transaction_id: 0,
+            proposer: Pubkey::new_unique(),
+            transaction_type: TransactionType::KeyRotation,
+            priority: TransactionPriority::High,
+            transaction_data: vec![0; 96],
+            signatures: Vec::new(),
+            required_weight: 0,
+            executed: false,
+            cancelled: false,
+            failed: false,
+            vetoed: false,
+            expires_at: 3600,
+            created_at: 0,
+            executed_at: None,
+            execution_result: None,
+            approved_at: None,
+            execution_deadline: None,
+            proposal_digest: [0; 32],
+            bump: 0,
+        };
+        assert!(transaction.check_timelock_elapsed(10, timelock).is_err());
+        assert!(transaction.mark_approved_if_ready(&wallet, 10));
+        assert!(!transaction.mark_approved_if_ready(&wallet, 20));
+        assert!(transaction.deadline() > 10 + timelock);
+        assert_eq!(transaction.expires_at, 3600);
+        assert!(transaction.check_timelock_elapsed(9 + timelock, timelock).is_err());
+        transaction.check_timelock_elapsed(10 + timelock, timelock).unwrap();
+    }
+
+    #[test]
+    fn test_weighted_approval_with_required_role() {
+        let cfo = weighted_signer(SignerRole::Admin, 2, true);
+        let operator = weighted_signer(SignerRole::Operator, 1, true);
+        let compliance = weighted_signer(SignerRole::Compliance, 1, true);
+        let mut wallet = MultisigWallet {
+            signers: vec![cfo.clone(), operator.clone(), compliance.clone()],
+            threshold: 3,
+            transaction_count: 0,
+            executed_count: 0,
+            hsm_enabled: false,
+            hsm_quorum: 0,
+            emergency_mode: false,
+            last_key_rotation: 0,
+            key_rotation_interval: MultisigWallet::DEFAULT_KEY_ROTATION_INTERVAL,
+            created_at: 0,
+            timelock_secs: MultisigWallet::DEFAULT_TIMELOCK_SECS,
+            required_roles: MultisigWallet::DEFAULT_REQUIRED_ROLES,
+            fast_path_daily_limit: 0,
+            fast_path_threshold: 0,
+            guardian: None,
+            activity: Vec::new(),
+            activity_count: 0,
+            approval_nonce: 0,
+            parent_wallet: None,
+            bump: 0,
+        };
+        let signature = |signer: &SignerInfo| MultisigSignature {
+            signer: signer.pubkey,
+            signature: [0; 64],
+            digest: [0; 32],
+            hsm_attestation: None,
+            signed_at: 0,
+            signature_type: SignatureType::Standard,
+        };
+        let mut freeze = MultisigTransaction {
+            multisig: Pubkey::new_unique(),
+            transaction_id: 0,
+            proposer: cfo.pubkey,
+            transaction_type: TransactionType::AccountFreeze,
+            priority: TransactionPriority::High,
+            transaction_data: vec![0; 32],
+            signatures: vec![signature(&cfo), signature(&operator)],
+            required_weight: 0,
+            executed: false,
+            cancelled: false,
+            failed: false,
+            vetoed: false,
+            expires_at: 3600,
+            created_at: 0,
+            executed_at: None,
+            execution_result: None,
+            approved_at: None,
+            execution_deadline: None,
+            proposal_digest: [0; 32],
+            bump: 0,
+        };
+        freeze.sync_required_weight(&wallet);
+        assert_eq!(freeze.required_weight, 3);
+
+        // Weight 3 is met, but no compliance signer has signed
+        assert!(!freeze.has_enough_signatures(&wallet));
+        freeze.signatures = vec![signature(&cfo), signature(&compliance)];
+        assert!(freeze.has_enough_signatures(&wallet));
+        freeze.signatures = vec![signature(&operator), signature(&compliance)];
+        assert!(!freeze.has_enough_signatures(&wallet));
+
+        // Rotation may not drop the only compliance signer or the weight needed
+        let mut no_compliance = wallet.signers.clone();
+        no_compliance[2] = weighted_signer(SignerRole::Operator, 1, true);
+        assert!(MultisigWallet::validate_signers(&no_compliance, 3, &wallet.required_roles).is_err());
+        let mut lighter = wallet.signers.clone();
+        lighter[0] = weighted_signer(SignerRole::Admin, 1, true);
+        MultisigWallet::validate_signers(&lighter, 3, &wallet.required_roles).unwrap();
+        assert!(MultisigWallet::validate_signers(&lighter, 4, &wallet.required_roles).is_err());
+        assert!(wallet.change_threshold(5).is_err());
+        wallet.change_threshold(4).unwrap();
+    }
+
+    #[test]
+    fn test_proposal_digest_detects_mutation() {
+        let mut transaction = MultisigTransaction {
+            multisig: Pubkey::new_unique(),
+            transaction_id: 4,
+            proposer: Pubkey::new_unique(),
+            transaction_type: TransactionType::TreasuryTransfer,
+            priority: TransactionPriority::Medium,
+            transaction_data: vec![7; 40],
+            signatures: Vec::new(),
+            required_weight: 2,
+            executed: false,
+            cancelled: false,
+            failed: false,
+            vetoed: false,
+            expires_at: 3600,
+            created_at: 0,
+            executed_at: None,
+            execution_result: None,
+            approved_at: None,
+            execution_deadline: None,
+            proposal_digest: [0; 32],
+            bump: 0,
+        };
+        transaction.seal_digest();
+        transaction.check_digest().unwrap();
+        let digest = transaction.proposal_digest;
+
+        // Every bound field changes the digest
+        let mut other = transaction.clone();
+        other.transaction_id = 5;
+        assert_ne!(other.compute_digest(), digest);
+        other = transaction.clone();
+        other.multisig = Pubkey::new_unique();
+        assert_ne!(other.compute_digest(), digest);
+        other = transaction.clone();
+        other.transaction_type = TransactionType::StakingOperation;
+        assert_ne!(other.compute_digest(), digest);
+        other = transaction.clone();
+        other.expires_at = 3601;
+        assert_ne!(other.compute_digest(), digest);
+
+        // A signature verified over another digest is not counted as approval
+        transaction.signatures.push(MultisigSignature {
+            signer: Pubkey::new_unique(),
+            signature: [0; 64],
+            digest: [9; 32],
+            hsm_attestation: None,
+            signed_at: 0,
+            signature_type: SignatureType::Standard,
+        });
+        assert!(transaction.check_digest().is_err());
+        transaction.signatures[0].digest = digest;
+        transaction.check_digest().unwrap();
+
+        // Payload edits after signing are caught, as is a timelock extension
+        // leaving the signed expiry alone
+        transaction.execution_deadline = Some(10_000);
+        transaction.check_digest().unwrap();
+        transaction.transaction_data[39] = 8;
+        assert!(transaction.check_digest().is_err());
+    }
+
+    #[test]
+    fn test_hsm_quorum_distinguishes_missing_from_invalid() {
+        let hsm_key = |chain: u8| HSMKeyInfo {
+            key_id: 1,
+            public_key: Pubkey::new_unique().to_bytes(),
+            key_label: "treasury".to_string(),
+            vendor: HsmVendor::Yubico,
+            attestation_chain_hash: [chain; 32],
+            created_at: 0,
+            last_used: 0,
+            is_active: true,
+            usage_count: 0,
+        };
+        let mut first = signer(true);
+        first.hsm_key = Some(hsm_key(1));
+        let mut second = signer(true);
+        second.hsm_key = Some(hsm_key(2));
+        let third = signer(true);
+
+        let signers = vec![first.clone(), second.clone(), third.clone()];
+        MultisigWallet::validate_hsm_quorum(&signers, 2).unwrap();
+        assert!(MultisigWallet::validate_hsm_quorum(&signers, 0).is_err());
+        assert!(MultisigWallet::validate_hsm_quorum(&signers, 3).is_err());
+
+        let mut wallet = MultisigWallet {
+            signers,
+            threshold: 2,
+            transaction_count: 0,
+            executed_count: 0,
+            hsm_enabled: true,
+            hsm_quorum: 2,
+            emergency_mode: false,
+            last_key_rotation: 0,
+            key_rotation_interval: MultisigWallet::DEFAULT_KEY_ROTATION_INTERVAL,
+            created_at: 0,
+            timelock_secs: MultisigWallet::DEFAULT_TIMELOCK_SECS,
+            required_roles: MultisigWallet::DEFAULT_REQUIRED_ROLES,
+            fast_path_daily_limit: 0,
+            fast_path_threshold: 0,
+            guardian: None,
+            activity: Vec::new(),
+            activity_count: 0,
+            approval_nonce: 0,
+            parent_wallet: None,
+            bump: 0,
+        };
+        let attested = |signer: &SignerInfo, chain: u8| MultisigSignature {
+            signer: signer.pubkey,
+            signature: [0; 64],
+            digest: [0; 32],
+            hsm_attestation: Some(HsmAttestation {
+                vendor: HsmVendor::Yubico,
+                cert_chain_hash: [chain; 32],
+                signature: [0; 64],
+            }),
+            signed_at: 0,
+            signature_type: SignatureType::HSM,
+        };
+        let mut transaction = MultisigTransaction {
+            multisig: Pubkey::new_unique(),
+            transaction_id: 0,
+            proposer: first.pubkey,
+            transaction_type: TransactionType::StakingOperation,
+            priority: TransactionPriority::Medium,
+            transaction_data: vec![1],
+            signatures: vec![attested(&first, 1)],
+            required_weight: 2,
+            executed: false,
+            cancelled: false,
+            failed: false,
+            vetoed: false,
+            expires_at: 3600,
+            created_at: 0,
+            executed_at: None,
+            execution_result: None,
+            approved_at: None,
+            execution_deadline: None,
+            proposal_digest: [0; 32],
+            bump: 0,
+        };
+
+        // Weight is met, but only one of two approvals is HSM-attested
+        let mut plain = attested(&third, 0);
+        plain.hsm_attestation = None;
+        transaction.signatures.push(plain);
+        let err = transaction.check_hsm_quorum(&wallet).unwrap_err();
+        assert_eq!(err, VaultError::MissingHsmAttestation.into());
+        assert!(!transaction.has_enough_signatures(&wallet));
+
+        // An attestation for the wrong certificate chain is invalid, not missing
+        transaction.signatures[1] = attested(&second, 1);
+        let err = transaction.check_hsm_quorum(&wallet).unwrap_err();
+        assert_eq!(err, VaultError::InvalidHsmAttestation.into());
+
+        transaction.signatures[1] = attested(&second, 2);
+        transaction.check_hsm_quorum(&wallet).unwrap();
+        assert!(transaction.has_enough_signatures(&wallet));
+
+        // Off when HSM is disabled
+        wallet.hsm_enabled = false;
+        transaction.signatures.truncate(1);
+        transaction.check_hsm_quorum(&wallet).unwrap();
+    }
+
+    #[test]
+    fn test_batch_readiness_and_failure() {
+        let first = signer(true);
+        let second = signer(true);
+        let wallet = MultisigWallet {
+            signers: vec![first.clone(), second.clone()],
+            threshold: 2,
+            transaction_count: 0,
+            executed_count: 0,
+            hsm_enabled: false,
+            hsm_quorum: 0,
+            emergency_mode: false,
+            last_key_rotation: 0,
+            key_rotation_interval: MultisigWallet::DEFAULT_KEY_ROTATION_INTERVAL,
+            created_at: 0,
+            timelock_secs: MultisigWallet::DEFAULT_TIMELOCK_SECS,
+            required_roles: MultisigWallet::DEFAULT_REQUIRED_ROLES,
+            fast_path_daily_limit: 0,
+            fast_path_threshold: 0,
+            guardian: None,
+            activity: Vec::new(),
+            activity_count: 0,
+            approval_nonce: 0,
+            parent_wallet: None,
+            bump: 0,
+        };
+        let signature = |signer: &SignerInfo| MultisigSignature {
+            signer: signer.pubkey,
+            signature: [0; 64],
+            digest: [0; 32],
+            hsm_attestation: None,
+            signed_at: 0,
+            signature_type: SignatureType::Standard,
+        };
+        let mut transaction = MultisigTransaction {
+            multisig: Pubkey::new_unique(),
+            transaction_id: 0,
+            proposer: first.pubkey,
+            transaction_type: TransactionType::TreasuryTransfer,
+            priority: TransactionPriority::High,
+            transaction_data: vec![0; 40],
+            signatures: vec![signature(&first)],
+            required_weight: 2,
+            executed: false,
+            cancelled: false,
+            failed: false,
+            vetoed: false,
+            expires_at: 3600,
+            created_at: 0,
+            executed_at: None,
+            execution_result: None,
+            approved_at: None,
+            execution_deadline: None,
+            proposal_digest: [0; 32],
+            bump: 0,
+        };
+        transaction.seal_digest();
+        let digest = transaction.proposal_digest;
+        transaction.signatures[0].digest = digest;
+        assert!(!transaction.is_ready_to_execute(&wallet, 10));
+        let err = transaction.check_executable(&wallet, 10).unwrap_err();
+        assert_eq!(err, VaultError::MultisigThresholdNotMet.into());
+        let err = transaction.check_executable(&wallet, 3601).unwrap_err();
+        assert_eq!(err, VaultError::SecurityViolation.into());
+
+        let mut second_signature = signature(&second);
+        second_signature.digest = digest;
+        transaction.signatures.push(second_signature);
+        assert!(transaction.mark_approved_if_ready(&wallet, 10));
+
+        // Treasury transfers wait out their 24h timelock
+        let unlocked = 10 + 24 * 3600;
+        assert!(!transaction.is_ready_to_execute(&wallet, unlocked - 1));
+        assert!(transaction.is_ready_to_execute(&wallet, unlocked));
+
+        let long_error = "x".repeat(400);
+        transaction.mark_failed(&long_error, unlocked);
+        assert!(transaction.is_settled());
+        assert!(!transaction.is_ready_to_execute(&wallet, unlocked));
+        let result = transaction.execution_result.clone().unwrap();
+        assert!(result.starts_with("Failed: "));
+        assert_eq!(result.len(), MultisigTransaction::MAX_EXECUTION_RESULT_LEN);
+        assert!(transaction.cancel("too late".to_string()).is_err());
+        let err = transaction.check_executable(&wallet, unlocked).unwrap_err();
+        assert_eq!(err, VaultError::TransactionAlreadyExecuted.into());
+
+        // Simulation reports the failing precondition within the same bound
+        let simulated = SimulationResult::failed(&VaultError::MultisigThresholdNotMet.into());
+        assert!(!simulated.would_succeed);
+        assert!(simulated.effects.is_empty());
+        assert!(simulated.failure_reason.unwrap().len() <= MultisigTransaction::MAX_EXECUTION_RESULT_LEN);
+    }
+
+    #[test]
+    fn test_actions_decode_strictly_for_their_type() {
+        let transaction = |transaction_type: TransactionType, transaction_data: Vec<u8>| MultisigTransaction {
+            multisig: Pubkey::new_unique(),
+            transaction_id: 0,
+            proposer: Pubkey::new_unique(),
+            transaction_type,
+            priority: TransactionPriority::Medium,
+            transaction_data,
+            signatures: Vec::new(),
+            required_weight: 2,
+            executed: false,
+            cancelled: false,
+            failed: false,
+            vetoed: false,
+            expires_at: 3600,
+            created_at: 0,
+            executed_at: None,
+            execution_result: None,
+            approved_at: None,
+            execution_deadline: None,
+            proposal_digest: [0; 32],
+            bump: 0,
+        };
+        let transfer = MultisigAction::TreasuryTransfer {
+            to: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            amount: 1_000,
+        };
+        let encoded = transfer.try_to_vec().unwrap();
+
+        let valid = transaction(TransactionType::TreasuryTransfer, encoded.clone());
+        valid.validate_transaction_data().unwrap();
+        assert_eq!(valid.decode_action().unwrap(), transfer);
+        assert!(!valid.is_batchable());
+
+        // Wrong type, trailing bytes, truncation and garbage all fail closed
+        let mut trailing = encoded.clone();
+        trailing.push(0);
+        for rejected in [
+            transaction(TransactionType::ConfigUpdate, encoded.clone()),
+            transaction(TransactionType::TreasuryTransfer, trailing),
+            transaction(TransactionType::TreasuryTransfer, encoded[..encoded.len() - 1].to_vec()),
+            transaction(TransactionType::StakingOperation, vec![0xff; 8]),
+        ] {
+            assert!(rejected.decode_action().is_err());
+            assert!(rejected.validate_transaction_data().is_err());
+        }
+
+        // Actions check their own parameters
+        let rates = |bps| MultisigAction::UpdateRewardRates { bps }.try_to_vec().unwrap();
+        let rate_change = transaction(TransactionType::RewardRateChange, rates(7000));
+        rate_change.validate_transaction_data().unwrap();
+        assert!(rate_change.is_batchable());
+        assert!(transaction(TransactionType::RewardRateChange, rates(9600)).decode_action().is_err());
+
+        let reentrant = MultisigAction::Custom { program: crate::ID, data: vec![1] };
+        assert!(transaction(TransactionType::StakingOperation, reentrant.try_to_vec().unwrap())
+            .decode_action()
+            .is_err());
+        let reverification = |before_ts, expires_at| MultisigAction::RequireReverification {
+            before_ts,
+            expires_at,
+            exempt_users: vec![Pubkey::new_unique()],
+        };
+        let campaign = transaction(TransactionType::ConfigUpdate, reverification(100, 200).try_to_vec().unwrap());
+        campaign.validate_transaction_data().unwrap();
+        assert!(campaign.is_batchable());
+        assert!(transaction(TransactionType::ConfigUpdate, reverification(200, 200).try_to_vec().unwrap())
+            .decode_action()
+            .is_err());
+        assert!(transaction(TransactionType::EmergencyAction, reverification(100, 200).try_to_vec().unwrap())
+            .decode_action()
+            .is_err());
+
+        let external = MultisigAction::Custom { program: Pubkey::new_unique(), data: vec![1] };
+        transaction(TransactionType::StakingOperation, external.try_to_vec().unwrap())
+            .decode_action()
+            .unwrap();
+
+        // Threshold changes keep their own format
+        transaction(TransactionType::ThresholdChange, vec![2]).validate_transaction_data().unwrap();
+        let removal = MultisigAction::RemoveSigner { signer: Pubkey::new_unique() };
+        assert!(transaction(TransactionType::KeyRotation, removal.try_to_vec().unwrap()).decode_action().is_ok());
+        assert!(transaction(TransactionType::ConfigUpdate, removal.try_to_vec().unwrap()).decode_action().is_err());
+        assert!(transaction(TransactionType::KeyRotation, vec![0; 96]).validate_transaction_data().is_err());
+
+        // Large payments are approved through reward distribution transactions
+        let approval = MultisigAction::PaymentApproval { payment_id: 7, approval_round: 0 }.try_to_vec().unwrap();
+        assert!(transaction(TransactionType::RewardDistribution, approval.clone()).decode_action().is_ok());
+        assert!(transaction(TransactionType::TreasuryTransfer, approval).decode_action().is_err());
+        let stream = MultisigAction::ApproveTreasuryStream {
+            stream: Pubkey::new_unique(),
+            recipient: Pubkey::new_unique(),
+            deposit: 1_000,
+        }.try_to_vec().unwrap();
+        assert!(transaction(TransactionType::RewardDistribution, stream.clone()).decode_action().is_ok());
+        assert!(transaction(TransactionType::ConfigUpdate, stream).decode_action().is_err());
+
+        // Payment float top-ups move treasury funds
+        let top_up = |amount| MultisigAction::TopUpPaymentFloat { method: PaymentMethod::USDC, amount }.try_to_vec().unwrap();
+        assert!(transaction(TransactionType::TreasuryTransfer, top_up(1_000)).decode_action().is_ok());
+        assert!(transaction(TransactionType::TreasuryTransfer, top_up(0)).decode_action().is_err());
+        assert!(transaction(TransactionType::ConfigUpdate, top_up(1_000)).decode_action().is_err());
+
+        // Sanctions roots are config updates and must stay current for a while
+        let sanctions_root = |max_staleness| MultisigAction::SetSanctionsRoot { root: [3; 32], max_staleness }.try_to_vec().unwrap();
+        assert!(transaction(TransactionType::ConfigUpdate, sanctions_root(86_400)).decode_action().is_ok());
+        assert!(transaction(TransactionType::ConfigUpdate, sanctions_root(0)).decode_action().is_err());
+        assert!(transaction(TransactionType::TreasuryTransfer, sanctions_root(86_400)).decode_action().is_err());
+
+        let region_policy = MultisigAction::SetRegionPolicy {
+            region: ComplianceRegion::Japan,
+            policy: RegionPolicy::unrestricted(),
+        }.try_to_vec().unwrap();
+        assert!(transaction(TransactionType::ConfigUpdate, region_policy.clone()).decode_action().is_ok());
+        assert!(transaction(TransactionType::AccountFreeze, region_policy).decode_action().is_err());
+
+        let rotation = |new_key_hash| MultisigAction::RotateScreeningProviderKey { provider_id: 1, new_key_hash }
+            .try_to_vec()
+            .unwrap();
+        assert!(transaction(TransactionType::ConfigUpdate, rotation([5; 32])).decode_action().is_ok());
+        assert!(transaction(TransactionType::ConfigUpdate, rotation([0; 32])).decode_action().is_err());
+        assert!(transaction(TransactionType::KeyRotation, rotation([5; 32])).decode_action().is_err());
+    }
+
+    #[test]
+    fn test_signer_changes_keep_history_and_revoke_signatures() {
+        let admin = weighted_signer(SignerRole::Admin, 1, true);
+        let mut operator = weighted_signer(SignerRole::Operator, 1, true);
+        operator.added_at = 5;
+        operator.last_signature = 50;
+        let mut wallet = MultisigWallet {
+            signers: vec![admin.clone(), operator.clone()],
+            threshold: 2,
+            transaction_count: 0,
+            executed_count: 0,
+            hsm_enabled: false,
+            hsm_quorum: 0,
+            emergency_mode: false,
+            last_key_rotation: 0,
+            key_rotation_interval: MultisigWallet::DEFAULT_KEY_ROTATION_INTERVAL,
+            created_at: 0,
+            timelock_secs: MultisigWallet::DEFAULT_TIMELOCK_SECS,
+            required_roles: [None, None, None, None, None, None, None, None, None],
+            fast_path_daily_limit: 0,
+            fast_path_threshold: 0,
+            guardian: None,
+            activity: Vec::new(),
+            activity_count: 0,
+            approval_nonce: 0,
+            parent_wallet: None,
+            bump: 0,
+        };
+
+        // Adding starts a fresh history; duplicates and a fourth signer are refused
+        let mut compliance = weighted_signer(SignerRole::Compliance, 1, true);
+        compliance.last_signature = 99;
+        wallet.add_signer(compliance.clone(), 100).unwrap();
+        assert_eq!(wallet.signers[2].added_at, 100);
+        assert_eq!(wallet.signers[2].last_signature, 0);
+        assert!(wallet.add_signer(compliance.clone(), 100).is_err());
+        assert!(wallet.add_signer(signer(true), 100).is_err());
+
+        // Replacing swaps one key and leaves the others' statistics alone
+        let replacement = Pubkey::new_unique();
+        assert!(wallet.replace_signer(&admin.pubkey, operator.pubkey, None, 200).is_err());
+        wallet.replace_signer(&admin.pubkey, replacement, None, 200).unwrap();
+        assert_eq!(wallet.signers[0].pubkey, replacement);
+        assert_eq!(wallet.signers[0].role, SignerRole::Admin);
+        assert_eq!(wallet.signers[0].added_at, 200);
+        assert_eq!(wallet.signers[1], operator);
+
+        // A signature made before a key joined counts for nothing
+        let signature = |signer: Pubkey, signed_at: i64| MultisigSignature {
+            signer,
+            signature: [0; 64],
+            digest: [0; 32],
+            hsm_attestation: None,
+            signed_at,
+            signature_type: SignatureType::Standard,
+        };
+        assert_eq!(wallet.signature_weight(&signature(replacement, 150)), 0);
+        assert_eq!(wallet.signature_weight(&signature(replacement, 200)), 1);
+
+        let mut pending = MultisigTransaction {
+            multisig: Pubkey::new_unique(),
+            transaction_id: 1,
+            proposer: operator.pubkey,
+            transaction_type: TransactionType::StakingOperation,
+            priority: TransactionPriority::Medium,
+            transaction_data: Vec::new(),
+            signatures: vec![signature(operator.pubkey, 300), signature(compliance.pubkey, 300)],
+            required_weight: 0,
+            executed: false,
+            cancelled: false,
+            failed: false,
+            vetoed: false,
+            expires_at: 3600,
+            created_at: 300,
+            executed_at: None,
+            execution_result: None,
+            approved_at: None,
+            execution_deadline: None,
+            proposal_digest: [0; 32],
+            bump: 0,
+        };
+        pending.sync_required_weight(&wallet);
+        assert!(pending.mark_approved_if_ready(&wallet, 300));
+
+        // Removing a signer drops their signature and revokes the approval
+        wallet.remove_signer(&compliance.pubkey).unwrap();
+        assert!(!pending.has_enough_signatures(&wallet));
+        assert!(pending.invalidate_signatures_from(&compliance.pubkey, &wallet));
+        assert_eq!(pending.signatures.len(), 1);
+        assert!(pending.approved_at.is_none());
+        assert!(pending.execution_deadline.is_none());
+        assert!(!pending.invalidate_signatures_from(&compliance.pubkey, &wallet));
+
+        // The remaining two can no longer lose anyone and still meet threshold
+        assert!(wallet.remove_signer(&operator.pubkey).is_err());
+        assert!(wallet.remove_signer(&Pubkey::new_unique()).is_err());
+
+        // Rotation carries continuing signers' history over
+        let mut continuing = operator.clone();
+        continuing.added_at = 0;
+        continuing.last_signature = 0;
+        continuing.carry_history_from(&wallet.signers[1]);
+        assert_eq!(continuing.added_at, 5);
+        assert_eq!(continuing.last_signature, 50);
+    }
+
+    #[test]
+    fn test_fast_path_caps_daily_spend() {
+        let first = signer(true);
+        let second = signer(true);
+        let mut wallet = MultisigWallet {
+            signers: vec![first.clone(), second.clone(), signer(true)],
+            threshold: 2,
+            transaction_count: 0,
+            executed_count: 0,
+            hsm_enabled: false,
+            hsm_quorum: 0,
+            emergency_mode: false,
+            last_key_rotation: 0,
+            key_rotation_interval: MultisigWallet::DEFAULT_KEY_ROTATION_INTERVAL,
+            created_at: 0,
+            timelock_secs: MultisigWallet::DEFAULT_TIMELOCK_SECS,
+            required_roles: [None, None, None, None, None, None, None, None, None],
+            fast_path_daily_limit: 0,
+            fast_path_threshold: 0,
+            guardian: None,
+            activity: Vec::new(),
+            activity_count: 0,
+            approval_nonce: 0,
+            parent_wallet: None,
+            bump: 0,
+        };
+        let action = MultisigAction::TreasuryTransfer {
+            to: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            amount: 60,
+        };
+        let mut transaction = MultisigTransaction {
+            multisig: Pubkey::new_unique(),
+            transaction_id: 0,
+            proposer: first.pubkey,
+            transaction_type: TransactionType::TreasuryTransfer,
+            priority: TransactionPriority::Low,
+            transaction_data: action.try_to_vec().unwrap(),
+            signatures: Vec::new(),
+            required_weight: 2,
+            executed: false,
+            cancelled: false,
+            failed: false,
+            vetoed: false,
+            expires_at: 3 * MultisigWallet::SECONDS_PER_DAY,
+            created_at: 0,
+            executed_at: None,
+            execution_result: None,
+            approved_at: None,
+            execution_deadline: None,
+            proposal_digest: [0; 32],
+            bump: 0,
+        };
+        transaction.seal_digest();
+        transaction.signatures.push(MultisigSignature {
+            signer: first.pubkey,
+            signature: [0; 64],
+            digest: transaction.proposal_digest,
+            hsm_attestation: None,
+            signed_at: 0,
+            signature_type: SignatureType::Standard,
+        });
+        let err = transaction.check_fast_path(&wallet, 10).unwrap_err();
+        assert_eq!(err, VaultError::FastPathUnavailable.into());
+
+        // Limits are set through a threshold change and stay below the full threshold
+        let limits = MultisigAction::SetFastPathLimits { daily_limit: 100, reduced_threshold: 1 };
+        let mut limits_change = transaction.clone();
+        limits_change.transaction_type = TransactionType::ThresholdChange;
+        limits_change.transaction_data = limits.try_to_vec().unwrap();
+        limits_change.validate_transaction_data().unwrap();
+        assert_eq!(limits_change.new_threshold(), None);
+        assert!(MultisigAction::SetFastPathLimits { daily_limit: 100, reduced_threshold: 0 }.validate().is_err());
+        assert!(wallet.set_fast_path_limits(100, 2).is_err());
+        wallet.set_fast_path_limits(100, 1).unwrap();
+        assert!(wallet.change_threshold(1).is_err());
+
+        // One approval is enough on the fast path, with no timelock
+        assert!(!transaction.is_ready_to_execute(&wallet, 10));
+        assert_eq!(transaction.check_fast_path(&wallet, 10).unwrap(), 60);
+        let approvers = transaction.counted_signers(&wallet);
+        assert_eq!(approvers, vec![first.pubkey]);
+        wallet.charge_fast_path(&approvers, 60, 10).unwrap();
+
+        // A second transfer the same day would pass the cap
+        let err = transaction.check_fast_path(&wallet, 20).unwrap_err();
+        assert_eq!(err, VaultError::FastPathLimitExceeded.into());
+        assert!(wallet.charge_fast_path(&approvers, 60, 20).is_err());
+        assert_eq!(wallet.signers[0].fast_path_spent, 60);
+        wallet.charge_fast_path(&[second.pubkey], 60, 20).unwrap();
+
+        // The spend resets at the next UTC day
+        let tomorrow = MultisigWallet::SECONDS_PER_DAY;
+        assert_eq!(transaction.check_fast_path(&wallet, tomorrow).unwrap(), 60);
+        wallet.charge_fast_path(&approvers, 40, tomorrow).unwrap();
+        assert_eq!(wallet.signers[0].fast_path_spent, 40);
+        assert_eq!(wallet.signers[0].fast_path_day, 1);
+
+        // Other transaction types never take the fast path
+        let mut freeze = transaction.clone();
+        freeze.transaction_type = TransactionType::AccountFreeze;
+        let err = freeze.check_fast_path(&wallet, tomorrow).unwrap_err();
+        assert_eq!(err, VaultError::FastPathUnavailable.into());
+    }
+
+    #[test]
+    fn test_guardian_veto_window_and_review_flag() {
+        let proposer = signer(true);
+        let mut wallet = MultisigWallet {
+            signers: vec![proposer.clone(), signer(true)],
+            threshold: 1,
+            transaction_count: 0,
+            executed_count: 0,
+            hsm_enabled: false,
+            hsm_quorum: 0,
+            emergency_mode: false,
+            last_key_rotation: 0,
+            key_rotation_interval: MultisigWallet::DEFAULT_KEY_ROTATION_INTERVAL,
+            created_at: 0,
+            timelock_secs: MultisigWallet::DEFAULT_TIMELOCK_SECS,
+            required_roles: [None, None, None, None, None, None, None, None, None],
+            fast_path_daily_limit: 0,
+            fast_path_threshold: 0,
+            guardian: None,
+            activity: Vec::new(),
+            activity_count: 0,
+            approval_nonce: 0,
+            parent_wallet: None,
+            bump: 0,
+        };
+        let guardian = Pubkey::new_unique();
+        assert_eq!(wallet.set_guardian(Some(guardian)), None);
+        let rotation = MultisigAction::SetGuardian { guardian: None };
+        assert!(rotation.is_permitted_for(&TransactionType::KeyRotation));
+        assert!(!rotation.is_permitted_for(&TransactionType::ConfigUpdate));
+
+        let mut transaction = MultisigTransaction {
+            multisig: Pubkey::new_unique(),
+            transaction_id: 0,
+            proposer: proposer.pubkey,
+            transaction_type: TransactionType::ThresholdChange,
+            priority: TransactionPriority::Low,
+            transaction_data: vec![1],
+            signatures: Vec::new(),
+            required_weight: 1,
+            executed: false,
+            cancelled: false,
+            failed: false,
+            vetoed: false,
+            expires_at: 3600,
+            created_at: 0,
+            executed_at: None,
+            execution_result: None,
+            approved_at: None,
+            execution_deadline: None,
+            proposal_digest: [0; 32],
+            bump: 0,
+        };
+        let timelock = wallet.timelock_for(&transaction.transaction_type);
+        let err = transaction.veto("too early".to_string(), 10, timelock).unwrap_err();
+        assert_eq!(err, VaultError::MultisigThresholdNotMet.into());
+
+        // Only during the timelock
+        transaction.approved_at = Some(10);
+        transaction.execution_deadline = Some(10 + timelock + 3600);
+        let err = transaction.veto("too late".to_string(), 10 + timelock, timelock).unwrap_err();
+        assert_eq!(err, VaultError::VetoWindowClosed.into());
+        assert!(transaction.veto("x".repeat(300), 20, timelock).is_err());
+        transaction.veto("unexpected threshold".to_string(), 20, timelock).unwrap();
+        assert!(transaction.is_settled());
+        assert!(transaction.check_executable(&wallet, 10 + timelock).is_err());
+        assert!(transaction.veto("again".to_string(), 30, timelock).is_err());
+
+        // The third veto within 30 days flags the proposer, once
+        let day = MultisigWallet::SECONDS_PER_DAY;
+        assert!(!wallet.record_veto(&proposer.pubkey, day));
+        assert!(!wallet.record_veto(&proposer.pubkey, 20 * day));
+        assert!(!wallet.record_veto(&proposer.pubkey, 40 * day));
+        assert!(!wallet.signers[0].flagged_for_review);
+        assert!(wallet.record_veto(&proposer.pubkey, 45 * day));
+        assert!(wallet.signers[0].flagged_for_review);
+        assert!(!wallet.record_veto(&proposer.pubkey, 46 * day));
+        assert!(!wallet.record_veto(&Pubkey::new_unique(), 46 * day));
+    }
+
+    #[test]
+    fn test_parent_wallet_rejects_cycles() {
+        let mut child = MultisigWallet {
+            signers: vec![signer(true)],
+            threshold: 1,
+            transaction_count: 0,
+            executed_count: 0,
+            hsm_enabled: false,
+            hsm_quorum: 0,
+            emergency_mode: false,
+            last_key_rotation: 0,
+            key_rotation_interval: MultisigWallet::DEFAULT_KEY_ROTATION_INTERVAL,
+            created_at: 0,
+            timelock_secs: MultisigWallet::DEFAULT_TIMELOCK_SECS,
+            required_roles: [None, None, None, None, None, None, None, None, None],
+            fast_path_daily_limit: 0,
+            fast_path_threshold: 0,
+            guardian: None,
+            activity: Vec::new(),
+            activity_count: 0,
+            approval_nonce: 0,
+            parent_wallet: None,
+            bump: 0,
+        };
+        let child_key = Pubkey::new_unique();
+        let parent_key = Pubkey::new_unique();
+        let mut parent = child.clone();
+
+        // A wallet cannot parent itself or its own parent
+        assert!(child.set_parent(child_key, child_key, &parent).is_err());
+        parent.parent_wallet = Some(child_key);
+        assert!(child.set_parent(child_key, parent_key, &parent).is_err());
+        assert_eq!(child.parent_wallet, None);
+
+        parent.parent_wallet = None;
+        child.set_parent(child_key, parent_key, &parent).unwrap();
+        assert_eq!(child.parent_wallet, Some(parent_key));
+        // The parent is fixed once set
+        assert!(child.set_parent(child_key, Pubkey::new_unique(), &parent).is_err());
+    }
+
+    #[test]
+    fn test_activity_log_keeps_latest_entries_and_pages() {
+        let mut wallet = MultisigWallet {
+            signers: vec![signer(true)],
+            threshold: 1,
+            transaction_count: 0,
+            executed_count: 0,
+            hsm_enabled: false,
+            hsm_quorum: 0,
+            emergency_mode: false,
+            last_key_rotation: 0,
+            key_rotation_interval: MultisigWallet::DEFAULT_KEY_ROTATION_INTERVAL,
+            created_at: 0,
+            timelock_secs: MultisigWallet::DEFAULT_TIMELOCK_SECS,
+            required_roles: [None, None, None, None, None, None, None, None, None],
+            fast_path_daily_limit: 0,
+            fast_path_threshold: 0,
+            guardian: None,
+            activity: Vec::new(),
+            activity_count: 0,
+            approval_nonce: 0,
+            parent_wallet: None,
+            bump: 0,
+        };
+        let actor = wallet.signers[0].pubkey;
+        let page = wallet.activity_page(0);
+        assert!(page.entries.is_empty());
+        assert_eq!((page.start, page.next_cursor, page.total), (0, 0, 0));
+
+        assert_eq!(wallet.record_activity(ActivityKind::Propose, actor, 0, 10), 0);
+        assert_eq!(wallet.record_activity(ActivityKind::Sign, actor, 0, 20), 1);
+        let page = wallet.activity_page(1);
+        assert_eq!(page.entries, vec![ActivityEntry { kind: ActivityKind::Sign, actor, transaction_id: 0, timestamp: 20 }]);
+        assert_eq!(page.next_cursor, 2);
+
+        // Past capacity the oldest entries are overwritten
+        let recorded = MultisigWallet::ACTIVITY_LOG_LEN as u64 + 30;
+        for sequence in 2..recorded {
+            wallet.record_activity(ActivityKind::Execute, actor, sequence as u32, sequence as i64);
+        }
+        assert_eq!(wallet.activity.len(), MultisigWallet::ACTIVITY_LOG_LEN);
+        assert_eq!(wallet.activity_count, recorded);
+
+        let page = wallet.activity_page(0);
+        assert_eq!(page.start, 30);
+        assert_eq!(page.entries.len(), MultisigWallet::ACTIVITY_PAGE_LEN);
+        assert_eq!(page.entries[0].transaction_id, 30);
+        assert_eq!(page.next_cursor, 30 + MultisigWallet::ACTIVITY_PAGE_LEN as u64);
+
+        let last = wallet.activity_page(recorded - 1);
+        assert_eq!(last.entries.len(), 1);
+        assert_eq!(last.entries[0].transaction_id as u64, recorded - 1);
+        assert_eq!(last.next_cursor, recorded);
+        assert!(wallet.activity_page(recorded + 5).entries.is_empty());
+    }
+}
@@ -203,6 +203,15 @@ impl OracleData {
         self.retry_config.last_retry = 0;
     }
 
+    /// Point the oracle at a new BTC/USD feed. The cached price came from
+    /// the old feed, so it is cleared and reads as stale until refreshed.
+    pub fn change_feed(&mut self, btc_usd_feed: Pubkey) {
+        self.btc_usd_feed = btc_usd_feed;
+        self.btc_price_usd = 0;
+        self.last_update = 0;
+        self.reset_retry();
+    }
+
     /// Validate ECDSA proof for anti-spoofing
     pub fn validate_ecdsa_proof(
         &self,
@@ -1,11 +1,19 @@
 use anchor_lang::prelude::*;
+use crate::crypto::Bolt11Invoice;
 use crate::errors::VaultError;
+use crate::state::kyc_compliance::{AMLScreening, SanctionsVerdict};
+use crate::state::security_monitoring::SecurityLevel;
 
 /// Payment method options for reward distribution
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
 pub enum PaymentMethod {
     Lightning,  // Bitcoin Lightning Network (default)
     USDC,      // USDC on Solana
+    SplToken { mint: Pubkey }, // Any SPL token configured on the payment system
+}
+
+impl PaymentMethod {
+    pub const LEN: usize = 1 + 32; // tag + largest variant (SplToken mint)
 }
 
 /// Payment status tracking
@@ -16,6 +24,17 @@ pub enum PaymentStatus {
     Completed,
     Failed,
     Cancelled,
+    Refunded,   // Failed or cancelled, with the claimed amount restored
+    PendingLimitReset, // Held over a daily payout limit until its window rolls
+    Expired,    // Pending past the payment timeout, awaiting its refund
+}
+
+impl PaymentStatus {
+    /// Whether the payment can no longer change state. A cancelled or
+    /// expired payment still awaits its refund.
+    pub fn is_final(&self) -> bool {
+        matches!(self, PaymentStatus::Completed | PaymentStatus::Failed | PaymentStatus::Refunded)
+    }
 }
 
 /// Lightning Network payment configuration
@@ -27,16 +46,49 @@ pub struct LightningConfig {
     pub timeout_blocks: u16,          // Payment timeout in blocks
     pub max_payment_amount: u64,      // Maximum payment in sats
     pub min_payment_amount: u64,      // Minimum payment in sats
+    pub float_watermark: u64,         // Alert when the channel budget falls below this, in sats
 }
 
 /// USDC payment configuration
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
 pub struct UsdcConfig {
     pub mint_address: Pubkey,         // USDC mint address
-    pub treasury_ata: Pubkey,         // Treasury associated token account
+    pub treasury_ata: Pubkey,         // Treasury associated token account, owned by the payment system PDA
+    pub fee_ata: Pubkey,              // Token account receiving payment fees
     pub fee_basis_points: u16,        // Fee in basis points (100 = 1%)
     pub max_payment_amount: u64,      // Maximum payment in USDC (6 decimals)
     pub min_payment_amount: u64,      // Minimum payment in USDC (6 decimals)
+    pub float_watermark: u64,         // Alert when the treasury ATA float falls below this
+}
+
+/// Per-mint configuration for SPL token payments
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct SplTokenConfig {
+    pub mint: Pubkey,                 // Token mint paid out
+    pub treasury_ata: Pubkey,         // Treasury token account for this mint
+    pub fee_basis_points: u16,        // Fee in basis points (100 = 1%)
+    pub max_payment_amount: u64,      // Maximum payment in base units
+    pub min_payment_amount: u64,      // Minimum payment in base units
+    pub approval_threshold: u64,      // Payments above this need multisig approval
+    pub float_watermark: u64,         // Alert when the treasury ATA float falls below this
+}
+
+impl SplTokenConfig {
+    pub const LEN: usize = 32 + 32 + 2 + 8 + 8 + 8 + 8;
+
+    /// Amount the recipient receives once the fee is withheld
+    pub fn net_amount(&self, amount: u64) -> Result<u64> {
+        let fee = payment_fee(amount, self.fee_basis_points)?;
+        amount.checked_sub(fee).ok_or(VaultError::ArithmeticOverflow.into())
+    }
+}
+
+/// Fee charged on a payment, rounded down
+pub fn payment_fee(amount: u64, fee_basis_points: u16) -> Result<u64> {
+    if fee_basis_points > 10_000 {
+        return Err(VaultError::InvalidPaymentMintConfig.into());
+    }
+    Ok((amount as u128 * fee_basis_points as u128 / 10_000) as u64)
 }
 
 /// Auto-reinvestment configuration
@@ -48,6 +100,204 @@ pub struct ReinvestmentConfig {
     pub compound_frequency: u32,      // Compounding frequency in seconds
 }
 
+impl ReinvestmentConfig {
+    /// Shortest allowed time between reinvestments
+    pub const MIN_COMPOUND_FREQUENCY: u32 = 3600;
+
+    /// Share of `pending_rewards` to reinvest, or None when reinvestment is
+    /// off or the share is below `min_threshold`
+    pub fn reinvestable_amount(&self, pending_rewards: u64) -> Option<u64> {
+        if !self.enabled {
+            return None;
+        }
+        let amount = (pending_rewards as u128 * self.percentage as u128 / 100) as u64;
+        (amount > 0 && amount >= self.min_threshold).then_some(amount)
+    }
+}
+
+/// Emitted for every reinvestment, manual or by the keeper crank
+#[event]
+pub struct ReinvestmentExecuted {
+    pub user: Pubkey,
+    pub reinvested_amount: u64,       // Rewards spent
+    pub sats: u64,                    // Virtual sats added to the commitment
+    pub total_reinvested_sats: u64,
+    pub timestamp: i64,
+}
+
+/// Informational copy of a reinvestment for users who opted into
+/// `NotificationPreferences::reinvestment_executed`
+#[event]
+pub struct ReinvestmentNotification {
+    pub user: Pubkey,
+    pub reinvested_amount: u64,
+    pub sats: u64,
+    pub pending_rewards: u64,         // Left after the reinvestment
+    pub next_reinvestment_at: i64,
+}
+
+/// Payout caps per day-long window, in reward base units as deducted from
+/// pending rewards. Zero disables a cap.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct VelocityLimits {
+    pub user_daily_limit: u64,          // Per user
+    pub enhanced_user_daily_limit: u64, // Per user with approved Enhanced KYC or above
+    pub protocol_daily_limit: u64,      // Across all users
+}
+
+impl VelocityLimits {
+    pub const LEN: usize = 8 + 8 + 8;
+}
+
+/// AML screening required of payout destinations. A zero TTL turns
+/// screening off.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct ScreeningPolicy {
+    pub ttl_seconds: i64,    // How long a destination screening stays valid
+    pub risk_threshold: u16, // Destinations scoring at or above this need multisig approval
+}
+
+impl ScreeningPolicy {
+    pub const LEN: usize = 8 + 2;
+
+    pub fn is_enabled(&self) -> bool {
+        self.ttl_seconds > 0
+    }
+}
+
+/// Payout volume within a day-long window. A window opens with the first
+/// payout after the previous one has closed.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct PayoutWindow {
+    pub window_start: i64,
+    pub volume: u64,
+}
+
+impl PayoutWindow {
+    pub const LEN: usize = 8 + 8;
+    pub const DURATION_SECONDS: i64 = 86_400;
+
+    fn volume_at(&self, now: i64) -> u64 {
+        if now < self.window_start.saturating_add(Self::DURATION_SECONDS) {
+            self.volume
+        } else {
+            0
+        }
+    }
+
+    /// Whether `amount` more fits under `limit` at `now`; a zero limit always fits
+    pub fn has_room(&self, amount: u64, limit: u64, now: i64) -> bool {
+        limit == 0 || self.volume_at(now).checked_add(amount).is_some_and(|v| v <= limit)
+    }
+
+    pub fn charge(&mut self, amount: u64, now: i64) {
+        if self.volume_at(now) == 0 {
+            self.window_start = now;
+            self.volume = 0;
+        }
+        self.volume = self.volume.saturating_add(amount);
+    }
+}
+
+/// A user's payouts against the daily velocity limits
+#[account]
+pub struct UserPayoutVelocity {
+    pub user: Pubkey,
+    pub window: PayoutWindow,
+    pub bump: u8,
+}
+
+impl UserPayoutVelocity {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // user
+        PayoutWindow::LEN + // window
+        1; // bump
+
+    pub fn initialize(&mut self, user: Pubkey, bump: u8) {
+        self.user = user;
+        self.window = PayoutWindow::default();
+        self.bump = bump;
+    }
+}
+
+/// A client idempotency key and the request it created
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct IdempotencyEntry {
+    pub key: [u8; 32],
+    pub params_hash: [u8; 32], // Hash of the request parameters sent with the key
+    pub payment_id: u64,
+    pub created_at: i64,
+}
+
+impl IdempotencyEntry {
+    pub const LEN: usize = 32 + 32 + 8 + 8;
+}
+
+/// A user's recent client idempotency keys, so a retried request returns
+/// the payment it already created instead of claiming twice
+#[account]
+pub struct PaymentIdempotency {
+    pub user: Pubkey,
+    pub entries: Vec<IdempotencyEntry>, // Oldest first
+    pub bump: u8,
+}
+
+impl PaymentIdempotency {
+    pub const MAX_KEYS: usize = 16;
+    pub const KEY_TTL_SECONDS: i64 = 24 * 3600;
+
+    pub const LEN: usize = 8 + // discriminator
+        32 + // user
+        4 + (Self::MAX_KEYS * IdempotencyEntry::LEN) + // entries
+        1; // bump
+
+    pub fn initialize(&mut self, user: Pubkey, bump: u8) {
+        self.user = user;
+        self.entries = Vec::new();
+        self.bump = bump;
+    }
+
+    /// Hash of a request's parameters as sent by the client
+    pub fn params_hash(
+        method: &Option<PaymentMethod>,
+        amount: u64,
+        destination: &str,
+        use_saved_destination: Option<u8>,
+        quote_id: Option<u64>,
+    ) -> Result<[u8; 32]> {
+        Ok(solana_program::hash::hashv(&[
+            &method.try_to_vec()?,
+            &amount.to_le_bytes(),
+            &destination.try_to_vec()?,
+            &use_saved_destination.try_to_vec()?,
+            &quote_id.try_to_vec()?,
+        ]).to_bytes())
+    }
+
+    /// The payment already created under `key` within the last day. Reusing
+    /// a key with different parameters is an error.
+    pub fn lookup(&self, key: &[u8; 32], params_hash: &[u8; 32], now: i64) -> Result<Option<u64>> {
+        let entry = self.entries.iter()
+            .rev()
+            .find(|e| e.key == *key && now - e.created_at < Self::KEY_TTL_SECONDS);
+        match entry {
+            Some(entry) if entry.params_hash != *params_hash => Err(VaultError::IdempotencyKeyReused.into()),
+            Some(entry) => Ok(Some(entry.payment_id)),
+            None => Ok(None),
+        }
+    }
+
+    /// Remember the payment created under `key`, dropping expired keys and,
+    /// once full, the oldest
+    pub fn remember(&mut self, key: [u8; 32], params_hash: [u8; 32], payment_id: u64, now: i64) {
+        self.entries.retain(|e| now - e.created_at < Self::KEY_TTL_SECONDS && e.key != key);
+        if self.entries.len() >= Self::MAX_KEYS {
+            self.entries.remove(0);
+        }
+        self.entries.push(IdempotencyEntry { key, params_hash, payment_id, created_at: now });
+    }
+}
+
 /// Payment request structure
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
 pub struct PaymentRequest {
@@ -63,40 +313,285 @@ pub struct PaymentRequest {
     pub failure_reason: Option<String>, // Failure reason if applicable
     pub retry_count: u8,              // Number of retry attempts
     pub multisig_required: bool,      // Whether multisig approval is required
+    pub claim_id: u64,                // Claim that funded this payment
+    pub refunded: bool,               // Whether the claim was restored after failure or cancellation
+    pub payment_hash: Option<[u8; 32]>, // Lightning invoice payment hash, settled by its preimage
+    pub quoted_fee_bps: Option<u16>,  // Protocol fee honored from a quote; live config fee when None
+    pub next_retry_at: Option<i64>,   // Earliest time of the next attempt after a failure
+    pub parent_id: Option<u64>,       // Split payment this request is a part of
+    pub high_risk: bool,              // Destination screened high risk; approval is needed whatever the amount
+    pub approval_round: u8,           // Advances when the risk tier or an approval-gated destination changes; older multisig approvals go stale
+    pub destination_history: Vec<[u8; 32]>, // Hashes of destinations replaced before payout, oldest first
+    pub travel_rule: Option<TravelRuleAttachment>, // Originator and beneficiary data for payouts over the travel rule threshold
+    pub risk_score: u8,               // Operation risk score (0-100) when requested
+    pub risk_gated: bool,             // Score reached the approval threshold; approval is needed whatever the amount
+}
+
+/// Encrypted travel rule data for a payment, held off-chain by a travel
+/// rule provider. Only its hash is kept on-chain.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct TravelRuleAttachment {
+    pub data_hash: [u8; 32],   // Hash of the encrypted originator/beneficiary blob
+    pub provider_id: u32,      // Provider holding the blob
+    pub attached_at: i64,
+}
+
+impl TravelRuleAttachment {
+    pub const LEN: usize = 32 + 4 + 8;
+}
+
+/// How a new payment request is priced and approved
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RequestTerms {
+    pub quoted_fee_bps: Option<u16>, // Protocol fee honored from a valid quote
+    pub high_risk: bool,             // Destination screened high risk; needs multisig approval
+    pub risk: RiskAssessment,        // Operation risk of the request
+}
+
+/// A request's operation risk score and whether it reached the threshold
+/// for multisig approval
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RiskAssessment {
+    pub score: u8,
+    pub requires_approval: bool,
+}
+
+/// Result of recording a completion attempt on a payment request
+#[derive(Clone, Debug, PartialEq)]
+pub enum CompletionOutcome {
+    Completed,
+    Retrying,
+    /// Retries exhausted; the request moves to the dead-letter queue
+    DeadLettered,
+    /// Payment already in a final state; nothing changed
+    NoOp,
+}
+
+impl PaymentRequest {
+    pub const LEN: usize = 8 + 32 + PaymentMethod::LEN + 8 + 4 + 64 + 1 + 8 + 9 + 9 + 4 + 64 + 1 + 1 + 8 + 1 + 33 + 3 + 9 + 9
+        + 1 + 1 + 4 + 32 * Self::MAX_DESTINATION_REPLACEMENTS + 1 + TravelRuleAttachment::LEN + 1 + 1;
+
+    /// How many times a pending request's destination can be replaced
+    pub const MAX_DESTINATION_REPLACEMENTS: usize = 3;
+
+    /// Delay before the first retry; doubled for every further failure
+    pub const BASE_RETRY_DELAY_SECONDS: i64 = 60;
+    pub const MAX_RETRY_DELAY_SECONDS: i64 = 6 * 3600;
+
+    /// Backoff after the `retry_count`th failure
+    pub fn retry_delay(retry_count: u8) -> i64 {
+        let doublings = retry_count.saturating_sub(1).min(16) as u32;
+        (Self::BASE_RETRY_DELAY_SECONDS << doublings).min(Self::MAX_RETRY_DELAY_SECONDS)
+    }
+
+    /// Reject an attempt made before the retry backoff has passed
+    pub fn require_retry_due(&self, now: i64) -> Result<()> {
+        match self.next_retry_at {
+            Some(next_retry_at) if now < next_retry_at => Err(VaultError::PaymentRetryBackoff.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether the payment can be processed: it needs no multisig approval,
+    /// or already has it
+    pub fn is_processable(&self) -> bool {
+        match self.status {
+            PaymentStatus::Processing => true,
+            PaymentStatus::Pending => !self.multisig_required,
+            _ => false,
+        }
+    }
+
+    /// Whether the payment still draws on its method's float. Lightning
+    /// spends the channel budget and USDC its ATA balance on completion; SPL
+    /// tokens leave the treasury ATA when processed.
+    pub fn holds_float(&self) -> bool {
+        let in_flight = matches!(
+            self.status,
+            PaymentStatus::Pending | PaymentStatus::Processing | PaymentStatus::PendingLimitReset
+        );
+        match self.method {
+            PaymentMethod::SplToken { .. } => in_flight && !self.tokens_sent(),
+            PaymentMethod::Lightning | PaymentMethod::USDC => in_flight,
+        }
+    }
+
+    /// Whether an SPL token payout has already left the treasury. SPL
+    /// transfers happen when the request is processed, so such a request
+    /// can't be processed again or reported as failed.
+    pub fn tokens_sent(&self) -> bool {
+        matches!(self.method, PaymentMethod::SplToken { .. }) && self.processed_at.is_some()
+    }
+
+    /// SHA-256 of the destination, so events identify it without repeating
+    /// a whole invoice
+    pub fn destination_hash(&self) -> [u8; 32] {
+        Self::hash_destination(&self.destination)
+    }
+
+    pub fn hash_destination(destination: &str) -> [u8; 32] {
+        solana_program::hash::hash(destination.as_bytes()).to_bytes()
+    }
+
+    /// Protocol fee withheld from this payment: the quoted rate if it was
+    /// requested under a valid quote, else the configured `fee_basis_points`
+    pub fn protocol_fee(&self, fee_basis_points: u16) -> Result<u64> {
+        payment_fee(self.amount, self.quoted_fee_bps.unwrap_or(fee_basis_points))
+    }
+
+    /// Record a completion attempt. Final states are sticky, so repeating the
+    /// call for the same claim never double-counts volume or double-refunds.
+    pub fn record_completion(
+        &mut self,
+        claim_id: u64,
+        success: bool,
+        failure_reason: Option<String>,
+        now: i64,
+        max_retries: u8,
+    ) -> Result<CompletionOutcome> {
+        if self.claim_id != claim_id {
+            return Err(VaultError::ClaimIdMismatch.into());
+        }
+
+        match self.status {
+            PaymentStatus::Completed
+            | PaymentStatus::Failed
+            | PaymentStatus::Cancelled
+            | PaymentStatus::Expired
+            | PaymentStatus::Refunded => {
+                return Ok(CompletionOutcome::NoOp);
+            },
+            _ => {},
+        }
+
+        if success {
+            self.status = PaymentStatus::Completed;
+            self.completed_at = Some(now);
+            return Ok(CompletionOutcome::Completed);
+        }
+
+        // Tokens already paid out can't be retried or refunded
+        if self.tokens_sent() {
+            return Err(VaultError::InvalidPaymentStatus.into());
+        }
+
+        self.retry_count = self.retry_count.saturating_add(1);
+        self.failure_reason = failure_reason;
+
+        if self.retry_count < max_retries {
+            // An approved payment keeps its approval across retries
+            self.status = if self.multisig_required {
+                PaymentStatus::Processing
+            } else {
+                PaymentStatus::Pending
+            };
+            self.next_retry_at = Some(now.saturating_add(Self::retry_delay(self.retry_count)));
+            return Ok(CompletionOutcome::Retrying);
+        }
+
+        self.status = PaymentStatus::Failed;
+        self.next_retry_at = None;
+        Ok(CompletionOutcome::DeadLettered)
+    }
+
+    /// Put a dead-lettered request back in line with a fresh set of retries.
+    /// It was processable when it failed, so it goes straight to Processing.
+    pub fn requeue(&mut self) -> Result<()> {
+        if self.status != PaymentStatus::Failed || self.refunded {
+            return Err(VaultError::InvalidPaymentStatus.into());
+        }
+        self.status = PaymentStatus::Processing;
+        self.retry_count = 0;
+        self.next_retry_at = None;
+        Ok(())
+    }
+
+    /// Expire a request left pending past the payment timeout. Requests
+    /// already processing belong to the off-chain executor and never expire.
+    pub fn expire(&mut self, now: i64) -> bool {
+        let overdue = now.saturating_sub(self.created_at) > PaymentSystem::PAYMENT_TIMEOUT_SECONDS;
+        if self.status != PaymentStatus::Pending || !overdue {
+            return false;
+        }
+        self.status = PaymentStatus::Expired;
+        self.completed_at = Some(now);
+        true
+    }
+
+    /// Mark a failed, cancelled or expired payment refunded. Returns the
+    /// amount to restore to the user, or None if it was already restored.
+    pub fn refund(&mut self) -> Result<Option<u64>> {
+        match self.status {
+            PaymentStatus::Refunded => Ok(None),
+            PaymentStatus::Failed | PaymentStatus::Cancelled | PaymentStatus::Expired => {
+                self.status = PaymentStatus::Refunded;
+                if self.refunded {
+                    return Ok(None);
+                }
+                self.refunded = true;
+                Ok(Some(self.amount))
+            },
+            _ => Err(VaultError::InvalidPaymentStatus.into()),
+        }
+    }
 }
 
 #[account]
 pub struct PaymentSystem {
     pub lightning_config: LightningConfig,
     pub usdc_config: UsdcConfig,
+    pub spl_token_configs: Vec<SplTokenConfig>, // Accepted SPL token mints
     pub payment_requests: Vec<PaymentRequest>,
     pub total_payments_processed: u64,
     pub total_lightning_volume: u64,
     pub total_usdc_volume: u64,
+    pub total_spl_token_volume: u64,  // Across all SPL token mints, in base units
     pub failed_payments_count: u64,
     pub last_payment_id: u64,
     pub emergency_pause: bool,        // Emergency pause for payments
     pub multisig_wallet: Pubkey,      // Associated multisig wallet
+    pub velocity_limits: VelocityLimits,
+    pub payout_window: PayoutWindow,  // Protocol-wide payouts against `protocol_daily_limit`
+    pub event_sequence: u64,          // Sequence number of the last payment event
+    pub daily_rollups: Vec<DailyRollup>, // Ring buffer of the last `ROLLUP_DAYS` UTC days, indexed by day
+    pub split_payments: Vec<SplitPayment>, // Split payments with parts still outstanding
+    pub lightning_float: u64,         // Channel budget in sats available for Lightning payouts
+    pub screening_policy: ScreeningPolicy,
     pub bump: u8,
 }
 
 impl PaymentSystem {
     pub const LEN: usize = 8 + // discriminator
-        (33 + 8 + 2 + 2 + 8 + 8) + // lightning_config
-        (32 + 32 + 2 + 8 + 8) + // usdc_config
-        4 + (20 * (8 + 32 + 1 + 8 + 4 + 64 + 1 + 8 + 9 + 9 + 4 + 64 + 1 + 1)) + // payment_requests (max 20)
+        (33 + 8 + 2 + 2 + 8 + 8 + 8) + // lightning_config
+        (32 + 32 + 32 + 2 + 8 + 8 + 8) + // usdc_config
+        4 + (Self::MAX_SPL_TOKEN_MINTS * SplTokenConfig::LEN) + // spl_token_configs
+        4 + (20 * PaymentRequest::LEN) + // payment_requests (max 20)
         8 + // total_payments_processed
         8 + // total_lightning_volume
         8 + // total_usdc_volume
+        8 + // total_spl_token_volume
         8 + // failed_payments_count
         8 + // last_payment_id
         1 + // emergency_pause
         32 + // multisig_wallet
+        VelocityLimits::LEN + // velocity_limits
+        PayoutWindow::LEN + // payout_window
+        8 + // event_sequence
+        4 + (Self::ROLLUP_DAYS * DailyRollup::LEN) + // daily_rollups
+        4 + (Self::MAX_SPLIT_PAYMENTS * SplitPayment::LEN) + // split_payments
+        8 + // lightning_float
+        ScreeningPolicy::LEN + // screening_policy
         1; // bump
 
     pub const MAX_PAYMENT_REQUESTS: usize = 20;
+    pub const ROLLUP_DAYS: usize = 30;
+    pub const ROLLUP_PAGE_LEN: usize = 10;
+    pub const MAX_SPLIT_PARTS: usize = 3;
+    pub const MAX_SPLIT_PAYMENTS: usize = Self::MAX_PAYMENT_REQUESTS / 2;
+    pub const MAX_SPL_TOKEN_MINTS: usize = 8;
     pub const MAX_RETRY_ATTEMPTS: u8 = 3;
     pub const PAYMENT_TIMEOUT_SECONDS: i64 = 3600; // 1 hour
+    pub const RETENTION_SECONDS: i64 = 24 * 3600; // Finalized requests kept in the queue after the timeout
 
     /// Initialize payment system with configurations
     pub fn initialize(
@@ -108,14 +603,23 @@ impl PaymentSystem {
     ) -> Result<()> {
         self.lightning_config = lightning_config;
         self.usdc_config = usdc_config;
+        self.spl_token_configs = Vec::new();
         self.payment_requests = Vec::new();
         self.total_payments_processed = 0;
         self.total_lightning_volume = 0;
         self.total_usdc_volume = 0;
+        self.total_spl_token_volume = 0;
         self.failed_payments_count = 0;
         self.last_payment_id = 0;
         self.emergency_pause = false;
         self.multisig_wallet = multisig_wallet;
+        self.velocity_limits = VelocityLimits::default();
+        self.payout_window = PayoutWindow::default();
+        self.event_sequence = 0;
+        self.daily_rollups = Vec::new();
+        self.split_payments = Vec::new();
+        self.lightning_float = 0;
+        self.screening_policy = ScreeningPolicy::default();
         self.bump = bump;
 
         Ok(())
@@ -128,6 +632,8 @@ impl PaymentSystem {
         method: PaymentMethod,
         amount: u64,
         destination: String,
+        claim_id: u64,
+        terms: RequestTerms,
     ) -> Result<u64> {
         if self.emergency_pause {
             return Err(VaultError::PaymentSystemPaused.into());
@@ -139,8 +645,18 @@ impl PaymentSystem {
         // Validate destination format
         self.validate_destination(&method, &destination)?;
 
+        let clock = Clock::get()?;
+        let payment_hash = match method {
+            PaymentMethod::Lightning => {
+                Some(Self::check_lightning_invoice(&destination, amount, clock.unix_timestamp)?)
+            },
+            _ => None,
+        };
+
         // Check if we need multisig approval
-        let multisig_required = self.requires_multisig_approval(&method, amount);
+        let multisig_required = terms.high_risk
+            || terms.risk.requires_approval
+            || self.requires_multisig_approval(&method, amount);
 
         // Clean up old payment requests
         self.cleanup_old_requests()?;
@@ -151,7 +667,6 @@ impl PaymentSystem {
 
         let payment_id = self.last_payment_id.checked_add(1)
             .ok_or(VaultError::ArithmeticOverflow)?;
-        let clock = Clock::get()?;
 
         let payment_request = PaymentRequest {
             id: payment_id,
@@ -170,8 +685,30 @@ impl PaymentSystem {
             failure_reason: None,
             retry_count: 0,
             multisig_required,
+            claim_id,
+            refunded: false,
+            payment_hash,
+            quoted_fee_bps: terms.quoted_fee_bps,
+            next_retry_at: None,
+            parent_id: None,
+            high_risk: terms.high_risk,
+            approval_round: 0,
+            destination_history: Vec::new(),
+            travel_rule: None,
+            risk_score: terms.risk.score,
+            risk_gated: terms.risk.requires_approval,
         };
 
+        emit!(PaymentRequested {
+            sequence: self.next_event_sequence()?,
+            payment_id,
+            user,
+            method: method.clone(),
+            amount,
+            destination_hash: payment_request.destination_hash(),
+            multisig_required,
+        });
+
         self.payment_requests.push(payment_request);
         self.last_payment_id = payment_id;
 
@@ -190,13 +727,26 @@ impl PaymentSystem {
 
         let payment = &mut self.payment_requests[payment_index];
 
-        if payment.status != PaymentStatus::Pending && payment.status != PaymentStatus::Processing {
+        if payment.status == PaymentStatus::Pending && payment.multisig_required {
+            return Err(VaultError::PaymentApprovalRequired.into());
+        }
+        if !payment.is_processable() {
             return Err(VaultError::InvalidPaymentStatus.into());
         }
-
         let clock = Clock::get()?;
+        payment.require_retry_due(clock.unix_timestamp)?;
+
         payment.status = PaymentStatus::Processing;
         payment.processed_at = Some(clock.unix_timestamp);
+        let event = PaymentProcessing {
+            sequence: 0,
+            payment_id,
+            user: payment.user,
+            method: payment.method.clone(),
+            amount: payment.amount,
+            destination_hash: payment.destination_hash(),
+            multisig_required: payment.multisig_required,
+        };
 
         // Execute payment based on method
         match payment.method {
@@ -206,55 +756,178 @@ impl PaymentSystem {
             PaymentMethod::USDC => {
                 self.process_usdc_payment(payment)?;
             },
+            PaymentMethod::SplToken { .. } => {
+                Self::process_spl_token_payment(payment)?;
+            },
         }
 
+        emit!(PaymentProcessing { sequence: self.next_event_sequence()?, ..event });
+
         Ok(())
     }
 
-    /// Complete a payment request
-    pub fn complete_payment(&mut self, payment_id: u64, success: bool, failure_reason: Option<String>) -> Result<()> {
+    /// Complete a payment request. Returns the outcome so the caller can
+    /// restore the claimed amount when retries are exhausted.
+    pub fn complete_payment(
+        &mut self,
+        payment_id: u64,
+        claim_id: u64,
+        success: bool,
+        failure_reason: Option<String>,
+    ) -> Result<CompletionOutcome> {
         let payment_index = self.payment_requests
             .iter()
             .position(|p| p.id == payment_id)
             .ok_or(VaultError::PaymentNotFound)?;
 
-        let payment = &mut self.payment_requests[payment_index];
         let clock = Clock::get()?;
+        let payment = &mut self.payment_requests[payment_index];
+        let outcome = payment.record_completion(
+            claim_id,
+            success,
+            failure_reason,
+            clock.unix_timestamp,
+            Self::MAX_RETRY_ATTEMPTS,
+        )?;
+        let method = payment.method.clone();
+        let amount = payment.amount;
+        let retry_count = payment.retry_count;
+        let user = payment.user;
+        let destination_hash = payment.destination_hash();
+        let multisig_required = payment.multisig_required;
+        let failure_reason = payment.failure_reason.clone();
+        let next_retry_at = payment.next_retry_at;
+        let quoted_fee_bps = payment.quoted_fee_bps;
+        let parent_id = payment.parent_id;
+        // A mint removed since the request was made no longer has a fee rate
+        let fee_bps = quoted_fee_bps.unwrap_or(self.protocol_fee_bps(&method).unwrap_or(0));
+        let fee = payment_fee(amount, fee_bps)?;
+        self.record_rollup(&outcome, &method, amount, fee, clock.unix_timestamp);
+
+        match outcome {
+            CompletionOutcome::Completed => {
+                // Update volume statistics
+                match &method {
+                    PaymentMethod::Lightning => {
+                        self.total_lightning_volume = self.total_lightning_volume
+                            .checked_add(amount).ok_or(VaultError::ArithmeticOverflow)?;
+                        self.lightning_float = self.lightning_float.saturating_sub(amount);
+                    },
+                    PaymentMethod::USDC => {
+                        self.total_usdc_volume = self.total_usdc_volume
+                            .checked_add(amount).ok_or(VaultError::ArithmeticOverflow)?;
+                    },
+                    PaymentMethod::SplToken { .. } => {
+                        self.total_spl_token_volume = self.total_spl_token_volume
+                            .checked_add(amount).ok_or(VaultError::ArithmeticOverflow)?;
+                    },
+                }
 
-        if success {
-            payment.status = PaymentStatus::Completed;
-            payment.completed_at = Some(clock.unix_timestamp);
-            
-            // Update volume statistics
-            match payment.method {
-                PaymentMethod::Lightning => {
-                    self.total_lightning_volume = self.total_lightning_volume
-                        .checked_add(payment.amount).ok_or(VaultError::ArithmeticOverflow)?;
-                },
-                PaymentMethod::USDC => {
-                    self.total_usdc_volume = self.total_usdc_volume
-                        .checked_add(payment.amount).ok_or(VaultError::ArithmeticOverflow)?;
-                },
-            }
-            
-            self.total_payments_processed = self.total_payments_processed
-                .checked_add(1).ok_or(VaultError::ArithmeticOverflow)?;
+                self.total_payments_processed = self.total_payments_processed
+                    .checked_add(1).ok_or(VaultError::ArithmeticOverflow)?;
+                self.settle_split_part(parent_id, true)?;
+
+                emit!(PaymentCompleted {
+                    sequence: self.next_event_sequence()?,
+                    payment_id,
+                    user,
+                    method,
+                    amount,
+                    destination_hash,
+                    multisig_required,
+                });
+                msg!("Payment {} completed successfully", payment_id);
+            },
+            CompletionOutcome::Retrying => {
+                emit!(PaymentFailed {
+                    sequence: self.next_event_sequence()?,
+                    payment_id,
+                    user,
+                    method,
+                    amount,
+                    destination_hash,
+                    multisig_required,
+                    failure_reason,
+                    will_retry: true,
+                });
+                let next_retry_at = next_retry_at.ok_or(VaultError::InvalidPaymentStatus)?;
+                emit!(PaymentRetryScheduled {
+                    sequence: self.next_event_sequence()?,
+                    payment_id,
+                    user,
+                    retry_count,
+                    next_retry_at,
+                });
+                msg!("Payment {} failed, retry {} of {} after {}", payment_id, retry_count, Self::MAX_RETRY_ATTEMPTS, next_retry_at);
+            },
+            CompletionOutcome::DeadLettered => {
+                self.failed_payments_count = self.failed_payments_count
+                    .checked_add(1).ok_or(VaultError::ArithmeticOverflow)?;
+                emit!(PaymentFailed {
+                    sequence: self.next_event_sequence()?,
+                    payment_id,
+                    user,
+                    method: method.clone(),
+                    amount,
+                    destination_hash,
+                    multisig_required,
+                    failure_reason: failure_reason.clone(),
+                    will_retry: false,
+                });
+                emit!(PaymentDeadLettered {
+                    sequence: self.next_event_sequence()?,
+                    payment_id,
+                    user,
+                    method,
+                    amount,
+                    retry_count,
+                    failure_reason,
+                });
+                msg!("Payment {} failed after {} attempts, dead-lettered", payment_id, retry_count);
+            },
+            CompletionOutcome::NoOp => {
+                msg!("Payment {} already finalized, completion ignored", payment_id);
+            },
+        }
 
-            msg!("Payment {} completed successfully", payment_id);
-        } else {
-            payment.retry_count = payment.retry_count.checked_add(1).unwrap();
-            payment.failure_reason = failure_reason;
+        Ok(outcome)
+    }
 
-            if payment.retry_count >= Self::MAX_RETRY_ATTEMPTS {
-                payment.status = PaymentStatus::Failed;
-                self.failed_payments_count = self.failed_payments_count
-                    .checked_add(1).unwrap();
-                msg!("Payment {} failed after {} attempts", payment_id, payment.retry_count);
-            } else {
-                payment.status = PaymentStatus::Pending;
-                msg!("Payment {} failed, retry {} of {}", payment_id, payment.retry_count, Self::MAX_RETRY_ATTEMPTS);
-            }
+    /// A payment awaiting multisig approval
+    pub fn approvable_payment(&self, payment_id: u64, approval_round: u8) -> Result<&PaymentRequest> {
+        let payment = self.get_payment_request(payment_id).ok_or(VaultError::PaymentNotFound)?;
+        if !payment.multisig_required || payment.status != PaymentStatus::Pending {
+            return Err(VaultError::InvalidPaymentStatus.into());
+        }
+        if payment.approval_round != approval_round {
+            return Err(VaultError::PaymentApprovalStale.into());
         }
+        Ok(payment)
+    }
+
+    /// Release a payment awaiting multisig approval for processing. Only an
+    /// executed multisig `PaymentApproval` transaction calls this, with the
+    /// wallet as `approver`.
+    pub fn approve_payment(&mut self, payment_id: u64, approval_round: u8, approver: Pubkey) -> Result<()> {
+        self.approvable_payment(payment_id, approval_round)?;
+        let payment = self.payment_requests
+            .iter_mut()
+            .find(|p| p.id == payment_id)
+            .ok_or(VaultError::PaymentNotFound)?;
+
+        payment.status = PaymentStatus::Processing;
+        let event = PaymentApproved {
+            sequence: 0,
+            payment_id,
+            user: payment.user,
+            method: payment.method.clone(),
+            amount: payment.amount,
+            destination_hash: payment.destination_hash(),
+            multisig_required: true,
+            approver,
+        };
+        emit!(PaymentApproved { sequence: self.next_event_sequence()?, ..event });
+        msg!("Payment {} approved by {}", payment_id, approver);
 
         Ok(())
     }
@@ -276,65 +949,407 @@ impl PaymentSystem {
             return Err(VaultError::PaymentInProgress.into());
         }
 
+        if payment.status != PaymentStatus::Pending && payment.status != PaymentStatus::PendingLimitReset {
+            return Err(VaultError::InvalidPaymentStatus.into());
+        }
+
         payment.status = PaymentStatus::Cancelled;
         msg!("Payment {} cancelled by user {}", payment_id, user);
 
         Ok(())
     }
 
-    /// Get payment request by ID
-    pub fn get_payment_request(&self, payment_id: u64) -> Option<&PaymentRequest> {
-        self.payment_requests.iter().find(|p| p.id == payment_id)
-    }
+    /// Replace the destination of a request still pending approval or a
+    /// retry, in place of cancelling and re-claiming. The new destination is
+    /// validated like a new request's; `high_risk` is its screening result.
+    /// While the payment needs multisig approval, or if its risk tier
+    /// changes, the approval round advances, so approvals collected for the
+    /// old destination no longer release the payment.
+    pub fn replace_payment_destination(
+        &mut self,
+        payment_id: u64,
+        user: Pubkey,
+        new_destination: String,
+        high_risk: bool,
+        now: i64,
+    ) -> Result<()> {
+        if self.emergency_pause {
+            return Err(VaultError::PaymentSystemPaused.into());
+        }
 
-    /// Get user's payment requests
-    pub fn get_user_payments(&self, user: Pubkey) -> Vec<&PaymentRequest> {
-        self.payment_requests.iter()
-            .filter(|p| p.user == user)
-            .collect()
-    }
+        let (method, amount) = match self.get_payment_request(payment_id) {
+            Some(payment) if payment.user == user => (payment.method.clone(), payment.amount),
+            _ => return Err(VaultError::PaymentNotFound.into()),
+        };
+        self.validate_destination(&method, &new_destination)?;
+        let payment_hash = match method {
+            PaymentMethod::Lightning => Some(Self::check_lightning_invoice(&new_destination, amount, now)?),
+            _ => None,
+        };
+        let amount_gated = self.requires_multisig_approval(&method, amount);
+
+        let payment = self.payment_requests
+            .iter_mut()
+            .find(|p| p.id == payment_id)
+            .ok_or(VaultError::PaymentNotFound)?;
+        if payment.status != PaymentStatus::Pending {
+            return Err(VaultError::InvalidPaymentStatus.into());
+        }
+        if payment.destination_history.len() >= PaymentRequest::MAX_DESTINATION_REPLACEMENTS {
+            return Err(VaultError::DestinationReplacementLimitReached.into());
+        }
+
+        let old_destination_hash = payment.destination_hash();
+        let tier_changed = payment.high_risk != high_risk;
+        let approvals_reset = tier_changed || payment.multisig_required;
+        if tier_changed {
+            payment.high_risk = high_risk;
+            payment.multisig_required = high_risk || amount_gated || payment.risk_gated;
+        }
+        if approvals_reset {
+            payment.approval_round = payment.approval_round.checked_add(1)
+                .ok_or(VaultError::ArithmeticOverflow)?;
+        }
+        payment.destination_history.push(old_destination_hash);
+        payment.destination = new_destination;
+        payment.payment_hash = payment_hash;
+
+        let event = PaymentDestinationReplaced {
+            sequence: 0,
+            payment_id,
+            user,
+            old_destination_hash,
+            new_destination_hash: payment.destination_hash(),
+            multisig_required: payment.multisig_required,
+            approvals_reset,
+        };
+        emit!(PaymentDestinationReplaced { sequence: self.next_event_sequence()?, ..event });
+        msg!("Payment {} destination replaced by user {}", payment_id, user);
 
-    /// Emergency pause/unpause payment system
-    pub fn set_emergency_pause(&mut self, paused: bool) -> Result<()> {
-        self.emergency_pause = paused;
-        msg!("Payment system emergency pause: {}", paused);
         Ok(())
     }
 
-    /// Update Lightning configuration
-    pub fn update_lightning_config(&mut self, config: LightningConfig) -> Result<()> {
-        self.lightning_config = config;
-        msg!("Lightning configuration updated");
+    /// Attach the hash of a payment's travel rule data. A correction can
+    /// replace it until the payment is first processed.
+    pub fn attach_travel_rule_data(
+        &mut self,
+        payment_id: u64,
+        data_hash: [u8; 32],
+        provider_id: u32,
+        now: i64,
+    ) -> Result<()> {
+        if data_hash == [0; 32] {
+            return Err(VaultError::TravelRuleDataRequired.into());
+        }
+        let payment = self.payment_requests
+            .iter_mut()
+            .find(|p| p.id == payment_id)
+            .ok_or(VaultError::PaymentNotFound)?;
+        let open = matches!(
+            payment.status,
+            PaymentStatus::Pending | PaymentStatus::PendingLimitReset | PaymentStatus::Processing
+        );
+        if !open || payment.processed_at.is_some() {
+            return Err(VaultError::InvalidPaymentStatus.into());
+        }
+
+        payment.travel_rule = Some(TravelRuleAttachment { data_hash, provider_id, attached_at: now });
+        let user = payment.user;
+        emit!(TravelRuleDataAttached {
+            sequence: self.next_event_sequence()?,
+            payment_id,
+            user,
+            data_hash,
+            provider_id,
+        });
+        msg!("Travel rule data from provider {} attached to payment {}", provider_id, payment_id);
+
         Ok(())
     }
 
-    /// Update USDC configuration
-    pub fn update_usdc_config(&mut self, config: UsdcConfig) -> Result<()> {
+    /// Charge a new request to the user's and the protocol's payout windows,
+    /// or hold it as `PendingLimitReset` if either limit would be exceeded.
+    /// Returns whether the request was held.
+    pub fn charge_velocity(
+        &mut self,
+        payment_id: u64,
+        user_window: &mut PayoutWindow,
+        enhanced_kyc: bool,
+        now: i64,
+    ) -> Result<bool> {
+        let index = self.payment_requests.iter()
+            .position(|p| p.id == payment_id)
+            .ok_or(VaultError::PaymentNotFound)?;
+        if self.try_charge_velocity(self.payment_requests[index].amount, user_window, enhanced_kyc, now) {
+            return Ok(false);
+        }
+
+        self.payment_requests[index].status = PaymentStatus::PendingLimitReset;
+        msg!("Payment {} held until the daily payout limit resets", payment_id);
+        Ok(true)
+    }
+
+    /// Charge a stream's reward movement to the user's and the protocol's
+    /// payout windows. Streams have no request to hold, so this rejects.
+    pub fn charge_stream_velocity(
+        &mut self,
+        amount: u64,
+        user_window: &mut PayoutWindow,
+        enhanced_kyc: bool,
+        now: i64,
+    ) -> Result<()> {
+        if !self.try_charge_velocity(amount, user_window, enhanced_kyc, now) {
+            return Err(VaultError::VelocityLimitExceeded.into());
+        }
+        Ok(())
+    }
+
+    /// Release a held request once both payout windows have room for it,
+    /// returning it to approval or processing
+    pub fn release_held_payment(
+        &mut self,
+        payment_id: u64,
+        user_window: &mut PayoutWindow,
+        enhanced_kyc: bool,
+        now: i64,
+    ) -> Result<()> {
+        let index = self.payment_requests.iter()
+            .position(|p| p.id == payment_id)
+            .ok_or(VaultError::PaymentNotFound)?;
+        if self.payment_requests[index].status != PaymentStatus::PendingLimitReset {
+            return Err(VaultError::InvalidPaymentStatus.into());
+        }
+        if !self.try_charge_velocity(self.payment_requests[index].amount, user_window, enhanced_kyc, now) {
+            return Err(VaultError::VelocityLimitExceeded.into());
+        }
+
+        let payment = &mut self.payment_requests[index];
+        payment.status = if payment.multisig_required {
+            PaymentStatus::Pending
+        } else {
+            PaymentStatus::Processing
+        };
+        msg!("Payment {} released after the daily payout limit reset", payment_id);
+        Ok(())
+    }
+
+    /// Replace the destination screening policy
+    pub fn set_screening_policy(&mut self, policy: ScreeningPolicy) -> Result<()> {
+        if policy.ttl_seconds < 0 {
+            return Err(VaultError::InvalidScreeningPolicy.into());
+        }
+        self.screening_policy = policy;
+        Ok(())
+    }
+
+    /// Send a payment whose destination has since screened high risk back
+    /// for multisig approval
+    pub fn route_for_approval(&mut self, payment_id: u64) -> Result<()> {
+        let payment = self.payment_requests
+            .iter_mut()
+            .find(|p| p.id == payment_id)
+            .ok_or(VaultError::PaymentNotFound)?;
+        if payment.multisig_required || !payment.is_processable() {
+            return Err(VaultError::InvalidPaymentStatus.into());
+        }
+        payment.multisig_required = true;
+        payment.high_risk = true;
+        payment.status = PaymentStatus::Pending;
+        msg!("Payment {} routed for multisig approval after a high-risk screening", payment_id);
+        Ok(())
+    }
+
+    /// Replace the daily payout limits. The Enhanced KYC limit can't be
+    /// tighter than the standard one.
+    pub fn set_velocity_limits(&mut self, limits: VelocityLimits) -> Result<()> {
+        let enhanced_tighter = limits.enhanced_user_daily_limit != 0
+            && (limits.user_daily_limit == 0 || limits.enhanced_user_daily_limit < limits.user_daily_limit);
+        if enhanced_tighter {
+            return Err(VaultError::InvalidVelocityLimits.into());
+        }
+        self.velocity_limits = limits;
+        Ok(())
+    }
+
+    /// Refund a failed or cancelled payment request. Returns the amount to
+    /// restore to the user, or None if it was already restored.
+    pub fn refund_payment(&mut self, payment_id: u64) -> Result<Option<u64>> {
+        let payment = self.payment_requests
+            .iter_mut()
+            .find(|p| p.id == payment_id)
+            .ok_or(VaultError::PaymentNotFound)?;
+        let refund = payment.refund()?;
+        let parent_id = payment.parent_id;
+        if let Some(amount) = refund {
+            msg!("Payment {} refunded (amount: {})", payment_id, amount);
+            self.settle_split_part(parent_id, false)?;
+        }
+        Ok(refund)
+    }
+
+    /// Remove a request that exhausted its retries from the in-flight queue
+    /// so it can be moved to the dead-letter queue
+    pub fn take_dead_lettered(&mut self, payment_id: u64) -> Option<PaymentRequest> {
+        let index = self.payment_requests.iter()
+            .position(|p| p.id == payment_id && p.status == PaymentStatus::Failed && !p.refunded)?;
+        Some(self.payment_requests.remove(index))
+    }
+
+    /// Put a request taken from the dead-letter queue back in line
+    pub fn requeue(&mut self, mut request: PaymentRequest) -> Result<()> {
+        if self.payment_requests.len() >= Self::MAX_PAYMENT_REQUESTS {
+            return Err(VaultError::PaymentQueueFull.into());
+        }
+        request.requeue()?;
+        msg!("Payment {} requeued from the dead-letter queue", request.id);
+        self.payment_requests.push(request);
+        Ok(())
+    }
+
+    /// Remove a request that has reached a final state from the in-flight
+    /// queue so it can be moved to its user's history
+    pub fn take_finalized(&mut self, payment_id: u64) -> Option<PaymentRequest> {
+        let index = self.payment_requests.iter()
+            .position(|p| p.id == payment_id && p.status.is_final())?;
+        Some(self.payment_requests.remove(index))
+    }
+
+    /// Remove every finalized request of `user`, oldest first. Used to move
+    /// requests left over from before per-user histories.
+    pub fn take_finalized_for(&mut self, user: Pubkey) -> Vec<PaymentRequest> {
+        let (finalized, in_flight) = std::mem::take(&mut self.payment_requests)
+            .into_iter()
+            .partition(|p| p.user == user && p.status.is_final());
+        self.payment_requests = in_flight;
+        finalized
+    }
+
+    /// Get payment request by ID
+    pub fn get_payment_request(&self, payment_id: u64) -> Option<&PaymentRequest> {
+        self.payment_requests.iter().find(|p| p.id == payment_id)
+    }
+
+    /// Get user's payment requests
+    pub fn get_user_payments(&self, user: Pubkey) -> Vec<&PaymentRequest> {
+        self.payment_requests.iter()
+            .filter(|p| p.user == user)
+            .collect()
+    }
+
+    /// Emergency pause/unpause payment system
+    pub fn set_emergency_pause(&mut self, paused: bool) -> Result<()> {
+        self.emergency_pause = paused;
+        msg!("Payment system emergency pause: {}", paused);
+        Ok(())
+    }
+
+    /// Update Lightning configuration
+    pub fn update_lightning_config(&mut self, config: LightningConfig) -> Result<()> {
+        self.lightning_config = config;
+        msg!("Lightning configuration updated");
+        Ok(())
+    }
+
+    /// Update USDC configuration
+    pub fn update_usdc_config(&mut self, config: UsdcConfig) -> Result<()> {
         self.usdc_config = config;
         msg!("USDC configuration updated");
         Ok(())
     }
 
-    // Private helper methods
+    /// Decode a BOLT11 invoice and check it is unexpired and asks for exactly
+    /// `amount` sats. Returns the invoice's payment hash.
+    pub fn check_lightning_invoice(invoice: &str, amount: u64, now: i64) -> Result<[u8; 32]> {
+        let invoice = Bolt11Invoice::decode(invoice)?;
+        if now >= invoice.expires_at() {
+            return Err(VaultError::InvoiceExpired.into());
+        }
+        if invoice.amount_msat != Some(amount.saturating_mul(1_000)) {
+            return Err(VaultError::InvoiceAmountMismatch.into());
+        }
+        Ok(invoice.payment_hash)
+    }
 
-    fn validate_payment_amount(&self, method: &PaymentMethod, amount: u64) -> Result<()> {
+    /// Configuration for an accepted SPL token mint
+    pub fn spl_token_config(&self, mint: &Pubkey) -> Result<&SplTokenConfig> {
+        self.spl_token_configs.iter()
+            .find(|c| c.mint == *mint)
+            .ok_or(VaultError::UnsupportedPaymentMint.into())
+    }
+
+    /// Add or replace the configuration for an SPL token mint
+    pub fn set_spl_token_config(&mut self, config: SplTokenConfig) -> Result<()> {
+        if config.min_payment_amount > config.max_payment_amount || config.fee_basis_points > 10_000 {
+            return Err(VaultError::InvalidPaymentMintConfig.into());
+        }
+
+        if let Some(existing) = self.spl_token_configs.iter_mut().find(|c| c.mint == config.mint) {
+            *existing = config;
+        } else {
+            if self.spl_token_configs.len() >= Self::MAX_SPL_TOKEN_MINTS {
+                return Err(VaultError::TooManyPaymentMints.into());
+            }
+            self.spl_token_configs.push(config);
+        }
+        Ok(())
+    }
+
+    /// Stop accepting an SPL token mint. Requests already queued for it can
+    /// no longer be processed.
+    pub fn remove_spl_token_config(&mut self, mint: &Pubkey) -> Result<()> {
+        let index = self.spl_token_configs.iter()
+            .position(|c| c.mint == *mint)
+            .ok_or(VaultError::UnsupportedPaymentMint)?;
+        self.spl_token_configs.remove(index);
+        Ok(())
+    }
+
+    /// Minimum and maximum payment amount for a method, in its base units
+    pub fn payment_limits(&self, method: &PaymentMethod) -> Result<(u64, u64)> {
         match method {
             PaymentMethod::Lightning => {
-                if amount < self.lightning_config.min_payment_amount {
-                    return Err(VaultError::PaymentAmountTooSmall.into());
-                }
-                if amount > self.lightning_config.max_payment_amount {
-                    return Err(VaultError::PaymentAmountTooLarge.into());
-                }
+                Ok((self.lightning_config.min_payment_amount, self.lightning_config.max_payment_amount))
             },
             PaymentMethod::USDC => {
-                if amount < self.usdc_config.min_payment_amount {
-                    return Err(VaultError::PaymentAmountTooSmall.into());
-                }
-                if amount > self.usdc_config.max_payment_amount {
-                    return Err(VaultError::PaymentAmountTooLarge.into());
-                }
+                Ok((self.usdc_config.min_payment_amount, self.usdc_config.max_payment_amount))
+            },
+            PaymentMethod::SplToken { mint } => {
+                let config = self.spl_token_config(mint)?;
+                Ok((config.min_payment_amount, config.max_payment_amount))
+            },
+        }
+    }
+
+    /// Protocol fee rate for `method` in basis points. Lightning payouts
+    /// carry no protocol fee, only routing fees.
+    pub fn protocol_fee_bps(&self, method: &PaymentMethod) -> Result<u16> {
+        match method {
+            PaymentMethod::Lightning => Ok(0),
+            PaymentMethod::USDC => Ok(self.usdc_config.fee_basis_points),
+            PaymentMethod::SplToken { mint } => Ok(self.spl_token_config(mint)?.fee_basis_points),
+        }
+    }
+
+    /// Expected routing cost of paying `amount` at the configured Lightning
+    /// fee rate, rounded up. Token transfers only cost the payer SOL.
+    pub fn estimated_network_fee(&self, method: &PaymentMethod, amount: u64) -> u64 {
+        match method {
+            PaymentMethod::Lightning => {
+                (amount as u128 * self.lightning_config.fee_rate as u128).div_ceil(1_000_000) as u64
             },
+            PaymentMethod::USDC | PaymentMethod::SplToken { .. } => 0,
+        }
+    }
+
+    // Private helper methods
+
+    fn validate_payment_amount(&self, method: &PaymentMethod, amount: u64) -> Result<()> {
+        let (min_amount, max_amount) = self.payment_limits(method)?;
+        if amount < min_amount {
+            return Err(VaultError::PaymentAmountTooSmall.into());
+        }
+        if amount > max_amount {
+            return Err(VaultError::PaymentAmountTooLarge.into());
         }
         Ok(())
     }
@@ -350,12 +1365,12 @@ impl PaymentSystem {
                     return Err(VaultError::InvalidLightningInvoice.into());
                 }
             },
-            PaymentMethod::USDC => {
-                // Validate Solana address format
-                if destination.len() != 44 {
-                    return Err(VaultError::InvalidSolanaAddress.into());
-                }
-                // Additional base58 validation could be added here
+            PaymentMethod::USDC | PaymentMethod::SplToken { .. } => {
+                // Wallet that owns the recipient token account. Base58
+                // pubkeys are 32 to 44 characters, so parse rather than
+                // check the length.
+                destination.parse::<Pubkey>()
+                    .map_err(|_| VaultError::InvalidSolanaAddress)?;
             },
         }
         Ok(())
@@ -366,25 +1381,272 @@ impl PaymentSystem {
         match method {
             PaymentMethod::Lightning => amount > 1000000, // 0.01 BTC in sats
             PaymentMethod::USDC => amount > 1000_000000,  // $1000 in USDC (6 decimals)
+            PaymentMethod::SplToken { mint } => self.spl_token_config(mint)
+                .map_or(true, |c| amount > c.approval_threshold),
+        }
+    }
+
+    /// Sequence number for the next payment event. Consumers that see a gap
+    /// know they missed an event.
+    fn next_event_sequence(&mut self) -> Result<u64> {
+        self.event_sequence = self.event_sequence.checked_add(1)
+            .ok_or(VaultError::ArithmeticOverflow)?;
+        Ok(self.event_sequence)
+    }
+
+    fn try_charge_velocity(&mut self, amount: u64, user_window: &mut PayoutWindow, enhanced_kyc: bool, now: i64) -> bool {
+        let user_limit = if enhanced_kyc {
+            self.velocity_limits.enhanced_user_daily_limit
+        } else {
+            self.velocity_limits.user_daily_limit
+        };
+        if !user_window.has_room(amount, user_limit, now)
+            || !self.payout_window.has_room(amount, self.velocity_limits.protocol_daily_limit, now)
+        {
+            return false;
         }
+        user_window.charge(amount, now);
+        self.payout_window.charge(amount, now);
+        true
     }
 
+    /// Expire overdue pending requests and drop finalized ones past the
+    /// retention window. Expired requests stay until they are refunded.
     fn cleanup_old_requests(&mut self) -> Result<()> {
-        let clock = Clock::get()?;
-        let cutoff_time = clock.unix_timestamp - Self::PAYMENT_TIMEOUT_SECONDS;
+        let now = Clock::get()?.unix_timestamp;
+        self.expire_overdue(now)?;
 
+        let retention_cutoff = now - Self::PAYMENT_TIMEOUT_SECONDS - Self::RETENTION_SECONDS;
         self.payment_requests.retain(|payment| {
-            if payment.status == PaymentStatus::Completed || payment.status == PaymentStatus::Failed {
-                // Keep completed/failed payments for a while for history
-                payment.created_at > cutoff_time - (24 * 3600) // 24 hours
-            } else if payment.status == PaymentStatus::Pending {
-                // Remove old pending payments
-                payment.created_at > cutoff_time
-            } else {
-                true // Keep processing payments
+            !payment.status.is_final() || payment.created_at > retention_cutoff
+        });
+
+        Ok(())
+    }
+
+    /// Move every pending request past the payment timeout to Expired.
+    /// Returns how many expired.
+    pub fn expire_overdue(&mut self, now: i64) -> Result<usize> {
+        let mut expired = Vec::new();
+        for payment in self.payment_requests.iter_mut() {
+            if payment.expire(now) {
+                expired.push((payment.id, payment.user, payment.amount));
+            }
+        }
+        for &(payment_id, user, amount) in &expired {
+            emit!(PaymentExpired {
+                sequence: self.next_event_sequence()?,
+                payment_id,
+                user,
+                amount,
+                expired_at: now,
+            });
+            msg!("Payment {} expired after {}s pending", payment_id, Self::PAYMENT_TIMEOUT_SECONDS);
+        }
+        Ok(expired.len())
+    }
+
+    /// Refund and remove every expired request of `user`. Returns them, now
+    /// Refunded, with the total to restore to the user's pending rewards.
+    pub fn take_expired_for(&mut self, user: Pubkey) -> Result<(Vec<PaymentRequest>, u64)> {
+        let (mut expired, rest): (Vec<_>, Vec<_>) = std::mem::take(&mut self.payment_requests)
+            .into_iter()
+            .partition(|p| p.user == user && p.status == PaymentStatus::Expired);
+        self.payment_requests = rest;
+
+        let mut total = 0u64;
+        for request in expired.iter_mut() {
+            if let Some(amount) = request.refund()? {
+                total = total.checked_add(amount).ok_or(VaultError::ArithmeticOverflow)?;
+                self.settle_split_part(request.parent_id, false)?;
+            }
+        }
+        Ok((expired, total))
+    }
+
+    /// Split `amount` across parts by basis points. The parts must add up to
+    /// exactly 10000; rounding dust goes to the last part.
+    pub fn split_amounts(amount: u64, basis_points: &[u16]) -> Result<Vec<u64>> {
+        if basis_points.len() < 2 || basis_points.len() > Self::MAX_SPLIT_PARTS {
+            return Err(VaultError::InvalidSplitPayment.into());
+        }
+        let total: u32 = basis_points.iter().map(|&bps| bps as u32).sum();
+        if total != 10_000 || basis_points.contains(&0) {
+            return Err(VaultError::InvalidSplitPayment.into());
+        }
+
+        let mut amounts = basis_points.iter()
+            .map(|&bps| (amount as u128 * bps as u128 / 10_000) as u64)
+            .collect::<Vec<_>>();
+        let dust = amount - amounts.iter().sum::<u64>();
+        if let Some(last) = amounts.last_mut() {
+            *last += dust;
+        }
+        Ok(amounts)
+    }
+
+    /// Create one payment request per part of a split claim, linked by a
+    /// shared parent id. Every part must meet its method's limits before any
+    /// is queued. `high_risk` flags the parts, in order, whose destinations
+    /// need multisig approval; `risk` is the operation risk of the whole
+    /// claim and applies to every part. Returns the parent id.
+    pub fn create_split_payment_request(
+        &mut self,
+        user: Pubkey,
+        amount: u64,
+        parts: Vec<SplitPart>,
+        claim_id: u64,
+        high_risk: &[bool],
+        risk: RiskAssessment,
+    ) -> Result<u64> {
+        let basis_points = parts.iter().map(|part| part.basis_points).collect::<Vec<_>>();
+        let amounts = Self::split_amounts(amount, &basis_points)?;
+        for (part, &part_amount) in parts.iter().zip(&amounts) {
+            self.validate_payment_amount(&part.method, part_amount)?;
+        }
+
+        self.cleanup_old_requests()?;
+        if self.payment_requests.len() + parts.len() > Self::MAX_PAYMENT_REQUESTS
+            || self.split_payments.len() >= Self::MAX_SPLIT_PAYMENTS
+        {
+            return Err(VaultError::PaymentQueueFull.into());
+        }
+
+        let parent_id = self.last_payment_id.checked_add(1)
+            .ok_or(VaultError::ArithmeticOverflow)?;
+        self.last_payment_id = parent_id;
+
+        let mut part_ids = Vec::with_capacity(parts.len());
+        for (index, (part, part_amount)) in parts.into_iter().zip(amounts).enumerate() {
+            let terms = RequestTerms {
+                high_risk: high_risk.get(index).copied().unwrap_or(false),
+                risk,
+                ..RequestTerms::default()
+            };
+            let payment_id = self.create_payment_request(user, part.method, part_amount, part.destination, claim_id, terms)?;
+            if let Some(payment) = self.payment_requests.last_mut() {
+                payment.parent_id = Some(parent_id);
             }
+            part_ids.push(payment_id);
+        }
+
+        self.split_payments.push(SplitPayment {
+            parent_id,
+            user,
+            parts: part_ids.len() as u8,
+            completed: 0,
+            refunded: 0,
+        });
+        emit!(SplitPaymentRequested {
+            sequence: self.next_event_sequence()?,
+            parent_id,
+            user,
+            amount,
+            part_ids,
+        });
+
+        Ok(parent_id)
+    }
+
+    /// Get a split payment with parts still outstanding
+    pub fn get_split_payment(&self, parent_id: u64) -> Option<&SplitPayment> {
+        self.split_payments.iter().find(|split| split.parent_id == parent_id)
+    }
+
+    /// Count a completed or refunded part towards its split payment. Once
+    /// every part has settled the split is removed and its status emitted.
+    pub fn settle_split_part(&mut self, parent_id: Option<u64>, completed: bool) -> Result<()> {
+        let Some(parent_id) = parent_id else {
+            return Ok(());
+        };
+        let Some(index) = self.split_payments.iter().position(|split| split.parent_id == parent_id) else {
+            return Ok(());
+        };
+
+        let split = &mut self.split_payments[index];
+        if completed {
+            split.completed = split.completed.saturating_add(1);
+        } else {
+            split.refunded = split.refunded.saturating_add(1);
+        }
+        let status = split.status();
+        if status == SplitStatus::InProgress {
+            return Ok(());
+        }
+
+        let split = self.split_payments.remove(index);
+        emit!(SplitPaymentSettled {
+            sequence: self.next_event_sequence()?,
+            parent_id,
+            user: split.user,
+            status: status.clone(),
+            completed: split.completed,
+            refunded: split.refunded,
         });
+        msg!("Split payment {} settled: {:?}", parent_id, status);
+        Ok(())
+    }
+
+    /// Amount of in-flight requests still to be paid from `method`'s float
+    pub fn committed_float(&self, method: &PaymentMethod) -> Result<u64> {
+        self.payment_requests.iter()
+            .filter(|p| p.method == *method && p.holds_float())
+            .try_fold(0u64, |total, p| total.checked_add(p.amount))
+            .ok_or(VaultError::ArithmeticOverflow.into())
+    }
 
+    /// Treasury ATA and mint a token method pays out of; None for Lightning,
+    /// whose float is the channel budget
+    pub fn float_account(&self, method: &PaymentMethod) -> Result<Option<(Pubkey, Pubkey)>> {
+        match method {
+            PaymentMethod::Lightning => Ok(None),
+            PaymentMethod::USDC => Ok(Some((self.usdc_config.treasury_ata, self.usdc_config.mint_address))),
+            PaymentMethod::SplToken { mint } => Ok(Some((self.spl_token_config(mint)?.treasury_ata, *mint))),
+        }
+    }
+
+    /// Low-balance watermark configured for `method`
+    pub fn float_watermark(&self, method: &PaymentMethod) -> Result<u64> {
+        match method {
+            PaymentMethod::Lightning => Ok(self.lightning_config.float_watermark),
+            PaymentMethod::USDC => Ok(self.usdc_config.float_watermark),
+            PaymentMethod::SplToken { mint } => Ok(self.spl_token_config(mint)?.float_watermark),
+        }
+    }
+
+    /// Check the requests queued against `method` fit its float: the
+    /// Lightning channel budget, or the live balance of the method's
+    /// treasury ATA. Emits a High alert once what is left drops below the
+    /// method's watermark. Returns the float left uncommitted.
+    pub fn require_float(&mut self, method: &PaymentMethod, token_balance: Option<u64>) -> Result<u64> {
+        let balance = match method {
+            PaymentMethod::Lightning => self.lightning_float,
+            PaymentMethod::USDC | PaymentMethod::SplToken { .. } => {
+                token_balance.ok_or(VaultError::MissingRequiredAccount)?
+            },
+        };
+        let available = balance.checked_sub(self.committed_float(method)?)
+            .ok_or(VaultError::InsufficientPaymentFloat)?;
+
+        let watermark = self.float_watermark(method)?;
+        if available < watermark {
+            emit!(PaymentFloatLow {
+                sequence: self.next_event_sequence()?,
+                method: method.clone(),
+                available,
+                watermark,
+                severity: SecurityLevel::High,
+            });
+            msg!("Payment float for {:?} low: {} left, watermark {}", method, available, watermark);
+        }
+        Ok(available)
+    }
+
+    /// Add sats moved from the main treasury into the Lightning channel
+    pub fn top_up_lightning_float(&mut self, amount: u64) -> Result<()> {
+        self.lightning_float = self.lightning_float.checked_add(amount)
+            .ok_or(VaultError::ArithmeticOverflow)?;
+        msg!("Lightning float topped up by {} to {} sats", amount, self.lightning_float);
         Ok(())
     }
 
@@ -414,29 +1676,79 @@ impl PaymentSystem {
     }
 
     fn process_usdc_payment(&self, payment: &PaymentRequest) -> Result<()> {
-        // In production, this would execute USDC transfer
+        // The token transfer itself happens in the process_payment instruction
         msg!("Processing USDC payment: {} USDC to {}", 
              payment.amount, payment.destination);
         
         // Validate recipient address
-        if payment.destination.len() != 44 {
-            return Err(VaultError::InvalidSolanaAddress.into());
-        }
-
-        // In production:
-        // 1. Create USDC transfer instruction
-        // 2. Execute transfer from treasury ATA
-        // 3. Verify transaction success
+        payment.destination.parse::<Pubkey>()
+            .map_err(|_| VaultError::InvalidSolanaAddress)?;
         
         Ok(())
     }
 
+    fn process_spl_token_payment(payment: &PaymentRequest) -> Result<()> {
+        // The token transfer itself happens in the process_payment instruction
+        msg!("Processing SPL token payment: {} to {}",
+             payment.amount, payment.destination);
+
+        payment.destination.parse::<Pubkey>()
+            .map_err(|_| VaultError::InvalidSolanaAddress)?;
+
+        Ok(())
+    }
+
+    /// The rollup bucket for `now`'s UTC day, reset if it still holds the
+    /// day `ROLLUP_DAYS` earlier
+    fn rollup_bucket(&mut self, method: &PaymentMethod, now: i64) -> &mut MethodRollup {
+        let day = now.div_euclid(86_400);
+        if self.daily_rollups.len() < Self::ROLLUP_DAYS {
+            self.daily_rollups.resize_with(Self::ROLLUP_DAYS, DailyRollup::default);
+        }
+        let bucket = &mut self.daily_rollups[day.rem_euclid(Self::ROLLUP_DAYS as i64) as usize];
+        if bucket.day != day {
+            *bucket = DailyRollup { day, ..DailyRollup::default() };
+        }
+        bucket.method_mut(method)
+    }
+
+    /// Add a completion attempt to today's rollup
+    fn record_rollup(&mut self, outcome: &CompletionOutcome, method: &PaymentMethod, amount: u64, fee: u64, now: i64) {
+        let bucket = self.rollup_bucket(method, now);
+        match outcome {
+            CompletionOutcome::Completed => {
+                bucket.count = bucket.count.saturating_add(1);
+                bucket.volume = bucket.volume.saturating_add(amount);
+                bucket.fees = bucket.fees.saturating_add(fee);
+            },
+            CompletionOutcome::Retrying | CompletionOutcome::DeadLettered => {
+                bucket.failures = bucket.failures.saturating_add(1);
+            },
+            CompletionOutcome::NoOp => {},
+        }
+    }
+
+    /// Up to `ROLLUP_PAGE_LEN` daily rollups from UTC day `from_day` on,
+    /// oldest first, within the last `ROLLUP_DAYS` days. Days are counted
+    /// from the Unix epoch.
+    pub fn rollups_page(&self, from_day: i64, now: i64) -> Vec<DailyRollup> {
+        let oldest = now.div_euclid(86_400) - Self::ROLLUP_DAYS as i64 + 1;
+        let mut buckets: Vec<DailyRollup> = self.daily_rollups.iter()
+            .filter(|b| b.day >= from_day.max(oldest))
+            .cloned()
+            .collect();
+        buckets.sort_by_key(|b| b.day);
+        buckets.truncate(Self::ROLLUP_PAGE_LEN);
+        buckets
+    }
+
     /// Get payment system statistics
     pub fn get_statistics(&self) -> PaymentStatistics {
         PaymentStatistics {
             total_payments: self.total_payments_processed,
             total_lightning_volume: self.total_lightning_volume,
             total_usdc_volume: self.total_usdc_volume,
+            total_spl_token_volume: self.total_spl_token_volume,
             failed_payments: self.failed_payments_count,
             pending_payments: self.payment_requests.iter()
                 .filter(|p| p.status == PaymentStatus::Pending).count() as u64,
@@ -446,40 +1758,340 @@ impl PaymentSystem {
     }
 }
 
-/// Payment system statistics
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
-pub struct PaymentStatistics {
-    pub total_payments: u64,
-    pub total_lightning_volume: u64,
-    pub total_usdc_volume: u64,
-    pub failed_payments: u64,
-    pub pending_payments: u64,
-    pub processing_payments: u64,
+/// Emitted when a payment request is created. Off-chain processors follow
+/// these events instead of polling; each carries the payment system's next
+/// `sequence` number so a consumer can tell when it missed one. Webhook
+/// delivery is left to the processor and configured off-chain.
+#[event]
+pub struct PaymentRequested {
+    pub sequence: u64,
+    pub payment_id: u64,
+    pub user: Pubkey,
+    pub method: PaymentMethod,
+    pub amount: u64,
+    pub destination_hash: [u8; 32],
+    pub multisig_required: bool,
 }
 
-/// User payment preferences
-#[account]
-pub struct UserPaymentPreferences {
+/// Emitted when a payment awaiting multisig approval is approved
+#[event]
+pub struct PaymentApproved {
+    pub sequence: u64,
+    pub payment_id: u64,
     pub user: Pubkey,
-    pub default_method: PaymentMethod,
-    pub lightning_address: Option<String>,
-    pub usdc_address: Option<Pubkey>,
-    pub reinvestment_config: ReinvestmentConfig,
-    pub notification_preferences: NotificationPreferences,
-    pub bump: u8,
+    pub method: PaymentMethod,
+    pub amount: u64,
+    pub destination_hash: [u8; 32],
+    pub multisig_required: bool,
+    pub approver: Pubkey,
 }
 
-impl UserPaymentPreferences {
-    pub const LEN: usize = 8 + // discriminator
-        32 + // user
-        1 + // default_method
-        4 + 200 + // lightning_address (optional)
-        33 + // usdc_address (optional)
-        (1 + 1 + 8 + 4) + // reinvestment_config
-        (1 + 1 + 1 + 1) + // notification_preferences
-        1; // bump
+/// Emitted when travel rule data is attached to a payment
+#[event]
+pub struct TravelRuleDataAttached {
+    pub sequence: u64,
+    pub payment_id: u64,
+    pub user: Pubkey,
+    pub data_hash: [u8; 32],
+    pub provider_id: u32,
+}
 
-    pub fn initialize(
+/// Emitted when a user replaces the destination of a pending payment.
+/// `approvals_reset` means the risk tier changed and the payment needs fresh
+/// multisig approval if it still requires one.
+#[event]
+pub struct PaymentDestinationReplaced {
+    pub sequence: u64,
+    pub payment_id: u64,
+    pub user: Pubkey,
+    pub old_destination_hash: [u8; 32],
+    pub new_destination_hash: [u8; 32],
+    pub multisig_required: bool,
+    pub approvals_reset: bool,
+}
+
+/// Emitted when a payment starts processing; Lightning payments are sent
+/// off-chain on this event
+#[event]
+pub struct PaymentProcessing {
+    pub sequence: u64,
+    pub payment_id: u64,
+    pub user: Pubkey,
+    pub method: PaymentMethod,
+    pub amount: u64,
+    pub destination_hash: [u8; 32],
+    pub multisig_required: bool,
+}
+
+/// Emitted when a payment completes
+#[event]
+pub struct PaymentCompleted {
+    pub sequence: u64,
+    pub payment_id: u64,
+    pub user: Pubkey,
+    pub method: PaymentMethod,
+    pub amount: u64,
+    pub destination_hash: [u8; 32],
+    pub multisig_required: bool,
+}
+
+/// Emitted for every failed attempt, including the last before a refund
+#[event]
+pub struct PaymentFailed {
+    pub sequence: u64,
+    pub payment_id: u64,
+    pub user: Pubkey,
+    pub method: PaymentMethod,
+    pub amount: u64,
+    pub destination_hash: [u8; 32],
+    pub multisig_required: bool,
+    pub failure_reason: Option<String>,
+    pub will_retry: bool,        // False once retries are exhausted
+}
+
+/// Emitted when a request left pending past the payment timeout expires.
+/// Its claimed amount is restored by the expiry crank or `refund_payment`.
+#[event]
+pub struct PaymentExpired {
+    pub sequence: u64,
+    pub payment_id: u64,
+    pub user: Pubkey,
+    pub amount: u64,
+    pub expired_at: i64,
+}
+
+/// Emitted when a method's uncommitted float drops below its watermark, so
+/// ops can top it up from the main treasury
+#[event]
+pub struct PaymentFloatLow {
+    pub sequence: u64,
+    pub method: PaymentMethod,
+    pub available: u64,
+    pub watermark: u64,
+    pub severity: SecurityLevel,
+}
+
+/// Emitted when a claim is split into linked payment requests
+#[event]
+pub struct SplitPaymentRequested {
+    pub sequence: u64,
+    pub parent_id: u64,
+    pub user: Pubkey,
+    pub amount: u64,
+    pub part_ids: Vec<u64>,
+}
+
+/// Emitted once every part of a split payment has completed or been refunded
+#[event]
+pub struct SplitPaymentSettled {
+    pub sequence: u64,
+    pub parent_id: u64,
+    pub user: Pubkey,
+    pub status: SplitStatus,
+    pub completed: u8,
+    pub refunded: u8,
+}
+
+/// Emitted when a failed attempt is scheduled for another try
+#[event]
+pub struct PaymentRetryScheduled {
+    pub sequence: u64,
+    pub payment_id: u64,
+    pub user: Pubkey,
+    pub retry_count: u8,
+    pub next_retry_at: i64,
+}
+
+/// Emitted when a payment exhausts its retries and moves to the dead-letter
+/// queue for ops to requeue or refund
+#[event]
+pub struct PaymentDeadLettered {
+    pub sequence: u64,
+    pub payment_id: u64,
+    pub user: Pubkey,
+    pub method: PaymentMethod,
+    pub amount: u64,
+    pub retry_count: u8,
+    pub failure_reason: Option<String>,
+}
+
+/// One part of a split claim: its method, share in basis points and
+/// destination, resolved like a single payment request's
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct SplitPart {
+    pub method: PaymentMethod,
+    pub basis_points: u16,
+    pub destination: String,
+    pub use_saved_destination: Option<u8>,
+}
+
+/// Rolled-up status of a split payment's parts
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub enum SplitStatus {
+    InProgress,
+    Completed,
+    PartiallyRefunded, // Some parts completed, the rest refunded
+    Refunded,
+}
+
+/// A split payment with parts still outstanding. Each part is its own
+/// payment request; a refunded part restores only its own slice.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct SplitPayment {
+    pub parent_id: u64,
+    pub user: Pubkey,
+    pub parts: u8,
+    pub completed: u8,
+    pub refunded: u8,
+}
+
+impl SplitPayment {
+    pub const LEN: usize = 8 + 32 + 1 + 1 + 1;
+
+    pub fn status(&self) -> SplitStatus {
+        if self.completed.saturating_add(self.refunded) < self.parts {
+            SplitStatus::InProgress
+        } else if self.refunded == 0 {
+            SplitStatus::Completed
+        } else if self.completed == 0 {
+            SplitStatus::Refunded
+        } else {
+            SplitStatus::PartiallyRefunded
+        }
+    }
+}
+
+/// A payment that exhausted its retries
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct DeadLetter {
+    pub request: PaymentRequest,
+    pub dead_lettered_at: i64,
+}
+
+/// Payments that exhausted their retries, held for ops to requeue or
+/// refund. Their claimed amounts stay deducted until refunded.
+#[account]
+pub struct DeadLetterQueue {
+    pub entries: Vec<DeadLetter>,
+    pub bump: u8,
+}
+
+impl DeadLetterQueue {
+    pub const MAX_ENTRIES: usize = 20;
+    pub const LEN: usize = 8 + // discriminator
+        4 + Self::MAX_ENTRIES * (PaymentRequest::LEN + 8) + // entries
+        1; // bump
+
+    pub fn push(&mut self, request: PaymentRequest, now: i64) -> Result<()> {
+        if self.entries.len() >= Self::MAX_ENTRIES {
+            return Err(VaultError::DeadLetterQueueFull.into());
+        }
+        self.entries.push(DeadLetter { request, dead_lettered_at: now });
+        Ok(())
+    }
+
+    pub fn take(&mut self, payment_id: u64) -> Result<PaymentRequest> {
+        let index = self.entries.iter()
+            .position(|e| e.request.id == payment_id)
+            .ok_or(VaultError::PaymentNotFound)?;
+        Ok(self.entries.remove(index).request)
+    }
+}
+
+/// One payment method's activity over a day
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct MethodRollup {
+    pub count: u32,    // Completed payments
+    pub volume: u64,   // Completed amount in the method's base units
+    pub fees: u64,     // Protocol fees withheld from completed payments
+    pub failures: u32, // Failed attempts, retried or dead-lettered
+}
+
+impl MethodRollup {
+    pub const LEN: usize = 4 + 8 + 8 + 4;
+}
+
+/// Payment activity over one UTC day, by method
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct DailyRollup {
+    pub day: i64,                 // Days since the Unix epoch
+    pub lightning: MethodRollup,
+    pub usdc: MethodRollup,
+    pub spl_token: MethodRollup,  // All SPL token mints together
+}
+
+impl DailyRollup {
+    pub const LEN: usize = 8 + 3 * MethodRollup::LEN;
+
+    fn method_mut(&mut self, method: &PaymentMethod) -> &mut MethodRollup {
+        match method {
+            PaymentMethod::Lightning => &mut self.lightning,
+            PaymentMethod::USDC => &mut self.usdc,
+            PaymentMethod::SplToken { .. } => &mut self.spl_token,
+        }
+    }
+}
+
+/// Payment system statistics
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct PaymentStatistics {
+    pub total_payments: u64,
+    pub total_lightning_volume: u64,
+    pub total_usdc_volume: u64,
+    pub total_spl_token_volume: u64,
+    pub failed_payments: u64,
+    pub pending_payments: u64,
+    pub processing_payments: u64,
+}
+
+/// An address book entry. Usable once `active_from` has passed.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct SavedDestination {
+    pub method: PaymentMethod,
+    pub destination: String,
+    pub added_at: i64,
+    pub active_from: i64,
+}
+
+impl SavedDestination {
+    pub const MAX_DESTINATION_LEN: usize = 200;
+    pub const LEN: usize = PaymentMethod::LEN + 4 + Self::MAX_DESTINATION_LEN + 8 + 8;
+}
+
+/// User payment preferences
+#[account]
+pub struct UserPaymentPreferences {
+    pub user: Pubkey,
+    pub default_method: PaymentMethod,
+    pub lightning_address: Option<String>,
+    pub usdc_address: Option<Pubkey>,
+    pub reinvestment_config: ReinvestmentConfig,
+    pub notification_preferences: NotificationPreferences,
+    pub saved_destinations: Vec<SavedDestination>, // Address book, at most 10 per method
+    pub saved_destinations_only: bool, // Reject destinations not in the address book
+    pub bump: u8,
+}
+
+impl UserPaymentPreferences {
+    /// Address book entries allowed per payment method
+    pub const MAX_SAVED_DESTINATIONS_PER_METHOD: usize = 10;
+    /// Delay before a newly saved destination can receive payouts
+    pub const SAVED_DESTINATION_DELAY_SECONDS: i64 = 86400;
+    /// Lightning, USDC and SPL token payouts each have their own entries
+    const PAYMENT_METHOD_KINDS: usize = 3;
+
+    pub const LEN: usize = 8 + // discriminator
+        32 + // user
+        PaymentMethod::LEN + // default_method
+        4 + 200 + // lightning_address (optional)
+        33 + // usdc_address (optional)
+        (1 + 1 + 8 + 4) + // reinvestment_config
+        (1 + 1 + 1 + 1) + // notification_preferences
+        4 + Self::PAYMENT_METHOD_KINDS * Self::MAX_SAVED_DESTINATIONS_PER_METHOD * SavedDestination::LEN + // saved_destinations
+        1 + // saved_destinations_only
+        1; // bump
+
+    pub fn initialize(
         &mut self,
         user: Pubkey,
         default_method: PaymentMethod,
@@ -501,6 +2113,8 @@ impl UserPaymentPreferences {
             large_payment_approval: true,
             reinvestment_executed: false,
         };
+        self.saved_destinations = Vec::new();
+        self.saved_destinations_only = false;
         self.bump = bump;
 
         Ok(())
@@ -530,9 +2144,587 @@ impl UserPaymentPreferences {
         if config.percentage > 100 {
             return Err(VaultError::InvalidReinvestmentPercentage.into());
         }
+        if config.compound_frequency < ReinvestmentConfig::MIN_COMPOUND_FREQUENCY {
+            return Err(VaultError::ReinvestmentTooFrequent.into());
+        }
         self.reinvestment_config = config;
         Ok(())
     }
+
+    /// Save a destination to the address book. The caller must have checked
+    /// a second factor; the entry activates after the 24h delay.
+    pub fn add_saved_destination(
+        &mut self,
+        method: PaymentMethod,
+        destination: String,
+        now: i64,
+    ) -> Result<u8> {
+        if destination.is_empty() || destination.len() > SavedDestination::MAX_DESTINATION_LEN {
+            return Err(VaultError::InvalidSavedDestination.into());
+        }
+        if self.saved_destinations.iter().any(|d| d.method == method && d.destination == destination) {
+            return Err(VaultError::InvalidSavedDestination.into());
+        }
+        let saved_for_method = self.saved_destinations
+            .iter()
+            .filter(|d| same_method_kind(&d.method, &method))
+            .count();
+        if saved_for_method >= Self::MAX_SAVED_DESTINATIONS_PER_METHOD {
+            return Err(VaultError::SavedDestinationLimitReached.into());
+        }
+
+        let active_from = now
+            .checked_add(Self::SAVED_DESTINATION_DELAY_SECONDS)
+            .ok_or(VaultError::ArithmeticOverflow)?;
+        self.saved_destinations.push(SavedDestination {
+            method,
+            destination,
+            added_at: now,
+            active_from,
+        });
+        Ok((self.saved_destinations.len() - 1) as u8)
+    }
+
+    /// Delete an address book entry, effective immediately. Later entries
+    /// shift down one index.
+    pub fn remove_saved_destination(&mut self, index: u8) -> Result<SavedDestination> {
+        let index = index as usize;
+        if index >= self.saved_destinations.len() {
+            return Err(VaultError::SavedDestinationNotFound.into());
+        }
+        Ok(self.saved_destinations.remove(index))
+    }
+
+    /// Whether `destination` is an active address book entry or the
+    /// preferred address for `method`
+    pub fn is_known_destination(&self, method: &PaymentMethod, destination: &str, now: i64) -> bool {
+        let saved = self.saved_destinations.iter()
+            .any(|d| d.method == *method && d.destination == destination && now >= d.active_from);
+        let preferred = match method {
+            PaymentMethod::Lightning => self.lightning_address.as_deref() == Some(destination),
+            PaymentMethod::USDC | PaymentMethod::SplToken { .. } => self.usdc_address
+                .is_some_and(|address| address.to_string() == destination),
+        };
+        saved || preferred
+    }
+
+    /// Whether a payout to `destination` passes the saved-destinations lock:
+    /// always with the lock off, else only to an active saved entry
+    pub fn allows_destination(&self, method: &PaymentMethod, destination: &str, now: i64) -> bool {
+        !self.saved_destinations_only || self.saved_destinations.iter()
+            .any(|d| d.method == *method && d.destination == destination && now >= d.active_from)
+    }
+
+    /// Resolve where a payout goes: a saved entry if one is referenced, else
+    /// the given destination or the preferred address for the method. With
+    /// the saved-destinations lock on, only active saved entries are accepted.
+    pub fn resolve_destination(
+        &self,
+        method: &PaymentMethod,
+        destination: String,
+        saved_index: Option<u8>,
+        now: i64,
+    ) -> Result<String> {
+        if let Some(index) = saved_index {
+            let saved = self.saved_destinations
+                .get(index as usize)
+                .ok_or(VaultError::SavedDestinationNotFound)?;
+            if saved.method != *method {
+                return Err(VaultError::InvalidSavedDestination.into());
+            }
+            if now < saved.active_from {
+                return Err(VaultError::SavedDestinationNotActive.into());
+            }
+            return Ok(saved.destination.clone());
+        }
+
+        if self.saved_destinations_only {
+            return Err(VaultError::SavedDestinationRequired.into());
+        }
+        if !destination.is_empty() {
+            return Ok(destination);
+        }
+        match method {
+            PaymentMethod::Lightning => self.lightning_address.clone()
+                .ok_or(VaultError::NoPaymentDestination.into()),
+            // SPL token payouts go to the same Solana wallet as USDC
+            PaymentMethod::USDC | PaymentMethod::SplToken { .. } => self.usdc_address
+                .map(|address| address.to_string())
+                .ok_or(VaultError::NoPaymentDestination.into()),
+        }
+    }
+}
+
+/// Whether two methods draw on the same address book allowance; all SPL
+/// token mints share one
+fn same_method_kind(a: &PaymentMethod, b: &PaymentMethod) -> bool {
+    std::mem::discriminant(a) == std::mem::discriminant(b)
+}
+
+/// Cached AML screening of a payout destination, keyed by the destination's
+/// hash and valid for the screening policy's TTL
+#[account]
+pub struct DestinationScreening {
+    pub destination_hash: [u8; 32],
+    pub risk_score: u16,
+    pub sanctions_match: bool,
+    pub manual_review: bool,  // Screened against a stale sanctions list; approval needed whatever the score
+    pub screened_at: i64,
+    pub expires_at: i64,
+    pub screened_by: Pubkey,
+    pub bump: u8,
+}
+
+impl DestinationScreening {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // destination_hash
+        2 + // risk_score
+        1 + // sanctions_match
+        1 + // manual_review
+        8 + // screened_at
+        8 + // expires_at
+        32 + // screened_by
+        1; // bump
+
+    /// Record a fresh screening, replacing any cached one. `sanctions` is
+    /// the destination's check against the on-chain sanctions list.
+    pub fn record(
+        &mut self,
+        destination_hash: [u8; 32],
+        screening: &AMLScreening,
+        sanctions: SanctionsVerdict,
+        policy: &ScreeningPolicy,
+        screened_by: Pubkey,
+        now: i64,
+    ) -> Result<()> {
+        if !policy.is_enabled() {
+            return Err(VaultError::InvalidScreeningPolicy.into());
+        }
+        self.destination_hash = destination_hash;
+        self.risk_score = screening.risk_score;
+        self.sanctions_match = screening.sanctions_match || sanctions == SanctionsVerdict::Listed;
+        self.manual_review = sanctions == SanctionsVerdict::ManualReview;
+        self.screened_at = now;
+        self.expires_at = now.checked_add(policy.ttl_seconds).ok_or(VaultError::ArithmeticOverflow)?;
+        self.screened_by = screened_by;
+        Ok(())
+    }
+
+    /// Whether payouts to `destination_hash` need multisig approval. Fails
+    /// if the screening is for another destination, has expired or found a
+    /// sanctions match.
+    pub fn requires_approval(&self, destination_hash: &[u8; 32], policy: &ScreeningPolicy, now: i64) -> Result<bool> {
+        if self.destination_hash != *destination_hash {
+            return Err(VaultError::DestinationNotScreened.into());
+        }
+        if now >= self.expires_at {
+            return Err(VaultError::DestinationScreeningExpired.into());
+        }
+        if self.sanctions_match {
+            return Err(VaultError::DestinationSanctioned.into());
+        }
+        Ok(self.manual_review || self.risk_score >= policy.risk_threshold)
+    }
+}
+
+/// A finalized payment as kept in its user's history
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct PaymentRecord {
+    pub id: u64,
+    pub method: PaymentMethod,
+    pub amount: u64,
+    pub status: PaymentStatus,        // Completed or Refunded, or Failed if recorded before refunds
+    pub created_at: i64,
+    pub completed_at: Option<i64>,
+    pub retry_count: u8,
+    pub claim_id: u64,
+}
+
+impl PaymentRecord {
+    pub const LEN: usize = 8 + PaymentMethod::LEN + 8 + 1 + 8 + 9 + 1 + 8;
+}
+
+impl From<&PaymentRequest> for PaymentRecord {
+    fn from(request: &PaymentRequest) -> Self {
+        Self {
+            id: request.id,
+            method: request.method.clone(),
+            amount: request.amount,
+            status: request.status.clone(),
+            created_at: request.created_at,
+            completed_at: request.completed_at,
+            retry_count: request.retry_count,
+            claim_id: request.claim_id,
+        }
+    }
+}
+
+/// A page of a user's payment history, returned to the caller
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct PaymentHistoryPage {
+    pub start: u64,          // Sequence number of the first record, past the cursor if those were overwritten
+    pub records: Vec<PaymentRecord>,
+    pub next_cursor: u64,    // Cursor for the following page, `total` once caught up
+    pub total: u64,          // Records ever kept
+}
+
+/// A user's finalized payments, the latest `HISTORY_LEN` of them. In-flight
+/// requests stay in the shared `PaymentSystem` queue until they finish.
+#[account]
+pub struct UserPaymentHistory {
+    pub user: Pubkey,
+    pub records: Vec<PaymentRecord>, // Ring buffer indexed by sequence number
+    pub record_count: u64,           // Records ever kept; the next sequence number
+    pub bump: u8,
+}
+
+impl UserPaymentHistory {
+    pub const HISTORY_LEN: usize = 64;
+    pub const PAGE_LEN: usize = 16;
+
+    pub const LEN: usize = 8 + // discriminator
+        32 + // user
+        4 + (Self::HISTORY_LEN * PaymentRecord::LEN) + // records
+        8 + // record_count
+        1; // bump
+
+    pub fn initialize(&mut self, user: Pubkey, bump: u8) {
+        self.user = user;
+        self.records = Vec::new();
+        self.record_count = 0;
+        self.bump = bump;
+    }
+
+    /// Append a finalized payment, overwriting the oldest record once full.
+    /// Returns the record's sequence number.
+    pub fn record(&mut self, request: &PaymentRequest) -> u64 {
+        let sequence = self.record_count;
+        let record = PaymentRecord::from(request);
+        if self.records.len() < Self::HISTORY_LEN {
+            self.records.push(record);
+        } else {
+            self.records[(sequence % Self::HISTORY_LEN as u64) as usize] = record;
+        }
+        self.record_count = sequence.saturating_add(1);
+        sequence
+    }
+
+    /// A kept record of payment `payment_id`, if it has not been overwritten
+    pub fn find(&self, payment_id: u64) -> Option<&PaymentRecord> {
+        self.records.iter().find(|r| r.id == payment_id)
+    }
+
+    /// Up to a page of records from sequence number `cursor` on. Records
+    /// already overwritten are skipped.
+    pub fn page(&self, cursor: u64) -> PaymentHistoryPage {
+        let oldest = self.record_count - self.records.len() as u64;
+        let start = cursor.clamp(oldest, self.record_count);
+        let end = start.saturating_add(Self::PAGE_LEN as u64).min(self.record_count);
+        let records = (start..end)
+            .map(|sequence| self.records[(sequence % Self::HISTORY_LEN as u64) as usize].clone())
+            .collect();
+        PaymentHistoryPage {
+            start,
+            records,
+            next_cursor: end,
+            total: self.record_count,
+        }
+    }
+}
+
+/// Price of a payout before it is requested: what the user gives up and
+/// what the recipient should get. Kept per user, so quoting never writes
+/// the shared `PaymentSystem`, and `create_payment_request` can honor the
+/// quoted protocol fee while the quote is valid.
+#[account]
+pub struct PaymentQuote {
+    pub user: Pubkey,
+    pub quote_id: u64,                // Increments with each quote for this user
+    pub method: PaymentMethod,
+    pub gross_amount: u64,            // Amount deducted from pending rewards
+    pub fee_basis_points: u16,        // Protocol fee rate honored while valid
+    pub protocol_fee: u64,
+    pub network_fee: u64,             // Estimated Lightning routing fee
+    pub net_amount: u64,              // Expected amount received
+    pub valid_until: i64,             // Last second the quoted fee is honored
+    pub bump: u8,
+}
+
+impl PaymentQuote {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // user
+        8 + // quote_id
+        PaymentMethod::LEN + // method
+        8 + // gross_amount
+        2 + // fee_basis_points
+        8 + // protocol_fee
+        8 + // network_fee
+        8 + // net_amount
+        8 + // valid_until
+        1; // bump
+
+    pub const VALIDITY_SECONDS: i64 = 300; // 5 minutes
+
+    /// Price `amount` paid out by `method` at the current configuration,
+    /// replacing the previous quote
+    pub fn quote(
+        &mut self,
+        payment_system: &PaymentSystem,
+        method: PaymentMethod,
+        amount: u64,
+        now: i64,
+    ) -> Result<()> {
+        if payment_system.emergency_pause {
+            return Err(VaultError::PaymentSystemPaused.into());
+        }
+        let (min_amount, max_amount) = payment_system.payment_limits(&method)?;
+        if amount < min_amount {
+            return Err(VaultError::PaymentAmountTooSmall.into());
+        }
+        if amount > max_amount {
+            return Err(VaultError::PaymentAmountTooLarge.into());
+        }
+
+        let fee_basis_points = payment_system.protocol_fee_bps(&method)?;
+        let protocol_fee = payment_fee(amount, fee_basis_points)?;
+        let network_fee = payment_system.estimated_network_fee(&method, amount);
+
+        self.quote_id = self.quote_id.checked_add(1).ok_or(VaultError::ArithmeticOverflow)?;
+        self.method = method;
+        self.gross_amount = amount;
+        self.fee_basis_points = fee_basis_points;
+        self.protocol_fee = protocol_fee;
+        self.network_fee = network_fee;
+        self.net_amount = amount.saturating_sub(protocol_fee).saturating_sub(network_fee);
+        self.valid_until = now.saturating_add(Self::VALIDITY_SECONDS);
+        Ok(())
+    }
+
+    /// Protocol fee rate to lock into a request made under quote `quote_id`,
+    /// or None once the quote has expired and live pricing applies
+    pub fn honored_fee_bps(
+        &self,
+        quote_id: u64,
+        method: &PaymentMethod,
+        amount: u64,
+        now: i64,
+    ) -> Result<Option<u16>> {
+        if quote_id != self.quote_id || *method != self.method || amount != self.gross_amount {
+            return Err(VaultError::InvalidPaymentQuote.into());
+        }
+        Ok((now <= self.valid_until).then_some(self.fee_basis_points))
+    }
+}
+
+/// Recurring payout of a user's pending rewards, run by a keeper crank.
+/// Lightning is not schedulable since each invoice is single-use and fixed
+/// to one amount.
+#[account]
+pub struct PaymentSchedule {
+    pub user: Pubkey,
+    pub method: PaymentMethod,        // USDC or an SPL token
+    pub destination: Pubkey,          // Solana wallet receiving each payout
+    pub frequency_secs: i64,          // Time between payouts
+    pub max_per_period: u64,          // Most paid out per run, in the method's base units
+    pub next_run_at: i64,             // Earliest time of the next payout
+    pub last_payment_id: Option<u64>, // Payment request created by the last run
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+impl PaymentSchedule {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // user
+        PaymentMethod::LEN + // method
+        32 + // destination
+        8 + // frequency_secs
+        8 + // max_per_period
+        8 + // next_run_at
+        (1 + 8) + // last_payment_id
+        8 + // created_at
+        1; // bump
+
+    pub const MIN_FREQUENCY_SECS: i64 = 86400; // Daily
+
+    /// Set the payout terms, checking the method is schedulable and accepted
+    pub fn configure(
+        &mut self,
+        payment_system: &PaymentSystem,
+        method: PaymentMethod,
+        destination: Pubkey,
+        frequency_secs: i64,
+        max_per_period: u64,
+        next_run_at: i64,
+    ) -> Result<()> {
+        match &method {
+            PaymentMethod::Lightning => return Err(VaultError::UnsupportedScheduleMethod.into()),
+            PaymentMethod::USDC => {},
+            PaymentMethod::SplToken { mint } => {
+                payment_system.spl_token_config(mint)?;
+            },
+        }
+        if frequency_secs < Self::MIN_FREQUENCY_SECS || max_per_period == 0 || next_run_at <= 0 {
+            return Err(VaultError::InvalidPaymentSchedule.into());
+        }
+
+        self.method = method;
+        self.destination = destination;
+        self.frequency_secs = frequency_secs;
+        self.max_per_period = max_per_period;
+        self.next_run_at = next_run_at;
+        Ok(())
+    }
+
+    pub fn is_due(&self, now: i64) -> bool {
+        now >= self.next_run_at
+    }
+
+    /// Amount to pay this run from `pending_rewards` within the method's
+    /// limits, or None when the balance is below the method minimum
+    pub fn payout_amount(&self, pending_rewards: u64, (min_amount, max_amount): (u64, u64)) -> Option<u64> {
+        let amount = pending_rewards.min(self.max_per_period).min(max_amount);
+        (amount > 0 && amount >= min_amount).then_some(amount)
+    }
+
+    /// Move to the first run time after `now`, skipping missed periods
+    /// rather than paying them out in a burst
+    pub fn advance(&mut self, now: i64) {
+        if now >= self.next_run_at {
+            let missed = (now - self.next_run_at) / self.frequency_secs + 1;
+            self.next_run_at = self.next_run_at
+                .saturating_add(missed.saturating_mul(self.frequency_secs));
+        }
+    }
+}
+
+/// Where a payment stream's deposit comes from
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub enum StreamFunding {
+    Rewards,  // The funder's pending rewards
+    Treasury, // The treasury's user rewards pool, opened by a payment admin and approved by the multisig
+}
+
+/// Continuous payout unlocking `rate_per_second` between `start` and `end`.
+/// The whole deposit is set aside when the stream is funded: at opening for
+/// reward-funded streams, at multisig approval for treasury-funded ones. The
+/// recipient withdraws unlocked amounts into their pending rewards and claims
+/// them through a normal payment request.
+#[account]
+pub struct PaymentStream {
+    pub funder: Pubkey,
+    pub recipient: Pubkey,
+    pub stream_id: u64,               // Funder-chosen id, part of the PDA seeds
+    pub funding: StreamFunding,
+    pub rate_per_second: u64,
+    pub start: i64,
+    pub end: i64,
+    pub deposited: u64,               // rate_per_second * (end - start)
+    pub withdrawn: u64,               // Unlocked amount already moved to the recipient
+    pub cancelled_at: Option<i64>,    // Unlocking stops here once cancelled
+    pub approved: bool,               // Funded; treasury streams unlock nothing until the multisig approves
+    pub bump: u8,
+}
+
+impl PaymentStream {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // funder
+        32 + // recipient
+        8 + // stream_id
+        1 + // funding
+        8 + // rate_per_second
+        8 + // start
+        8 + // end
+        8 + // deposited
+        8 + // withdrawn
+        (1 + 8) + // cancelled_at
+        1 + // approved
+        1; // bump
+
+    /// Set the stream's terms and return the deposit to set aside. The start
+    /// can't be in the past, so nothing unlocks at opening. Reward-funded
+    /// streams are funded at once; treasury-funded ones wait for `approve`.
+    pub fn open(
+        &mut self,
+        funding: StreamFunding,
+        recipient: Pubkey,
+        rate_per_second: u64,
+        start: i64,
+        end: i64,
+        now: i64,
+    ) -> Result<u64> {
+        if rate_per_second == 0 || start < now || end <= start || recipient == Pubkey::default() {
+            return Err(VaultError::InvalidPaymentStream.into());
+        }
+        let deposited = rate_per_second
+            .checked_mul((end - start) as u64)
+            .ok_or(VaultError::ArithmeticOverflow)?;
+
+        self.funding = funding;
+        self.recipient = recipient;
+        self.rate_per_second = rate_per_second;
+        self.start = start;
+        self.end = end;
+        self.deposited = deposited;
+        self.withdrawn = 0;
+        self.cancelled_at = None;
+        self.approved = self.funding == StreamFunding::Rewards;
+        Ok(deposited)
+    }
+
+    /// Mark a treasury-funded stream as funded, once the multisig has
+    /// approved it and its deposit has left the treasury
+    pub fn approve(&mut self) -> Result<()> {
+        if self.cancelled_at.is_some() {
+            return Err(VaultError::PaymentStreamCancelled.into());
+        }
+        if self.funding != StreamFunding::Treasury || self.approved {
+            return Err(VaultError::InvalidPaymentStream.into());
+        }
+        self.approved = true;
+        Ok(())
+    }
+
+    /// Total unlocked by `now`, withdrawn or not. Exact at both ends: zero
+    /// up to `start`, the full deposit from `end` on. Zero until approved.
+    pub fn unlocked_at(&self, now: i64) -> u64 {
+        if !self.approved {
+            return 0;
+        }
+        let stop = self.cancelled_at.map_or(self.end, |cancelled| cancelled.min(self.end));
+        let elapsed = now.min(stop).saturating_sub(self.start).max(0) as u64;
+        // Bounded by the deposit, which was checked not to overflow
+        self.rate_per_second * elapsed
+    }
+
+    pub fn withdrawable(&self, now: i64) -> u64 {
+        self.unlocked_at(now) - self.withdrawn
+    }
+
+    /// Withdraw part of the unlocked amount
+    pub fn withdraw(&mut self, amount: u64, now: i64) -> Result<()> {
+        if amount == 0 || amount > self.withdrawable(now) {
+            return Err(VaultError::StreamWithdrawalExceedsUnlocked.into());
+        }
+        self.withdrawn += amount;
+        Ok(())
+    }
+
+    /// Stop the stream. Returns what is owed to the recipient (unlocked but
+    /// not yet withdrawn) and what goes back to the funder (still locked).
+    /// An unapproved stream was never funded, so nothing goes back.
+    pub fn cancel(&mut self, now: i64) -> Result<(u64, u64)> {
+        if self.cancelled_at.is_some() {
+            return Err(VaultError::PaymentStreamCancelled.into());
+        }
+        let unlocked = self.unlocked_at(now);
+        let recipient_due = unlocked - self.withdrawn;
+        let funded = if self.approved { self.deposited } else { 0 };
+        let funder_refund = funded - unlocked;
+
+        self.cancelled_at = Some(now);
+        self.withdrawn = unlocked;
+        Ok((recipient_due, funder_refund))
+    }
 }
 
 /// Notification preferences for payment events
@@ -543,3 +2735,1109 @@ pub struct NotificationPreferences {
     pub large_payment_approval: bool,
     pub reinvestment_executed: bool,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::rewards::UserRewards;
+
+    fn payment_request(claim_id: u64, amount: u64) -> PaymentRequest {
+        PaymentRequest {
+            id: 1,
+            user: Pubkey::new_unique(),
+            method: PaymentMethod::Lightning,
+            amount,
+            destination: "lnbc1".to_string(),
+            status: PaymentStatus::Processing,
+            created_at: 0,
+            processed_at: None,
+            completed_at: None,
+            failure_reason: None,
+            retry_count: 0,
+            multisig_required: false,
+            claim_id,
+            refunded: false,
+            payment_hash: None,
+            quoted_fee_bps: None,
+            next_retry_at: None,
+            parent_id: None,
+            high_risk: false,
+            approval_round: 0,
+            destination_history: Vec::new(),
+            travel_rule: None,
+            risk_score: 0,
+            risk_gated: false,
+        }
+    }
+
+    fn payment_system() -> PaymentSystem {
+        PaymentSystem {
+            lightning_config: LightningConfig {
+                node_pubkey: [0; 33],
+                channel_capacity: 0,
+                fee_rate: 0,
+                timeout_blocks: 0,
+                max_payment_amount: 0,
+                min_payment_amount: 0,
+                float_watermark: 0,
+            },
+            usdc_config: UsdcConfig {
+                mint_address: Pubkey::new_unique(),
+                treasury_ata: Pubkey::new_unique(),
+                fee_ata: Pubkey::new_unique(),
+                fee_basis_points: 0,
+                max_payment_amount: 0,
+                min_payment_amount: 0,
+                float_watermark: 0,
+            },
+            spl_token_configs: Vec::new(),
+            payment_requests: Vec::new(),
+            total_payments_processed: 0,
+            total_lightning_volume: 0,
+            total_usdc_volume: 0,
+            total_spl_token_volume: 0,
+            failed_payments_count: 0,
+            last_payment_id: 0,
+            emergency_pause: false,
+            multisig_wallet: Pubkey::new_unique(),
+            velocity_limits: VelocityLimits::default(),
+            payout_window: PayoutWindow::default(),
+            event_sequence: 0,
+            daily_rollups: Vec::new(),
+            split_payments: Vec::new(),
+            lightning_float: 0,
+            screening_policy: ScreeningPolicy::default(),
+            bump: 0,
+        }
+    }
+
+    fn user_rewards(pending_rewards: u64) -> UserRewards {
+        UserRewards {
+            user: Pubkey::new_unique(),
+            pending_rewards,
+            total_reinvested: 0,
+            last_claim_request: 0,
+            claim_nonce: 0,
+            bump: 0,
+        }
+    }
+
+    #[test]
+    fn test_failed_payment_refunds_claim_exactly_once() {
+        let mut rewards = user_rewards(1_000);
+        let claim_id = rewards.begin_claim(600).unwrap();
+        let mut payment = payment_request(claim_id, 600);
+        assert_eq!(rewards.pending_rewards, 400);
+
+        for _ in 0..PaymentSystem::MAX_RETRY_ATTEMPTS - 1 {
+            let outcome = payment.record_completion(claim_id, false, None, 10, PaymentSystem::MAX_RETRY_ATTEMPTS).unwrap();
+            assert_eq!(outcome, CompletionOutcome::Retrying);
+        }
+
+        let outcome = payment.record_completion(claim_id, false, None, 20, PaymentSystem::MAX_RETRY_ATTEMPTS).unwrap();
+        assert_eq!(outcome, CompletionOutcome::DeadLettered);
+        assert_eq!(payment.refund().unwrap(), Some(600));
+        rewards.refund_claim(600).unwrap();
+
+        // Repeated completion or refund for the same claim must not refund again
+        let outcome = payment.record_completion(claim_id, false, None, 30, PaymentSystem::MAX_RETRY_ATTEMPTS).unwrap();
+        assert_eq!(outcome, CompletionOutcome::NoOp);
+        assert_eq!(payment.refund().unwrap(), None);
+        assert_eq!(rewards.pending_rewards, 1_000);
+        assert!(payment.refunded);
+    }
+
+    #[test]
+    fn test_refund_lifecycle_from_deduction_to_history() {
+        let mut rewards = user_rewards(1_000);
+        let mut history = UserPaymentHistory {
+            user: Pubkey::default(),
+            records: Vec::new(),
+            record_count: 0,
+            bump: 0,
+        };
+        history.initialize(rewards.user, 255);
+        let mut system = payment_system();
+
+        // Deduct, then fail every attempt: the last one dead-letters the
+        // payment, and ops refund it from the queue
+        let claim_id = rewards.begin_claim(600).unwrap();
+        let mut payment = payment_request(claim_id, 600);
+        payment.user = rewards.user;
+        system.payment_requests.push(payment);
+        let payment = &mut system.payment_requests[0];
+        for _ in 0..PaymentSystem::MAX_RETRY_ATTEMPTS - 1 {
+            assert_eq!(payment.record_completion(claim_id, false, None, 10, PaymentSystem::MAX_RETRY_ATTEMPTS).unwrap(),
+                       CompletionOutcome::Retrying);
+            assert_eq!(payment.status, PaymentStatus::Pending);
+        }
+        let outcome = payment.record_completion(claim_id, false, None, 20, PaymentSystem::MAX_RETRY_ATTEMPTS).unwrap();
+        assert_eq!(outcome, CompletionOutcome::DeadLettered);
+        let mut dead_letter_queue = DeadLetterQueue { entries: Vec::new(), bump: 0 };
+        dead_letter_queue.push(system.take_dead_lettered(1).unwrap(), 20).unwrap();
+        assert!(system.get_payment_request(1).is_none());
+
+        let mut request = dead_letter_queue.take(1).unwrap();
+        rewards.refund_claim(request.refund().unwrap().unwrap()).unwrap();
+        assert_eq!(request.status, PaymentStatus::Refunded);
+        assert_eq!(rewards.pending_rewards, 1_000);
+        history.record(&request);
+        assert_eq!(history.find(1).unwrap().status, PaymentStatus::Refunded);
+        assert!(dead_letter_queue.take(1).is_err());
+
+        // Cancelled payments wait for a manual refund, which is idempotent
+        let claim_id = rewards.begin_claim(300).unwrap();
+        let mut payment = payment_request(claim_id, 300);
+        payment.id = 2;
+        payment.user = rewards.user;
+        payment.status = PaymentStatus::Pending;
+        system.payment_requests.push(payment);
+        assert!(system.refund_payment(2).is_err());
+        system.cancel_payment(2, rewards.user).unwrap();
+        assert!(system.take_finalized(2).is_none());
+        assert_eq!(system.refund_payment(2).unwrap(), Some(300));
+        rewards.refund_claim(300).unwrap();
+        assert_eq!(system.refund_payment(2).unwrap(), None);
+        assert!(system.cancel_payment(2, rewards.user).is_err());
+        assert_eq!(rewards.pending_rewards, 1_000);
+        assert!(system.take_finalized(2).is_some());
+    }
+
+    #[test]
+    fn test_payouts_over_daily_limits_are_held_until_the_window_rolls() {
+        let day = PayoutWindow::DURATION_SECONDS;
+        let mut system = payment_system();
+        assert!(system.set_velocity_limits(VelocityLimits {
+            user_daily_limit: 1_000,
+            enhanced_user_daily_limit: 500,
+            protocol_daily_limit: 0,
+        }).is_err());
+        system.set_velocity_limits(VelocityLimits {
+            user_daily_limit: 1_000,
+            enhanced_user_daily_limit: 5_000,
+            protocol_daily_limit: 6_000,
+        }).unwrap();
+        for (id, amount) in [(1, 800), (2, 300), (3, 4_000), (4, 1_500)] {
+            let mut request = payment_request(id, amount);
+            request.id = id;
+            system.payment_requests.push(request);
+        }
+        let mut alice = PayoutWindow::default();
+        let mut bob = PayoutWindow::default();
+
+        assert!(!system.charge_velocity(1, &mut alice, false, 100).unwrap());
+        assert!(system.charge_velocity(2, &mut alice, false, 200).unwrap());
+        assert_eq!(system.payment_requests[1].status, PaymentStatus::PendingLimitReset);
+
+        // Enhanced KYC raises the user limit, but not past the protocol's
+        assert!(!system.charge_velocity(3, &mut bob, true, 300).unwrap());
+        assert!(system.charge_velocity(4, &mut bob, true, 400).unwrap());
+        assert_eq!(system.payout_window.volume, 4_800);
+
+        // Held requests stay held until their user's window rolls
+        assert!(system.release_held_payment(2, &mut alice, false, 100 + day - 1).is_err());
+        system.release_held_payment(2, &mut alice, false, 100 + day).unwrap();
+        assert_eq!(system.payment_requests[1].status, PaymentStatus::Processing);
+        assert_eq!((alice.window_start, alice.volume), (100 + day, 300));
+        assert!(system.release_held_payment(2, &mut alice, false, 100 + day).is_err());
+
+        // Cancelling a held request leaves the windows untouched
+        system.payment_requests[3].user = Pubkey::new_unique();
+        let bob_user = system.payment_requests[3].user;
+        system.cancel_payment(4, bob_user).unwrap();
+        assert_eq!(bob.volume, 4_000);
+    }
+
+    #[test]
+    fn test_approval_releases_multisig_payments_and_sequences_events() {
+        let mut system = payment_system();
+        let mut small = payment_request(1, 100);
+        small.status = PaymentStatus::Pending;
+        let mut large = payment_request(2, 5_000_000);
+        large.id = 2;
+        large.status = PaymentStatus::Pending;
+        large.multisig_required = true;
+        system.payment_requests = vec![small, large];
+
+        let approver = Pubkey::new_unique();
+        assert!(system.approve_payment(1, 0, approver).is_err());
+        system.approve_payment(2, 0, approver).unwrap();
+        assert_eq!(system.payment_requests[1].status, PaymentStatus::Processing);
+        assert_eq!(system.event_sequence, 1);
+        assert!(system.approve_payment(2, 0, approver).is_err());
+        assert_eq!(system.event_sequence, 1);
+
+        assert_eq!(system.next_event_sequence().unwrap(), 2);
+        assert_eq!(
+            system.payment_requests[1].destination_hash(),
+            solana_program::hash::hash(b"lnbc1").to_bytes(),
+        );
+    }
+
+    #[test]
+    fn test_saved_destinations_activate_after_delay_and_lock_payouts() {
+        let mut preferences = UserPaymentPreferences {
+            user: Pubkey::new_unique(),
+            default_method: PaymentMethod::Lightning,
+            lightning_address: None,
+            usdc_address: None,
+            reinvestment_config: ReinvestmentConfig {
+                enabled: false,
+                percentage: 0,
+                min_threshold: 0,
+                compound_frequency: 0,
+            },
+            notification_preferences: NotificationPreferences {
+                payment_completed: false,
+                payment_failed: false,
+                large_payment_approval: false,
+                reinvestment_executed: false,
+            },
+            saved_destinations: Vec::new(),
+            saved_destinations_only: false,
+            bump: 0,
+        };
+        let lightning = PaymentMethod::Lightning;
+
+        let index = preferences.add_saved_destination(lightning.clone(), "lnbc1".to_string(), 100).unwrap();
+        assert!(preferences.add_saved_destination(lightning.clone(), "lnbc1".to_string(), 100).is_err());
+        assert!(!preferences.is_known_destination(&lightning, "lnbc1", 100));
+        assert!(preferences.is_known_destination(&lightning, "lnbc1", 86_500));
+        assert!(!preferences.is_known_destination(&PaymentMethod::USDC, "lnbc1", 86_500));
+        assert!(preferences.resolve_destination(&lightning, String::new(), Some(index), 100).is_err());
+        assert!(preferences.resolve_destination(&PaymentMethod::USDC, String::new(), Some(index), 86_500).is_err());
+        assert_eq!(
+            preferences.resolve_destination(&lightning, "ignored".to_string(), Some(index), 86_500).unwrap(),
+            "lnbc1",
+        );
+
+        for i in 1..UserPaymentPreferences::MAX_SAVED_DESTINATIONS_PER_METHOD {
+            preferences.add_saved_destination(lightning.clone(), format!("lnbc{}", i + 1), 100).unwrap();
+        }
+        assert!(preferences.add_saved_destination(lightning.clone(), "lnbc99".to_string(), 100).is_err());
+        preferences.add_saved_destination(PaymentMethod::USDC, "wallet".to_string(), 100).unwrap();
+
+        // Free-form destinations work until the lock is on
+        assert_eq!(
+            preferences.resolve_destination(&lightning, "lnbc_other".to_string(), None, 86_500).unwrap(),
+            "lnbc_other",
+        );
+        preferences.saved_destinations_only = true;
+        assert!(preferences.resolve_destination(&lightning, "lnbc_other".to_string(), None, 86_500).is_err());
+        assert!(preferences.allows_destination(&lightning, "lnbc1", 86_500));
+        assert!(!preferences.allows_destination(&lightning, "lnbc1", 100));
+        assert!(!preferences.allows_destination(&lightning, "lnbc_other", 86_500));
+
+        // Deletion is immediate
+        preferences.remove_saved_destination(index).unwrap();
+        assert_eq!(
+            preferences.resolve_destination(&lightning, String::new(), Some(index), 86_500).unwrap(),
+            "lnbc2",
+        );
+        assert!(preferences.remove_saved_destination(20).is_err());
+    }
+
+    #[test]
+    fn test_payment_stream_unlocks_exactly_between_start_and_end() {
+        let mut stream = PaymentStream {
+            funder: Pubkey::new_unique(),
+            recipient: Pubkey::default(),
+            stream_id: 1,
+            funding: StreamFunding::Rewards,
+            rate_per_second: 0,
+            start: 0,
+            end: 0,
+            deposited: 0,
+            withdrawn: 0,
+            cancelled_at: None,
+            approved: false,
+            bump: 0,
+        };
+        let recipient = Pubkey::new_unique();
+        assert!(stream.open(StreamFunding::Rewards, recipient, 7, 1_000, 1_000, 0).is_err());
+        assert!(stream.open(StreamFunding::Rewards, recipient, u64::MAX, 0, 2, 0).is_err());
+        // A start in the past would unlock the elapsed part at once
+        assert!(stream.open(StreamFunding::Rewards, recipient, 7, 1_000, 1_100, 1_001).is_err());
+        assert_eq!(stream.open(StreamFunding::Rewards, recipient, 7, 1_000, 1_100, 1_000).unwrap(), 700);
+        assert!(stream.approve().is_err());
+
+        // Nothing before start, even if the stream was funded earlier
+        assert_eq!(stream.withdrawable(0), 0);
+        assert_eq!(stream.withdrawable(1_000), 0);
+        assert!(stream.withdraw(1, 1_000).is_err());
+
+        assert_eq!(stream.withdrawable(1_010), 70);
+        stream.withdraw(50, 1_010).unwrap();
+        assert!(stream.withdraw(21, 1_010).is_err());
+
+        // Exactly the deposit at and after end
+        assert_eq!(stream.withdrawable(1_100), 650);
+        assert_eq!(stream.withdrawable(5_000), 650);
+        stream.withdraw(650, 1_100).unwrap();
+        assert_eq!(stream.withdrawn, stream.deposited);
+        assert_eq!(stream.cancel(1_200).unwrap(), (0, 0));
+    }
+
+    #[test]
+    fn test_cancelled_stream_splits_unlocked_and_locked_remainder() {
+        let mut stream = PaymentStream {
+            funder: Pubkey::new_unique(),
+            recipient: Pubkey::default(),
+            stream_id: 1,
+            funding: StreamFunding::Treasury,
+            rate_per_second: 0,
+            start: 0,
+            end: 0,
+            deposited: 0,
+            withdrawn: 0,
+            cancelled_at: None,
+            approved: false,
+            bump: 0,
+        };
+        stream.open(StreamFunding::Treasury, Pubkey::new_unique(), 10, 100, 200, 0).unwrap();
+        stream.approve().unwrap();
+        stream.withdraw(100, 120).unwrap();
+
+        assert_eq!(stream.cancel(150).unwrap(), (400, 500));
+        assert!(stream.cancel(160).is_err());
+        assert_eq!(stream.withdrawable(1_000), 0);
+        assert_eq!(stream.unlocked_at(1_000), 500);
+
+        // Cancelling before start returns the whole deposit
+        stream.open(StreamFunding::Treasury, Pubkey::new_unique(), 10, 100, 200, 0).unwrap();
+        stream.approve().unwrap();
+        assert_eq!(stream.cancel(50).unwrap(), (0, 1_000));
+        assert_eq!(stream.withdrawable(150), 0);
+    }
+
+    #[test]
+    fn test_treasury_stream_unlocks_nothing_until_approved() {
+        let mut stream = PaymentStream {
+            funder: Pubkey::new_unique(),
+            recipient: Pubkey::default(),
+            stream_id: 1,
+            funding: StreamFunding::Treasury,
+            rate_per_second: 0,
+            start: 0,
+            end: 0,
+            deposited: 0,
+            withdrawn: 0,
+            cancelled_at: None,
+            approved: false,
+            bump: 0,
+        };
+        stream.open(StreamFunding::Treasury, Pubkey::new_unique(), 10, 100, 200, 0).unwrap();
+        assert_eq!(stream.withdrawable(150), 0);
+        assert!(stream.withdraw(1, 150).is_err());
+
+        // Approved late, the elapsed part unlocks at once
+        stream.approve().unwrap();
+        assert!(stream.approve().is_err());
+        assert_eq!(stream.withdrawable(150), 500);
+
+        // Cancelled unapproved, nothing was funded and nothing goes back
+        stream.open(StreamFunding::Treasury, Pubkey::new_unique(), 10, 100, 200, 0).unwrap();
+        assert_eq!(stream.cancel(150).unwrap(), (0, 0));
+        assert!(stream.approve().is_err());
+    }
+
+    #[test]
+    fn test_failed_payments_back_off_then_dead_letter_and_requeue() {
+        let mut system = payment_system();
+        system.payment_requests.push(payment_request(1, 500));
+        let payment = &mut system.payment_requests[0];
+
+        assert_eq!(payment.record_completion(1, false, None, 1_000, 3).unwrap(), CompletionOutcome::Retrying);
+        assert_eq!(payment.next_retry_at, Some(1_060));
+        assert!(payment.require_retry_due(1_059).is_err());
+        payment.require_retry_due(1_060).unwrap();
+
+        assert_eq!(payment.record_completion(1, false, None, 1_100, 3).unwrap(), CompletionOutcome::Retrying);
+        assert_eq!(payment.next_retry_at, Some(1_220));
+        assert_eq!(PaymentRequest::retry_delay(3), 240);
+        assert_eq!(PaymentRequest::retry_delay(u8::MAX), PaymentRequest::MAX_RETRY_DELAY_SECONDS);
+
+        assert_eq!(payment.record_completion(1, false, None, 1_300, 3).unwrap(), CompletionOutcome::DeadLettered);
+        assert_eq!(payment.status, PaymentStatus::Failed);
+        assert!(system.take_dead_lettered(2).is_none());
+        let mut dead_letter_queue = DeadLetterQueue { entries: Vec::new(), bump: 0 };
+        dead_letter_queue.push(system.take_dead_lettered(1).unwrap(), 1_300).unwrap();
+
+        system.requeue(dead_letter_queue.take(1).unwrap()).unwrap();
+        let payment = system.get_payment_request(1).unwrap();
+        assert_eq!(payment.status, PaymentStatus::Processing);
+        assert_eq!(payment.retry_count, 0);
+        assert_eq!(payment.next_retry_at, None);
+        assert!(payment.is_processable());
+
+        // A request that was refunded can't be put back in line
+        let mut refunded = payment_request(2, 500);
+        refunded.id = 2;
+        refunded.status = PaymentStatus::Failed;
+        refunded.refund().unwrap();
+        assert!(system.requeue(refunded).is_err());
+    }
+
+    #[test]
+    fn test_idempotency_keys_return_the_first_payment_within_a_day() {
+        let mut idempotency = PaymentIdempotency { user: Pubkey::new_unique(), entries: Vec::new(), bump: 0 };
+        let params = PaymentIdempotency::params_hash(&Some(PaymentMethod::USDC), 500, "dest", None, None).unwrap();
+        let other = PaymentIdempotency::params_hash(&Some(PaymentMethod::USDC), 501, "dest", None, None).unwrap();
+        assert_ne!(params, other);
+
+        assert_eq!(idempotency.lookup(&[1; 32], &params, 100).unwrap(), None);
+        idempotency.remember([1; 32], params, 7, 100);
+        assert_eq!(idempotency.lookup(&[1; 32], &params, 200).unwrap(), Some(7));
+        assert!(idempotency.lookup(&[1; 32], &other, 200).is_err());
+
+        // After a day the key is free again
+        let expiry = 100 + PaymentIdempotency::KEY_TTL_SECONDS;
+        assert_eq!(idempotency.lookup(&[1; 32], &other, expiry).unwrap(), None);
+
+        // The set stays bounded, dropping the oldest key
+        for key in 2..=PaymentIdempotency::MAX_KEYS as u8 + 1 {
+            idempotency.remember([key; 32], params, key as u64, 300);
+        }
+        assert_eq!(idempotency.entries.len(), PaymentIdempotency::MAX_KEYS);
+        assert_eq!(idempotency.lookup(&[1; 32], &params, 300).unwrap(), None);
+        assert_eq!(idempotency.lookup(&[2; 32], &params, 300).unwrap(), Some(2));
+        let full = idempotency.try_to_vec().unwrap().len() + 8;
+        assert!(full <= PaymentIdempotency::LEN);
+    }
+
+    #[test]
+    fn test_destination_screenings_gate_and_route_payouts() {
+        let mut system = payment_system();
+        assert!(system.set_screening_policy(ScreeningPolicy { ttl_seconds: -1, risk_threshold: 70 }).is_err());
+        system.set_screening_policy(ScreeningPolicy { ttl_seconds: 3600, risk_threshold: 70 }).unwrap();
+        let policy = system.screening_policy.clone();
+
+        let payment = payment_request(1, 100);
+        let destination_hash = payment.destination_hash();
+        let aml = AMLScreening {
+            screening_id: "CHA_1".to_string(),
+            risk_score: 20,
+            screening_date: 100,
+            alerts: Vec::new(),
+            sanctions_match: false,
+            pep_match: false,
+        };
+        let mut screening = DestinationScreening {
+            destination_hash: [0; 32],
+            risk_score: 0,
+            sanctions_match: false,
+            manual_review: false,
+            screened_at: 0,
+            expires_at: 0,
+            screened_by: Pubkey::default(),
+            bump: 0,
+        };
+        assert!(screening.record(destination_hash, &aml, SanctionsVerdict::Clear, &ScreeningPolicy::default(), Pubkey::new_unique(), 100).is_err());
+        screening.record(destination_hash, &aml, SanctionsVerdict::Clear, &policy, Pubkey::new_unique(), 100).unwrap();
+
+        assert!(!screening.requires_approval(&destination_hash, &policy, 200).unwrap());
+        assert!(screening.requires_approval(&[1; 32], &policy, 200).is_err());
+        assert!(screening.requires_approval(&destination_hash, &policy, 3_700).is_err());
+
+        // High risk routes through approval; sanctions block outright
+        screening.record(destination_hash, &AMLScreening { risk_score: 85, ..aml.clone() }, SanctionsVerdict::Clear, &policy, Pubkey::new_unique(), 100).unwrap();
+        assert!(screening.requires_approval(&destination_hash, &policy, 200).unwrap());
+        screening.record(destination_hash, &AMLScreening { sanctions_match: true, ..aml.clone() }, SanctionsVerdict::Clear, &policy, Pubkey::new_unique(), 100).unwrap();
+        assert!(screening.requires_approval(&destination_hash, &policy, 200).is_err());
+
+        // A proven listing blocks a low score; a stale sanctions list needs approval
+        screening.record(destination_hash, &aml, SanctionsVerdict::Listed, &policy, Pubkey::new_unique(), 100).unwrap();
+        assert!(screening.requires_approval(&destination_hash, &policy, 200).is_err());
+        screening.record(destination_hash, &aml, SanctionsVerdict::ManualReview, &policy, Pubkey::new_unique(), 100).unwrap();
+        assert!(screening.requires_approval(&destination_hash, &policy, 200).unwrap());
+
+        system.payment_requests.push(payment);
+        system.route_for_approval(1).unwrap();
+        assert_eq!(system.payment_requests[0].status, PaymentStatus::Pending);
+        assert!(system.payment_requests[0].multisig_required);
+        assert!(system.approvable_payment(1, 0).is_ok());
+        assert!(system.route_for_approval(1).is_err());
+    }
+
+    #[test]
+    fn test_travel_rule_data_attaches_until_processing() {
+        let mut system = payment_system();
+        let mut payment = payment_request(1, 5_000_000_000);
+        payment.status = PaymentStatus::Pending;
+        system.payment_requests.push(payment);
+
+        assert!(system.attach_travel_rule_data(1, [0; 32], 4, 10).is_err());
+        assert!(system.attach_travel_rule_data(2, [1; 32], 4, 10).is_err());
+        system.attach_travel_rule_data(1, [1; 32], 4, 10).unwrap();
+        system.attach_travel_rule_data(1, [2; 32], 4, 20).unwrap();
+        assert_eq!(
+            system.payment_requests[0].travel_rule,
+            Some(TravelRuleAttachment { data_hash: [2; 32], provider_id: 4, attached_at: 20 })
+        );
+        assert_eq!(system.event_sequence, 2);
+
+        system.payment_requests[0].processed_at = Some(30);
+        assert!(system.attach_travel_rule_data(1, [3; 32], 4, 40).is_err());
+    }
+
+    #[test]
+    fn test_destination_replacement_resets_approvals_on_risk_tier_change() {
+        let mut system = payment_system();
+        let mut payment = payment_request(1, 100);
+        payment.method = PaymentMethod::USDC;
+        payment.destination = Pubkey::new_unique().to_string();
+        payment.status = PaymentStatus::Pending;
+        let user = payment.user;
+        let original_hash = payment.destination_hash();
+        system.payment_requests.push(payment);
+
+        let destination = Pubkey::new_unique().to_string();
+        assert!(system.replace_payment_destination(1, Pubkey::new_unique(), destination.clone(), false, 10).is_err());
+        assert!(system.replace_payment_destination(1, user, "not-a-wallet".to_string(), false, 10).is_err());
+
+        // Same tier: approvals carry over
+        system.replace_payment_destination(1, user, destination.clone(), false, 10).unwrap();
+        let payment = &system.payment_requests[0];
+        assert_eq!(payment.destination, destination);
+        assert_eq!(payment.destination_history, vec![original_hash]);
+        assert_eq!(payment.approval_round, 0);
+        assert!(payment.is_processable());
+
+        // High risk needs a fresh approval round
+        system.replace_payment_destination(1, user, Pubkey::new_unique().to_string(), true, 10).unwrap();
+        assert!(system.payment_requests[0].multisig_required);
+        assert!(system.approvable_payment(1, 0).is_err());
+        assert!(system.approvable_payment(1, 1).is_ok());
+
+        system.replace_payment_destination(1, user, Pubkey::new_unique().to_string(), false, 10).unwrap();
+        assert!(!system.payment_requests[0].multisig_required);
+        assert_eq!(system.payment_requests[0].approval_round, 2);
+        assert_eq!(system.payment_requests[0].destination_history.len(), PaymentRequest::MAX_DESTINATION_REPLACEMENTS);
+        assert!(system.replace_payment_destination(1, user, Pubkey::new_unique().to_string(), false, 10).is_err());
+
+        system.payment_requests[0].destination_history.clear();
+        system.payment_requests[0].status = PaymentStatus::Processing;
+        assert!(system.replace_payment_destination(1, user, Pubkey::new_unique().to_string(), false, 10).is_err());
+    }
+
+    #[test]
+    fn test_destination_replacement_resets_pending_approvals() {
+        let mut system = payment_system();
+        let mut payment = payment_request(1, 100);
+        payment.method = PaymentMethod::USDC;
+        payment.destination = Pubkey::new_unique().to_string();
+        payment.status = PaymentStatus::Pending;
+        payment.multisig_required = true;
+        payment.risk_gated = true;
+        let user = payment.user;
+        system.payment_requests.push(payment);
+
+        // Same tier, but approvals were given for the old destination
+        system.replace_payment_destination(1, user, Pubkey::new_unique().to_string(), false, 10).unwrap();
+        assert!(system.payment_requests[0].multisig_required);
+        assert!(system.approvable_payment(1, 0).is_err());
+        assert!(system.approvable_payment(1, 1).is_ok());
+    }
+
+    #[test]
+    fn test_requests_must_fit_the_payment_float() {
+        let mut system = payment_system();
+        system.lightning_config.float_watermark = 400;
+        system.top_up_lightning_float(1_000).unwrap();
+        for (id, amount) in [(1, 300), (2, 500)] {
+            let mut payment = payment_request(id, amount);
+            payment.id = id;
+            payment.status = PaymentStatus::Processing;
+            system.payment_requests.push(payment);
+        }
+
+        // 800 committed of 1000 leaves 200, under the watermark
+        assert_eq!(system.require_float(&PaymentMethod::Lightning, None).unwrap(), 200);
+        assert_eq!(system.event_sequence, 1);
+
+        // Finished requests no longer hold float
+        system.payment_requests[1].status = PaymentStatus::Refunded;
+        assert_eq!(system.require_float(&PaymentMethod::Lightning, None).unwrap(), 700);
+        assert_eq!(system.event_sequence, 1);
+
+        system.payment_requests[1].status = PaymentStatus::Pending;
+        system.payment_requests.push(payment_request(3, 300));
+        assert!(system.require_float(&PaymentMethod::Lightning, None).is_err());
+
+        // Token methods need the live ATA balance
+        let mut usdc = payment_request(4, 300);
+        usdc.method = PaymentMethod::USDC;
+        system.payment_requests.push(usdc);
+        assert!(system.require_float(&PaymentMethod::USDC, None).is_err());
+        assert_eq!(system.require_float(&PaymentMethod::USDC, Some(500)).unwrap(), 200);
+
+        // SPL tokens leave the treasury when processed
+        let mut spl = payment_request(5, 300);
+        spl.method = PaymentMethod::SplToken { mint: Pubkey::new_unique() };
+        assert!(spl.holds_float());
+        spl.processed_at = Some(10);
+        assert!(!spl.holds_float());
+    }
+
+    #[test]
+    fn test_split_amounts_require_full_basis_points_and_keep_dust() {
+        assert_eq!(PaymentSystem::split_amounts(1_001, &[7_000, 3_000]).unwrap(), vec![700, 301]);
+        assert_eq!(PaymentSystem::split_amounts(100, &[5_000, 2_500, 2_500]).unwrap(), vec![50, 25, 25]);
+        assert!(PaymentSystem::split_amounts(100, &[10_000]).is_err());
+        assert!(PaymentSystem::split_amounts(100, &[7_000, 2_000]).is_err());
+        assert!(PaymentSystem::split_amounts(100, &[10_000, 0]).is_err());
+        assert!(PaymentSystem::split_amounts(100, &[2_500, 2_500, 2_500, 2_500]).is_err());
+    }
+
+    #[test]
+    fn test_split_parts_refund_their_own_slice_and_roll_up() {
+        let mut system = payment_system();
+        let mut rewards = user_rewards(1_000);
+        let claim_id = rewards.begin_claim(1_000).unwrap();
+        for (id, amount) in [(2, 700), (3, 300)] {
+            let mut payment = payment_request(claim_id, amount);
+            payment.id = id;
+            payment.user = rewards.user;
+            payment.parent_id = Some(1);
+            system.payment_requests.push(payment);
+        }
+        system.split_payments.push(SplitPayment { parent_id: 1, user: rewards.user, parts: 2, completed: 0, refunded: 0 });
+
+        system.settle_split_part(Some(1), true).unwrap();
+        assert_eq!(system.get_split_payment(1).unwrap().status(), SplitStatus::InProgress);
+
+        system.payment_requests[1].status = PaymentStatus::Cancelled;
+        let refund = system.refund_payment(3).unwrap().unwrap();
+        rewards.refund_claim(refund).unwrap();
+        assert_eq!(rewards.pending_rewards, 300);
+        assert!(system.get_split_payment(1).is_none());
+        assert_eq!(system.event_sequence, 1);
+
+        // A repeated refund neither restores nor settles again
+        assert_eq!(system.refund_payment(3).unwrap(), None);
+        assert_eq!(system.event_sequence, 1);
+
+        let split = SplitPayment { parent_id: 1, user: rewards.user, parts: 3, completed: 1, refunded: 2 };
+        assert_eq!(split.status(), SplitStatus::PartiallyRefunded);
+        assert_eq!(SplitPayment { completed: 0, refunded: 3, ..split.clone() }.status(), SplitStatus::Refunded);
+        assert_eq!(SplitPayment { completed: 3, refunded: 0, ..split }.status(), SplitStatus::Completed);
+    }
+
+    #[test]
+    fn test_overdue_pending_requests_expire_and_refund_instead_of_vanishing() {
+        let mut system = payment_system();
+        let user = Pubkey::new_unique();
+        let timeout = PaymentSystem::PAYMENT_TIMEOUT_SECONDS;
+        for (id, status) in [(1, PaymentStatus::Pending), (2, PaymentStatus::Processing), (3, PaymentStatus::Pending)] {
+            let mut payment = payment_request(id, 100 * id);
+            payment.id = id;
+            payment.user = user;
+            payment.status = status;
+            system.payment_requests.push(payment);
+        }
+        system.payment_requests[2].created_at = 1_000;
+
+        assert_eq!(system.expire_overdue(timeout).unwrap(), 0);
+        assert_eq!(system.expire_overdue(timeout + 1).unwrap(), 1);
+        assert_eq!(system.payment_requests[0].status, PaymentStatus::Expired);
+        assert_eq!(system.payment_requests[1].status, PaymentStatus::Processing);
+        assert_eq!(system.payment_requests[2].status, PaymentStatus::Pending);
+        assert_eq!(system.event_sequence, 1);
+
+        // Expired requests can't be processed, completed or cancelled
+        assert!(!system.payment_requests[0].is_processable());
+        assert_eq!(system.payment_requests[0].record_completion(1, true, None, 10, 3).unwrap(), CompletionOutcome::NoOp);
+        assert!(system.cancel_payment(1, user).is_err());
+
+        // Processing requests never expire, however old
+        assert_eq!(system.expire_overdue(10 * timeout).unwrap(), 1);
+        assert_eq!(system.payment_requests[1].status, PaymentStatus::Processing);
+
+        let mut rewards = user_rewards(0);
+        let (refunded, amount) = system.take_expired_for(user).unwrap();
+        rewards.refund_claim(amount).unwrap();
+        assert_eq!(rewards.pending_rewards, 400);
+        assert!(refunded.iter().all(|p| p.status == PaymentStatus::Refunded));
+        assert_eq!(system.payment_requests.len(), 1);
+        assert_eq!(system.take_expired_for(user).unwrap().1, 0);
+    }
+
+    #[test]
+    fn test_reinvestable_amount_respects_threshold_and_frequency() {
+        let mut config = ReinvestmentConfig {
+            enabled: false,
+            percentage: 25,
+            min_threshold: 100,
+            compound_frequency: 86400,
+        };
+        assert_eq!(config.reinvestable_amount(1_000), None);
+        config.enabled = true;
+        assert_eq!(config.reinvestable_amount(1_000), Some(250));
+        assert_eq!(config.reinvestable_amount(399), None);
+        assert_eq!(config.reinvestable_amount(400), Some(100));
+        config.min_threshold = 0;
+        assert_eq!(config.reinvestable_amount(3), None);
+
+        let mut preferences = UserPaymentPreferences {
+            user: Pubkey::new_unique(),
+            default_method: PaymentMethod::Lightning,
+            lightning_address: None,
+            usdc_address: None,
+            reinvestment_config: config.clone(),
+            notification_preferences: NotificationPreferences {
+                payment_completed: false,
+                payment_failed: false,
+                large_payment_approval: false,
+                reinvestment_executed: false,
+            },
+            saved_destinations: Vec::new(),
+            saved_destinations_only: false,
+            bump: 0,
+        };
+        config.compound_frequency = ReinvestmentConfig::MIN_COMPOUND_FREQUENCY - 1;
+        assert!(preferences.update_reinvestment_config(config.clone()).is_err());
+        config.compound_frequency = ReinvestmentConfig::MIN_COMPOUND_FREQUENCY;
+        preferences.update_reinvestment_config(config).unwrap();
+    }
+
+    #[test]
+    fn test_daily_rollups_ring_over_thirty_days() {
+        let mut system = payment_system();
+        let day = 20_000 * 86_400;
+        system.record_rollup(&CompletionOutcome::Completed, &PaymentMethod::USDC, 1_000, 10, day + 5);
+        system.record_rollup(&CompletionOutcome::Completed, &PaymentMethod::USDC, 500, 5, day + 80_000);
+        system.record_rollup(&CompletionOutcome::Retrying, &PaymentMethod::Lightning, 700, 0, day + 90);
+        system.record_rollup(&CompletionOutcome::NoOp, &PaymentMethod::Lightning, 700, 0, day + 95);
+
+        let page = system.rollups_page(0, day + 100);
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].day, 20_000);
+        assert_eq!(page[0].usdc, MethodRollup { count: 2, volume: 1_500, fees: 15, failures: 0 });
+        assert_eq!(page[0].lightning, MethodRollup { count: 0, volume: 0, fees: 0, failures: 1 });
+
+        // The same slot thirty days on starts over, and the old day ages out
+        let later = day + 30 * 86_400;
+        system.record_rollup(&CompletionOutcome::DeadLettered, &PaymentMethod::SplToken { mint: Pubkey::new_unique() }, 1, 0, later);
+        let page = system.rollups_page(0, later);
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].day, 20_030);
+        assert_eq!(page[0].spl_token.failures, 1);
+        assert_eq!(page[0].usdc, MethodRollup::default());
+        assert_eq!(system.daily_rollups.len(), PaymentSystem::ROLLUP_DAYS);
+    }
+
+    #[test]
+    fn test_daily_rollups_stay_within_account_and_return_data_bounds() {
+        let mut system = payment_system();
+        let full = MethodRollup { count: u32::MAX, volume: u64::MAX, fees: u64::MAX, failures: u32::MAX };
+        let today = 20_000;
+        system.daily_rollups = (0..PaymentSystem::ROLLUP_DAYS as i64)
+            .map(|offset| DailyRollup {
+                day: today - offset,
+                lightning: full.clone(),
+                usdc: full.clone(),
+                spl_token: full.clone(),
+            })
+            .collect();
+
+        let rollups = system.daily_rollups.try_to_vec().unwrap();
+        assert_eq!(rollups.len(), 4 + PaymentSystem::ROLLUP_DAYS * DailyRollup::LEN);
+        assert!(8 + system.try_to_vec().unwrap().len() <= PaymentSystem::LEN);
+
+        // Pages are oldest first and fit in transaction return data
+        let mut from_day = 0;
+        let mut days = Vec::new();
+        loop {
+            let page = system.rollups_page(from_day, today * 86_400);
+            if page.is_empty() {
+                break;
+            }
+            assert!(page.try_to_vec().unwrap().len() <= solana_program::program::MAX_RETURN_DATA);
+            let decoded = Vec::<DailyRollup>::try_from_slice(&page.try_to_vec().unwrap()).unwrap();
+            assert_eq!(decoded, page);
+            from_day = page.last().unwrap().day + 1;
+            days.extend(page.iter().map(|b| b.day));
+        }
+        assert_eq!(days, (today - 29..=today).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_large_payments_stay_gated_until_multisig_approval() {
+        let mut system = payment_system();
+        let mut large = payment_request(1, 5_000_000);
+        large.status = PaymentStatus::Pending;
+        large.multisig_required = true;
+        system.payment_requests = vec![large];
+
+        assert!(!system.payment_requests[0].is_processable());
+        assert!(system.process_payment(1).is_err());
+        assert_eq!(system.approvable_payment(1, 0).unwrap().amount, 5_000_000);
+
+        system.approve_payment(1, 0, Pubkey::new_unique()).unwrap();
+        assert!(system.payment_requests[0].is_processable());
+        assert!(system.approvable_payment(1, 0).is_err());
+
+        let outcome = system.payment_requests[0]
+            .record_completion(1, false, Some("timeout".to_string()), 10, 3)
+            .unwrap();
+        assert_eq!(outcome, CompletionOutcome::Retrying);
+        assert_eq!(system.payment_requests[0].status, PaymentStatus::Processing);
+        assert!(system.payment_requests[0].is_processable());
+    }
+
+    #[test]
+    fn test_completed_payment_is_sticky() {
+        let mut payment = payment_request(7, 500);
+        assert_eq!(payment.record_completion(7, true, None, 10, 3).unwrap(), CompletionOutcome::Completed);
+        assert_eq!(payment.record_completion(7, true, None, 11, 3).unwrap(), CompletionOutcome::NoOp);
+        assert_eq!(payment.record_completion(7, false, None, 12, 3).unwrap(), CompletionOutcome::NoOp);
+        assert_eq!(payment.completed_at, Some(10));
+        assert!(payment.record_completion(8, true, None, 13, 3).is_err());
+    }
+
+    #[test]
+    fn test_spl_token_payments_require_a_configured_mint() {
+        let mint = Pubkey::new_unique();
+        let mut system = payment_system();
+        let method = PaymentMethod::SplToken { mint };
+        assert!(system.validate_payment_amount(&method, 500).is_err());
+
+        let config = SplTokenConfig {
+            mint,
+            treasury_ata: Pubkey::new_unique(),
+            fee_basis_points: 50,
+            max_payment_amount: 1_000_000,
+            min_payment_amount: 100,
+            approval_threshold: 100_000,
+            float_watermark: 0,
+        };
+        assert!(system.set_spl_token_config(SplTokenConfig { min_payment_amount: 2_000_000, ..config.clone() }).is_err());
+        system.set_spl_token_config(config.clone()).unwrap();
+        system.validate_payment_amount(&method, 500).unwrap();
+        assert!(system.validate_payment_amount(&method, 50).is_err());
+        assert!(system.validate_payment_amount(&method, 2_000_000).is_err());
+        assert!(!system.requires_multisig_approval(&method, 100_000));
+        assert!(system.requires_multisig_approval(&method, 100_001));
+        assert_eq!(config.net_amount(10_000).unwrap(), 9_950);
+
+        // Updating a mint replaces its config rather than adding another
+        system.set_spl_token_config(SplTokenConfig { fee_basis_points: 0, ..config }).unwrap();
+        assert_eq!(system.spl_token_configs.len(), 1);
+        system.remove_spl_token_config(&mint).unwrap();
+        assert!(system.validate_payment_amount(&method, 500).is_err());
+    }
+
+    #[test]
+    fn test_lightning_invoice_must_match_amount_and_be_unexpired() {
+        // BOLT11 test vector: 2500u (250,000 sats), created at 1496314658
+        // with a 60 second expiry
+        let invoice = "lnbc2500u1pvjluezpp5qqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqypqdq5xysxxatsyp3k7enxv4jsxqzpuaztrnwngzn3kdzw5hydlzf03qdgm2hdq27cqv3agm2awhz5se903vruatfhq77w3ls4evs3ch9zw97j25emudupq63nyw24cg27h2rspfj9srp";
+        let payment_hash = PaymentSystem::check_lightning_invoice(invoice, 250_000, 1496314700).unwrap();
+        assert_eq!(payment_hash[..4], [0, 1, 2, 3]);
+
+        assert!(PaymentSystem::check_lightning_invoice(invoice, 249_999, 1496314700).is_err());
+        assert!(PaymentSystem::check_lightning_invoice(invoice, 250_000, 1496314718).is_err());
+        assert!(PaymentSystem::check_lightning_invoice(&invoice.replacen("pvjluez", "pvjluex", 1), 250_000, 1496314700)
+            .is_err());
+    }
+
+    #[test]
+    fn test_payment_schedule_skips_small_balances_and_missed_periods() {
+        let mut schedule = PaymentSchedule {
+            user: Pubkey::new_unique(),
+            method: PaymentMethod::USDC,
+            destination: Pubkey::new_unique(),
+            frequency_secs: PaymentSchedule::MIN_FREQUENCY_SECS,
+            max_per_period: 500,
+            next_run_at: 1_000,
+            last_payment_id: None,
+            created_at: 0,
+            bump: 0,
+        };
+        let limits = (100, 400);
+        assert!(!schedule.is_due(999));
+        assert!(schedule.is_due(1_000));
+
+        // Capped by the schedule and the method maximum, skipped below the minimum
+        assert_eq!(schedule.payout_amount(1_000, limits), Some(400));
+        assert_eq!(schedule.payout_amount(250, limits), Some(250));
+        assert_eq!(schedule.payout_amount(99, limits), None);
+        assert_eq!(schedule.payout_amount(0, (0, 400)), None);
+
+        let day = PaymentSchedule::MIN_FREQUENCY_SECS;
+        schedule.advance(1_000);
+        assert_eq!(schedule.next_run_at, 1_000 + day);
+        schedule.advance(1_000 + 3 * day + 5);
+        assert_eq!(schedule.next_run_at, 1_000 + 4 * day);
+        schedule.advance(1_000 + 3 * day + 6);
+        assert_eq!(schedule.next_run_at, 1_000 + 4 * day);
+    }
+
+    #[test]
+    fn test_finalized_payments_move_to_a_bounded_history() {
+        let user = Pubkey::new_unique();
+        let mut history = UserPaymentHistory {
+            user: Pubkey::default(),
+            records: Vec::new(),
+            record_count: 0,
+            bump: 0,
+        };
+        history.initialize(user, 255);
+
+        let mut in_flight = payment_request(1, 100);
+        in_flight.user = user;
+        let mut other_user = payment_request(2, 100);
+        other_user.id = 2;
+        other_user.status = PaymentStatus::Completed;
+        let mut requests = vec![in_flight.clone(), other_user];
+        for id in 10..14 {
+            let mut request = payment_request(id, 100);
+            request.id = id;
+            request.user = user;
+            request.status = if id % 2 == 0 { PaymentStatus::Completed } else { PaymentStatus::Refunded };
+            requests.push(request);
+        }
+        let mut system = payment_system();
+        system.payment_requests = requests;
+
+        // In-flight requests and other users' requests stay queued
+        assert!(system.take_finalized(1).is_none());
+        let migrated = system.take_finalized_for(user);
+        assert_eq!(migrated.iter().map(|r| r.id).collect::<Vec<_>>(), vec![10, 11, 12, 13]);
+        assert_eq!(system.payment_requests.len(), 2);
+        assert!(system.take_finalized(2).is_some());
+        for request in &migrated {
+            history.record(request);
+        }
+        assert_eq!(history.find(11).unwrap().status, PaymentStatus::Refunded);
+
+        // Past capacity the oldest records are overwritten
+        let recorded = UserPaymentHistory::HISTORY_LEN as u64 + 4;
+        for id in 4..recorded {
+            let mut request = payment_request(id, 100);
+            request.id = 100 + id;
+            request.status = PaymentStatus::Failed;
+            history.record(&request);
+        }
+        assert_eq!(history.records.len(), UserPaymentHistory::HISTORY_LEN);
+        assert!(history.find(10).is_none());
+
+        let page = history.page(0);
+        assert_eq!(page.start, 4);
+        assert_eq!(page.records.len(), UserPaymentHistory::PAGE_LEN);
+        assert_eq!(page.records[0].id, 104);
+        assert_eq!(page.next_cursor, 4 + UserPaymentHistory::PAGE_LEN as u64);
+        let last = history.page(recorded - 1);
+        assert_eq!((last.records.len(), last.next_cursor, last.total), (1, recorded, recorded));
+    }
+
+    #[test]
+    fn test_payment_fee_rounds_down() {
+        assert_eq!(payment_fee(1_000_000, 25).unwrap(), 2_500);
+        assert_eq!(payment_fee(399, 25).unwrap(), 0);
+        assert_eq!(payment_fee(u64::MAX, 10_000).unwrap(), u64::MAX);
+        assert!(payment_fee(100, 10_001).is_err());
+    }
+
+    #[test]
+    fn test_quote_fee_is_honored_until_expiry() {
+        let mut system = payment_system();
+        system.usdc_config.fee_basis_points = 25;
+        system.usdc_config.max_payment_amount = 10_000_000;
+        system.lightning_config.fee_rate = 1_000;
+        system.lightning_config.max_payment_amount = 1_000_000;
+        let mut quote = PaymentQuote {
+            user: Pubkey::new_unique(),
+            quote_id: 0,
+            method: PaymentMethod::Lightning,
+            gross_amount: 0,
+            fee_basis_points: 0,
+            protocol_fee: 0,
+            network_fee: 0,
+            net_amount: 0,
+            valid_until: 0,
+            bump: 0,
+        };
+
+        // Lightning pays routing fees only, rounded up
+        quote.quote(&system, PaymentMethod::Lightning, 1_500, 1_000).unwrap();
+        assert_eq!((quote.protocol_fee, quote.network_fee, quote.net_amount), (0, 2, 1_498));
+
+        quote.quote(&system, PaymentMethod::USDC, 1_000_000, 1_000).unwrap();
+        assert_eq!(quote.quote_id, 2);
+        assert_eq!((quote.protocol_fee, quote.network_fee, quote.net_amount), (2_500, 0, 997_500));
+        assert!(quote.quote(&system, PaymentMethod::USDC, 20_000_000, 1_000).is_err());
+
+        // A fee raise after quoting does not apply until the quote expires
+        system.usdc_config.fee_basis_points = 100;
+        let valid_until = 1_000 + PaymentQuote::VALIDITY_SECONDS;
+        assert_eq!(quote.honored_fee_bps(2, &PaymentMethod::USDC, 1_000_000, valid_until).unwrap(), Some(25));
+        assert_eq!(quote.honored_fee_bps(2, &PaymentMethod::USDC, 1_000_000, valid_until + 1).unwrap(), None);
+        assert!(quote.honored_fee_bps(1, &PaymentMethod::USDC, 1_000_000, 1_000).is_err());
+        assert!(quote.honored_fee_bps(2, &PaymentMethod::USDC, 999_999, 1_000).is_err());
+        assert!(quote.honored_fee_bps(2, &PaymentMethod::Lightning, 1_000_000, 1_000).is_err());
+
+        let mut request = payment_request(1, 1_000_000);
+        request.quoted_fee_bps = Some(25);
+        assert_eq!(request.protocol_fee(system.usdc_config.fee_basis_points).unwrap(), 2_500);
+        request.quoted_fee_bps = None;
+        assert_eq!(request.protocol_fee(system.usdc_config.fee_basis_points).unwrap(), 10_000);
+    }
+
+    #[test]
+    fn test_crash_between_deduct_and_payment_creation() {
+        let mut rewards = user_rewards(1_000);
+        let mut system = payment_system();
+
+        // A failed deduction leaves no partial state behind
+        assert!(rewards.begin_claim(5_000).is_err());
+        assert_eq!(rewards.pending_rewards, 1_000);
+        assert_eq!(rewards.claim_nonce, 0);
+
+        // Deduction succeeds but creating the payment fails afterwards
+        let orphaned_claim = rewards.begin_claim(400).unwrap();
+        assert_eq!(rewards.pending_rewards, 600);
+        system.emergency_pause = true;
+        let created = system.create_payment_request(
+            rewards.user,
+            PaymentMethod::Lightning,
+            400,
+            "lnbc1".to_string(),
+            orphaned_claim,
+            RequestTerms::default(),
+        );
+        assert!(created.is_err());
+        assert!(system.payment_requests.is_empty());
+
+        // Restoring the claim brings the balance back exactly once: no payment
+        // carries the orphaned claim, so nothing can refund it a second time
+        rewards.refund_claim(400).unwrap();
+        assert_eq!(rewards.pending_rewards, 1_000);
+        assert!(system.refund_payment(1).is_err());
+
+        // Later claims never reuse an id, so a stale completion can't touch them
+        let next_claim = rewards.begin_claim(100).unwrap();
+        assert!(next_claim > orphaned_claim);
+        let mut payment = payment_request(next_claim, 100);
+        assert!(payment.record_completion(orphaned_claim, false, None, 10, 1).is_err());
+        assert_eq!(rewards.pending_rewards, 900);
+    }
+
+    #[test]
+    fn test_sent_spl_payout_cannot_be_reported_failed() {
+        let mut payment = payment_request(1, 500);
+        payment.method = PaymentMethod::SplToken { mint: Pubkey::new_unique() };
+        assert!(!payment.tokens_sent());
+        assert!(payment.holds_float());
+
+        payment.processed_at = Some(10);
+        assert!(payment.tokens_sent());
+        assert!(!payment.holds_float());
+        assert!(payment.record_completion(1, false, None, 20, PaymentSystem::MAX_RETRY_ATTEMPTS).is_err());
+        assert_eq!(payment.status, PaymentStatus::Processing);
+        assert_eq!(payment.retry_count, 0);
+
+        assert_eq!(payment.record_completion(1, true, None, 20, PaymentSystem::MAX_RETRY_ATTEMPTS).unwrap(),
+                   CompletionOutcome::Completed);
+    }
+}
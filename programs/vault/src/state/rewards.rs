@@ -1,4 +1,6 @@
 use anchor_lang::prelude::*;
+use crate::errors::VaultError;
+use crate::traits::PaymentType;
 
 /// Reward calculation and distribution state
 #[account]
@@ -9,9 +11,47 @@ pub struct RewardPool {
     pub user_share_bps: u16, // Basis points (10000 = 100%)
     pub protocol_share_bps: u16,
     pub last_distribution: i64,
+    pub current_epoch: u64,
+    pub epoch_history: Vec<EpochSummary>, // Ring buffer of the last finalized epochs
+    pub epoch_history_head: u8,           // Next slot to overwrite once the buffer is full
+    pub multisig_rate_control: bool,      // Once set, rate changes only via multisig
     pub bump: u8,
 }
 
+/// Summary of a finalized reward epoch, kept for APY charts
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct EpochSummary {
+    pub epoch: u64,
+    pub total_pot: u64,
+    pub total_commitments: u64,
+    pub reward_per_sat: u64, // Scaled by RewardPool::RATE_SCALE
+    pub user_share_bps: u16,
+    pub duration: i64,
+    pub finalized_at: i64,
+}
+
+/// Emitted when a reward epoch is finalized
+#[event]
+pub struct EpochFinalized {
+    pub epoch: u64,
+    pub total_pot: u64,
+    pub total_commitments: u64,
+    pub reward_per_sat: u64,
+    pub user_share_bps: u16,
+    pub duration: i64,
+}
+
+/// Emitted when the user reward share changes
+#[event]
+pub struct RewardRatesUpdated {
+    pub transaction_id: u32,
+    pub old_user_share_bps: u16,
+    pub new_user_share_bps: u16,
+    pub old_protocol_share_bps: u16,
+    pub new_protocol_share_bps: u16,
+    pub effective_at: i64,
+}
+
 /// Individual reward calculation
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
 pub struct RewardCalculation {
@@ -29,5 +69,470 @@ impl RewardPool {
         2 + // user_share_bps
         2 + // protocol_share_bps
         8 + // last_distribution
+        8 + // current_epoch
+        4 + (Self::MAX_EPOCH_HISTORY * EpochSummary::LEN) + // epoch_history
+        1 + // epoch_history_head
+        1 + // multisig_rate_control
         1; // bump
-}
\ No newline at end of file
+
+    pub const MAX_EPOCH_HISTORY: usize = 12;
+    /// Fixed-point scale for reward-per-sat rates
+    pub const RATE_SCALE: u128 = 1_000_000_000_000;
+    /// Bounds for multisig-approved user share changes
+    pub const MIN_USER_SHARE_BPS: u16 = 5000;
+    pub const MAX_USER_SHARE_BPS: u16 = 9500;
+
+    pub fn initialize(&mut self, user_share_bps: u16, timestamp: i64, bump: u8) -> Result<()> {
+        if user_share_bps > 10000 {
+            return Err(VaultError::InvalidAllocation.into());
+        }
+        self.total_rewards = 0;
+        self.distributed_rewards = 0;
+        self.user_share_bps = user_share_bps;
+        self.protocol_share_bps = 10000 - user_share_bps;
+        self.last_distribution = timestamp;
+        self.current_epoch = 0;
+        self.epoch_history = Vec::new();
+        self.epoch_history_head = 0;
+        self.multisig_rate_control = false;
+        self.bump = bump;
+        Ok(())
+    }
+
+    /// Close out the current epoch and record its summary in the ring buffer.
+    /// The rate is derived from the same `split_rewards` user share the pot
+    /// is paid out with.
+    pub fn finalize_epoch(
+        &mut self,
+        total_pot: u64,
+        total_commitments: u64,
+        timestamp: i64,
+    ) -> Result<EpochSummary> {
+        let epoch = self.current_epoch
+            .checked_add(1)
+            .ok_or(VaultError::ArithmeticOverflow)?;
+
+        let (user_pot, _) = self.split_rewards(total_pot)?;
+        let reward_per_sat = if total_commitments == 0 {
+            0
+        } else {
+            u64::try_from(user_pot as u128 * Self::RATE_SCALE / total_commitments as u128)
+                .map_err(|_| VaultError::ArithmeticOverflow)?
+        };
+
+        let summary = EpochSummary {
+            epoch,
+            total_pot,
+            total_commitments,
+            reward_per_sat,
+            user_share_bps: self.user_share_bps,
+            duration: timestamp.saturating_sub(self.last_distribution),
+            finalized_at: timestamp,
+        };
+
+        self.push_epoch_summary(summary.clone());
+        self.total_rewards = self.total_rewards
+            .checked_add(total_pot)
+            .ok_or(VaultError::ArithmeticOverflow)?;
+        self.current_epoch = epoch;
+        self.last_distribution = timestamp;
+
+        Ok(summary)
+    }
+
+    /// Split a reward pot by the current user share, returning
+    /// `(user_share, protocol_share)`. Rounding goes to the protocol.
+    pub fn split_rewards(&self, total: u64) -> Result<(u64, u64)> {
+        let user_share = u64::try_from(total as u128 * self.user_share_bps as u128 / 10000)
+            .map_err(|_| VaultError::ArithmeticOverflow)?;
+        Ok((user_share, total - user_share))
+    }
+
+    pub fn validate_user_share_bps(user_share_bps: u16) -> Result<()> {
+        if !(Self::MIN_USER_SHARE_BPS..=Self::MAX_USER_SHARE_BPS).contains(&user_share_bps) {
+            return Err(VaultError::InvalidAllocation.into());
+        }
+        Ok(())
+    }
+
+    /// Apply a new user share, returning the previous one
+    pub fn set_user_share_bps(&mut self, user_share_bps: u16) -> Result<u16> {
+        Self::validate_user_share_bps(user_share_bps)?;
+        let old = self.user_share_bps;
+        self.user_share_bps = user_share_bps;
+        self.protocol_share_bps = 10000 - user_share_bps;
+        Ok(old)
+    }
+
+    /// Bounded insert: the buffer never grows past MAX_EPOCH_HISTORY entries
+    fn push_epoch_summary(&mut self, summary: EpochSummary) {
+        if self.epoch_history.len() < Self::MAX_EPOCH_HISTORY {
+            self.epoch_history.push(summary);
+        } else {
+            let head = self.epoch_history_head as usize % Self::MAX_EPOCH_HISTORY;
+            self.epoch_history[head] = summary;
+            self.epoch_history_head = ((head + 1) % Self::MAX_EPOCH_HISTORY) as u8;
+        }
+    }
+}
+
+impl EpochSummary {
+    pub const LEN: usize = 8 + // epoch
+        8 + // total_pot
+        8 + // total_commitments
+        8 + // reward_per_sat
+        2 + // user_share_bps
+        8 + // duration
+        8; // finalized_at
+}
+
+/// Per-user reward balance awaiting payout or reinvestment
+#[account]
+#[derive(Debug)]
+pub struct UserRewards {
+    pub user: Pubkey,
+    pub pending_rewards: u64,
+    pub total_reinvested: u64,
+    pub last_claim_request: i64,
+    pub claim_nonce: u64, // Last claim id handed out; ids are never reused
+    pub bump: u8,
+}
+
+impl UserRewards {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // user
+        8 + // pending_rewards
+        8 + // total_reinvested
+        8 + // last_claim_request
+        8 + // claim_nonce
+        1; // bump
+
+    /// Deduct a claim from pending rewards and return its claim id
+    pub fn begin_claim(&mut self, amount: u64) -> Result<u64> {
+        if self.pending_rewards < amount {
+            return Err(VaultError::InsufficientRewards.into());
+        }
+        let claim_id = self.claim_nonce
+            .checked_add(1)
+            .ok_or(VaultError::ArithmeticOverflow)?;
+        self.pending_rewards -= amount;
+        self.claim_nonce = claim_id;
+        Ok(claim_id)
+    }
+
+    /// Restore a claim whose payment failed permanently
+    pub fn refund_claim(&mut self, amount: u64) -> Result<()> {
+        self.pending_rewards = self.pending_rewards
+            .checked_add(amount)
+            .ok_or(VaultError::ArithmeticOverflow)?;
+        Ok(())
+    }
+
+    /// Move pending rewards out without a claim, e.g. to fund a stream
+    pub fn debit(&mut self, amount: u64) -> Result<()> {
+        if self.pending_rewards < amount {
+            return Err(VaultError::InsufficientRewards.into());
+        }
+        self.pending_rewards -= amount;
+        Ok(())
+    }
+
+    /// Add claimable rewards, e.g. a stream withdrawal
+    pub fn credit(&mut self, amount: u64) -> Result<()> {
+        self.pending_rewards = self.pending_rewards
+            .checked_add(amount)
+            .ok_or(VaultError::ArithmeticOverflow)?;
+        Ok(())
+    }
+}
+
+/// Leaderboard entry ranked by lifetime rewards
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct LeaderboardEntry {
+    pub user: Pubkey,
+    pub lifetime_rewards: u64,
+}
+
+/// Protocol-wide reward statistics for dashboards
+#[account]
+#[derive(Debug)]
+pub struct RewardStats {
+    pub lifetime_distributed: u64,
+    pub lifetime_claimed: u64,
+    pub btc_payout_volume: u64,
+    pub usdc_payout_volume: u64,
+    pub reinvest_volume: u64,
+    pub current_epoch: u64,
+    pub epoch_claimers: u32,
+    pub largest_single_claim: u64,
+    pub largest_claim_user: Pubkey,
+    pub leaderboard: Vec<LeaderboardEntry>, // Top 10 by lifetime rewards, descending
+    pub last_updated: i64,
+    pub bump: u8,
+}
+
+impl RewardStats {
+    pub const MAX_LEADERBOARD_ENTRIES: usize = 10;
+
+    pub const LEN: usize = 8 + // discriminator
+        8 + // lifetime_distributed
+        8 + // lifetime_claimed
+        8 + // btc_payout_volume
+        8 + // usdc_payout_volume
+        8 + // reinvest_volume
+        8 + // current_epoch
+        4 + // epoch_claimers
+        8 + // largest_single_claim
+        32 + // largest_claim_user
+        4 + (Self::MAX_LEADERBOARD_ENTRIES * (32 + 8)) + // leaderboard
+        8 + // last_updated
+        1; // bump
+
+    pub fn initialize(&mut self, epoch: u64, bump: u8) -> Result<()> {
+        self.lifetime_distributed = 0;
+        self.lifetime_claimed = 0;
+        self.btc_payout_volume = 0;
+        self.usdc_payout_volume = 0;
+        self.reinvest_volume = 0;
+        self.current_epoch = epoch;
+        self.epoch_claimers = 0;
+        self.largest_single_claim = 0;
+        self.largest_claim_user = Pubkey::default();
+        self.leaderboard = Vec::new();
+        self.last_updated = 0;
+        self.bump = bump;
+        Ok(())
+    }
+
+    /// Record a distribution and refresh the user's leaderboard position
+    pub fn record_distribution(
+        &mut self,
+        user: Pubkey,
+        amount: u64,
+        user_lifetime_rewards: u64,
+        timestamp: i64,
+    ) -> Result<()> {
+        self.lifetime_distributed = self.lifetime_distributed
+            .checked_add(amount)
+            .ok_or(VaultError::ArithmeticOverflow)?;
+        self.update_leaderboard(user, user_lifetime_rewards);
+        self.last_updated = timestamp;
+        Ok(())
+    }
+
+    /// Record a claim; `first_claim_this_epoch` is tracked on the user account
+    pub fn record_claim(
+        &mut self,
+        user: Pubkey,
+        amount: u64,
+        payment_type: PaymentType,
+        epoch: u64,
+        first_claim_this_epoch: bool,
+        timestamp: i64,
+    ) -> Result<()> {
+        if epoch != self.current_epoch {
+            self.current_epoch = epoch;
+            self.epoch_claimers = 0;
+        }
+        if first_claim_this_epoch {
+            self.epoch_claimers = self.epoch_claimers.saturating_add(1);
+        }
+
+        let volume = match payment_type {
+            PaymentType::BTC => &mut self.btc_payout_volume,
+            PaymentType::USDC => &mut self.usdc_payout_volume,
+            PaymentType::AutoReinvest => &mut self.reinvest_volume,
+        };
+        *volume = volume.checked_add(amount).ok_or(VaultError::ArithmeticOverflow)?;
+
+        self.lifetime_claimed = self.lifetime_claimed
+            .checked_add(amount)
+            .ok_or(VaultError::ArithmeticOverflow)?;
+
+        if amount > self.largest_single_claim {
+            self.largest_single_claim = amount;
+            self.largest_claim_user = user;
+        }
+
+        self.last_updated = timestamp;
+        Ok(())
+    }
+
+    /// Bounded top-N update: at most MAX_LEADERBOARD_ENTRIES entries are touched
+    pub fn update_leaderboard(&mut self, user: Pubkey, lifetime_rewards: u64) {
+        if let Some(entry) = self.leaderboard.iter_mut().find(|e| e.user == user) {
+            entry.lifetime_rewards = lifetime_rewards;
+        } else if self.leaderboard.len() < Self::MAX_LEADERBOARD_ENTRIES {
+            self.leaderboard.push(LeaderboardEntry { user, lifetime_rewards });
+        } else {
+            // Leaderboard is kept sorted, so the last entry is the minimum
+            match self.leaderboard.last_mut() {
+                Some(last) if lifetime_rewards > last.lifetime_rewards => {
+                    *last = LeaderboardEntry { user, lifetime_rewards };
+                }
+                _ => return,
+            }
+        }
+
+        self.leaderboard.sort_by(|a, b| b.lifetime_rewards.cmp(&a.lifetime_rewards));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn empty_pool() -> RewardPool {
+        RewardPool {
+            total_rewards: 0,
+            distributed_rewards: 0,
+            user_share_bps: 5000,
+            protocol_share_bps: 5000,
+            last_distribution: 0,
+            current_epoch: 0,
+            epoch_history: Vec::new(),
+            epoch_history_head: 0,
+            multisig_rate_control: false,
+            bump: 0,
+        }
+    }
+
+    #[test]
+    fn test_epoch_history_ring_buffer_wraps() {
+        let mut pool = empty_pool();
+
+        for i in 0..(RewardPool::MAX_EPOCH_HISTORY as i64 + 3) {
+            pool.finalize_epoch(1_000_000, 100_000_000, (i + 1) * 86400).unwrap();
+        }
+
+        assert_eq!(pool.current_epoch, 15);
+        assert_eq!(pool.epoch_history.len(), RewardPool::MAX_EPOCH_HISTORY);
+        assert_eq!(pool.epoch_history_head, 3);
+
+        // Oldest three slots were overwritten by epochs 13-15
+        let mut epochs: Vec<u64> = pool.epoch_history.iter().map(|e| e.epoch).collect();
+        epochs.sort();
+        assert_eq!(epochs, (4..=15).collect::<Vec<u64>>());
+    }
+
+    #[test]
+    fn test_epoch_reward_per_sat() {
+        let mut pool = empty_pool();
+        let summary = pool.finalize_epoch(2_000_000, 100_000_000, 604800).unwrap();
+
+        // 50% of 2_000_000 spread over 1 BTC
+        assert_eq!(summary.reward_per_sat, 10_000_000_000);
+        assert_eq!(summary.duration, 604800);
+        assert_eq!(summary.user_share_bps, 5000);
+
+        let empty = pool.finalize_epoch(2_000_000, 0, 604900).unwrap();
+        assert_eq!(empty.reward_per_sat, 0);
+        assert_eq!(empty.duration, 100);
+    }
+
+    #[test]
+    fn test_user_share_bounds() {
+        let mut pool = empty_pool();
+        assert!(pool.set_user_share_bps(4999).is_err());
+        assert!(pool.set_user_share_bps(9501).is_err());
+
+        assert_eq!(pool.set_user_share_bps(7000).unwrap(), 5000);
+        assert_eq!(pool.protocol_share_bps, 3000);
+    }
+
+    #[test]
+    fn test_rewards_split_by_user_share() {
+        let mut pool = empty_pool();
+        assert_eq!(pool.split_rewards(1_000_001).unwrap(), (500_000, 500_001));
+
+        pool.set_user_share_bps(7000).unwrap();
+        assert_eq!(pool.split_rewards(1_000_000).unwrap(), (700_000, 300_000));
+        assert_eq!(pool.split_rewards(0).unwrap(), (0, 0));
+    }
+
+    #[test]
+    fn test_epoch_summary_matches_paid_user_share() {
+        let mut pool = empty_pool();
+        pool.set_user_share_bps(7000).unwrap();
+        let (user_share, _) = pool.split_rewards(1_000_001).unwrap();
+        let summary = pool.finalize_epoch(1_000_001, 100_000_000, 604800).unwrap();
+
+        assert_eq!(summary.user_share_bps, 7000);
+        assert_eq!(summary.reward_per_sat as u128 * 100_000_000 / RewardPool::RATE_SCALE, user_share as u128);
+    }
+
+    #[test]
+    fn test_full_reward_pool_fits_allocated_space() {
+        let mut pool = empty_pool();
+        for i in 0..RewardPool::MAX_EPOCH_HISTORY as i64 {
+            pool.finalize_epoch(1_000_000, 100_000_000, i).unwrap();
+        }
+
+        let serialized = pool.try_to_vec().unwrap();
+        assert_eq!(serialized.len() + 8, RewardPool::LEN);
+
+        let summary = pool.epoch_history[0].try_to_vec().unwrap();
+        assert_eq!(summary.len(), EpochSummary::LEN);
+    }
+
+    fn empty_stats() -> RewardStats {
+        RewardStats {
+            lifetime_distributed: 0,
+            lifetime_claimed: 0,
+            btc_payout_volume: 0,
+            usdc_payout_volume: 0,
+            reinvest_volume: 0,
+            current_epoch: 0,
+            epoch_claimers: 0,
+            largest_single_claim: 0,
+            largest_claim_user: Pubkey::default(),
+            leaderboard: Vec::new(),
+            last_updated: 0,
+            bump: 0,
+        }
+    }
+
+    #[test]
+    fn test_leaderboard_bounded_and_sorted() {
+        let mut stats = empty_stats();
+        let users: Vec<Pubkey> = (0..12).map(|_| Pubkey::new_unique()).collect();
+
+        for (i, user) in users.iter().enumerate() {
+            stats.update_leaderboard(*user, (i as u64 + 1) * 100);
+        }
+
+        assert_eq!(stats.leaderboard.len(), RewardStats::MAX_LEADERBOARD_ENTRIES);
+        assert_eq!(stats.leaderboard[0].user, users[11]);
+        assert_eq!(stats.leaderboard[9].lifetime_rewards, 300);
+        assert!(!stats.leaderboard.iter().any(|e| e.user == users[0]));
+
+        // Existing entry moves up instead of being duplicated
+        stats.update_leaderboard(users[2], 5_000);
+        assert_eq!(stats.leaderboard.len(), RewardStats::MAX_LEADERBOARD_ENTRIES);
+        assert_eq!(stats.leaderboard[0].user, users[2]);
+    }
+
+    #[test]
+    fn test_record_claim_tracks_epoch_and_largest() {
+        let mut stats = empty_stats();
+        let user_a = Pubkey::new_unique();
+        let user_b = Pubkey::new_unique();
+
+        stats.record_claim(user_a, 500, PaymentType::BTC, 1, true, 10).unwrap();
+        stats.record_claim(user_b, 900, PaymentType::USDC, 1, true, 20).unwrap();
+        stats.record_claim(user_a, 100, PaymentType::BTC, 1, false, 30).unwrap();
+
+        assert_eq!(stats.epoch_claimers, 2);
+        assert_eq!(stats.btc_payout_volume, 600);
+        assert_eq!(stats.usdc_payout_volume, 900);
+        assert_eq!(stats.lifetime_claimed, 1_500);
+        assert_eq!(stats.largest_single_claim, 900);
+        assert_eq!(stats.largest_claim_user, user_b);
+
+        // New epoch resets the claimer counter
+        stats.record_claim(user_a, 50, PaymentType::AutoReinvest, 2, true, 40).unwrap();
+        assert_eq!(stats.current_epoch, 2);
+        assert_eq!(stats.epoch_claimers, 1);
+        assert_eq!(stats.reinvest_volume, 50);
+    }
+}
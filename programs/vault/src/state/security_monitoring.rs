@@ -1,5 +1,6 @@
 use anchor_lang::prelude::*;
 use std::collections::HashMap;
+use crate::errors::VaultError;
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
 pub enum SecurityEventType {
@@ -55,6 +56,9 @@ pub enum SecurityEventType {
     VelocityAlert,
     DeviceChange,
     IPChange,
+    
+    // Multisig review events
+    SignerFlaggedForReview,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Copy)]
@@ -65,12 +69,53 @@ pub enum SecurityLevel {
     Critical,
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+impl SecurityLevel {
+    /// Seconds an alert of this severity has to reach Resolved or Dismissed
+    pub fn sla_seconds(&self) -> i64 {
+        match self {
+            SecurityLevel::Critical => 3600,
+            SecurityLevel::High => 4 * 3600,
+            SecurityLevel::Medium => 24 * 3600,
+            SecurityLevel::Low => 72 * 3600,
+        }
+    }
+}
+
+/// Case state of an alert. Cases only move forward; Resolved and Dismissed
+/// are final.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
 pub enum AlertStatus {
-    Active,
-    Investigating,
+    Open,
+    Acknowledged,
+    InvestigationInProgress,
+    Escalated,
     Resolved,
-    FalsePositive,
+    Dismissed, // False positive
+}
+
+impl AlertStatus {
+    fn rank(&self) -> u8 {
+        match self {
+            AlertStatus::Open => 0,
+            AlertStatus::Acknowledged => 1,
+            AlertStatus::InvestigationInProgress => 2,
+            AlertStatus::Escalated => 3,
+            AlertStatus::Resolved | AlertStatus::Dismissed => 4,
+        }
+    }
+
+    pub fn is_closed(&self) -> bool {
+        matches!(self, AlertStatus::Resolved | AlertStatus::Dismissed)
+    }
+
+    /// Whether a case may move from this state to `next`. Steps may be
+    /// skipped, but an alert must be acknowledged before it's closed.
+    pub fn can_transition_to(&self, next: AlertStatus) -> bool {
+        if next.is_closed() && *self == AlertStatus::Open {
+            return false;
+        }
+        next.rank() > self.rank()
+    }
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
@@ -139,6 +184,7 @@ pub struct SecurityAlert {
     pub auto_resolved: bool,
     pub resolution_time: Option<i64>,
     pub false_positive: bool,
+    pub sla_deadline: i64, // Must be closed by then or it's escalated
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
@@ -200,6 +246,7 @@ pub struct SecurityMonitor {
     pub emergency_contacts: Vec<Pubkey>,
     pub created_at: i64,
     pub last_maintenance: i64,
+    pub compliance_authority: Pubkey, // Takes over alerts that miss their SLA
 }
 
 #[account]
@@ -416,6 +463,8 @@ impl UserBehaviorProfile {
 }
 
 impl SecurityAlert {
+    pub const MAX_NOTE_LENGTH: usize = 200;
+
     pub fn new(
         alert_id: u64,
         alert_type: SecurityEventType,
@@ -430,7 +479,8 @@ impl SecurityAlert {
             user,
             created_at: now,
             updated_at: now,
-            status: AlertStatus::Active,
+            status: AlertStatus::Open,
+            sla_deadline: now.saturating_add(security_level.sla_seconds()),
             security_level,
             description,
             related_events: Vec::new(),
@@ -454,18 +504,117 @@ impl SecurityAlert {
         self.updated_at = Clock::get().unwrap().unix_timestamp;
     }
 
-    pub fn resolve(&mut self, false_positive: bool) {
-        self.status = if false_positive { AlertStatus::FalsePositive } else { AlertStatus::Resolved };
-        self.false_positive = false_positive;
-        self.resolution_time = Some(Clock::get().unwrap().unix_timestamp);
-        self.updated_at = Clock::get().unwrap().unix_timestamp;
+    /// Move the case to `next`, recording who moved it and why in the
+    /// investigation notes
+    pub fn transition(&mut self, next: AlertStatus, actor: Pubkey, notes: &str, now: i64) -> Result<()> {
+        if notes.len() > Self::MAX_NOTE_LENGTH {
+            return Err(VaultError::ReasonTooLong.into());
+        }
+        if !self.status.can_transition_to(next) {
+            return Err(VaultError::InvalidAlertTransition.into());
+        }
+        self.investigation_notes.push(format!("{} {}: {:?} -> {:?}: {}", now, actor, self.status, next, notes));
+        self.status = next;
+        if next.is_closed() {
+            self.false_positive = next == AlertStatus::Dismissed;
+            self.resolution_time = Some(now);
+        }
+        self.updated_at = now;
+        Ok(())
     }
 
     pub fn assign_to(&mut self, officer: Pubkey) {
         self.assigned_to = Some(officer);
-        self.status = AlertStatus::Investigating;
         self.updated_at = Clock::get().unwrap().unix_timestamp;
     }
+
+    /// Escalate to `compliance_authority` if the case is still open past
+    /// its SLA. Returns whether it was escalated.
+    pub fn escalate_if_breached(&mut self, compliance_authority: Pubkey, now: i64) -> bool {
+        if now <= self.sla_deadline || !self.status.can_transition_to(AlertStatus::Escalated) {
+            return false;
+        }
+        self.investigation_notes.push(format!(
+            "{} SLA breached: {:?} -> Escalated, reassigned to compliance authority {}",
+            now, self.status, compliance_authority
+        ));
+        self.status = AlertStatus::Escalated;
+        self.assigned_to = Some(compliance_authority);
+        self.updated_at = now;
+        true
+    }
+}
+
+impl SecurityAlertStore {
+    /// Most alerts one `batch_resolve_alerts` call may close
+    pub const MAX_BATCH_ALERTS: usize = 20;
+
+    /// Move alert `alert_id` to `next` and keep the store's counts in step.
+    /// Returns the state it moved from.
+    pub fn transition_alert(
+        &mut self,
+        alert_id: u64,
+        next: AlertStatus,
+        actor: Pubkey,
+        notes: &str,
+        now: i64,
+    ) -> Result<AlertStatus> {
+        let alert = self.alerts.iter_mut()
+            .find(|a| a.alert_id == alert_id)
+            .ok_or(VaultError::AlertNotFound)?;
+        let previous = alert.status;
+        alert.transition(next, actor, notes, now)?;
+
+        if next.is_closed() {
+            self.active_count = self.active_count.saturating_sub(1);
+            if next == AlertStatus::Resolved {
+                self.resolved_count += 1;
+            }
+        }
+        self.last_updated = now;
+        Ok(previous)
+    }
+
+    /// Escalate every open case past its SLA, emitting `AlertEscalated` for
+    /// each. Runs whenever an alert in the store is touched.
+    pub fn escalate_breached(&mut self, compliance_authority: Pubkey, now: i64) {
+        for alert in self.alerts.iter_mut() {
+            let previous_status = alert.status;
+            if alert.escalate_if_breached(compliance_authority, now) {
+                emit!(AlertEscalated {
+                    alert_id: alert.alert_id,
+                    previous_status,
+                    assigned_to: compliance_authority,
+                    sla_deadline: alert.sla_deadline,
+                    timestamp: now,
+                });
+                self.last_updated = now;
+            }
+        }
+    }
+}
+
+/// Emitted by `batch_resolve_alerts`; `succeeded[i]` says whether
+/// `alert_ids[i]` was closed. Each closed alert also notes it in its own
+/// investigation notes.
+#[event]
+pub struct AlertsBatchResolved {
+    pub alert_ids: Vec<u64>,
+    pub succeeded: Vec<bool>,
+    pub new_status: AlertStatus,
+    pub resolved_by: Pubkey,
+    pub timestamp: i64,
+}
+
+/// Emitted when an alert misses its SLA and is handed to the compliance
+/// authority
+#[event]
+pub struct AlertEscalated {
+    pub alert_id: u64,
+    pub previous_status: AlertStatus,
+    pub assigned_to: Pubkey,
+    pub sla_deadline: i64,
+    pub timestamp: i64,
 }
 
 impl AuditTrail {
@@ -528,3 +677,105 @@ impl AuditTrail {
         self
     }
 }
+
+impl SecurityEventLog {
+    /// Append an event, dropping the oldest once the log is full
+    pub fn record(&mut self, event: SecurityEvent, now: i64) {
+        if self.events.len() >= self.max_size as usize {
+            self.events.remove(0);
+        }
+        self.events.push(event);
+        self.last_updated = now;
+    }
+}
+
+impl AuditTrailStore {
+    /// Store a trail, also keeping compliance-relevant trails separately
+    pub fn record(&mut self, trail: AuditTrail) {
+        if trail.compliance_relevant {
+            self.compliance_trails.push(trail.clone());
+        }
+        self.trails.push(trail);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alert(security_level: SecurityLevel) -> SecurityAlert {
+        SecurityAlert {
+            alert_id: 1,
+            alert_type: SecurityEventType::ComplianceAlert,
+            user: None,
+            created_at: 0,
+            updated_at: 0,
+            status: AlertStatus::Open,
+            security_level,
+            description: String::new(),
+            related_events: Vec::new(),
+            investigation_notes: Vec::new(),
+            assigned_to: None,
+            auto_resolved: false,
+            resolution_time: None,
+            false_positive: false,
+            sla_deadline: security_level.sla_seconds(),
+        }
+    }
+
+    #[test]
+    fn test_alert_cases_only_move_forward() {
+        let officer = Pubkey::new_unique();
+        let mut alert = alert(SecurityLevel::Medium);
+
+        assert!(alert.transition(AlertStatus::Resolved, officer, "done", 10).is_err());
+        alert.transition(AlertStatus::Acknowledged, officer, "seen", 10).unwrap();
+        alert.transition(AlertStatus::InvestigationInProgress, officer, "checking", 20).unwrap();
+        assert!(alert.transition(AlertStatus::Acknowledged, officer, "back", 30).is_err());
+        assert!(alert.transition(AlertStatus::Dismissed, officer, &"x".repeat(201), 30).is_err());
+
+        alert.transition(AlertStatus::Dismissed, officer, "false positive", 30).unwrap();
+        assert!(alert.false_positive);
+        assert_eq!(alert.resolution_time, Some(30));
+        assert!(alert.transition(AlertStatus::Resolved, officer, "again", 40).is_err());
+        assert_eq!(alert.investigation_notes.len(), 3);
+    }
+
+    #[test]
+    fn test_store_transitions_keep_counts_in_step() {
+        let officer = Pubkey::new_unique();
+        let mut acknowledged = alert(SecurityLevel::Low);
+        acknowledged.status = AlertStatus::Acknowledged;
+        let mut store = SecurityAlertStore {
+            monitor: Pubkey::default(),
+            alerts: vec![alert(SecurityLevel::Low), SecurityAlert { alert_id: 2, ..acknowledged }],
+            active_count: 2,
+            resolved_count: 0,
+            created_at: 0,
+            last_updated: 0,
+        };
+
+        assert!(store.transition_alert(1, AlertStatus::Resolved, officer, "done", 10).is_err());
+        assert!(store.transition_alert(3, AlertStatus::Resolved, officer, "done", 10).is_err());
+        assert_eq!(store.transition_alert(2, AlertStatus::Resolved, officer, "done", 10).unwrap(), AlertStatus::Acknowledged);
+        assert_eq!((store.active_count, store.resolved_count, store.last_updated), (1, 1, 10));
+    }
+
+    #[test]
+    fn test_alerts_past_their_sla_escalate_to_the_compliance_authority() {
+        let compliance_authority = Pubkey::new_unique();
+        let mut open = alert(SecurityLevel::Critical);
+
+        assert!(!open.escalate_if_breached(compliance_authority, 3600));
+        assert!(open.escalate_if_breached(compliance_authority, 3601));
+        assert_eq!(open.status, AlertStatus::Escalated);
+        assert_eq!(open.assigned_to, Some(compliance_authority));
+        assert_eq!(open.investigation_notes.len(), 1);
+        assert!(!open.escalate_if_breached(compliance_authority, 7200));
+
+        // Closed cases are left alone
+        let mut closed = alert(SecurityLevel::Critical);
+        closed.status = AlertStatus::Resolved;
+        assert!(!closed.escalate_if_breached(compliance_authority, 7200));
+    }
+}
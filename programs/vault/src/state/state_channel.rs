@@ -6,6 +6,7 @@ use crate::errors::VaultError;
 pub struct StateChannel {
     pub channel_id: [u8; 32],
     pub participants: Vec<Pubkey>,
+    pub pending_balances: Vec<u64>, // Unsettled reward per participant, index-aligned
     pub state_hash: [u8; 32],
     pub nonce: u64,
     pub timeout: i64,
@@ -14,7 +15,147 @@ pub struct StateChannel {
     pub last_update: i64,
     pub dispute_period: i64,
     pub settlement_amount: u64,
+    pub checkpoints: Vec<StateCheckpoint>, // Last 8 mutually signed checkpoints, oldest first
+    pub last_submitter: Pubkey, // Participant who submitted the current on-chain state
+    pub is_settled: bool,
+    pub dispute: Option<ChannelDispute>, // Open challenge, if any
+    pub challenge_extension: i64, // Seconds a late response adds to the dispute window
+    pub fee_bps: u16, // Treasury channel fee at open time
+    pub purpose: ChannelPurpose, // Fixed at open; checked against the settler's compliance region
+    pub settlement: Option<BatchSettlement>, // Committed final state once settlement begins
     pub bump: u8,
+    pub collateralized: bool, // Set on first collateral deposit; settlement then pays from the vaults
+    pub max_participants: u8, // Participant bound fixed at open, at most MAX_PARTICIPANTS
+}
+
+/// Final reward calculations committed as a Merkle root and applied in batches
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct BatchSettlement {
+    pub root: [u8; 32],
+    pub leaf_count: u32,
+    pub applied_count: u32,
+    pub applied_leaves: Vec<u8>, // Bitmap, one bit per leaf index
+    pub from_collateral: bool,
+}
+
+/// One reward calculation from a committed settlement, addressed by leaf index
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct SettlementLeaf {
+    pub index: u32,
+    pub calculation: RewardCalculation,
+}
+
+/// What a state channel is used for, declared at open for compliance checks
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum ChannelPurpose {
+    RewardSettlement,
+    Payments,
+    Trading,
+    HighFrequencyTrading,
+}
+
+/// Side expected to answer next in an open dispute
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub enum DisputeTurn {
+    Respondent,
+    Challenger,
+}
+
+/// Open challenge on a state channel
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct ChannelDispute {
+    pub challenger: Pubkey,
+    pub turn: DisputeTurn,
+    pub deadline: i64,
+    pub extensions: u8,
+    pub last_evidence_hash: [u8; 32],
+}
+
+/// Collateral a participant has locked into a state channel, held as
+/// lamports on this PDA on top of its rent-exempt reserve
+#[account]
+pub struct ChannelCollateral {
+    pub channel_id: [u8; 32],
+    pub participant: Pubkey,
+    pub amount: u64,
+    pub withdrawal_nonce: u64, // Bumped per withdrawal so approvals can't be replayed
+    pub bump: u8,
+}
+
+impl ChannelCollateral {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // channel_id
+        32 + // participant
+        8 + // amount
+        8 + // withdrawal_nonce
+        1; // bump
+
+    pub fn deposit(&mut self, amount: u64) -> Result<()> {
+        if amount == 0 {
+            return Err(VaultError::InvalidDepositAmount.into());
+        }
+        self.amount = self.amount
+            .checked_add(amount)
+            .ok_or(VaultError::ArithmeticOverflow)?;
+        Ok(())
+    }
+
+    pub fn withdraw(&mut self, amount: u64) -> Result<()> {
+        if amount == 0 || amount > self.amount {
+            return Err(VaultError::InsufficientBalance.into());
+        }
+        self.amount -= amount;
+        self.withdrawal_nonce = self.withdrawal_nonce
+            .checked_add(1)
+            .ok_or(VaultError::ArithmeticOverflow)?;
+        Ok(())
+    }
+}
+
+/// Emitted when a state channel settles
+#[event]
+pub struct ChannelSettled {
+    pub channel_id: [u8; 32],
+    pub total_rewards: u64,
+    pub fee_bps: u16,
+    pub total_fees: u64,
+    pub cooperative: bool,
+}
+
+/// Emitted when settlement entitlements exceed the channel's collateral
+#[event]
+pub struct ChannelUndercollateralized {
+    pub channel_id: [u8; 32],
+    pub total_collateral: u64,
+    pub total_entitlements: u64,
+    pub shortfall: u64,
+}
+
+/// Newer mutually signed state offered as conclusive dispute evidence
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct SignedStateProof {
+    pub update: StateChannelUpdate,
+    pub signatures: Vec<Vec<u8>>,
+}
+
+/// Emitted when a challenge replaces a stale on-chain state with a newer one
+#[event]
+pub struct StaleStateOverridden {
+    pub channel_id: [u8; 32],
+    pub stale_version: u64,
+    pub new_version: u64,
+    pub stale_submitter: Pubkey,
+    pub challenger: Pubkey,
+    pub penalty: u64,
+}
+
+/// Mutually signed snapshot of channel state, chained to the previous one
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct StateCheckpoint {
+    pub state_hash: [u8; 32],
+    pub version: u64,
+    pub chain_hash: [u8; 32], // hash(previous chain_hash || state_hash || version)
+    pub recorded_at: i64,
 }
 
 /// State channel update for reward calculations
@@ -42,6 +183,7 @@ pub struct DisputeData {
     pub challenger: Pubkey,
     pub disputed_state_hash: [u8; 32],
     pub evidence: Vec<u8>,
+    pub disputed_version: u64,
     pub challenge_timestamp: i64,
 }
 
@@ -49,6 +191,7 @@ impl StateChannel {
     pub const LEN: usize = 8 + // discriminator
         32 + // channel_id
         4 + 32 * 10 + // participants (max 10)
+        4 + 8 * 10 + // pending_balances (max 10)
         32 + // state_hash
         8 + // nonce
         8 + // timeout
@@ -57,7 +200,27 @@ impl StateChannel {
         8 + // last_update
         8 + // dispute_period
         8 + // settlement_amount
-        1; // bump
+        4 + (32 + 8 + 32 + 8) * 8 + // checkpoints (max 8)
+        32 + // last_submitter
+        1 + // is_settled
+        1 + (32 + 1 + 8 + 1 + 32) + // dispute
+        8 + // challenge_extension
+        2 + // fee_bps
+        1 + // purpose
+        1 + (32 + 4 + 4 + 4 + 128 + 1) + // settlement (bitmap for max 1024 leaves)
+        1 + // bump
+        1 + // collateralized
+        1; // max_participants
+
+    pub const MAX_PARTICIPANTS: usize = 10; // Account space is sized for this many
+    pub const MAX_CHECKPOINTS: usize = 8;
+    pub const FORCE_SETTLE_GRACE_PERIOD: i64 = 86400; // 24 hours past timeout
+    pub const FORCE_SETTLE_CRANK_FEE: u64 = 5_000_000; // Lamports paid to whoever cranks an expired channel
+    pub const STALE_STATE_PENALTY_BPS: u64 = 1000; // 10% of the stale submitter's balance
+    pub const DEFAULT_CHALLENGE_EXTENSION: i64 = 21600; // 6 hours
+    pub const MAX_CHALLENGE_EXTENSIONS: u8 = 2;
+    pub const COOPERATIVE_FEE_DISCOUNT_BPS: u16 = 5000; // Undisputed closes pay half
+    pub const MAX_SETTLEMENT_LEAVES: u32 = 1024;
 
     /// Initialize a new state channel
     pub fn initialize(
@@ -65,15 +228,26 @@ impl StateChannel {
         channel_id: [u8; 32],
         participants: Vec<Pubkey>,
         timeout_seconds: i64,
+        challenge_extension: Option<i64>,
+        max_participants: Option<u8>,
+        fee_bps: u16,
+        purpose: ChannelPurpose,
         bump: u8,
     ) -> Result<()> {
-        if participants.len() > 10 {
+        let max_participants = max_participants.unwrap_or(Self::MAX_PARTICIPANTS as u8);
+        if max_participants as usize > Self::MAX_PARTICIPANTS || participants.len() > max_participants as usize {
             return Err(VaultError::InvalidAllocation.into());
         }
 
+        let challenge_extension = challenge_extension.unwrap_or(Self::DEFAULT_CHALLENGE_EXTENSION);
+        if challenge_extension <= 0 {
+            return Err(VaultError::InvalidTimeWindow.into());
+        }
+
         let clock = Clock::get()?;
         
         self.channel_id = channel_id;
+        self.pending_balances = vec![0; participants.len()];
         self.participants = participants;
         self.state_hash = [0; 32]; // Initial empty state
         self.nonce = 0;
@@ -83,7 +257,17 @@ impl StateChannel {
         self.last_update = clock.unix_timestamp;
         self.dispute_period = 86400; // 24 hours in seconds
         self.settlement_amount = 0;
+        self.checkpoints = Vec::new();
+        self.last_submitter = Pubkey::default();
+        self.is_settled = false;
+        self.dispute = None;
+        self.challenge_extension = challenge_extension;
+        self.fee_bps = fee_bps;
+        self.purpose = purpose;
+        self.settlement = None;
         self.bump = bump;
+        self.collateralized = false;
+        self.max_participants = max_participants;
 
         Ok(())
     }
@@ -93,14 +277,19 @@ impl StateChannel {
         &mut self,
         update: StateChannelUpdate,
         signatures: Vec<Vec<u8>>,
+        submitter: Pubkey,
     ) -> Result<()> {
         // Validate channel is active
         if !self.is_active {
             return Err(VaultError::SecurityViolation.into());
         }
 
-        // Validate nonce progression
-        if update.nonce != self.nonce + 1 {
+        // The nonce is the channel version: each update must be the next one,
+        // so an older update can never be replayed over a newer state
+        let next_version = self.nonce
+            .checked_add(1)
+            .ok_or(VaultError::ArithmeticOverflow)?;
+        if update.nonce != next_version {
             return Err(VaultError::SecurityViolation.into());
         }
 
@@ -109,16 +298,15 @@ impl StateChannel {
             return Err(VaultError::SecurityViolation.into());
         }
 
-        // Validate sufficient signatures (require majority)
-        let required_signatures = (self.participants.len() / 2) + 1;
-        if signatures.len() < required_signatures {
-            return Err(VaultError::MultisigThresholdNotMet.into());
-        }
+        Self::verify_update_hash(&update)?;
+
+        self.apply_reward_calculations(&update.reward_calculations);
 
         // Update state
         self.state_hash = update.new_state_hash;
         self.nonce = update.nonce;
         self.signatures = signatures;
+        self.last_submitter = submitter;
         self.last_update = Clock::get()?.unix_timestamp;
 
         msg!("State channel {} updated to nonce {}", 
@@ -131,13 +319,18 @@ impl StateChannel {
     pub fn challenge_state(
         &mut self,
         challenger: Pubkey,
-        _dispute_data: DisputeData,
+        dispute_data: DisputeData,
     ) -> Result<()> {
         // Validate challenger is a participant
         if !self.participants.contains(&challenger) {
             return Err(VaultError::UnauthorizedAccess.into());
         }
 
+        // Evidence may not reach behind the latest checkpoint
+        if dispute_data.disputed_version < self.latest_checkpoint_version() {
+            return Err(VaultError::StateBeforeCheckpoint.into());
+        }
+
         // Validate challenge is within dispute period
         let clock = Clock::get()?;
         if clock.unix_timestamp > self.last_update + self.dispute_period {
@@ -146,6 +339,13 @@ impl StateChannel {
 
         // Mark channel as disputed (would trigger resolution process)
         self.is_active = false;
+        self.dispute = Some(ChannelDispute {
+            challenger,
+            turn: DisputeTurn::Respondent,
+            deadline: clock.unix_timestamp + self.dispute_period,
+            extensions: 0,
+            last_evidence_hash: solana_program::hash::hash(&dispute_data.evidence).to_bytes(),
+        });
 
         msg!("State channel {} challenged by {}", 
              bs58::encode(self.channel_id).into_string(),
@@ -154,29 +354,499 @@ impl StateChannel {
         Ok(())
     }
 
-    /// Settle state channel and finalize rewards on-chain
-    pub fn settle_channel(&mut self, final_calculations: Vec<RewardCalculation>) -> Result<()> {
-        // Validate channel can be settled
-        let clock = Clock::get()?;
-        if clock.unix_timestamp < self.timeout {
+    /// Record counter-evidence in an open dispute and hand the turn to the
+    /// other side. A response landing within the extension of the deadline
+    /// pushes the deadline out so the other side can still answer.
+    pub fn respond_to_challenge(
+        &mut self,
+        responder: Pubkey,
+        evidence: &[u8],
+        timestamp: i64,
+    ) -> Result<()> {
+        if !self.participants.contains(&responder) {
+            return Err(VaultError::UnauthorizedAccess.into());
+        }
+
+        let extension = self.challenge_extension;
+        let dispute = self.dispute.as_mut().ok_or(VaultError::SecurityViolation)?;
+
+        if timestamp > dispute.deadline {
+            return Err(VaultError::SecurityViolation.into());
+        }
+
+        let is_challenger = responder == dispute.challenger;
+        match dispute.turn {
+            DisputeTurn::Respondent if is_challenger => return Err(VaultError::UnauthorizedAccess.into()),
+            DisputeTurn::Challenger if !is_challenger => return Err(VaultError::UnauthorizedAccess.into()),
+            _ => {},
+        }
+
+        let extended_deadline = timestamp
+            .checked_add(extension)
+            .ok_or(VaultError::ArithmeticOverflow)?;
+        if extended_deadline > dispute.deadline {
+            if dispute.extensions >= Self::MAX_CHALLENGE_EXTENSIONS {
+                return Err(VaultError::SecurityViolation.into());
+            }
+            dispute.deadline = extended_deadline;
+            dispute.extensions += 1;
+        }
+
+        dispute.turn = if is_challenger { DisputeTurn::Respondent } else { DisputeTurn::Challenger };
+        dispute.last_evidence_hash = solana_program::hash::hash(evidence).to_bytes();
+
+        Ok(())
+    }
+
+    /// Fail while a dispute window is still open
+    pub fn ensure_dispute_closed(&self, timestamp: i64) -> Result<()> {
+        match &self.dispute {
+            Some(dispute) if timestamp <= dispute.deadline => Err(VaultError::SecurityViolation.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Replace the on-chain state with a newer fully signed one. The newer
+    /// version is conclusive, so the participant who left the stale state
+    /// on-chain forfeits part of their balance to the challenger. Signatures
+    /// must already have been verified by the caller.
+    /// Returns the stale submitter and the penalty moved.
+    pub fn override_with_newer_state(
+        &mut self,
+        challenger: Pubkey,
+        update: StateChannelUpdate,
+        signatures: Vec<Vec<u8>>,
+        timestamp: i64,
+    ) -> Result<(Pubkey, u64)> {
+        if !self.is_active {
             return Err(VaultError::SecurityViolation.into());
         }
 
-        // Calculate total settlement amount
-        let total_rewards: u64 = final_calculations
+        if !self.participants.contains(&challenger) {
+            return Err(VaultError::UnauthorizedAccess.into());
+        }
+
+        if update.channel_id != self.channel_id || update.nonce <= self.nonce {
+            return Err(VaultError::SecurityViolation.into());
+        }
+
+        if timestamp > self.last_update + self.dispute_period {
+            return Err(VaultError::SecurityViolation.into());
+        }
+
+        Self::verify_update_hash(&update)?;
+
+        let stale_submitter = self.last_submitter;
+
+        self.apply_reward_calculations(&update.reward_calculations);
+        self.state_hash = update.new_state_hash;
+        self.nonce = update.nonce;
+        self.signatures = signatures;
+        self.last_update = timestamp;
+        self.last_submitter = challenger;
+
+        let mut penalty = 0;
+        if stale_submitter != challenger {
+            let from = self.participants.iter().position(|p| *p == stale_submitter);
+            let to = self.participants.iter().position(|p| *p == challenger);
+            if let (Some(from), Some(to)) = (from, to) {
+                penalty = ((self.pending_balances[from] as u128 * Self::STALE_STATE_PENALTY_BPS as u128) / 10000) as u64;
+                self.pending_balances[from] -= penalty;
+                self.pending_balances[to] = self.pending_balances[to]
+                    .checked_add(penalty)
+                    .ok_or(VaultError::ArithmeticOverflow)?;
+            }
+        }
+
+        Ok((stale_submitter, penalty))
+    }
+
+    /// Reallocate deposited collateral according to final entitlements.
+    /// Collateral left over is returned pro rata to deposits; if entitlements
+    /// exceed the collateral they are clamped pro rata instead and the second
+    /// return value is true.
+    pub fn allocate_collateral(deposits: &[u64], entitlements: &[u64]) -> Result<(Vec<u64>, bool)> {
+        if deposits.len() != entitlements.len() {
+            return Err(VaultError::InvalidAllocation.into());
+        }
+
+        let total_collateral: u128 = deposits.iter().map(|d| *d as u128).sum();
+        let total_entitlements: u128 = entitlements.iter().map(|e| *e as u128).sum();
+
+        if total_entitlements > total_collateral {
+            let payouts = entitlements
+                .iter()
+                .map(|e| (*e as u128 * total_collateral / total_entitlements) as u64)
+                .collect();
+            return Ok((payouts, true));
+        }
+
+        let leftover = total_collateral - total_entitlements;
+        let payouts = entitlements
             .iter()
-            .map(|calc| calc.calculated_reward)
-            .sum();
+            .zip(deposits)
+            .map(|(e, d)| {
+                let refund = if total_collateral == 0 { 0 } else { *d as u128 * leftover / total_collateral };
+                (*e as u128 + refund) as u64
+            })
+            .collect();
+        Ok((payouts, false))
+    }
+
+    /// Fee rate for settling this channel; closes that never saw a challenge
+    /// are cooperative and get the discounted rate
+    pub fn settlement_fee_bps(&self) -> u16 {
+        if self.dispute.is_none() {
+            (self.fee_bps as u32 * (10000 - Self::COOPERATIVE_FEE_DISCOUNT_BPS as u32) / 10000) as u16
+        } else {
+            self.fee_bps
+        }
+    }
+
+    /// Charge `fee_bps` on each participant's gross outflow (collateral paid
+    /// to others), deducted from what they get back. Returns net payouts and
+    /// the fee taken from each vault.
+    pub fn apply_settlement_fees(deposits: &[u64], payouts: &[u64], fee_bps: u16) -> Result<(Vec<u64>, Vec<u64>)> {
+        if deposits.len() != payouts.len() {
+            return Err(VaultError::InvalidAllocation.into());
+        }
+
+        let mut net_payouts = Vec::with_capacity(payouts.len());
+        let mut fees = Vec::with_capacity(payouts.len());
+        for (deposit, payout) in deposits.iter().zip(payouts) {
+            let outflow = deposit.saturating_sub(*payout);
+            let fee = ((outflow as u128 * fee_bps as u128) / 10000) as u64;
+            let fee = fee.min(*payout);
+            net_payouts.push(payout - fee);
+            fees.push(fee);
+        }
+        Ok((net_payouts, fees))
+    }
+
+    /// Participants sign only the state hash, so the reward calculations
+    /// carried alongside it must be exactly the ones it commits to
+    fn verify_update_hash(update: &StateChannelUpdate) -> Result<()> {
+        if Self::calculate_state_hash(&update.reward_calculations) != update.new_state_hash {
+            return Err(VaultError::SecurityViolation.into());
+        }
+        Ok(())
+    }
 
-        self.settlement_amount = total_rewards;
+    /// Track each participant's unsettled share of a new state
+    fn apply_reward_calculations(&mut self, calculations: &[RewardCalculation]) {
+        for calculation in calculations {
+            if let Some(index) = self.participants.iter().position(|p| *p == calculation.user) {
+                self.pending_balances[index] = calculation.calculated_reward;
+            }
+        }
+    }
+
+    /// Commit to the final reward calculations by Merkle root and stop
+    /// further updates. Leaves are then applied with `apply_settlement_leaf`.
+    /// The signed state hash covers the leaf count, so a wrong count can't
+    /// leave the settlement impossible to complete.
+    pub fn begin_settlement(
+        &mut self,
+        root: [u8; 32],
+        leaf_count: u32,
+        from_collateral: bool,
+        timestamp: i64,
+    ) -> Result<()> {
+        if self.is_settled || self.settlement.is_some() {
+            return Err(VaultError::SecurityViolation.into());
+        }
+        if timestamp < self.timeout {
+            return Err(VaultError::SecurityViolation.into());
+        }
+        self.ensure_dispute_closed(timestamp)?;
+
+        if leaf_count > Self::MAX_SETTLEMENT_LEAVES {
+            return Err(VaultError::InvalidAllocation.into());
+        }
+
+        // The committed root and count must extend the checkpointed history
+        self.verify_settlement_hash(Self::commit_leaf_count(&root, leaf_count))?;
+
+        self.settlement = Some(BatchSettlement {
+            root,
+            leaf_count,
+            applied_count: 0,
+            applied_leaves: vec![0; (leaf_count as usize + 7) / 8],
+            from_collateral,
+        });
+        self.settlement_amount = 0;
+        self.pending_balances.iter_mut().for_each(|balance| *balance = 0);
         self.is_active = false;
 
+        Ok(())
+    }
+
+    /// Apply one committed reward calculation. Each leaf index can be applied
+    /// once; returns the reward it carries.
+    pub fn apply_settlement_leaf(
+        &mut self,
+        leaf: &SettlementLeaf,
+        proof: &[[u8; 32]],
+    ) -> Result<u64> {
+        if self.is_settled {
+            return Err(VaultError::SecurityViolation.into());
+        }
+        let participant_index = self.participants.iter().position(|p| *p == leaf.calculation.user);
+        let settlement = self.settlement.as_mut().ok_or(VaultError::SecurityViolation)?;
+
+        if !Self::verify_settlement_proof(&settlement.root, settlement.leaf_count, leaf.index, &leaf.calculation, proof) {
+            return Err(VaultError::InvalidSettlementProof.into());
+        }
+
+        let byte = leaf.index as usize / 8;
+        let bit = 1u8 << (leaf.index % 8);
+        if settlement.applied_leaves[byte] & bit != 0 {
+            return Err(VaultError::SettlementLeafAlreadyApplied.into());
+        }
+
+        // Collateral only covers participants, so every leaf must map to one
+        if settlement.from_collateral {
+            let index = participant_index.ok_or(VaultError::UnauthorizedAccess)?;
+            self.pending_balances[index] = self.pending_balances[index]
+                .checked_add(leaf.calculation.calculated_reward)
+                .ok_or(VaultError::ArithmeticOverflow)?;
+        }
+
+        settlement.applied_leaves[byte] |= bit;
+        settlement.applied_count += 1;
+        self.settlement_amount = self.settlement_amount
+            .checked_add(leaf.calculation.calculated_reward)
+            .ok_or(VaultError::ArithmeticOverflow)?;
+
+        Ok(leaf.calculation.calculated_reward)
+    }
+
+    /// Whether every committed leaf has been applied
+    pub fn settlement_complete(&self) -> bool {
+        !self.is_settled && self.settlement.as_ref()
+            .map_or(false, |settlement| settlement.applied_count == settlement.leaf_count)
+    }
+
+    /// Mark the channel settled once every leaf is applied. Returns the
+    /// per-participant entitlements accumulated for collateral payout.
+    pub fn complete_settlement(&mut self) -> Result<Vec<u64>> {
+        if !self.settlement_complete() {
+            return Err(VaultError::SecurityViolation.into());
+        }
+
+        let entitlements = self.pending_balances.clone();
+        self.pending_balances.iter_mut().for_each(|balance| *balance = 0);
+        self.is_settled = true;
+
         msg!("State channel {} settled with {} total rewards", 
-             bs58::encode(self.channel_id).into_string(), total_rewards);
+             bs58::encode(self.channel_id).into_string(), self.settlement_amount);
+
+        Ok(entitlements)
+    }
+
+    /// Settle an abandoned channel from the latest on-chain state once the
+    /// timeout and grace period have passed. Returns each participant's final
+    /// balance, to be paid as in a normal settlement.
+    pub fn force_settle_expired(&mut self, timestamp: i64) -> Result<Vec<u64>> {
+        // Inactive channels are either settled or under challenge
+        if !self.is_active {
+            return Err(VaultError::SecurityViolation.into());
+        }
+
+        let grace_deadline = self.timeout
+            .checked_add(Self::FORCE_SETTLE_GRACE_PERIOD)
+            .ok_or(VaultError::ArithmeticOverflow)?;
+        if timestamp < grace_deadline {
+            return Err(VaultError::SecurityViolation.into());
+        }
+
+        // Never cut off a participant's chance to challenge the last update
+        let challenge_deadline = self.last_update
+            .checked_add(self.dispute_period)
+            .ok_or(VaultError::ArithmeticOverflow)?;
+        if timestamp <= challenge_deadline {
+            return Err(VaultError::SecurityViolation.into());
+        }
+
+        let total = self.pending_balances
+            .iter()
+            .try_fold(0u64, |total, balance| total.checked_add(*balance))
+            .ok_or(VaultError::ArithmeticOverflow)?;
+
+        self.settlement_amount = self.settlement_amount
+            .checked_add(total)
+            .ok_or(VaultError::ArithmeticOverflow)?;
+        let entitlements = self.pending_balances.clone();
+        self.pending_balances.iter_mut().for_each(|balance| *balance = 0);
+        self.is_active = false;
+        self.is_settled = true;
+
+        Ok(entitlements)
+    }
+
+    /// Add and remove participants on a live channel. Every current participant
+    /// and every newcomer must sign. Removed participants with a pending balance
+    /// are returned so the caller can settle their slice before they leave.
+    pub fn change_participants(
+        &mut self,
+        add: &[Pubkey],
+        remove: &[Pubkey],
+        signers: &[Pubkey],
+        timestamp: i64,
+    ) -> Result<Vec<(Pubkey, u64)>> {
+        if !self.is_active {
+            return Err(VaultError::SecurityViolation.into());
+        }
+
+        if add.is_empty() && remove.is_empty() {
+            return Err(VaultError::InvalidAllocation.into());
+        }
+
+        // Reject duplicates and overlapping add/remove sets
+        for (i, key) in add.iter().enumerate() {
+            if self.participants.contains(key) || add[..i].contains(key) {
+                return Err(VaultError::InvalidAllocation.into());
+            }
+        }
+        for (i, key) in remove.iter().enumerate() {
+            if !self.participants.contains(key) || remove[..i].contains(key) {
+                return Err(VaultError::InvalidAllocation.into());
+            }
+        }
+
+        // Require unanimous consent from the current set plus newcomers
+        let all_signed = self.participants.iter()
+            .chain(add.iter())
+            .all(|key| signers.contains(key));
+        if !all_signed {
+            return Err(VaultError::MultisigThresholdNotMet.into());
+        }
+
+        let new_count = self.participants.len() - remove.len() + add.len();
+        if new_count == 0 || new_count > self.max_participants as usize {
+            return Err(VaultError::InvalidAllocation.into());
+        }
+
+        // Force-settle the slices of departing participants
+        let mut settled = Vec::new();
+        for key in remove {
+            let index = self.participants.iter().position(|p| p == key)
+                .ok_or(VaultError::InvalidAllocation)?;
+            let balance = self.pending_balances[index];
+            if balance > 0 {
+                self.settlement_amount = self.settlement_amount
+                    .checked_add(balance)
+                    .ok_or(VaultError::ArithmeticOverflow)?;
+                settled.push((*key, balance));
+            }
+            self.participants.remove(index);
+            self.pending_balances.remove(index);
+        }
+
+        for key in add {
+            self.participants.push(*key);
+            self.pending_balances.push(0);
+        }
+
+        // Signatures over the previous state no longer cover the new set
+        self.nonce = self.nonce
+            .checked_add(1)
+            .ok_or(VaultError::ArithmeticOverflow)?;
+        self.signatures = Vec::new();
+        self.last_update = timestamp;
+
+        Ok(settled)
+    }
+
+    /// Record a checkpoint signed by every participant. Callers verify the
+    /// signatures over `Ed25519Verifier::checkpoint_message` first.
+    /// Checkpoints ahead of the on-chain nonce advance the channel to the
+    /// checkpointed state.
+    pub fn record_checkpoint(
+        &mut self,
+        state_hash: [u8; 32],
+        version: u64,
+        signatures: Vec<Vec<u8>>,
+        timestamp: i64,
+    ) -> Result<()> {
+        if !self.is_active {
+            return Err(VaultError::SecurityViolation.into());
+        }
+
+        // Checkpoints must move forward and can't predate the on-chain state
+        if version <= self.latest_checkpoint_version() && !self.checkpoints.is_empty() {
+            return Err(VaultError::SecurityViolation.into());
+        }
+        if version < self.nonce {
+            return Err(VaultError::SecurityViolation.into());
+        }
+        if version == self.nonce && state_hash != self.state_hash {
+            return Err(VaultError::SecurityViolation.into());
+        }
+
+        // Mutual signature: one per participant
+        if signatures.len() != self.participants.len() {
+            return Err(VaultError::MultisigThresholdNotMet.into());
+        }
+
+        let previous_chain_hash = self.checkpoints
+            .last()
+            .map(|checkpoint| checkpoint.chain_hash)
+            .unwrap_or([0; 32]);
+
+        if self.checkpoints.len() >= Self::MAX_CHECKPOINTS {
+            self.checkpoints.remove(0);
+        }
+        self.checkpoints.push(StateCheckpoint {
+            state_hash,
+            version,
+            chain_hash: Self::chain_checkpoint(&previous_chain_hash, &state_hash, version),
+            recorded_at: timestamp,
+        });
+
+        self.state_hash = state_hash;
+        self.nonce = version;
+        self.signatures = signatures;
+        self.last_update = timestamp;
+
+        Ok(())
+    }
+
+    /// Version of the latest checkpoint, or 0 when none has been recorded
+    pub fn latest_checkpoint_version(&self) -> u64 {
+        self.checkpoints.last().map(|checkpoint| checkpoint.version).unwrap_or(0)
+    }
+
+    /// Verify that a settlement state hash is the signed on-chain state and is
+    /// consistent with the checkpoint history: at least one checkpoint must
+    /// exist, the retained chain must be intact and the on-chain state must be
+    /// the latest checkpoint or a co-signed update that followed it.
+    pub fn verify_settlement_hash(&self, final_state_hash: [u8; 32]) -> Result<()> {
+        let latest = self.checkpoints.last().ok_or(VaultError::SecurityViolation)?;
+
+        for pair in self.checkpoints.windows(2) {
+            let expected = Self::chain_checkpoint(&pair[0].chain_hash, &pair[1].state_hash, pair[1].version);
+            if pair[1].chain_hash != expected {
+                return Err(VaultError::SecurityViolation.into());
+            }
+        }
+
+        if final_state_hash != self.state_hash || self.nonce < latest.version {
+            return Err(VaultError::SecurityViolation.into());
+        }
+        if self.nonce == latest.version && latest.state_hash != self.state_hash {
+            return Err(VaultError::SecurityViolation.into());
+        }
 
         Ok(())
     }
 
+    fn chain_checkpoint(previous: &[u8; 32], state_hash: &[u8; 32], version: u64) -> [u8; 32] {
+        use solana_program::hash::hashv;
+
+        hashv(&[previous, state_hash, &version.to_le_bytes()]).to_bytes()
+    }
+
     /// Validate state channel integrity
     pub fn validate_state(&self) -> Result<()> {
         // Check if channel has expired
@@ -186,7 +856,7 @@ impl StateChannel {
         }
 
         // Validate participants
-        if self.participants.is_empty() || self.participants.len() > 10 {
+        if self.participants.is_empty() || self.participants.len() > self.max_participants as usize {
             return Err(VaultError::InvalidAllocation.into());
         }
 
@@ -248,21 +918,112 @@ impl StateChannel {
         Ok(true)
     }
 
-    /// Calculate state hash for reward calculations
+    /// Calculate state hash for reward calculations: their settlement root
+    /// bound to the number of calculations
     pub fn calculate_state_hash(calculations: &[RewardCalculation]) -> [u8; 32] {
-        use solana_program::hash::hash;
-        
-        let mut data = Vec::new();
-        
-        for calc in calculations {
-            data.extend_from_slice(&calc.user.to_bytes());
-            data.extend_from_slice(&calc.btc_commitment.to_le_bytes());
-            data.extend_from_slice(&calc.calculated_reward.to_le_bytes());
-            data.extend_from_slice(&calc.calculation_timestamp.to_le_bytes());
+        Self::commit_leaf_count(&Self::settlement_root(calculations), calculations.len() as u32)
+    }
+
+    /// Merkle root over one leaf per calculation, with an odd node promoted
+    /// to the next level
+    pub fn settlement_root(calculations: &[RewardCalculation]) -> [u8; 32] {
+        Self::merkle_root(calculations.iter().map(Self::settlement_leaf_hash).collect())
+    }
+
+    /// Positional Merkle root over `leaves`, with an odd node promoted to
+    /// the next level. Enhanced channels build their balance roots with it.
+    pub(crate) fn merkle_root(leaves: Vec<[u8; 32]>) -> [u8; 32] {
+        let mut level = leaves;
+        while level.len() > 1 {
+            level = level
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => Self::merkle_node(left, right),
+                    _ => pair[0],
+                })
+                .collect();
         }
-        
-        let hash_result = hash(&data);
-        hash_result.to_bytes()
+        level.first().copied().unwrap_or([0; 32])
+    }
+
+    fn commit_leaf_count(root: &[u8; 32], leaf_count: u32) -> [u8; 32] {
+        use solana_program::hash::hashv;
+
+        hashv(&[&[2u8], root, &leaf_count.to_le_bytes()]).to_bytes()
+    }
+
+    /// Check a leaf against a settlement root. The proof holds one sibling per
+    /// level where the node has one, bottom-up.
+    pub fn verify_settlement_proof(
+        root: &[u8; 32],
+        leaf_count: u32,
+        index: u32,
+        calculation: &RewardCalculation,
+        proof: &[[u8; 32]],
+    ) -> bool {
+        Self::verify_merkle_proof(
+            root,
+            leaf_count as usize,
+            index as usize,
+            Self::settlement_leaf_hash(calculation),
+            proof,
+        )
+    }
+
+    /// Check `leaf` at `index` against a root built by `merkle_root` over
+    /// `leaf_count` leaves. The proof holds one sibling per level where the
+    /// node has one, bottom-up.
+    pub(crate) fn verify_merkle_proof(
+        root: &[u8; 32],
+        leaf_count: usize,
+        index: usize,
+        leaf: [u8; 32],
+        proof: &[[u8; 32]],
+    ) -> bool {
+        if index >= leaf_count {
+            return false;
+        }
+
+        let mut hash = leaf;
+        let mut index = index;
+        let mut width = leaf_count;
+        let mut siblings = proof.iter();
+        while width > 1 {
+            if index ^ 1 < width {
+                let sibling = match siblings.next() {
+                    Some(sibling) => sibling,
+                    None => return false,
+                };
+                hash = if index % 2 == 0 {
+                    Self::merkle_node(&hash, sibling)
+                } else {
+                    Self::merkle_node(sibling, &hash)
+                };
+            }
+            index /= 2;
+            width = (width + 1) / 2;
+        }
+
+        siblings.next().is_none() && hash == *root
+    }
+
+    // Leaves and inner nodes are domain-separated so one can't pose as the other
+    fn settlement_leaf_hash(calculation: &RewardCalculation) -> [u8; 32] {
+        use solana_program::hash::hashv;
+
+        hashv(&[
+            &[0u8],
+            calculation.user.as_ref(),
+            &calculation.btc_commitment.to_le_bytes(),
+            &calculation.calculated_reward.to_le_bytes(),
+            &calculation.calculation_timestamp.to_le_bytes(),
+        ]).to_bytes()
+    }
+
+    pub(crate) fn merkle_node(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+        use solana_program::hash::hashv;
+
+        hashv(&[&[1u8], left, right]).to_bytes()
     }
 
     /// Get channel status for monitoring
@@ -346,6 +1107,7 @@ mod tests {
         let mut channel = StateChannel {
             channel_id: [0; 32],
             participants: Vec::new(),
+            pending_balances: Vec::new(),
             state_hash: [0; 32],
             nonce: 0,
             timeout: 0,
@@ -354,16 +1116,27 @@ mod tests {
             last_update: 0,
             dispute_period: 0,
             settlement_amount: 0,
+            checkpoints: Vec::new(),
+            last_submitter: Pubkey::default(),
+            is_settled: false,
+            dispute: None,
+            challenge_extension: StateChannel::DEFAULT_CHALLENGE_EXTENSION,
+            fee_bps: 0,
+            purpose: ChannelPurpose::RewardSettlement,
+            settlement: None,
             bump: 0,
+            collateralized: false,
+            max_participants: 0,
         };
 
         let participants = vec![Pubkey::new_unique(), Pubkey::new_unique()];
         let channel_id = [1; 32];
         
-        assert!(channel.initialize(channel_id, participants.clone(), 3600, 255).is_ok());
+        assert!(channel.initialize(channel_id, participants.clone(), 3600, None, None, 10, ChannelPurpose::Trading, 255).is_ok());
         assert_eq!(channel.channel_id, channel_id);
         assert_eq!(channel.participants, participants);
         assert!(channel.is_active);
+        assert_eq!(channel.purpose, ChannelPurpose::Trading);
     }
 
     #[test]
@@ -408,4 +1181,510 @@ mod tests {
         // User 2: (200000000 / 300000000) * 75000000 = 50000000
         assert_eq!(calculations[1].calculated_reward, 50000000);
     }
+
+    fn live_channel(participants: Vec<Pubkey>, balances: Vec<u64>) -> StateChannel {
+        StateChannel {
+            channel_id: [1; 32],
+            participants,
+            pending_balances: balances,
+            state_hash: [0; 32],
+            nonce: 5,
+            timeout: 10_000,
+            signatures: vec![vec![0; 64]],
+            is_active: true,
+            last_update: 0,
+            dispute_period: 86400,
+            settlement_amount: 0,
+            checkpoints: Vec::new(),
+            last_submitter: Pubkey::default(),
+            is_settled: false,
+            dispute: None,
+            challenge_extension: StateChannel::DEFAULT_CHALLENGE_EXTENSION,
+            fee_bps: 0,
+            purpose: ChannelPurpose::RewardSettlement,
+            settlement: None,
+            bump: 0,
+            collateralized: false,
+            max_participants: StateChannel::MAX_PARTICIPANTS as u8,
+        }
+    }
+
+    #[test]
+    fn test_add_participant_requires_all_signatures() {
+        let (a, b, c) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let mut channel = live_channel(vec![a, b], vec![0, 0]);
+
+        // Newcomer must sign too
+        assert!(channel.change_participants(&[c], &[], &[a, b], 100).is_err());
+        // Every existing participant must sign
+        assert!(channel.change_participants(&[c], &[], &[a, c], 100).is_err());
+
+        let settled = channel.change_participants(&[c], &[], &[a, b, c], 100).unwrap();
+        assert!(settled.is_empty());
+        assert_eq!(channel.participants, vec![a, b, c]);
+        assert_eq!(channel.pending_balances, vec![0, 0, 0]);
+        assert_eq!(channel.nonce, 6);
+        assert!(channel.signatures.is_empty());
+        assert_eq!(channel.last_update, 100);
+    }
+
+    #[test]
+    fn test_remove_participant_settles_pending_slice() {
+        let (a, b, c) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let mut channel = live_channel(vec![a, b, c], vec![10, 250, 0]);
+
+        let settled = channel.change_participants(&[], &[b, c], &[a, b, c], 100).unwrap();
+        assert_eq!(settled, vec![(b, 250)]);
+        assert_eq!(channel.participants, vec![a]);
+        assert_eq!(channel.pending_balances, vec![10]);
+        assert_eq!(channel.settlement_amount, 250);
+
+        // The last participant can't be removed
+        assert!(channel.change_participants(&[], &[a], &[a], 200).is_err());
+    }
+
+    #[test]
+    fn test_participant_change_respects_bounds() {
+        let participants: Vec<Pubkey> = (0..StateChannel::MAX_PARTICIPANTS).map(|_| Pubkey::new_unique()).collect();
+        let mut channel = live_channel(participants.clone(), vec![0; StateChannel::MAX_PARTICIPANTS]);
+        let newcomer = Pubkey::new_unique();
+        let mut signers = participants.clone();
+        signers.push(newcomer);
+
+        assert!(channel.change_participants(&[newcomer], &[], &signers, 100).is_err());
+        // Swapping one out keeps the channel at the limit
+        assert!(channel.change_participants(&[newcomer], &[participants[0]], &signers, 100).is_ok());
+        // Duplicates and unknown removals are rejected
+        assert!(channel.change_participants(&[participants[1]], &[], &signers, 100).is_err());
+        assert!(channel.change_participants(&[], &[Pubkey::new_unique()], &signers, 100).is_err());
+    }
+
+    #[test]
+    fn test_participant_change_respects_channel_bound() {
+        let (a, b, c) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let mut channel = live_channel(vec![a, b], vec![0, 0]);
+        channel.max_participants = 2;
+
+        assert!(channel.change_participants(&[c], &[], &[a, b, c], 100).is_err());
+        channel.max_participants = 3;
+        assert!(channel.change_participants(&[c], &[], &[a, b, c], 100).is_ok());
+    }
+
+    #[test]
+    fn test_checkpoint_history_is_bounded_and_chained() {
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut channel = live_channel(vec![a, b], vec![0, 0]);
+        let signatures = vec![vec![1; 64], vec![2; 64]];
+
+        for version in 6..6 + StateChannel::MAX_CHECKPOINTS as u64 + 3 {
+            let state_hash = [version as u8; 32];
+            channel.record_checkpoint(state_hash, version, signatures.clone(), version as i64).unwrap();
+        }
+
+        assert_eq!(channel.checkpoints.len(), StateChannel::MAX_CHECKPOINTS);
+        assert_eq!(channel.latest_checkpoint_version(), 16);
+        assert_eq!(channel.nonce, 16);
+        assert!(channel.verify_settlement_hash([16; 32]).is_ok());
+        assert!(channel.verify_settlement_hash([15; 32]).is_err());
+
+        // Tampering with the retained chain is detected
+        channel.checkpoints[3].state_hash = [0; 32];
+        assert!(channel.verify_settlement_hash([16; 32]).is_err());
+    }
+
+    #[test]
+    fn test_checkpoint_requires_every_participant_and_forward_version() {
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut channel = live_channel(vec![a, b], vec![0, 0]);
+
+        // Majority is not enough for a checkpoint
+        assert!(channel.record_checkpoint([9; 32], 9, vec![vec![1; 64]], 10).is_err());
+        // Can't checkpoint behind the on-chain nonce
+        assert!(channel.record_checkpoint([4; 32], 4, vec![vec![1; 64], vec![2; 64]], 10).is_err());
+
+        channel.record_checkpoint([9; 32], 9, vec![vec![1; 64], vec![2; 64]], 10).unwrap();
+        assert!(channel.record_checkpoint([9; 32], 9, vec![vec![1; 64], vec![2; 64]], 11).is_err());
+    }
+
+    #[test]
+    fn test_dispute_cannot_reference_pre_checkpoint_state() {
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut channel = live_channel(vec![a, b], vec![0, 0]);
+        channel.record_checkpoint([9; 32], 9, vec![vec![1; 64], vec![2; 64]], 10).unwrap();
+
+        let stale = DisputeData {
+            challenger: a,
+            disputed_state_hash: [7; 32],
+            evidence: Vec::new(),
+            disputed_version: 7,
+            challenge_timestamp: 20,
+        };
+        assert!(channel.challenge_state(a, stale).is_err());
+        assert!(channel.is_active);
+    }
+
+    #[test]
+    fn test_force_settle_expired_channel() {
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut channel = live_channel(vec![a, b], vec![300, 700]);
+        let deadline = channel.timeout + StateChannel::FORCE_SETTLE_GRACE_PERIOD;
+
+        // Still inside timeout plus grace
+        assert!(channel.force_settle_expired(deadline - 1).is_err());
+
+        // Activity near the deadline reopens the challenge window
+        channel.last_update = deadline;
+        assert!(channel.force_settle_expired(deadline + 1).is_err());
+
+        let after_window = deadline + channel.dispute_period + 1;
+        assert_eq!(channel.force_settle_expired(after_window).unwrap(), vec![300, 700]);
+        assert_eq!(channel.settlement_amount, 1_000);
+        assert!(!channel.is_active);
+        assert_eq!(channel.pending_balances, vec![0, 0]);
+        assert!(channel.force_settle_expired(after_window).is_err());
+    }
+
+    #[test]
+    fn test_force_settle_rejects_challenged_channel() {
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut channel = live_channel(vec![a, b], vec![300, 700]);
+        channel.is_active = false; // challenged
+
+        let far_future = channel.timeout + StateChannel::FORCE_SETTLE_GRACE_PERIOD * 10;
+        assert!(channel.force_settle_expired(far_future).is_err());
+        assert_eq!(channel.pending_balances, vec![300, 700]);
+    }
+
+    fn newer_update(channel: &StateChannel, nonce: u64, calculations: Vec<RewardCalculation>) -> StateChannelUpdate {
+        StateChannelUpdate {
+            channel_id: channel.channel_id,
+            new_state_hash: StateChannel::calculate_state_hash(&calculations),
+            nonce,
+            reward_calculations: calculations,
+            timestamp: 0,
+        }
+    }
+
+    fn reward(user: Pubkey, calculated_reward: u64) -> RewardCalculation {
+        RewardCalculation {
+            user,
+            btc_commitment: 0,
+            calculated_reward,
+            calculation_timestamp: 0,
+        }
+    }
+
+    #[test]
+    fn test_newer_state_overrides_and_penalizes_stale_submitter() {
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut channel = live_channel(vec![a, b], vec![500, 500]);
+        channel.last_submitter = a;
+
+        let update = newer_update(&channel, 9, vec![reward(a, 1_000), reward(b, 2_000)]);
+        let (stale_submitter, penalty) = channel
+            .override_with_newer_state(b, update, vec![vec![1; 64], vec![2; 64]], 100)
+            .unwrap();
+
+        assert_eq!(stale_submitter, a);
+        assert_eq!(penalty, 100);
+        assert_eq!(channel.pending_balances, vec![900, 2_100]);
+        assert_eq!(channel.nonce, 9);
+        assert_eq!(channel.last_submitter, b);
+        assert!(channel.is_active);
+    }
+
+    #[test]
+    fn test_override_rejects_stale_or_foreign_state() {
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut channel = live_channel(vec![a, b], vec![500, 500]);
+
+        // Same or older version is not conclusive
+        let replay = newer_update(&channel, 5, vec![reward(a, 1)]);
+        assert!(channel.override_with_newer_state(b, replay, Vec::new(), 100).is_err());
+
+        let mut foreign = newer_update(&channel, 6, vec![reward(a, 1)]);
+        foreign.channel_id = [2; 32];
+        assert!(channel.override_with_newer_state(b, foreign, Vec::new(), 100).is_err());
+
+        // Outsiders can't override
+        let update = newer_update(&channel, 6, vec![reward(a, 1)]);
+        assert!(channel.override_with_newer_state(Pubkey::new_unique(), update, Vec::new(), 100).is_err());
+        assert_eq!(channel.nonce, 5);
+    }
+
+    #[test]
+    fn test_override_rejects_calculations_outside_signed_hash() {
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut channel = live_channel(vec![a, b], vec![500, 500]);
+
+        // The signed hash covers a small reward; the attached balances don't match it
+        let mut update = newer_update(&channel, 6, vec![reward(a, 1), reward(b, 1)]);
+        update.reward_calculations = vec![reward(a, 1), reward(b, 1_000_000)];
+        assert!(channel.override_with_newer_state(a, update, vec![vec![1; 64], vec![2; 64]], 100).is_err());
+        assert_eq!(channel.pending_balances, vec![500, 500]);
+        assert_eq!(channel.nonce, 5);
+    }
+
+    #[test]
+    fn test_collateral_allocation_follows_entitlements() {
+        // Fully collateralized: entitlements paid, leftover refunded by deposit
+        let (payouts, clamped) = StateChannel::allocate_collateral(&[600, 400], &[300, 500]).unwrap();
+        assert!(!clamped);
+        assert_eq!(payouts, vec![300 + 120, 500 + 80]);
+        assert_eq!(payouts.iter().sum::<u64>(), 1_000);
+
+        // Under-collateralized: clamped pro rata, never paying out more than deposited
+        let (payouts, clamped) = StateChannel::allocate_collateral(&[500, 500], &[1_500, 500]).unwrap();
+        assert!(clamped);
+        assert_eq!(payouts, vec![750, 250]);
+
+        assert!(StateChannel::allocate_collateral(&[500], &[1, 2]).is_err());
+    }
+
+    #[test]
+    fn test_collateral_allocation_pays_participant_without_deposit() {
+        // A participant who never deposited counts as zero collateral but is still paid
+        let (payouts, clamped) = StateChannel::allocate_collateral(&[1_000, 0], &[300, 500]).unwrap();
+        assert!(!clamped);
+        assert_eq!(payouts, vec![300 + 200, 500]);
+    }
+
+    #[test]
+    fn test_collateral_withdrawal_bumps_nonce() {
+        let mut collateral = ChannelCollateral {
+            channel_id: [1; 32],
+            participant: Pubkey::new_unique(),
+            amount: 0,
+            withdrawal_nonce: 0,
+            bump: 0,
+        };
+
+        collateral.deposit(1_000).unwrap();
+        assert!(collateral.withdraw(1_001).is_err());
+        collateral.withdraw(400).unwrap();
+        assert_eq!(collateral.amount, 600);
+        assert_eq!(collateral.withdrawal_nonce, 1);
+        assert!(collateral.deposit(0).is_err());
+    }
+
+    fn disputed_channel(challenger: Pubkey, respondent: Pubkey) -> StateChannel {
+        let mut channel = live_channel(vec![challenger, respondent], vec![0, 0]);
+        channel.is_active = false;
+        channel.dispute = Some(ChannelDispute {
+            challenger,
+            turn: DisputeTurn::Respondent,
+            deadline: 1_000,
+            extensions: 0,
+            last_evidence_hash: [0; 32],
+        });
+        channel
+    }
+
+    #[test]
+    fn test_late_response_extends_window_with_alternating_turns() {
+        let (challenger, respondent) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut channel = disputed_channel(challenger, respondent);
+        let extension = channel.challenge_extension;
+
+        // Challenger can't answer their own challenge
+        assert!(channel.respond_to_challenge(challenger, b"x", 990).is_err());
+
+        channel.respond_to_challenge(respondent, b"counter", 990).unwrap();
+        let dispute = channel.dispute.clone().unwrap();
+        assert_eq!(dispute.turn, DisputeTurn::Challenger);
+        assert_eq!(dispute.deadline, 990 + extension);
+        assert_eq!(dispute.extensions, 1);
+
+        // Respondent can't answer twice in a row
+        assert!(channel.respond_to_challenge(respondent, b"again", 1_000).is_err());
+
+        channel.respond_to_challenge(challenger, b"rebuttal", 990 + extension).unwrap();
+        assert_eq!(channel.dispute.as_ref().unwrap().extensions, 2);
+
+        // Third extension is refused; settlement waits for the window to close
+        let deadline = channel.dispute.as_ref().unwrap().deadline;
+        assert!(channel.respond_to_challenge(respondent, b"late", deadline).is_err());
+        assert!(channel.ensure_dispute_closed(deadline).is_err());
+        assert!(channel.ensure_dispute_closed(deadline + 1).is_ok());
+    }
+
+    #[test]
+    fn test_early_response_does_not_consume_extension() {
+        let (challenger, respondent) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut channel = disputed_channel(challenger, respondent);
+        channel.dispute.as_mut().unwrap().deadline = channel.challenge_extension * 10;
+
+        channel.respond_to_challenge(respondent, b"counter", 0).unwrap();
+        let dispute = channel.dispute.clone().unwrap();
+        assert_eq!(dispute.extensions, 0);
+        assert_eq!(dispute.deadline, channel.challenge_extension * 10);
+
+        // Responses after the window closes are rejected
+        assert!(channel.respond_to_challenge(challenger, b"late", dispute.deadline + 1).is_err());
+    }
+
+    #[test]
+    fn test_settlement_fee_on_gross_outflow() {
+        // Participant 0 pays 400 to participant 1; only the payer is charged
+        let (net, fees) = StateChannel::apply_settlement_fees(&[1_000, 1_000], &[600, 1_400], 100).unwrap();
+        assert_eq!(fees, vec![4, 0]);
+        assert_eq!(net, vec![596, 1_400]);
+
+        // A fee never exceeds what the payer has left
+        let (net, fees) = StateChannel::apply_settlement_fees(&[1_000], &[1], 100).unwrap();
+        assert_eq!(fees, vec![1]);
+        assert_eq!(net, vec![0]);
+    }
+
+    #[test]
+    fn test_cooperative_close_discount_uses_rate_at_open() {
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut channel = live_channel(vec![a, b], vec![0, 0]);
+        channel.fee_bps = 40;
+        assert_eq!(channel.settlement_fee_bps(), 20);
+
+        channel.dispute = Some(ChannelDispute {
+            challenger: a,
+            turn: DisputeTurn::Respondent,
+            deadline: 0,
+            extensions: 0,
+            last_evidence_hash: [0; 32],
+        });
+        assert_eq!(channel.settlement_fee_bps(), 40);
+    }
+
+    /// Build the proof for `index` the way an off-chain settler would
+    fn settlement_proof(calculations: &[RewardCalculation], index: usize) -> Vec<[u8; 32]> {
+        let mut level: Vec<[u8; 32]> = calculations.iter().map(StateChannel::settlement_leaf_hash).collect();
+        let mut index = index;
+        let mut proof = Vec::new();
+        while level.len() > 1 {
+            if let Some(sibling) = level.get(index ^ 1) {
+                proof.push(*sibling);
+            }
+            level = level
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => StateChannel::merkle_node(left, right),
+                    _ => pair[0],
+                })
+                .collect();
+            index /= 2;
+        }
+        proof
+    }
+
+    fn settlement_leaves(calculations: &[RewardCalculation]) -> Vec<(SettlementLeaf, Vec<[u8; 32]>)> {
+        calculations
+            .iter()
+            .enumerate()
+            .map(|(i, calculation)| {
+                let leaf = SettlementLeaf { index: i as u32, calculation: calculation.clone() };
+                (leaf, settlement_proof(calculations, i))
+            })
+            .collect()
+    }
+
+    fn checkpoint(channel: &mut StateChannel, state_hash: [u8; 32]) {
+        let signatures = vec![vec![1; 64]; channel.participants.len()];
+        channel.record_checkpoint(state_hash, channel.nonce + 1, signatures, 100).unwrap();
+    }
+
+    #[test]
+    fn test_settlement_root_must_be_signed_state() {
+        let mut channel = live_channel(vec![Pubkey::new_unique()], vec![0]);
+        let calculations = vec![reward(Pubkey::new_unique(), 100)];
+        let root = StateChannel::calculate_state_hash(&calculations);
+
+        // No checkpoint means there's no co-signed state to settle against
+        assert!(channel.verify_settlement_hash(channel.state_hash).is_err());
+
+        checkpoint(&mut channel, root);
+        assert!(channel.verify_settlement_hash(root).is_ok());
+        assert!(channel.begin_settlement([3; 32], 1, false, 20_000).is_err());
+        // The signed hash fixes the leaf count too
+        let merkle_root = StateChannel::settlement_root(&calculations);
+        assert!(channel.begin_settlement(merkle_root, 2, false, 20_000).is_err());
+        assert!(channel.begin_settlement(merkle_root, 0, false, 20_000).is_err());
+
+        // A later co-signed update supersedes the checkpointed root
+        channel.state_hash = [4; 32];
+        channel.nonce += 1;
+        assert!(channel.verify_settlement_hash(root).is_err());
+        assert!(channel.verify_settlement_hash([4; 32]).is_ok());
+    }
+
+    #[test]
+    fn test_settlement_proofs_verify_every_leaf() {
+        let calculations: Vec<RewardCalculation> = (0..5)
+            .map(|i| reward(Pubkey::new_unique(), 100 + i))
+            .collect();
+        let root = StateChannel::settlement_root(&calculations);
+
+        for (leaf, proof) in settlement_leaves(&calculations) {
+            assert!(StateChannel::verify_settlement_proof(&root, 5, leaf.index, &leaf.calculation, &proof));
+            // Same leaf claimed at another index or with a forged amount fails
+            assert!(!StateChannel::verify_settlement_proof(&root, 5, (leaf.index + 1) % 5, &leaf.calculation, &proof));
+            let mut forged = leaf.calculation.clone();
+            forged.calculated_reward += 1;
+            assert!(!StateChannel::verify_settlement_proof(&root, 5, leaf.index, &forged, &proof));
+        }
+    }
+
+    #[test]
+    fn test_batch_settlement_applies_each_leaf_once() {
+        let mut channel = live_channel(vec![Pubkey::new_unique()], vec![0]);
+        let calculations: Vec<RewardCalculation> = (0..3)
+            .map(|_| reward(Pubkey::new_unique(), 100))
+            .collect();
+        let root = StateChannel::settlement_root(&calculations);
+        let leaves = settlement_leaves(&calculations);
+
+        assert!(channel.begin_settlement(root, 3, false, 20_000).is_err()); // never checkpointed
+        checkpoint(&mut channel, StateChannel::calculate_state_hash(&calculations));
+        assert!(channel.begin_settlement(root, 3, false, 5_000).is_err()); // before timeout
+        channel.begin_settlement(root, 3, false, 20_000).unwrap();
+        assert!(channel.begin_settlement(root, 3, false, 20_000).is_err());
+        assert!(!channel.is_active);
+
+        let (leaf, proof) = &leaves[0];
+        assert_eq!(channel.apply_settlement_leaf(leaf, proof).unwrap(), 100);
+        assert!(channel.apply_settlement_leaf(leaf, proof).is_err());
+        assert!(!channel.settlement_complete());
+        assert!(channel.complete_settlement().is_err());
+
+        for (leaf, proof) in &leaves[1..] {
+            channel.apply_settlement_leaf(leaf, proof).unwrap();
+        }
+        assert!(channel.settlement_complete());
+        channel.complete_settlement().unwrap();
+        assert!(channel.is_settled);
+        assert_eq!(channel.settlement_amount, 300);
+        assert!(channel.apply_settlement_leaf(&leaves[2].0, &leaves[2].1).is_err());
+    }
+
+    #[test]
+    fn test_collateral_batch_settlement_accumulates_entitlements() {
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut channel = live_channel(vec![a, b], vec![50, 50]);
+        let calculations = vec![reward(b, 30), reward(a, 10), reward(b, 5)];
+        let root = StateChannel::settlement_root(&calculations);
+
+        checkpoint(&mut channel, StateChannel::calculate_state_hash(&calculations));
+        channel.begin_settlement(root, 3, true, 20_000).unwrap();
+        for (leaf, proof) in settlement_leaves(&calculations) {
+            channel.apply_settlement_leaf(&leaf, &proof).unwrap();
+        }
+        assert_eq!(channel.complete_settlement().unwrap(), vec![10, 35]);
+        assert_eq!(channel.pending_balances, vec![0, 0]);
+
+        // Non-participants can't be paid out of collateral
+        let mut channel = live_channel(vec![a], vec![0]);
+        let calculations = vec![reward(b, 30)];
+        let root = StateChannel::settlement_root(&calculations);
+        checkpoint(&mut channel, StateChannel::calculate_state_hash(&calculations));
+        channel.begin_settlement(root, 1, true, 20_000).unwrap();
+        let (leaf, proof) = &settlement_leaves(&calculations)[0];
+        assert!(channel.apply_settlement_leaf(leaf, proof).is_err());
+    }
 }
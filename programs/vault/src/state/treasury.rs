@@ -21,6 +21,8 @@ pub struct Treasury {
     pub max_deposit_amount: u64,     // Maximum deposit amount
     pub created_at: i64,             // Treasury creation timestamp
     pub updated_at: i64,             // Last update timestamp
+    pub channel_fee_bps: u16,        // Fee on state channel settlement outflows, in basis points
+    pub insurance_fund: u64,         // Lamports collected from dispute slashing
     pub bump: u8,                    // PDA bump
 }
 
@@ -43,6 +45,8 @@ impl Treasury {
         8 + // max_deposit_amount
         8 + // created_at
         8 + // updated_at
+        2 + // channel_fee_bps
+        8 + // insurance_fund
         1; // bump
 
     pub const DEPOSIT_FREQUENCY_SECONDS: u32 = 14 * 24 * 60 * 60; // 14 days
@@ -50,6 +54,9 @@ impl Treasury {
     pub const DEFAULT_REBALANCE_THRESHOLD: u16 = 500; // 5% in basis points
     pub const MIN_DEPOSIT_AMOUNT: u64 = 10_000_000; // $10 USD minimum
     pub const MAX_DEPOSIT_AMOUNT: u64 = 1_000_000_000; // $1000 USD maximum
+    pub const DEFAULT_CHANNEL_FEE_BPS: u16 = 10; // 0.1%
+    pub const MAX_CHANNEL_FEE_BPS: u16 = 100; // 1%
+    pub const SLASH_INSURANCE_CUT_BPS: u16 = 1_000; // 10% of slashed collateral
 
     /// Initialize treasury with default values
     pub fn initialize(&mut self, bump: u8) -> Result<()> {
@@ -72,6 +79,8 @@ impl Treasury {
         self.max_deposit_amount = Self::MAX_DEPOSIT_AMOUNT;
         self.created_at = clock.unix_timestamp;
         self.updated_at = clock.unix_timestamp;
+        self.channel_fee_bps = Self::DEFAULT_CHANNEL_FEE_BPS;
+        self.insurance_fund = 0;
         self.bump = bump;
         
         Ok(())
@@ -173,6 +182,17 @@ impl Treasury {
         Ok(())
     }
 
+    /// Return unused rewards to the user rewards pool
+    pub fn return_user_rewards(&mut self, amount: u64) -> Result<()> {
+        self.user_rewards_pool = self.user_rewards_pool
+            .checked_add(amount)
+            .ok_or(VaultError::MathOverflow)?;
+        
+        self.updated_at = Clock::get()?.unix_timestamp;
+        
+        Ok(())
+    }
+
     /// Set emergency pause status
     pub fn set_emergency_pause(&mut self, paused: bool) -> Result<()> {
         self.emergency_pause = paused;
@@ -218,6 +238,15 @@ impl Treasury {
         Ok(())
     }
 
+    /// Set the state channel fee; only channels opened afterwards use it
+    pub fn set_channel_fee_bps(&mut self, fee_bps: u16) -> Result<()> {
+        if fee_bps > Self::MAX_CHANNEL_FEE_BPS {
+            return Err(VaultError::InvalidThresholdValue.into());
+        }
+        self.channel_fee_bps = fee_bps;
+        Ok(())
+    }
+
     /// Get current asset allocation percentages
     pub fn get_allocation_percentages(&self, oracle_prices: &OraclePrices) -> Result<AllocationPercentages> {
         if self.total_assets == 0 {
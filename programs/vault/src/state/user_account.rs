@@ -16,6 +16,7 @@ pub struct UserAccount {
     pub btc_address: String,
     pub created_at: i64,
     pub bump: u8,
+    pub last_claim_epoch: u64, // Epoch of the most recent reward claim; appended to keep earlier fields in place
 }
 
 impl UserAccount {
@@ -31,5 +32,6 @@ impl UserAccount {
         8 + // btc_commitment_amount
         64 + // btc_address (max length)
         8 + // created_at
-        1; // bump
+        1 + // bump
+        8; // last_claim_epoch
 }
\ No newline at end of file